@@ -12,8 +12,11 @@ mod source;
 
 pub use package_id::PackageId;
 pub use report::{
-    Count, CounterBlock, DependencyKind, PackageInfo, QuickReportEntry, QuickSafetyReport,
-    ReportEntry, SafetyReport, UnsafeInfo,
+    CargoConfigSettings, Count, CounterBlock, DependencyKind, DuplicatedFile, FileCopy,
+    FileUnsafeInfo, MemorySafetyIndicators, MemorySafetyStatus, PackageInfo, PackageMetadata,
+    PartySplit, PartyTotal, PathSafetyReport, QuickReportEntry, QuickSafetyReport, ReportEntry,
+    SafetyReport, ScanOptions, ScanWarning, SecurityInsights, SecurityInsightsDependencies,
+    SecurityInsightsHeader, SelfReportedClaims, TreemapNode, UnsafeBlame, UnsafeInfo,
 };
 pub use source::Source;
 
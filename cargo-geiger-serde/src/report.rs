@@ -1,7 +1,8 @@
 use crate::PackageId;
+use semver::Version;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     ops::{Add, AddAssign},
     path::PathBuf,
 };
@@ -16,6 +17,56 @@ pub struct PackageInfo {
     pub dev_dependencies: HashSet<PackageId>,
     #[serde(serialize_with = "set_serde::serialize")]
     pub build_dependencies: HashSet<PackageId>,
+    /// Whether the package is taken from a `[patch]` or `[replace]` of the
+    /// root manifest instead of its original source
+    #[serde(default)]
+    pub patched: bool,
+    /// Whether the package is taken from a local path by a `[patch]` or
+    /// `[replace]`, e.g. a local fork of a registry package
+    #[serde(default)]
+    pub local_override: bool,
+    /// Whether the version of the package is yanked from its registry
+    #[serde(default)]
+    pub yanked: bool,
+    /// Metadata from the manifest of the package, only present with
+    /// `--include-metadata`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<PackageMetadata>,
+    /// Claims from the `[package.metadata.geiger]` table of the manifest of
+    /// the package, only present when the package declares any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub self_reported: Option<SelfReportedClaims>,
+    /// Number of distinct crates.io owners, users and teams, of a crates.io
+    /// package with unsafe code, only present with `--include-owners`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner_count: Option<u64>,
+    /// Notes on a `rust-version` newer than the toolchain of the scan, and
+    /// the unsafe-relevant language changes the toolchain predates
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rust_version_notes: Vec<String>,
+    /// How the downloaded archive of a registry package differs from the
+    /// checksum pinned in `Cargo.lock`, or the scanned source from the
+    /// archive, only checked with `--verify-checksums`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checksum_mismatches: Vec<String>,
+    /// Files documenting the unsafe code found in the source of the
+    /// package, like `SAFETY.md`, only looked up for the packages with the
+    /// most unsafe usage
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unsafe_docs: Vec<PathBuf>,
+    /// The release date of the resolved version of a crates.io package with
+    /// unsafe code, as `YYYY-MM-DD`, only present with `--include-release-age`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub release_date: Option<String>,
+    /// The number of days since any version of the crate of a crates.io
+    /// package with unsafe code was released, only present with
+    /// `--include-release-age`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub days_since_last_release: Option<u64>,
+    /// Free-text notes on the crate from the `[geiger.notes]` table of the
+    /// cargo configuration, e.g. on its audit
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
 }
 
 impl PackageInfo {
@@ -25,6 +76,18 @@ impl PackageInfo {
             dependencies: Default::default(),
             dev_dependencies: Default::default(),
             build_dependencies: Default::default(),
+            patched: false,
+            local_override: false,
+            yanked: false,
+            metadata: None,
+            self_reported: None,
+            owner_count: None,
+            rust_version_notes: Vec::new(),
+            checksum_mismatches: Vec::new(),
+            unsafe_docs: Vec::new(),
+            release_date: None,
+            days_since_last_release: None,
+            notes: Vec::new(),
         }
     }
 
@@ -37,12 +100,43 @@ impl PackageInfo {
     }
 }
 
+/// Metadata from the manifest of a package
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct PackageMetadata {
+    pub authors: Vec<String>,
+    pub description: Option<String>,
+    pub repository: Option<String>,
+    /// The minimum supported Rust version, from the `rust-version` field
+    pub rust_version: Option<String>,
+}
+
+/// Claims a package makes about itself in the `[package.metadata.geiger]`
+/// table of its manifest. They are self-reported by its maintainers and not
+/// verified by the scan
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct SelfReportedClaims {
+    /// `audited`, whether the unsafe code of the package was audited
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audited: Option<bool>,
+    /// `safety-docs`, the document explaining the unsafe code of the package
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub safety_docs: Option<String>,
+    /// `categories`, what the unsafe code of the package is for, e.g. `ffi`
+    /// or `simd`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<String>,
+}
+
 /// Entry of the report generated from scanning for packages that forbid the use of `unsafe`
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct QuickReportEntry {
     pub package: PackageInfo,
     /// Whether this package forbids the use of `unsafe`
     pub forbids_unsafe: bool,
+    /// Whether each entry point source file, keyed by path, forbids the use
+    /// of `unsafe`, only present with `--include-files`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub entry_points: BTreeMap<PathBuf, bool>,
 }
 
 /// Report generated from scanning for packages that forbid the use of `unsafe`
@@ -73,6 +167,157 @@ pub struct SafetyReport {
     pub packages_without_metrics: HashSet<PackageId>,
     #[serde(serialize_with = "set_serde::serialize")]
     pub used_but_not_scanned_files: HashSet<PathBuf>,
+    /// The last commit to touch each unsafe occurrence in the workspace, only
+    /// present when requested
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blame: Vec<UnsafeBlame>,
+    /// Unsafe usage statistics for the standard library crates, keyed by
+    /// crate name, not part of the dependency graph and only present when
+    /// requested
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub sysroot: BTreeMap<String, CounterBlock>,
+    /// Files that could not be read, canonicalized or parsed and were left
+    /// out of the scan
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<ScanWarning>,
+    /// The rustup toolchain the build was run with, when selected with
+    /// `--toolchain` or pinned by a toolchain file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub toolchain: Option<String>,
+    /// The settings of the cargo configuration the scan was run with, only
+    /// present when any of them is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cargo_config: Option<CargoConfigSettings>,
+    /// The options of the scan that change the statistics of the report,
+    /// missing in reports of earlier versions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scan_options: Option<ScanOptions>,
+    /// The unsafe usage of the workspace members and of the external
+    /// dependencies, only present when requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub party_split: Option<PartySplit>,
+    /// Files with unsafe code that are byte-identical in more than one
+    /// package, e.g. vendored copies and forks
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub duplicated_files: Vec<DuplicatedFile>,
+}
+
+/// The settings of the cargo configuration, e.g. `.cargo/config.toml`, that
+/// change what is resolved and built, as `cargo build` would apply them
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct CargoConfigSettings {
+    /// `build.target`, the target built for when no `--target` is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// `net.offline`
+    #[serde(default)]
+    pub offline: bool,
+    /// Whether `http.proxy` is set, the proxy itself is left out as it may
+    /// contain credentials
+    #[serde(default)]
+    pub proxy: bool,
+    /// The keys set in `[profile.dev]`, the profile of the build the used
+    /// files are collected from
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub profile_dev: Vec<String>,
+}
+
+/// The options of the scan that change what is built and scanned, a report
+/// is only comparable with a scan run with the same options
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ScanOptions {
+    /// `--include-tests`
+    #[serde(default)]
+    pub include_tests: bool,
+    /// `--all-targets`
+    #[serde(default)]
+    pub all_targets: bool,
+    /// The kinds of targets built, of `--lib`, `--bins`, `--tests` and
+    /// `--examples`, empty for the default targets
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub selected_targets: Vec<String>,
+    /// `--all-features`
+    #[serde(default)]
+    pub all_features: bool,
+    /// `--no-default-features`
+    #[serde(default)]
+    pub no_default_features: bool,
+    /// The features given with `--features`, sorted
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub features: Vec<String>,
+    /// `--target`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+}
+
+/// The unsafe usage split between the workspace members, the first-party
+/// code, and the external dependencies, the third-party code
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct PartySplit {
+    pub first_party: PartyTotal,
+    pub third_party: PartyTotal,
+}
+
+/// The packages on one side of a `PartySplit` and their unsafe usage
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct PartyTotal {
+    pub packages: usize,
+    /// Unsafe usage in the code used by the build
+    pub used: CounterBlock,
+    /// Unsafe usage in the code not used by the build
+    pub unused: CounterBlock,
+}
+
+/// Report generated from scanning a directory of `.rs` files that is not a
+/// cargo package
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct PathSafetyReport {
+    /// Unsafe usage statistics per file, keyed by the path relative to the
+    /// scanned directory
+    pub files: BTreeMap<PathBuf, CounterBlock>,
+    /// Unsafe usage statistics for all files combined
+    pub total: CounterBlock,
+    /// Files that could not be parsed
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub files_without_metrics: BTreeSet<PathBuf>,
+}
+
+/// A file left out of the scan, the scan continues without it
+#[derive(Clone, Debug, Default, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct ScanWarning {
+    /// The package, or the crate for files referenced by dep-info files
+    pub package: String,
+    pub path: PathBuf,
+    pub cause: String,
+}
+
+/// A file found byte-identical in more than one package
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct DuplicatedFile {
+    /// Hex encoded SHA-256 digest of the content of the copies
+    pub sha256: String,
+    /// Unsafe usage statistics for one copy of the file
+    pub counters: CounterBlock,
+    /// The copies of the file, sorted by package
+    pub copies: Vec<FileCopy>,
+}
+
+/// A copy of a duplicated file
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct FileCopy {
+    pub package: PackageId,
+    /// Path of the copy relative to the root of the package
+    pub path: PathBuf,
+}
+
+/// The last commit and author to touch an occurrence of the `unsafe` keyword
+#[derive(Clone, Debug, Default, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct UnsafeBlame {
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    pub commit: String,
+    pub author: String,
 }
 
 /// Unsafety usage in a package
@@ -84,6 +329,133 @@ pub struct UnsafeInfo {
     pub unused: CounterBlock,
     /// Whether this package forbids the use of `unsafe`
     pub forbids_unsafe: bool,
+    /// Whether this package forbids the use of `unsafe` in its entry points,
+    /// yet `unsafe` usage or `#[allow(unsafe_code)]` was found in the code
+    /// used by the project, e.g. in files pulled in with `include!`
+    #[serde(default)]
+    pub forbid_mismatch: bool,
+    /// Whether the scan of this package was stopped by
+    /// `--timeout-per-crate`, the statistics only cover the files scanned
+    /// before
+    #[serde(default)]
+    pub not_fully_scanned: bool,
+    /// Unsafe usage statistics for generated code used by the project, files
+    /// in the `OUT_DIR` of a build script, marked `@generated` or matching
+    /// `--generated-globs`. The same code is also included in `used`
+    #[serde(default)]
+    pub generated: CounterBlock,
+    /// Unsafe usage statistics for code guarded by platform specific `cfg`
+    /// attributes, keyed by the `cfg` predicate, used and unused code combined
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub platforms: BTreeMap<String, CounterBlock>,
+    /// Unsafe usage statistics for code that is not compiled for the current
+    /// target and features, keyed by the `cfg` predicate guarding it, only
+    /// filled in with `--latent-unsafe`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub latent: BTreeMap<String, CounterBlock>,
+    /// Unsafe usage statistics per source file, keyed by path, only present
+    /// in the formats listing files
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub files: BTreeMap<PathBuf, FileUnsafeInfo>,
+}
+
+/// Node of the hierarchical report of unsafe usage, crate -> file, in the
+/// layout expected by treemap visualizations like `d3.hierarchy`
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct TreemapNode {
+    /// Crate name and version, or file path
+    pub name: String,
+    /// Number of unsafe occurrences, only present for the leaves so that
+    /// summing over the hierarchy does not count anything twice
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<u64>,
+    /// Whether the file is used by the build, only present for files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub used: Option<bool>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<TreemapNode>,
+}
+
+/// Memory safety indicators of the dependencies, a fragment following the
+/// conventions of the OpenSSF security insights file, for dashboards that
+/// already ingest Scorecard data
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SecurityInsights {
+    pub header: SecurityInsightsHeader,
+    pub dependencies: SecurityInsightsDependencies,
+}
+
+/// Version of the fragment and the tool that generated it
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SecurityInsightsHeader {
+    pub schema_version: String,
+    pub generator: String,
+}
+
+/// The `dependencies` section of the security insights
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SecurityInsightsDependencies {
+    pub memory_safety: Vec<MemorySafetyIndicators>,
+}
+
+/// Memory safety indicators of a single package
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct MemorySafetyIndicators {
+    pub name: String,
+    pub version: Version,
+    /// Package URL, only present for packages from crates.io
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purl: Option<String>,
+    pub status: MemorySafetyStatus,
+    /// Whether all entry points declare `#![forbid(unsafe_code)]`
+    pub forbids_unsafe: bool,
+    /// Number of unsafe occurrences in code used by the build
+    pub unsafe_used: u64,
+    /// Number of unsafe occurrences in code not used by the build
+    pub unsafe_unused: u64,
+}
+
+/// Detection status of a package, from safest to least safe
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MemorySafetyStatus {
+    /// No unsafe usage found and `#![forbid(unsafe_code)]` declared
+    ForbidsUnsafe,
+    /// No unsafe usage found, but unsafe code is not forbidden
+    NoUnsafeFound,
+    /// Unsafe usage found in code used by the build
+    UnsafeFound,
+    /// `#![forbid(unsafe_code)]` declared, but unsafe usage found in code
+    /// used by the build
+    ForbidMismatch,
+}
+
+/// Unsafety usage in a single source file of a package
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct FileUnsafeInfo {
+    /// Whether the file is used by the build
+    pub used: bool,
+    /// Whether the file is the root of a build target, like `lib.rs`
+    pub is_crate_entry_point: bool,
+    /// Whether the file declares `#![forbid(unsafe_code)]`
+    pub forbids_unsafe: bool,
+    /// Unsafe usage statistics for the file
+    pub counters: CounterBlock,
+    /// Size of the file in bytes when it was scanned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// Hex encoded SHA-256 digest of the content of the file when it was
+    /// scanned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// The lines of the `unsafe` keywords in the file, sorted, only present
+    /// when the locations are kept, e.g. for the HTML output
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unsafe_lines: Vec<usize>,
 }
 
 /// Kind of dependency for a package
@@ -142,6 +514,68 @@ pub struct CounterBlock {
     pub item_impls: Count,
     pub item_traits: Count,
     pub methods: Count,
+    /// Number of inline assembly macro invocations
+    #[serde(default)]
+    pub asm: u64,
+    /// Expressions inside `Drop` implementations
+    #[serde(default)]
+    pub drop_exprs: Count,
+    /// Number of unsafe expressions in the bodies of `unsafe fn` functions
+    /// and methods outside of any `unsafe` block, implicitly unsafe
+    /// operations. These are also counted in `exprs`
+    #[serde(default)]
+    pub unsafe_fn_body_exprs: u64,
+    /// Expressions in const evaluation contexts, `const fn` bodies and the
+    /// initializers of `const` and `static` items. These are also counted in
+    /// `exprs`
+    #[serde(default)]
+    pub const_exprs: Count,
+    /// Number of calls to each of the watched high-risk APIs, e.g.
+    /// `mem::transmute`. Method calls are matched by the method name only, so
+    /// their count is a heuristic
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub api_calls: BTreeMap<String, u64>,
+    /// Number of `#[repr(packed)]` structs
+    #[serde(default)]
+    pub packed_structs: u64,
+    /// Number of `static mut` items
+    #[serde(default)]
+    pub static_muts: u64,
+    /// Number of references to `static mut` items declared in the same file.
+    /// Paths are matched by their last segment only, so this is a heuristic
+    #[serde(default)]
+    pub static_mut_refs: u64,
+    /// Number of `union` definitions
+    #[serde(default)]
+    pub unions: u64,
+    /// Number of reads of the fields of unions defined in the same file,
+    /// inside unsafe code. Fields are matched by name only, so this is a
+    /// heuristic
+    #[serde(default)]
+    pub union_field_accesses: u64,
+    /// Number of `static` declarations in `extern` blocks
+    #[serde(default)]
+    pub extern_statics: u64,
+    /// Number of calls to functions declared in native `extern` blocks in
+    /// the same file
+    #[serde(default)]
+    pub ffi_calls: u64,
+    /// Number of calls to functions imported from JavaScript, declared in
+    /// `#[wasm_bindgen]` or `#[link(wasm_import_module = "...")]` `extern`
+    /// blocks, or in `extern` blocks only built for `target_arch = "wasm32"`,
+    /// in the same file. These are not counted in `ffi_calls`
+    #[serde(default)]
+    pub js_ffi_calls: u64,
+    /// Number of statics exported with `#[no_mangle]` or `#[export_name]`
+    #[serde(default)]
+    pub exported_globals: u64,
+    /// Number of `pub unsafe fn` functions and methods, unsafe contracts
+    /// exported to the consumers of the crate
+    #[serde(default)]
+    pub public_unsafe_fns: u64,
+    /// Number of `pub unsafe trait` items
+    #[serde(default)]
+    pub public_unsafe_traits: u64,
 }
 
 impl CounterBlock {
@@ -158,12 +592,33 @@ impl Add for CounterBlock {
     type Output = CounterBlock;
 
     fn add(self, other: CounterBlock) -> CounterBlock {
+        let mut api_calls = self.api_calls;
+        for (api, calls) in other.api_calls {
+            *api_calls.entry(api).or_insert(0) += calls;
+        }
         CounterBlock {
             functions: self.functions + other.functions,
             exprs: self.exprs + other.exprs,
             item_impls: self.item_impls + other.item_impls,
             item_traits: self.item_traits + other.item_traits,
             methods: self.methods + other.methods,
+            asm: self.asm + other.asm,
+            drop_exprs: self.drop_exprs + other.drop_exprs,
+            unsafe_fn_body_exprs: self.unsafe_fn_body_exprs + other.unsafe_fn_body_exprs,
+            const_exprs: self.const_exprs + other.const_exprs,
+            api_calls,
+            packed_structs: self.packed_structs + other.packed_structs,
+            static_muts: self.static_muts + other.static_muts,
+            static_mut_refs: self.static_mut_refs + other.static_mut_refs,
+            unions: self.unions + other.unions,
+            union_field_accesses: self.union_field_accesses + other.union_field_accesses,
+            extern_statics: self.extern_statics + other.extern_statics,
+            ffi_calls: self.ffi_calls + other.ffi_calls,
+            js_ffi_calls: self.js_ffi_calls + other.js_ffi_calls,
+            exported_globals: self.exported_globals + other.exported_globals,
+            public_unsafe_fns: self.public_unsafe_fns + other.public_unsafe_fns,
+            public_unsafe_traits: self.public_unsafe_traits
+                + other.public_unsafe_traits,
         }
     }
 }
@@ -268,7 +723,7 @@ mod set_serde {
         S: Serializer,
     {
         let mut values = set.iter().collect::<Vec<_>>();
-        values.sort_by(|a, b| a.cmp(b));
+        values.sort();
         let mut seq = serializer.serialize_seq(Some(values.len()))?;
         for value in values {
             seq.serialize_element(value)?;
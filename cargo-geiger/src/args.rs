@@ -1,24 +1,60 @@
+use crate::format::i18n::Lang;
 use crate::format::print_config::OutputFormat;
 use crate::format::Charset;
+use crate::graph::{ExtraDeps, GraphOptions};
+use crate::scan::{
+    CacheCommand, RequireForbidScope, UndocumentedUnsafeScope, UnsafeCountLimit,
+};
 
+use cargo::core::PackageIdSpec;
 use pico_args::Arguments;
+use std::net::SocketAddr;
 use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 pub const HELP: &str =
     "Detects usage of unsafe Rust in a Rust crate and its dependencies.
 
 USAGE:
-    cargo geiger [OPTIONS]
+    cargo geiger [<SPEC>] [OPTIONS]
+    cargo geiger check-update [--json] [OPTIONS]
+    cargo geiger cache <stats|prune> [--cache-dir <PATH>] [--max-age <DAYS>]
+    cargo geiger scan-path <DIR> [--json|--html] [--include-tests]
+    cargo geiger fleet <DIR> [--json] [OPTIONS]
+    cargo geiger serve [--listen <ADDR>] [OPTIONS]
+    cargo geiger render <REPORT> [--html|--json|--markdown|--treemap]
+    cargo geiger explain [<TOPIC>]
+    cargo geiger verify <REPORT> [OPTIONS]
 
 OPTIONS:
-    -p, --package <SPEC>          Package to be used as the root of the tree.
+    -p, --package <SPEC>          Package to be used as the root of the tree,
+                                  can also be given as the first argument,
+                                  e.g. cargo geiger tokio.
         --features <FEATURES>     Space-separated list of features to activate.
         --all-features            Activate all available features.
         --no-default-features     Do not activate the `default` feature.
-        --target <TARGET>         Set the target triple.
+        --target <TARGET>         Set the target triple, or all to include
+                                  the dependencies of all platforms, like
+                                  cargo tree.
         --all-targets             Return dependencies for all targets. By
                                   default only the host target is matched.
-        --manifest-path <PATH>    Path to Cargo.toml.
+        --lib                     Build only the library of the packages,
+                                  its code is counted as used. Like the
+                                  cargo target selection, --lib, --bins,
+                                  --examples and --tests can be combined,
+                                  the default targets are built otherwise.
+        --bins                    Build all binaries of the packages.
+        --examples                Build all examples of the packages.
+        --tests                   Build all tests of the packages, unlike
+                                  --include-tests this changes which code is
+                                  used by the build.
+        --manifest-path <PATH>    Path to Cargo.toml, found in the current
+                                  directory or a parent directory by
+                                  default. Given several times, the
+                                  workspaces are scanned into one combined
+                                  report, like with fleet.
+        --toolchain <TOOLCHAIN>   Build with this rustup toolchain instead of
+                                  the one pinned by rust-toolchain.toml.
     -i, --invert                  Invert the tree direction.
         --no-indent               Display the dependencies as a list (rather
                                   than a tree).
@@ -27,20 +63,129 @@ OPTIONS:
     -a, --all                     Don't truncate dependencies that have already
                                   been displayed.
         --charset <CHARSET>       Character set to use in output: utf8, ascii
-                                  [default: utf8].
+                                  [default: utf8]. The status symbols can be
+                                  replaced in the [geiger.symbols] table of
+                                  the cargo configuration, with the keys
+                                  forbids, unknown, unsafe, mismatch and
+                                  missing.
+        --lang <LANG>             Language of the legend, summaries and
+                                  warnings: en, de [default: en].
     --format <FORMAT>             Format string used for printing dependencies
                                   [default: {p}].
     --json                        Output in JSON format.
+    --html                        Output a standalone HTML page with sortable,
+                                  searchable tables and the files of each
+                                  package.
+    --baseline <REPORT>           Compare with the JSON report at REPORT,
+                                  e.g. written by --save-baseline, and list
+                                  the regressions since then. With --html,
+                                  mark the changes: new and removed
+                                  packages, more or less unsafe code used
+                                  and resolved unsafe usage.
+    --fail-on-regression          Exit with an error if any package uses
+                                  more unsafe code than in the --baseline
+                                  report, or a new package uses unsafe code.
+    --save-baseline <REPORT>      Write the unsafe usage of every package to
+                                  REPORT, for later runs with --baseline.
+    --security-insights           Output the memory safety indicators of each
+                                  package as JSON, following the conventions
+                                  of the OpenSSF security insights, for risk
+                                  dashboards.
+    --treemap                     Output the unsafe usage per crate and file
+                                  as hierarchical JSON, for treemap
+                                  visualizations like d3.hierarchy.
+    --markdown                    Output a Markdown table with the unsafe
+                                  usage of each package, e.g. for pull
+                                  request comments.
+    --serve <ADDR>                Serve the HTML output on a local address,
+                                  e.g. 127.0.0.1:8080, with a page listing
+                                  the files of each package.
+    --rescan                      With --serve, scan again whenever the
+                                  report page is reloaded.
+    --listen <ADDR>               Address of the HTTP scan API of serve
+                                  [default: 127.0.0.1:7878]. Scans build the
+                                  requested workspaces, running their build
+                                  scripts. Requests to start a scan need an
+                                  X-Cargo-Geiger header.
+    --allow-remote                Allow --serve and --listen on addresses
+                                  other than loopback ones, only do so on
+                                  trusted networks.
     -v, --verbose                 Use verbose output (-vv very verbose/build.rs
                                   output).
     -q, --quiet                   No output printed to stdout other than the
                                   tree.
         --color <WHEN>            Coloring: auto, always, never.
+        --snapshot                Deterministic output for asserting on in
+                                  tests: ASCII charset, no color and no
+                                  timestamps.
         --frozen                  Require Cargo.lock and cache are up to date.
         --locked                  Require Cargo.lock is up to date.
         --offline                 Run without accessing the network.
-    -Z \"<FLAG>...\"                Unstable (nightly-only) flags to Cargo.
+    -Z \"<FLAG>...\"                Unstable (nightly-only) flags to Cargo,
+                                  also used by the build, e.g. -Z build-std
+                                  with --target. Can be given more than once.
         --include-tests           Count unsafe usage in tests..
+        --clean-members           Clean only the artifacts of the workspace
+                                  members before the build, instead of the
+                                  whole target directory. The dependencies
+                                  are only rebuilt when out of date, but
+                                  their stale build artifacts, e.g. built
+                                  with other features, can inflate the used
+                                  counts.
+        --no-clean                Don't clean before the build, only rebuild
+                                  what is out of date and read the dep-info
+                                  files of the rest from the target directory.
+                                  Falls back to a clean build when they are
+                                  missing or stale.
+        --include-files           Include the metrics of each source file,
+                                  with its path, size, SHA-256 digest, the
+                                  lines of its unsafe code and whether it is
+                                  used and an entry point, in the JSON
+                                  output. The HTML output always includes
+                                  them. With --forbid-only, list whether
+                                  each entry point forbids unsafe code.
+        --include-metadata        Include the authors, description,
+                                  repository and rust-version of each package
+                                  from its manifest in the JSON and HTML
+                                  output.
+        --include-owners          Include the number of crates.io owners of
+                                  each crates.io package with unsafe code in
+                                  the JSON output. The owners are cached for
+                                  a week, and cached owners of any age are
+                                  used with --offline.
+        --include-release-age     Include the release date of the resolved
+                                  version and the days since the last
+                                  release of each crates.io package with
+                                  unsafe code in the JSON output, and list
+                                  the ones without a release for over a year
+                                  below the tree. The release dates are
+                                  cached like the owners.
+        --include-sysroot         Also scan the std, core and alloc crates in
+                                  the rust-src component, reported separately
+                                  from the dependency graph.
+        --first-party-split       Split the unsafe usage between the
+                                  workspace members and the external
+                                  dependencies, below the tree and in the
+                                  JSON report.
+        --latent-unsafe           Also list the unsafe usage in code that is
+                                  not compiled for the current target and
+                                  features, per cfg predicate.
+        --show-inactive           Also list the optional dependencies not
+                                  activated by the selected features, greyed
+                                  out, with the unsafe usage found in them.
+        --used-only               Show only the unsafe usage in code used by
+                                  the build in the table, instead of
+                                  used/total.
+        --unused-only             Show only the unsafe usage in code not used
+                                  by the build in the table.
+        --why <SPEC>              Don't build anything, only list every
+                                  dependency path from the root packages to
+                                  the package, with the kind of each
+                                  dependency, the features requested of it
+                                  and the features enabled for it.
+        --why-most-unsafe         List the packages only reachable through
+                                  the direct dependency contributing the most
+                                  unsafe usage, named below the tree.
         --build-dependencies      Also analyze build dependencies.
         --dev-dependencies        Also analyze dev dependencies.
         --all-dependencies        Analyze all dependencies, including build and
@@ -49,8 +194,143 @@ OPTIONS:
                                   entry point .rs source files for.
                                   forbid(unsafe_code) flags. This is
                                   significantly faster than the default
-                                  scanning mode. TODO: Add ability to combine
+                                  scanning mode. With --verbose, the entry
+                                  points of each package are listed. TODO: Add ability to combine
                                   this with a whitelist for use in CI.
+        --list-undocumented-unsafe <SCOPE>
+                                  Don't build anything, only list the unsafe
+                                  blocks lacking a `// SAFETY:` comment, in
+                                  the workspace members or in all packages:
+                                  workspace, all.
+        --list-unsafe <SPEC>      Don't build anything, only list every unsafe
+                                  function, expression, impl, trait and method
+                                  of the package matching the spec, with its
+                                  file, line and kind.
+        --blame                   Annotate each unsafe occurrence in the
+                                  workspace members with the last commit and
+                                  author that touched it, and summarize unsafe
+                                  usage per author and per commit.
+        --github-output           Write unsafe_total, policy_ok and
+                                  crates_with_unsafe as step outputs to the
+                                  file in $GITHUB_OUTPUT, for use in GitHub
+                                  Actions workflows.
+        --pr-comment <FILE>       Write a Markdown body for a pull request
+                                  comment to FILE, with a summary, the top
+                                  offenders, the verdict of the checks and
+                                  the full tree, truncated to the comment
+                                  size limit of GitHub. Only with the default
+                                  text output.
+        --attest <PATH>           Write an in-toto statement to PATH, with
+                                  the SHA-256 digest of Cargo.lock as subject
+                                  and the scan summary as predicate.
+        --bundle <PATH>           Write a .tar, .tar.gz or .tar.zst archive
+                                  to PATH instead of printing the output,
+                                  with the JSON report, the HTML output, the
+                                  policy options and the statement of
+                                  --attest as provenance.
+        --cache-dir <PATH>        Directory of the metrics cache for registry
+                                  packages, can also be set with
+                                  CARGO_GEIGER_CACHE_DIR [default:
+                                  $CARGO_HOME/geiger-cache].
+        --cached                  Skip the build if Cargo.lock, the feature
+                                  options, the target, the toolchain and
+                                  the -Z flags are unchanged since a
+                                  previous scan, reusing the source files it
+                                  used. Workspace members and packages not
+                                  from a registry are still rescanned.
+        --verify-checksums        Verify the downloaded archive of every
+                                  registry dependency against the checksum
+                                  in Cargo.lock, and the scanned source
+                                  against the archive.
+        --timeout-per-crate <SECS>
+                                  Stop scanning a package after SECS
+                                  seconds, e.g. when a pathological
+                                  generated file stalls the parser. The
+                                  package is marked (not fully scanned)
+                                  and the files left out are listed as
+                                  warnings.
+        --max-age <DAYS>          Only prune cache entries older than this,
+                                  for `cache prune`.
+        --deny-undocumented-unsafe
+                                  Exit with an error if any unsafe blocks
+                                  of the workspace members, or of the
+                                  packages listed by
+                                  --list-undocumented-unsafe, lack a
+                                  `// SAFETY:` comment.
+        --generated-globs <GLOBS> Comma separated list of path globs of
+                                  generated files, e.g. **/bindings.rs.
+                                  Files in the OUT_DIR of a build script or
+                                  marked @generated are always treated as
+                                  generated, their unsafe usage is reported
+                                  separately.
+        --generated-weight <WEIGHT>
+                                  Weight between 0 and 1 of the expressions
+                                  in generated files for the unsafe ratio
+                                  thresholds, e.g. 0 to ignore them
+                                  [default: 1].
+        --max-unsafe-ratio <PERCENT>
+                                  Exit with an error if more than this
+                                  percentage of the expressions used by the
+                                  build, across all packages, are unsafe,
+                                  e.g. 2 or 2%.
+        --max-unsafe-ratio-per-crate <PERCENT>
+                                  Exit with an error if more than this
+                                  percentage of the expressions used by the
+                                  build in any single package are unsafe.
+        --max-unsafe-functions <[NAME=]N>
+                                  Exit with an error if more than N unsafe
+                                  functions are used by the build, across
+                                  all packages, or with NAME= in any package
+                                  of the crate NAME, e.g. 0 or libc=10. Can
+                                  be given several times.
+        --max-unsafe-expressions <[NAME=]N>
+                                  Like --max-unsafe-functions, for the
+                                  unsafe expressions.
+        --max-unsafe-impls <[NAME=]N>
+                                  Like --max-unsafe-functions, for the
+                                  unsafe impls.
+        --max-unsafe-traits <[NAME=]N>
+                                  Like --max-unsafe-functions, for the
+                                  unsafe traits.
+        --max-unsafe-methods <[NAME=]N>
+                                  Like --max-unsafe-functions, for the
+                                  unsafe methods.
+        --fail-on-missing-metrics Exit with an error if any package has no
+                                  metrics, e.g. because its source files
+                                  could not be parsed, listing the packages.
+        --require-forbid <SCOPE>  Exit with an error if any dependency does not
+                                  declare #![forbid(unsafe_code)], listing the
+                                  offenders, for the direct dependencies or
+                                  all dependencies: direct, all.
+        --policy <PATH>           Exit with an error if any dependency breaks
+                                  a rule of the TOML policy file at PATH,
+                                  listing the violations. Rules can apply to
+                                  the direct dependencies only, or to a range
+                                  of depths or packages from some sources,
+                                  and allowlist packages.
+        --quarantine <REPORT>     Exit with an error if a package in the
+                                  dependency graph is not in the JSON report
+                                  at REPORT, e.g. of the last recorded scan,
+                                  unless the --policy file acknowledges it.
+                                  A new version of a package is not new.
+        --report-only             Always exit successfully, printing the
+                                  violations of the checks above as
+                                  warnings, or as GitHub Actions warning
+                                  annotations with --github-output.
+        --suggest-forbid          List the entry points of the workspace
+                                  members without any unsafe usage that do
+                                  not declare #![forbid(unsafe_code)].
+        --fix                     Insert #![forbid(unsafe_code)] into the
+                                  entry points listed by --suggest-forbid,
+                                  and restore them if cargo check fails.
+        --suggest-features        Suggest the features of the root package to
+                                  disable to remove the most unsafe usage
+                                  from the dependency graph, listing the
+                                  optional dependencies and packages each
+                                  would drop.
+        --unsafe-apis <APIS>      Comma separated list of high-risk APIs to
+                                  count calls to, replacing the default list,
+                                  e.g. mem::transmute,Vec::set_len.
     -h, --help                    Prints help information.
     -V, --version                 Prints version information.
 ";
@@ -60,29 +340,89 @@ pub struct Args {
     pub all_deps: bool,
     pub all_features: bool,
     pub all_targets: bool,
+    pub allow_remote: bool,
+    pub attest: Option<PathBuf>,
+    pub baseline: Option<PathBuf>,
+    pub bins: bool,
+    pub blame: bool,
     pub build_deps: bool,
+    pub bundle: Option<PathBuf>,
+    pub cache_dir: Option<PathBuf>,
+    pub cached: bool,
     pub charset: Charset,
+    pub clean_members: bool,
     pub color: Option<String>,
+    pub deny_undocumented_unsafe: bool,
     pub dev_deps: bool,
+    pub examples: bool,
+    pub fail_on_missing_metrics: bool,
+    pub fail_on_regression: bool,
     pub features: Option<String>,
+    pub first_party_split: bool,
+    pub fix: bool,
     pub forbid_only: bool,
     pub format: String,
     pub frozen: bool,
+    pub generated_globs: Vec<String>,
+    pub generated_weight: Option<f64>,
+    pub github_output: bool,
     pub help: bool,
+    pub include_files: bool,
+    pub include_metadata: bool,
+    pub include_owners: bool,
+    pub include_release_age: bool,
+    pub include_sysroot: bool,
     pub include_tests: bool,
     pub invert: bool,
+    pub lang: Lang,
+    pub latent_unsafe: bool,
+    pub lib: bool,
+    pub listen: Option<SocketAddr>,
+    pub list_undocumented_unsafe: Option<UndocumentedUnsafeScope>,
+    pub list_unsafe: Option<String>,
     pub locked: bool,
-    pub manifest_path: Option<PathBuf>,
+    pub manifest_paths: Vec<PathBuf>,
+    pub max_age: Option<u64>,
+    pub max_unsafe_expressions: Vec<UnsafeCountLimit>,
+    pub max_unsafe_functions: Vec<UnsafeCountLimit>,
+    pub max_unsafe_impls: Vec<UnsafeCountLimit>,
+    pub max_unsafe_methods: Vec<UnsafeCountLimit>,
+    pub max_unsafe_ratio: Option<f64>,
+    pub max_unsafe_ratio_per_crate: Option<f64>,
+    pub max_unsafe_traits: Vec<UnsafeCountLimit>,
+    pub no_clean: bool,
     pub no_default_features: bool,
     pub no_indent: bool,
     pub offline: bool,
     pub package: Option<String>,
+    pub policy: Option<PathBuf>,
+    pub pr_comment: Option<PathBuf>,
     pub prefix_depth: bool,
+    pub quarantine: Option<PathBuf>,
     pub quiet: bool,
+    pub report_only: bool,
+    pub require_forbid: Option<RequireForbidScope>,
+    pub rescan: bool,
+    pub save_baseline: Option<PathBuf>,
+    pub serve: Option<SocketAddr>,
+    pub show_inactive: bool,
+    pub snapshot: bool,
+    pub subcommand: Option<Subcommand>,
+    pub suggest_features: bool,
+    pub suggest_forbid: bool,
     pub target: Option<String>,
+    pub tests: bool,
+    pub timeout_per_crate: Option<u64>,
+    pub toolchain: Option<String>,
+    pub unsafe_apis: Option<Vec<String>>,
     pub unstable_flags: Vec<String>,
+    pub unused_only: bool,
+    pub used_only: bool,
     pub verbose: u32,
+    pub verify_checksums: bool,
     pub version: bool,
+    pub why: Option<String>,
+    pub why_most_unsafe: bool,
     pub output_format: Option<OutputFormat>,
 }
 
@@ -90,39 +430,118 @@ impl Args {
     pub fn parse_args(
         mut raw_args: Arguments,
     ) -> Result<Args, Box<dyn std::error::Error>> {
-        let args = Args {
+        let (subcommand, package_spec) = parse_subcommand(&mut raw_args)?;
+        let mut args = Args {
             all: raw_args.contains(["-a", "--all"]),
             all_deps: raw_args.contains("--all-dependencies"),
             all_features: raw_args.contains("--all-features"),
             all_targets: raw_args.contains("--all-targets"),
+            allow_remote: raw_args.contains("--allow-remote"),
+            attest: raw_args.opt_value_from_str("--attest")?,
+            baseline: raw_args.opt_value_from_str("--baseline")?,
+            bins: raw_args.contains("--bins"),
+            blame: raw_args.contains("--blame"),
             build_deps: raw_args.contains("--build-dependencies"),
+            bundle: raw_args.opt_value_from_str("--bundle")?,
+            cache_dir: raw_args.opt_value_from_str("--cache-dir")?,
+            cached: raw_args.contains("--cached"),
             charset: raw_args
                 .opt_value_from_str("--charset")?
                 .unwrap_or(Charset::Utf8),
+            clean_members: raw_args.contains("--clean-members"),
             color: raw_args.opt_value_from_str("--color")?,
+            deny_undocumented_unsafe: raw_args
+                .contains("--deny-undocumented-unsafe"),
             dev_deps: raw_args.contains("--dev-dependencies"),
+            examples: raw_args.contains("--examples"),
+            fail_on_missing_metrics: raw_args
+                .contains("--fail-on-missing-metrics"),
+            fail_on_regression: raw_args.contains("--fail-on-regression"),
             features: raw_args.opt_value_from_str("--features")?,
+            first_party_split: raw_args.contains("--first-party-split"),
+            fix: raw_args.contains("--fix"),
             forbid_only: raw_args.contains(["-f", "--forbid-only"]),
             format: raw_args
                 .opt_value_from_str("--format")?
                 .unwrap_or_else(|| "{p}".to_string()),
             frozen: raw_args.contains("--frozen"),
+            generated_globs: raw_args
+                .opt_value_from_str("--generated-globs")?
+                .map(|s: String| {
+                    s.split(',').map(|s| s.trim().to_owned()).collect()
+                })
+                .unwrap_or_else(Vec::new),
+            generated_weight: raw_args
+                .opt_value_from_fn("--generated-weight", parse_weight)?,
+            github_output: raw_args.contains("--github-output"),
             help: raw_args.contains(["-h", "--help"]),
+            include_files: raw_args.contains("--include-files"),
+            include_metadata: raw_args.contains("--include-metadata"),
+            include_owners: raw_args.contains("--include-owners"),
+            include_release_age: raw_args.contains("--include-release-age"),
+            include_sysroot: raw_args.contains("--include-sysroot"),
             include_tests: raw_args.contains("--include-tests"),
             invert: raw_args.contains(["-i", "--invert"]),
+            lang: raw_args.opt_value_from_str("--lang")?.unwrap_or(Lang::En),
+            latent_unsafe: raw_args.contains("--latent-unsafe"),
+            lib: raw_args.contains("--lib"),
+            listen: raw_args.opt_value_from_str("--listen")?,
+            list_undocumented_unsafe: raw_args
+                .opt_value_from_str("--list-undocumented-unsafe")?,
+            list_unsafe: raw_args.opt_value_from_str("--list-unsafe")?,
             locked: raw_args.contains("--locked"),
-            manifest_path: raw_args.opt_value_from_str("--manifest-path")?,
+            manifest_paths: raw_args.values_from_str("--manifest-path")?,
+            max_age: raw_args.opt_value_from_str("--max-age")?,
+            max_unsafe_expressions: raw_args
+                .values_from_str("--max-unsafe-expressions")?,
+            max_unsafe_functions: raw_args
+                .values_from_str("--max-unsafe-functions")?,
+            max_unsafe_impls: raw_args.values_from_str("--max-unsafe-impls")?,
+            max_unsafe_methods: raw_args
+                .values_from_str("--max-unsafe-methods")?,
+            max_unsafe_ratio: raw_args
+                .opt_value_from_fn("--max-unsafe-ratio", parse_percentage)?,
+            max_unsafe_ratio_per_crate: raw_args.opt_value_from_fn(
+                "--max-unsafe-ratio-per-crate",
+                parse_percentage,
+            )?,
+            max_unsafe_traits: raw_args
+                .values_from_str("--max-unsafe-traits")?,
+            no_clean: raw_args.contains("--no-clean"),
             no_default_features: raw_args.contains("--no-default-features"),
             no_indent: raw_args.contains("--no-indent"),
             offline: raw_args.contains("--offline"),
-            package: raw_args.opt_value_from_str("--manifest-path")?,
+            package: raw_args.opt_value_from_str(["-p", "--package"])?,
+            policy: raw_args.opt_value_from_str("--policy")?,
+            pr_comment: raw_args.opt_value_from_str("--pr-comment")?,
             prefix_depth: raw_args.contains("--prefix-depth"),
+            quarantine: raw_args.opt_value_from_str("--quarantine")?,
             quiet: raw_args.contains(["-q", "--quiet"]),
+            report_only: raw_args.contains("--report-only"),
+            require_forbid: raw_args.opt_value_from_str("--require-forbid")?,
+            rescan: raw_args.contains("--rescan"),
+            save_baseline: raw_args.opt_value_from_str("--save-baseline")?,
+            serve: raw_args.opt_value_from_str("--serve")?,
+            show_inactive: raw_args.contains("--show-inactive"),
+            snapshot: raw_args.contains("--snapshot"),
+            subcommand,
+            suggest_features: raw_args.contains("--suggest-features"),
+            suggest_forbid: raw_args.contains("--suggest-forbid"),
             target: raw_args.opt_value_from_str("--target")?,
+            tests: raw_args.contains("--tests"),
+            timeout_per_crate: raw_args
+                .opt_value_from_str("--timeout-per-crate")?,
+            toolchain: raw_args.opt_value_from_str("--toolchain")?,
+            unsafe_apis: raw_args.opt_value_from_str("--unsafe-apis")?.map(
+                |s: String| s.split(',').map(|s| s.trim().to_owned()).collect(),
+            ),
             unstable_flags: raw_args
-                .opt_value_from_str("-Z")?
-                .map(|s: String| s.split(' ').map(|s| s.to_owned()).collect())
-                .unwrap_or_else(Vec::new),
+                .values_from_str::<_, String>("-Z")?
+                .iter()
+                .flat_map(|s| s.split_whitespace().map(str::to_owned))
+                .collect(),
+            unused_only: raw_args.contains("--unused-only"),
+            used_only: raw_args.contains("--used-only"),
             verbose: match (
                 raw_args.contains("-vv"),
                 raw_args.contains(["-v", "--verbose"]),
@@ -131,15 +550,181 @@ impl Args {
                 (false, true) => 1,
                 (true, _) => 2,
             },
+            verify_checksums: raw_args.contains("--verify-checksums"),
             version: raw_args.contains(["-V", "--version"]),
+            why: raw_args.opt_value_from_str("--why")?,
+            why_most_unsafe: raw_args.contains("--why-most-unsafe"),
             output_format: if raw_args.contains("--json") {
                 Some(OutputFormat::Json)
+            } else if raw_args.contains("--html") {
+                Some(OutputFormat::Html)
+            } else if raw_args.contains("--security-insights") {
+                Some(OutputFormat::SecurityInsights)
+            } else if raw_args.contains("--treemap") {
+                Some(OutputFormat::Treemap)
+            } else if raw_args.contains("--markdown") {
+                Some(OutputFormat::Markdown)
             } else {
                 None
             },
         };
+        if let Some(package_spec) = package_spec {
+            if args.package.is_some() {
+                return Err(format!(
+                    "The package {} is given both as argument and with \
+                     --package",
+                    package_spec
+                )
+                .into());
+            }
+            args.package = Some(package_spec);
+        }
+        if args.fail_on_regression && args.baseline.is_none() {
+            return Err("--fail-on-regression requires --baseline".into());
+        }
+        if args.snapshot {
+            args.charset = Charset::Ascii;
+            args.color = Some(String::from("never"));
+        }
         Ok(args)
     }
+
+    /// The dependencies and platforms of the graph selected with the options.
+    pub fn graph_options(&self) -> GraphOptions {
+        let extra_deps = if self.all_deps {
+            ExtraDeps::All
+        } else if self.build_deps {
+            ExtraDeps::Build
+        } else if self.dev_deps {
+            ExtraDeps::Dev
+        } else {
+            ExtraDeps::NoMore
+        };
+        GraphOptions {
+            extra_deps,
+            target: self.target.clone(),
+            all_targets: self.all_targets,
+        }
+    }
+
+    /// The time the scan is made at, the Unix epoch with `--snapshot` so
+    /// that snapshots do not depend on the wall clock.
+    pub fn scan_time(&self) -> SystemTime {
+        if self.snapshot {
+            UNIX_EPOCH
+        } else {
+            SystemTime::now()
+        }
+    }
+}
+
+/// The `cargo geiger` subcommands, the dependency tree is scanned when none
+/// is given.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Subcommand {
+    /// Inspect or prune the metrics cache.
+    Cache(CacheCommand),
+
+    /// Compare the unsafe usage of the dependencies before and after a
+    /// `cargo update`, without updating.
+    CheckUpdate,
+
+    /// Explain a metric, symbol or detection status, or list the topics.
+    Explain(Option<String>),
+
+    /// Scan every cargo workspace below a directory.
+    Fleet(PathBuf),
+
+    /// Output a previously written JSON report in another format.
+    Render(PathBuf),
+
+    /// Scan a directory of `.rs` files that is not a cargo package.
+    ScanPath(PathBuf),
+
+    /// Run an HTTP API that scans workspaces on request.
+    Serve,
+
+    /// Scan again and compare the result with a published JSON report.
+    Verify(PathBuf),
+}
+
+/// Parses the optional subcommand. When invoked through cargo, the first free
+/// argument is `geiger` and is skipped. Any other first free argument is the
+/// spec of the package to root the tree at, like `cargo geiger tokio`, the
+/// second half of the result.
+fn parse_subcommand(
+    raw_args: &mut Arguments,
+) -> Result<(Option<Subcommand>, Option<String>), Box<dyn std::error::Error>> {
+    let mut subcommand = raw_args.subcommand()?;
+    if subcommand.as_deref() == Some("geiger") {
+        subcommand = raw_args.subcommand()?;
+    }
+    let subcommand = match subcommand.as_deref() {
+        None => None,
+        Some("cache") => match raw_args.subcommand()? {
+            Some(cache_command) => {
+                Some(Subcommand::Cache(cache_command.parse()?))
+            }
+            None => {
+                return Err(
+                    "Missing cache command, expected: stats, prune".into()
+                )
+            }
+        },
+        Some("check-update") => Some(Subcommand::CheckUpdate),
+        Some("explain") => Some(Subcommand::Explain(raw_args.subcommand()?)),
+        Some("fleet") => match raw_args.subcommand()? {
+            Some(dir) => Some(Subcommand::Fleet(PathBuf::from(dir))),
+            None => return Err("Missing directory to scan".into()),
+        },
+        Some("render") => match raw_args.subcommand()? {
+            Some(report) => Some(Subcommand::Render(PathBuf::from(report))),
+            None => return Err("Missing report to render".into()),
+        },
+        Some("scan-path") => match raw_args.subcommand()? {
+            Some(dir) => Some(Subcommand::ScanPath(PathBuf::from(dir))),
+            None => return Err("Missing directory to scan".into()),
+        },
+        Some("serve") => Some(Subcommand::Serve),
+        Some("verify") => match raw_args.subcommand()? {
+            Some(report) => Some(Subcommand::Verify(PathBuf::from(report))),
+            None => return Err("Missing report to verify".into()),
+        },
+        Some(package_spec) => {
+            if PackageIdSpec::parse(package_spec).is_err() {
+                return Err(format!(
+                    "Unrecognised subcommand or package spec: {}",
+                    package_spec
+                )
+                .into());
+            }
+            return Ok((None, Some(String::from(package_spec))));
+        }
+    };
+    Ok((subcommand, None))
+}
+
+/// Parses a percentage between 0 and 100, with or without a trailing `%`.
+fn parse_percentage(s: &str) -> Result<f64, String> {
+    let percentage = s
+        .trim_end_matches('%')
+        .parse::<f64>()
+        .map_err(|e| e.to_string())?;
+    if (0.0..=100.0).contains(&percentage) {
+        Ok(percentage)
+    } else {
+        Err(format!("{} is not a percentage between 0 and 100", s))
+    }
+}
+
+/// Parses a weight between 0 and 1.
+fn parse_weight(s: &str) -> Result<f64, String> {
+    let weight = s.parse::<f64>().map_err(|e| e.to_string())?;
+    if (0.0..=1.0).contains(&weight) {
+        Ok(weight)
+    } else {
+        Err(format!("{} is not a weight between 0 and 1", s))
+    }
 }
 
 #[cfg(test)]
@@ -202,4 +787,239 @@ pub mod args_tests {
         assert_eq!(args.charset, expected_charset);
         assert_eq!(args.verbose, expected_verbose)
     }
+
+    #[rstest(
+        input_str,
+        expected_percentage,
+        case("2", Ok(2.0)),
+        case("2.5%", Ok(2.5)),
+        case("100%", Ok(100.0)),
+        case("101", Err(())),
+        case("-1", Err(())),
+        case("two", Err(()))
+    )]
+    fn parse_percentage_test(
+        input_str: &str,
+        expected_percentage: Result<f64, ()>,
+    ) {
+        assert_eq!(
+            parse_percentage(input_str).map_err(|_| ()),
+            expected_percentage
+        );
+    }
+
+    #[rstest(
+        input_str,
+        expected_weight,
+        case("0", Ok(0.0)),
+        case("0.25", Ok(0.25)),
+        case("1", Ok(1.0)),
+        case("1.5", Err(())),
+        case("-0.5", Err(()))
+    )]
+    fn parse_weight_test(input_str: &str, expected_weight: Result<f64, ()>) {
+        assert_eq!(parse_weight(input_str).map_err(|_| ()), expected_weight);
+    }
+
+    #[rstest(
+        input_argument_vector,
+        expected_subcommand,
+        case(vec!["geiger", "--all"], None),
+        case(
+            vec!["geiger", "cache", "stats"],
+            Some(Subcommand::Cache(CacheCommand::Stats))
+        ),
+        case(
+            vec!["cache", "prune"],
+            Some(Subcommand::Cache(CacheCommand::Prune))
+        ),
+        case(
+            vec!["geiger", "fleet", "projects"],
+            Some(Subcommand::Fleet(PathBuf::from("projects")))
+        ),
+        case(
+            vec!["geiger", "scan-path", "vendor/snippets"],
+            Some(Subcommand::ScanPath(PathBuf::from("vendor/snippets")))
+        ),
+        case(
+            vec!["geiger", "explain", "exprs"],
+            Some(Subcommand::Explain(Some(String::from("exprs"))))
+        ),
+        case(vec!["geiger", "explain"], Some(Subcommand::Explain(None))),
+        case(vec!["geiger", "check-update"], Some(Subcommand::CheckUpdate)),
+        case(
+            vec!["geiger", "render", "geiger-report.json", "--markdown"],
+            Some(Subcommand::Render(PathBuf::from("geiger-report.json")))
+        ),
+        case(
+            vec!["geiger", "serve", "--listen", "127.0.0.1:7878"],
+            Some(Subcommand::Serve)
+        ),
+        case(
+            vec!["geiger", "verify", "geiger-report.json"],
+            Some(Subcommand::Verify(PathBuf::from("geiger-report.json")))
+        )
+    )]
+    fn parse_args_subcommand_test(
+        input_argument_vector: Vec<&str>,
+        expected_subcommand: Option<Subcommand>,
+    ) {
+        let args = Args::parse_args(Arguments::from_vec(
+            input_argument_vector
+                .into_iter()
+                .map(OsString::from)
+                .collect(),
+        ))
+        .unwrap();
+
+        assert_eq!(args.subcommand, expected_subcommand);
+    }
+
+    #[rstest(
+        input_argument_vector,
+        expected_package,
+        case(vec!["geiger", "--all"], None),
+        case(vec!["geiger", "tokio", "--all"], Some("tokio")),
+        case(vec!["tokio:0.2.22"], Some("tokio:0.2.22")),
+        case(vec!["geiger", "-p", "tokio"], Some("tokio")),
+        case(vec!["geiger", "--package", "tokio"], Some("tokio"))
+    )]
+    fn parse_args_package_test(
+        input_argument_vector: Vec<&str>,
+        expected_package: Option<&str>,
+    ) {
+        let args = Args::parse_args(Arguments::from_vec(
+            input_argument_vector
+                .into_iter()
+                .map(OsString::from)
+                .collect(),
+        ))
+        .unwrap();
+
+        assert_eq!(args.subcommand, None);
+        assert_eq!(args.package.as_deref(), expected_package);
+    }
+
+    #[rstest]
+    fn parse_args_manifest_paths_test() {
+        let args = Args::parse_args(Arguments::from_vec(vec![
+            OsString::from("--manifest-path"),
+            OsString::from("service/Cargo.toml"),
+            OsString::from("--manifest-path"),
+            OsString::from("tools/Cargo.toml"),
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            args.manifest_paths,
+            vec![
+                PathBuf::from("service/Cargo.toml"),
+                PathBuf::from("tools/Cargo.toml"),
+            ]
+        );
+    }
+
+    #[rstest(
+        input_args,
+        expected_fail_on_regression,
+        case(vec!["--baseline", "baseline.json"], Some(false)),
+        case(
+            vec!["--baseline", "baseline.json", "--fail-on-regression"],
+            Some(true)
+        ),
+        case(vec!["--fail-on-regression"], None)
+    )]
+    fn parse_args_fail_on_regression_test(
+        input_args: Vec<&str>,
+        expected_fail_on_regression: Option<bool>,
+    ) {
+        let args = Args::parse_args(Arguments::from_vec(
+            input_args.into_iter().map(OsString::from).collect(),
+        ));
+
+        assert_eq!(
+            args.ok().map(|args| args.fail_on_regression),
+            expected_fail_on_regression
+        );
+    }
+
+    #[rstest]
+    fn parse_args_snapshot_test() {
+        let args = Args::parse_args(Arguments::from_vec(vec![
+            OsString::from("--snapshot"),
+            OsString::from("--charset"),
+            OsString::from("utf8"),
+        ]))
+        .unwrap();
+
+        assert!(args.snapshot);
+        assert_eq!(args.charset, Charset::Ascii);
+        assert_eq!(args.color.as_deref(), Some("never"));
+        assert_eq!(args.scan_time(), UNIX_EPOCH);
+    }
+
+    #[rstest(
+        input_argument_vector,
+        expected_extra_deps,
+        case(vec!["--all-dependencies"], ExtraDeps::All),
+        case(vec!["--build-dependencies"], ExtraDeps::Build),
+        case(vec!["--dev-dependencies"], ExtraDeps::Dev),
+        case(vec![], ExtraDeps::NoMore)
+    )]
+    fn graph_options_extra_deps_test(
+        input_argument_vector: Vec<&str>,
+        expected_extra_deps: ExtraDeps,
+    ) {
+        let args = Args::parse_args(Arguments::from_vec(
+            input_argument_vector
+                .into_iter()
+                .map(OsString::from)
+                .collect(),
+        ))
+        .unwrap();
+
+        assert_eq!(args.graph_options().extra_deps, expected_extra_deps);
+    }
+
+    #[rstest]
+    fn parse_args_unstable_flags_test() {
+        let args = Args::parse_args(Arguments::from_vec(vec![
+            OsString::from("-Z"),
+            OsString::from("build-std unstable-options"),
+            OsString::from("-Z"),
+            OsString::from("features=host_dep"),
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            args.unstable_flags,
+            vec![
+                String::from("build-std"),
+                String::from("unstable-options"),
+                String::from("features=host_dep"),
+            ]
+        );
+    }
+
+    #[rstest(
+        input_argument_vector,
+        case(vec!["geiger", "cache"]),
+        case(vec!["geiger", "cache", "clear"]),
+        case(vec!["geiger", "fleet"]),
+        case(vec!["geiger", "render"]),
+        case(vec!["geiger", "scan-path"]),
+        case(vec!["geiger", "verify"]),
+        case(vec!["geiger", "unknown!"]),
+        case(vec!["geiger", "tokio", "--package", "tokio"])
+    )]
+    fn parse_args_subcommand_error_test(input_argument_vector: Vec<&str>) {
+        let args_result = Args::parse_args(Arguments::from_vec(
+            input_argument_vector
+                .into_iter()
+                .map(OsString::from)
+                .collect(),
+        ));
+
+        assert!(args_result.is_err());
+    }
 }
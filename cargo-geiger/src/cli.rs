@@ -11,25 +11,38 @@
 // TODO: Consider making this a lib.rs (again) and expose a full API, excluding
 // only the terminal output..? That API would be dependent on cargo.
 
+use crate::args::Args;
+
 use cargo::core::package::PackageSet;
 use cargo::core::registry::PackageRegistry;
 use cargo::core::resolver::ResolveOpts;
+use cargo::core::shell::Shell;
 use cargo::core::{Package, PackageId, PackageIdSpec, Resolve, Workspace};
 use cargo::ops;
 use cargo::util::{self, important_paths, CargoResult};
 use cargo::Config;
 use cargo_platform::Cfg;
-use std::path::PathBuf;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::str::{self, FromStr};
 
+/// The `--target` value selecting the union of all platforms, like
+/// `cargo tree --target all`.
+pub const ALL_TARGETS: &str = "all";
+
 /// TODO: Write proper documentation for this.
 /// This function seems to be looking up the active flags for conditional
-/// compilation (cargo_platform::Cfg instances).
+/// compilation (cargo_platform::Cfg instances). There are no active flags for
+/// `--target all`.
 pub fn get_cfgs(
     config: &Config,
     target: &Option<String>,
     workspace: &Workspace,
 ) -> CargoResult<Option<Vec<Cfg>>> {
+    if target.as_deref() == Some(ALL_TARGETS) {
+        return Ok(None);
+    }
     let mut process =
         util::process(&config.load_global_rustc(Some(workspace))?.path);
     process.arg("--print=cfg").env_remove("RUST_LOG");
@@ -49,19 +62,134 @@ pub fn get_cfgs(
     ))
 }
 
+/// Looks up the sysroot of the rustc used by the workspace, the rust-src
+/// component is installed below it.
+pub fn get_sysroot(
+    config: &Config,
+    workspace: &Workspace,
+) -> CargoResult<PathBuf> {
+    let mut process =
+        util::process(&config.load_global_rustc(Some(workspace))?.path);
+    process.arg("--print=sysroot").env_remove("RUST_LOG");
+    let output = process.exec_with_output()?;
+    let output = str::from_utf8(&output.stdout)?;
+    Ok(PathBuf::from(output.trim()))
+}
+
+/// Selects the toolchain of `--toolchain`, or else the one pinned by a
+/// `rust-toolchain` or `rust-toolchain.toml` file next to the manifest or in a
+/// parent directory, and builds with its rustc by setting `RUSTC` before the
+/// cargo configuration is loaded. The selected toolchain is left in
+/// `args.toolchain` for the report. A `RUSTC` already set in the environment
+/// wins over a pinned toolchain, and a pinned toolchain that rustup can't
+/// find is warned about and ignored. Like rustup, the toolchain of
+/// `cargo +<TOOLCHAIN> geiger`, passed on in `RUSTUP_TOOLCHAIN`, wins over
+/// the toolchain files.
+pub fn select_toolchain(args: &mut Args, shell: &mut Shell) -> CargoResult<()> {
+    let explicit = args.toolchain.is_some();
+    if !explicit {
+        if env::var_os("RUSTC").is_some() {
+            return Ok(());
+        }
+        if let Ok(toolchain) = env::var("RUSTUP_TOOLCHAIN") {
+            args.toolchain = Some(toolchain);
+            return Ok(());
+        }
+        let manifest_dir = match args.manifest_paths.first() {
+            Some(manifest_path) => manifest_path
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default(),
+            None => env::current_dir()?,
+        };
+        args.toolchain = find_pinned_toolchain(&manifest_dir);
+    }
+    let toolchain = match &args.toolchain {
+        Some(toolchain) => toolchain,
+        None => return Ok(()),
+    };
+    match get_toolchain_rustc(toolchain) {
+        Ok(rustc) => env::set_var("RUSTC", rustc),
+        Err(e) if explicit => return Err(e),
+        Err(e) => {
+            shell.warn(format!(
+                "ignoring the pinned toolchain {}: {}",
+                toolchain, e
+            ))?;
+            args.toolchain = None;
+        }
+    }
+    Ok(())
+}
+
+/// The toolchain pinned for `dir` like rustup looks it up, the closest
+/// toolchain file wins.
+fn find_pinned_toolchain(dir: &Path) -> Option<String> {
+    dir.ancestors().find_map(|dir| {
+        ["rust-toolchain", "rust-toolchain.toml"]
+            .iter()
+            .find_map(|file_name| fs::read_to_string(dir.join(file_name)).ok())
+            .and_then(|contents| parse_toolchain_file(&contents))
+    })
+}
+
+/// The channel of a toolchain file, either in the `[toolchain]` table or,
+/// for the legacy `rust-toolchain` file, alone on the first line.
+fn parse_toolchain_file(contents: &str) -> Option<String> {
+    match contents.parse::<toml::Value>() {
+        Ok(toolchain_file) => toolchain_file
+            .get("toolchain")?
+            .get("channel")?
+            .as_str()
+            .map(String::from),
+        Err(_) => contents
+            .lines()
+            .next()
+            .map(str::trim)
+            .filter(|channel| !channel.is_empty())
+            .map(String::from),
+    }
+}
+
+/// The path of the rustc of a toolchain installed with rustup.
+fn get_toolchain_rustc(toolchain: &str) -> CargoResult<PathBuf> {
+    let mut process = util::process("rustup");
+    process.args(&["which", "rustc", "--toolchain", toolchain]);
+    let output = process.exec_with_output()?;
+    let output = str::from_utf8(&output.stdout)?;
+    Ok(PathBuf::from(output.trim()))
+}
+
+/// The `build.target` of the cargo configuration, the target `cargo build`
+/// builds for when no `--target` is given.
+pub fn get_config_target(config: &Config) -> CargoResult<Option<String>> {
+    config.get::<Option<String>>("build.target")
+}
+
+/// The features of `--features`, space separated.
+pub fn get_features(args: &Args) -> Vec<String> {
+    args.features
+        .as_ref()
+        .cloned()
+        .unwrap_or_else(String::new)
+        .split(' ')
+        .map(str::to_owned)
+        .collect::<Vec<String>>()
+}
+
 pub fn get_registry<'a>(
     config: &'a Config,
-    package: &Package,
+    package_ids: &[PackageId],
 ) -> CargoResult<PackageRegistry<'a>> {
     let mut registry = PackageRegistry::new(config)?;
-    registry.add_sources(Some(package.package_id().source_id()))?;
+    registry.add_sources(package_ids.iter().map(|id| id.source_id()))?;
     Ok(registry)
 }
 
 pub fn get_workspace(
     config: &Config,
     manifest_path: Option<PathBuf>,
-) -> CargoResult<Workspace> {
+) -> CargoResult<Workspace<'_>> {
     let root = match manifest_path {
         Some(path) => path,
         None => important_paths::find_root_manifest_for_wd(config.cwd())?,
@@ -69,14 +197,80 @@ pub fn get_workspace(
     Workspace::new(&root, config)
 }
 
+/// The packages to root the scan at, the current package of the workspace,
+/// or every member of a virtual workspace, which has no root package.
+pub fn get_root_package_ids(
+    workspace: &Workspace,
+) -> CargoResult<Vec<PackageId>> {
+    if !workspace.is_virtual() {
+        return Ok(vec![workspace.current()?.package_id()]);
+    }
+    let mut root_package_ids = workspace
+        .members()
+        .map(Package::package_id)
+        .collect::<Vec<PackageId>>();
+    root_package_ids.sort();
+    Ok(root_package_ids)
+}
+
+/// Resolves the dependencies of the packages together, like the members of
+/// a workspace are resolved.
 pub fn resolve<'a, 'cfg>(
-    package_id: PackageId,
+    package_ids: &[PackageId],
     registry: &mut PackageRegistry<'cfg>,
     workspace: &'a Workspace<'cfg>,
     features: &[String],
     all_features: bool,
     no_default_features: bool,
 ) -> CargoResult<(PackageSet<'a>, Resolve)> {
+    let prev = ops::load_pkg_lockfile(workspace)?;
+    let resolve = resolve_with_lockfile(
+        package_ids,
+        registry,
+        workspace,
+        features,
+        all_features,
+        no_default_features,
+        prev.as_ref(),
+    )?;
+    let packages = ops::get_resolved_packages(
+        &resolve,
+        PackageRegistry::new(workspace.config())?,
+    )?;
+    Ok((packages, resolve))
+}
+
+/// Resolves the dependencies to the versions `cargo update` would pick,
+/// ignoring the versions locked in `Cargo.lock`. The lockfile is not
+/// written.
+pub fn resolve_updated<'cfg>(
+    package_ids: &[PackageId],
+    registry: &mut PackageRegistry<'cfg>,
+    workspace: &Workspace<'cfg>,
+    features: &[String],
+    all_features: bool,
+    no_default_features: bool,
+) -> CargoResult<Resolve> {
+    resolve_with_lockfile(
+        package_ids,
+        registry,
+        workspace,
+        features,
+        all_features,
+        no_default_features,
+        None,
+    )
+}
+
+fn resolve_with_lockfile<'cfg>(
+    package_ids: &[PackageId],
+    registry: &mut PackageRegistry<'cfg>,
+    workspace: &Workspace<'cfg>,
+    features: &[String],
+    all_features: bool,
+    no_default_features: bool,
+    prev: Option<&Resolve>,
+) -> CargoResult<Resolve> {
     let dev_deps = true; // TODO: Review this.
     let uses_default_features = !no_default_features;
     let opts = ResolveOpts::new(
@@ -85,21 +279,18 @@ pub fn resolve<'a, 'cfg>(
         all_features,
         uses_default_features,
     );
-    let prev = ops::load_pkg_lockfile(workspace)?;
-    let resolve = ops::resolve_with_previous(
+    ops::resolve_with_previous(
         registry,
         workspace,
         &opts,
-        prev.as_ref(),
+        prev,
         None,
-        &[PackageIdSpec::from_package_id(package_id)],
+        &package_ids
+            .iter()
+            .map(|package_id| PackageIdSpec::from_package_id(*package_id))
+            .collect::<Vec<PackageIdSpec>>(),
         true,
-    )?;
-    let packages = ops::get_resolved_packages(
-        &resolve,
-        PackageRegistry::new(workspace.config())?,
-    )?;
-    Ok((packages, resolve))
+    )
 }
 
 // TODO: Make a wrapper type for canonical paths and hide all mutable access.
@@ -141,6 +332,54 @@ mod cli_tests {
         assert!(!key_pairs.is_empty());
     }
 
+    #[rstest]
+    fn find_pinned_toolchain_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let member_dir = dir.path().join("member");
+        fs::create_dir(&member_dir).unwrap();
+        fs::write(
+            dir.path().join("rust-toolchain.toml"),
+            "[toolchain]\nchannel = \"1.47.0\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            find_pinned_toolchain(&member_dir),
+            Some(String::from("1.47.0"))
+        );
+
+        fs::write(member_dir.join("rust-toolchain"), "nightly-2020-10-01\n")
+            .unwrap();
+
+        assert_eq!(
+            find_pinned_toolchain(&member_dir),
+            Some(String::from("nightly-2020-10-01"))
+        );
+    }
+
+    #[rstest(
+        input_contents,
+        expected_toolchain,
+        case("[toolchain]\nchannel = \"stable\"\n", Some("stable")),
+        case(
+            "[toolchain]\nchannel = \"nightly-2020-10-01\"\n\
+             components = [\"rust-src\"]\n",
+            Some("nightly-2020-10-01")
+        ),
+        case("[toolchain]\ncomponents = [\"rust-src\"]\n", None),
+        case("1.47.0\n", Some("1.47.0")),
+        case("", None)
+    )]
+    fn parse_toolchain_file_test(
+        input_contents: &str,
+        expected_toolchain: Option<&str>,
+    ) {
+        assert_eq!(
+            parse_toolchain_file(input_contents).as_deref(),
+            expected_toolchain
+        );
+    }
+
     #[rstest]
     fn get_registry_test() {
         let config = Config::default().unwrap();
@@ -151,7 +390,7 @@ mod cli_tests {
         .unwrap();
         let package = workspace.current().unwrap();
 
-        let registry_result = get_registry(&config, &package);
+        let registry_result = get_registry(&config, &[package.package_id()]);
 
         assert!(registry_result.is_ok());
         let registry = registry_result.unwrap();
@@ -179,4 +418,65 @@ mod cli_tests {
 
         assert_eq!(package.package_id().name(), "cargo-geiger");
     }
+
+    #[rstest]
+    fn get_workspace_from_nested_dir_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested_dir = dir.path().join("src").join("nested");
+        fs::create_dir_all(&nested_dir).unwrap();
+        fs::write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"nested\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("src").join("lib.rs"), "").unwrap();
+        let config = Config::new(
+            Shell::new(),
+            nested_dir,
+            dir.path().join("cargo-home"),
+        );
+
+        let workspace = get_workspace(&config, None).unwrap();
+
+        assert_eq!(workspace.current().unwrap().name(), "nested");
+    }
+
+    #[rstest]
+    fn get_root_package_ids_of_virtual_workspace_test() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("Cargo.toml"),
+            "[workspace]\nmembers = [\"b\", \"a\"]\n",
+        )
+        .unwrap();
+        for member in &["a", "b"] {
+            let member_dir = dir.path().join(member);
+            fs::create_dir_all(member_dir.join("src")).unwrap();
+            fs::write(
+                member_dir.join("Cargo.toml"),
+                format!(
+                    "[package]\nname = \"{}\"\nversion = \"0.1.0\"\n",
+                    member
+                ),
+            )
+            .unwrap();
+            fs::write(member_dir.join("src").join("lib.rs"), "").unwrap();
+        }
+        let config = Config::new(
+            Shell::new(),
+            dir.path().to_path_buf(),
+            dir.path().join("cargo-home"),
+        );
+
+        let workspace = get_workspace(&config, None).unwrap();
+        let root_package_ids = get_root_package_ids(&workspace).unwrap();
+
+        assert_eq!(
+            root_package_ids
+                .iter()
+                .map(|package_id| package_id.name().as_str())
+                .collect::<Vec<&str>>(),
+            vec!["a", "b"]
+        );
+    }
 }
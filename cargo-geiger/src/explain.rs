@@ -0,0 +1,339 @@
+//! Definitions of the metrics, symbols and detection statuses printed by
+//! `cargo geiger`, for `cargo geiger explain`.
+
+use cargo::{CliError, CliResult};
+use std::error::Error;
+use std::fmt;
+
+/// A metric, symbol or detection status that can be explained, with the names
+/// it can be looked up by.
+struct Topic {
+    names: &'static [&'static str],
+    explanation: &'static str,
+}
+
+const TOPICS: &[Topic] = &[
+    Topic {
+        names: &["x/y", "metrics", "used"],
+        explanation: "\
+Every metric column is printed as x/y.
+    x = unsafe occurrences in the source files used by the build
+    y = unsafe occurrences in all source files found in the crate
+A file counts as used if it is listed in the dep-info files written by
+`cargo check`, so x depends on the target, features and flags of the scan.
+Files that are never compiled, like unused modules or examples, only count
+towards y. With --used-only only x is printed, with --unused-only only
+y - x.",
+    },
+    Topic {
+        names: &["functions", "fns"],
+        explanation: "\
+Functions: the number of free-standing `unsafe fn` items. Methods in impl
+blocks are counted in the Methods column instead. Functions with a #[test]
+attribute and everything in a #[cfg(test)] module are skipped, unless
+--include-tests is given.",
+    },
+    Topic {
+        names: &["expressions", "exprs"],
+        explanation: "\
+Expressions: the number of expressions inside an `unsafe` block or an
+`unsafe fn` or method body. Paths and literals are not counted on their own,
+so `f(x)` counts as a single expression. The body of a macro invocation is
+not parsed and is not counted, neither is code generated by macros, but
+files pulled in with `include!` are scanned. The expressions of an `unsafe
+fn` body outside of any `unsafe` block, which may be unsafe operations
+without a block, are also listed as a category below the table.",
+    },
+    Topic {
+        names: &["impls"],
+        explanation: "\
+Impls: the number of `unsafe impl` blocks, implementations of unsafe traits
+like `unsafe impl Send for T {}`.",
+    },
+    Topic {
+        names: &["traits"],
+        explanation: "\
+Traits: the number of `unsafe trait` declarations.",
+    },
+    Topic {
+        names: &["methods"],
+        explanation: "\
+Methods: the number of `unsafe fn` methods in impl blocks. Methods declared
+in traits are not counted, with or without a default body.",
+    },
+    Topic {
+        names: &["asm", "inline-assembly"],
+        explanation: "\
+Inline assembly: the number of asm!, global_asm!, naked_asm! and llvm_asm!
+invocations, matched by the last segment of the macro path. Their locations
+are listed with --verbose.",
+    },
+    Topic {
+        names: &["drop"],
+        explanation: "\
+Unsafe expressions in Drop implementations: the unsafe expressions inside
+`impl Drop for T` blocks, run implicitly whenever a value goes out of
+scope. These are also counted in the Expressions column.",
+    },
+    Topic {
+        names: &["unsafe-fn-bodies"],
+        explanation: "\
+Unsafe expressions in unsafe fn bodies, outside unsafe blocks: the
+expressions directly in the body of an `unsafe fn` function or method.
+These may be unsafe operations without an `unsafe` block, which the
+unsafe_op_in_unsafe_fn lint flags. They are also counted in the Expressions
+column.",
+    },
+    Topic {
+        names: &["const"],
+        explanation: "\
+Unsafe expressions in const fn bodies and const initializers: the unsafe
+expressions evaluated at compile time, in `const fn` bodies and in the
+initializers of `const` and `static` items. These are also counted in the
+Expressions column.",
+    },
+    Topic {
+        names: &["packed"],
+        explanation: "\
+#[repr(packed)] structs: the number of structs with a packed representation,
+taking references to their fields is undefined behavior for misaligned
+fields.",
+    },
+    Topic {
+        names: &["static-mut"],
+        explanation: "\
+static mut items: the number of `static mut` declarations, every access to
+them is unsafe.",
+    },
+    Topic {
+        names: &["static-mut-refs"],
+        explanation: "\
+References to static mut items (matched by name): the number of paths whose
+last segment is the name of a `static mut` item declared in the same file.
+This is a heuristic, the paths are not resolved, a local binding or an item
+of another module with the same name is also counted.",
+    },
+    Topic {
+        names: &["unions"],
+        explanation: "\
+union definitions: the number of `union` items, reading their fields is
+unsafe.",
+    },
+    Topic {
+        names: &["union-fields"],
+        explanation: "\
+Union field reads in unsafe code (matched by field name): the number of field
+expressions inside unsafe code naming a field of a union declared in the same
+file. Plain assignments to a union field are safe and not counted. This is a
+heuristic, the type of the receiver is not known, a struct field with the
+same name is also counted.",
+    },
+    Topic {
+        names: &["extern-statics"],
+        explanation: "\
+extern static declarations (FFI): the number of `static` items declared in
+`extern` blocks, accessing them is unsafe.",
+    },
+    Topic {
+        names: &["ffi"],
+        explanation: "\
+Calls to extern functions (FFI): the number of calls to functions declared in
+native `extern` blocks of the same file.",
+    },
+    Topic {
+        names: &["js-ffi"],
+        explanation: "\
+Calls to JavaScript imports (wasm-bindgen, JS FFI): the number of calls to
+functions declared in #[wasm_bindgen] or wasm_import_module `extern` blocks,
+or in `extern` blocks only built for wasm32, of the same file. These are not
+counted as FFI calls.",
+    },
+    Topic {
+        names: &["exported-globals"],
+        explanation: "\
+Exported globals (FFI): the number of statics exported with #[no_mangle] or
+#[export_name], which foreign code can read and write.",
+    },
+    Topic {
+        names: &["public-unsafe-fns"],
+        explanation: "\
+pub unsafe fn items (public API): the number of `pub unsafe fn` functions and
+methods, unsafe contracts the crate exports to its users.",
+    },
+    Topic {
+        names: &["public-unsafe-traits"],
+        explanation: "\
+pub unsafe trait items (public API): the number of `pub unsafe trait`
+declarations, which users of the crate implement with `unsafe impl`.",
+    },
+    Topic {
+        names: &["dependency", "tree"],
+        explanation: "\
+Dependency: the package in the dependency tree, prefixed with its detection
+status symbol. Packages already listed higher up are marked with (*) and
+their dependencies are not repeated, unless --all is given. Build scripts
+are scanned like any other entry point and counted for their package, build
+and dev dependencies are only included with --build-dependencies,
+--dev-dependencies or --all-dependencies.",
+    },
+    Topic {
+        names: &["forbids", "lock", "🔒", ":)"],
+        explanation: "\
+🔒 (:) with --charset ascii): no unsafe usage was found in the crate and all
+of its entry points, lib.rs, main.rs, build.rs and so on, declare
+#![forbid(unsafe_code)]. The compiler then rejects any unsafe code in the
+crate, except code generated by macros from other crates.",
+    },
+    Topic {
+        names: &["unknown", "question", "❓", "?"],
+        explanation: "\
+❓ (? with --charset ascii): no unsafe usage was found in the source files
+used by the build, but at least one entry point does not declare
+#![forbid(unsafe_code)]. Unsafe code can still be hidden in macro bodies or
+in files that were not scanned.",
+    },
+    Topic {
+        names: &["unsafe", "rads", "☢", "!"],
+        explanation: "\
+☢️ (! with --charset ascii): unsafe usage was found in the source files used
+by the build, at least one of the x values is non-zero. Unsafe usage that is
+only in unused files shows up in the y values but does not set this status.",
+    },
+    Topic {
+        names: &["mismatch", "warning", "⚠", "~"],
+        explanation: "\
+⚠️ (~ with --charset ascii): all entry points of the crate declare
+#![forbid(unsafe_code)], yet unsafe usage or #[allow(unsafe_code)] was found
+in the source files used by the build, e.g. in files pulled in with
+`include!` or generated by a build script. The declaration does not cover
+all of the code the crate is built from.",
+    },
+    Topic {
+        names: &["missing", "❌", "x"],
+        explanation: "\
+❌ (x with --charset ascii): no metrics are available for the crate, e.g.
+because its source files could not be parsed. Nothing is known about its
+unsafe usage, the crate is a gap in the coverage of the scan. Use
+--fail-on-missing-metrics to exit with an error when any crate has no
+metrics.",
+    },
+    Topic {
+        names: &["status", "detection"],
+        explanation: "\
+The detection status of a crate is one of, in order of precedence:
+    ⚠️  #![forbid(unsafe_code)] declared, but unsafe usage found anyway
+    ☢️  unsafe usage found in the code used by the build
+    ❓  no unsafe usage found, missing #![forbid(unsafe_code)]
+    🔒  no unsafe usage found, declares #![forbid(unsafe_code)]
+The summary below the table counts the crates per status.",
+    },
+];
+
+/// Prints the explanation of a topic, or lists the topics when none is given.
+pub fn run_explain(topic: Option<&str>) -> CliResult {
+    match topic {
+        None => {
+            println!("{}", construct_topics_line());
+            Ok(())
+        }
+        Some(name) => match find_topic(name) {
+            Some(topic) => {
+                println!("{}", topic.explanation);
+                Ok(())
+            }
+            None => Err(CliError::new(
+                anyhow::Error::new(UnknownTopicError {
+                    topic: name.to_string(),
+                    known_topics: construct_topics_line(),
+                }),
+                1,
+            )),
+        },
+    }
+}
+
+#[derive(Debug)]
+struct UnknownTopicError {
+    topic: String,
+    known_topics: String,
+}
+
+impl Error for UnknownTopicError {}
+
+impl fmt::Display for UnknownTopicError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown topic `{}`. {}", self.topic, self.known_topics)
+    }
+}
+
+/// Looks up a topic by any of its names, ignoring case. The variation
+/// selector some terminals add to the symbols is ignored too.
+fn find_topic(name: &str) -> Option<&'static Topic> {
+    let name = name.trim().trim_end_matches('\u{fe0f}').to_lowercase();
+    TOPICS
+        .iter()
+        .find(|topic| topic.names.iter().any(|topic_name| *topic_name == name))
+}
+
+fn construct_topics_line() -> String {
+    format!(
+        "Topics: {}",
+        TOPICS
+            .iter()
+            .map(|topic| topic.names.join(", "))
+            .collect::<Vec<_>>()
+            .join("; ")
+    )
+}
+
+#[cfg(test)]
+mod explain_tests {
+    use super::*;
+
+    use crate::format::table::CATEGORIES;
+
+    use rstest::*;
+
+    #[rstest(
+        input_name,
+        expected_first_name,
+        case("exprs", Some("expressions")),
+        case("Expressions", Some("expressions")),
+        case("☢", Some("unsafe")),
+        case("☢️", Some("unsafe")),
+        case(":)", Some("forbids")),
+        case("⚠️", Some("mismatch")),
+        case("x/y", Some("x/y")),
+        case("columns", None)
+    )]
+    fn find_topic_test(input_name: &str, expected_first_name: Option<&str>) {
+        assert_eq!(
+            find_topic(input_name).map(|topic| topic.names[0]),
+            expected_first_name
+        );
+    }
+
+    #[rstest]
+    fn every_category_has_a_topic_test() {
+        for category in CATEGORIES {
+            assert!(
+                find_topic(category.topic).is_some(),
+                "no topic for {:?}",
+                category.message
+            );
+        }
+    }
+
+    #[rstest]
+    fn topic_names_are_unique_test() {
+        let mut names = TOPICS
+            .iter()
+            .flat_map(|topic| topic.names.iter())
+            .collect::<Vec<_>>();
+        let name_count = names.len();
+        names.sort_unstable();
+        names.dedup();
+
+        assert_eq!(names.len(), name_count);
+    }
+}
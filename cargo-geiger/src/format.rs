@@ -1,11 +1,19 @@
 pub mod emoji_symbols;
+pub mod html;
+pub mod i18n;
+pub mod markdown;
+pub mod numbers;
 pub mod pattern;
 pub mod print_config;
+pub mod security_insights;
 pub mod table;
+pub mod treemap;
 
 mod display;
 mod parse;
 
+use i18n::{tr, Lang, Message};
+
 use cargo::core::dependency::DepKind;
 use std::fmt;
 use std::str::{self, FromStr};
@@ -42,6 +50,7 @@ pub enum CrateDetectionStatus {
     NoneDetectedForbidsUnsafe,
     NoneDetectedAllowsUnsafe,
     UnsafeDetected,
+    ForbidMismatch,
 }
 
 #[derive(Debug, PartialEq)]
@@ -56,6 +65,8 @@ pub enum SymbolKind {
     Lock = 0,
     QuestionMark = 1,
     Rads = 2,
+    Warning = 3,
+    Missing = 4,
 }
 
 #[derive(Debug)]
@@ -81,6 +92,43 @@ pub fn get_kind_group_name(dep_kind: DepKind) -> Option<&'static str> {
     }
 }
 
+/// The markers appended to the name of a package in the tree, for packages
+/// that are not taken from their original source or have a yanked version.
+pub fn package_name_markers(
+    lang: Lang,
+    patched: bool,
+    local_override: bool,
+    yanked: bool,
+    via_feature: Option<&str>,
+) -> String {
+    let mut markers = String::new();
+    for (is_marked, message) in &[
+        (patched, Message::Patched),
+        (local_override, Message::LocalOverride),
+        (yanked, Message::Yanked),
+    ] {
+        if *is_marked {
+            markers.push(' ');
+            markers.push_str(tr(lang, *message));
+        }
+    }
+    if let Some(feature) = via_feature {
+        markers.push_str(&format!(
+            " ({} \"{}\")",
+            tr(lang, Message::ViaFeature),
+            feature
+        ));
+    }
+    markers
+}
+
+/// Pads `text` with trailing spaces to `width` terminal columns. The width
+/// is measured ignoring color codes and counting wide characters, like CJK,
+/// as two columns, so columns stay aligned where `{: <N}` would count chars.
+pub fn pad_to_width(text: &str, width: usize) -> String {
+    console::pad_str(text, width, console::Alignment::Left, None).into_owned()
+}
+
 #[cfg(test)]
 mod format_tests {
     use super::*;
@@ -108,4 +156,73 @@ mod format_tests {
 
         assert_eq!(get_kind_group_name(DepKind::Normal), None);
     }
+
+    #[rstest(
+        input_patched,
+        input_local_override,
+        input_yanked,
+        input_via_feature,
+        expected_markers,
+        case(false, false, false, None, ""),
+        case(true, false, false, None, " (patched)"),
+        case(false, true, false, None, " (local override)"),
+        case(true, true, false, None, " (patched) (local override)"),
+        case(false, false, true, None, " (yanked)"),
+        case(true, true, true, None, " (patched) (local override) (yanked)"),
+        case(false, false, false, Some("tls"), " (via feature \"tls\")"),
+        case(
+            false,
+            false,
+            true,
+            Some("tls"),
+            " (yanked) (via feature \"tls\")"
+        )
+    )]
+    fn package_name_markers_test(
+        input_patched: bool,
+        input_local_override: bool,
+        input_yanked: bool,
+        input_via_feature: Option<&str>,
+        expected_markers: &str,
+    ) {
+        assert_eq!(
+            package_name_markers(
+                Lang::En,
+                input_patched,
+                input_local_override,
+                input_yanked,
+                input_via_feature
+            ),
+            expected_markers
+        );
+    }
+
+    #[rstest(
+        input_text,
+        input_width,
+        expected_text,
+        case("!", 2, "! "),
+        case("危", 2, "危"),
+        case("危", 3, "危 "),
+        case("e\u{301}", 2, "e\u{301} "),
+        case("UNSAFE", 2, "UNSAFE")
+    )]
+    fn pad_to_width_test(
+        input_text: &str,
+        input_width: usize,
+        expected_text: &str,
+    ) {
+        assert_eq!(pad_to_width(input_text, input_width), expected_text);
+    }
+
+    #[rstest]
+    fn pad_to_width_ignores_color_codes_test() {
+        use colored::Colorize;
+
+        let colored_text = "危".red().to_string();
+        assert_eq!(
+            pad_to_width(&colored_text, 3),
+            format!("{} ", colored_text)
+        );
+    }
 }
@@ -1,32 +1,131 @@
 use crate::format::{Charset, SymbolKind};
 
+use cargo::util::CargoResult;
+use cargo::Config;
 use colored::Colorize;
+use serde::Deserialize;
+
+/// The `cargo` configuration key of the symbol overrides.
+const SYMBOLS_CONFIG_KEY: &str = "geiger.symbols";
 
 pub struct EmojiSymbols {
     charset: Charset,
-    emojis: [&'static str; 3],
-    fallbacks: [colored::ColoredString; 3],
+    emojis: [String; 5],
+    fallbacks: [colored::ColoredString; 5],
+    customized: bool,
 }
 
 impl EmojiSymbols {
     pub fn emoji(&self, kind: SymbolKind) -> Box<dyn std::fmt::Display> {
         let idx = kind as usize;
         if self.will_output_emoji() {
-            Box::new(self.emojis[idx])
+            Box::new(self.emojis[idx].clone())
         } else {
             Box::new(self.fallbacks[idx].clone())
         }
     }
-    pub fn new(charset: Charset) -> EmojiSymbols {
-        Self {
+    pub fn new(charset: Charset, overrides: &SymbolOverrides) -> EmojiSymbols {
+        let mut emoji_symbols = Self {
             charset,
-            emojis: ["🔒", "❓", "☢️"],
-            fallbacks: [":)".green(), "?".normal(), "!".red().bold()],
+            emojis: [
+                String::from("🔒"),
+                String::from("❓"),
+                String::from("☢️"),
+                String::from("⚠️"),
+                String::from("❌"),
+            ],
+            fallbacks: [
+                colorize_symbol(SymbolKind::Lock, ":)"),
+                colorize_symbol(SymbolKind::QuestionMark, "?"),
+                colorize_symbol(SymbolKind::Rads, "!"),
+                colorize_symbol(SymbolKind::Warning, "~"),
+                colorize_symbol(SymbolKind::Missing, "x"),
+            ],
+            customized: false,
+        };
+        for (kind, symbol) in &[
+            (SymbolKind::Lock, &overrides.forbids),
+            (SymbolKind::QuestionMark, &overrides.unknown),
+            (SymbolKind::Rads, &overrides.unsafe_),
+            (SymbolKind::Warning, &overrides.mismatch),
+            (SymbolKind::Missing, &overrides.missing),
+        ] {
+            if let Some(symbol) = symbol {
+                let idx = *kind as usize;
+                emoji_symbols.emojis[idx] = symbol.clone();
+                emoji_symbols.fallbacks[idx] = colorize_symbol(*kind, symbol);
+                emoji_symbols.customized = true;
+            }
         }
+        emoji_symbols
     }
 
     pub fn will_output_emoji(&self) -> bool {
         self.charset == Charset::Utf8
             && console::Term::stdout().features().wants_emoji()
     }
+
+    /// The cursor is moved past the symbols to work around emoji rendered
+    /// narrower than their width, this is not needed for custom symbols.
+    pub fn needs_cursor_shift(&self) -> bool {
+        !self.customized && self.will_output_emoji()
+    }
+}
+
+fn colorize_symbol(kind: SymbolKind, symbol: &str) -> colored::ColoredString {
+    match kind {
+        SymbolKind::Lock => symbol.green(),
+        SymbolKind::QuestionMark => symbol.normal(),
+        SymbolKind::Rads => symbol.red().bold(),
+        SymbolKind::Warning => symbol.yellow().bold(),
+        SymbolKind::Missing => symbol.yellow(),
+    }
+}
+
+/// Replacements for the symbols of the detection statuses, configured in the
+/// `[geiger.symbols]` table of the `cargo` configuration, e.g.
+/// `unsafe = "UNSAFE"`. The replacements are used with both charsets.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+pub struct SymbolOverrides {
+    pub forbids: Option<String>,
+    pub unknown: Option<String>,
+    #[serde(rename = "unsafe")]
+    pub unsafe_: Option<String>,
+    pub mismatch: Option<String>,
+    pub missing: Option<String>,
+}
+
+impl SymbolOverrides {
+    pub fn from_config(config: &Config) -> CargoResult<SymbolOverrides> {
+        Ok(config
+            .get::<Option<SymbolOverrides>>(SYMBOLS_CONFIG_KEY)?
+            .unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod emoji_symbols_tests {
+    use super::*;
+
+    use rstest::*;
+
+    #[rstest]
+    fn emoji_symbols_new_with_overrides_test() {
+        let overrides = SymbolOverrides {
+            unsafe_: Some(String::from("UNSAFE")),
+            ..Default::default()
+        };
+
+        let emoji_symbols = EmojiSymbols::new(Charset::Ascii, &overrides);
+
+        assert_eq!(
+            emoji_symbols.emoji(SymbolKind::Rads).to_string(),
+            "UNSAFE".red().bold().to_string()
+        );
+        assert_eq!(
+            emoji_symbols.emoji(SymbolKind::Lock).to_string(),
+            ":)".green().to_string()
+        );
+        assert!(!emoji_symbols.needs_cursor_shift());
+    }
 }
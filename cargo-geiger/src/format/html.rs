@@ -0,0 +1,1392 @@
+//! Standalone HTML rendering of the reports. The tables can be sorted by
+//! clicking a column header and filtered with the search box, rows with
+//! details, like the files of a package, can be expanded, and the files link
+//! to their source. The dependency tree is drawn as nested lists that can be
+//! collapsed, and the unsafe usage per crate and file as a treemap. Given a
+//! baseline report, the changes since it are marked.
+
+use crate::format::treemap::{
+    treemap_from_path_report, treemap_from_report, treemap_total,
+};
+
+use cargo_geiger_serde::{
+    Count, CounterBlock, FileUnsafeInfo, PackageId, PackageMetadata,
+    PathSafetyReport, QuickSafetyReport, ReportEntry, SafetyReport,
+    SelfReportedClaims, TreemapNode,
+};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+pub const UNSAFE_COUNTER_COLUMNS: [&str; 5] =
+    ["Functions", "Expressions", "Impls", "Traits", "Methods"];
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; }
+input#search { margin-bottom: 1em; padding: 0.3em; width: 20em; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: right; }
+th { background: #eee; cursor: pointer; user-select: none; }
+td:first-child, th:first-child { text-align: left; }
+tr.details { display: none; }
+tr.details.open { display: table-row; }
+tr.details > td { background: #fafafa; }
+button.toggle { border: none; background: none; cursor: pointer; }
+.treemap-node { display: flex; min-width: 0; min-height: 0;
+  border: 1px solid #fff; background: rgba(187, 0, 0, 0.15);
+  overflow: hidden; font-size: 0.7em; }
+.treemap { display: flex; height: 40em; }
+.unsafe { color: #b00; font-weight: bold; }
+.increased { color: #b00; }
+.decreased { color: #080; }
+ul.tree, ul.tree ul { list-style: none; padding-left: 1.2em; }
+ul.tree summary { cursor: pointer; }
+";
+
+/// Sorting moves whole `tbody` elements, so that the details row stays below
+/// the row it belongs to. Cells are compared by their `data-sort` value,
+/// numerically if both values are numbers.
+const SCRIPT: &str = "
+function sortTable(table, column) {
+  var ascending = table.dataset.sortColumn != column
+    || table.dataset.sortOrder != 'asc';
+  table.dataset.sortColumn = column;
+  table.dataset.sortOrder = ascending ? 'asc' : 'desc';
+  var bodies = Array.prototype.slice.call(table.tBodies);
+  bodies.sort(function (a, b) {
+    var x = a.rows[0].cells[column].dataset.sort;
+    var y = b.rows[0].cells[column].dataset.sort;
+    var order = (x !== '' && y !== '' && !isNaN(x) && !isNaN(y))
+      ? Number(x) - Number(y)
+      : x.localeCompare(y);
+    return ascending ? order : -order;
+  });
+  bodies.forEach(function (body) { table.appendChild(body); });
+}
+function filterTable(table, text) {
+  var needle = text.toLowerCase();
+  Array.prototype.forEach.call(table.tBodies, function (body) {
+    var key = body.rows[0].cells[0].dataset.sort.toLowerCase();
+    body.style.display = key.indexOf(needle) >= 0 ? '' : 'none';
+  });
+}
+function toggleDetails(button) {
+  var details = button.closest('tbody').querySelector('tr.details');
+  details.classList.toggle('open');
+  button.textContent = details.classList.contains('open') ? '-' : '+';
+}
+document.addEventListener('DOMContentLoaded', function () {
+  var table = document.getElementById('report');
+  Array.prototype.forEach.call(table.tHead.rows[0].cells, function (th, i) {
+    th.addEventListener('click', function () { sortTable(table, i); });
+  });
+  document.getElementById('search').addEventListener('input', function (e) {
+    filterTable(table, e.target.value);
+  });
+});
+";
+
+/// A table of the report, one row per package or file.
+pub struct HtmlTable {
+    pub headers: Vec<String>,
+    pub rows: Vec<HtmlRow>,
+}
+
+/// A row with an optional table of details, shown when the row is expanded.
+pub struct HtmlRow {
+    pub cells: Vec<HtmlCell>,
+    pub details: Option<HtmlTable>,
+}
+
+/// A package of the dependency tree, with the unsafe code used by the build
+/// of it, and its dependencies.
+pub struct HtmlTreeNode {
+    pub name: String,
+    /// None for a package without metrics.
+    pub used_unsafe: Option<u64>,
+    /// Whether the dependencies of the package are listed at its first
+    /// occurrence in the tree instead.
+    pub repeated: bool,
+    pub children: Vec<HtmlTreeNode>,
+}
+
+pub enum HtmlCell {
+    Text(String),
+    Number(u64),
+    /// Unsafe usage in the code used by the build out of the total found,
+    /// sorted by the former.
+    UsedOfTotal(u64, u64),
+    /// Like `UsedOfTotal`, with the change of the used unsafe usage since the
+    /// baseline report.
+    UsedOfTotalChange(u64, u64, i64),
+    /// A text linking to another page, sorted by the text.
+    Link(String, String),
+    /// Texts linking to other pages, separated by commas and sorted by
+    /// their count.
+    Links(Vec<(String, String)>),
+}
+
+/// Renders the report of a full scan, the packages can be expanded to list
+/// their files when the report includes them.
+pub fn render_html_report(report: &SafetyReport) -> String {
+    render_full_html_report(report, false, None)
+}
+
+/// Renders the report of a full scan with the packages linking to their own
+/// page, see `render_package_html_report`, for `--serve`.
+pub fn render_linked_html_report(report: &SafetyReport) -> String {
+    render_full_html_report(report, true, None)
+}
+
+/// Renders the report of a full scan marking the changes since the baseline
+/// report, for `--baseline`. Packages are matched by name, so that a new
+/// version of a package is not a new package.
+pub fn render_diff_html_report(
+    report: &SafetyReport,
+    baseline: &SafetyReport,
+) -> String {
+    render_full_html_report(report, false, Some(baseline))
+}
+
+/// Renders the files of a single package of a full scan.
+pub fn render_package_html_report(entry: &ReportEntry) -> String {
+    let unsafety = &entry.unsafety;
+    let mut summary = entry
+        .package
+        .metadata
+        .as_ref()
+        .map(metadata_lines)
+        .unwrap_or_default();
+    if let Some(self_reported) = &entry.package.self_reported {
+        summary.append(&mut self_reported_lines(self_reported));
+    }
+    if !entry.package.unsafe_docs.is_empty() {
+        summary.push(format!(
+            "Unsafe documentation: {}.",
+            entry
+                .package
+                .unsafe_docs
+                .iter()
+                .map(|doc_path| doc_path.display().to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        ));
+    }
+    for note in &entry.package.notes {
+        summary.push(format!("Note: {}", note));
+    }
+    summary.push(format!(
+        "Forbids unsafe code: {}.",
+        yes_no(unsafety.forbids_unsafe)
+    ));
+    summary.push(format!("{} file(s) scanned.", unsafety.files.len()));
+    render_html_page(
+        &format!(
+            "cargo-geiger report: {} {}",
+            entry.package.id.name, entry.package.id.version
+        ),
+        &summary,
+        &files_table(&unsafety.files),
+        &[],
+        None,
+    )
+}
+
+/// The path of the page of a package served by `--serve`.
+pub fn package_page_path(package_id: &PackageId) -> String {
+    format!("/package/{}/{}", package_id.name, package_id.version)
+}
+
+fn render_full_html_report(
+    report: &SafetyReport,
+    link_packages: bool,
+    baseline: Option<&SafetyReport>,
+) -> String {
+    let mut headers = vec![String::from("Package"), String::from("Forbids")];
+    headers.extend(UNSAFE_COUNTER_COLUMNS.iter().map(|c| c.to_string()));
+    if baseline.is_some() {
+        headers.push(String::from("Change"));
+    }
+    let include_metadata = report
+        .packages
+        .values()
+        .any(|entry| entry.package.metadata.is_some());
+    if include_metadata {
+        headers.push(String::from("Repository"));
+        headers.push(String::from("Rust version"));
+    }
+    let include_unsafe_docs = report
+        .packages
+        .values()
+        .any(|entry| !entry.package.unsafe_docs.is_empty());
+    if include_unsafe_docs {
+        headers.push(String::from("Unsafe docs"));
+    }
+    let include_notes = report
+        .packages
+        .values()
+        .any(|entry| !entry.package.notes.is_empty());
+    if include_notes {
+        headers.push(String::from("Notes"));
+    }
+
+    let mut entries = report.packages.values().collect::<Vec<_>>();
+    entries.sort_by(|a, b| a.package.id.cmp(&b.package.id));
+    let rows = entries
+        .into_iter()
+        .map(|entry| {
+            let unsafety = &entry.unsafety;
+            let name = format!(
+                "{} {}",
+                entry.package.id.name, entry.package.id.version
+            );
+            let mut cells = vec![
+                if link_packages {
+                    HtmlCell::Link(name, package_page_path(&entry.package.id))
+                } else {
+                    HtmlCell::Text(name)
+                },
+                HtmlCell::Text(yes_no(unsafety.forbids_unsafe)),
+            ];
+            match baseline {
+                Some(baseline) => {
+                    let baseline_entry = find_baseline_entry(entry, baseline);
+                    cells.extend(used_of_total_change_cells(
+                        &unsafety.used,
+                        &unsafety.unused,
+                        baseline_entry.map(|entry| &entry.unsafety.used),
+                    ));
+                    cells.push(HtmlCell::Text(
+                        describe_package_changes(entry, baseline_entry)
+                            .join(", "),
+                    ));
+                }
+                None => cells.extend(used_of_total_cells(
+                    &unsafety.used,
+                    &unsafety.unused,
+                )),
+            }
+            if include_metadata {
+                let metadata = entry.package.metadata.as_ref();
+                for value in &[
+                    metadata.and_then(|m| m.repository.as_ref()),
+                    metadata.and_then(|m| m.rust_version.as_ref()),
+                ] {
+                    cells.push(HtmlCell::Text(
+                        value.cloned().unwrap_or_default(),
+                    ));
+                }
+            }
+            if include_unsafe_docs {
+                cells.push(unsafe_docs_cell(&entry.package.unsafe_docs));
+            }
+            if include_notes {
+                cells.push(HtmlCell::Text(entry.package.notes.join(" ")));
+            }
+            HtmlRow {
+                cells,
+                details: if unsafety.files.is_empty() {
+                    None
+                } else {
+                    Some(files_table(&unsafety.files))
+                },
+            }
+        })
+        .collect();
+
+    let mut summary = vec![
+        String::from(
+            "Metrics are shown as x/y, x = unsafe code used by the build, \
+             y = total unsafe code found in the crate.",
+        ),
+        format!("{} package(s) scanned.", report.packages.len()),
+    ];
+    if !report.packages_without_metrics.is_empty() {
+        summary.push(format!(
+            "{} package(s) without metrics.",
+            report.packages_without_metrics.len()
+        ));
+    }
+    if let Some(baseline) = baseline {
+        summary.append(&mut baseline_summary_lines(report, baseline));
+    }
+    let treemap = treemap_from_report(report);
+    render_html_page(
+        "cargo-geiger report",
+        &summary,
+        &HtmlTable { headers, rows },
+        &dependency_tree(report),
+        Some(&treemap),
+    )
+}
+
+/// Renders the report of a `--forbid-only` scan.
+pub fn render_quick_html_report(report: &QuickSafetyReport) -> String {
+    let headers = vec![String::from("Package"), String::from("Forbids")];
+    let mut entries = report.packages.values().collect::<Vec<_>>();
+    entries.sort_by(|a, b| a.package.id.cmp(&b.package.id));
+    let rows = entries
+        .into_iter()
+        .map(|entry| HtmlRow {
+            cells: vec![
+                HtmlCell::Text(format!(
+                    "{} {}",
+                    entry.package.id.name, entry.package.id.version
+                )),
+                HtmlCell::Text(yes_no(entry.forbids_unsafe)),
+            ],
+            details: None,
+        })
+        .collect();
+    let summary =
+        vec![format!("{} package(s) scanned.", report.packages.len())];
+    render_html_page(
+        "cargo-geiger report",
+        &summary,
+        &HtmlTable { headers, rows },
+        &[],
+        None,
+    )
+}
+
+/// Renders the report of `cargo geiger scan-path`.
+pub fn render_path_html_report(report: &PathSafetyReport) -> String {
+    let mut headers = vec![String::from("File")];
+    headers.extend(UNSAFE_COUNTER_COLUMNS.iter().map(|c| c.to_string()));
+    let rows = report
+        .files
+        .iter()
+        .map(|(path, counter_block)| {
+            let mut cells = vec![HtmlCell::Text(path.display().to_string())];
+            cells.extend(unsafe_cells(counter_block));
+            HtmlRow {
+                cells,
+                details: None,
+            }
+        })
+        .collect();
+    let summary = vec![format!("{} file(s) scanned.", report.files.len())];
+    let treemap = treemap_from_path_report(report);
+    render_html_page(
+        "cargo-geiger report",
+        &summary,
+        &HtmlTable { headers, rows },
+        &[],
+        Some(&treemap),
+    )
+}
+
+fn files_table(files: &BTreeMap<PathBuf, FileUnsafeInfo>) -> HtmlTable {
+    let mut headers = vec![
+        String::from("File"),
+        String::from("Used"),
+        String::from("Entry point"),
+    ];
+    headers.extend(UNSAFE_COUNTER_COLUMNS.iter().map(|c| c.to_string()));
+    headers.push(String::from("Unsafe lines"));
+    let rows = files
+        .iter()
+        .map(|(path, file_unsafe_info)| {
+            let mut cells = vec![
+                file_cell(path),
+                HtmlCell::Text(yes_no(file_unsafe_info.used)),
+                HtmlCell::Text(yes_no(file_unsafe_info.is_crate_entry_point)),
+            ];
+            cells.extend(unsafe_cells(&file_unsafe_info.counters));
+            cells.push(lines_cell(path, &file_unsafe_info.unsafe_lines));
+            HtmlRow {
+                cells,
+                details: None,
+            }
+        })
+        .collect();
+    HtmlTable { headers, rows }
+}
+
+/// Links the file to its source, if the path is absolute, like the paths of
+/// a full scan.
+fn file_cell(path: &Path) -> HtmlCell {
+    let text = path.display().to_string();
+    match Url::from_file_path(path) {
+        Ok(url) => HtmlCell::Link(text, url.to_string()),
+        Err(_) => HtmlCell::Text(text),
+    }
+}
+
+/// Links each line to its `#L<line>` anchor in the source, if the path is
+/// absolute, otherwise lists the lines.
+fn lines_cell(path: &Path, lines: &[usize]) -> HtmlCell {
+    match Url::from_file_path(path) {
+        Ok(url) => HtmlCell::Links(
+            lines
+                .iter()
+                .map(|line| (line.to_string(), format!("{}#L{}", url, line)))
+                .collect(),
+        ),
+        Err(_) => HtmlCell::Text(
+            lines
+                .iter()
+                .map(usize::to_string)
+                .collect::<Vec<String>>()
+                .join(", "),
+        ),
+    }
+}
+
+/// The packages of the report that no other package in the report depends
+/// on, sorted.
+pub fn report_root_ids(report: &SafetyReport) -> Vec<&PackageId> {
+    let dependency_ids = report
+        .packages
+        .values()
+        .flat_map(|entry| report_dependency_ids(report, &entry.package.id))
+        .collect::<HashSet<&PackageId>>();
+    let mut root_ids = report
+        .packages
+        .keys()
+        .chain(report.packages_without_metrics.iter())
+        .filter(|package_id| !dependency_ids.contains(package_id))
+        .collect::<Vec<&PackageId>>();
+    root_ids.sort();
+    root_ids
+}
+
+/// The dependencies of a package in the report, the normal dependencies
+/// first, then the build and the development dependencies, each sorted.
+pub fn report_dependency_ids<'a>(
+    report: &'a SafetyReport,
+    package_id: &PackageId,
+) -> Vec<&'a PackageId> {
+    let package = match report.packages.get(package_id) {
+        Some(entry) => &entry.package,
+        None => return Vec::new(),
+    };
+    let mut dependency_ids = Vec::new();
+    for dependencies in &[
+        &package.dependencies,
+        &package.build_dependencies,
+        &package.dev_dependencies,
+    ] {
+        let mut kind_ids = dependencies.iter().collect::<Vec<&PackageId>>();
+        kind_ids.sort();
+        for dependency_id in kind_ids {
+            if !dependency_ids.contains(&dependency_id) {
+                dependency_ids.push(dependency_id);
+            }
+        }
+    }
+    dependency_ids
+}
+
+/// The dependency tree of the report, from every root package. Like the
+/// tree of the terminal output, the dependencies of a package are only
+/// listed at its first occurrence.
+fn dependency_tree(report: &SafetyReport) -> Vec<HtmlTreeNode> {
+    let mut visited_ids = HashSet::new();
+    report_root_ids(report)
+        .into_iter()
+        .map(|root_id| dependency_tree_node(report, root_id, &mut visited_ids))
+        .collect()
+}
+
+fn dependency_tree_node<'a>(
+    report: &'a SafetyReport,
+    package_id: &'a PackageId,
+    visited_ids: &mut HashSet<&'a PackageId>,
+) -> HtmlTreeNode {
+    let name = format!("{} {}", package_id.name, package_id.version);
+    let used_unsafe = report
+        .packages
+        .get(package_id)
+        .map(|entry| used_unsafe_total(&entry.unsafety.used));
+    let dependency_ids = report_dependency_ids(report, package_id);
+    if !visited_ids.insert(package_id) {
+        return HtmlTreeNode {
+            name,
+            used_unsafe,
+            repeated: !dependency_ids.is_empty(),
+            children: vec![],
+        };
+    }
+    HtmlTreeNode {
+        name,
+        used_unsafe,
+        repeated: false,
+        children: dependency_ids
+            .into_iter()
+            .map(|dependency_id| {
+                dependency_tree_node(report, dependency_id, visited_ids)
+            })
+            .collect(),
+    }
+}
+
+pub fn unsafe_counts(counter_block: &CounterBlock) -> [&Count; 5] {
+    [
+        &counter_block.functions,
+        &counter_block.exprs,
+        &counter_block.item_impls,
+        &counter_block.item_traits,
+        &counter_block.methods,
+    ]
+}
+
+fn unsafe_cells(counter_block: &CounterBlock) -> Vec<HtmlCell> {
+    unsafe_counts(counter_block)
+        .iter()
+        .map(|count| HtmlCell::Number(count.unsafe_))
+        .collect()
+}
+
+fn used_of_total_cells(
+    used: &CounterBlock,
+    unused: &CounterBlock,
+) -> Vec<HtmlCell> {
+    unsafe_counts(used)
+        .iter()
+        .zip(unsafe_counts(unused).iter())
+        .map(|(used, unused)| {
+            HtmlCell::UsedOfTotal(used.unsafe_, used.unsafe_ + unused.unsafe_)
+        })
+        .collect()
+}
+
+fn used_of_total_change_cells(
+    used: &CounterBlock,
+    unused: &CounterBlock,
+    baseline_used: Option<&CounterBlock>,
+) -> Vec<HtmlCell> {
+    let no_baseline_used = CounterBlock::default();
+    let baseline_used = baseline_used.unwrap_or(&no_baseline_used);
+    unsafe_counts(used)
+        .iter()
+        .zip(unsafe_counts(unused).iter())
+        .zip(unsafe_counts(baseline_used).iter())
+        .map(|((used, unused), baseline_used)| {
+            HtmlCell::UsedOfTotalChange(
+                used.unsafe_,
+                used.unsafe_ + unused.unsafe_,
+                used.unsafe_ as i64 - baseline_used.unsafe_ as i64,
+            )
+        })
+        .collect()
+}
+
+/// How the unsafe code used by the build of a package changed since the
+/// baseline report.
+#[derive(Debug, PartialEq)]
+enum UnsafeChange {
+    /// The package is not in the baseline report.
+    New,
+    More,
+    Less,
+    /// Unsafe code was used before, and none is used now.
+    Resolved,
+    Unchanged,
+}
+
+/// The entry of the package in the baseline report, of the same version if
+/// it is there, otherwise of the latest version of the package.
+pub fn find_baseline_entry<'a>(
+    entry: &ReportEntry,
+    baseline: &'a SafetyReport,
+) -> Option<&'a ReportEntry> {
+    baseline.packages.get(&entry.package.id).or_else(|| {
+        baseline
+            .packages
+            .values()
+            .filter(|baseline_entry| {
+                baseline_entry.package.id.name == entry.package.id.name
+            })
+            .max_by(|a, b| a.package.id.version.cmp(&b.package.id.version))
+    })
+}
+
+fn unsafe_change(
+    entry: &ReportEntry,
+    baseline_entry: Option<&ReportEntry>,
+) -> UnsafeChange {
+    let baseline_entry = match baseline_entry {
+        Some(baseline_entry) => baseline_entry,
+        None => return UnsafeChange::New,
+    };
+    let used = used_unsafe_total(&entry.unsafety.used);
+    let baseline_used = used_unsafe_total(&baseline_entry.unsafety.used);
+    if used == 0 && baseline_used > 0 {
+        UnsafeChange::Resolved
+    } else if used > baseline_used {
+        UnsafeChange::More
+    } else if used < baseline_used {
+        UnsafeChange::Less
+    } else {
+        UnsafeChange::Unchanged
+    }
+}
+
+/// Describes how a package changed since the baseline report, nothing if it
+/// is unchanged.
+fn describe_package_changes(
+    entry: &ReportEntry,
+    baseline_entry: Option<&ReportEntry>,
+) -> Vec<String> {
+    let mut changes = Vec::new();
+    match unsafe_change(entry, baseline_entry) {
+        UnsafeChange::New => return vec![String::from("new")],
+        UnsafeChange::More => {
+            changes.push(String::from("more unsafe code used"))
+        }
+        UnsafeChange::Less => {
+            changes.push(String::from("less unsafe code used"))
+        }
+        UnsafeChange::Resolved => {
+            changes.push(String::from("resolved, no unsafe code used"))
+        }
+        UnsafeChange::Unchanged => {}
+    }
+    let baseline_entry = match baseline_entry {
+        Some(baseline_entry) => baseline_entry,
+        None => return changes,
+    };
+    if baseline_entry.package.id.version != entry.package.id.version {
+        changes.insert(
+            0,
+            format!("updated from {}", baseline_entry.package.id.version),
+        );
+    }
+    match (
+        baseline_entry.unsafety.forbids_unsafe,
+        entry.unsafety.forbids_unsafe,
+    ) {
+        (false, true) => changes.push(String::from("now forbids unsafe code")),
+        (true, false) => {
+            changes.push(String::from("no longer forbids unsafe code"))
+        }
+        _ => {}
+    }
+    changes
+}
+
+/// Counts the changes since the baseline report and lists the packages that
+/// are no longer in the dependency graph.
+fn baseline_summary_lines(
+    report: &SafetyReport,
+    baseline: &SafetyReport,
+) -> Vec<String> {
+    let unsafe_changes = report
+        .packages
+        .values()
+        .map(|entry| unsafe_change(entry, find_baseline_entry(entry, baseline)))
+        .collect::<Vec<UnsafeChange>>();
+    let count = |unsafe_change: UnsafeChange| {
+        unsafe_changes
+            .iter()
+            .filter(|change| **change == unsafe_change)
+            .count()
+    };
+    let mut removed_packages = baseline
+        .packages
+        .keys()
+        .filter(|baseline_id| {
+            !report
+                .packages
+                .keys()
+                .any(|package_id| package_id.name == baseline_id.name)
+        })
+        .map(|baseline_id| {
+            format!("{} {}", baseline_id.name, baseline_id.version)
+        })
+        .collect::<Vec<String>>();
+    removed_packages.sort();
+    let mut lines = vec![format!(
+        "Since the baseline: {} new package(s), {} removed, {} with more \
+         unsafe code used, {} with less, {} resolved.",
+        count(UnsafeChange::New),
+        removed_packages.len(),
+        count(UnsafeChange::More),
+        count(UnsafeChange::Less),
+        count(UnsafeChange::Resolved)
+    )];
+    if !removed_packages.is_empty() {
+        lines.push(format!(
+            "Removed since the baseline: {}.",
+            removed_packages.join(", ")
+        ));
+    }
+    lines
+}
+
+fn used_unsafe_total(counter_block: &CounterBlock) -> u64 {
+    unsafe_counts(counter_block)
+        .iter()
+        .map(|count| count.unsafe_)
+        .sum()
+}
+
+/// The manifest metadata of a package, one line per field that is set.
+fn metadata_lines(metadata: &PackageMetadata) -> Vec<String> {
+    let mut lines = Vec::new();
+    if let Some(description) = &metadata.description {
+        lines.push(description.clone());
+    }
+    if !metadata.authors.is_empty() {
+        lines.push(format!("Authors: {}.", metadata.authors.join(", ")));
+    }
+    if let Some(repository) = &metadata.repository {
+        lines.push(format!("Repository: {}.", repository));
+    }
+    if let Some(rust_version) = &metadata.rust_version {
+        lines.push(format!("Rust version: {}.", rust_version));
+    }
+    lines
+}
+
+/// The claims a package declares about itself, marked as such.
+fn self_reported_lines(claims: &SelfReportedClaims) -> Vec<String> {
+    let mut lines = Vec::new();
+    if let Some(audited) = claims.audited {
+        lines.push(format!("Audited (self-reported): {}.", yes_no(audited)));
+    }
+    if let Some(safety_docs) = &claims.safety_docs {
+        lines.push(format!(
+            "Safety documentation (self-reported): {}.",
+            safety_docs
+        ));
+    }
+    if !claims.categories.is_empty() {
+        lines.push(format!(
+            "Unsafe categories (self-reported): {}.",
+            claims.categories.join(", ")
+        ));
+    }
+    lines
+}
+
+/// Links the first unsafe documentation file of a package, the package page
+/// lists all of them.
+fn unsafe_docs_cell(unsafe_docs: &[PathBuf]) -> HtmlCell {
+    let doc_path = match unsafe_docs.first() {
+        Some(doc_path) => doc_path,
+        None => return HtmlCell::Text(String::new()),
+    };
+    let name = doc_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    match Url::from_file_path(doc_path) {
+        Ok(url) => HtmlCell::Link(name, url.to_string()),
+        Err(_) => HtmlCell::Text(name),
+    }
+}
+
+fn yes_no(value: bool) -> String {
+    String::from(if value { "yes" } else { "no" })
+}
+
+/// Renders a complete page holding the summary, the table, the dependency
+/// tree and the treemap, if any, with the styles and scripts inlined so that
+/// the page can be attached as a single file.
+pub fn render_html_page(
+    title: &str,
+    summary: &[String],
+    table: &HtmlTable,
+    dependency_tree: &[HtmlTreeNode],
+    treemap: Option<&TreemapNode>,
+) -> String {
+    let mut html = String::new();
+    let _ = writeln!(
+        html,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{}</title>\n<style>{}</style>\n<script>{}</script>\n\
+         </head>\n<body>\n<h1>{}</h1>",
+        escape_html(title),
+        STYLE,
+        SCRIPT,
+        escape_html(title)
+    );
+    for line in summary {
+        let _ = writeln!(html, "<p>{}</p>", escape_html(line));
+    }
+    html.push_str(
+        "<input id=\"search\" type=\"search\" placeholder=\"Search\">\n",
+    );
+    render_table(&mut html, table, Some("report"));
+    if !dependency_tree.is_empty() {
+        html.push_str("<h2>Dependency tree</h2>\n");
+        html.push_str(
+            "<p>Expand a package to list its dependencies, (*) marks a \
+             package with the dependencies listed above.</p>\n",
+        );
+        html.push_str("<ul class=\"tree\">\n");
+        for node in dependency_tree {
+            render_tree_node(&mut html, node, 0);
+        }
+        html.push_str("</ul>\n");
+    }
+    if let Some(treemap) = treemap {
+        if treemap_total(treemap) > 0 {
+            html.push_str("<h2>Treemap</h2>\n");
+            html.push_str(
+                "<p>Area is proportional to the unsafe occurrences, used and \
+                 unused code combined.</p>\n",
+            );
+            html.push_str("<div class=\"treemap\">\n");
+            render_treemap_node(&mut html, treemap, 0);
+            html.push_str("</div>\n");
+        }
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// Renders a package of the dependency tree as a list item, that can be
+/// collapsed if the package has dependencies. Only the root packages are
+/// expanded at first.
+fn render_tree_node(html: &mut String, node: &HtmlTreeNode, depth: usize) {
+    let mut label = escape_html(&node.name);
+    match node.used_unsafe {
+        Some(0) => {}
+        Some(used_unsafe) => {
+            let _ = write!(
+                label,
+                " <span class=\"unsafe\">{} unsafe used</span>",
+                used_unsafe
+            );
+        }
+        None => label.push_str(" (no metrics)"),
+    }
+    if node.repeated {
+        label.push_str(" (*)");
+    }
+    if node.children.is_empty() {
+        let _ = writeln!(html, "<li>{}</li>", label);
+        return;
+    }
+    let _ = writeln!(
+        html,
+        "<li><details{}><summary>{}</summary>\n<ul>",
+        if depth == 0 { " open" } else { "" },
+        label
+    );
+    for child in &node.children {
+        render_tree_node(html, child, depth + 1);
+    }
+    html.push_str("</ul></details></li>\n");
+}
+
+/// Renders a slice-and-dice treemap with nested flex boxes, alternating
+/// between rows and columns per level. Nodes without unsafe usage take no
+/// area and are left out.
+fn render_treemap_node(html: &mut String, node: &TreemapNode, depth: usize) {
+    let total = treemap_total(node);
+    let direction = if depth % 2 == 0 { "row" } else { "column" };
+    let _ = write!(
+        html,
+        "<div class=\"treemap-node\" title=\"{}: {}\" \
+         style=\"flex: {} 1 0; flex-direction: {};\">",
+        escape_html(&node.name),
+        total,
+        total,
+        direction
+    );
+    if node.children.is_empty() {
+        let _ = write!(html, "<span>{}</span>", escape_html(&node.name));
+    }
+    for child in &node.children {
+        if treemap_total(child) > 0 {
+            render_treemap_node(html, child, depth + 1);
+        }
+    }
+    html.push_str("</div>\n");
+}
+
+fn render_table(html: &mut String, table: &HtmlTable, id: Option<&str>) {
+    let has_details = table.rows.iter().any(|row| row.details.is_some());
+    match id {
+        Some(id) => {
+            let _ = writeln!(html, "<table id=\"{}\">", id);
+        }
+        None => html.push_str("<table>\n"),
+    }
+    html.push_str("<thead><tr>");
+    for header in &table.headers {
+        let _ = write!(html, "<th>{}</th>", escape_html(header));
+    }
+    if has_details {
+        html.push_str("<th></th>");
+    }
+    html.push_str("</tr></thead>\n");
+    for row in &table.rows {
+        html.push_str("<tbody><tr>");
+        for cell in &row.cells {
+            render_cell(html, cell);
+        }
+        if has_details {
+            match &row.details {
+                Some(_) => html.push_str(
+                    "<td data-sort=\"\"><button class=\"toggle\" \
+                     onclick=\"toggleDetails(this)\">+</button></td>",
+                ),
+                None => html.push_str("<td data-sort=\"\"></td>"),
+            }
+        }
+        html.push_str("</tr>\n");
+        if let Some(details) = &row.details {
+            let _ = writeln!(
+                html,
+                "<tr class=\"details\"><td colspan=\"{}\">",
+                table.headers.len() + 1
+            );
+            render_table(html, details, None);
+            html.push_str("</td></tr>\n");
+        }
+        html.push_str("</tbody>\n");
+    }
+    html.push_str("</table>\n");
+}
+
+fn render_cell(html: &mut String, cell: &HtmlCell) {
+    let (sort_key, content, is_unsafe) = match cell {
+        HtmlCell::Text(text) => (text.clone(), escape_html(text), false),
+        HtmlCell::Number(number) => {
+            (number.to_string(), number.to_string(), *number > 0)
+        }
+        HtmlCell::UsedOfTotal(used, total) => {
+            (used.to_string(), format!("{}/{}", used, total), *used > 0)
+        }
+        HtmlCell::UsedOfTotalChange(used, total, change) => {
+            let content = if *change == 0 {
+                format!("{}/{}", used, total)
+            } else {
+                let class = if *change > 0 {
+                    "increased"
+                } else {
+                    "decreased"
+                };
+                format!(
+                    "{}/{} <span class=\"{}\">({:+})</span>",
+                    used, total, class, change
+                )
+            };
+            (used.to_string(), content, *used > 0)
+        }
+        HtmlCell::Link(text, href) => (
+            text.clone(),
+            format!(
+                "<a href=\"{}\">{}</a>",
+                escape_html(href),
+                escape_html(text)
+            ),
+            false,
+        ),
+        HtmlCell::Links(links) => (
+            links.len().to_string(),
+            links
+                .iter()
+                .map(|(text, href)| {
+                    format!(
+                        "<a href=\"{}\">{}</a>",
+                        escape_html(href),
+                        escape_html(text)
+                    )
+                })
+                .collect::<Vec<String>>()
+                .join(", "),
+            false,
+        ),
+    };
+    let _ = write!(
+        html,
+        "<td data-sort=\"{}\"{}>{}</td>",
+        escape_html(&sort_key),
+        if is_unsafe { " class=\"unsafe\"" } else { "" },
+        content
+    );
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod html_tests {
+    use super::*;
+
+    use cargo_geiger_serde::{PackageInfo, Source, UnsafeInfo};
+    use rstest::*;
+    use semver::Version;
+
+    #[rstest(
+        input_text,
+        expected_escaped_text,
+        case("serde 1.0.0", "serde 1.0.0"),
+        case("<script>", "&lt;script&gt;"),
+        case("a & \"b\" 'c'", "a &amp; &quot;b&quot; &#39;c&#39;")
+    )]
+    fn escape_html_test(input_text: &str, expected_escaped_text: &str) {
+        assert_eq!(escape_html(input_text), expected_escaped_text);
+    }
+
+    #[rstest(
+        input_cell,
+        expected_html,
+        case(
+            HtmlCell::Text(String::from("a<b")),
+            "<td data-sort=\"a&lt;b\">a&lt;b</td>"
+        ),
+        case(HtmlCell::Number(0), "<td data-sort=\"0\">0</td>"),
+        case(
+            HtmlCell::UsedOfTotal(2, 5),
+            "<td data-sort=\"2\" class=\"unsafe\">2/5</td>"
+        ),
+        case(
+            HtmlCell::UsedOfTotalChange(2, 5, 1),
+            "<td data-sort=\"2\" class=\"unsafe\">\
+             2/5 <span class=\"increased\">(+1)</span></td>"
+        ),
+        case(
+            HtmlCell::UsedOfTotalChange(0, 3, -2),
+            "<td data-sort=\"0\">\
+             0/3 <span class=\"decreased\">(-2)</span></td>"
+        ),
+        case(
+            HtmlCell::UsedOfTotalChange(2, 5, 0),
+            "<td data-sort=\"2\" class=\"unsafe\">2/5</td>"
+        ),
+        case(
+            HtmlCell::Link(
+                String::from("serde 1.0.0"),
+                String::from("/package/serde/1.0.0")
+            ),
+            "<td data-sort=\"serde 1.0.0\">\
+             <a href=\"/package/serde/1.0.0\">serde 1.0.0</a></td>"
+        ),
+        case(
+            HtmlCell::Links(vec![
+                (String::from("3"), String::from("file:///lib.rs#L3")),
+                (String::from("7"), String::from("file:///lib.rs#L7"))
+            ]),
+            "<td data-sort=\"2\"><a href=\"file:///lib.rs#L3\">3</a>, \
+             <a href=\"file:///lib.rs#L7\">7</a></td>"
+        )
+    )]
+    fn render_cell_test(input_cell: HtmlCell, expected_html: &str) {
+        let mut html = String::new();
+        render_cell(&mut html, &input_cell);
+        assert_eq!(html, expected_html);
+    }
+
+    #[rstest]
+    fn metadata_lines_test() {
+        let metadata = PackageMetadata {
+            authors: vec![String::from("A"), String::from("B")],
+            description: Some(String::from("A crate.")),
+            repository: None,
+            rust_version: Some(String::from("1.40")),
+        };
+
+        assert_eq!(
+            metadata_lines(&metadata),
+            vec![
+                String::from("A crate."),
+                String::from("Authors: A, B."),
+                String::from("Rust version: 1.40."),
+            ]
+        );
+        assert!(metadata_lines(&PackageMetadata::default()).is_empty());
+    }
+
+    #[rstest]
+    fn self_reported_lines_test() {
+        let claims = SelfReportedClaims {
+            audited: Some(false),
+            safety_docs: None,
+            categories: vec![String::from("ffi"), String::from("simd")],
+        };
+
+        assert_eq!(
+            self_reported_lines(&claims),
+            vec![
+                String::from("Audited (self-reported): no."),
+                String::from("Unsafe categories (self-reported): ffi, simd."),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn render_treemap_node_test() {
+        let leaf = |name: &str, value: u64| TreemapNode {
+            name: String::from(name),
+            value: Some(value),
+            used: None,
+            children: vec![],
+        };
+        let treemap = TreemapNode {
+            name: String::from("dependencies"),
+            value: None,
+            used: None,
+            children: vec![leaf("src/lib.rs", 3), leaf("src/safe.rs", 0)],
+        };
+
+        let mut html = String::new();
+        render_treemap_node(&mut html, &treemap, 0);
+
+        assert!(html.starts_with(
+            "<div class=\"treemap-node\" title=\"dependencies: 3\" \
+             style=\"flex: 3 1 0; flex-direction: row;\">"
+        ));
+        assert!(html.contains("<span>src/lib.rs</span>"));
+        assert!(!html.contains("src/safe.rs"));
+    }
+
+    #[rstest]
+    fn unsafe_docs_cell_test() {
+        let doc_path = std::env::temp_dir().join("a").join("SAFETY.md");
+
+        let mut html = String::new();
+        render_cell(
+            &mut html,
+            &unsafe_docs_cell(std::slice::from_ref(&doc_path)),
+        );
+        let href = Url::from_file_path(&doc_path).unwrap().to_string();
+
+        assert!(html.contains(&format!(
+            "<a href=\"{}\">SAFETY.md</a>",
+            escape_html(&href)
+        )));
+        let mut html = String::new();
+        render_cell(&mut html, &unsafe_docs_cell(&[]));
+        assert!(!html.contains("<a "));
+    }
+
+    #[rstest]
+    fn render_table_with_details_test() {
+        let table = HtmlTable {
+            headers: vec![String::from("Package")],
+            rows: vec![
+                HtmlRow {
+                    cells: vec![HtmlCell::Text(String::from("a"))],
+                    details: Some(HtmlTable {
+                        headers: vec![String::from("File")],
+                        rows: vec![HtmlRow {
+                            cells: vec![HtmlCell::Text(String::from(
+                                "src/lib.rs",
+                            ))],
+                            details: None,
+                        }],
+                    }),
+                },
+                HtmlRow {
+                    cells: vec![HtmlCell::Text(String::from("b"))],
+                    details: None,
+                },
+            ],
+        };
+
+        let mut html = String::new();
+        render_table(&mut html, &table, Some("report"));
+
+        assert!(html.starts_with("<table id=\"report\">"));
+        // A body per row, the row of the nested details table included.
+        assert_eq!(html.matches("<tbody>").count(), 3);
+        assert_eq!(html.matches("toggleDetails(this)").count(), 1);
+        assert!(html.contains("<tr class=\"details\"><td colspan=\"2\">"));
+        assert!(html.contains("src/lib.rs"));
+    }
+
+    #[rstest]
+    fn file_cell_test() {
+        let path = std::env::temp_dir().join("src").join("lib.rs");
+
+        let mut html = String::new();
+        render_cell(&mut html, &file_cell(&path));
+        let href = Url::from_file_path(&path).unwrap().to_string();
+
+        assert!(html.contains(&format!("<a href=\"{}\">", escape_html(&href))));
+        let mut html = String::new();
+        render_cell(&mut html, &file_cell(Path::new("src/lib.rs")));
+        assert!(!html.contains("<a "));
+    }
+
+    #[rstest]
+    fn lines_cell_test() {
+        let path = std::env::temp_dir().join("src").join("lib.rs");
+
+        let mut html = String::new();
+        render_cell(&mut html, &lines_cell(&path, &[3, 7]));
+        let href = Url::from_file_path(&path).unwrap().to_string();
+
+        assert!(html.contains(&format!(
+            "<a href=\"{}\">3</a>, <a href=\"{}\">7</a>",
+            escape_html(&format!("{}#L3", href)),
+            escape_html(&format!("{}#L7", href))
+        )));
+        let mut html = String::new();
+        render_cell(&mut html, &lines_cell(Path::new("src/lib.rs"), &[3, 7]));
+        assert_eq!(html, "<td data-sort=\"3, 7\">3, 7</td>");
+    }
+
+    #[rstest]
+    fn dependency_tree_test() {
+        let mut root = create_entry("root", 1, 0, false);
+        let mut a = create_entry("a", 1, 0, false);
+        let mut b = create_entry("b", 1, 0, false);
+        let mut shared = create_entry("shared", 1, 2, false);
+        let c = create_entry("c", 1, 0, false);
+        root.package.dependencies.insert(a.package.id.clone());
+        root.package.build_dependencies.insert(b.package.id.clone());
+        a.package.dependencies.insert(shared.package.id.clone());
+        b.package.dependencies.insert(shared.package.id.clone());
+        shared.package.dependencies.insert(c.package.id.clone());
+        let mut report = SafetyReport::default();
+        for entry in [root, a, b, shared, c] {
+            report.packages.insert(entry.package.id.clone(), entry);
+        }
+
+        let mut html = String::new();
+        for node in &dependency_tree(&report) {
+            render_tree_node(&mut html, node, 0);
+        }
+
+        assert_eq!(
+            html,
+            [
+                "<li><details open><summary>root 1.0.0</summary>",
+                "<ul>",
+                "<li><details><summary>a 1.0.0</summary>",
+                "<ul>",
+                "<li><details><summary>shared 1.0.0 \
+                 <span class=\"unsafe\">2 unsafe used</span></summary>",
+                "<ul>",
+                "<li>c 1.0.0</li>",
+                "</ul></details></li>",
+                "</ul></details></li>",
+                "<li><details><summary>b 1.0.0</summary>",
+                "<ul>",
+                "<li>shared 1.0.0 <span class=\"unsafe\">2 unsafe used</span> \
+                 (*)</li>",
+                "</ul></details></li>",
+                "</ul></details></li>",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[rstest(
+        input_baseline_entry,
+        expected_changes,
+        case(None, vec!["new"]),
+        case(Some(create_entry("a", 1, 3, false)), vec![]),
+        case(
+            Some(create_entry("a", 0, 1, false)),
+            vec!["updated from 0.0.0", "more unsafe code used"]
+        ),
+        case(
+            Some(create_entry("a", 1, 5, false)),
+            vec!["less unsafe code used"]
+        ),
+        case(
+            Some(create_entry("a", 1, 3, true)),
+            vec!["no longer forbids unsafe code"]
+        )
+    )]
+    fn describe_package_changes_test(
+        input_baseline_entry: Option<ReportEntry>,
+        expected_changes: Vec<&str>,
+    ) {
+        let entry = create_entry("a", 1, 3, false);
+
+        assert_eq!(
+            describe_package_changes(&entry, input_baseline_entry.as_ref()),
+            expected_changes
+        );
+    }
+
+    #[rstest]
+    fn describe_package_changes_resolved_test() {
+        let entry = create_entry("a", 1, 0, true);
+
+        assert_eq!(
+            describe_package_changes(
+                &entry,
+                Some(&create_entry("a", 1, 2, false))
+            ),
+            vec!["resolved, no unsafe code used", "now forbids unsafe code"]
+        );
+    }
+
+    #[rstest]
+    fn baseline_summary_lines_test() {
+        let mut report = SafetyReport::default();
+        let mut baseline = SafetyReport::default();
+        for entry in [
+            create_entry("a", 2, 3, false),
+            create_entry("b", 1, 1, false),
+            create_entry("c", 1, 0, false),
+        ] {
+            report.packages.insert(entry.package.id.clone(), entry);
+        }
+        for entry in [
+            create_entry("a", 1, 1, false),
+            create_entry("c", 1, 4, false),
+            create_entry("d", 1, 4, false),
+        ] {
+            baseline.packages.insert(entry.package.id.clone(), entry);
+        }
+
+        assert_eq!(
+            baseline_summary_lines(&report, &baseline),
+            vec![
+                String::from(
+                    "Since the baseline: 1 new package(s), 1 removed, 1 with \
+                     more unsafe code used, 0 with less, 1 resolved."
+                ),
+                String::from("Removed since the baseline: d 1.0.0."),
+            ]
+        );
+    }
+
+    fn create_entry(
+        name: &str,
+        major_version: u64,
+        used_unsafe_exprs: u64,
+        forbids_unsafe: bool,
+    ) -> ReportEntry {
+        ReportEntry {
+            package: PackageInfo::new(PackageId {
+                name: String::from(name),
+                version: Version::new(major_version, 0, 0),
+                source: Source::Registry {
+                    name: String::from("crates.io"),
+                    url: Url::parse(
+                        "https://github.com/rust-lang/crates.io-index",
+                    )
+                    .unwrap(),
+                },
+            }),
+            unsafety: UnsafeInfo {
+                used: CounterBlock {
+                    exprs: Count {
+                        safe: 10,
+                        unsafe_: used_unsafe_exprs,
+                    },
+                    ..Default::default()
+                },
+                forbids_unsafe,
+                ..Default::default()
+            },
+        }
+    }
+}
@@ -0,0 +1,286 @@
+//! Translations of the legend, summaries and warnings printed with the
+//! table. Each language maps every `Message` to its text, the exhaustive
+//! matches make sure nothing is left untranslated when a message is added.
+
+use std::str::FromStr;
+use strum_macros::EnumIter;
+
+/// The language of the user facing strings, selected with `--lang`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Lang {
+    De,
+    En,
+}
+
+impl FromStr for Lang {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Lang, &'static str> {
+        match s {
+            "de" => Ok(Lang::De),
+            "en" => Ok(Lang::En),
+            _ => Err("invalid language, expected `en` or `de`"),
+        }
+    }
+}
+
+/// A user facing string that has a translation in every language.
+#[derive(Clone, Copy, Debug, EnumIter, PartialEq)]
+pub enum Message {
+    MetricOutputFormat,
+    MetricOutputFormatSingle,
+    MetricUsed,
+    MetricTotal,
+    MetricUnused,
+    Symbols,
+    NoneDetectedForbidsUnsafe,
+    NoneDetectedAllowsUnsafe,
+    UnsafeDetected,
+    ForbidMismatch,
+    EntryPointsForbidUnsafe,
+    MayUseUnsafe,
+    UnsafeUsagePerCategory,
+    InlineAssembly,
+    UnsafeDropExprs,
+    UnsafeFnBodyExprs,
+    UnsafeConstExprs,
+    PackedStructs,
+    StaticMuts,
+    StaticMutRefs,
+    Unions,
+    UnionFieldAccesses,
+    ExternStatics,
+    FfiCalls,
+    JsFfiCalls,
+    ExportedGlobals,
+    PublicUnsafeFns,
+    PublicUnsafeTraits,
+    GeneratedUnsafe,
+    UnsafeUsagePerPlatform,
+    ActiveForTarget,
+    DependencyFileNeverScanned,
+    NoMetricsFound,
+    Patched,
+    LocalOverride,
+    Yanked,
+    ViaFeature,
+    NotFullyScanned,
+    YankedWithUnsafe,
+    RustVersionMismatch,
+    ChecksumMismatch,
+    MissingForbid,
+    MetricsMissing,
+}
+
+/// Looks up the text of a message in the given language.
+pub fn tr(lang: Lang, message: Message) -> &'static str {
+    match lang {
+        Lang::De => german(message),
+        Lang::En => english(message),
+    }
+}
+
+fn english(message: Message) -> &'static str {
+    match message {
+        Message::MetricOutputFormat => "Metric output format: x/y",
+        Message::MetricOutputFormatSingle => "Metric output format: x",
+        Message::MetricUsed => "    x = unsafe code used by the build",
+        Message::MetricTotal => "    y = total unsafe code found in the crate",
+        Message::MetricUnused => "    x = unsafe code not used by the build",
+        Message::Symbols => "Symbols: ",
+        Message::NoneDetectedForbidsUnsafe => {
+            "No `unsafe` usage found, declares #![forbid(unsafe_code)]"
+        }
+        Message::NoneDetectedAllowsUnsafe => {
+            "No `unsafe` usage found, missing #![forbid(unsafe_code)]"
+        }
+        Message::UnsafeDetected => "`unsafe` usage found",
+        Message::ForbidMismatch => {
+            "`unsafe` usage found despite declaring #![forbid(unsafe_code)]"
+        }
+        Message::EntryPointsForbidUnsafe => {
+            "All entry point .rs files declare #![forbid(unsafe_code)]."
+        }
+        Message::MayUseUnsafe => "This crate may use unsafe code.",
+        Message::UnsafeUsagePerCategory => "Unsafe usage per category:",
+        Message::InlineAssembly => "Inline assembly",
+        Message::UnsafeDropExprs => {
+            "Unsafe expressions in Drop implementations"
+        }
+        Message::UnsafeFnBodyExprs => {
+            "Unsafe expressions in unsafe fn bodies, outside unsafe blocks"
+        }
+        Message::UnsafeConstExprs => {
+            "Unsafe expressions in const fn bodies and const initializers"
+        }
+        Message::PackedStructs => "#[repr(packed)] structs",
+        Message::StaticMuts => "static mut items",
+        Message::StaticMutRefs => {
+            "References to static mut items (matched by name)"
+        }
+        Message::Unions => "union definitions",
+        Message::UnionFieldAccesses => {
+            "Union field reads in unsafe code (matched by field name)"
+        }
+        Message::ExternStatics => "extern static declarations (FFI)",
+        Message::FfiCalls => "Calls to extern functions (FFI)",
+        Message::JsFfiCalls => {
+            "Calls to JavaScript imports (wasm-bindgen, JS FFI)"
+        }
+        Message::ExportedGlobals => "Exported globals (FFI)",
+        Message::PublicUnsafeFns => "pub unsafe fn items (public API)",
+        Message::PublicUnsafeTraits => "pub unsafe trait items (public API)",
+        Message::GeneratedUnsafe => {
+            "Unsafe usage in generated code, included in the used counts:"
+        }
+        Message::UnsafeUsagePerPlatform => {
+            "Unsafe usage per platform specific cfg:"
+        }
+        Message::ActiveForTarget => "    * = active for the current target",
+        Message::DependencyFileNeverScanned => {
+            "WARNING: Dependency file was never scanned:"
+        }
+        Message::NoMetricsFound => "WARNING: No metrics found for package:",
+        Message::Patched => "(patched)",
+        Message::LocalOverride => "(local override)",
+        Message::Yanked => "(yanked)",
+        Message::ViaFeature => "via feature",
+        Message::NotFullyScanned => "(not fully scanned)",
+        Message::YankedWithUnsafe => {
+            "WARNING: Yanked version with unsafe code in use:"
+        }
+        Message::RustVersionMismatch => "WARNING: Rust version mismatch:",
+        Message::ChecksumMismatch => {
+            "ERROR: Scanned source does not match Cargo.lock:"
+        }
+        Message::MissingForbid => "(lacks #![forbid(unsafe_code)])",
+        Message::MetricsMissing => {
+            "No metrics, the source files could not be scanned."
+        }
+    }
+}
+
+fn german(message: Message) -> &'static str {
+    match message {
+        Message::MetricOutputFormat => "Format der Metriken: x/y",
+        Message::MetricOutputFormatSingle => "Format der Metriken: x",
+        Message::MetricUsed => "    x = vom Build verwendeter unsafe-Code",
+        Message::MetricTotal => {
+            "    y = gesamter im Crate gefundener unsafe-Code"
+        }
+        Message::MetricUnused => {
+            "    x = nicht vom Build verwendeter unsafe-Code"
+        }
+        Message::Symbols => "Symbole: ",
+        Message::NoneDetectedForbidsUnsafe => {
+            "Kein `unsafe` gefunden, deklariert #![forbid(unsafe_code)]"
+        }
+        Message::NoneDetectedAllowsUnsafe => {
+            "Kein `unsafe` gefunden, #![forbid(unsafe_code)] fehlt"
+        }
+        Message::UnsafeDetected => "`unsafe` gefunden",
+        Message::ForbidMismatch => {
+            "`unsafe` gefunden, trotz #![forbid(unsafe_code)]"
+        }
+        Message::EntryPointsForbidUnsafe => {
+            "Alle Einstiegspunkte deklarieren #![forbid(unsafe_code)]."
+        }
+        Message::MayUseUnsafe => {
+            "Dieses Crate verwendet möglicherweise unsafe-Code."
+        }
+        Message::UnsafeUsagePerCategory => {
+            "Verwendung von unsafe je Kategorie:"
+        }
+        Message::InlineAssembly => "Inline-Assembler",
+        Message::UnsafeDropExprs => {
+            "Unsafe-Ausdrücke in Drop-Implementierungen"
+        }
+        Message::UnsafeFnBodyExprs => {
+            "Unsafe-Ausdrücke in unsafe fn-Rümpfen, außerhalb von unsafe-Blöcken"
+        }
+        Message::UnsafeConstExprs => {
+            "Unsafe-Ausdrücke in const fn-Rümpfen und const-Initialisierern"
+        }
+        Message::PackedStructs => "#[repr(packed)]-Structs",
+        Message::StaticMuts => "static mut-Items",
+        Message::StaticMutRefs => {
+            "Referenzen auf static mut-Items (nach Name)"
+        }
+        Message::Unions => "union-Definitionen",
+        Message::UnionFieldAccesses => {
+            "Lesezugriffe auf union-Felder in unsafe-Code (nach Feldname)"
+        }
+        Message::ExternStatics => "extern static-Deklarationen (FFI)",
+        Message::FfiCalls => "Aufrufe von extern-Funktionen (FFI)",
+        Message::JsFfiCalls => {
+            "Aufrufe von JavaScript-Importen (wasm-bindgen, JS-FFI)"
+        }
+        Message::ExportedGlobals => "Exportierte globale Variablen (FFI)",
+        Message::PublicUnsafeFns => "pub unsafe fn-Items (öffentliche API)",
+        Message::PublicUnsafeTraits => {
+            "pub unsafe trait-Items (öffentliche API)"
+        }
+        Message::GeneratedUnsafe => {
+            "Verwendung von unsafe in generiertem Code, in x enthalten:"
+        }
+        Message::UnsafeUsagePerPlatform => {
+            "Verwendung von unsafe je plattformspezifischem cfg:"
+        }
+        Message::ActiveForTarget => "    * = aktiv für das aktuelle Target",
+        Message::DependencyFileNeverScanned => {
+            "WARNUNG: Datei einer Abhängigkeit wurde nie gescannt:"
+        }
+        Message::NoMetricsFound => "WARNUNG: Keine Metriken für Paket:",
+        Message::Patched => "(gepatcht)",
+        Message::LocalOverride => "(lokal überschrieben)",
+        Message::Yanked => "(zurückgezogen)",
+        Message::ViaFeature => "über Feature",
+        Message::NotFullyScanned => "(nicht vollständig gescannt)",
+        Message::YankedWithUnsafe => {
+            "WARNUNG: Zurückgezogene Version mit unsicherem Code in Verwendung:"
+        }
+        Message::RustVersionMismatch => "WARNUNG: Abweichende Rust-Version:",
+        Message::ChecksumMismatch => {
+            "FEHLER: Gescannter Quelltext entspricht nicht Cargo.lock:"
+        }
+        Message::MissingForbid => "(ohne #![forbid(unsafe_code)])",
+        Message::MetricsMissing => {
+            "Keine Metriken, die Quelldateien konnten nicht gescannt werden."
+        }
+    }
+}
+
+#[cfg(test)]
+mod i18n_tests {
+    use super::*;
+
+    use rstest::*;
+    use strum::IntoEnumIterator;
+
+    #[rstest(
+        input_lang,
+        expected_lang,
+        case("de", Ok(Lang::De)),
+        case("en", Ok(Lang::En)),
+        case("fr", Err("invalid language, expected `en` or `de`"))
+    )]
+    fn lang_from_str_test(
+        input_lang: &str,
+        expected_lang: Result<Lang, &'static str>,
+    ) {
+        assert_eq!(Lang::from_str(input_lang), expected_lang);
+    }
+
+    #[rstest(input_lang, case(Lang::De), case(Lang::En))]
+    fn tr_keeps_leading_indentation_test(input_lang: Lang) {
+        for message in Message::iter() {
+            let indentation = |text: &str| text.len() - text.trim_start().len();
+            assert_eq!(
+                indentation(tr(input_lang, message)),
+                indentation(tr(Lang::En, message)),
+                "{:?}",
+                message
+            );
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use crate::format::html::UNSAFE_COUNTER_COLUMNS;
+
+use cargo_geiger_serde::{Count, CounterBlock, SafetyReport};
+
+/// Renders the report of a full scan as a Markdown table, one row per
+/// package and a total row, e.g. for pull request comments and job
+/// summaries.
+pub fn render_markdown_report(report: &SafetyReport) -> String {
+    let mut markdown_lines = vec![
+        String::from("# cargo-geiger report"),
+        String::new(),
+        String::from(
+            "Metrics are shown as x/y, x = unsafe code used by the build, \
+             y = total unsafe code found in the crate.",
+        ),
+        String::new(),
+    ];
+    let mut headers = vec!["Package", "Forbids unsafe"];
+    headers.extend(UNSAFE_COUNTER_COLUMNS.iter());
+    let include_notes = report
+        .packages
+        .values()
+        .any(|entry| !entry.package.notes.is_empty());
+    if include_notes {
+        headers.push("Notes");
+    }
+    markdown_lines.push(markdown_row(&headers));
+    markdown_lines.push(markdown_row(&vec!["---"; headers.len()]));
+
+    let mut entries = report.packages.values().collect::<Vec<_>>();
+    entries.sort_by(|a, b| a.package.id.cmp(&b.package.id));
+    let mut total_used = CounterBlock::default();
+    let mut total_unused = CounterBlock::default();
+    for entry in entries {
+        let unsafety = &entry.unsafety;
+        total_used += unsafety.used.clone();
+        total_unused += unsafety.unused.clone();
+        let mut cells = vec![
+            format!("{} {}", entry.package.id.name, entry.package.id.version),
+            String::from(if unsafety.forbids_unsafe { "yes" } else { "no" }),
+        ];
+        cells.extend(used_of_total_cells(&unsafety.used, &unsafety.unused));
+        if include_notes {
+            cells.push(entry.package.notes.join(" "));
+        }
+        markdown_lines.push(markdown_row(&cells));
+    }
+    let mut total_cells = vec![String::from("**Total**"), String::new()];
+    total_cells.extend(used_of_total_cells(&total_used, &total_unused));
+    if include_notes {
+        total_cells.push(String::new());
+    }
+    markdown_lines.push(markdown_row(&total_cells));
+
+    if !report.packages_without_metrics.is_empty() {
+        markdown_lines.push(String::new());
+        markdown_lines.push(format!(
+            "{} package(s) without metrics.",
+            report.packages_without_metrics.len()
+        ));
+    }
+    markdown_lines.join("\n")
+}
+
+fn used_of_total_cells(
+    used: &CounterBlock,
+    unused: &CounterBlock,
+) -> Vec<String> {
+    let used_of_total = |used: &Count, unused: &Count| {
+        format!("{}/{}", used.unsafe_, used.unsafe_ + unused.unsafe_)
+    };
+    vec![
+        used_of_total(&used.functions, &unused.functions),
+        used_of_total(&used.exprs, &unused.exprs),
+        used_of_total(&used.item_impls, &unused.item_impls),
+        used_of_total(&used.item_traits, &unused.item_traits),
+        used_of_total(&used.methods, &unused.methods),
+    ]
+}
+
+/// A table row, with the pipes in the cells escaped and the line breaks
+/// replaced by spaces.
+pub fn markdown_row<S: AsRef<str>>(cells: &[S]) -> String {
+    let cells = cells
+        .iter()
+        .map(|cell| cell.as_ref().replace('|', "\\|").replace('\n', " "))
+        .collect::<Vec<String>>();
+    format!("| {} |", cells.join(" | "))
+}
+
+#[cfg(test)]
+mod markdown_tests {
+    use super::*;
+
+    use cargo_geiger_serde::{
+        PackageId, PackageInfo, ReportEntry, Source, UnsafeInfo,
+    };
+    use rstest::*;
+    use semver::Version;
+    use url::Url;
+
+    #[rstest]
+    fn render_markdown_report_test() {
+        let mut report = SafetyReport::default();
+        for (name, unsafe_exprs) in &[("serde", 2), ("ref_slice", 3)] {
+            let package = PackageInfo::new(PackageId {
+                name: String::from(*name),
+                version: Version::new(1, 0, 0),
+                source: Source::Registry {
+                    name: String::from("crates.io"),
+                    url: Url::parse(
+                        "https://github.com/rust-lang/crates.io-index",
+                    )
+                    .unwrap(),
+                },
+            });
+            let mut unsafety = UnsafeInfo::default();
+            unsafety.used.exprs.unsafe_ = *unsafe_exprs;
+            unsafety.unused.exprs.unsafe_ = 1;
+            report
+                .packages
+                .insert(package.id.clone(), ReportEntry { package, unsafety });
+        }
+
+        let markdown_lines = render_markdown_report(&report)
+            .lines()
+            .map(String::from)
+            .collect::<Vec<String>>();
+
+        assert_eq!(
+            markdown_lines[4],
+            "| Package | Forbids unsafe | Functions | Expressions | Impls | \
+             Traits | Methods |"
+        );
+        assert_eq!(
+            markdown_lines[5],
+            "| --- | --- | --- | --- | --- | --- | --- |"
+        );
+        assert_eq!(
+            markdown_lines[6],
+            "| ref_slice 1.0.0 | no | 0/0 | 3/4 | 0/0 | 0/0 | 0/0 |"
+        );
+        assert_eq!(
+            markdown_lines[8],
+            "| **Total** |  | 0/0 | 5/7 | 0/0 | 0/0 | 0/0 |"
+        );
+    }
+
+    #[rstest]
+    fn render_markdown_report_notes_test() {
+        let mut report = SafetyReport::default();
+        let mut package = PackageInfo::new(PackageId {
+            name: String::from("libc"),
+            version: Version::new(0, 2, 80),
+            source: Source::Registry {
+                name: String::from("crates.io"),
+                url: Url::parse("https://github.com/rust-lang/crates.io-index")
+                    .unwrap(),
+            },
+        });
+        package.notes = vec![String::from("Audited 2024-03, see JIRA-123")];
+        report.packages.insert(
+            package.id.clone(),
+            ReportEntry {
+                package,
+                unsafety: UnsafeInfo::default(),
+            },
+        );
+
+        let markdown_lines = render_markdown_report(&report)
+            .lines()
+            .map(String::from)
+            .collect::<Vec<String>>();
+
+        assert_eq!(
+            markdown_lines[4],
+            "| Package | Forbids unsafe | Functions | Expressions | Impls | \
+             Traits | Methods | Notes |"
+        );
+        assert_eq!(
+            markdown_lines[6],
+            "| libc 0.2.80 | no | 0/0 | 0/0 | 0/0 | 0/0 | 0/0 | Audited \
+             2024-03, see JIRA-123 |"
+        );
+        assert_eq!(
+            markdown_lines[7],
+            "| **Total** |  | 0/0 | 0/0 | 0/0 | 0/0 | 0/0 |  |"
+        );
+    }
+
+    #[rstest]
+    fn markdown_row_test() {
+        assert_eq!(markdown_row(&["a|b", "c"]), "| a\\|b | c |");
+        assert_eq!(markdown_row(&["a\nb"]), "| a b |");
+    }
+}
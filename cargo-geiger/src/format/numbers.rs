@@ -0,0 +1,99 @@
+use cargo::util::CargoResult;
+use cargo::Config;
+use serde::Deserialize;
+
+/// The `cargo` configuration key of the number format.
+const NUMBERS_CONFIG_KEY: &str = "geiger.numbers";
+
+/// The decimal places of ratios when the precision is not configured.
+const DEFAULT_PRECISION: usize = 2;
+
+const COMPACT_UNITS: [&str; 6] = ["k", "M", "G", "T", "P", "E"];
+
+/// How counts and ratios are written in the human readable output,
+/// configured in the `[geiger.numbers]` table of the `cargo` configuration,
+/// e.g. `compact = true` and `precision = 1`. The JSON output always has the
+/// exact counts.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+pub struct NumberFormat {
+    /// Counts of 1000 and more are written with one decimal and a unit, like
+    /// `12.4k`.
+    #[serde(default)]
+    pub compact: bool,
+
+    /// The decimal places of ratios and percentages.
+    pub precision: Option<usize>,
+}
+
+impl NumberFormat {
+    pub fn from_config(config: &Config) -> CargoResult<NumberFormat> {
+        Ok(config
+            .get::<Option<NumberFormat>>(NUMBERS_CONFIG_KEY)?
+            .unwrap_or_default())
+    }
+
+    pub fn count(self, count: u64) -> String {
+        if !self.compact || count < 1000 {
+            return count.to_string();
+        }
+        let mut value = count as f64 / 1000.0;
+        let mut unit = 0;
+        // Rounding to one decimal must not give 1000.0 of a unit.
+        while (value * 10.0).round() >= 10_000.0
+            && unit + 1 < COMPACT_UNITS.len()
+        {
+            value /= 1000.0;
+            unit += 1;
+        }
+        format!("{:.1}{}", value, COMPACT_UNITS[unit])
+    }
+
+    pub fn ratio(self, ratio: f64) -> String {
+        format!("{:.*}", self.precision.unwrap_or(DEFAULT_PRECISION), ratio)
+    }
+}
+
+#[cfg(test)]
+mod numbers_tests {
+    use super::*;
+
+    use rstest::*;
+
+    #[rstest(
+        input_compact,
+        input_count,
+        expected_text,
+        case(false, 12_400, "12400"),
+        case(true, 999, "999"),
+        case(true, 1000, "1.0k"),
+        case(true, 12_449, "12.4k"),
+        case(true, 999_949, "999.9k"),
+        case(true, 999_950, "1.0M"),
+        case(true, 3_100_000_000, "3.1G"),
+        case(true, u64::MAX, "18.4E")
+    )]
+    fn count_test(input_compact: bool, input_count: u64, expected_text: &str) {
+        let number_format = NumberFormat {
+            compact: input_compact,
+            precision: None,
+        };
+
+        assert_eq!(number_format.count(input_count), expected_text);
+    }
+
+    #[rstest(
+        input_precision,
+        expected_text,
+        case(None, "2.47"),
+        case(Some(0), "2"),
+        case(Some(4), "2.4681")
+    )]
+    fn ratio_test(input_precision: Option<usize>, expected_text: &str) {
+        let number_format = NumberFormat {
+            compact: false,
+            precision: input_precision,
+        };
+
+        assert_eq!(number_format.ratio(2.468_13), expected_text);
+    }
+}
@@ -1,12 +1,18 @@
 use crate::args::Args;
+use crate::format::emoji_symbols::SymbolOverrides;
+use crate::format::i18n::Lang;
+use crate::format::numbers::NumberFormat;
 use crate::format::pattern::Pattern;
 use crate::format::{Charset, CrateDetectionStatus, FormatError};
+use crate::tree::TreeOptions;
 
 use cargo::core::shell::Verbosity;
 use cargo::util::errors::CliError;
 use colored::Colorize;
-use geiger::IncludeTests;
+use geiger::{IncludeTests, DEFAULT_UNSAFE_APIS};
 use petgraph::EdgeDirection;
+use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Prefix {
@@ -15,9 +21,52 @@ pub enum Prefix {
     None,
 }
 
+/// The half of the unsafe usage shown in the metric columns of the table.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MetricsFilter {
+    /// `used/total`, the default.
+    All,
+    /// Only the usage in code used by the build, with `--used-only`.
+    Used,
+    /// Only the usage in code not used by the build, with `--unused-only`.
+    Unused,
+}
+
+impl MetricsFilter {
+    /// The count shown for a metric, the total for `All`.
+    pub fn shown(self, used: u64, not_used: u64) -> u64 {
+        match self {
+            MetricsFilter::All => used + not_used,
+            MetricsFilter::Used => used,
+            MetricsFilter::Unused => not_used,
+        }
+    }
+
+    /// The text of a metric cell.
+    pub fn format(
+        self,
+        used: u64,
+        not_used: u64,
+        numbers: NumberFormat,
+    ) -> String {
+        match self {
+            MetricsFilter::All => format!(
+                "{}/{}",
+                numbers.count(used),
+                numbers.count(used + not_used)
+            ),
+            _ => numbers.count(self.shown(used, not_used)),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum OutputFormat {
+    Html,
     Json,
+    Markdown,
+    SecurityInsights,
+    Treemap,
 }
 
 #[derive(Debug, PartialEq)]
@@ -26,6 +75,10 @@ pub struct PrintConfig {
     pub all: bool,
 
     pub allow_partial_results: bool,
+
+    /// Overrides the metrics cache location.
+    pub cache_dir: Option<PathBuf>,
+
     pub charset: Charset,
     pub direction: EdgeDirection,
 
@@ -33,9 +86,37 @@ pub struct PrintConfig {
     // TODO: Open a github issue to discuss deprecation.
     pub format: Pattern,
 
+    /// Globs of the paths of generated files, in addition to the files in
+    /// `OUT_DIR` and those marked `@generated`.
+    pub generated_globs: Vec<glob::Pattern>,
+
     pub include_tests: IncludeTests,
+
+    /// The language of the legend, summaries and warnings.
+    pub lang: Lang,
+
+    pub metrics_filter: MetricsFilter,
+
+    /// How the counts and ratios are written.
+    pub numbers: NumberFormat,
+
     pub prefix: Prefix,
     pub output_format: Option<OutputFormat>,
+
+    /// Keeps the locations of the unsafe usage in each file after its package
+    /// is scanned, for the outputs that list them.
+    pub retain_locations: bool,
+
+    /// Replacements for the detection status symbols.
+    pub symbols: SymbolOverrides,
+
+    /// How long the files of a package may take to scan before the rest of
+    /// them are left out.
+    pub timeout_per_crate: Option<Duration>,
+
+    /// The high-risk APIs to count calls to, e.g. `mem::transmute`.
+    pub unsafe_apis: Vec<String>,
+
     pub verbosity: Verbosity,
 }
 
@@ -60,12 +141,47 @@ impl PrintConfig {
             )
         })?;
 
+        let generated_globs = args
+            .generated_globs
+            .iter()
+            .map(|generated_glob| {
+                glob::Pattern::new(generated_glob).map_err(|e| {
+                    CliError::new(
+                        (FormatError {
+                            message: format!(
+                                "Invalid generated glob {}: {}",
+                                generated_glob, e
+                            ),
+                        })
+                        .into(),
+                        1,
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>, CliError>>()?;
+
         let include_tests = if args.include_tests {
             IncludeTests::Yes
         } else {
             IncludeTests::No
         };
 
+        let metrics_filter =
+            match (args.used_only, args.unused_only) {
+                (false, false) => MetricsFilter::All,
+                (true, false) => MetricsFilter::Used,
+                (false, true) => MetricsFilter::Unused,
+                (true, true) => return Err(CliError::new(
+                    (FormatError {
+                        message: String::from(
+                            "--used-only and --unused-only can't be combined",
+                        ),
+                    })
+                    .into(),
+                    1,
+                )),
+            };
+
         let prefix = if args.prefix_depth {
             Prefix::Depth
         } else if args.no_indent {
@@ -74,6 +190,23 @@ impl PrintConfig {
             Prefix::Indent
         };
 
+        let retain_locations = args.blame
+            || args.deny_undocumented_unsafe
+            || args.list_undocumented_unsafe.is_some()
+            || args.list_unsafe.is_some()
+            || args.include_files
+            || args.output_format == Some(OutputFormat::Html)
+            || args.serve.is_some()
+            || args.verbose > 0;
+
+        let unsafe_apis = match &args.unsafe_apis {
+            Some(unsafe_apis) => unsafe_apis.clone(),
+            None => DEFAULT_UNSAFE_APIS
+                .iter()
+                .map(|api| String::from(*api))
+                .collect(),
+        };
+
         let verbosity = if args.verbose == 0 {
             Verbosity::Normal
         } else {
@@ -83,15 +216,34 @@ impl PrintConfig {
         Ok(PrintConfig {
             all: args.all,
             allow_partial_results,
+            cache_dir: args.cache_dir.clone(),
             charset: args.charset,
             direction,
             format,
+            generated_globs,
             include_tests,
+            lang: args.lang,
+            metrics_filter,
+            numbers: NumberFormat::default(),
             output_format: args.output_format,
             prefix,
+            retain_locations,
+            symbols: SymbolOverrides::default(),
+            timeout_per_crate: args.timeout_per_crate.map(Duration::from_secs),
+            unsafe_apis,
             verbosity,
         })
     }
+
+    /// The options of the dependency tree walk.
+    pub fn tree_options(&self) -> TreeOptions {
+        TreeOptions {
+            all: self.all,
+            charset: self.charset,
+            direction: self.direction,
+            prefix: self.prefix,
+        }
+    }
 }
 
 pub fn colorize(
@@ -102,6 +254,7 @@ pub fn colorize(
         CrateDetectionStatus::NoneDetectedForbidsUnsafe => string.green(),
         CrateDetectionStatus::NoneDetectedAllowsUnsafe => string.normal(),
         CrateDetectionStatus::UnsafeDetected => string.red().bold(),
+        CrateDetectionStatus::ForbidMismatch => string.yellow().bold(),
     }
 }
 
@@ -140,6 +293,24 @@ mod print_config_tests {
         );
     }
 
+    #[rstest(
+        input_generated_glob,
+        expected_is_ok,
+        case("**/bindings.rs", true),
+        case("src/[generated", false)
+    )]
+    fn print_config_new_test_generated_globs(
+        input_generated_glob: &str,
+        expected_is_ok: bool,
+    ) {
+        let mut args = create_args();
+        args.generated_globs = vec![String::from(input_generated_glob)];
+
+        let print_config_result = PrintConfig::new(&args);
+
+        assert_eq!(print_config_result.is_ok(), expected_is_ok);
+    }
+
     #[rstest(
         input_include_tests_bool,
         expected_include_tests,
@@ -257,6 +428,10 @@ mod print_config_tests {
         case(
             CrateDetectionStatus::UnsafeDetected,
             String::from("string_value").red().bold()
+        ),
+        case(
+            CrateDetectionStatus::ForbidMismatch,
+            String::from("string_value").yellow().bold()
         )
     )]
     fn colorize_test(
@@ -280,29 +455,89 @@ mod print_config_tests {
             all_deps: false,
             all_features: false,
             all_targets: false,
+            allow_remote: false,
+            attest: None,
+            baseline: None,
+            bins: false,
+            blame: false,
             build_deps: false,
+            bundle: None,
+            cache_dir: None,
+            cached: false,
             charset: Charset::Ascii,
+            clean_members: false,
             color: None,
+            deny_undocumented_unsafe: false,
             dev_deps: false,
+            examples: false,
             features: None,
+            fail_on_missing_metrics: false,
+            fail_on_regression: false,
+            first_party_split: false,
+            fix: false,
             forbid_only: false,
             format: "".to_string(),
             frozen: false,
+            generated_globs: vec![],
+            generated_weight: None,
+            github_output: false,
             help: false,
+            include_files: false,
+            include_metadata: false,
+            include_owners: false,
+            include_release_age: false,
+            include_sysroot: false,
             include_tests: false,
             invert: false,
+            lang: Lang::En,
+            latent_unsafe: false,
+            lib: false,
+            listen: None,
+            list_undocumented_unsafe: None,
+            list_unsafe: None,
             locked: false,
-            manifest_path: None,
+            manifest_paths: vec![],
+            max_age: None,
+            max_unsafe_expressions: vec![],
+            max_unsafe_functions: vec![],
+            max_unsafe_impls: vec![],
+            max_unsafe_methods: vec![],
+            max_unsafe_ratio: None,
+            max_unsafe_ratio_per_crate: None,
+            max_unsafe_traits: vec![],
+            no_clean: false,
             no_default_features: false,
             no_indent: false,
             offline: false,
             package: None,
+            policy: None,
+            pr_comment: None,
             prefix_depth: false,
+            quarantine: None,
             quiet: false,
+            report_only: false,
+            require_forbid: None,
+            rescan: false,
+            save_baseline: None,
+            serve: None,
+            show_inactive: false,
+            snapshot: false,
+            subcommand: None,
+            suggest_features: false,
+            suggest_forbid: false,
             target: None,
+            tests: false,
+            timeout_per_crate: None,
+            toolchain: None,
+            unsafe_apis: None,
             unstable_flags: vec![],
+            unused_only: false,
+            used_only: false,
             verbose: 0,
+            verify_checksums: false,
             version: false,
+            why: None,
+            why_most_unsafe: false,
             output_format: None
         }
     }
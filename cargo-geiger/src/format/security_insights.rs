@@ -0,0 +1,160 @@
+use crate::format::treemap::unsafe_count;
+
+use cargo_geiger_serde::{
+    MemorySafetyIndicators, MemorySafetyStatus, ReportEntry, SafetyReport,
+    SecurityInsights, SecurityInsightsDependencies, SecurityInsightsHeader,
+    Source,
+};
+
+const SCHEMA_VERSION: &str = "1.0.0";
+
+const VERSION: Option<&'static str> = option_env!("CARGO_PKG_VERSION");
+
+/// Builds the memory safety indicators of every package in the report,
+/// sorted by package.
+pub fn security_insights_from_report(
+    report: &SafetyReport,
+) -> SecurityInsights {
+    let mut entries = report.packages.values().collect::<Vec<_>>();
+    entries.sort_by(|a, b| a.package.id.cmp(&b.package.id));
+    SecurityInsights {
+        header: SecurityInsightsHeader {
+            schema_version: String::from(SCHEMA_VERSION),
+            generator: format!(
+                "cargo-geiger {}",
+                VERSION.unwrap_or("unknown version")
+            ),
+        },
+        dependencies: SecurityInsightsDependencies {
+            memory_safety: entries
+                .into_iter()
+                .map(memory_safety_indicators)
+                .collect(),
+        },
+    }
+}
+
+fn memory_safety_indicators(entry: &ReportEntry) -> MemorySafetyIndicators {
+    let package_id = &entry.package.id;
+    let unsafe_used = unsafe_count(&entry.unsafety.used);
+    let status = if entry.unsafety.forbid_mismatch {
+        MemorySafetyStatus::ForbidMismatch
+    } else if unsafe_used > 0 {
+        MemorySafetyStatus::UnsafeFound
+    } else if entry.unsafety.forbids_unsafe {
+        MemorySafetyStatus::ForbidsUnsafe
+    } else {
+        MemorySafetyStatus::NoUnsafeFound
+    };
+    MemorySafetyIndicators {
+        name: package_id.name.clone(),
+        version: package_id.version.clone(),
+        purl: match &package_id.source {
+            Source::Registry { name, .. } if name == "crates.io" => Some(
+                format!("pkg:cargo/{}@{}", package_id.name, package_id.version),
+            ),
+            _ => None,
+        },
+        status,
+        forbids_unsafe: entry.unsafety.forbids_unsafe,
+        unsafe_used,
+        unsafe_unused: unsafe_count(&entry.unsafety.unused),
+    }
+}
+
+#[cfg(test)]
+mod security_insights_tests {
+    use super::*;
+
+    use cargo_geiger_serde::{
+        Count, CounterBlock, PackageId, PackageInfo, UnsafeInfo,
+    };
+    use rstest::*;
+    use semver::Version;
+    use url::Url;
+
+    #[rstest(
+        input_source,
+        input_used_exprs,
+        input_forbids_unsafe,
+        expected_purl,
+        expected_status,
+        case(
+            create_registry_source("crates.io"),
+            1,
+            false,
+            Some("pkg:cargo/foo@1.2.3"),
+            MemorySafetyStatus::UnsafeFound
+        ),
+        case(
+            create_registry_source("crates.io"),
+            0,
+            true,
+            Some("pkg:cargo/foo@1.2.3"),
+            MemorySafetyStatus::ForbidsUnsafe
+        ),
+        case(
+            create_registry_source("internal"),
+            0,
+            false,
+            None,
+            MemorySafetyStatus::NoUnsafeFound
+        )
+    )]
+    fn memory_safety_indicators_test(
+        input_source: Source,
+        input_used_exprs: u64,
+        input_forbids_unsafe: bool,
+        expected_purl: Option<&str>,
+        expected_status: MemorySafetyStatus,
+    ) {
+        let entry = ReportEntry {
+            package: PackageInfo::new(PackageId {
+                name: String::from("foo"),
+                version: Version::new(1, 2, 3),
+                source: input_source,
+            }),
+            unsafety: UnsafeInfo {
+                used: CounterBlock {
+                    exprs: Count {
+                        safe: 0,
+                        unsafe_: input_used_exprs,
+                    },
+                    ..Default::default()
+                },
+                unused: CounterBlock {
+                    exprs: Count {
+                        safe: 0,
+                        unsafe_: 2,
+                    },
+                    ..Default::default()
+                },
+                forbids_unsafe: input_forbids_unsafe,
+                ..Default::default()
+            },
+        };
+
+        let indicators = memory_safety_indicators(&entry);
+
+        assert_eq!(indicators.purl.as_deref(), expected_purl);
+        assert_eq!(indicators.status, expected_status);
+        assert_eq!(indicators.unsafe_used, input_used_exprs);
+        assert_eq!(indicators.unsafe_unused, 2);
+    }
+
+    #[rstest]
+    fn memory_safety_status_serialization_test() {
+        assert_eq!(
+            serde_json::to_string(&MemorySafetyStatus::NoUnsafeFound).unwrap(),
+            "\"no-unsafe-found\""
+        );
+    }
+
+    fn create_registry_source(name: &str) -> Source {
+        Source::Registry {
+            name: String::from(name),
+            url: Url::parse("https://github.com/rust-lang/crates.io-index")
+                .unwrap(),
+        }
+    }
+}
@@ -1,12 +1,15 @@
 mod handle_text_tree_line;
 mod total_package_counts;
 
-use crate::format::emoji_symbols::EmojiSymbols;
-use crate::format::print_config::{colorize, PrintConfig};
-use crate::format::CrateDetectionStatus;
+use crate::format::i18n::{tr, Lang, Message};
+use crate::format::numbers::NumberFormat;
+use crate::format::print_config::{colorize, MetricsFilter, PrintConfig};
+use crate::format::treemap::unsafe_count;
+use crate::format::{package_name_markers, CrateDetectionStatus};
 use crate::scan::GeigerContext;
 use crate::tree::TextTreeLine;
 
+use colored::Colorize;
 use handle_text_tree_line::{
     handle_text_tree_line_extra_deps_group, handle_text_tree_line_package,
     HandlePackageParameters,
@@ -15,8 +18,10 @@ use total_package_counts::TotalPackageCounts;
 
 use cargo::core::package::PackageSet;
 use cargo_geiger_serde::{Count, CounterBlock};
-use std::collections::HashSet;
+use cargo_platform::{Cfg, CfgExpr};
+use std::collections::{BTreeMap, HashSet};
 use std::path::PathBuf;
+use std::str::FromStr;
 
 // TODO: use a table library, or factor the tableness out in a smarter way. This
 // is probably easier now when the tree formatting is separated from the tree
@@ -39,8 +44,6 @@ pub fn create_table_from_text_tree_lines(
     let mut total_package_counts = TotalPackageCounts::new();
     let mut warning_count = 0;
     let mut visited_package_ids = HashSet::new();
-    let emoji_symbols =
-        EmojiSymbols::new(table_parameters.print_config.charset);
     let mut handle_package_parameters = HandlePackageParameters {
         total_package_counts: &mut total_package_counts,
         visited_package_ids: &mut visited_package_ids,
@@ -59,11 +62,21 @@ pub fn create_table_from_text_tree_lines(
             ),
             TextTreeLine::Package {
                 id: package_id,
+                patched,
+                local_override,
+                yanked,
+                via_feature,
                 tree_vines,
             } => handle_text_tree_line_package(
-                &emoji_symbols,
                 &mut handle_package_parameters,
                 package_id,
+                package_name_markers(
+                    table_parameters.print_config.lang,
+                    patched,
+                    local_override,
+                    yanked,
+                    via_feature.as_deref(),
+                ),
                 package_set,
                 &mut table_lines,
                 table_parameters,
@@ -75,17 +88,43 @@ pub fn create_table_from_text_tree_lines(
     table_lines.push(String::new());
     let total_detection_status =
         total_package_counts.get_total_detection_status();
+    let mut category_lines = construct_category_lines(
+        &total_package_counts.total_counter_block,
+        &total_package_counts.total_unused_counter_block,
+        table_parameters.print_config.lang,
+        table_parameters.print_config.metrics_filter,
+        table_parameters.print_config.numbers,
+    );
 
     table_lines.push(format!(
         "{}",
         table_footer(
             total_package_counts.total_counter_block,
             total_package_counts.total_unused_counter_block,
-            total_detection_status
+            total_detection_status,
+            table_parameters.print_config.metrics_filter,
+            table_parameters.print_config.numbers
         )
     ));
 
     table_lines.push(String::new());
+    table_lines.append(&mut category_lines);
+    table_lines.append(&mut construct_generated_lines(
+        &total_package_counts.total_generated_counter_block,
+        table_parameters.print_config.lang,
+    ));
+
+    if !total_package_counts
+        .total_platform_counter_blocks
+        .is_empty()
+    {
+        let mut platform_breakdown_lines = construct_platform_breakdown_lines(
+            &total_package_counts.total_platform_counter_blocks,
+            table_parameters.target_cfgs,
+            table_parameters.print_config.lang,
+        );
+        table_lines.append(&mut platform_breakdown_lines);
+    }
 
     (table_lines, warning_count)
 }
@@ -94,15 +133,227 @@ pub struct TableParameters<'a> {
     pub geiger_context: &'a GeigerContext,
     pub print_config: &'a PrintConfig,
     pub rs_files_used: &'a HashSet<PathBuf>,
+    /// The active `cfg` values for the target, used to mark which platform
+    /// specific code is built.
+    pub target_cfgs: Option<&'a [Cfg]>,
+}
+
+/// An unsafe usage category listed below the table.
+pub struct Category {
+    pub message: Message,
+    /// The name of its `cargo geiger explain` topic.
+    pub topic: &'static str,
+    pub unsafe_count: fn(&CounterBlock) -> u64,
+}
+
+/// The unsafe usage categories listed below the table, in order.
+pub const CATEGORIES: &[Category] = &[
+    Category {
+        message: Message::InlineAssembly,
+        topic: "asm",
+        unsafe_count: |c| c.asm,
+    },
+    Category {
+        message: Message::UnsafeDropExprs,
+        topic: "drop",
+        unsafe_count: |c| c.drop_exprs.unsafe_,
+    },
+    Category {
+        message: Message::UnsafeFnBodyExprs,
+        topic: "unsafe-fn-bodies",
+        unsafe_count: |c| c.unsafe_fn_body_exprs,
+    },
+    Category {
+        message: Message::UnsafeConstExprs,
+        topic: "const",
+        unsafe_count: |c| c.const_exprs.unsafe_,
+    },
+    Category {
+        message: Message::PackedStructs,
+        topic: "packed",
+        unsafe_count: |c| c.packed_structs,
+    },
+    Category {
+        message: Message::StaticMuts,
+        topic: "static-mut",
+        unsafe_count: |c| c.static_muts,
+    },
+    Category {
+        message: Message::StaticMutRefs,
+        topic: "static-mut-refs",
+        unsafe_count: |c| c.static_mut_refs,
+    },
+    Category {
+        message: Message::Unions,
+        topic: "unions",
+        unsafe_count: |c| c.unions,
+    },
+    Category {
+        message: Message::UnionFieldAccesses,
+        topic: "union-fields",
+        unsafe_count: |c| c.union_field_accesses,
+    },
+    Category {
+        message: Message::ExternStatics,
+        topic: "extern-statics",
+        unsafe_count: |c| c.extern_statics,
+    },
+    Category {
+        message: Message::FfiCalls,
+        topic: "ffi",
+        unsafe_count: |c| c.ffi_calls,
+    },
+    Category {
+        message: Message::JsFfiCalls,
+        topic: "js-ffi",
+        unsafe_count: |c| c.js_ffi_calls,
+    },
+    Category {
+        message: Message::ExportedGlobals,
+        topic: "exported-globals",
+        unsafe_count: |c| c.exported_globals,
+    },
+    Category {
+        message: Message::PublicUnsafeFns,
+        topic: "public-unsafe-fns",
+        unsafe_count: |c| c.public_unsafe_fns,
+    },
+    Category {
+        message: Message::PublicUnsafeTraits,
+        topic: "public-unsafe-traits",
+        unsafe_count: |c| c.public_unsafe_traits,
+    },
+];
+
+/// Lists the totals for the unsafe usage categories that are not part of the
+/// table columns, in the same format as the table. Categories without any
+/// unsafe usage shown are left out.
+fn construct_category_lines(
+    used: &CounterBlock,
+    not_used: &CounterBlock,
+    lang: Lang,
+    metrics_filter: MetricsFilter,
+    numbers: NumberFormat,
+) -> Vec<String> {
+    let categories = CATEGORIES.iter().map(|category| {
+        (
+            category.message,
+            (category.unsafe_count)(used),
+            (category.unsafe_count)(not_used),
+        )
+    });
+
+    let mut category_lines = categories
+        .filter(|(_, used, not_used)| {
+            metrics_filter.shown(*used, *not_used) > 0
+        })
+        .map(|(message, used, not_used)| {
+            format!(
+                "{: <10} {}",
+                metrics_filter.format(used, not_used, numbers),
+                tr(lang, message)
+            )
+        })
+        .collect::<Vec<String>>();
+
+    if !category_lines.is_empty() {
+        category_lines
+            .insert(0, String::from(tr(lang, Message::UnsafeUsagePerCategory)));
+        category_lines.insert(1, String::new());
+        category_lines.push(String::new());
+    }
+
+    category_lines
+}
+
+/// Summarizes the unsafe usage in generated files used by the build, these are
+/// also part of the used counts of the table.
+fn construct_generated_lines(
+    generated: &CounterBlock,
+    lang: Lang,
+) -> Vec<String> {
+    let generated_unsafe = unsafe_count(generated);
+    if generated_unsafe == 0 {
+        return vec![];
+    }
+    vec![
+        format!(
+            "{} {}",
+            tr(lang, Message::GeneratedUnsafe),
+            generated_unsafe
+        ),
+        String::new(),
+    ]
+}
+
+/// Lists the unsafe usage guarded by platform specific `cfg` attributes, one
+/// row per `cfg` predicate. Predicates matching the current target are
+/// marked with an asterisk.
+fn construct_platform_breakdown_lines(
+    platform_counter_blocks: &BTreeMap<String, CounterBlock>,
+    target_cfgs: Option<&[Cfg]>,
+    lang: Lang,
+) -> Vec<String> {
+    let mut platform_breakdown_lines = vec![
+        String::from(tr(lang, Message::UnsafeUsagePerPlatform)),
+        String::from(tr(lang, Message::ActiveForTarget)),
+        String::new(),
+        unsafe_counters_header("Platform"),
+    ];
+
+    for (platform, counter_block) in platform_counter_blocks {
+        let is_active = match (CfgExpr::from_str(platform), target_cfgs) {
+            (Ok(cfg_expr), Some(cfgs)) => cfg_expr.matches(cfgs),
+            _ => false,
+        };
+        platform_breakdown_lines.push(format!(
+            "{}  {} {}",
+            unsafe_counters_row(counter_block),
+            if is_active { "*" } else { " " },
+            platform
+        ));
+    }
+
+    platform_breakdown_lines.push(String::new());
+    platform_breakdown_lines
+}
+
+/// The table header, with the last column replaced, for tables listing only
+/// the unsafe counts.
+pub fn unsafe_counters_header(last_column: &str) -> String {
+    format!(
+        "{}",
+        UNSAFE_COUNTERS_HEADER[..UNSAFE_COUNTERS_HEADER.len() - 1]
+            .iter()
+            .map(|s| s.to_owned())
+            .chain(std::iter::once(last_column))
+            .collect::<Vec<_>>()
+            .join(" ")
+            .bold()
+    )
+}
+
+/// A table row listing only the unsafe counts, without the used/total split.
+pub fn unsafe_counters_row(counter_block: &CounterBlock) -> String {
+    format!(
+        "{: <10} {: <12} {: <6} {: <7} {: <7}",
+        counter_block.functions.unsafe_,
+        counter_block.exprs.unsafe_,
+        counter_block.item_impls.unsafe_,
+        counter_block.item_traits.unsafe_,
+        counter_block.methods.unsafe_,
+    )
 }
 
-fn table_footer(
+pub fn table_footer(
     used: CounterBlock,
     not_used: CounterBlock,
     status: CrateDetectionStatus,
+    metrics_filter: MetricsFilter,
+    numbers: NumberFormat,
 ) -> colored::ColoredString {
     let fmt = |used: &Count, not_used: &Count| {
-        format!("{}/{}", used.unsafe_, used.unsafe_ + not_used.unsafe_)
+        metrics_filter.format(used.unsafe_, not_used.unsafe_, numbers)
     };
     let output = format!(
         "{: <10} {: <12} {: <6} {: <7} {: <7}",
@@ -115,9 +366,14 @@ fn table_footer(
     colorize(output, &status)
 }
 
-fn table_row(used: &CounterBlock, not_used: &CounterBlock) -> String {
+pub fn table_row(
+    used: &CounterBlock,
+    not_used: &CounterBlock,
+    metrics_filter: MetricsFilter,
+    numbers: NumberFormat,
+) -> String {
     let fmt = |used: &Count, not_used: &Count| {
-        format!("{}/{}", used.unsafe_, used.unsafe_ + not_used.unsafe_)
+        metrics_filter.format(used.unsafe_, not_used.unsafe_, numbers)
     };
     format!(
         "{: <10} {: <12} {: <6} {: <7} {: <7}",
@@ -129,12 +385,12 @@ fn table_row(used: &CounterBlock, not_used: &CounterBlock) -> String {
     )
 }
 
-fn table_row_empty() -> String {
+pub fn table_row_empty() -> String {
     let headers_but_last =
         &UNSAFE_COUNTERS_HEADER[..UNSAFE_COUNTERS_HEADER.len() - 1];
     let n = headers_but_last
         .iter()
-        .map(|s| s.len())
+        .map(|s| console::measure_text_width(s))
         .sum::<usize>()
         + headers_but_last.len() // Space after each column
         + 2 // Unsafety symbol width
@@ -168,6 +424,8 @@ mod table_tests {
                 used_counter_block.clone(),
                 not_used_counter_block.clone(),
                 crate_detection_status.clone(),
+                MetricsFilter::All,
+                NumberFormat::default(),
             );
 
             assert_eq!(
@@ -177,6 +435,363 @@ mod table_tests {
         }
     }
 
+    #[rstest]
+    fn construct_category_lines_test() {
+        let used_counter_block = CounterBlock {
+            asm: 1,
+            ..Default::default()
+        };
+        let not_used_counter_block = CounterBlock {
+            asm: 2,
+            ..Default::default()
+        };
+
+        let category_lines = construct_category_lines(
+            &used_counter_block,
+            &not_used_counter_block,
+            Lang::En,
+            MetricsFilter::All,
+            NumberFormat::default(),
+        );
+
+        assert_eq!(
+            category_lines,
+            vec![
+                String::from("Unsafe usage per category:"),
+                String::new(),
+                String::from("1/3        Inline assembly"),
+                String::new(),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn construct_category_lines_unsafe_fn_body_test() {
+        let used_counter_block = CounterBlock {
+            unsafe_fn_body_exprs: 4,
+            ..Default::default()
+        };
+
+        let category_lines = construct_category_lines(
+            &used_counter_block,
+            &CounterBlock::default(),
+            Lang::En,
+            MetricsFilter::All,
+            NumberFormat::default(),
+        );
+
+        assert_eq!(
+            category_lines[2],
+            "4/4        Unsafe expressions in unsafe fn bodies, outside unsafe \
+             blocks"
+        );
+    }
+
+    #[rstest]
+    fn construct_category_lines_const_test() {
+        let used_counter_block = CounterBlock {
+            const_exprs: Count {
+                safe: 5,
+                unsafe_: 2,
+            },
+            ..Default::default()
+        };
+        let not_used_counter_block = CounterBlock {
+            const_exprs: Count {
+                safe: 1,
+                unsafe_: 1,
+            },
+            ..Default::default()
+        };
+
+        let category_lines = construct_category_lines(
+            &used_counter_block,
+            &not_used_counter_block,
+            Lang::En,
+            MetricsFilter::All,
+            NumberFormat::default(),
+        );
+
+        assert_eq!(
+            category_lines[2],
+            "2/3        Unsafe expressions in const fn bodies and const \
+             initializers"
+        );
+    }
+
+    #[rstest]
+    fn construct_category_lines_static_mut_test() {
+        let used_counter_block = CounterBlock {
+            static_muts: 1,
+            static_mut_refs: 3,
+            ..Default::default()
+        };
+        let not_used_counter_block = CounterBlock {
+            packed_structs: 2,
+            ..Default::default()
+        };
+
+        let category_lines = construct_category_lines(
+            &used_counter_block,
+            &not_used_counter_block,
+            Lang::En,
+            MetricsFilter::All,
+            NumberFormat::default(),
+        );
+
+        assert_eq!(
+            category_lines,
+            vec![
+                String::from("Unsafe usage per category:"),
+                String::new(),
+                String::from("0/2        #[repr(packed)] structs"),
+                String::from("1/1        static mut items"),
+                String::from(
+                    "3/3        References to static mut items (matched by name)"
+                ),
+                String::new(),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn construct_category_lines_union_test() {
+        let used_counter_block = CounterBlock {
+            unions: 1,
+            union_field_accesses: 2,
+            ..Default::default()
+        };
+        let not_used_counter_block = CounterBlock {
+            union_field_accesses: 1,
+            ..Default::default()
+        };
+
+        let category_lines = construct_category_lines(
+            &used_counter_block,
+            &not_used_counter_block,
+            Lang::En,
+            MetricsFilter::All,
+            NumberFormat::default(),
+        );
+
+        assert_eq!(
+            category_lines[2..4],
+            [
+                "1/1        union definitions",
+                "2/3        Union field reads in unsafe code (matched by field name)",
+            ]
+        );
+    }
+
+    #[rstest]
+    fn construct_category_lines_ffi_test() {
+        let used_counter_block = CounterBlock {
+            extern_statics: 2,
+            ffi_calls: 4,
+            js_ffi_calls: 7,
+            exported_globals: 1,
+            ..Default::default()
+        };
+
+        let category_lines = construct_category_lines(
+            &used_counter_block,
+            &CounterBlock::default(),
+            Lang::En,
+            MetricsFilter::All,
+            NumberFormat::default(),
+        );
+
+        assert_eq!(
+            category_lines,
+            vec![
+                String::from("Unsafe usage per category:"),
+                String::new(),
+                String::from("2/2        extern static declarations (FFI)"),
+                String::from("4/4        Calls to extern functions (FFI)"),
+                String::from(
+                    "7/7        Calls to JavaScript imports (wasm-bindgen, JS FFI)"
+                ),
+                String::from("1/1        Exported globals (FFI)"),
+                String::new(),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn construct_category_lines_public_unsafe_api_test() {
+        let used_counter_block = CounterBlock {
+            public_unsafe_fns: 3,
+            ..Default::default()
+        };
+        let not_used_counter_block = CounterBlock {
+            public_unsafe_fns: 1,
+            public_unsafe_traits: 1,
+            ..Default::default()
+        };
+
+        let category_lines = construct_category_lines(
+            &used_counter_block,
+            &not_used_counter_block,
+            Lang::En,
+            MetricsFilter::All,
+            NumberFormat::default(),
+        );
+
+        assert_eq!(
+            category_lines,
+            vec![
+                String::from("Unsafe usage per category:"),
+                String::new(),
+                String::from("3/4        pub unsafe fn items (public API)"),
+                String::from("0/1        pub unsafe trait items (public API)"),
+                String::new(),
+            ]
+        );
+    }
+
+    #[rstest(
+        input_metrics_filter,
+        expected_lines,
+        case(
+            MetricsFilter::Used,
+            vec!["3          pub unsafe fn items (public API)"]
+        ),
+        case(
+            MetricsFilter::Unused,
+            vec![
+                "1          pub unsafe fn items (public API)",
+                "1          pub unsafe trait items (public API)",
+            ]
+        )
+    )]
+    fn construct_category_lines_metrics_filter_test(
+        input_metrics_filter: MetricsFilter,
+        expected_lines: Vec<&str>,
+    ) {
+        let used_counter_block = CounterBlock {
+            public_unsafe_fns: 3,
+            ..Default::default()
+        };
+        let not_used_counter_block = CounterBlock {
+            public_unsafe_fns: 1,
+            public_unsafe_traits: 1,
+            ..Default::default()
+        };
+
+        let category_lines = construct_category_lines(
+            &used_counter_block,
+            &not_used_counter_block,
+            Lang::En,
+            input_metrics_filter,
+            NumberFormat::default(),
+        );
+
+        assert_eq!(
+            category_lines[2..category_lines.len() - 1],
+            expected_lines[..]
+        );
+    }
+
+    #[rstest]
+    fn construct_generated_lines_test() {
+        let generated_counter_block = CounterBlock {
+            functions: Count {
+                safe: 1,
+                unsafe_: 2,
+            },
+            exprs: Count {
+                safe: 4,
+                unsafe_: 3,
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(
+            construct_generated_lines(&generated_counter_block, Lang::En),
+            vec![
+                String::from(
+                    "Unsafe usage in generated code, included in the used \
+                     counts: 5"
+                ),
+                String::new(),
+            ]
+        );
+        assert!(
+            construct_generated_lines(&CounterBlock::default(), Lang::En)
+                .is_empty()
+        );
+    }
+
+    #[rstest]
+    fn construct_category_lines_empty_test() {
+        let category_lines = construct_category_lines(
+            &CounterBlock::default(),
+            &CounterBlock::default(),
+            Lang::En,
+            MetricsFilter::All,
+            NumberFormat::default(),
+        );
+
+        assert!(category_lines.is_empty());
+    }
+
+    #[rstest]
+    fn construct_category_lines_translated_test() {
+        let used_counter_block = CounterBlock {
+            asm: 1,
+            ..Default::default()
+        };
+
+        let category_lines = construct_category_lines(
+            &used_counter_block,
+            &CounterBlock::default(),
+            Lang::De,
+            MetricsFilter::All,
+            NumberFormat::default(),
+        );
+
+        assert_eq!(
+            category_lines,
+            vec![
+                String::from("Verwendung von unsafe je Kategorie:"),
+                String::new(),
+                String::from("1/1        Inline-Assembler"),
+                String::new(),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn construct_platform_breakdown_lines_test() {
+        let mut platform_counter_blocks = BTreeMap::new();
+        platform_counter_blocks
+            .insert(String::from("windows"), create_counter_block());
+        platform_counter_blocks.insert(
+            String::from("target_arch = \"x86_64\""),
+            create_counter_block(),
+        );
+        let target_cfgs = vec![
+            Cfg::from_str("unix").unwrap(),
+            Cfg::from_str("target_arch = \"x86_64\"").unwrap(),
+        ];
+
+        let platform_breakdown_lines = construct_platform_breakdown_lines(
+            &platform_counter_blocks,
+            Some(&target_cfgs),
+            Lang::En,
+        );
+
+        assert_eq!(platform_breakdown_lines.len(), 7);
+        assert_eq!(
+            platform_breakdown_lines[4],
+            "2          4            6      8       10       * target_arch = \"x86_64\""
+        );
+        assert_eq!(
+            platform_breakdown_lines[5],
+            "2          4            6      8       10         windows"
+        );
+    }
+
     #[rstest]
     fn table_row_test() {
         let mut rs_path_to_metrics =
@@ -197,7 +812,10 @@ mod table_tests {
             create_rs_file_metrics_wrapper(false, false),
         );
 
-        let package_metrics = PackageMetrics { rs_path_to_metrics };
+        let package_metrics = PackageMetrics {
+            rs_path_to_metrics,
+            ..Default::default()
+        };
         let rs_files_used: HashSet<PathBuf> = [
             Path::new("package_1_path").to_path_buf(),
             Path::new("package_3_path").to_path_buf(),
@@ -207,7 +825,31 @@ mod table_tests {
         .collect();
         let unsafety = unsafe_stats(&package_metrics, &rs_files_used);
 
-        let table_row = table_row(&unsafety.used, &unsafety.unused);
+        assert_eq!(
+            table_row(
+                &unsafety.used,
+                &unsafety.unused,
+                MetricsFilter::Used,
+                NumberFormat::default()
+            ),
+            "4          8            12     16      20     "
+        );
+        assert_eq!(
+            table_row(
+                &unsafety.used,
+                &unsafety.unused,
+                MetricsFilter::Unused,
+                NumberFormat::default()
+            ),
+            "2          4            6      8       10     "
+        );
+
+        let table_row = table_row(
+            &unsafety.used,
+            &unsafety.unused,
+            MetricsFilter::All,
+            NumberFormat::default(),
+        );
         assert_eq!(table_row, "4/6        8/12         12/18  16/24   20/30  ");
     }
 
@@ -221,23 +863,29 @@ mod table_tests {
         input_none_detected_forbids_unsafe,
         input_none_detected_allows_unsafe,
         input_unsafe_detected,
+        input_forbid_mismatch,
         expected_crate_detection_status,
-        case(0, 0, 1, CrateDetectionStatus::UnsafeDetected),
-        case(1, 0, 0, CrateDetectionStatus::NoneDetectedForbidsUnsafe),
-        case(4, 1, 0, CrateDetectionStatus::NoneDetectedAllowsUnsafe)
+        case(0, 0, 1, 0, CrateDetectionStatus::UnsafeDetected),
+        case(1, 0, 0, 0, CrateDetectionStatus::NoneDetectedForbidsUnsafe),
+        case(4, 1, 0, 0, CrateDetectionStatus::NoneDetectedAllowsUnsafe),
+        case(4, 1, 1, 1, CrateDetectionStatus::ForbidMismatch)
     )]
     fn total_package_counts_get_total_detection_status_tests(
         input_none_detected_forbids_unsafe: i32,
         input_none_detected_allows_unsafe: i32,
         input_unsafe_detected: i32,
+        input_forbid_mismatch: i32,
         expected_crate_detection_status: CrateDetectionStatus,
     ) {
         let total_detection_status = TotalPackageCounts {
             none_detected_forbids_unsafe: input_none_detected_forbids_unsafe,
             none_detected_allows_unsafe: input_none_detected_allows_unsafe,
             unsafe_detected: input_unsafe_detected,
+            forbid_mismatch: input_forbid_mismatch,
             total_counter_block: CounterBlock::default(),
             total_unused_counter_block: CounterBlock::default(),
+            total_generated_counter_block: CounterBlock::default(),
+            total_platform_counter_blocks: BTreeMap::new(),
         };
 
         assert_eq!(
@@ -254,8 +902,10 @@ mod table_tests {
             metrics: RsFileMetrics {
                 counters: create_counter_block(),
                 forbids_unsafe,
+                ..Default::default()
             },
             is_crate_entry_point,
+            is_generated: false,
         }
     }
 
@@ -281,6 +931,7 @@ mod table_tests {
                 safe: 9,
                 unsafe_: 10,
             },
+            ..Default::default()
         }
     }
 }
@@ -1,5 +1,8 @@
+use crate::format::i18n::{tr, Message};
 use crate::format::print_config::colorize;
-use crate::format::{get_kind_group_name, CrateDetectionStatus, SymbolKind};
+use crate::format::{
+    get_kind_group_name, pad_to_width, CrateDetectionStatus, SymbolKind,
+};
 use crate::scan::unsafe_stats;
 
 use super::total_package_counts::TotalPackageCounts;
@@ -34,14 +37,18 @@ pub fn handle_text_tree_line_extra_deps_group(
 }
 
 pub fn handle_text_tree_line_package(
-    emoji_symbols: &EmojiSymbols,
     handle_package_parameters: &mut HandlePackageParameters,
     package_id: PackageId,
+    package_name_markers: String,
     package_set: &PackageSet,
     table_lines: &mut Vec<String>,
     table_parameters: &TableParameters,
     tree_vines: String,
 ) {
+    let emoji_symbols = EmojiSymbols::new(
+        table_parameters.print_config.charset,
+        &table_parameters.print_config.symbols,
+    );
     let package_is_new = handle_package_parameters
         .visited_package_ids
         .insert(package_id);
@@ -57,7 +64,11 @@ pub fn handle_text_tree_line_package(
         Some(m) => m,
         None => {
             *handle_package_parameters.warning_count += package_is_new as u64;
-            eprintln!("WARNING: No metrics found for package: {}", package_id);
+            eprintln!(
+                "{} {}",
+                tr(table_parameters.print_config.lang, Message::NoMetricsFound),
+                package_id
+            );
             return;
         }
     };
@@ -70,6 +81,16 @@ pub fn handle_text_tree_line_package(
         handle_package_parameters
             .total_package_counts
             .total_unused_counter_block += unsafe_info.unused.clone();
+        handle_package_parameters
+            .total_package_counts
+            .total_generated_counter_block += unsafe_info.generated.clone();
+        for (platform, counter_block) in &unsafe_info.platforms {
+            *handle_package_parameters
+                .total_package_counts
+                .total_platform_counter_blocks
+                .entry(platform.clone())
+                .or_default() += counter_block.clone();
+        }
     }
     let unsafe_found = unsafe_info.used.has_unsafe();
     let crate_forbids_unsafe = unsafe_info.forbids_unsafe;
@@ -77,6 +98,7 @@ pub fn handle_text_tree_line_package(
     let crate_detection_status =
         get_crate_detection_status_and_update_package_counts(
             crate_forbids_unsafe,
+            unsafe_info.forbid_mismatch,
             handle_package_parameters,
             total_inc,
             unsafe_found,
@@ -92,27 +114,44 @@ pub fn handle_text_tree_line_package(
         CrateDetectionStatus::UnsafeDetected => {
             emoji_symbols.emoji(SymbolKind::Rads)
         }
+        CrateDetectionStatus::ForbidMismatch => {
+            emoji_symbols.emoji(SymbolKind::Warning)
+        }
     };
 
-    let package_name = colorize(
-        format!(
-            "{}",
-            table_parameters
-                .print_config
-                .format
-                .display(&package_id, package.manifest().metadata())
-        ),
-        &crate_detection_status,
+    let mut package_name = format!(
+        "{}",
+        table_parameters
+            .print_config
+            .format
+            .display(&package_id, package.manifest().metadata())
     );
+    package_name.push_str(&package_name_markers);
+    if unsafe_info.not_fully_scanned {
+        package_name.push(' ');
+        package_name.push_str(tr(
+            table_parameters.print_config.lang,
+            Message::NotFullyScanned,
+        ));
+    }
+    let package_name = colorize(package_name, &crate_detection_status);
     let unsafe_info = colorize(
-        table_row(&unsafe_info.used, &unsafe_info.unused),
+        table_row(
+            &unsafe_info.used,
+            &unsafe_info.unused,
+            table_parameters.print_config.metrics_filter,
+            table_parameters.print_config.numbers,
+        ),
         &crate_detection_status,
     );
 
-    let shift_chars = unsafe_info.chars().count() + 4;
+    let shift_chars = console::measure_text_width(&unsafe_info) + 4;
 
     let mut line = String::new();
-    line.push_str(format!("{}  {: <2}", unsafe_info, icon).as_str());
+    line.push_str(
+        format!("{}  {}", unsafe_info, pad_to_width(&icon.to_string(), 2))
+            .as_str(),
+    );
 
     // Here comes some special control characters to position the cursor
     // properly for printing the last column containing the tree vines, after
@@ -121,7 +160,7 @@ pub fn handle_text_tree_line_package(
     // count as a single character if using the column formatting provided by
     // Rust. This could be unrelated to Rust and a quirk of this particular
     // symbol or something in the Terminal app on macOS.
-    if emoji_symbols.will_output_emoji() {
+    if emoji_symbols.needs_cursor_shift() {
         line.push('\r'); // Return the cursor to the start of the line.
         line.push_str(format!("\x1B[{}C", shift_chars).as_str()); // Move the cursor to the right so that it points to the icon character.
     }
@@ -129,12 +168,21 @@ pub fn handle_text_tree_line_package(
     table_lines.push(format!("{} {}{}", line, tree_vines, package_name));
 }
 
+/// A crate that declares `#![forbid(unsafe_code)]` but still contains unsafe
+/// code is reported as a mismatch, whether or not anything else was found.
 fn get_crate_detection_status_and_update_package_counts(
     crate_forbids_unsafe: bool,
+    forbid_mismatch: bool,
     handle_package_parameters: &mut HandlePackageParameters,
     total_inc: i32,
     unsafe_found: bool,
 ) -> CrateDetectionStatus {
+    if forbid_mismatch {
+        handle_package_parameters
+            .total_package_counts
+            .forbid_mismatch += total_inc;
+        return CrateDetectionStatus::ForbidMismatch;
+    }
     match (crate_forbids_unsafe, unsafe_found) {
         (true, false) => {
             handle_package_parameters
@@ -184,7 +232,7 @@ mod handle_text_tree_line_tests {
             tree_vines.clone(),
         );
 
-        if expected_kind_group_name.is_some() {
+        if let Some(expected_kind_group_name) = expected_kind_group_name {
             assert_eq!(table_lines.len(), 1);
             assert_eq!(
                 table_lines.first().unwrap().as_str(),
@@ -192,7 +240,7 @@ mod handle_text_tree_line_tests {
                     "{}{}{}",
                     table_row_empty(),
                     tree_vines,
-                    expected_kind_group_name.unwrap(),
+                    expected_kind_group_name,
                 )
             );
         } else {
@@ -261,8 +309,11 @@ mod handle_text_tree_line_tests {
                 none_detected_forbids_unsafe: 0,
                 none_detected_allows_unsafe: 0,
                 unsafe_detected: 0,
+                forbid_mismatch: 0,
                 total_counter_block: Default::default(),
                 total_unused_counter_block: Default::default(),
+                total_generated_counter_block: Default::default(),
+                total_platform_counter_blocks: Default::default(),
             },
             visited_package_ids: &mut Default::default(),
             warning_count: &mut 0,
@@ -271,6 +322,7 @@ mod handle_text_tree_line_tests {
         let crate_detection_status =
             get_crate_detection_status_and_update_package_counts(
                 input_crate_forbids_unsafe,
+                false,
                 &mut handle_package_parameters,
                 input_total_inc,
                 input_unsafe_found,
@@ -299,4 +351,40 @@ mod handle_text_tree_line_tests {
             expected_unsafe_detected
         );
     }
+
+    #[rstest]
+    fn get_crate_detection_status_and_update_package_counts_mismatch_test() {
+        let mut total_package_counts = TotalPackageCounts::new();
+        let mut handle_package_parameters = HandlePackageParameters {
+            total_package_counts: &mut total_package_counts,
+            visited_package_ids: &mut Default::default(),
+            warning_count: &mut 0,
+        };
+
+        let crate_detection_status =
+            get_crate_detection_status_and_update_package_counts(
+                true,
+                true,
+                &mut handle_package_parameters,
+                1,
+                true,
+            );
+
+        assert_eq!(
+            crate_detection_status,
+            CrateDetectionStatus::ForbidMismatch
+        );
+        assert_eq!(
+            handle_package_parameters
+                .total_package_counts
+                .forbid_mismatch,
+            1
+        );
+        assert_eq!(
+            handle_package_parameters
+                .total_package_counts
+                .unsafe_detected,
+            0
+        );
+    }
 }
@@ -1,13 +1,17 @@
 use crate::format::CrateDetectionStatus;
 
 use cargo_geiger_serde::CounterBlock;
+use std::collections::BTreeMap;
 
 pub struct TotalPackageCounts {
     pub none_detected_forbids_unsafe: i32,
     pub none_detected_allows_unsafe: i32,
     pub unsafe_detected: i32,
+    pub forbid_mismatch: i32,
     pub total_counter_block: CounterBlock,
     pub total_unused_counter_block: CounterBlock,
+    pub total_generated_counter_block: CounterBlock,
+    pub total_platform_counter_blocks: BTreeMap<String, CounterBlock>,
 }
 
 impl TotalPackageCounts {
@@ -16,12 +20,18 @@ impl TotalPackageCounts {
             none_detected_forbids_unsafe: 0,
             none_detected_allows_unsafe: 0,
             unsafe_detected: 0,
+            forbid_mismatch: 0,
             total_counter_block: CounterBlock::default(),
             total_unused_counter_block: CounterBlock::default(),
+            total_generated_counter_block: CounterBlock::default(),
+            total_platform_counter_blocks: BTreeMap::new(),
         }
     }
 
     pub fn get_total_detection_status(&self) -> CrateDetectionStatus {
+        if self.forbid_mismatch > 0 {
+            return CrateDetectionStatus::ForbidMismatch;
+        }
         match (
             self.none_detected_forbids_unsafe > 0,
             self.none_detected_allows_unsafe > 0,
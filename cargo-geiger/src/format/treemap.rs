@@ -0,0 +1,149 @@
+use cargo_geiger_serde::{
+    CounterBlock, PathSafetyReport, SafetyReport, TreemapNode,
+};
+
+/// Builds the crate -> file hierarchy of unsafe usage from a report with the
+/// files of each package, sorted by crate and path.
+pub fn treemap_from_report(report: &SafetyReport) -> TreemapNode {
+    let mut entries = report.packages.values().collect::<Vec<_>>();
+    entries.sort_by(|a, b| a.package.id.cmp(&b.package.id));
+    let children = entries
+        .into_iter()
+        .map(|entry| TreemapNode {
+            name: format!(
+                "{} {}",
+                entry.package.id.name, entry.package.id.version
+            ),
+            value: None,
+            used: None,
+            children: entry
+                .unsafety
+                .files
+                .iter()
+                .map(|(path, file_unsafe_info)| TreemapNode {
+                    name: path.display().to_string(),
+                    value: Some(unsafe_count(&file_unsafe_info.counters)),
+                    used: Some(file_unsafe_info.used),
+                    children: vec![],
+                })
+                .collect(),
+        })
+        .collect();
+    TreemapNode {
+        name: String::from("dependencies"),
+        value: None,
+        used: None,
+        children,
+    }
+}
+
+/// Builds the file hierarchy of unsafe usage of a `scan-path` report, the
+/// files are direct children of the scanned directory.
+pub fn treemap_from_path_report(report: &PathSafetyReport) -> TreemapNode {
+    TreemapNode {
+        name: String::from("files"),
+        value: None,
+        used: None,
+        children: report
+            .files
+            .iter()
+            .map(|(path, counter_block)| TreemapNode {
+                name: path.display().to_string(),
+                value: Some(unsafe_count(counter_block)),
+                used: None,
+                children: vec![],
+            })
+            .collect(),
+    }
+}
+
+/// The total of the unsafe functions, expressions, impls, traits and methods.
+pub fn unsafe_count(counter_block: &CounterBlock) -> u64 {
+    counter_block.functions.unsafe_
+        + counter_block.exprs.unsafe_
+        + counter_block.item_impls.unsafe_
+        + counter_block.item_traits.unsafe_
+        + counter_block.methods.unsafe_
+}
+
+/// The sum of the values of all leaves below the node.
+pub fn treemap_total(node: &TreemapNode) -> u64 {
+    node.value.unwrap_or(0)
+        + node.children.iter().map(treemap_total).sum::<u64>()
+}
+
+#[cfg(test)]
+mod treemap_tests {
+    use super::*;
+
+    use cargo_geiger_serde::{
+        Count, FileUnsafeInfo, PackageId, PackageInfo, ReportEntry, Source,
+        UnsafeInfo,
+    };
+    use rstest::*;
+    use semver::Version;
+    use std::path::PathBuf;
+    use url::Url;
+
+    #[rstest]
+    fn treemap_from_report_test() {
+        let mut report = SafetyReport::default();
+        for (name, files) in &[
+            (
+                "b",
+                vec![("src/lib.rs", 3, true), ("src/unused.rs", 1, false)],
+            ),
+            ("a", vec![("src/main.rs", 0, true)]),
+        ] {
+            let package_id = PackageId {
+                name: name.to_string(),
+                version: Version::new(1, 0, 0),
+                source: Source::Registry {
+                    name: String::from("crates.io"),
+                    url: Url::parse(
+                        "https://github.com/rust-lang/crates.io-index",
+                    )
+                    .unwrap(),
+                },
+            };
+            let mut unsafety = UnsafeInfo::default();
+            for (path, unsafe_exprs, used) in files {
+                unsafety.files.insert(
+                    PathBuf::from(path),
+                    FileUnsafeInfo {
+                        used: *used,
+                        counters: CounterBlock {
+                            exprs: Count {
+                                safe: 1,
+                                unsafe_: *unsafe_exprs,
+                            },
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                );
+            }
+            report.packages.insert(
+                package_id.clone(),
+                ReportEntry {
+                    package: PackageInfo::new(package_id),
+                    unsafety,
+                },
+            );
+        }
+
+        let treemap = treemap_from_report(&report);
+
+        assert_eq!(
+            treemap
+                .children
+                .iter()
+                .map(|node| node.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["a 1.0.0", "b 1.0.0"]
+        );
+        assert_eq!(treemap.children[1].children[1].value, Some(1));
+        assert_eq!(treemap.children[1].children[1].used, Some(false));
+        assert_eq!(treemap_total(&treemap), 4);
+    }
+}
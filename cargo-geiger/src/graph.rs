@@ -1,46 +1,123 @@
-use crate::args::Args;
-use crate::cli::get_cfgs;
+use crate::cli::{get_cfgs, ALL_TARGETS};
 
 use cargo::core::dependency::DepKind;
 use cargo::core::package::PackageSet;
-use cargo::core::{Dependency, PackageId, Resolve, Workspace};
+use cargo::core::{
+    Dependency, FeatureValue, Package, PackageId, Resolve, Workspace,
+};
 use cargo::util::interning::InternedString;
 use cargo::util::CargoResult;
 use cargo::Config;
 use cargo_platform::Cfg;
-use petgraph::graph::NodeIndex;
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use petgraph::EdgeDirection;
 use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use url::Url;
 
-#[derive(Debug, PartialEq)]
+/// The dependencies added to the graph on top of the normal ones.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub enum ExtraDeps {
+    /// The build and development dependencies.
     All,
+    /// The build dependencies.
     Build,
+    /// The development dependencies.
     Dev,
+    /// Only the normal dependencies.
+    #[default]
     NoMore,
 }
 
 impl ExtraDeps {
     pub fn allows(&self, dep: DepKind) -> bool {
-        match (self, dep) {
-            (_, DepKind::Normal) => true,
-            (ExtraDeps::All, _) => true,
-            (ExtraDeps::Build, DepKind::Build) => true,
-            (ExtraDeps::Dev, DepKind::Development) => true,
-            _ => false,
-        }
+        matches!(
+            (self, dep),
+            (_, DepKind::Normal)
+                | (ExtraDeps::All, _)
+                | (ExtraDeps::Build, DepKind::Build)
+                | (ExtraDeps::Dev, DepKind::Development)
+        )
     }
 }
 
+/// What `build_graph` adds to the graph, the normal dependencies of the host
+/// platform by default.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct GraphOptions {
+    /// The dependencies added on top of the normal ones.
+    pub extra_deps: ExtraDeps,
+
+    /// The target triple the platform specific dependencies are resolved
+    /// for, the host when `None` and every platform when `"all"`.
+    pub target: Option<String>,
+
+    /// Include the platform specific dependencies of every platform.
+    pub all_targets: bool,
+}
+
 /// Representation of the package dependency graph
 pub struct Graph {
+    /// The packages, with an edge from each package to each of its
+    /// dependencies, weighted by the kind of the dependency.
     pub graph: petgraph::Graph<Node, DepKind>,
+    /// The index of each package in `graph`.
     pub nodes: HashMap<PackageId, NodeIndex>,
+    /// The feature of the dependent that the edge of an optional dependency
+    /// only exists through, for the edges no default feature accounts for.
+    pub edge_features: HashMap<EdgeIndex, String>,
+}
+
+/// The packages reachable from the root package, without following the
+/// dependencies of the root package for which `skip_root_dependency` is true.
+pub fn reachable_package_ids<F>(
+    graph: &Graph,
+    root_package_id: PackageId,
+    skip_root_dependency: F,
+) -> HashSet<PackageId>
+where
+    F: Fn(PackageId) -> bool,
+{
+    let root_index = graph.nodes[&root_package_id];
+    let mut reachable = HashSet::new();
+    reachable.insert(root_package_id);
+    let mut pending = vec![root_index];
+    while let Some(index) = pending.pop() {
+        for dependency_index in graph
+            .graph
+            .neighbors_directed(index, EdgeDirection::Outgoing)
+        {
+            let dependency_id = graph.graph[dependency_index].id;
+            if index == root_index && skip_root_dependency(dependency_id) {
+                continue;
+            }
+            if reachable.insert(dependency_id) {
+                pending.push(dependency_index);
+            }
+        }
+    }
+    reachable
 }
 
 /// Representation of a node within the package dependency graph
+#[non_exhaustive]
 pub struct Node {
     pub id: PackageId,
+
+    /// The features enabled for the package by the resolve.
+    pub features: Vec<String>,
+
+    /// Whether the package is taken from a `[patch]` or `[replace]` of the
+    /// root manifest instead of its original source.
+    pub patched: bool,
+
+    /// Whether the package is taken from a local path by a `[patch]` or
+    /// `[replace]`, e.g. a local fork of a registry package.
+    pub local_override: bool,
+
+    /// Whether the version of the package is yanked from its registry.
+    pub yanked: bool,
     // TODO: Investigate why this was needed before the separation of printing
     // and graph traversal and if it should be added back.
     //pack: &'a Package,
@@ -48,39 +125,43 @@ pub struct Node {
 
 // Almost unmodified compared to the original in cargo-tree, should be fairly
 // simple to move this and the dependency graph structure out to a library.
-/// Function to build a graph of packages dependencies
+/// Function to build a graph of packages dependencies, from one root package
+/// or, for a virtual workspace, from each of its members.
 pub fn build_graph<'a>(
-    args: &Args,
+    options: &GraphOptions,
     config: &Config,
     resolve: &'a Resolve,
     package_set: &'a PackageSet,
-    root_package_id: PackageId,
+    root_package_ids: &[PackageId],
     workspace: &Workspace,
 ) -> CargoResult<Graph> {
-    let config_host = config.load_global_rustc(Some(&workspace))?.host;
-    let (extra_deps, target) = build_graph_prerequisites(args, &config_host)?;
-    let cfgs = get_cfgs(config, &args.target, &workspace)?;
+    let config_host = config.load_global_rustc(Some(workspace))?.host;
+    let (extra_deps, target) =
+        build_graph_prerequisites(options, &config_host)?;
+    let cfgs = get_cfgs(config, &options.target, workspace)?;
 
     let mut graph = Graph {
         graph: petgraph::Graph::new(),
         nodes: HashMap::new(),
+        edge_features: HashMap::new(),
     };
-    let node = Node {
-        id: root_package_id,
-        //pack: packages.get_one(root)?,
-    };
-    graph
-        .nodes
-        .insert(root_package_id, graph.graph.add_node(node));
-
-    let mut pending_packages = vec![root_package_id];
 
     let graph_configuration = GraphConfiguration {
         target,
         cfgs: cfgs.as_deref(),
         extra_deps,
+        patches: workspace.root_patch(),
     };
 
+    for root_package_id in root_package_ids {
+        let node = create_node(resolve, &graph_configuration, *root_package_id);
+        graph
+            .nodes
+            .insert(*root_package_id, graph.graph.add_node(node));
+    }
+
+    let mut pending_packages = root_package_ids.to_vec();
+
     while let Some(package_id) = pending_packages.pop() {
         add_package_dependencies_to_graph(
             resolve,
@@ -92,6 +173,10 @@ pub fn build_graph<'a>(
         )?;
     }
 
+    for node in graph.graph.node_weights_mut() {
+        node.yanked = is_yanked(package_set, node.id);
+    }
+
     Ok(graph)
 }
 
@@ -99,6 +184,7 @@ struct GraphConfiguration<'a> {
     target: Option<&'a str>,
     cfgs: Option<&'a [Cfg]>,
     extra_deps: ExtraDeps,
+    patches: &'a HashMap<Url, Vec<Dependency>>,
 }
 
 fn add_graph_node_if_not_present_and_edge(
@@ -107,21 +193,24 @@ fn add_graph_node_if_not_present_and_edge(
     graph: &mut Graph,
     index: NodeIndex,
     pending_packages: &mut Vec<PackageId>,
-) {
+    resolve: &Resolve,
+    graph_configuration: &GraphConfiguration,
+) -> EdgeIndex {
     let dependency_index = match graph.nodes.entry(dependency_package_id) {
         Entry::Occupied(e) => *e.get(),
         Entry::Vacant(e) => {
             pending_packages.push(dependency_package_id);
-            let node = Node {
-                id: dependency_package_id,
-                //pack: packages.get_one(dep_id)?,
-            };
+            let node = create_node(
+                resolve,
+                graph_configuration,
+                dependency_package_id,
+            );
             *e.insert(graph.graph.add_node(node))
         }
     };
     graph
         .graph
-        .add_edge(index, dependency_index, dependency.kind());
+        .add_edge(index, dependency_index, dependency.kind())
 }
 
 #[doc(hidden)]
@@ -163,46 +252,180 @@ fn add_package_dependencies_to_graph<'a>(
             };
 
         for dependency in dependency_iterator {
-            add_graph_node_if_not_present_and_edge(
+            let edge_index = add_graph_node_if_not_present_and_edge(
                 dependency,
                 dependency_package_id,
                 graph,
                 index,
                 pending_packages,
+                resolve,
+                graph_configuration,
             );
+            if let Some(feature) =
+                enabling_feature(resolve, package, dependency)
+            {
+                graph.edge_features.insert(edge_index, feature);
+            }
         }
     }
 
     Ok(())
 }
 
+fn create_node(
+    resolve: &Resolve,
+    graph_configuration: &GraphConfiguration,
+    package_id: PackageId,
+) -> Node {
+    let patched = is_patched(resolve, graph_configuration.patches, package_id);
+    Node {
+        id: package_id,
+        features: resolved_features(resolve, package_id),
+        patched,
+        local_override: patched && package_id.source_id().is_path(),
+        yanked: false,
+    }
+}
+
+/// Whether the version of a registry package is yanked, as told by the index
+/// fetched for the resolve. A source that fails to tell counts as not
+/// yanked, since the scan does not depend on it.
+fn is_yanked(package_set: &PackageSet, package_id: PackageId) -> bool {
+    if !package_id.source_id().is_registry() {
+        return false;
+    }
+    let mut sources = package_set.sources_mut();
+    sources
+        .get_mut(package_id.source_id())
+        .is_some_and(|source| source.is_yanked(package_id).unwrap_or(false))
+}
+
+/// The feature of the package that enables the optional dependency, when no
+/// default feature of the package does. See `non_default_enabling_feature`.
+fn enabling_feature(
+    resolve: &Resolve,
+    package: &Package,
+    dependency: &Dependency,
+) -> Option<String> {
+    if !dependency.is_optional() {
+        return None;
+    }
+    non_default_enabling_feature(
+        package.summary().features(),
+        &resolved_features(resolve, package.package_id()),
+        dependency.name_in_toml().as_str(),
+    )
+}
+
+/// The first enabled feature, by name, that lists the optional dependency and
+/// is not turned on by the `default` feature. When only a dependent of the
+/// package enables the dependency, that is its implicit feature, named after
+/// the dependency. Returns `None` when the default features enable it.
+fn non_default_enabling_feature(
+    feature_map: &BTreeMap<InternedString, Vec<FeatureValue>>,
+    enabled_features: &[String],
+    dependency_name: &str,
+) -> Option<String> {
+    let mut default_enabled = HashSet::new();
+    if enabled_features.iter().any(|feature| feature == "default") {
+        let mut pending = vec![String::from("default")];
+        while let Some(feature) = pending.pop() {
+            if !default_enabled.insert(feature.clone()) {
+                continue;
+            }
+            let feature_values = feature_map
+                .get(&InternedString::new(&feature))
+                .into_iter()
+                .flatten();
+            for feature_value in feature_values {
+                match feature_value {
+                    FeatureValue::Feature(name) => {
+                        pending.push(name.to_string())
+                    }
+                    FeatureValue::Crate(name)
+                    | FeatureValue::CrateFeature(name, _) => {
+                        default_enabled.insert(name.to_string());
+                    }
+                }
+            }
+        }
+    }
+    if default_enabled.contains(dependency_name) {
+        return None;
+    }
+
+    let lists_dependency = |feature: &String| {
+        let feature_values = feature_map.get(&InternedString::new(feature));
+        feature_values.into_iter().flatten().any(|feature_value| {
+            match feature_value {
+                FeatureValue::Feature(_) => false,
+                FeatureValue::Crate(name)
+                | FeatureValue::CrateFeature(name, _) => {
+                    name.as_str() == dependency_name
+                }
+            }
+        })
+    };
+    enabled_features
+        .iter()
+        .filter(|feature| {
+            feature.as_str() != dependency_name
+                && !default_enabled.contains(*feature)
+                && lists_dependency(feature)
+        })
+        .min()
+        .or_else(|| {
+            enabled_features
+                .iter()
+                .find(|feature| feature.as_str() == dependency_name)
+        })
+        .cloned()
+}
+
+fn resolved_features(resolve: &Resolve, package_id: PackageId) -> Vec<String> {
+    resolve
+        .features(package_id)
+        .iter()
+        .map(|feature| feature.to_string())
+        .collect()
+}
+
+/// The replacements of `[replace]` are known to the resolve, the packages of
+/// a `[patch]` are matched against the patch dependencies, as they take the
+/// place of the original package without a trace in the resolve.
+fn is_patched(
+    resolve: &Resolve,
+    patches: &HashMap<Url, Vec<Dependency>>,
+    package_id: PackageId,
+) -> bool {
+    resolve
+        .replacements()
+        .values()
+        .any(|replacement| *replacement == package_id)
+        || patches
+            .values()
+            .flatten()
+            .any(|patch| patch.matches_id(package_id))
+}
+
 fn build_graph_prerequisites<'a>(
-    args: &'a Args,
+    options: &'a GraphOptions,
     config_host: &'a InternedString,
 ) -> CargoResult<(ExtraDeps, Option<&'a str>)> {
-    let extra_deps = if args.all_deps {
-        ExtraDeps::All
-    } else if args.build_deps {
-        ExtraDeps::Build
-    } else if args.dev_deps {
-        ExtraDeps::Dev
-    } else {
-        ExtraDeps::NoMore
-    };
-
-    let target = if args.all_targets {
+    let target = if options.all_targets
+        || options.target.as_deref() == Some(ALL_TARGETS)
+    {
         None
     } else {
-        Some(args.target.as_deref().unwrap_or(&config_host))
+        Some(options.target.as_deref().unwrap_or(config_host))
     };
 
-    Ok((extra_deps, target))
+    Ok((options.extra_deps, target))
 }
 
 #[cfg(test)]
 mod graph_tests {
     use super::*;
-    use crate::format::Charset;
     use rstest::*;
 
     #[rstest(
@@ -228,44 +451,13 @@ mod graph_tests {
         assert_eq!(input_extra_deps.allows(input_dep_kind), expected_allows);
     }
 
-    #[rstest(
-        input_all_deps,
-        input_build_deps,
-        input_dev_deps,
-        expected_extra_deps,
-        case(true, false, false, ExtraDeps::All),
-        case(false, true, false, ExtraDeps::Build),
-        case(false, false, true, ExtraDeps::Dev),
-        case(false, false, false, ExtraDeps::NoMore)
-    )]
-    fn build_graph_prerequisites_extra_deps_test(
-        input_all_deps: bool,
-        input_build_deps: bool,
-        input_dev_deps: bool,
-        expected_extra_deps: ExtraDeps,
-    ) {
-        let mut args = create_args();
-        args.all_deps = input_all_deps;
-        args.build_deps = input_build_deps;
-        args.dev_deps = input_dev_deps;
-
-        let config_host = InternedString::new("config_host");
-
-        let result = build_graph_prerequisites(&args, &config_host);
-
-        assert!(result.is_ok());
-
-        let (extra_deps, _) = result.unwrap();
-
-        assert_eq!(extra_deps, expected_extra_deps);
-    }
-
     #[rstest(
         input_all_targets,
         input_target,
         expected_target,
         case(true, None, None),
         case(false, None, Some("default_config_host")),
+        case(false, Some(String::from("all")), None),
         case(
             false,
             Some(String::from("provided_config_host")),
@@ -277,14 +469,15 @@ mod graph_tests {
         input_target: Option<String>,
         expected_target: Option<&str>,
     ) {
-        let mut args = create_args();
-
-        args.all_targets = input_all_targets;
-        args.target = input_target;
+        let options = GraphOptions {
+            all_targets: input_all_targets,
+            target: input_target,
+            ..GraphOptions::default()
+        };
 
         let config_host = InternedString::new("default_config_host");
 
-        let result = build_graph_prerequisites(&args, &config_host);
+        let result = build_graph_prerequisites(&options, &config_host);
 
         assert!(result.is_ok());
 
@@ -293,36 +486,69 @@ mod graph_tests {
         assert_eq!(target, expected_target);
     }
 
-    fn create_args() -> Args {
-        Args{
-            all: false,
-            all_deps: false,
-            all_features: false,
-            all_targets: false,
-            build_deps: false,
-            charset: Charset::Ascii,
-            color: None,
-            dev_deps: false,
-            features: None,
-            forbid_only: false,
-            format: "".to_string(),
-            frozen: false,
-            help: false,
-            include_tests: false,
-            invert: false,
-            locked: false,
-            manifest_path: None,
-            no_default_features: false,
-            no_indent: false,
-            offline: false,
-            package: None,
-            prefix_depth: false,
-            quiet: false,
-            target: None,
-            unstable_flags: vec![],
-            verbose: 0,
-            version: false,
-            output_format: None
-        }
+    #[rstest(
+        input_enabled_features,
+        input_dependency_name,
+        expected_feature,
+        case(
+            vec!["default", "std", "tls", "native-tls"],
+            "native-tls",
+            Some("tls")
+        ),
+        case(
+            vec!["full", "tls", "native-tls", "flate2"],
+            "flate2",
+            Some("full")
+        ),
+        case(
+            vec!["full", "tls", "native-tls", "flate2"],
+            "native-tls",
+            Some("tls")
+        ),
+        case(vec!["default", "std", "serde"], "serde", None),
+        case(vec!["serde"], "serde", Some("serde")),
+        case(vec!["default", "std", "log"], "log", Some("log"))
+    )]
+    fn non_default_enabling_feature_test(
+        input_enabled_features: Vec<&str>,
+        input_dependency_name: &str,
+        expected_feature: Option<&str>,
+    ) {
+        let mut feature_map = BTreeMap::new();
+        feature_map.insert(
+            InternedString::new("default"),
+            vec![
+                FeatureValue::Feature(InternedString::new("std")),
+                FeatureValue::CrateFeature(
+                    InternedString::new("serde"),
+                    InternedString::new("derive"),
+                ),
+            ],
+        );
+        feature_map.insert(InternedString::new("std"), vec![]);
+        feature_map.insert(
+            InternedString::new("tls"),
+            vec![FeatureValue::Crate(InternedString::new("native-tls"))],
+        );
+        feature_map.insert(
+            InternedString::new("full"),
+            vec![
+                FeatureValue::Feature(InternedString::new("tls")),
+                FeatureValue::Crate(InternedString::new("flate2")),
+            ],
+        );
+        let enabled_features = input_enabled_features
+            .into_iter()
+            .map(String::from)
+            .collect::<Vec<String>>();
+
+        assert_eq!(
+            non_default_enabling_feature(
+                &feature_map,
+                &enabled_features,
+                input_dependency_name
+            ),
+            expected_feature.map(String::from)
+        );
     }
 }
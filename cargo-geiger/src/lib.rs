@@ -0,0 +1,50 @@
+//! The library parts of the `cargo-geiger` cargo plugin, for other cargo
+//! plugins to reuse:
+//!
+//!  - the dependency graph built from the cargo resolve by
+//!    `graph::build_graph`, with `graph::GraphOptions`,
+//!  - its traversal into the lines of the tree output by
+//!    `tree::traversal::walk_dependency_tree`, with `tree::TreeOptions`,
+//!  - the scan of the `.rs` files of the packages by `scan_packages`, with
+//!    `ScanOptions`, passing its progress to a `ScanObserver`.
+//!
+//! # Stability
+//!
+//! The documented API follows semver: a breaking change to it is only made
+//! in a new major version, or a new minor version while the major version is
+//! 0. The option and result structs are `#[non_exhaustive]` so that fields
+//! can be added in a minor version, build the options from their `Default`.
+//! Types of the `cargo`, `petgraph`, `geiger` and `cargo-geiger-serde`
+//! crates appear in the API, a breaking upgrade of one of them is a breaking
+//! change too.
+//!
+//! The hidden modules, `args`, `cli`, `explain`, `format` and `scan`, are
+//! only public for the `cargo-geiger` executable and are not covered.
+
+#![forbid(unsafe_code)]
+#![forbid(warnings)]
+
+extern crate cargo;
+extern crate colored;
+extern crate petgraph;
+extern crate strum;
+extern crate strum_macros;
+
+#[doc(hidden)]
+pub mod args;
+#[doc(hidden)]
+pub mod cli;
+#[doc(hidden)]
+pub mod explain;
+#[doc(hidden)]
+pub mod format;
+pub mod graph;
+mod rs_file;
+#[doc(hidden)]
+pub mod scan;
+pub mod tree;
+
+pub use scan::{
+    scan_packages, FileScan, PackageScan, PackagesScan, ScanEvent,
+    ScanObserver, ScanOptions,
+};
@@ -6,29 +6,25 @@
 
 extern crate cargo;
 extern crate colored;
-extern crate petgraph;
-extern crate strum;
-extern crate strum_macros;
 
-mod args;
-mod cli;
-mod format;
-mod graph;
-mod rs_file;
-mod scan;
-mod tree;
-
-use crate::args::{Args, HELP};
-use crate::cli::{get_registry, get_workspace, resolve};
-use crate::graph::build_graph;
-use crate::scan::scan;
+use cargo_geiger::args::{Args, Subcommand, HELP};
+use cargo_geiger::cli::{
+    get_config_target, get_features, get_registry, get_root_package_ids,
+    get_workspace, resolve, select_toolchain,
+};
+use cargo_geiger::explain::run_explain;
+use cargo_geiger::graph::build_graph;
+use cargo_geiger::scan::{
+    run_cache_command, run_check_update, run_fleet, run_manifests, run_render,
+    run_scan_path, run_serve, scan,
+};
 
 use cargo::core::shell::{ColorChoice, Shell};
 use cargo::{CliResult, Config};
 
 const VERSION: Option<&'static str> = option_env!("CARGO_PKG_VERSION");
 
-fn real_main(args: &Args, config: &mut Config) -> CliResult {
+fn real_main(args: &mut Args, config: &mut Config) -> CliResult {
     if args.version {
         println!("cargo-geiger {}", VERSION.unwrap_or("unknown version"));
         return Ok(());
@@ -50,6 +46,9 @@ fn real_main(args: &Args, config: &mut Config) -> CliResult {
         &args.unstable_flags,
         &[], // Some cargo API change, TODO: Look closer at this later.
     )?;
+    if args.target.is_none() {
+        args.target = get_config_target(config)?;
+    }
 
     match config.shell().color_choice() {
         ColorChoice::Always => colored::control::set_override(true),
@@ -57,20 +56,34 @@ fn real_main(args: &Args, config: &mut Config) -> CliResult {
         ColorChoice::CargoAuto => {}
     }
 
-    let workspace = get_workspace(config, args.manifest_path.clone())?;
-    let package = workspace.current()?;
-    let mut registry = get_registry(config, &package)?;
-    let features = args
-        .features
-        .as_ref()
-        .cloned()
-        .unwrap_or_else(String::new)
-        .split(' ')
-        .map(str::to_owned)
-        .collect::<Vec<String>>();
+    match &args.subcommand {
+        Some(Subcommand::Cache(cache_command)) => {
+            return run_cache_command(*cache_command, args, config);
+        }
+        Some(Subcommand::CheckUpdate) => return run_check_update(args, config),
+        Some(Subcommand::Serve) => return run_serve(args, config),
+        Some(Subcommand::Explain(topic)) => {
+            return run_explain(topic.as_deref());
+        }
+        Some(Subcommand::Fleet(dir)) => return run_fleet(dir, args, config),
+        Some(Subcommand::Render(report)) => {
+            return run_render(report, args, config)
+        }
+        Some(Subcommand::ScanPath(dir)) => return run_scan_path(dir, args),
+        Some(Subcommand::Verify(_)) | None => {}
+    }
+    if args.manifest_paths.len() > 1 {
+        return run_manifests(&args.manifest_paths, args, config);
+    }
+
+    let workspace =
+        get_workspace(config, args.manifest_paths.first().cloned())?;
+    let member_ids = get_root_package_ids(&workspace)?;
+    let mut registry = get_registry(config, &member_ids)?;
+    let features = get_features(args);
 
     let (package_set, resolve) = resolve(
-        package.package_id(),
+        &member_ids,
         &mut registry,
         &workspace,
         &features,
@@ -80,17 +93,20 @@ fn real_main(args: &Args, config: &mut Config) -> CliResult {
     let package_ids = package_set.package_ids().collect::<Vec<_>>();
     let package_set = registry.get(&package_ids)?;
 
-    let root_package_id = match args.package {
-        Some(ref pkg) => resolve.query(pkg)?,
-        None => package.package_id(),
+    let root_package_ids = match args.package {
+        // An ambiguous spec lists the specs of the matching versions.
+        Some(ref pkg) => vec![resolve.query(pkg).map_err(|e| {
+            e.context(format!("could not root the tree at `{}`", pkg))
+        })?],
+        None => member_ids,
     };
 
     let graph = build_graph(
-        args,
+        &args.graph_options(),
         config,
         &resolve,
         &package_set,
-        package.package_id(),
+        &root_package_ids,
         &workspace,
     )?;
 
@@ -99,13 +115,20 @@ fn real_main(args: &Args, config: &mut Config) -> CliResult {
         config,
         &graph,
         &package_set,
-        root_package_id,
+        &root_package_ids,
         &workspace,
     )
 }
 
 fn main() {
     env_logger::init();
+    let mut args = Args::parse_args(pico_args::Arguments::from_env()).unwrap();
+    // The toolchain is selected through the environment, before the cargo
+    // configuration reads it.
+    let mut shell = Shell::new();
+    if let Err(e) = select_toolchain(&mut args, &mut shell) {
+        cargo::exit_with_error(e.into(), &mut shell)
+    }
     let mut config = match Config::default() {
         Ok(cfg) => cfg,
         Err(e) => {
@@ -113,8 +136,7 @@ fn main() {
             cargo::exit_with_error(e.into(), &mut shell)
         }
     };
-    let args = Args::parse_args(pico_args::Arguments::from_env()).unwrap();
-    if let Err(e) = real_main(&args, &mut config) {
+    if let Err(e) = real_main(&mut args, &mut config) {
         let mut shell = Shell::new();
         cargo::exit_with_error(e, &mut shell)
     }
@@ -4,14 +4,16 @@ use custom_executor::{CustomExecutor, CustomExecutorInnerContext};
 
 use cargo::core::compiler::Executor;
 use cargo::core::manifest::TargetKind;
-use cargo::core::Workspace;
+use cargo::core::{PackageIdSpec, Workspace};
 use cargo::ops;
 use cargo::ops::{CleanOptions, CompileOptions};
 use cargo::util::{interning::InternedString, paths, CargoResult};
 use cargo::Config;
+use cargo_geiger_serde::ScanWarning;
 use geiger::RsFileMetrics;
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::error::Error;
+use std::ffi::OsStr;
 use std::fmt;
 use std::io;
 use std::path::{Path, PathBuf};
@@ -21,7 +23,7 @@ use walkdir::{DirEntry, WalkDir};
 /// Provides information needed to scan for crate root
 /// `#![forbid(unsafe_code)]`.
 /// The wrapped PathBufs are canonicalized.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum RsFile {
     /// Library entry point source file, usually src/lib.rs
     LibRoot(PathBuf),
@@ -36,6 +38,17 @@ pub enum RsFile {
     Other(PathBuf),
 }
 
+impl RsFile {
+    pub fn path(&self) -> &PathBuf {
+        match self {
+            RsFile::LibRoot(path)
+            | RsFile::BinRoot(path)
+            | RsFile::CustomBuildRoot(path)
+            | RsFile::Other(path) => path,
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct RsFileMetricsWrapper {
     /// The information returned by the `geiger` crate for a `.rs` file.
@@ -46,36 +59,72 @@ pub struct RsFileMetricsWrapper {
     /// and cannot know if a file is a crate entry point or not, so we add this
     /// information here.
     pub is_crate_entry_point: bool,
+
+    /// Generated files, e.g. bindgen output in the `OUT_DIR` of a build
+    /// script, are reported separately so that policies can weigh them
+    /// differently. This is not cached, the globs can change between runs.
+    pub is_generated: bool,
 }
 
+/// The errors of resolving the `.rs` files used by the build, with the path
+/// and package context of the failure. The underlying error is kept as the
+/// source, so that the whole chain is reported.
 #[derive(Debug)]
 pub enum RsResolveError {
     /// This should not happen unless incorrect assumptions have been made in
     /// cargo-geiger about how the cargo API works.
     ArcUnwrap(),
 
-    /// Would like cargo::Error here, but it's private, why?
-    /// This is still way better than a panic though.
-    Cargo(String),
+    /// Cleaning or building the workspace failed.
+    Cargo(anyhow::Error),
 
     /// Failed to parse a .dep file.
-    DepParse(String, PathBuf),
+    DepParse {
+        dep_file: PathBuf,
+        source: anyhow::Error,
+    },
 
     /// Failed to get the inner context out of the mutex.
     InnerContextMutex(String),
 
-    /// Like io::Error but with the related path.
-    Io(io::Error, PathBuf),
-
     Walkdir(walkdir::Error),
 }
 
-impl Error for RsResolveError {}
+impl Error for RsResolveError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RsResolveError::ArcUnwrap()
+            | RsResolveError::InnerContextMutex(_) => None,
+            RsResolveError::Cargo(source)
+            | RsResolveError::DepParse { source, .. } => Some(&**source),
+            RsResolveError::Walkdir(source) => Some(source),
+        }
+    }
+}
 
-/// Forward Display to Debug.
 impl fmt::Display for RsResolveError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::Debug::fmt(self, f)
+        match self {
+            RsResolveError::ArcUnwrap() => {
+                write!(f, "the build executor is still in use")
+            }
+            RsResolveError::Cargo(_) => {
+                write!(f, "failed to build the workspace")
+            }
+            RsResolveError::DepParse { dep_file, .. } => write!(
+                f,
+                "failed to parse the dep-info file {}",
+                dep_file.display()
+            ),
+            RsResolveError::InnerContextMutex(message) => write!(
+                f,
+                "failed to get the files used by the build: {}",
+                message
+            ),
+            RsResolveError::Walkdir(_) => {
+                write!(f, "failed to walk the build output directory")
+            }
+        }
     }
 }
 
@@ -112,19 +161,188 @@ pub fn is_file_with_ext(entry: &DirEntry, file_ext: &str) -> bool {
     ext.to_string_lossy() == file_ext
 }
 
+/// Canonicalizes a path like `std::fs::canonicalize`, but without the `\\?\`
+/// verbatim prefix that Windows adds, whenever the path is also valid without
+/// it. The walked files, the files passed to rustc and the paths listed in the
+/// `.d` dep-info files are only matched up when they share the same form.
+pub fn canonicalize(path: &Path) -> io::Result<PathBuf> {
+    let canonical_path = path.canonicalize()?;
+    if cfg!(windows) {
+        if let Some(simplified) =
+            canonical_path.to_str().and_then(strip_verbatim_prefix)
+        {
+            return Ok(PathBuf::from(simplified));
+        }
+    }
+    Ok(canonical_path)
+}
+
+/// The shortest path for a Windows `\\?\C:\...` or `\\?\UNC\server\share\...`
+/// path, or `None` if the path relies on the verbatim prefix, because it is
+/// too long for the legacy APIs or has components that would be normalized
+/// away without it.
+fn strip_verbatim_prefix(path: &str) -> Option<String> {
+    const MAX_PATH: usize = 260;
+    let rest = path.strip_prefix(r"\\?\")?;
+    let (simplified, components) = match rest.strip_prefix(r"UNC\") {
+        Some(unc) => (format!(r"\\{}", unc), unc),
+        None => {
+            let bytes = rest.as_bytes();
+            let is_drive = bytes.len() >= 3
+                && bytes[0].is_ascii_alphabetic()
+                && bytes[1] == b':'
+                && bytes[2] == b'\\';
+            if !is_drive {
+                return None;
+            }
+            (rest.to_string(), &rest[3..])
+        }
+    };
+    if simplified.len() >= MAX_PATH
+        || !components
+            .split('\\')
+            .filter(|component| !component.is_empty())
+            .all(is_valid_legacy_component)
+    {
+        return None;
+    }
+    Some(simplified)
+}
+
+/// Path components that the legacy Windows APIs would reinterpret, such as
+/// `..`, trailing dots or spaces and reserved device names, need the verbatim
+/// prefix.
+fn is_valid_legacy_component(component: &str) -> bool {
+    const RESERVED_NAMES: [&str; 22] = [
+        "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5",
+        "COM6", "COM7", "COM8", "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5",
+        "LPT6", "LPT7", "LPT8", "LPT9",
+    ];
+    if component == "." || component == ".." {
+        return false;
+    }
+    if component.ends_with('.') || component.ends_with(' ') {
+        return false;
+    }
+    if component
+        .chars()
+        .any(|c| c < ' ' || "<>:\"/|?*".contains(c))
+    {
+        return false;
+    }
+    let base_name = component.split('.').next().unwrap_or_default().trim_end();
+    !RESERVED_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(base_name))
+}
+
+/// Whether the file system holding `dir` ignores the case of file names, as
+/// is the default on macOS and Windows. This is probed by looking up the
+/// nearest directory name with letters in it in the opposite case.
+pub fn is_case_insensitive_dir(dir: &Path) -> bool {
+    for ancestor in dir.ancestors() {
+        let file_name = match ancestor.file_name().and_then(OsStr::to_str) {
+            Some(file_name) => file_name,
+            None => continue,
+        };
+        let swapped_file_name = swap_case(file_name);
+        if swapped_file_name == file_name {
+            continue;
+        }
+        return ancestor.with_file_name(swapped_file_name).exists();
+    }
+    false
+}
+
+fn swap_case(s: &str) -> String {
+    s.chars()
+        .map(|c| {
+            if c.is_uppercase() {
+                c.to_lowercase().collect::<String>()
+            } else {
+                c.to_uppercase().collect::<String>()
+            }
+        })
+        .collect()
+}
+
+/// Merges the used files that only differ in case, e.g. when the same file
+/// was passed to rustc and listed in a `.d` dep-info file with different
+/// casing. The casing of the scanned files is preferred, so that the used
+/// files are matched with their metrics.
+pub fn dedup_paths_ignoring_case<'a>(
+    paths: HashSet<PathBuf>,
+    scanned_paths: impl Iterator<Item = &'a PathBuf>,
+) -> HashSet<PathBuf> {
+    let scanned_paths = scanned_paths
+        .map(|path| (case_folded(path), path))
+        .collect::<HashMap<String, &PathBuf>>();
+    let mut folded_to_path = HashMap::<String, PathBuf>::new();
+    for path in paths {
+        let folded = case_folded(&path);
+        let path = match scanned_paths.get(&folded) {
+            Some(scanned_path) => (*scanned_path).clone(),
+            None => path,
+        };
+        // Keep the smallest of the variants for a stable result.
+        match folded_to_path.get(&folded) {
+            Some(kept_path) if *kept_path <= path => {}
+            _ => {
+                folded_to_path.insert(folded, path);
+            }
+        }
+    }
+    folded_to_path.into_values().collect()
+}
+
+fn case_folded(path: &Path) -> String {
+    path.to_string_lossy().to_lowercase()
+}
+
 /// Trigger a `cargo clean` + `cargo check` and listen to the cargo/rustc
 /// communication to figure out which source files were used by the build.
+///
+/// With `clean_members` only the artifacts of the workspace members are
+/// cleaned. The dependencies are then only rebuilt when they are out of date,
+/// the `.d` dep-info files of fresh dependencies are still found next to the
+/// rebuilt workspace members, along with the stale ones of earlier builds.
+///
+/// The paths listed in the `.d` dep-info files that can not be canonicalized
+/// are returned as warnings next to the used files.
+///
+/// With `no_clean` the clean is skipped and only the out of date units are
+/// rebuilt, the `.d` dep-info files of the fresh units are read from the
+/// target directory instead. When they are missing or stale, see
+/// `read_dep_info_without_clean`, the clean build is done after all.
 pub fn resolve_rs_file_deps(
     compile_options: &CompileOptions,
     workspace: &Workspace,
-) -> Result<HashSet<PathBuf>, RsResolveError> {
+    clean_members: bool,
+    no_clean: bool,
+    target: Option<&str>,
+) -> Result<(HashSet<PathBuf>, Vec<ScanWarning>), RsResolveError> {
     let config = workspace.config();
+    if no_clean {
+        if let Some(rs_files_used) = resolve_rs_file_deps_without_clean(
+            compile_options,
+            workspace,
+            target,
+        )? {
+            return Ok((rs_files_used, vec![]));
+        }
+        config
+            .shell()
+            .status(
+                "Cleaning",
+                "the dep-info files are missing or stale, falling back to a \
+                 clean build",
+            )
+            .map_err(RsResolveError::Cargo)?;
+    }
     // Need to run a cargo clean to identify all new .d deps files.
-    // TODO: Figure out how this can be avoided to improve performance, clean
-    // Rust builds are __slow__.
     let clean_options = CleanOptions {
-        config: &config,
-        spec: vec![],
+        config,
+        spec: clean_spec(workspace, clean_members),
         targets: vec![],
         profile_specified: false,
         // A temporary hack to get cargo 0.43 to build, TODO: look closer at the updated cargo API
@@ -133,8 +351,7 @@ pub fn resolve_rs_file_deps(
         doc: false,
     };
 
-    ops::clean(workspace, &clean_options)
-        .map_err(|e| RsResolveError::Cargo(e.to_string()))?;
+    ops::clean(workspace, &clean_options).map_err(RsResolveError::Cargo)?;
 
     let inner_arc = Arc::new(Mutex::new(CustomExecutorInnerContext::default()));
     {
@@ -143,17 +360,19 @@ pub fn resolve_rs_file_deps(
             config,
             inner_arc.clone(),
             workspace,
+            true,
         )?;
     }
 
     let workspace_root = workspace.root().to_path_buf();
     let inner_mutex =
         Arc::try_unwrap(inner_arc).map_err(|_| RsResolveError::ArcUnwrap())?;
-    let (rs_files, out_dir_args) = {
+    let (rs_files, out_dir_args, dep_info_cwds) = {
         let ctx = inner_mutex.into_inner()?;
-        (ctx.rs_file_args, ctx.out_dir_args)
+        (ctx.rs_file_args, ctx.out_dir_args, ctx.dep_info_cwds)
     };
     let mut path_buf_hash_set = HashSet::<PathBuf>::new();
+    let mut warnings = Vec::new();
     for out_dir in out_dir_args {
         // TODO: Figure out if the `.d` dep files are used by one or more rustc
         // calls. It could be useful to know which `.d` dep files belong to
@@ -162,59 +381,247 @@ pub fn resolve_rs_file_deps(
         add_dir_entries_to_path_buf_hash_set(
             out_dir,
             &mut path_buf_hash_set,
-            workspace_root.clone(),
+            &mut warnings,
+            &workspace_root,
+            &dep_info_cwds,
         )?;
     }
     for path_buf in rs_files {
         // rs_files must already be canonicalized
         path_buf_hash_set.insert(path_buf);
     }
+    warnings.sort();
+
+    Ok((path_buf_hash_set, warnings))
+}
+
+/// Builds without cleaning first. The `.d` dep-info files of the rebuilt
+/// units are written by this build, the ones of the fresh units are left in
+/// the `deps` and `build` directories of the dev profile by earlier builds.
+fn resolve_rs_file_deps_without_clean(
+    compile_options: &CompileOptions,
+    workspace: &Workspace,
+    target: Option<&str>,
+) -> Result<Option<HashSet<PathBuf>>, RsResolveError> {
+    let config = workspace.config();
+    let inner_arc = Arc::new(Mutex::new(CustomExecutorInnerContext::default()));
+    compile_with_exec(
+        compile_options,
+        config,
+        inner_arc.clone(),
+        workspace,
+        false,
+    )?;
+
+    let workspace_root = workspace.root().to_path_buf();
+    let inner_mutex =
+        Arc::try_unwrap(inner_arc).map_err(|_| RsResolveError::ArcUnwrap())?;
+    let ctx = inner_mutex.into_inner()?;
+    let target_dir = workspace.target_dir().into_path_unlocked();
+    let rs_file_args = ctx.rs_file_args;
+    let mut dep_dirs = ctx.out_dir_args;
+    for profile_dir in profile_dirs(&target_dir, target) {
+        dep_dirs.insert(profile_dir.join("deps"));
+        dep_dirs.insert(profile_dir.join("build"));
+    }
+    let rs_files_used = read_dep_info_without_clean(
+        &dep_dirs,
+        &workspace_root,
+        &ctx.dep_info_cwds,
+    )?;
+    Ok(rs_files_used.map(|mut rs_files_used| {
+        // rs_files must already be canonicalized
+        rs_files_used.extend(rs_file_args);
+        rs_files_used
+    }))
+}
+
+/// The dev profile directories of the host and, when cross compiling, of the
+/// target. The directory of a custom target is named after its spec file.
+fn profile_dirs(target_dir: &Path, target: Option<&str>) -> Vec<PathBuf> {
+    let mut profile_dirs = vec![target_dir.join("debug")];
+    if let Some(target) = target {
+        let target_path = Path::new(target);
+        let target_name = match target_path.extension() {
+            Some(extension) if extension == "json" => target_path
+                .file_stem()
+                .unwrap_or_else(|| OsStr::new(target)),
+            _ => OsStr::new(target),
+        };
+        profile_dirs.push(target_dir.join(target_name).join("debug"));
+    }
+    profile_dirs
+}
 
-    Ok(path_buf_hash_set)
+/// The paths listed in the `.d` dep-info files in `dep_dirs`, or `None` when
+/// the dep-info files can not be trusted without a clean build:
+///
+/// - No dep-info files are found, e.g. in a new target directory.
+/// - A listed path no longer exists.
+/// - Two dep-info files in one directory are for the same crate root, e.g.
+///   left behind by a build with other features. It can not be told which of
+///   them belongs to this build. Dep-info files of the same crate for other
+///   versions list other crate roots, and are only read in vain.
+fn read_dep_info_without_clean(
+    dep_dirs: &HashSet<PathBuf>,
+    workspace_root: &Path,
+    dep_info_cwds: &HashMap<PathBuf, PathBuf>,
+) -> Result<Option<HashSet<PathBuf>>, RsResolveError> {
+    // The out dirs of build scripts are below the `build` directories.
+    let mut dep_files = BTreeSet::new();
+    for dep_dir in dep_dirs.iter().filter(|dep_dir| dep_dir.is_dir()) {
+        for entry in WalkDir::new(dep_dir) {
+            let entry = entry.map_err(RsResolveError::Walkdir)?;
+            if is_file_with_ext(&entry, "d") {
+                dep_files.insert(entry.into_path());
+            }
+        }
+    }
+    let mut crate_roots = HashSet::new();
+    let mut path_buf_hash_set = HashSet::new();
+    for dep_file in dep_files {
+        let dependencies = parse_rustc_dep_info(&dep_file).map_err(|e| {
+            RsResolveError::DepParse {
+                dep_file: dep_file.clone(),
+                source: e,
+            }
+        })?;
+        let base_dir = dep_info_cwds
+            .get(&dep_file)
+            .map_or(workspace_root, PathBuf::as_path);
+        let mut paths = Vec::new();
+        for path in dependencies.into_iter().flat_map(|t| t.1) {
+            match canonicalize(&base_dir.join(path)) {
+                Ok(canonical_path) => paths.push(canonical_path),
+                Err(_) => return Ok(None),
+            }
+        }
+        // The crate root is the first file listed for the build output.
+        if let Some(crate_root) = paths.first() {
+            if !crate_roots.insert((
+                dep_file.parent().map(Path::to_path_buf),
+                dep_file_crate_name(&dep_file),
+                crate_root.clone(),
+            )) {
+                return Ok(None);
+            }
+        }
+        path_buf_hash_set.extend(paths);
+    }
+    if path_buf_hash_set.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(path_buf_hash_set))
+}
+
+/// The packages to clean, an empty spec cleans the whole target directory.
+fn clean_spec(workspace: &Workspace, clean_members: bool) -> Vec<String> {
+    if !clean_members {
+        return vec![];
+    }
+    workspace
+        .members()
+        .map(|member| {
+            PackageIdSpec::from_package_id(member.package_id()).to_string()
+        })
+        .collect()
 }
 
+/// Adds the paths listed in the `.d` dep-info files in `out_dir`. Relative
+/// paths are resolved against the work dir of the rustc call that wrote the
+/// dep-info file, or against the workspace root for dep-info files of calls
+/// that were not intercepted.
 fn add_dir_entries_to_path_buf_hash_set(
     out_dir: PathBuf,
     path_buf_hash_set: &mut HashSet<PathBuf>,
-    workspace_root: PathBuf,
+    warnings: &mut Vec<ScanWarning>,
+    workspace_root: &Path,
+    dep_info_cwds: &HashMap<PathBuf, PathBuf>,
 ) -> Result<(), RsResolveError> {
     for entry in WalkDir::new(&out_dir) {
         let entry = entry.map_err(RsResolveError::Walkdir)?;
         if !is_file_with_ext(&entry, "d") {
             continue;
         }
-        let dependencies = parse_rustc_dep_info(entry.path()).map_err(|e| {
-            RsResolveError::DepParse(e.to_string(), entry.path().to_path_buf())
+        let dep_file = entry.path();
+        let dependencies = parse_rustc_dep_info(dep_file).map_err(|e| {
+            RsResolveError::DepParse {
+                dep_file: dep_file.to_path_buf(),
+                source: e,
+            }
         })?;
-        let canonical_paths = dependencies
+        let base_dir = dep_info_cwds
+            .get(dep_file)
+            .map_or(workspace_root, PathBuf::as_path);
+        let paths = dependencies
             .into_iter()
             .flat_map(|t| t.1)
             .map(PathBuf::from)
-            .map(|pb| workspace_root.join(pb))
-            .map(|pb| pb.canonicalize().map_err(|e| RsResolveError::Io(e, pb)));
-        for path_buf in canonical_paths {
-            path_buf_hash_set.insert(path_buf?);
+            .map(|pb| base_dir.join(pb));
+        for path_buf in paths {
+            match canonicalize(&path_buf) {
+                Ok(canonical_path) => {
+                    path_buf_hash_set.insert(canonical_path);
+                }
+                Err(e) => warnings.push(dep_file_canonicalize_warning(
+                    path_buf, dep_file, &e,
+                )),
+            }
         }
     }
 
     Ok(())
 }
 
+/// A path listed in a `.d` dep-info file that could not be canonicalized,
+/// usually because it no longer exists.
+fn dep_file_canonicalize_warning(
+    path: PathBuf,
+    dep_file: &Path,
+    error: &io::Error,
+) -> ScanWarning {
+    ScanWarning {
+        package: dep_file_crate_name(dep_file),
+        path,
+        cause: format!(
+            "failed to canonicalize the path referenced by the dep-info file \
+             {}, {}",
+            dep_file.display(),
+            error
+        ),
+    }
+}
+
+/// The name of the crate a `.d` dep-info file was written for, its file name
+/// is the crate name followed by a hash of the build, e.g. `serde-1a2b3c.d`.
+fn dep_file_crate_name(dep_file: &Path) -> String {
+    let file_stem = dep_file
+        .file_stem()
+        .map(|file_stem| file_stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    match file_stem.rfind('-') {
+        Some(index) => file_stem[..index].to_string(),
+        None => file_stem,
+    }
+}
+
 fn compile_with_exec(
     compile_options: &CompileOptions,
     config: &Config,
     inner_arc: Arc<Mutex<CustomExecutorInnerContext>>,
     workspace: &Workspace,
+    force_rebuild: bool,
 ) -> Result<(), RsResolveError> {
     let custom_executor = CustomExecutor {
         cwd: config.cwd().to_path_buf(),
+        force_rebuild,
         inner_ctx: inner_arc,
     };
 
     let custom_executor_arc: Arc<dyn Executor> = Arc::new(custom_executor);
 
-    ops::compile_with_exec(workspace, &compile_options, &custom_executor_arc)
-        .map_err(|e| RsResolveError::Cargo(e.to_string()))?;
+    ops::compile_with_exec(workspace, compile_options, &custom_executor_arc)
+        .map_err(RsResolveError::Cargo)?;
 
     Ok(())
 }
@@ -317,27 +724,287 @@ mod rs_file_tests {
         );
     }
 
+    #[rstest(
+        input_dep_file,
+        expected_crate_name,
+        case("target/debug/deps/serde-1a2b3c.d", "serde"),
+        case("target/debug/deps/cargo_geiger-1a2b3c.d", "cargo_geiger"),
+        case("target/debug/build/serde-1a2b3c/out/bindings.d", "bindings")
+    )]
+    fn dep_file_crate_name_test(
+        input_dep_file: &str,
+        expected_crate_name: &str,
+    ) {
+        assert_eq!(
+            dep_file_crate_name(Path::new(input_dep_file)),
+            expected_crate_name
+        );
+    }
+
+    #[rstest(
+        input_path,
+        expected_path,
+        case(
+            r"\\?\C:\Users\geiger\src\lib.rs",
+            Some(r"C:\Users\geiger\src\lib.rs")
+        ),
+        case(
+            r"\\?\UNC\server\share\src\lib.rs",
+            Some(r"\\server\share\src\lib.rs")
+        ),
+        case(r"C:\Users\geiger\src\lib.rs", None),
+        case(r"\\?\Volume{1234}\src\lib.rs", None),
+        case(r"\\?\C:\src\aux.rs", None),
+        case(r"\\?\C:\src\trailing.\lib.rs", None),
+        case(r"\\?\C:\src\a:b.rs", None)
+    )]
+    fn strip_verbatim_prefix_test(
+        input_path: &str,
+        expected_path: Option<&str>,
+    ) {
+        assert_eq!(strip_verbatim_prefix(input_path).as_deref(), expected_path);
+    }
+
+    #[rstest]
+    fn strip_verbatim_prefix_long_path_test() {
+        let long_path = format!(r"\\?\C:\{}\lib.rs", "a".repeat(300));
+
+        assert_eq!(strip_verbatim_prefix(&long_path), None);
+    }
+
+    #[rstest]
+    fn is_case_insensitive_dir_test() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path().join("Geiger");
+        std::fs::create_dir(&dir).unwrap();
+        let is_case_insensitive = temp_dir.path().join("gEIGER").exists();
+
+        assert_eq!(is_case_insensitive_dir(&dir), is_case_insensitive);
+    }
+
+    #[rstest]
+    fn dedup_paths_ignoring_case_test() {
+        let paths = vec![
+            PathBuf::from("/ws/Src/lib.rs"),
+            PathBuf::from("/ws/src/lib.rs"),
+            PathBuf::from("/dep/SRC/lib.rs"),
+            PathBuf::from("/dep/src/lib.rs"),
+            PathBuf::from("/dep/src/other.rs"),
+        ]
+        .into_iter()
+        .collect::<HashSet<PathBuf>>();
+        let scanned_paths = [PathBuf::from("/ws/SRC/lib.rs")];
+
+        let mut deduped_paths =
+            dedup_paths_ignoring_case(paths, scanned_paths.iter())
+                .into_iter()
+                .collect::<Vec<PathBuf>>();
+        deduped_paths.sort();
+
+        assert_eq!(
+            deduped_paths,
+            vec![
+                PathBuf::from("/dep/SRC/lib.rs"),
+                PathBuf::from("/dep/src/other.rs"),
+                PathBuf::from("/ws/SRC/lib.rs"),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn add_dir_entries_to_path_buf_hash_set_test() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = canonicalize(temp_dir.path()).unwrap();
+        let workspace_root = root.join("workspace");
+        let shared_root = root.join("shared-libs").join("shared");
+        let out_dir = workspace_root.join("target").join("debug").join("deps");
+        for dir in &[
+            workspace_root.join("src"),
+            shared_root.join("src"),
+            out_dir.clone(),
+        ] {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        std::fs::write(workspace_root.join("src").join("main.rs"), "").unwrap();
+        std::fs::write(shared_root.join("src").join("lib.rs"), "").unwrap();
+        std::fs::write(
+            out_dir.join("workspace-1a2b3c.d"),
+            "main: src/main.rs\n",
+        )
+        .unwrap();
+        std::fs::write(out_dir.join("shared-4d5e6f.d"), "lib: src/lib.rs\n")
+            .unwrap();
+        let mut dep_info_cwds = HashMap::new();
+        dep_info_cwds
+            .insert(out_dir.join("shared-4d5e6f.d"), shared_root.clone());
+
+        let mut path_buf_hash_set = HashSet::new();
+        let mut warnings = Vec::new();
+        add_dir_entries_to_path_buf_hash_set(
+            out_dir,
+            &mut path_buf_hash_set,
+            &mut warnings,
+            &workspace_root,
+            &dep_info_cwds,
+        )
+        .unwrap();
+
+        assert_eq!(
+            path_buf_hash_set,
+            vec![
+                workspace_root.join("src").join("main.rs"),
+                shared_root.join("src").join("lib.rs"),
+            ]
+            .into_iter()
+            .collect::<HashSet<PathBuf>>()
+        );
+        assert!(warnings.is_empty());
+    }
+
+    #[rstest]
+    fn read_dep_info_without_clean_test() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = canonicalize(temp_dir.path()).unwrap();
+        let workspace_root = root.join("workspace");
+        let deps_dir = workspace_root.join("target").join("debug").join("deps");
+        for dir in &[workspace_root.join("src"), deps_dir.clone()] {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        std::fs::write(workspace_root.join("src").join("main.rs"), "").unwrap();
+        std::fs::write(workspace_root.join("src").join("ffi.rs"), "").unwrap();
+        let dep_dirs = vec![deps_dir.clone(), workspace_root.join("missing")]
+            .into_iter()
+            .collect::<HashSet<PathBuf>>();
+
+        assert_eq!(
+            read_dep_info_without_clean(
+                &dep_dirs,
+                &workspace_root,
+                &HashMap::new()
+            )
+            .unwrap(),
+            None
+        );
+
+        std::fs::write(
+            deps_dir.join("workspace-1a2b3c.d"),
+            "main: src/main.rs src/ffi.rs\n",
+        )
+        .unwrap();
+        assert_eq!(
+            read_dep_info_without_clean(
+                &dep_dirs,
+                &workspace_root,
+                &HashMap::new()
+            )
+            .unwrap(),
+            Some(
+                vec![
+                    workspace_root.join("src").join("main.rs"),
+                    workspace_root.join("src").join("ffi.rs"),
+                ]
+                .into_iter()
+                .collect::<HashSet<PathBuf>>()
+            )
+        );
+
+        std::fs::write(
+            deps_dir.join("workspace-4d5e6f.d"),
+            "main: src/main.rs\n",
+        )
+        .unwrap();
+        assert_eq!(
+            read_dep_info_without_clean(
+                &dep_dirs,
+                &workspace_root,
+                &HashMap::new()
+            )
+            .unwrap(),
+            None
+        );
+
+        std::fs::remove_file(deps_dir.join("workspace-4d5e6f.d")).unwrap();
+        std::fs::remove_file(workspace_root.join("src").join("ffi.rs"))
+            .unwrap();
+        assert_eq!(
+            read_dep_info_without_clean(
+                &dep_dirs,
+                &workspace_root,
+                &HashMap::new()
+            )
+            .unwrap(),
+            None
+        );
+    }
+
+    #[rstest(
+        input_target,
+        expected_profile_dirs,
+        case(None, vec!["target/debug"]),
+        case(
+            Some("x86_64-unknown-linux-musl"),
+            vec!["target/debug", "target/x86_64-unknown-linux-musl/debug"]
+        ),
+        case(
+            Some("specs/custom-target.json"),
+            vec!["target/debug", "target/custom-target/debug"]
+        )
+    )]
+    fn profile_dirs_test(
+        input_target: Option<&str>,
+        expected_profile_dirs: Vec<&str>,
+    ) {
+        assert_eq!(
+            profile_dirs(Path::new("target"), input_target),
+            expected_profile_dirs
+                .into_iter()
+                .map(PathBuf::from)
+                .collect::<Vec<PathBuf>>()
+        );
+    }
+
+    #[rstest]
+    fn dep_file_canonicalize_warning_test() {
+        let scan_warning = dep_file_canonicalize_warning(
+            PathBuf::from("/src/missing.rs"),
+            Path::new("target/debug/deps/serde-1a2b3c.d"),
+            &io::Error::new(io::ErrorKind::NotFound, "not found"),
+        );
+
+        assert_eq!(
+            scan_warning,
+            ScanWarning {
+                package: String::from("serde"),
+                path: PathBuf::from("/src/missing.rs"),
+                cause: String::from(
+                    "failed to canonicalize the path referenced by the \
+                     dep-info file target/debug/deps/serde-1a2b3c.d, not found"
+                ),
+            }
+        );
+    }
+
     #[rstest]
     fn is_file_with_ext_test() {
         let config = Config::default().unwrap();
         let cwd = config.cwd();
 
-        let walk_dir_rust_files = WalkDir::new(&cwd)
+        let walk_dir_rust_files = WalkDir::new(cwd)
             .into_iter()
             .filter_map(|e| e.ok())
             .filter(|e| e.path().to_str().unwrap().ends_with(".rs"));
 
         for entry in walk_dir_rust_files {
-            assert_eq!(is_file_with_ext(&entry, "rs"), true);
+            assert!(is_file_with_ext(&entry, "rs"));
         }
 
-        let walk_dir_readme_files = WalkDir::new(&cwd)
+        let walk_dir_readme_files = WalkDir::new(cwd)
             .into_iter()
             .filter_map(|e| e.ok())
             .filter(|e| e.path().to_str().unwrap().contains("README"));
 
         for entry in walk_dir_readme_files {
-            assert_eq!(is_file_with_ext(&entry, "rs"), false);
+            assert!(!is_file_with_ext(&entry, "rs"));
         }
     }
 }
@@ -1,12 +1,14 @@
+use super::canonicalize;
+
 use cargo::core::compiler::{CompileMode, Executor, Unit};
 use cargo::core::{PackageId, Target};
 use cargo::util::{CargoResult, ProcessBuilder};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::ffi::OsString;
 use std::fmt;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
 /// A cargo Executor to intercept all build tasks and store all ".rs" file
@@ -20,14 +22,23 @@ pub struct CustomExecutor {
     /// Current work dir
     pub cwd: PathBuf,
 
+    /// Rebuild every unit, also the fresh ones, so that rustc is called and
+    /// observed for all of them.
+    pub force_rebuild: bool,
+
     /// Needed since multiple rustc calls can be in flight at the same time.
     pub inner_ctx: Arc<Mutex<CustomExecutorInnerContext>>,
 }
 
 #[derive(Debug)]
 enum CustomExecutorError {
+    /// A `.rs` file passed to rustc for a package could not be canonicalized.
+    Canonicalize {
+        path: PathBuf,
+        package_id: PackageId,
+        source: io::Error,
+    },
     InnerContextMutex(String),
-    Io(io::Error, PathBuf),
     OutDirKeyMissing(String),
     OutDirValueMissing(String),
 }
@@ -38,7 +49,7 @@ impl Executor for CustomExecutor {
     fn exec(
         &self,
         cmd: &ProcessBuilder,
-        _id: PackageId,
+        id: PackageId,
         _target: &Target,
         _mode: CompileMode,
         _on_stdout_line: &mut dyn FnMut(&str) -> CargoResult<()>,
@@ -76,11 +87,18 @@ impl Executor for CustomExecutor {
                 .filter(|(_, arg_value)| arg_value.ends_with(".rs"))
             {
                 let raw_path = cwd.join(arg_name);
-                let path = raw_path
-                    .canonicalize()
-                    .map_err(|e| CustomExecutorError::Io(e, raw_path))?;
+                let path = canonicalize(&raw_path).map_err(|e| {
+                    CustomExecutorError::Canonicalize {
+                        path: raw_path,
+                        package_id: id,
+                        source: e,
+                    }
+                })?;
                 ctx.rs_file_args.insert(path);
             }
+            if let Some(dep_info_path) = dep_info_path(args, &out_dir) {
+                ctx.dep_info_cwds.insert(dep_info_path, cwd);
+            }
             ctx.out_dir_args.insert(out_dir);
         }
         cmd.exec()?;
@@ -90,18 +108,46 @@ impl Executor for CustomExecutor {
     /// Queried when queuing each unit of work. If it returns true, then the
     /// unit will always be rebuilt, independent of whether it needs to be.
     fn force_rebuild(&self, _unit: &Unit) -> bool {
-        true // Overriding the default to force all units to be processed.
+        self.force_rebuild
     }
 }
 
-/// Forward Display to Debug. See the crate root documentation.
 impl fmt::Display for CustomExecutorError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::Debug::fmt(self, f)
+        match self {
+            CustomExecutorError::Canonicalize {
+                path, package_id, ..
+            } => write!(
+                f,
+                "failed to canonicalize {}, passed to rustc for {}",
+                path.display(),
+                package_id
+            ),
+            CustomExecutorError::InnerContextMutex(message) => write!(
+                f,
+                "failed to record the files used by the build: {}",
+                message
+            ),
+            CustomExecutorError::OutDirKeyMissing(cmd) => {
+                write!(f, "missing --out-dir in the rustc call: {}", cmd)
+            }
+            CustomExecutorError::OutDirValueMissing(cmd) => write!(
+                f,
+                "missing the value of --out-dir in the rustc call: {}",
+                cmd
+            ),
+        }
     }
 }
 
-impl Error for CustomExecutorError {}
+impl Error for CustomExecutorError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CustomExecutorError::Canonicalize { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
 
 #[derive(Debug, Default)]
 pub struct CustomExecutorInnerContext {
@@ -111,4 +157,66 @@ pub struct CustomExecutorInnerContext {
     /// Investigate if this needs to be intercepted like this or if it can be
     /// looked up in a nicer way.
     pub out_dir_args: HashSet<PathBuf>,
+
+    /// The work dir of the rustc call that wrote each `.d` dep-info file. The
+    /// relative paths in a dep-info file are relative to it, which is the
+    /// package root instead of the workspace root for path dependencies
+    /// outside of the workspace directory.
+    pub dep_info_cwds: HashMap<PathBuf, PathBuf>,
+}
+
+/// The `.d` dep-info file a rustc call writes to its `--out-dir`, named after
+/// the `--crate-name` and the `-C extra-filename` of the call.
+fn dep_info_path(args: &[OsString], out_dir: &Path) -> Option<PathBuf> {
+    let crate_name = args
+        .iter()
+        .position(|arg| arg == "--crate-name")
+        .and_then(|index| args.get(index + 1))?
+        .to_string_lossy();
+    let extra_filename = args
+        .iter()
+        .filter_map(|arg| {
+            arg.to_str()?
+                .trim_start_matches("-C")
+                .strip_prefix("extra-filename=")
+        })
+        .next_back()
+        .unwrap_or_default();
+    Some(out_dir.join(format!("{}{}.d", crate_name, extra_filename)))
+}
+
+#[cfg(test)]
+mod custom_executor_tests {
+    use super::*;
+
+    use rstest::*;
+
+    #[rstest(
+        input_args,
+        expected_path,
+        case(
+            vec!["--crate-name", "shared", "-C", "extra-filename=-1a2b3c"],
+            Some("/target/debug/deps/shared-1a2b3c.d")
+        ),
+        case(
+            vec!["--crate-name", "shared", "-Cextra-filename=-1a2b3c"],
+            Some("/target/debug/deps/shared-1a2b3c.d")
+        ),
+        case(
+            vec!["--crate-name", "build_script_build"],
+            Some("/target/debug/deps/build_script_build.d")
+        ),
+        case(vec!["--edition=2018", "src/lib.rs"], None)
+    )]
+    fn dep_info_path_test(input_args: Vec<&str>, expected_path: Option<&str>) {
+        let args = input_args
+            .into_iter()
+            .map(OsString::from)
+            .collect::<Vec<OsString>>();
+
+        assert_eq!(
+            dep_info_path(&args, Path::new("/target/debug/deps")),
+            expected_path.map(PathBuf::from)
+        );
+    }
 }
@@ -1,34 +1,154 @@
+mod attest;
+mod baseline;
+mod blame;
+mod cache;
+mod cargo_config;
+mod checksum;
 mod default;
+mod duplicates;
 mod find;
 mod forbid;
+mod github_output;
+mod inactive;
+mod latent;
+mod library;
+mod list_unsafe;
+mod metadata;
+mod missing_metrics;
+mod most_unsafe;
+mod notes;
+mod observer;
+mod owners;
+mod party;
+mod path;
+mod policy;
+mod pr_comment;
+mod quarantine;
+mod release_age;
+mod render;
+mod require_forbid;
+mod rust_version;
+mod stage;
+mod suggest_features;
+mod suggest_forbid;
+mod sysroot;
+#[cfg(test)]
+mod test_utils;
+mod threshold;
+mod undocumented;
+mod unsafe_docs;
+mod update;
+mod violation;
+mod why;
 
 use crate::args::Args;
+use crate::format::emoji_symbols::SymbolOverrides;
+use crate::format::numbers::NumberFormat;
 use crate::format::print_config::PrintConfig;
 use crate::graph::Graph;
-use crate::rs_file::RsFileMetricsWrapper;
+use crate::rs_file::{canonicalize, RsFileMetricsWrapper};
 
 use default::scan_unsafe;
+use find::find_unsafe;
 use forbid::scan_forbid_unsafe;
+use list_unsafe::scan_list_unsafe;
+use missing_metrics::check_missing_metrics;
+use policy::check_policy;
+use quarantine::check_quarantine;
+use require_forbid::check_require_forbid;
+use suggest_features::scan_suggest_features;
+use suggest_forbid::scan_suggest_forbid;
+use undocumented::{check_undocumented_unsafe, scan_undocumented_unsafe};
+use violation::Checks;
+use why::scan_why;
+
+pub use cache::{run_cache_command, CacheCommand};
+pub use default::{run_fleet, run_manifests, run_serve};
+pub use library::{
+    scan_packages, FileScan, PackageScan, PackagesScan, ScanOptions,
+};
+pub use observer::{ScanEvent, ScanObserver};
+pub use path::run_scan_path;
+pub use render::run_render;
+pub use threshold::UnsafeCountLimit;
+pub use update::run_check_update;
 
 use cargo::core::{PackageId, PackageSet, Workspace};
 use cargo::core::dependency::DepKind;
-use cargo::{CliResult, Config};
-use cargo_geiger_serde::{CounterBlock, DependencyKind, PackageInfo, UnsafeInfo};
+use cargo::util::Sha256;
+use cargo::{CliError, CliResult, Config};
+use cargo_geiger_serde::{
+    CounterBlock, DependencyKind, FileUnsafeInfo, PackageInfo, ScanWarning,
+    UnsafeInfo,
+};
+use geiger::RsFileMetrics;
 use petgraph::visit::EdgeRef;
-use std::collections::{HashMap, HashSet};
-use std::path::PathBuf;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use url::Url;
 
 /// Provides a more terse and searchable name for the wrapped generic
 /// collection.
+#[derive(Default)]
 pub struct GeigerContext {
     pub package_id_to_metrics: HashMap<PackageId, PackageMetrics>,
+    /// The files used by the build that were not scanned, listed by the scans
+    /// that fold the files of each package, see `PackageMetrics::folded`.
+    pub used_but_not_scanned: Option<Vec<PathBuf>>,
+    /// The files that were skipped because they failed to be found,
+    /// canonicalized or parsed.
+    pub warnings: Vec<ScanWarning>,
 }
 
 #[derive(Debug, Default)]
 pub struct PackageMetrics {
     /// The key is the canonicalized path to the rs source file.
     pub rs_path_to_metrics: HashMap<PathBuf, RsFileMetricsWrapper>,
+
+    /// The files without unsafe code that are neither crate entry points nor
+    /// generated, folded out of `rs_path_to_metrics` once the package is
+    /// scanned, unless an output lists the files.
+    pub folded: FoldedFiles,
+
+    /// The scan of the package was stopped by `--timeout-per-crate`, the
+    /// metrics only cover the files scanned before.
+    pub not_fully_scanned: bool,
+}
+
+/// The totals of the files folded out of `PackageMetrics::rs_path_to_metrics`,
+/// split by whether the build uses them.
+#[derive(Debug, Default)]
+pub struct FoldedFiles {
+    pub used: FoldedMetrics,
+    pub unused: FoldedMetrics,
+}
+
+/// The counters of a set of files, summed like the counters of their package.
+#[derive(Debug, Default)]
+pub struct FoldedMetrics {
+    pub counters: CounterBlock,
+    pub platform_counters: BTreeMap<String, CounterBlock>,
+    pub cfg_counters: BTreeMap<String, CounterBlock>,
+}
+
+impl FoldedMetrics {
+    /// Adds the counters of a file, taking them out of its metrics.
+    pub fn fold(&mut self, metrics: &mut RsFileMetrics) {
+        self.counters += mem::take(&mut metrics.counters);
+        for (platform, counter_block) in
+            mem::take(&mut metrics.platform_counters)
+        {
+            *self.platform_counters.entry(platform).or_default() +=
+                counter_block;
+        }
+        for (predicate, counter_block) in mem::take(&mut metrics.cfg_counters) {
+            *self.cfg_counters.entry(predicate).or_default() += counter_block;
+        }
+    }
 }
 
 pub enum ScanMode {
@@ -41,6 +161,51 @@ pub enum ScanMode {
     EntryPointsOnly,
 }
 
+/// The packages to list undocumented unsafe blocks for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UndocumentedUnsafeScope {
+    /// Only the workspace members.
+    Workspace,
+
+    /// All packages in the dependency graph.
+    All,
+}
+
+impl FromStr for UndocumentedUnsafeScope {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<UndocumentedUnsafeScope, &'static str> {
+        match s {
+            "workspace" => Ok(UndocumentedUnsafeScope::Workspace),
+            "all" => Ok(UndocumentedUnsafeScope::All),
+            _ => Err("invalid scope, expected `workspace` or `all`"),
+        }
+    }
+}
+
+/// The dependencies required to declare `#![forbid(unsafe_code)]`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RequireForbidScope {
+    /// Only the direct dependencies of the root package.
+    Direct,
+
+    /// All direct and transitive dependencies of the root package.
+    All,
+}
+
+impl FromStr for RequireForbidScope {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<RequireForbidScope, &'static str> {
+        match s {
+            "direct" => Ok(RequireForbidScope::Direct),
+            "all" => Ok(RequireForbidScope::All),
+            _ => Err("invalid scope, expected `direct` or `all`"),
+        }
+    }
+}
+
 pub struct ScanParameters<'a> {
     pub args: &'a Args,
     pub config: &'a Config,
@@ -52,35 +217,198 @@ pub fn scan(
     config: &Config,
     graph: &Graph,
     package_set: &PackageSet,
-    root_package_id: PackageId,
+    root_package_ids: &[PackageId],
     workspace: &Workspace,
 ) -> CliResult {
-    let print_config = PrintConfig::new(args)?;
+    let print_config = PrintConfig {
+        numbers: NumberFormat::from_config(config)?,
+        symbols: SymbolOverrides::from_config(config)?,
+        ..PrintConfig::new(args)?
+    };
 
     let scan_parameters = ScanParameters {
-        args: &args,
-        config: &config,
+        args,
+        config,
         print_config: &print_config,
     };
 
-    if args.forbid_only {
-        scan_forbid_unsafe(
+    if default_scan(args) {
+        return scan_unsafe(
+            workspace,
             package_set,
-            root_package_id,
-            &graph,
+            root_package_ids,
+            graph,
             &scan_parameters,
-        )
-    } else {
-        scan_unsafe(
-            workspace,
+        );
+    }
+
+    // The mode and the checks share the metrics of a single scan.
+    let geiger_context = match metrics_scan_mode(args) {
+        Some(scan_mode) => {
+            find_unsafe(scan_mode, config, package_set, &print_config, None)?
+        }
+        None => GeigerContext::default(),
+    };
+
+    if let Some(scope) = args.list_undocumented_unsafe {
+        scan_undocumented_unsafe(&geiger_context, graph, scope, workspace);
+    } else if let Some(spec) = &args.list_unsafe {
+        scan_list_unsafe(&geiger_context, graph, spec)?;
+    } else if let Some(spec) = &args.why {
+        scan_why(package_set, root_package_ids, graph, spec)?;
+    } else if args.suggest_features {
+        let root_package_id = match root_package_ids {
+            [root_package_id] => *root_package_id,
+            _ => {
+                return Err(CliError::new(
+                    anyhow::anyhow!(
+                        "--suggest-features needs a single root package, \
+                         select a member of the virtual workspace with \
+                         --package"
+                    ),
+                    1,
+                ));
+            }
+        };
+        scan_suggest_features(
+            &geiger_context,
             package_set,
             root_package_id,
-            &graph,
+            graph,
+        )?;
+    } else if args.suggest_forbid {
+        scan_suggest_forbid(&geiger_context, &scan_parameters, workspace)?;
+    } else {
+        scan_forbid_unsafe(
+            &geiger_context,
+            package_set,
+            root_package_ids,
+            graph,
             &scan_parameters,
-        )
+        )?;
+    }
+
+    run_checks(
+        &geiger_context,
+        graph,
+        root_package_ids,
+        workspace,
+        &scan_parameters,
+    )?
+    .report(args)
+}
+
+/// The default scan builds the packages before scanning them, and makes the
+/// checks on the metrics of its own scan.
+fn default_scan(args: &Args) -> bool {
+    args.list_undocumented_unsafe.is_none()
+        && args.list_unsafe.is_none()
+        && args.why.is_none()
+        && !args.suggest_features
+        && !args.suggest_forbid
+        && !args.forbid_only
+}
+
+/// The scan mode of the metrics the other modes than the default scan and
+/// the checks made after them need, or `None` when none of them reads any
+/// metrics. Only the entry points are scanned when they are enough for all
+/// of them.
+fn metrics_scan_mode(args: &Args) -> Option<ScanMode> {
+    let mode_scan_mode = if args.why.is_some() {
+        None
+    } else if args.forbid_only {
+        Some(ScanMode::EntryPointsOnly)
+    } else {
+        Some(ScanMode::Full)
+    };
+    match (mode_scan_mode, checks_scan_mode(args)) {
+        (Some(ScanMode::Full), _) | (_, Some(ScanMode::Full)) => {
+            Some(ScanMode::Full)
+        }
+        (None, None) => None,
+        _ => Some(ScanMode::EntryPointsOnly),
+    }
+}
+
+/// The scan mode of the metrics the checks need, or `None` when no check
+/// needs metrics.
+fn checks_scan_mode(args: &Args) -> Option<ScanMode> {
+    if args.policy.is_some()
+        || args.deny_undocumented_unsafe
+        || (args.fail_on_missing_metrics && !args.forbid_only)
+    {
+        Some(ScanMode::Full)
+    } else if args.fail_on_missing_metrics || args.require_forbid.is_some() {
+        Some(ScanMode::EntryPointsOnly)
+    } else {
+        None
     }
 }
 
+/// Makes the checks asked for with the options on the metrics of the scan:
+/// the missing metrics, the required forbids, the policy, the undocumented
+/// unsafe blocks and the quarantine. Their violations are collected, so that
+/// the outcome of all of them is known before anything is reported.
+fn run_checks(
+    geiger_context: &GeigerContext,
+    graph: &Graph,
+    root_package_ids: &[PackageId],
+    workspace: &Workspace,
+    scan_parameters: &ScanParameters,
+) -> Result<Checks, CliError> {
+    let args = scan_parameters.args;
+    let mut checks = Checks::default();
+
+    if args.fail_on_missing_metrics {
+        checks.push(check_missing_metrics(geiger_context, graph));
+    }
+
+    if let Some(scope) = args.require_forbid {
+        checks.push(check_require_forbid(
+            geiger_context,
+            root_package_ids,
+            graph,
+            scope,
+        ));
+    }
+
+    if let Some(policy_path) = &args.policy {
+        checks.push(check_policy(
+            geiger_context,
+            root_package_ids,
+            graph,
+            scan_parameters.print_config.numbers,
+            policy_path,
+        )?);
+    }
+
+    if args.deny_undocumented_unsafe {
+        checks.push(check_undocumented_unsafe(
+            geiger_context,
+            graph,
+            args.list_undocumented_unsafe
+                .unwrap_or(UndocumentedUnsafeScope::Workspace),
+            workspace,
+        ));
+    }
+
+    if let Some(report_path) = &args.quarantine {
+        checks.push(check_quarantine(graph, args, report_path)?);
+    }
+
+    Ok(checks)
+}
+
+/// For a crate to be classified as forbidding unsafe code, all entry point
+/// source files must declare `forbid(unsafe_code)`.
+pub fn package_forbids_unsafe(package_metrics: &PackageMetrics) -> bool {
+    package_metrics
+        .rs_path_to_metrics
+        .iter()
+        .filter(|(_, v)| v.is_crate_entry_point)
+        .all(|(_, v)| v.metrics.forbids_unsafe)
+}
+
 pub fn unsafe_stats(
     pack_metrics: &PackageMetrics,
     rs_files_used: &HashSet<PathBuf>,
@@ -91,31 +419,108 @@ pub fn unsafe_stats(
     // classified as forbidding unsafe code, all entry point source
     // files must declare `forbid(unsafe_code)`. Either a crate
     // forbids all unsafe code or it allows it _to some degree_.
-    let forbids_unsafe = pack_metrics
-        .rs_path_to_metrics
-        .iter()
-        .filter(|(_, v)| v.is_crate_entry_point)
-        .all(|(_, v)| v.metrics.forbids_unsafe);
+    let forbids_unsafe = package_forbids_unsafe(pack_metrics);
 
     let mut used = CounterBlock::default();
     let mut unused = CounterBlock::default();
+    let mut generated = CounterBlock::default();
+    let mut platforms = BTreeMap::new();
+    let mut allows_unsafe_code = false;
 
     for (path_buf, rs_file_metrics_wrapper) in &pack_metrics.rs_path_to_metrics
     {
         let target = if rs_files_used.contains(path_buf) {
+            allows_unsafe_code |= !rs_file_metrics_wrapper
+                .metrics
+                .allow_unsafe_code_locations
+                .is_empty();
+            if rs_file_metrics_wrapper.is_generated {
+                generated += rs_file_metrics_wrapper.metrics.counters.clone();
+            }
             &mut used
         } else {
             &mut unused
         };
         *target += rs_file_metrics_wrapper.metrics.counters.clone();
+
+        for (platform, counter_block) in
+            &rs_file_metrics_wrapper.metrics.platform_counters
+        {
+            *platforms
+                .entry(platform.clone())
+                .or_insert_with(CounterBlock::default) += counter_block.clone();
+        }
+    }
+    used += pack_metrics.folded.used.counters.clone();
+    unused += pack_metrics.folded.unused.counters.clone();
+    for folded_metrics in
+        [&pack_metrics.folded.used, &pack_metrics.folded.unused]
+    {
+        for (platform, counter_block) in &folded_metrics.platform_counters {
+            *platforms.entry(platform.clone()).or_default() +=
+                counter_block.clone();
+        }
     }
+    let forbid_mismatch =
+        forbids_unsafe && (used.has_unsafe() || allows_unsafe_code);
     UnsafeInfo {
         used,
         unused,
         forbids_unsafe,
+        forbid_mismatch,
+        not_fully_scanned: pack_metrics.not_fully_scanned,
+        generated,
+        platforms,
+        latent: BTreeMap::new(),
+        files: BTreeMap::new(),
     }
 }
 
+/// The unsafe usage of each source file of a package, keyed by path, with the
+/// size and digest of the file for an inventory of what was scanned, and the
+/// lines of the unsafe usage if the locations were kept.
+pub fn file_stats(
+    pack_metrics: &PackageMetrics,
+    rs_files_used: &HashSet<PathBuf>,
+) -> BTreeMap<PathBuf, FileUnsafeInfo> {
+    pack_metrics
+        .rs_path_to_metrics
+        .iter()
+        .map(|(path_buf, rs_file_metrics_wrapper)| {
+            let digest = file_digest(path_buf);
+            let mut unsafe_lines = rs_file_metrics_wrapper
+                .metrics
+                .unsafe_locations
+                .iter()
+                .map(|location| location.line)
+                .collect::<Vec<usize>>();
+            unsafe_lines.sort_unstable();
+            unsafe_lines.dedup();
+            let file_unsafe_info = FileUnsafeInfo {
+                used: rs_files_used.contains(path_buf),
+                is_crate_entry_point: rs_file_metrics_wrapper
+                    .is_crate_entry_point,
+                forbids_unsafe: rs_file_metrics_wrapper.metrics.forbids_unsafe,
+                counters: rs_file_metrics_wrapper.metrics.counters.clone(),
+                size: digest.as_ref().map(|(size, _)| *size),
+                sha256: digest.map(|(_, sha256)| sha256),
+                unsafe_lines,
+            };
+            (path_buf.clone(), file_unsafe_info)
+        })
+        .collect()
+}
+
+/// The size and SHA-256 digest of a file, left out if it can no longer be
+/// read.
+fn file_digest(path: &Path) -> Option<(u64, String)> {
+    let bytes = fs::read(path).ok()?;
+    Some((
+        bytes.len() as u64,
+        Sha256::new().update(&bytes).finish_hex(),
+    ))
+}
+
 struct ScanDetails {
     rs_files_used: HashSet<PathBuf>,
     geiger_context: GeigerContext,
@@ -138,34 +543,105 @@ fn construct_rs_files_used_lines(
         .collect::<Vec<String>>()
 }
 
+fn construct_asm_locations_lines(
+    geiger_context: &GeigerContext,
+) -> Vec<String> {
+    // Print the location of every inline assembly invocation, in sorted order.
+    let mut locations = geiger_context
+        .package_id_to_metrics
+        .values()
+        .flat_map(|package_metrics| package_metrics.rs_path_to_metrics.iter())
+        .flat_map(|(path_buf, rs_file_metrics_wrapper)| {
+            rs_file_metrics_wrapper
+                .metrics
+                .asm_locations
+                .iter()
+                .map(move |location| (path_buf, location))
+        })
+        .collect::<Vec<_>>();
+
+    locations.sort();
+
+    locations
+        .iter()
+        .map(|(path_buf, location)| {
+            format!(
+                "Inline assembly (sorted): {}:{}",
+                path_buf.display(),
+                location
+            )
+        })
+        .collect::<Vec<String>>()
+}
+
 fn list_files_used_but_not_scanned(
     geiger_context: &GeigerContext,
     rs_files_used: &HashSet<PathBuf>,
 ) -> Vec<PathBuf> {
+    if let Some(used_but_not_scanned) = &geiger_context.used_but_not_scanned {
+        return used_but_not_scanned.clone();
+    }
     let scanned_files = geiger_context
         .package_id_to_metrics
-        .iter()
-        .flat_map(|(_, v)| v.rs_path_to_metrics.keys())
+        .values()
+        .flat_map(|v| v.rs_path_to_metrics.keys())
         .collect::<HashSet<&PathBuf>>();
-    rs_files_used
+    let mut used_but_not_scanned = rs_files_used
         .iter()
+        .filter(|&p| !scanned_files.contains(p))
         .cloned()
-        .filter(|p| !scanned_files.contains(p))
-        .collect()
+        .collect::<Vec<PathBuf>>();
+    used_but_not_scanned.sort();
+    used_but_not_scanned
 }
 
+/// The packages in the graph pinned to a yanked version that use unsafe code
+/// in the build, sorted.
+fn list_yanked_packages_with_unsafe(
+    geiger_context: &GeigerContext,
+    graph: &Graph,
+    rs_files_used: &HashSet<PathBuf>,
+) -> Vec<PackageId> {
+    let mut yanked_packages = graph
+        .graph
+        .raw_nodes()
+        .iter()
+        .map(|node| &node.weight)
+        .filter(|node| node.yanked)
+        .filter(|node| {
+            geiger_context
+                .package_id_to_metrics
+                .get(&node.id)
+                .is_some_and(|package_metrics| {
+                    unsafe_stats(package_metrics, rs_files_used)
+                        .used
+                        .has_unsafe()
+                })
+        })
+        .map(|node| node.id)
+        .collect::<Vec<PackageId>>();
+    yanked_packages.sort();
+    yanked_packages
+}
+
+/// The packages reachable from the roots, each once, with their metrics.
 fn package_metrics<'a>(
     geiger_context: &'a GeigerContext,
     graph: &'a Graph,
-    root_id: PackageId,
+    root_ids: &[PackageId],
 ) -> impl Iterator<Item = (PackageInfo, Option<&'a PackageMetrics>)> {
-    let root_index = graph.nodes[&root_id];
-    let mut indices = vec![root_index];
-    let mut visited = HashSet::new();
+    let mut indices = root_ids
+        .iter()
+        .map(|root_id| graph.nodes[root_id])
+        .collect::<Vec<_>>();
+    let mut visited = indices.iter().copied().collect::<HashSet<_>>();
     std::iter::from_fn(move || {
         let i = indices.pop()?;
         let id = graph.graph[i].id;
         let mut package = PackageInfo::new(from_cargo_package_id(id));
+        package.patched = graph.graph[i].patched;
+        package.local_override = graph.graph[i].local_override;
+        package.yanked = graph.graph[i].yanked;
         for edge in graph.graph.edges(i) {
             let dep_index = edge.target();
             if visited.insert(dep_index) {
@@ -191,7 +667,7 @@ fn from_cargo_package_id(id: PackageId) -> cargo_geiger_serde::PackageId {
     let source_url = if source_url.scheme() == "file" {
         match source_url.to_file_path() {
             Ok(p) => {
-                let p = p.canonicalize().expect("A package source path could not be canonicalized");
+                let p = canonicalize(&p).expect("A package source path could not be canonicalized");
                 Url::from_file_path(p).expect("A URL could not be created from a file path")
             }
             Err(_) => source_url.clone(),
@@ -233,13 +709,17 @@ fn from_cargo_dependency_kind(kind: DepKind) -> DependencyKind {
 mod scan_tests {
     use super::*;
 
+    use crate::graph::Node;
+    use crate::scan::test_utils::create_package_id;
     use crate::{
         rs_file::RsFileMetricsWrapper,
         scan::PackageMetrics,
     };
 
     use cargo_geiger_serde::{Count, UnsafeInfo};
+    use geiger::Location;
     use rstest::*;
+    use std::ffi::OsString;
     use std::{collections::HashSet, path::PathBuf};
 
     #[rstest]
@@ -262,6 +742,101 @@ mod scan_tests {
         );
     }
 
+    #[rstest(
+        input_args,
+        expected_scan_mode,
+        case(vec!["--why", "foo"], None),
+        case(vec!["--why", "foo", "--require-forbid", "all"], Some("entry points")),
+        case(vec!["--why", "foo", "--fail-on-missing-metrics"], Some("full")),
+        case(vec!["--forbid-only"], Some("entry points")),
+        case(vec!["--forbid-only", "--require-forbid", "all"], Some("entry points")),
+        case(vec!["--forbid-only", "--fail-on-missing-metrics"], Some("entry points")),
+        case(vec!["--forbid-only", "--policy", "geiger.toml"], Some("full")),
+        case(vec!["--forbid-only", "--deny-undocumented-unsafe"], Some("full")),
+        case(vec!["--list-unsafe", "foo"], Some("full"))
+    )]
+    fn metrics_scan_mode_test(
+        input_args: Vec<&str>,
+        expected_scan_mode: Option<&str>,
+    ) {
+        let args = Args::parse_args(pico_args::Arguments::from_vec(
+            input_args.into_iter().map(OsString::from).collect(),
+        ))
+        .unwrap();
+
+        let scan_mode =
+            metrics_scan_mode(&args).map(|scan_mode| match scan_mode {
+                ScanMode::Full => "full",
+                ScanMode::EntryPointsOnly => "entry points",
+            });
+
+        assert_eq!(scan_mode, expected_scan_mode);
+    }
+
+    #[rstest]
+    fn undocumented_unsafe_scope_from_str_test() {
+        assert_eq!(
+            UndocumentedUnsafeScope::from_str("workspace"),
+            Ok(UndocumentedUnsafeScope::Workspace)
+        );
+        assert_eq!(
+            UndocumentedUnsafeScope::from_str("all"),
+            Ok(UndocumentedUnsafeScope::All)
+        );
+        assert!(UndocumentedUnsafeScope::from_str("invalid_str").is_err());
+    }
+
+    #[rstest]
+    fn require_forbid_scope_from_str_test() {
+        assert_eq!(
+            RequireForbidScope::from_str("direct"),
+            Ok(RequireForbidScope::Direct)
+        );
+        assert_eq!(
+            RequireForbidScope::from_str("all"),
+            Ok(RequireForbidScope::All)
+        );
+        assert!(RequireForbidScope::from_str("invalid_str").is_err());
+    }
+
+    #[rstest]
+    fn construct_asm_locations_lines_test() {
+        let mut package_id_to_metrics = HashMap::new();
+        package_id_to_metrics.insert(
+            create_package_id("package_name"),
+            metrics_from_iter(vec![
+                (
+                    "b/path.rs",
+                    MetricsBuilder::default().asm_location(7, 8).build(),
+                ),
+                (
+                    "a/path.rs",
+                    MetricsBuilder::default()
+                        .asm_location(12, 4)
+                        .asm_location(3, 4)
+                        .build(),
+                ),
+            ]),
+        );
+        let geiger_context = GeigerContext {
+            package_id_to_metrics,
+            used_but_not_scanned: None,
+            warnings: vec![],
+        };
+
+        let asm_locations_lines =
+            construct_asm_locations_lines(&geiger_context);
+
+        assert_eq!(
+            asm_locations_lines,
+            vec![
+                String::from("Inline assembly (sorted): a/path.rs:3:4"),
+                String::from("Inline assembly (sorted): a/path.rs:12:4"),
+                String::from("Inline assembly (sorted): b/path.rs:7:8"),
+            ]
+        );
+    }
+
     #[rstest]
     fn unsafe_stats_from_nothing_are_empty() {
         let stats = unsafe_stats(&Default::default(), &Default::default());
@@ -310,6 +885,39 @@ mod scan_tests {
         assert!(!stats.forbids_unsafe)
     }
 
+    #[rstest(
+        input_used_functions,
+        input_allows_unsafe_code,
+        expected_forbid_mismatch,
+        case(0, false, false),
+        case(1, false, true),
+        case(0, true, true)
+    )]
+    fn unsafe_stats_report_forbid_mismatch_if_forbidding_crate_uses_unsafe(
+        input_used_functions: u64,
+        input_allows_unsafe_code: bool,
+        expected_forbid_mismatch: bool,
+    ) {
+        let mut included =
+            MetricsBuilder::default().functions(1, input_used_functions);
+        if input_allows_unsafe_code {
+            included = included.allow_unsafe_code_location(1, 0);
+        }
+        let metrics = metrics_from_iter(vec![
+            (
+                "lib.rs",
+                MetricsBuilder::default()
+                    .forbids_unsafe(true)
+                    .set_is_crate_entry_point(true)
+                    .build(),
+            ),
+            ("included.rs", included.build()),
+        ]);
+        let stats =
+            unsafe_stats(&metrics, &set_of_paths(&["lib.rs", "included.rs"]));
+        assert_eq!(stats.forbid_mismatch, expected_forbid_mismatch)
+    }
+
     #[rstest]
     fn unsafe_stats_accumulate_counters() {
         let metrics = metrics_from_iter(vec![
@@ -332,6 +940,161 @@ mod scan_tests {
         assert_eq!(stats.unused.functions.unsafe_, 110);
     }
 
+    #[rstest]
+    fn unsafe_stats_accumulate_generated_counters() {
+        let metrics = metrics_from_iter(vec![
+            ("lib.rs", MetricsBuilder::default().functions(2, 1).build()),
+            (
+                "bindings.rs",
+                MetricsBuilder::default()
+                    .functions(5, 3)
+                    .set_is_generated(true)
+                    .build(),
+            ),
+            (
+                "unused_bindings.rs",
+                MetricsBuilder::default()
+                    .functions(20, 10)
+                    .set_is_generated(true)
+                    .build(),
+            ),
+        ]);
+        let stats =
+            unsafe_stats(&metrics, &set_of_paths(&["lib.rs", "bindings.rs"]));
+        assert_eq!(stats.used.functions.unsafe_, 4);
+        assert_eq!(stats.generated.functions.safe, 5);
+        assert_eq!(stats.generated.functions.unsafe_, 3);
+    }
+
+    #[rstest]
+    fn file_stats_lists_every_file() {
+        let metrics = metrics_from_iter(vec![
+            (
+                "lib.rs",
+                MetricsBuilder::default()
+                    .functions(2, 1)
+                    .forbids_unsafe(true)
+                    .set_is_crate_entry_point(true)
+                    .build(),
+            ),
+            (
+                "unused.rs",
+                MetricsBuilder::default().functions(5, 3).build(),
+            ),
+        ]);
+        let stats = file_stats(&metrics, &set_of_paths(&["lib.rs"]));
+        assert_eq!(stats.len(), 2);
+        let lib = &stats[&PathBuf::from("lib.rs")];
+        assert!(lib.used && lib.is_crate_entry_point && lib.forbids_unsafe);
+        assert_eq!(lib.counters.functions.unsafe_, 1);
+        let unused = &stats[&PathBuf::from("unused.rs")];
+        assert!(!unused.used && !unused.is_crate_entry_point);
+        assert_eq!(unused.counters.functions.unsafe_, 3);
+    }
+
+    #[rstest]
+    fn file_stats_record_size_and_digest_of_readable_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let lib_path = temp_dir.path().join("lib.rs");
+        std::fs::write(&lib_path, "abc").unwrap();
+        let missing_path = temp_dir.path().join("missing.rs");
+        let metrics = metrics_from_iter(vec![
+            (lib_path.clone(), MetricsBuilder::default().build()),
+            (missing_path.clone(), MetricsBuilder::default().build()),
+        ]);
+
+        let stats = file_stats(&metrics, &HashSet::new());
+
+        assert_eq!(stats[&lib_path].size, Some(3));
+        assert_eq!(
+            stats[&lib_path].sha256.as_deref(),
+            Some(
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+            )
+        );
+        assert_eq!(stats[&missing_path].size, None);
+        assert_eq!(stats[&missing_path].sha256, None);
+    }
+
+    #[rstest]
+    fn unsafe_stats_accumulate_platform_counters() {
+        let metrics = metrics_from_iter(vec![
+            (
+                "foo.rs",
+                MetricsBuilder::default()
+                    .platform_functions("windows", 2, 1)
+                    .build(),
+            ),
+            (
+                "bar.rs",
+                MetricsBuilder::default()
+                    .platform_functions("windows", 5, 3)
+                    .platform_functions("target_os = \"linux\"", 1, 1)
+                    .build(),
+            ),
+        ]);
+        let stats = unsafe_stats(&metrics, &set_of_paths(&["foo.rs"]));
+        assert_eq!(stats.platforms.len(), 2);
+        assert_eq!(stats.platforms["windows"].functions.safe, 7);
+        assert_eq!(stats.platforms["windows"].functions.unsafe_, 4);
+        assert_eq!(
+            stats.platforms["target_os = \"linux\""].functions.unsafe_,
+            1
+        );
+    }
+
+    #[rstest]
+    fn list_yanked_packages_with_unsafe_test() {
+        let mut graph = Graph {
+            graph: petgraph::Graph::new(),
+            nodes: HashMap::new(),
+            edge_features: HashMap::new(),
+        };
+        let mut package_id_to_metrics = HashMap::new();
+        let mut package_ids = vec![];
+        for (name, yanked, unsafe_functions) in &[
+            ("yanked_unsafe", true, 1),
+            ("yanked_safe", true, 0),
+            ("unsafe", false, 1),
+        ] {
+            let package_id = create_package_id(name);
+            let index = graph.graph.add_node(Node {
+                id: package_id,
+                features: vec![],
+                patched: false,
+                local_override: false,
+                yanked: *yanked,
+            });
+            graph.nodes.insert(package_id, index);
+            package_id_to_metrics.insert(
+                package_id,
+                metrics_from_iter(vec![(
+                    format!("{}.rs", name),
+                    MetricsBuilder::default()
+                        .functions(1, *unsafe_functions)
+                        .build(),
+                )]),
+            );
+            package_ids.push(package_id);
+        }
+        let geiger_context = GeigerContext {
+            package_id_to_metrics,
+            used_but_not_scanned: None,
+            warnings: vec![],
+        };
+        let rs_files_used =
+            set_of_paths(&["yanked_unsafe.rs", "yanked_safe.rs", "unsafe.rs"]);
+
+        assert_eq!(
+            list_yanked_packages_with_unsafe(
+                &geiger_context,
+                &graph,
+                &rs_files_used
+            ),
+            vec![package_ids[0]]
+        );
+    }
+
     fn metrics_from_iter<I, P>(it: I) -> PackageMetrics
     where
         I: IntoIterator<Item = (P, RsFileMetricsWrapper)>,
@@ -342,6 +1105,8 @@ mod scan_tests {
                 .into_iter()
                 .map(|(p, m)| (p.into(), m))
                 .collect(),
+            folded: FoldedFiles::default(),
+            not_fully_scanned: false,
         }
     }
 
@@ -369,11 +1134,52 @@ mod scan_tests {
             self
         }
 
+        fn platform_functions(
+            mut self,
+            platform: &str,
+            safe: u64,
+            unsafe_: u64,
+        ) -> Self {
+            self.inner
+                .metrics
+                .platform_counters
+                .entry(platform.to_string())
+                .or_default()
+                .functions = Count { safe, unsafe_ };
+            self
+        }
+
+        fn allow_unsafe_code_location(
+            mut self,
+            line: usize,
+            column: usize,
+        ) -> Self {
+            self.inner
+                .metrics
+                .allow_unsafe_code_locations
+                .push(Location { line, column });
+            self
+        }
+
+        fn asm_location(mut self, line: usize, column: usize) -> Self {
+            self.inner.metrics.counters.asm += 1;
+            self.inner
+                .metrics
+                .asm_locations
+                .push(Location { line, column });
+            self
+        }
+
         fn set_is_crate_entry_point(mut self, yes: bool) -> Self {
             self.inner.is_crate_entry_point = yes;
             self
         }
 
+        fn set_is_generated(mut self, yes: bool) -> Self {
+            self.inner.is_generated = yes;
+            self
+        }
+
         fn build(self) -> RsFileMetricsWrapper {
             self.inner
         }
@@ -0,0 +1,159 @@
+use crate::graph::Graph;
+
+use super::{unsafe_stats, GeigerContext};
+
+use cargo::core::Workspace;
+use cargo::util::{CargoResult, Sha256};
+use cargo_geiger_serde::CounterBlock;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const STATEMENT_TYPE: &str = "https://in-toto.io/Statement/v0.1";
+
+const PREDICATE_TYPE: &str =
+    "https://github.com/rust-secure-code/cargo-geiger/summary/v1";
+
+const VERSION: Option<&'static str> = option_env!("CARGO_PKG_VERSION");
+
+/// An in-toto statement binding the scan summary to the lockfile that the
+/// scanned dependency tree was resolved from.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Statement {
+    #[serde(rename = "_type")]
+    pub statement_type: String,
+    pub subject: Vec<Subject>,
+    #[serde(rename = "predicateType")]
+    pub predicate_type: String,
+    pub predicate: GeigerSummary,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Subject {
+    pub name: String,
+    pub digest: BTreeMap<String, String>,
+}
+
+/// The totals of a scan, over all packages in the dependency graph.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct GeigerSummary {
+    pub generator: String,
+    pub packages: u64,
+    pub packages_without_metrics: u64,
+    pub crates_with_unsafe: u64,
+    pub crates_forbidding_unsafe: u64,
+    /// Unsafe usage in code used by the build.
+    pub used: CounterBlock,
+    /// Unsafe usage in code not used by the build.
+    pub unused: CounterBlock,
+    /// False if the scan failed any of the checks it was asked to make.
+    pub policy_ok: bool,
+}
+
+impl GeigerSummary {
+    pub fn new(
+        geiger_context: &GeigerContext,
+        graph: &Graph,
+        rs_files_used: &HashSet<PathBuf>,
+        policy_ok: bool,
+    ) -> Self {
+        let mut geiger_summary = GeigerSummary {
+            generator: format!(
+                "cargo-geiger {}",
+                VERSION.unwrap_or("unknown version")
+            ),
+            policy_ok,
+            ..Default::default()
+        };
+        for package_id in graph.nodes.keys() {
+            geiger_summary.packages += 1;
+            let package_metrics =
+                match geiger_context.package_id_to_metrics.get(package_id) {
+                    Some(package_metrics) => package_metrics,
+                    None => {
+                        geiger_summary.packages_without_metrics += 1;
+                        continue;
+                    }
+                };
+            let unsafe_info = unsafe_stats(package_metrics, rs_files_used);
+            if unsafe_info.used.has_unsafe() {
+                geiger_summary.crates_with_unsafe += 1;
+            }
+            if unsafe_info.forbids_unsafe {
+                geiger_summary.crates_forbidding_unsafe += 1;
+            }
+            geiger_summary.used += unsafe_info.used;
+            geiger_summary.unused += unsafe_info.unused;
+        }
+        geiger_summary
+    }
+}
+
+/// Writes the statement for the lockfile of the workspace to `path`.
+pub fn write_attestation(
+    path: &Path,
+    workspace: &Workspace,
+    geiger_summary: GeigerSummary,
+) -> CargoResult<()> {
+    let statement = create_statement(workspace, geiger_summary)?;
+    fs::write(path, serde_json::to_string_pretty(&statement)?).map_err(
+        |e| anyhow::anyhow!("failed to write {}: {}", path.display(), e),
+    )?;
+    Ok(())
+}
+
+/// Creates the statement for the lockfile of the workspace.
+pub fn create_statement(
+    workspace: &Workspace,
+    geiger_summary: GeigerSummary,
+) -> CargoResult<Statement> {
+    let lock_path = workspace.root().join("Cargo.lock");
+    let lock_bytes = fs::read(&lock_path).map_err(|e| {
+        anyhow::anyhow!("failed to read {}: {}", lock_path.display(), e)
+    })?;
+    Ok(construct_statement(&lock_bytes, geiger_summary))
+}
+
+fn construct_statement(
+    lock_bytes: &[u8],
+    geiger_summary: GeigerSummary,
+) -> Statement {
+    let mut digest = BTreeMap::new();
+    digest.insert(
+        String::from("sha256"),
+        Sha256::new().update(lock_bytes).finish_hex(),
+    );
+    Statement {
+        statement_type: String::from(STATEMENT_TYPE),
+        subject: vec![Subject {
+            name: String::from("Cargo.lock"),
+            digest,
+        }],
+        predicate_type: String::from(PREDICATE_TYPE),
+        predicate: geiger_summary,
+    }
+}
+
+#[cfg(test)]
+mod attest_tests {
+    use super::*;
+
+    use rstest::*;
+
+    #[rstest]
+    fn construct_statement_test() {
+        let statement = construct_statement(b"", GeigerSummary::default());
+
+        assert_eq!(statement.subject[0].name, "Cargo.lock");
+        assert_eq!(
+            statement.subject[0].digest["sha256"],
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+
+        let value = serde_json::to_value(&statement).unwrap();
+        assert_eq!(value["_type"], STATEMENT_TYPE);
+        assert_eq!(value["predicateType"], PREDICATE_TYPE);
+        assert_eq!(value["predicate"]["policy_ok"], false);
+    }
+}
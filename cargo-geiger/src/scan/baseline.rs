@@ -0,0 +1,506 @@
+use crate::format::html::{
+    find_baseline_entry, unsafe_counts, UNSAFE_COUNTER_COLUMNS,
+};
+use crate::graph::Graph;
+
+use super::policy::{load_policy, DiffRules};
+use super::render::read_report;
+use super::violation::Check;
+use super::{package_metrics, unsafe_stats, GeigerContext};
+
+use cargo::core::PackageId;
+use cargo::{CliError, CliResult};
+use cargo_geiger_serde::{CounterBlock, ReportEntry, SafetyReport};
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The unsafe usage of every package in the graph, a report with only the
+/// unsafe counters. It is read like any JSON report, also by `--html` with
+/// `--baseline`.
+pub fn construct_baseline_report(
+    geiger_context: &GeigerContext,
+    graph: &Graph,
+    root_package_ids: &[PackageId],
+    rs_files_used: &HashSet<PathBuf>,
+) -> SafetyReport {
+    let mut report = SafetyReport::default();
+    for (package, package_metrics) in
+        package_metrics(geiger_context, graph, root_package_ids)
+    {
+        match package_metrics {
+            Some(package_metrics) => {
+                let entry = ReportEntry {
+                    package,
+                    unsafety: unsafe_stats(package_metrics, rs_files_used),
+                };
+                report.packages.insert(entry.package.id.clone(), entry);
+            }
+            None => {
+                report.packages_without_metrics.insert(package.id);
+            }
+        }
+    }
+    report
+}
+
+/// Writes the baseline report for `--save-baseline`, pretty printed so that
+/// it can be committed and reviewed.
+pub fn save_baseline(
+    baseline_path: &Path,
+    baseline_report: &SafetyReport,
+) -> CliResult {
+    serde_json::to_string_pretty(baseline_report)
+        .map_err(anyhow::Error::from)
+        .and_then(|json| {
+            fs::write(baseline_path, json).map_err(anyhow::Error::from)
+        })
+        .map_err(|e| {
+            CliError::new(
+                e.context(format!(
+                    "Failed to write the baseline {}",
+                    baseline_path.display()
+                )),
+                1,
+            )
+        })
+}
+
+/// Describes the packages that use more unsafe code than recorded in the
+/// `--baseline` report, sorted by package. Packages are matched by name like
+/// in the HTML output, so an update of a package only regresses if the new
+/// version uses more unsafe code.
+pub fn list_baseline_regressions(
+    baseline_path: &Path,
+    policy_path: Option<&Path>,
+    report: &SafetyReport,
+) -> Result<Vec<String>, CliError> {
+    let baseline = read_baseline(baseline_path, policy_path, report)?;
+    Ok(find_regressions(report, &baseline))
+}
+
+/// Reads the `--baseline` report to compare the report with, with the
+/// `[diff]` rules of the `--policy` file, if any, applied.
+pub fn read_baseline(
+    baseline_path: &Path,
+    policy_path: Option<&Path>,
+    report: &SafetyReport,
+) -> Result<SafetyReport, CliError> {
+    let baseline = read_report(baseline_path)?;
+    let policy_path = match policy_path {
+        Some(policy_path) => policy_path,
+        None => return Ok(baseline),
+    };
+    let diff_rules = load_policy(policy_path)?.diff;
+    apply_diff_rules(&diff_rules, report, &baseline).map_err(|e| {
+        CliError::new(
+            e.context(format!(
+                "Failed to apply the diff rules of the policy {}",
+                policy_path.display()
+            )),
+            1,
+        )
+    })
+}
+
+/// Lists the regressions of the unsafe usage since the baseline, failing on
+/// them with `--fail-on-regression`. The unsafe usage already in the
+/// baseline passes.
+pub fn check_baseline_regressions(
+    regressions: Vec<String>,
+    fail_on_regression: bool,
+) -> Check {
+    if fail_on_regression {
+        let regression_count = regressions.len() as u64;
+        Check::new(regressions, BaselineRegressionError { regression_count })
+    } else {
+        Check::warnings(regressions)
+    }
+}
+
+#[derive(Debug)]
+struct BaselineRegressionError {
+    regression_count: u64,
+}
+
+impl Error for BaselineRegressionError {}
+
+impl fmt::Display for BaselineRegressionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} regression(s) against the baseline",
+            self.regression_count
+        )
+    }
+}
+
+/// The baseline as the report is compared with under the diff rules, so
+/// that every comparison with it leaves out the ignored changes. The ignored
+/// packages of the report are taken over unchanged, those of the baseline
+/// are dropped, and with `ignore-decreases` the unsafe usage of the baseline
+/// is lowered to the usage of the report.
+fn apply_diff_rules(
+    diff_rules: &DiffRules,
+    report: &SafetyReport,
+    baseline: &SafetyReport,
+) -> anyhow::Result<SafetyReport> {
+    let crate_patterns = diff_rules
+        .ignore_crates
+        .iter()
+        .map(|ignore_crate| glob::Pattern::new(ignore_crate))
+        .collect::<Result<Vec<glob::Pattern>, glob::PatternError>>()?;
+    let is_ignored =
+        |entry: &ReportEntry,
+         test_only_ids: &HashSet<&cargo_geiger_serde::PackageId>| {
+            crate_patterns
+                .iter()
+                .any(|pattern| pattern.matches(&entry.package.id.name))
+                || (diff_rules.ignore_test_only
+                    && test_only_ids.contains(&entry.package.id))
+        };
+
+    let baseline_test_only_ids = test_only_package_ids(baseline);
+    let mut canonical_baseline = baseline.clone();
+    canonical_baseline.packages.retain(|_, baseline_entry| {
+        !is_ignored(baseline_entry, &baseline_test_only_ids)
+    });
+    if diff_rules.ignore_decreases {
+        canonical_baseline.packages.retain(|baseline_id, _| {
+            report
+                .packages
+                .keys()
+                .any(|package_id| package_id.name == baseline_id.name)
+        });
+    }
+
+    let report_test_only_ids = test_only_package_ids(report);
+    for entry in report.packages.values() {
+        if is_ignored(entry, &report_test_only_ids) {
+            canonical_baseline
+                .packages
+                .insert(entry.package.id.clone(), entry.clone());
+            continue;
+        }
+        if !diff_rules.ignore_decreases {
+            continue;
+        }
+        let baseline_id = match find_baseline_entry(entry, &canonical_baseline)
+        {
+            Some(baseline_entry) => baseline_entry.package.id.clone(),
+            None => continue,
+        };
+        if let Some(baseline_entry) =
+            canonical_baseline.packages.get_mut(&baseline_id)
+        {
+            lower_unsafe_counts(
+                &mut baseline_entry.unsafety.used,
+                &entry.unsafety.used,
+            );
+        }
+    }
+    Ok(canonical_baseline)
+}
+
+/// The packages of the report that are only used by tests, those not
+/// reached from the root packages, that no package depends on, through
+/// normal and build dependencies.
+fn test_only_package_ids(
+    report: &SafetyReport,
+) -> HashSet<&cargo_geiger_serde::PackageId> {
+    let dependency_ids = report
+        .packages
+        .values()
+        .flat_map(|entry| {
+            entry
+                .package
+                .dependencies
+                .iter()
+                .chain(entry.package.build_dependencies.iter())
+                .chain(entry.package.dev_dependencies.iter())
+        })
+        .collect::<HashSet<&cargo_geiger_serde::PackageId>>();
+    let mut queue = report
+        .packages
+        .keys()
+        .filter(|package_id| !dependency_ids.contains(package_id))
+        .collect::<Vec<&cargo_geiger_serde::PackageId>>();
+    let mut used_ids = queue
+        .iter()
+        .copied()
+        .collect::<HashSet<&cargo_geiger_serde::PackageId>>();
+    while let Some(package_id) = queue.pop() {
+        let package = match report.packages.get(package_id) {
+            Some(entry) => &entry.package,
+            None => continue,
+        };
+        for dependency_id in package
+            .dependencies
+            .iter()
+            .chain(package.build_dependencies.iter())
+        {
+            if used_ids.insert(dependency_id) {
+                queue.push(dependency_id);
+            }
+        }
+    }
+    report
+        .packages
+        .keys()
+        .filter(|package_id| !used_ids.contains(package_id))
+        .collect()
+}
+
+/// Lowers every unsafe count of the baseline to the count of the report,
+/// where it is higher.
+fn lower_unsafe_counts(baseline_used: &mut CounterBlock, used: &CounterBlock) {
+    let mut baseline_counts = [
+        &mut baseline_used.functions,
+        &mut baseline_used.exprs,
+        &mut baseline_used.item_impls,
+        &mut baseline_used.item_traits,
+        &mut baseline_used.methods,
+    ];
+    for (baseline_count, count) in
+        baseline_counts.iter_mut().zip(unsafe_counts(used).iter())
+    {
+        baseline_count.unsafe_ = baseline_count.unsafe_.min(count.unsafe_);
+    }
+}
+
+fn find_regressions(
+    report: &SafetyReport,
+    baseline: &SafetyReport,
+) -> Vec<String> {
+    let mut entries = report.packages.values().collect::<Vec<&ReportEntry>>();
+    entries.sort_by(|a, b| a.package.id.cmp(&b.package.id));
+    let mut regressions = Vec::new();
+    for entry in entries {
+        let package_id = &entry.package.id;
+        let used = unsafe_counts(&entry.unsafety.used);
+        let baseline_entry = match find_baseline_entry(entry, baseline) {
+            Some(baseline_entry) => baseline_entry,
+            None => {
+                let used_total =
+                    used.iter().map(|count| count.unsafe_).sum::<u64>();
+                if used_total > 0 {
+                    regressions.push(format!(
+                        "New package with unsafe code: {} {}, {} unsafe \
+                         item(s) used",
+                        package_id.name, package_id.version, used_total
+                    ));
+                }
+                continue;
+            }
+        };
+        let baseline_used = unsafe_counts(&baseline_entry.unsafety.used);
+        let increases = UNSAFE_COUNTER_COLUMNS
+            .iter()
+            .zip(used.iter().zip(baseline_used.iter()))
+            .filter(|(_, (count, baseline_count))| {
+                count.unsafe_ > baseline_count.unsafe_
+            })
+            .map(|(column, (count, baseline_count))| {
+                format!(
+                    "{} {} -> {}",
+                    column, baseline_count.unsafe_, count.unsafe_
+                )
+            })
+            .collect::<Vec<String>>();
+        if !increases.is_empty() {
+            regressions.push(format!(
+                "More unsafe code than the baseline: {} {}, {}",
+                package_id.name,
+                package_id.version,
+                increases.join(", ")
+            ));
+        }
+    }
+    regressions
+}
+
+#[cfg(test)]
+mod baseline_tests {
+    use super::*;
+
+    use cargo_geiger_serde::{PackageInfo, Source, UnsafeInfo};
+    use rstest::*;
+    use semver::Version;
+    use url::Url;
+
+    #[rstest]
+    fn find_regressions_test() {
+        let baseline = create_report(&[
+            ("libc", "0.2.79", 10, 1),
+            ("smallvec", "1.0.0", 5, 0),
+            ("removed", "1.0.0", 3, 0),
+        ]);
+        let report = create_report(&[
+            ("libc", "0.2.80", 12, 2),
+            ("smallvec", "1.0.0", 4, 0),
+            ("new_safe", "1.0.0", 0, 0),
+            ("new_unsafe", "1.0.0", 2, 1),
+        ]);
+
+        assert_eq!(
+            find_regressions(&report, &baseline),
+            vec![
+                String::from(
+                    "More unsafe code than the baseline: libc 0.2.80, \
+                     Functions 1 -> 2, Expressions 10 -> 12"
+                ),
+                String::from(
+                    "New package with unsafe code: new_unsafe 1.0.0, 3 \
+                     unsafe item(s) used"
+                ),
+            ]
+        );
+        assert!(find_regressions(&baseline, &baseline).is_empty());
+    }
+
+    #[rstest]
+    fn apply_diff_rules_test() {
+        let mut baseline = create_report(&[
+            ("app", "1.0.0", 0, 0),
+            ("libc", "0.2.79", 10, 1),
+            ("windows-sys", "0.1.0", 5, 0),
+            ("proptest", "1.0.0", 3, 0),
+            ("removed", "1.0.0", 2, 0),
+        ]);
+        add_dev_dependency(&mut baseline, "app", "proptest");
+        let mut report = create_report(&[
+            ("app", "1.0.0", 0, 0),
+            ("libc", "0.2.80", 8, 2),
+            ("windows-sys", "0.2.0", 9, 0),
+            ("proptest", "1.1.0", 7, 0),
+        ]);
+        add_dev_dependency(&mut report, "app", "proptest");
+        let diff_rules = DiffRules {
+            ignore_test_only: true,
+            ignore_crates: vec![String::from("windows*")],
+            ignore_decreases: true,
+        };
+
+        let canonical_baseline =
+            apply_diff_rules(&diff_rules, &report, &baseline).unwrap();
+
+        assert_eq!(
+            find_regressions(&report, &canonical_baseline),
+            vec![String::from(
+                "More unsafe code than the baseline: libc 0.2.80, Functions \
+                 1 -> 2"
+            )]
+        );
+        assert!(find_regressions(&report, &baseline).len() > 1);
+        assert!(!canonical_baseline
+            .packages
+            .keys()
+            .any(|package_id| package_id.name == "removed"));
+        assert!(apply_diff_rules(
+            &DiffRules {
+                ignore_crates: vec![String::from("[")],
+                ..Default::default()
+            },
+            &report,
+            &baseline
+        )
+        .is_err());
+    }
+
+    #[rstest]
+    fn test_only_package_ids_test() {
+        let mut report = create_report(&[
+            ("app", "1.0.0", 0, 0),
+            ("libc", "0.2.80", 0, 0),
+            ("proptest", "1.0.0", 0, 0),
+            ("rand", "0.7.3", 0, 0),
+        ]);
+        add_dev_dependency(&mut report, "app", "proptest");
+        let libc_id = find_package_id(&report, "libc");
+        let rand_id = find_package_id(&report, "rand");
+        for (name, dependency_id) in &[("app", libc_id), ("proptest", rand_id)]
+        {
+            let package_id = find_package_id(&report, name);
+            report
+                .packages
+                .get_mut(&package_id)
+                .unwrap()
+                .package
+                .dependencies
+                .insert(dependency_id.clone());
+        }
+
+        let mut test_only_names = test_only_package_ids(&report)
+            .into_iter()
+            .map(|package_id| package_id.name.as_str())
+            .collect::<Vec<&str>>();
+        test_only_names.sort();
+
+        assert_eq!(test_only_names, vec!["proptest", "rand"]);
+    }
+
+    #[rstest]
+    fn save_baseline_test() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let baseline_path = temp_dir.path().join("geiger-baseline.json");
+        let baseline = create_report(&[("libc", "0.2.80", 12, 2)]);
+
+        save_baseline(&baseline_path, &baseline).unwrap();
+
+        assert_eq!(read_report(&baseline_path).unwrap(), baseline);
+    }
+
+    fn add_dev_dependency(
+        report: &mut SafetyReport,
+        name: &str,
+        dependency_name: &str,
+    ) {
+        let package_id = find_package_id(report, name);
+        let dependency_id = find_package_id(report, dependency_name);
+        report
+            .packages
+            .get_mut(&package_id)
+            .unwrap()
+            .package
+            .dev_dependencies
+            .insert(dependency_id);
+    }
+
+    fn find_package_id(
+        report: &SafetyReport,
+        name: &str,
+    ) -> cargo_geiger_serde::PackageId {
+        report
+            .packages
+            .keys()
+            .find(|package_id| package_id.name == name)
+            .unwrap()
+            .clone()
+    }
+
+    fn create_report(packages: &[(&str, &str, u64, u64)]) -> SafetyReport {
+        let mut report = SafetyReport::default();
+        for (name, version, unsafe_exprs, unsafe_functions) in packages {
+            let package = PackageInfo::new(cargo_geiger_serde::PackageId {
+                name: String::from(*name),
+                version: Version::parse(version).unwrap(),
+                source: Source::Registry {
+                    name: String::from("crates.io"),
+                    url: Url::parse(
+                        "https://github.com/rust-lang/crates.io-index",
+                    )
+                    .unwrap(),
+                },
+            });
+            let mut unsafety = UnsafeInfo::default();
+            unsafety.used.exprs.unsafe_ = *unsafe_exprs;
+            unsafety.used.functions.unsafe_ = *unsafe_functions;
+            report
+                .packages
+                .insert(package.id.clone(), ReportEntry { package, unsafety });
+        }
+        report
+    }
+}
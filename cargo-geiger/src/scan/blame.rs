@@ -0,0 +1,207 @@
+use crate::rs_file::canonicalize;
+
+use super::GeigerContext;
+
+use cargo::core::Workspace;
+use cargo_geiger_serde::UnsafeBlame;
+use geiger::Location;
+use git2::Repository;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Used for lines that are not part of any commit yet, the same way as
+/// `git blame` does.
+const NOT_COMMITTED_YET: &str = "Not Committed Yet";
+
+/// Finds the last commit and author that touched each occurrence of the
+/// `unsafe` keyword in the workspace members. Files outside of a git
+/// repository are skipped.
+pub fn blame_workspace_unsafe(
+    geiger_context: &GeigerContext,
+    workspace: &Workspace,
+) -> Vec<UnsafeBlame> {
+    let mut unsafe_blame = workspace
+        .members()
+        .filter_map(|package| {
+            geiger_context
+                .package_id_to_metrics
+                .get(&package.package_id())
+        })
+        .flat_map(|package_metrics| package_metrics.rs_path_to_metrics.iter())
+        .filter(|(_, rs_file_metrics_wrapper)| {
+            !rs_file_metrics_wrapper.metrics.unsafe_locations.is_empty()
+        })
+        .flat_map(|(path_buf, rs_file_metrics_wrapper)| {
+            blame_file(
+                path_buf,
+                &rs_file_metrics_wrapper.metrics.unsafe_locations,
+            )
+        })
+        .collect::<Vec<UnsafeBlame>>();
+
+    unsafe_blame.sort();
+    unsafe_blame
+}
+
+/// Lists each blamed unsafe occurrence, followed by the number of unsafe
+/// occurrences per author and per commit, most first.
+pub fn construct_blame_lines(unsafe_blame: &[UnsafeBlame]) -> Vec<String> {
+    let mut blame_lines = vec![String::from("Unsafe usage by last commit:")];
+    blame_lines.push(String::new());
+    for blame in unsafe_blame {
+        blame_lines.push(format!(
+            "{}:{}:{}  {} {}",
+            blame.path.display(),
+            blame.line,
+            blame.column,
+            blame.commit,
+            blame.author
+        ));
+    }
+    blame_lines.push(String::new());
+
+    blame_lines.push(String::from("Unsafe usage per author:"));
+    blame_lines.push(String::new());
+    blame_lines.append(&mut construct_count_lines(
+        unsafe_blame.iter().map(|blame| blame.author.clone()),
+    ));
+    blame_lines.push(String::new());
+
+    blame_lines.push(String::from("Unsafe usage per commit:"));
+    blame_lines.push(String::new());
+    blame_lines.append(&mut construct_count_lines(
+        unsafe_blame.iter().map(|blame| blame.commit.clone()),
+    ));
+    blame_lines.push(String::new());
+
+    blame_lines
+}
+
+fn blame_file(path: &Path, locations: &[Location]) -> Vec<UnsafeBlame> {
+    let repository = match Repository::discover(path) {
+        Ok(repository) => repository,
+        Err(_) => return vec![],
+    };
+    let relative_path = match repository
+        .workdir()
+        .and_then(|workdir| canonicalize(workdir).ok())
+        .and_then(|workdir| {
+            path.strip_prefix(workdir).ok().map(Path::to_path_buf)
+        }) {
+        Some(relative_path) => relative_path,
+        None => return vec![],
+    };
+    let blame = match repository.blame_file(&relative_path, None) {
+        Ok(blame) => blame,
+        Err(_) => return vec![],
+    };
+
+    locations
+        .iter()
+        .map(|location| {
+            let (commit, author) = match blame.get_line(location.line) {
+                Some(hunk) if !hunk.final_commit_id().is_zero() => {
+                    let mut commit = hunk.final_commit_id().to_string();
+                    commit.truncate(7);
+                    let author = hunk
+                        .final_signature()
+                        .name()
+                        .unwrap_or_default()
+                        .to_string();
+                    (commit, author)
+                }
+                _ => (String::from("0000000"), String::from(NOT_COMMITTED_YET)),
+            };
+            UnsafeBlame {
+                path: path.to_path_buf(),
+                line: location.line,
+                column: location.column,
+                commit,
+                author,
+            }
+        })
+        .collect()
+}
+
+fn construct_count_lines<I>(keys: I) -> Vec<String>
+where
+    I: Iterator<Item = String>,
+{
+    let mut counts = HashMap::<String, u64>::new();
+    for key in keys {
+        *counts.entry(key).or_insert(0) += 1;
+    }
+    let mut counts = counts.into_iter().collect::<Vec<_>>();
+    counts.sort_by(|(a_key, a_count), (b_key, b_count)| {
+        b_count.cmp(a_count).then_with(|| a_key.cmp(b_key))
+    });
+
+    counts
+        .into_iter()
+        .map(|(key, count)| format!("{: <6} {}", count, key))
+        .collect()
+}
+
+#[cfg(test)]
+mod blame_tests {
+    use super::*;
+
+    use rstest::*;
+    use std::path::PathBuf;
+
+    #[rstest]
+    fn construct_blame_lines_test() {
+        let unsafe_blame = vec![
+            create_unsafe_blame("src/lib.rs", 3, "abcdef1", "Alice"),
+            create_unsafe_blame("src/lib.rs", 7, "1234567", "Bob"),
+            create_unsafe_blame("src/main.rs", 2, "1234567", "Bob"),
+        ];
+
+        assert_eq!(
+            construct_blame_lines(&unsafe_blame),
+            vec![
+                String::from("Unsafe usage by last commit:"),
+                String::new(),
+                String::from("src/lib.rs:3:4  abcdef1 Alice"),
+                String::from("src/lib.rs:7:4  1234567 Bob"),
+                String::from("src/main.rs:2:4  1234567 Bob"),
+                String::new(),
+                String::from("Unsafe usage per author:"),
+                String::new(),
+                String::from("2      Bob"),
+                String::from("1      Alice"),
+                String::new(),
+                String::from("Unsafe usage per commit:"),
+                String::new(),
+                String::from("2      1234567"),
+                String::from("1      abcdef1"),
+                String::new(),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn blame_file_outside_of_repository_test() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().canonicalize().unwrap().join("lib.rs");
+
+        assert!(
+            blame_file(&path, &[Location { line: 1, column: 0 }]).is_empty()
+        );
+    }
+
+    fn create_unsafe_blame(
+        path: &str,
+        line: usize,
+        commit: &str,
+        author: &str,
+    ) -> UnsafeBlame {
+        UnsafeBlame {
+            path: PathBuf::from(path),
+            line,
+            column: 4,
+            commit: String::from(commit),
+            author: String::from(author),
+        }
+    }
+}
@@ -0,0 +1,675 @@
+use crate::args::Args;
+use crate::format::print_config::PrintConfig;
+use crate::rs_file::{canonicalize, RsFileMetricsWrapper};
+
+use super::find::find_rs_files_in_dir;
+use super::{FoldedFiles, PackageMetrics};
+
+use cargo::core::{Package, PackageId, Workspace};
+use cargo::util::{short_hash, Sha256};
+use cargo::{CliError, CliResult, Config};
+use geiger::{IncludeTests, RsFileMetrics};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+/// Overrides the default metrics cache location, `$CARGO_HOME/geiger-cache`.
+/// The `--cache-dir` flag takes precedence over this.
+pub const CACHE_DIR_ENV: &str = "CARGO_GEIGER_CACHE_DIR";
+
+const CACHE_DIR_NAME: &str = "geiger-cache";
+const LAST_RUN_FILE_NAME: &str = "last-run.json";
+const ENTRY_EXTENSION: &str = "json";
+const USED_FILES_ENTRY_PREFIX: &str = "used-files-";
+
+const VERSION: Option<&'static str> = option_env!("CARGO_PKG_VERSION");
+
+/// The `cargo geiger cache` subcommands.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CacheCommand {
+    /// Show the entry count, total size and hit rate of the last run.
+    Stats,
+
+    /// Remove cache entries, optionally only those older than `--max-age`.
+    Prune,
+}
+
+impl FromStr for CacheCommand {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<CacheCommand, &'static str> {
+        match s {
+            "stats" => Ok(CacheCommand::Stats),
+            "prune" => Ok(CacheCommand::Prune),
+            _ => Err("Unrecognised cache command, expected: stats, prune"),
+        }
+    }
+}
+
+/// Caches the scan results of registry and git packages on disk. The entries
+/// are keyed by the package id and a digest of the `.rs` files of the
+/// package, so the results are reused until the sources, the scan options or
+/// the cargo-geiger version change.
+pub struct MetricsCache {
+    dir: PathBuf,
+    include_tests: IncludeTests,
+    unsafe_apis: Vec<String>,
+    run_stats: RunStats,
+    /// The entry path of each package looked up, digesting the sources once
+    /// per run. `None` if the sources could not be read.
+    entry_paths: HashMap<PackageId, Option<PathBuf>>,
+}
+
+impl MetricsCache {
+    pub fn new(config: &Config, print_config: &PrintConfig) -> Self {
+        MetricsCache {
+            dir: cache_dir(print_config.cache_dir.as_deref(), config),
+            include_tests: print_config.include_tests,
+            unsafe_apis: print_config.unsafe_apis.clone(),
+            run_stats: RunStats::default(),
+            entry_paths: HashMap::new(),
+        }
+    }
+
+    /// Returns the cached metrics for the package, if any. Only registry and
+    /// git packages are cached.
+    pub fn get(&mut self, package: &Package) -> Option<PackageMetrics> {
+        if !is_cached_source(package) {
+            return None;
+        }
+        let entry_path = self.entry_path(package)?;
+        let cache_entry = File::open(entry_path).ok().and_then(|file| {
+            serde_json::from_reader::<_, CacheEntry>(file).ok()
+        });
+        match cache_entry {
+            Some(cache_entry) => {
+                self.run_stats.hits += 1;
+                Some(cache_entry.into())
+            }
+            None => {
+                self.run_stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Stores the metrics for the package, failures are ignored since the
+    /// cache is only an optimization.
+    pub fn put(&mut self, package: &Package, package_metrics: &PackageMetrics) {
+        if !is_cached_source(package) {
+            return;
+        }
+        let entry_path = match self.entry_path(package) {
+            Some(entry_path) => entry_path,
+            None => return,
+        };
+        if fs::create_dir_all(&self.dir).is_err() {
+            return;
+        }
+        if let Ok(file) = File::create(entry_path) {
+            let cache_entry = CacheEntryRef::from(package_metrics);
+            let _ = serde_json::to_writer(file, &cache_entry);
+        }
+    }
+
+    /// Saves the hits and misses of this run, for `cargo geiger cache stats`.
+    pub fn save_run_stats(&self) {
+        if self.run_stats.hits + self.run_stats.misses == 0 {
+            return;
+        }
+        if fs::create_dir_all(&self.dir).is_err() {
+            return;
+        }
+        if let Ok(file) = File::create(self.dir.join(LAST_RUN_FILE_NAME)) {
+            let _ = serde_json::to_writer(file, &self.run_stats);
+        }
+    }
+
+    fn entry_path(&mut self, package: &Package) -> Option<PathBuf> {
+        let dir = &self.dir;
+        let include_tests = self.include_tests;
+        let unsafe_apis = &self.unsafe_apis;
+        self.entry_paths
+            .entry(package.package_id())
+            .or_insert_with(|| {
+                let sources_digest = digest_rs_files(package.root())?;
+                let hash = short_hash(&(
+                    VERSION,
+                    package.package_id(),
+                    package.root(),
+                    include_tests == IncludeTests::Yes,
+                    unsafe_apis,
+                    sources_digest,
+                ));
+                Some(dir.join(format!(
+                    "{}-{}-{}.{}",
+                    package.name(),
+                    package.version(),
+                    hash,
+                    ENTRY_EXTENSION
+                )))
+            })
+            .clone()
+    }
+}
+
+/// Registry and git packages are cached, the sources of path packages are
+/// edited in place and can include files from outside of the package.
+fn is_cached_source(package: &Package) -> bool {
+    let source_id = package.package_id().source_id();
+    source_id.is_registry() || source_id.is_git()
+}
+
+/// The SHA-256 digest of the paths, relative to the package root where
+/// possible, and the contents of the `.rs` files of a package, or `None` if
+/// any of them can not be read.
+fn digest_rs_files(package_root: &Path) -> Option<String> {
+    let package_root = canonicalize(package_root).ok()?;
+    let mut rs_files = find_rs_files_in_dir(&package_root).collect::<Vec<_>>();
+    rs_files.sort();
+    let mut sha256 = Sha256::new();
+    for rs_file in rs_files {
+        let contents = fs::read(&rs_file).ok()?;
+        let relative_path =
+            rs_file.strip_prefix(&package_root).unwrap_or(&rs_file);
+        sha256.update(relative_path.to_string_lossy().as_bytes());
+        sha256.update(&(contents.len() as u64).to_le_bytes());
+        sha256.update(&contents);
+    }
+    Some(sha256.finish_hex())
+}
+
+/// Caches the source files used by the build of a workspace, so that
+/// `--cached` runs can skip the build. The entry is keyed by the contents of
+/// `Cargo.lock` and the options that change what is built, any dependency
+/// change invalidates it.
+pub struct UsedFilesCache {
+    entry_path: Option<PathBuf>,
+}
+
+impl UsedFilesCache {
+    pub fn new(config: &Config, args: &Args, workspace: &Workspace) -> Self {
+        let dir = cache_dir(args.cache_dir.as_deref(), config);
+        let entry_path = fs::read(workspace.root().join("Cargo.lock"))
+            .ok()
+            .map(|lockfile| {
+                // The target includes the `build.target` of the cargo
+                // configuration, and `RUSTC` is set to the rustc of the
+                // selected toolchain, if any.
+                let hash = short_hash(&(
+                    VERSION,
+                    workspace.root(),
+                    lockfile,
+                    &args.features,
+                    args.all_features,
+                    args.no_default_features,
+                    (args.lib, args.bins, args.examples, args.tests),
+                    &args.target,
+                    (&args.toolchain, env::var_os("RUSTC")),
+                    &args.unstable_flags,
+                ));
+                dir.join(format!(
+                    "{}{}.{}",
+                    USED_FILES_ENTRY_PREFIX, hash, ENTRY_EXTENSION
+                ))
+            });
+        UsedFilesCache { entry_path }
+    }
+
+    /// Returns the source files used by the last build with the same
+    /// lockfile and options, if any. Workspace member files added since then
+    /// are counted as used, since it cannot be told without a build.
+    pub fn get(&self, workspace: &Workspace) -> Option<HashSet<PathBuf>> {
+        let file = File::open(self.entry_path.as_ref()?).ok()?;
+        let used_files_entry =
+            serde_json::from_reader::<_, UsedFilesEntry>(file).ok()?;
+        Some(used_files_entry.into_rs_files_used(find_workspace_rs_files(
+            workspace,
+        )))
+    }
+
+    /// Stores the source files used by the build, failures are ignored since
+    /// the cache is only an optimization.
+    pub fn put(&self, workspace: &Workspace, rs_files_used: &HashSet<PathBuf>) {
+        let entry_path = match &self.entry_path {
+            Some(entry_path) => entry_path,
+            None => return,
+        };
+        if let Some(dir) = entry_path.parent() {
+            if fs::create_dir_all(dir).is_err() {
+                return;
+            }
+        }
+        if let Ok(file) = File::create(entry_path) {
+            let used_files_entry = UsedFilesEntry {
+                rs_files_used: rs_files_used.clone(),
+                workspace_files: find_workspace_rs_files(workspace),
+            };
+            let _ = serde_json::to_writer(file, &used_files_entry);
+        }
+    }
+}
+
+fn find_workspace_rs_files(workspace: &Workspace) -> HashSet<PathBuf> {
+    workspace
+        .members()
+        .flat_map(|member| find_rs_files_in_dir(member.root()))
+        .collect()
+}
+
+pub fn run_cache_command(
+    cache_command: CacheCommand,
+    args: &Args,
+    config: &Config,
+) -> CliResult {
+    let dir = cache_dir(args.cache_dir.as_deref(), config);
+    let cache_lines = match cache_command {
+        CacheCommand::Stats => {
+            let entries = list_entries(&dir).map_err(cache_error)?;
+            let run_stats = File::open(dir.join(LAST_RUN_FILE_NAME))
+                .ok()
+                .and_then(|file| serde_json::from_reader(file).ok());
+            construct_stats_lines(&dir, &entries, run_stats)
+        }
+        CacheCommand::Prune => {
+            let max_age =
+                args.max_age.map(|days| Duration::from_secs(days * 86_400));
+            let removed_entries =
+                prune_entries(&dir, max_age).map_err(cache_error)?;
+            vec![format!(
+                "Removed {} cache entries, freed {}.",
+                removed_entries.len(),
+                format_size(
+                    removed_entries.iter().map(|entry| entry.size).sum()
+                )
+            )]
+        }
+    };
+    for cache_line in cache_lines {
+        println!("{}", cache_line);
+    }
+    Ok(())
+}
+
+/// The cache location, from `--cache-dir`, `CARGO_GEIGER_CACHE_DIR` or the
+/// default location under `$CARGO_HOME`, in that order.
+pub(super) fn cache_dir(
+    cache_dir_arg: Option<&Path>,
+    config: &Config,
+) -> PathBuf {
+    match (cache_dir_arg, env::var_os(CACHE_DIR_ENV)) {
+        (Some(cache_dir), _) => cache_dir.to_path_buf(),
+        (None, Some(cache_dir)) => PathBuf::from(cache_dir),
+        (None, None) => config.home().as_path_unlocked().join(CACHE_DIR_NAME),
+    }
+}
+
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+struct RunStats {
+    hits: u64,
+    misses: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+struct UsedFilesEntry {
+    rs_files_used: HashSet<PathBuf>,
+    /// All source files of the workspace members at the time of the build.
+    workspace_files: HashSet<PathBuf>,
+}
+
+impl UsedFilesEntry {
+    /// The used files, together with the workspace files that did not exist
+    /// at the time of the build.
+    fn into_rs_files_used(
+        self,
+        workspace_files: HashSet<PathBuf>,
+    ) -> HashSet<PathBuf> {
+        let mut rs_files_used = self.rs_files_used;
+        for path in workspace_files {
+            if !self.workspace_files.contains(&path) {
+                rs_files_used.insert(path);
+            }
+        }
+        rs_files_used
+    }
+}
+
+#[derive(Deserialize)]
+struct CacheEntry {
+    files: Vec<CachedRsFile>,
+}
+
+impl From<CacheEntry> for PackageMetrics {
+    fn from(cache_entry: CacheEntry) -> Self {
+        let rs_path_to_metrics = cache_entry
+            .files
+            .into_iter()
+            .map(|cached_rs_file| {
+                (
+                    cached_rs_file.path,
+                    RsFileMetricsWrapper {
+                        metrics: cached_rs_file.metrics,
+                        is_crate_entry_point: cached_rs_file
+                            .is_crate_entry_point,
+                        is_generated: false,
+                    },
+                )
+            })
+            .collect();
+        PackageMetrics {
+            rs_path_to_metrics,
+            folded: FoldedFiles::default(),
+            not_fully_scanned: false,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct CachedRsFile {
+    path: PathBuf,
+    is_crate_entry_point: bool,
+    metrics: RsFileMetrics,
+}
+
+/// Borrowing counterpart of `CacheEntry`, to avoid cloning the metrics when
+/// storing them.
+#[derive(Serialize)]
+struct CacheEntryRef<'a> {
+    files: Vec<CachedRsFileRef<'a>>,
+}
+
+impl<'a> From<&'a PackageMetrics> for CacheEntryRef<'a> {
+    fn from(package_metrics: &'a PackageMetrics) -> Self {
+        let files = package_metrics
+            .rs_path_to_metrics
+            .iter()
+            .map(|(path, rs_file_metrics_wrapper)| CachedRsFileRef {
+                path,
+                is_crate_entry_point: rs_file_metrics_wrapper
+                    .is_crate_entry_point,
+                metrics: &rs_file_metrics_wrapper.metrics,
+            })
+            .collect();
+        CacheEntryRef { files }
+    }
+}
+
+#[derive(Serialize)]
+struct CachedRsFileRef<'a> {
+    path: &'a Path,
+    is_crate_entry_point: bool,
+    metrics: &'a RsFileMetrics,
+}
+
+#[derive(Debug, PartialEq)]
+struct EntryInfo {
+    size: u64,
+    modified: SystemTime,
+}
+
+fn list_entries(dir: &Path) -> io::Result<Vec<(PathBuf, EntryInfo)>> {
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut entries = vec![];
+    for dir_entry in fs::read_dir(dir)? {
+        let path = dir_entry?.path();
+        let is_entry = path
+            .extension()
+            .is_some_and(|extension| extension == ENTRY_EXTENSION)
+            && path
+                .file_name()
+                .is_some_and(|file_name| file_name != LAST_RUN_FILE_NAME);
+        if !is_entry {
+            continue;
+        }
+        let metadata = fs::metadata(&path)?;
+        entries.push((
+            path,
+            EntryInfo {
+                size: metadata.len(),
+                modified: metadata.modified()?,
+            },
+        ));
+    }
+    Ok(entries)
+}
+
+/// Removes the entries older than `max_age`, or all entries if no max age is
+/// given.
+fn prune_entries(
+    dir: &Path,
+    max_age: Option<Duration>,
+) -> io::Result<Vec<EntryInfo>> {
+    let now = SystemTime::now();
+    let mut removed_entries = vec![];
+    for (path, entry_info) in list_entries(dir)? {
+        let is_expired = match max_age {
+            Some(max_age) => now
+                .duration_since(entry_info.modified)
+                .is_ok_and(|age| age > max_age),
+            None => true,
+        };
+        if is_expired {
+            fs::remove_file(path)?;
+            removed_entries.push(entry_info);
+        }
+    }
+    Ok(removed_entries)
+}
+
+fn construct_stats_lines(
+    dir: &Path,
+    entries: &[(PathBuf, EntryInfo)],
+    run_stats: Option<RunStats>,
+) -> Vec<String> {
+    let mut stats_lines = vec![
+        format!("Cache directory: {}", dir.display()),
+        format!("Entries:         {}", entries.len()),
+        format!(
+            "Total size:      {}",
+            format_size(entries.iter().map(|(_, entry)| entry.size).sum())
+        ),
+    ];
+    stats_lines.push(match run_stats {
+        Some(run_stats) => format!(
+            "Last run:        {} hits, {} misses, {:.1}% hit rate",
+            run_stats.hits,
+            run_stats.misses,
+            100.0 * run_stats.hits as f64
+                / (run_stats.hits + run_stats.misses) as f64
+        ),
+        None => String::from("Last run:        no cache lookups recorded"),
+    });
+    stats_lines
+}
+
+fn format_size(bytes: u64) -> String {
+    const KIB: u64 = 1024;
+    const MIB: u64 = 1024 * KIB;
+    if bytes >= MIB {
+        format!("{:.1} MiB", bytes as f64 / MIB as f64)
+    } else if bytes >= KIB {
+        format!("{:.1} KiB", bytes as f64 / KIB as f64)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+fn cache_error(error: io::Error) -> CliError {
+    CliError::new(anyhow::Error::new(CacheError { error }), 1)
+}
+
+#[derive(Debug)]
+struct CacheError {
+    error: io::Error,
+}
+
+impl Error for CacheError {}
+
+impl fmt::Display for CacheError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "failed to access the cache: {}", self.error)
+    }
+}
+
+#[cfg(test)]
+mod cache_tests {
+    use super::*;
+
+    use rstest::*;
+    use std::collections::HashMap;
+
+    #[rstest(
+        input_str,
+        expected_cache_command,
+        case("stats", Ok(CacheCommand::Stats)),
+        case("prune", Ok(CacheCommand::Prune)),
+        case("clear", Err(()))
+    )]
+    fn cache_command_from_str_test(
+        input_str: &str,
+        expected_cache_command: Result<CacheCommand, ()>,
+    ) {
+        assert_eq!(
+            CacheCommand::from_str(input_str).map_err(|_| ()),
+            expected_cache_command
+        );
+    }
+
+    #[rstest]
+    fn digest_rs_files_test() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src").join("lib.rs"), "mod a;").unwrap();
+        fs::write(dir.path().join("src").join("a.rs"), "").unwrap();
+        let digest = digest_rs_files(dir.path()).unwrap();
+
+        fs::write(dir.path().join("README.md"), "# a").unwrap();
+        assert_eq!(digest_rs_files(dir.path()).unwrap(), digest);
+
+        fs::write(dir.path().join("src").join("a.rs"), "fn f() {}").unwrap();
+        assert_ne!(digest_rs_files(dir.path()).unwrap(), digest);
+
+        fs::write(dir.path().join("src").join("a.rs"), "").unwrap();
+        fs::rename(
+            dir.path().join("src").join("a.rs"),
+            dir.path().join("src").join("b.rs"),
+        )
+        .unwrap();
+        assert_ne!(digest_rs_files(dir.path()).unwrap(), digest);
+    }
+
+    #[rstest]
+    fn cache_entry_round_trip_test() {
+        let mut rs_file_metrics_wrapper = RsFileMetricsWrapper {
+            is_crate_entry_point: true,
+            ..RsFileMetricsWrapper::default()
+        };
+        rs_file_metrics_wrapper.metrics.forbids_unsafe = true;
+        rs_file_metrics_wrapper.metrics.counters.exprs.unsafe_ = 3;
+        let mut rs_path_to_metrics = HashMap::new();
+        rs_path_to_metrics
+            .insert(PathBuf::from("src/lib.rs"), rs_file_metrics_wrapper);
+        let package_metrics = PackageMetrics {
+            rs_path_to_metrics,
+            ..Default::default()
+        };
+
+        let json =
+            serde_json::to_string(&CacheEntryRef::from(&package_metrics))
+                .unwrap();
+        let round_tripped: PackageMetrics =
+            serde_json::from_str::<CacheEntry>(&json).unwrap().into();
+
+        let wrapper =
+            &round_tripped.rs_path_to_metrics[Path::new("src/lib.rs")];
+        assert!(wrapper.is_crate_entry_point);
+        assert!(wrapper.metrics.forbids_unsafe);
+        assert_eq!(wrapper.metrics.counters.exprs.unsafe_, 3);
+    }
+
+    #[rstest]
+    fn used_files_entry_into_rs_files_used_test() {
+        let paths = |paths: &[&str]| -> HashSet<PathBuf> {
+            paths.iter().map(PathBuf::from).collect()
+        };
+        let used_files_entry = UsedFilesEntry {
+            rs_files_used: paths(&["dep/lib.rs", "ws/lib.rs"]),
+            workspace_files: paths(&["ws/lib.rs", "ws/unused.rs"]),
+        };
+
+        let rs_files_used = used_files_entry.into_rs_files_used(paths(&[
+            "ws/lib.rs",
+            "ws/unused.rs",
+            "ws/added.rs",
+        ]));
+
+        assert_eq!(
+            rs_files_used,
+            paths(&["dep/lib.rs", "ws/lib.rs", "ws/added.rs"])
+        );
+    }
+
+    #[rstest]
+    fn prune_entries_test() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::write(temp_dir.path().join("a-1.0.0-abc.json"), "{}").unwrap();
+        fs::write(temp_dir.path().join(LAST_RUN_FILE_NAME), "{}").unwrap();
+
+        let kept_entries =
+            prune_entries(temp_dir.path(), Some(Duration::from_secs(3600)))
+                .unwrap();
+        assert!(kept_entries.is_empty());
+
+        let removed_entries = prune_entries(temp_dir.path(), None).unwrap();
+        assert_eq!(removed_entries.len(), 1);
+        assert!(temp_dir.path().join(LAST_RUN_FILE_NAME).exists());
+    }
+
+    #[rstest(
+        input_bytes,
+        expected_size,
+        case(512, "512 B"),
+        case(2048, "2.0 KiB"),
+        case(3 * 1024 * 1024, "3.0 MiB")
+    )]
+    fn format_size_test(input_bytes: u64, expected_size: &str) {
+        assert_eq!(format_size(input_bytes), expected_size);
+    }
+
+    #[rstest]
+    fn construct_stats_lines_test() {
+        let stats_lines = construct_stats_lines(
+            Path::new("/cache"),
+            &[(
+                PathBuf::from("/cache/a-1.0.0-abc.json"),
+                EntryInfo {
+                    size: 2048,
+                    modified: SystemTime::now(),
+                },
+            )],
+            Some(RunStats { hits: 3, misses: 1 }),
+        );
+
+        assert_eq!(
+            stats_lines,
+            vec![
+                String::from("Cache directory: /cache"),
+                String::from("Entries:         1"),
+                String::from("Total size:      2.0 KiB"),
+                String::from(
+                    "Last run:        3 hits, 1 misses, 75.0% hit rate"
+                ),
+            ]
+        );
+    }
+}
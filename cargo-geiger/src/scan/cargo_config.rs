@@ -0,0 +1,103 @@
+use crate::cli::get_config_target;
+
+use cargo::util::CargoResult;
+use cargo::Config;
+use cargo_geiger_serde::CargoConfigSettings;
+use std::collections::BTreeMap;
+
+/// The settings of the cargo configuration the scan was run with, for the
+/// report. The build and the resolution apply them through the `Config`,
+/// this only records them. `target` is the target built for, the configured
+/// one is only recorded when it was not replaced with `--target`.
+pub fn collect_cargo_config_settings(
+    config: &Config,
+    target: Option<&str>,
+) -> CargoResult<Option<CargoConfigSettings>> {
+    let cargo_config_settings = CargoConfigSettings {
+        target: get_config_target(config)?
+            .filter(|config_target| Some(config_target.as_str()) == target),
+        offline: config.get::<Option<bool>>("net.offline")?.unwrap_or(false),
+        proxy: config.get::<Option<String>>("http.proxy")?.is_some(),
+        profile_dev: config
+            .get::<Option<BTreeMap<String, toml::Value>>>("profile.dev")?
+            .map(|profile| profile.keys().cloned().collect())
+            .unwrap_or_default(),
+    };
+    if cargo_config_settings == CargoConfigSettings::default() {
+        Ok(None)
+    } else {
+        Ok(Some(cargo_config_settings))
+    }
+}
+
+#[cfg(test)]
+mod cargo_config_tests {
+    use super::*;
+
+    use cargo::core::shell::Shell;
+    use rstest::*;
+    use std::fs;
+
+    #[rstest]
+    fn collect_cargo_config_settings_test() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join(".cargo")).unwrap();
+        fs::write(
+            dir.path().join(".cargo").join("config.toml"),
+            "[build]\n\
+             target = \"wasm32-unknown-unknown\"\n\
+             [net]\n\
+             offline = true\n\
+             [http]\n\
+             proxy = \"user:secret@proxy.example.com:8080\"\n\
+             [profile.dev]\n\
+             opt-level = 1\n\
+             debug-assertions = false\n",
+        )
+        .unwrap();
+        let config = Config::new(
+            Shell::new(),
+            dir.path().to_path_buf(),
+            dir.path().join("cargo-home"),
+        );
+
+        assert_eq!(
+            collect_cargo_config_settings(
+                &config,
+                Some("wasm32-unknown-unknown")
+            )
+            .unwrap(),
+            Some(CargoConfigSettings {
+                target: Some(String::from("wasm32-unknown-unknown")),
+                offline: true,
+                proxy: true,
+                profile_dev: vec![
+                    String::from("debug-assertions"),
+                    String::from("opt-level"),
+                ],
+            })
+        );
+        assert_eq!(
+            collect_cargo_config_settings(
+                &config,
+                Some("x86_64-pc-windows-msvc")
+            )
+            .unwrap()
+            .unwrap()
+            .target,
+            None
+        );
+    }
+
+    #[rstest]
+    fn collect_cargo_config_settings_without_config_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config::new(
+            Shell::new(),
+            dir.path().to_path_buf(),
+            dir.path().join("cargo-home"),
+        );
+
+        assert_eq!(collect_cargo_config_settings(&config, None).unwrap(), None);
+    }
+}
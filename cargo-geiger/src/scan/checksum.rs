@@ -0,0 +1,260 @@
+use crate::graph::Graph;
+
+use cargo::core::{PackageId, PackageSet, Workspace};
+use cargo::ops;
+use cargo::util::{CargoResult, Sha256};
+use flate2::read::GzDecoder;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// The file cargo writes into an unpacked package when unpacking is done, it
+/// is not part of the downloaded archive.
+const UNPACK_MARKER: &str = ".cargo-ok";
+
+/// Verifies the registry packages in the graph against the checksums pinned
+/// in `Cargo.lock`. The downloaded archive of a package must match its
+/// checksum and the unpacked source, which is what gets scanned and built,
+/// must match the archive. Packages without a pinned checksum or without a
+/// downloaded archive, like vendored sources, cannot be verified and are
+/// skipped.
+pub fn find_checksum_mismatches(
+    workspace: &Workspace,
+    package_set: &PackageSet,
+    graph: &Graph,
+) -> CargoResult<BTreeMap<PackageId, Vec<String>>> {
+    let lockfile = match ops::load_pkg_lockfile(workspace)? {
+        Some(lockfile) => lockfile,
+        None => return Ok(BTreeMap::new()),
+    };
+    let mut checksum_mismatches = BTreeMap::new();
+    for node in graph.graph.raw_nodes().iter().map(|node| &node.weight) {
+        if !node.id.source_id().is_registry() {
+            continue;
+        }
+        let checksum = match lockfile.checksums().get(&node.id) {
+            Some(Some(checksum)) => checksum,
+            _ => continue,
+        };
+        let package = package_set.get_one(node.id)?;
+        let archive = match archive_path(package.root(), node.id)
+            .and_then(|archive_path| fs::read(archive_path).ok())
+        {
+            Some(archive) => archive,
+            None => continue,
+        };
+        // A package that cannot be read is reported like a mismatch, it
+        // cannot be verified that its source is the one pinned.
+        let mismatches = verify_package_source(
+            &archive,
+            checksum,
+            &format!("{}-{}", node.id.name(), node.id.version()),
+            package.root(),
+        )
+        .unwrap_or_else(|e| {
+            vec![format!("the source could not be verified: {}", e)]
+        });
+        if !mismatches.is_empty() {
+            checksum_mismatches.insert(node.id, mismatches);
+        }
+    }
+    Ok(checksum_mismatches)
+}
+
+/// The downloaded archive of a registry package, which cargo keeps in the
+/// `cache` directory next to the `src` directory the package is unpacked
+/// into, under the same directory for the registry index.
+fn archive_path(package_root: &Path, package_id: PackageId) -> Option<PathBuf> {
+    let index_dir = package_root.parent()?;
+    let registry_dir = index_dir.parent()?.parent()?;
+    Some(
+        registry_dir
+            .join("cache")
+            .join(index_dir.file_name()?)
+            .join(format!(
+                "{}-{}.crate",
+                package_id.name(),
+                package_id.version()
+            )),
+    )
+}
+
+/// Describes how the archive differs from the checksum, or the unpacked
+/// source from the archive. The files in the archive are under
+/// `<name>-<version>/`.
+fn verify_package_source(
+    archive: &[u8],
+    checksum: &str,
+    archive_prefix: &str,
+    package_root: &Path,
+) -> io::Result<Vec<String>> {
+    let archive_checksum = Sha256::new().update(archive).finish_hex();
+    if archive_checksum != checksum {
+        return Ok(vec![format!(
+            "the downloaded archive has checksum {}, Cargo.lock pins {}",
+            archive_checksum, checksum
+        )]);
+    }
+    let mut mismatches = Vec::new();
+    let mut archive_files = BTreeSet::new();
+    let mut archive = tar::Archive::new(GzDecoder::new(archive));
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let relative_path = match entry.path()?.strip_prefix(archive_prefix) {
+            Ok(relative_path) => relative_path.to_path_buf(),
+            Err(_) => continue,
+        };
+        let mut archived_content = Vec::new();
+        entry.read_to_end(&mut archived_content)?;
+        match fs::read(package_root.join(&relative_path)) {
+            Ok(content) if content == archived_content => {}
+            Ok(_) => mismatches.push(format!(
+                "{} differs from the downloaded archive",
+                relative_path.display()
+            )),
+            Err(_) => mismatches.push(format!(
+                "{} of the downloaded archive is missing",
+                relative_path.display()
+            )),
+        }
+        archive_files.insert(relative_path);
+    }
+    for entry in WalkDir::new(package_root) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative_path = entry
+            .path()
+            .strip_prefix(package_root)
+            .expect("A walked path is below the walked directory");
+        if relative_path != Path::new(UNPACK_MARKER)
+            && !archive_files.contains(relative_path)
+        {
+            mismatches.push(format!(
+                "{} is not in the downloaded archive",
+                relative_path.display()
+            ));
+        }
+    }
+    Ok(mismatches)
+}
+
+#[cfg(test)]
+mod checksum_tests {
+    use super::*;
+
+    use cargo::core::SourceId;
+    use cargo::util::ToSemver;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use rstest::*;
+
+    #[rstest]
+    fn archive_path_test() {
+        let package_id = PackageId::new(
+            "libc",
+            "0.2.80".to_semver().unwrap(),
+            SourceId::from_url(
+                "registry+https://github.com/rust-lang/crates.io-index",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            archive_path(
+                Path::new(
+                    "/home/.cargo/registry/src/github.com-1ecc6299db9ec823/\
+                     libc-0.2.80"
+                ),
+                package_id
+            ),
+            Some(PathBuf::from(
+                "/home/.cargo/registry/cache/github.com-1ecc6299db9ec823/\
+                 libc-0.2.80.crate"
+            ))
+        );
+    }
+
+    #[rstest(
+        input_unpacked_files,
+        input_checksum_matches,
+        expected_mismatches,
+        case(
+            vec![("src/lib.rs", "pub fn f() {}"), (UNPACK_MARKER, "ok")],
+            true,
+            vec![]
+        ),
+        case(
+            vec![("src/lib.rs", "pub fn f() {}")],
+            false,
+            vec!["the downloaded archive has checksum"]
+        ),
+        case(
+            vec![("src/lib.rs", "pub unsafe fn f() {}")],
+            true,
+            vec!["src/lib.rs differs from the downloaded archive"]
+        ),
+        case(
+            vec![],
+            true,
+            vec!["src/lib.rs of the downloaded archive is missing"]
+        ),
+        case(
+            vec![("src/lib.rs", "pub fn f() {}"), ("src/extra.rs", "")],
+            true,
+            vec!["src/extra.rs is not in the downloaded archive"]
+        )
+    )]
+    fn verify_package_source_test(
+        input_unpacked_files: Vec<(&str, &str)>,
+        input_checksum_matches: bool,
+        expected_mismatches: Vec<&str>,
+    ) {
+        let mut builder = tar::Builder::new(GzEncoder::new(
+            Vec::new(),
+            Compression::default(),
+        ));
+        let content = b"pub fn f() {}";
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        builder
+            .append_data(&mut header, "foo-1.0.0/src/lib.rs", &content[..])
+            .unwrap();
+        let archive = builder.into_inner().unwrap().finish().unwrap();
+        let checksum = if input_checksum_matches {
+            Sha256::new().update(&archive).finish_hex()
+        } else {
+            String::from("0000")
+        };
+        let package_root = tempfile::tempdir().unwrap();
+        for (path, content) in input_unpacked_files {
+            let path = package_root.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+
+        let mismatches = verify_package_source(
+            &archive,
+            &checksum,
+            "foo-1.0.0",
+            package_root.path(),
+        )
+        .unwrap();
+
+        assert_eq!(mismatches.len(), expected_mismatches.len());
+        for (mismatch, expected_mismatch) in
+            mismatches.iter().zip(expected_mismatches)
+        {
+            assert!(mismatch.starts_with(expected_mismatch));
+        }
+    }
+}
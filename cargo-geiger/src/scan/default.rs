@@ -1,36 +1,112 @@
+mod api;
+mod bundle;
+mod fleet;
+mod serve;
 mod table;
+mod verify;
 
-use crate::args::Args;
+use crate::args::{Args, Subcommand};
+use crate::cli::{get_cfgs, ALL_TARGETS};
+use crate::format::markdown::render_markdown_report;
 use crate::format::print_config::OutputFormat;
+use crate::format::security_insights::security_insights_from_report;
+use crate::format::treemap::treemap_from_report;
 use crate::graph::Graph;
-use crate::rs_file::resolve_rs_file_deps;
+use crate::rs_file::{
+    dedup_paths_ignoring_case, is_case_insensitive_dir, resolve_rs_file_deps,
+};
 
-use super::find::find_unsafe;
+use super::attest::{write_attestation, GeigerSummary};
+use super::baseline::{
+    check_baseline_regressions, construct_baseline_report,
+    list_baseline_regressions, save_baseline,
+};
+use super::blame::blame_workspace_unsafe;
+use super::cache::UsedFilesCache;
+use super::cargo_config::collect_cargo_config_settings;
+use super::checksum::find_checksum_mismatches;
+use super::duplicates::find_duplicated_files;
+use super::find::{find_unsafe, warn_scan_warnings};
+use super::github_output::{write_github_outputs, GitHubOutputs};
+use super::latent::find_latent_unsafe;
+use super::metadata::{collect_package_metadata, collect_self_reported_claims};
+use super::notes::CrateNotes;
+use super::owners::collect_owner_counts;
+use super::party::find_party_split;
+use super::release_age::collect_release_ages;
+use super::render::render_html_with_baseline;
+use super::rust_version::find_rust_version_notes;
+use super::sysroot::scan_sysroot;
+use super::threshold::check_thresholds;
+use super::unsafe_docs::find_unsafe_docs;
+use super::violation::Checks;
 use super::{
-    list_files_used_but_not_scanned, package_metrics, unsafe_stats,
-    ScanDetails, ScanMode, ScanParameters,
+    file_stats, from_cargo_package_id, list_files_used_but_not_scanned,
+    package_metrics, run_checks, unsafe_stats, GeigerContext, ScanDetails,
+    ScanMode, ScanParameters,
 };
 
+pub use api::run_serve;
+pub use fleet::{run_fleet, run_manifests};
+
+use bundle::scan_bundle;
+use serve::scan_serve;
 use table::scan_to_table;
+use verify::scan_verify;
 
-use cargo::core::compiler::CompileMode;
+use cargo::core::compiler::{BuildConfig, CompileMode};
 use cargo::core::{PackageId, PackageSet, Workspace};
-use cargo::ops::CompileOptions;
+use cargo::ops::{CompileFilter, CompileOptions};
+use cargo::util::CargoResult;
 use cargo::{CliError, CliResult, Config};
-use cargo_geiger_serde::{ReportEntry, SafetyReport};
+use cargo_geiger_serde::{ReportEntry, SafetyReport, ScanOptions};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
 
 pub fn scan_unsafe(
     workspace: &Workspace,
     package_set: &PackageSet,
-    root_pack_id: PackageId,
+    root_pack_ids: &[PackageId],
     graph: &Graph,
     scan_parameters: &ScanParameters,
 ) -> CliResult {
+    if let Some(Subcommand::Verify(report_path)) =
+        &scan_parameters.args.subcommand
+    {
+        return scan_verify(
+            workspace,
+            package_set,
+            root_pack_ids,
+            graph,
+            scan_parameters,
+            report_path,
+        );
+    }
+    if let Some(bundle_path) = &scan_parameters.args.bundle {
+        return scan_bundle(
+            workspace,
+            package_set,
+            root_pack_ids,
+            graph,
+            scan_parameters,
+            bundle_path,
+        );
+    }
+    if let Some(address) = scan_parameters.args.serve {
+        return scan_serve(
+            workspace,
+            package_set,
+            root_pack_ids,
+            graph,
+            scan_parameters,
+            address,
+        );
+    }
     match scan_parameters.args.output_format {
         Some(output_format) => scan_to_report(
             workspace,
             package_set,
-            root_pack_id,
+            root_pack_ids,
             graph,
             scan_parameters,
             output_format,
@@ -38,7 +114,7 @@ pub fn scan_unsafe(
         None => scan_to_table(
             workspace,
             package_set,
-            root_pack_id,
+            root_pack_ids,
             graph,
             scan_parameters,
         ),
@@ -51,7 +127,7 @@ pub fn scan_unsafe(
 fn build_compile_options<'a>(
     args: &'a Args,
     config: &'a Config,
-) -> CompileOptions {
+) -> CargoResult<CompileOptions> {
     let features = args
         .features
         .as_ref()
@@ -61,34 +137,72 @@ fn build_compile_options<'a>(
         .map(str::to_owned)
         .collect::<Vec<String>>();
     let mut compile_options =
-        CompileOptions::new(&config, CompileMode::Check { test: false })
+        CompileOptions::new(config, CompileMode::Check { test: false })
             .unwrap();
     compile_options.features = features;
     compile_options.all_features = args.all_features;
     compile_options.no_default_features = args.no_default_features;
+    // The unstable flags are read from the config by the build, but -Z
+    // build-std also needs an explicit target to build the sysroot for.
+    if let Some(target) = args.target.as_ref().filter(|t| *t != ALL_TARGETS) {
+        compile_options.build_config = BuildConfig::new(
+            config,
+            None,
+            std::slice::from_ref(target),
+            CompileMode::Check { test: false },
+        )?;
+    }
+
+    // Like cargo, the default targets are built unless some are selected.
+    if args.lib || args.bins || args.examples || args.tests {
+        compile_options.filter = CompileFilter::from_raw_arguments(
+            args.lib,
+            vec![],
+            args.bins,
+            vec![],
+            args.tests,
+            vec![],
+            args.examples,
+            vec![],
+            false,
+            false,
+        );
+    }
+
+    Ok(compile_options)
+}
 
-    // TODO: Investigate if this is relevant to cargo-geiger.
-    //let mut bins = Vec::new();
-    //let mut examples = Vec::new();
-    // opt.release = args.release;
-    // opt.target = args.target.clone();
-    // if let Some(ref name) = args.bin {
-    //     bins.push(name.clone());
-    // } else if let Some(ref name) = args.example {
-    //     examples.push(name.clone());
-    // }
-    // if args.bin.is_some() || args.example.is_some() {
-    //     opt.filter = ops::CompileFilter::new(
-    //         false,
-    //         bins.clone(), false,
-    //         Vec::new(), false,
-    //         examples.clone(), false,
-    //         Vec::new(), false,
-    //         false,
-    //     );
-    // }
-
-    compile_options
+/// The options of the scan recorded in the report, so that `verify` can
+/// reject a report produced with other options.
+fn construct_scan_options(args: &Args) -> ScanOptions {
+    let mut features = args
+        .features
+        .iter()
+        .flat_map(|features| features.split(' '))
+        .filter(|feature| !feature.is_empty())
+        .map(str::to_owned)
+        .collect::<Vec<String>>();
+    features.sort();
+    features.dedup();
+    let selected_targets = [
+        ("lib", args.lib),
+        ("bins", args.bins),
+        ("tests", args.tests),
+        ("examples", args.examples),
+    ]
+    .iter()
+    .filter(|(_, selected)| *selected)
+    .map(|(kind, _)| String::from(*kind))
+    .collect();
+    ScanOptions {
+        include_tests: args.include_tests,
+        all_targets: args.all_targets,
+        selected_targets,
+        all_features: args.all_features,
+        no_default_features: args.no_default_features,
+        features,
+        target: args.target.clone(),
+    }
 }
 
 fn scan(
@@ -97,37 +211,285 @@ fn scan(
     scan_parameters: &ScanParameters,
 ) -> Result<ScanDetails, CliError> {
     let compile_options =
-        build_compile_options(scan_parameters.args, scan_parameters.config);
-    let rs_files_used =
-        resolve_rs_file_deps(&compile_options, workspace).unwrap();
-    let geiger_context = find_unsafe(
+        build_compile_options(scan_parameters.args, scan_parameters.config)?;
+    let used_files_cache = UsedFilesCache::new(
+        scan_parameters.config,
+        scan_parameters.args,
+        workspace,
+    );
+    let cached_rs_files_used = if scan_parameters.args.cached {
+        used_files_cache.get(workspace)
+    } else {
+        None
+    };
+    let (rs_files_used, resolve_warnings) = match cached_rs_files_used {
+        Some(rs_files_used) => {
+            scan_parameters.config.shell().status(
+                "Skipping",
+                "build, Cargo.lock is unchanged since the cached build",
+            )?;
+            (rs_files_used, vec![])
+        }
+        None => {
+            let (rs_files_used, resolve_warnings) = resolve_rs_file_deps(
+                &compile_options,
+                workspace,
+                scan_parameters.args.clean_members,
+                scan_parameters.args.no_clean,
+                scan_parameters
+                    .args
+                    .target
+                    .as_deref()
+                    .filter(|t| *t != ALL_TARGETS),
+            )
+            .map_err(|e| CliError::new(anyhow::Error::new(e), 1))?;
+            used_files_cache.put(workspace, &rs_files_used);
+            (rs_files_used, resolve_warnings)
+        }
+    };
+    warn_scan_warnings(scan_parameters.config, &resolve_warnings)?;
+    let is_case_insensitive = is_case_insensitive_dir(workspace.root());
+    // The used files are matched against the scanned files ignoring case
+    // below, the scanned files are only all known if none is folded.
+    let fold_rs_files_used =
+        if is_case_insensitive || keeps_file_metrics(scan_parameters) {
+            None
+        } else {
+            Some(&rs_files_used)
+        };
+    let mut geiger_context = find_unsafe(
         ScanMode::Full,
         scan_parameters.config,
         packages,
         scan_parameters.print_config,
+        fold_rs_files_used,
     )?;
+    geiger_context.warnings.extend(resolve_warnings);
+    let rs_files_used = if is_case_insensitive {
+        dedup_paths_ignoring_case(
+            rs_files_used,
+            geiger_context.package_id_to_metrics.values().flat_map(
+                |package_metrics| package_metrics.rs_path_to_metrics.keys(),
+            ),
+        )
+    } else {
+        rs_files_used
+    };
     Ok(ScanDetails {
         rs_files_used,
         geiger_context,
     })
 }
 
+/// Whether an output reads the metrics of every file, the files listed with
+/// `--include-files` and in the reports other than JSON, or their locations.
+/// Otherwise the files without unsafe code are folded into their package.
+fn keeps_file_metrics(scan_parameters: &ScanParameters) -> bool {
+    let print_config = scan_parameters.print_config;
+    scan_parameters.args.include_files
+        || print_config.retain_locations
+        || print_config
+            .output_format
+            .is_some_and(|output_format| output_format != OutputFormat::Json)
+}
+
 fn scan_to_report(
     workspace: &Workspace,
     packages: &PackageSet,
-    root_pack_id: PackageId,
+    root_pack_ids: &[PackageId],
     graph: &Graph,
     scan_parameters: &ScanParameters,
     output_format: OutputFormat,
 ) -> CliResult {
+    let scan_details = scan(workspace, packages, scan_parameters)?;
+    let report = build_report(
+        workspace,
+        packages,
+        root_pack_ids,
+        graph,
+        scan_parameters,
+        &scan_details,
+        output_format != OutputFormat::Json
+            || scan_parameters.args.include_files,
+    )?;
     let ScanDetails {
         rs_files_used,
         geiger_context,
-    } = scan(workspace, packages, scan_parameters)?;
+    } = scan_details;
+    let checks = run_scan_checks(
+        &geiger_context,
+        &rs_files_used,
+        workspace,
+        root_pack_ids,
+        graph,
+        scan_parameters,
+    )?;
+    if let Some(save_baseline_path) = &scan_parameters.args.save_baseline {
+        save_baseline(
+            save_baseline_path,
+            &construct_baseline_report(
+                &geiger_context,
+                graph,
+                root_pack_ids,
+                &rs_files_used,
+            ),
+        )?;
+    }
+    let checks_passed =
+        report.used_but_not_scanned_files.is_empty() && checks.passed();
+    if scan_parameters.args.github_output {
+        let github_outputs = GitHubOutputs::new(
+            &geiger_context,
+            graph,
+            &rs_files_used,
+            checks_passed,
+        );
+        write_github_outputs(&github_outputs)
+            .map_err(|e| CliError::new(e.into(), 1))?;
+    }
+    if let Some(attest_path) = &scan_parameters.args.attest {
+        let geiger_summary = GeigerSummary::new(
+            &geiger_context,
+            graph,
+            &rs_files_used,
+            checks_passed,
+        );
+        write_attestation(attest_path, workspace, geiger_summary)?;
+    }
+    let s = match output_format {
+        OutputFormat::Html => {
+            render_html_with_baseline(&report, scan_parameters.args)?
+        }
+        OutputFormat::Json => serde_json::to_string(&report).unwrap(),
+        OutputFormat::Markdown => render_markdown_report(&report),
+        OutputFormat::SecurityInsights => {
+            serde_json::to_string(&security_insights_from_report(&report))
+                .unwrap()
+        }
+        OutputFormat::Treemap => {
+            serde_json::to_string(&treemap_from_report(&report)).unwrap()
+        }
+    };
+    println!("{}", s);
+    checks.report(scan_parameters.args)
+}
+
+/// Makes every check on the metrics of a scan that built the packages: the
+/// unsafe thresholds, the regressions since the `--baseline` report, which
+/// only fail the scan with `--fail-on-regression`, and the checks of the
+/// other scans. Everything
+/// recording whether the checks passed is written after this.
+fn run_scan_checks(
+    geiger_context: &GeigerContext,
+    rs_files_used: &HashSet<PathBuf>,
+    workspace: &Workspace,
+    root_pack_ids: &[PackageId],
+    graph: &Graph,
+    scan_parameters: &ScanParameters,
+) -> Result<Checks, CliError> {
+    let args = scan_parameters.args;
+    let mut checks = Checks::default();
+    checks.push(check_thresholds(
+        geiger_context,
+        graph,
+        rs_files_used,
+        args,
+        scan_parameters.print_config.numbers,
+    ));
+    if let Some(baseline_path) = &args.baseline {
+        let baseline_regressions = list_baseline_regressions(
+            baseline_path,
+            args.policy.as_deref(),
+            &construct_baseline_report(
+                geiger_context,
+                graph,
+                root_pack_ids,
+                rs_files_used,
+            ),
+        )?;
+        checks.push(check_baseline_regressions(
+            baseline_regressions,
+            args.fail_on_regression,
+        ));
+    }
+    checks.extend(run_checks(
+        geiger_context,
+        graph,
+        root_pack_ids,
+        workspace,
+        scan_parameters,
+    )?);
+    Ok(checks)
+}
+
+/// Collects the metrics of the scan into the report of the packages in the
+/// graph, with the sections selected by the options.
+fn build_report(
+    workspace: &Workspace,
+    package_set: &PackageSet,
+    root_pack_ids: &[PackageId],
+    graph: &Graph,
+    scan_parameters: &ScanParameters,
+    scan_details: &ScanDetails,
+    include_files: bool,
+) -> Result<SafetyReport, CliError> {
+    let ScanDetails {
+        rs_files_used,
+        geiger_context,
+    } = scan_details;
+    let mut latent_unsafe = if scan_parameters.args.latent_unsafe {
+        let target_cfgs = get_cfgs(
+            scan_parameters.config,
+            &scan_parameters.args.target,
+            workspace,
+        )?;
+        find_latent_unsafe(geiger_context, graph, target_cfgs.as_deref())
+            .into_iter()
+            .map(|(package_id, latent)| {
+                (from_cargo_package_id(package_id), latent)
+            })
+            .collect()
+    } else {
+        BTreeMap::new()
+    };
+    let mut package_metadata = if scan_parameters.args.include_metadata {
+        collect_package_metadata(package_set, graph)?
+    } else {
+        HashMap::new()
+    };
+    let mut self_reported_claims =
+        collect_self_reported_claims(package_set, graph)?;
+    let mut rust_version_notes = find_rust_version_notes(
+        scan_parameters.config,
+        workspace,
+        package_set,
+        graph,
+    )?
+    .into_iter()
+    .map(|(package_id, notes)| (from_cargo_package_id(package_id), notes))
+    .collect::<HashMap<_, _>>();
+    let mut checksum_mismatches = if scan_parameters.args.verify_checksums {
+        find_checksum_mismatches(workspace, package_set, graph)?
+            .into_iter()
+            .map(|(package_id, mismatches)| {
+                (from_cargo_package_id(package_id), mismatches)
+            })
+            .collect::<HashMap<_, _>>()
+    } else {
+        HashMap::new()
+    };
+    let crate_notes = CrateNotes::from_config(scan_parameters.config)?;
     let mut report = SafetyReport::default();
-    for (package, pack_metrics) in
-        package_metrics(&geiger_context, graph, root_pack_id)
+    for (mut package, pack_metrics) in
+        package_metrics(geiger_context, graph, root_pack_ids)
     {
+        package.metadata = package_metadata.remove(&package.id);
+        package.self_reported = self_reported_claims.remove(&package.id);
+        package.rust_version_notes =
+            rust_version_notes.remove(&package.id).unwrap_or_default();
+        package.checksum_mismatches =
+            checksum_mismatches.remove(&package.id).unwrap_or_default();
+        package.notes = crate_notes.notes_for(&package.id);
         let pack_metrics = match pack_metrics {
             Some(m) => m,
             None => {
@@ -135,66 +497,204 @@ fn scan_to_report(
                 continue;
             }
         };
-        let unsafety = unsafe_stats(pack_metrics, &rs_files_used);
+        let mut unsafety = unsafe_stats(pack_metrics, rs_files_used);
+        if include_files {
+            unsafety.files = file_stats(pack_metrics, rs_files_used);
+        }
+        if let Some(latent) = latent_unsafe.remove(&package.id) {
+            unsafety.latent = latent;
+        }
         let entry = ReportEntry { package, unsafety };
         report.packages.insert(entry.package.id.clone(), entry);
     }
+    for (package_id, unsafe_docs) in
+        find_unsafe_docs(geiger_context, package_set, rs_files_used)?
+    {
+        if let Some(entry) =
+            report.packages.get_mut(&from_cargo_package_id(package_id))
+        {
+            entry.package.unsafe_docs = unsafe_docs;
+        }
+    }
+    if scan_parameters.args.include_owners {
+        for (package_id, owner_count) in collect_owner_counts(
+            report.packages.values(),
+            scan_parameters.args,
+            scan_parameters.config,
+        ) {
+            if let Some(entry) = report.packages.get_mut(&package_id) {
+                entry.package.owner_count = Some(owner_count);
+            }
+        }
+    }
+    if scan_parameters.args.include_release_age {
+        let release_ages = collect_release_ages(
+            report
+                .packages
+                .values()
+                .filter(|entry| entry.unsafety.used.has_unsafe())
+                .map(|entry| &entry.package.id),
+            scan_parameters.args,
+            scan_parameters.config,
+        );
+        for (package_id, release_age) in release_ages {
+            if let Some(entry) = report.packages.get_mut(&package_id) {
+                entry.package.release_date = release_age.release_date;
+                entry.package.days_since_last_release =
+                    Some(release_age.days_since_last_release);
+            }
+        }
+    }
     report.used_but_not_scanned_files =
-        list_files_used_but_not_scanned(&geiger_context, &rs_files_used).into_iter().collect();
-    let s = match output_format {
-        OutputFormat::Json => serde_json::to_string(&report).unwrap(),
-    };
-    println!("{}", s);
-    Ok(())
+        list_files_used_but_not_scanned(geiger_context, rs_files_used)
+            .into_iter()
+            .collect();
+    report.warnings = geiger_context.warnings.clone();
+    report.toolchain = scan_parameters.args.toolchain.clone();
+    report.cargo_config = collect_cargo_config_settings(
+        scan_parameters.config,
+        scan_parameters.args.target.as_deref(),
+    )?;
+    report.scan_options = Some(construct_scan_options(scan_parameters.args));
+    if scan_parameters.args.first_party_split {
+        report.party_split = Some(find_party_split(
+            geiger_context,
+            graph,
+            workspace,
+            rs_files_used,
+        ));
+    }
+    report.duplicated_files =
+        find_duplicated_files(geiger_context, package_set)?;
+    if scan_parameters.args.blame {
+        report.blame = blame_workspace_unsafe(geiger_context, workspace);
+    }
+    if scan_parameters.args.include_sysroot {
+        report.sysroot = scan_sysroot(
+            scan_parameters.config,
+            workspace,
+            scan_parameters.print_config,
+        )?;
+    }
+    Ok(report)
 }
 
-#[cfg(tests)]
+#[cfg(test)]
 mod default_tests {
     use super::*;
+    use crate::format::i18n::Lang;
     use crate::format::Charset;
 
     use rstest::*;
+    use std::ffi::OsString;
 
-    #[rstest]
-    fn build_compile_options_test() {
-        let args_all_features = rand::random();
+    #[rstest(
+        args_all_features,
+        args_no_default_features,
+        case(false, false),
+        case(true, false),
+        case(false, true)
+    )]
+    fn build_compile_options_test(
+        args_all_features: bool,
+        args_no_default_features: bool,
+    ) {
         let args_features = Some(String::from("unit test features"));
-        let args_no_default_features = rand::random();
 
         let args = Args {
             all: false,
             all_deps: false,
             all_features: args_all_features,
             all_targets: false,
+            allow_remote: false,
+            attest: None,
+            baseline: None,
+            bins: false,
+            blame: false,
             build_deps: false,
+            bundle: None,
+            cache_dir: None,
+            cached: false,
             charset: Charset::Utf8,
+            clean_members: false,
             color: None,
+            deny_undocumented_unsafe: false,
             dev_deps: false,
+            examples: false,
             features: args_features,
+            fail_on_missing_metrics: false,
+            fail_on_regression: false,
+            first_party_split: false,
+            fix: false,
             forbid_only: false,
             format: "".to_string(),
             frozen: false,
+            generated_globs: vec![],
+            generated_weight: None,
+            github_output: false,
             help: false,
+            include_files: false,
+            include_metadata: false,
+            include_owners: false,
+            include_release_age: false,
+            include_sysroot: false,
             include_tests: false,
             invert: false,
+            lang: Lang::En,
+            latent_unsafe: false,
+            lib: false,
+            listen: None,
+            list_undocumented_unsafe: None,
+            list_unsafe: None,
             locked: false,
-            manifest_path: None,
+            manifest_paths: vec![],
+            max_age: None,
+            max_unsafe_expressions: vec![],
+            max_unsafe_functions: vec![],
+            max_unsafe_impls: vec![],
+            max_unsafe_methods: vec![],
+            max_unsafe_ratio: None,
+            max_unsafe_ratio_per_crate: None,
+            max_unsafe_traits: vec![],
+            no_clean: false,
             no_default_features: args_no_default_features,
             no_indent: false,
             offline: false,
             package: None,
+            policy: None,
+            pr_comment: None,
             prefix_depth: false,
+            quarantine: None,
             quiet: false,
+            report_only: false,
+            require_forbid: None,
+            rescan: false,
+            save_baseline: None,
+            serve: None,
+            show_inactive: false,
+            snapshot: false,
+            subcommand: None,
+            suggest_features: false,
+            suggest_forbid: false,
             target: None,
+            tests: false,
+            timeout_per_crate: None,
+            toolchain: None,
+            unsafe_apis: None,
             unstable_flags: vec![],
+            unused_only: false,
+            used_only: false,
             verbose: 0,
+            verify_checksums: false,
             version: false,
+            why: None,
+            why_most_unsafe: false,
             output_format: None,
         };
 
         let config = Config::default().unwrap();
 
-        let compile_options = build_compile_options(&args, &config);
+        let compile_options = build_compile_options(&args, &config).unwrap();
 
         assert_eq!(compile_options.all_features, args_all_features);
         assert_eq!(compile_options.features, vec!["unit", "test", "features"]);
@@ -205,11 +705,35 @@ mod default_tests {
     }
 
     #[rstest]
-    fn construct_scan_mode_default_output_key_lines_test() {
-        let emoji_symbols = EmojiSymbols::new(Charset::Utf8);
-        let output_key_lines =
-            construct_scan_mode_default_output_key_lines(&emoji_symbols);
+    fn construct_scan_options_test() {
+        let args = Args::parse_args(pico_args::Arguments::from_vec(
+            vec![
+                "--include-tests",
+                "--features",
+                "b a  b",
+                "--lib",
+                "--tests",
+                "--target",
+                "x86_64-unknown-linux-gnu",
+            ]
+            .into_iter()
+            .map(OsString::from)
+            .collect(),
+        ))
+        .unwrap();
 
-        assert_eq!(output_key_lines.len(), 12);
+        assert_eq!(
+            construct_scan_options(&args),
+            ScanOptions {
+                include_tests: true,
+                selected_targets: vec![
+                    String::from("lib"),
+                    String::from("tests")
+                ],
+                features: vec![String::from("a"), String::from("b")],
+                target: Some(String::from("x86_64-unknown-linux-gnu")),
+                ..Default::default()
+            }
+        );
     }
 }
@@ -0,0 +1,338 @@
+use crate::args::Args;
+use crate::format::emoji_symbols::SymbolOverrides;
+use crate::format::numbers::NumberFormat;
+use crate::format::print_config::PrintConfig;
+
+use super::super::attest::GeigerSummary;
+use super::super::ScanParameters;
+use super::fleet::scan_workspace;
+use super::serve::{
+    bind_listener, is_allowed_host, read_stream_request, Request,
+};
+
+use cargo::{CliResult, Config};
+use cargo_geiger_serde::SafetyReport;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use url::Url;
+
+/// The address of `cargo geiger serve` without `--listen`.
+const DEFAULT_LISTEN_ADDRESS: &str = "127.0.0.1:7878";
+
+/// The header, lowercase, that requests to start a scan must have. Browsers
+/// only send custom headers cross-origin after a preflight request, which
+/// the API does not answer, so other sites cannot make a browser start scans.
+const API_HEADER: &str = "x-cargo-geiger";
+
+/// The most scans that are kept, the oldest ones are dropped first.
+const MAX_KEPT_SCANS: usize = 100;
+
+/// A scan requested from the API, kept until the server stops or it is one of
+/// the oldest scans when `MAX_KEPT_SCANS` are exceeded.
+#[derive(Debug, Serialize)]
+struct ApiScan {
+    id: usize,
+    manifest_path: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    package: Option<String>,
+    /// Why the scan failed, the summary and report are then empty.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<GeigerSummary>,
+    /// Only sent when the scan itself is fetched.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    report: Option<SafetyReport>,
+}
+
+/// A request to the scan API.
+#[derive(Debug, PartialEq)]
+enum ApiRoute {
+    /// `POST /scans?manifest_path=<PATH>[&package=<SPEC>]`
+    CreateScan {
+        manifest_path: PathBuf,
+        package: Option<String>,
+    },
+    /// `GET /scans`
+    ListScans,
+    /// `GET /scans/<ID>`
+    GetScan(usize),
+    BadRequest(&'static str),
+    Forbidden(&'static str),
+    NotFound,
+}
+
+/// Serves an HTTP API that scans the workspace of a manifest on request and
+/// keeps the results to be fetched later. The requests are handled one
+/// after the other in this process, the cargo configuration, the package
+/// sources and the metrics cache stay warm between them. Each scan builds
+/// the workspace like `cargo geiger` does, so only loopback addresses are
+/// listened on without `--allow-remote`, and requests whose `Host` is
+/// neither that address nor localhost are refused.
+pub fn run_serve(args: &Args, config: &Config) -> CliResult {
+    let address = match args.listen {
+        Some(address) => address,
+        None => DEFAULT_LISTEN_ADDRESS.parse::<SocketAddr>().unwrap(),
+    };
+    let print_config = PrintConfig {
+        numbers: NumberFormat::from_config(config)?,
+        symbols: SymbolOverrides::from_config(config)?,
+        ..PrintConfig::new(args)?
+    };
+    let scan_parameters = ScanParameters {
+        args,
+        config,
+        print_config: &print_config,
+    };
+    let listener = bind_listener(address, args.allow_remote)?;
+    let local_address = listener.local_addr().unwrap_or(address);
+    config.shell().status(
+        "Serving",
+        format!(
+            "the scan API on http://{}, press Ctrl-C to stop",
+            local_address
+        ),
+    )?;
+    let mut scans = VecDeque::<ApiScan>::new();
+    let mut next_scan_id = 0;
+    for stream in listener.incoming() {
+        let mut stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("Failed to accept a connection: {}", e);
+                continue;
+            }
+        };
+        let request = match read_stream_request(&stream) {
+            Ok(request) => request,
+            Err(e) => {
+                eprintln!("Failed to read a request: {}", e);
+                continue;
+            }
+        };
+        let api_route =
+            if is_allowed_host(request.host.as_deref(), local_address) {
+                api_route(&request)
+            } else {
+                ApiRoute::Forbidden("Host not allowed")
+            };
+        let response = match api_route {
+            ApiRoute::CreateScan {
+                manifest_path,
+                package,
+            } => {
+                config.shell().status(
+                    "Scanning",
+                    format!("{}", manifest_path.display()),
+                )?;
+                let scan = run_scan(
+                    next_scan_id,
+                    manifest_path,
+                    package,
+                    &scan_parameters,
+                );
+                next_scan_id += 1;
+                let response = json_response("201 Created", &scan);
+                scans.push_back(scan);
+                if scans.len() > MAX_KEPT_SCANS {
+                    scans.pop_front();
+                }
+                response
+            }
+            ApiRoute::ListScans => {
+                let listed_scans = scans
+                    .iter()
+                    .map(|scan| ApiScan {
+                        id: scan.id,
+                        manifest_path: scan.manifest_path.clone(),
+                        package: scan.package.clone(),
+                        error: scan.error.clone(),
+                        summary: None,
+                        report: None,
+                    })
+                    .collect::<Vec<ApiScan>>();
+                json_response("200 OK", &listed_scans)
+            }
+            ApiRoute::GetScan(id) => {
+                match scans.iter().find(|scan| scan.id == id) {
+                    Some(scan) => json_response("200 OK", scan),
+                    None => error_response("404 Not Found", "Scan not found"),
+                }
+            }
+            ApiRoute::BadRequest(message) => {
+                error_response("400 Bad Request", message)
+            }
+            ApiRoute::Forbidden(message) => {
+                error_response("403 Forbidden", message)
+            }
+            ApiRoute::NotFound => error_response("404 Not Found", "Not found"),
+        };
+        if let Err(e) = stream.write_all(response.as_bytes()) {
+            eprintln!("Failed to send a response: {}", e);
+        }
+    }
+    Ok(())
+}
+
+fn run_scan(
+    id: usize,
+    manifest_path: PathBuf,
+    package: Option<String>,
+    scan_parameters: &ScanParameters,
+) -> ApiScan {
+    match scan_workspace(&manifest_path, package.as_deref(), scan_parameters) {
+        Ok((summary, report)) => ApiScan {
+            id,
+            manifest_path,
+            package,
+            error: None,
+            summary: Some(summary),
+            report: Some(report),
+        },
+        Err(e) => ApiScan {
+            id,
+            manifest_path,
+            package,
+            error: Some(
+                e.error
+                    .map(|error| format!("{:#}", error))
+                    .unwrap_or_else(|| String::from("unknown error")),
+            ),
+            summary: None,
+            report: None,
+        },
+    }
+}
+
+fn api_route(request: &Request) -> ApiRoute {
+    let mut parts = request.request_line.split_whitespace();
+    let (method, target) = match (parts.next(), parts.next()) {
+        (Some(method), Some(target)) => (method, target),
+        _ => return ApiRoute::BadRequest("Malformed request"),
+    };
+    let url = match Url::parse("http://localhost")
+        .and_then(|base| base.join(target))
+    {
+        Ok(url) => url,
+        Err(_) => return ApiRoute::BadRequest("Malformed request target"),
+    };
+    let segments = url
+        .path()
+        .trim_start_matches('/')
+        .split('/')
+        .collect::<Vec<&str>>();
+    match (method, &segments[..]) {
+        ("POST", ["scans"]) if !request.header_names.contains(API_HEADER) => {
+            ApiRoute::Forbidden("Missing the X-Cargo-Geiger header")
+        }
+        ("POST", ["scans"]) => {
+            let query = |key: &str| {
+                url.query_pairs()
+                    .find(|(name, _)| name == key)
+                    .map(|(_, value)| value.into_owned())
+            };
+            match query("manifest_path") {
+                Some(manifest_path) => ApiRoute::CreateScan {
+                    manifest_path: PathBuf::from(manifest_path),
+                    package: query("package"),
+                },
+                None => ApiRoute::BadRequest("Missing manifest_path"),
+            }
+        }
+        ("GET", ["scans"]) => ApiRoute::ListScans,
+        ("GET", ["scans", id]) => match id.parse() {
+            Ok(id) => ApiRoute::GetScan(id),
+            Err(_) => ApiRoute::NotFound,
+        },
+        _ => ApiRoute::NotFound,
+    }
+}
+
+fn json_response<T: Serialize>(status: &str, body: &T) -> String {
+    let body = serde_json::to_string(body).unwrap();
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+fn error_response(status: &str, message: &str) -> String {
+    json_response(status, &serde_json::json!({ "error": message }))
+}
+
+#[cfg(test)]
+mod api_tests {
+    use super::*;
+
+    use rstest::*;
+
+    #[rstest(
+        input_request_line,
+        expected_api_route,
+        case(
+            "POST /scans?manifest_path=%2Fsrv%2Fapp%2FCargo.toml HTTP/1.1\r\n",
+            ApiRoute::CreateScan {
+                manifest_path: PathBuf::from("/srv/app/Cargo.toml"),
+                package: None,
+            }
+        ),
+        case(
+            "POST /scans?manifest_path=Cargo.toml&package=tokio:0.2.22 \
+             HTTP/1.1\r\n",
+            ApiRoute::CreateScan {
+                manifest_path: PathBuf::from("Cargo.toml"),
+                package: Some(String::from("tokio:0.2.22")),
+            }
+        ),
+        case(
+            "POST /scans HTTP/1.1\r\n",
+            ApiRoute::BadRequest("Missing manifest_path")
+        ),
+        case("GET /scans HTTP/1.1\r\n", ApiRoute::ListScans),
+        case("GET /scans/3 HTTP/1.1\r\n", ApiRoute::GetScan(3)),
+        case("GET /scans/latest HTTP/1.1\r\n", ApiRoute::NotFound),
+        case("DELETE /scans/3 HTTP/1.1\r\n", ApiRoute::NotFound),
+        case("", ApiRoute::BadRequest("Malformed request"))
+    )]
+    fn api_route_test(input_request_line: &str, expected_api_route: ApiRoute) {
+        let request = Request {
+            request_line: String::from(input_request_line),
+            header_names: vec![String::from(API_HEADER)].into_iter().collect(),
+            host: Some(String::from("localhost")),
+        };
+
+        assert_eq!(api_route(&request), expected_api_route);
+    }
+
+    #[rstest]
+    fn api_route_without_api_header_test() {
+        let request = Request {
+            request_line: String::from(
+                "POST /scans?manifest_path=Cargo.toml HTTP/1.1\r\n",
+            ),
+            header_names: vec![String::from("host")].into_iter().collect(),
+            host: Some(String::from("localhost")),
+        };
+
+        assert_eq!(
+            api_route(&request),
+            ApiRoute::Forbidden("Missing the X-Cargo-Geiger header")
+        );
+    }
+
+    #[rstest]
+    fn error_response_test() {
+        assert_eq!(
+            error_response("404 Not Found", "Not found"),
+            "HTTP/1.1 404 Not Found\r\nContent-Type: application/json\r\n\
+             Content-Length: 21\r\nConnection: close\r\n\r\n\
+             {\"error\":\"Not found\"}"
+        );
+    }
+}
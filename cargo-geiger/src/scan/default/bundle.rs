@@ -0,0 +1,280 @@
+use crate::graph::Graph;
+
+use super::super::attest::{create_statement, GeigerSummary};
+use super::super::render::render_html_with_baseline;
+use super::super::threshold::UnsafeRatioThresholds;
+use super::super::{RequireForbidScope, ScanDetails, ScanParameters};
+use super::{build_report, run_scan_checks, scan};
+
+use cargo::core::{PackageId, PackageSet, Workspace};
+use cargo::{CliError, CliResult};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+/// The compression of the bundle archive, chosen by its file extension.
+#[derive(Debug, PartialEq)]
+enum BundleCompression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+/// The checks the scan was asked to make, recorded in the bundle next to
+/// their outcome in the provenance statement.
+#[derive(Debug, PartialEq, Serialize)]
+struct BundlePolicy {
+    max_unsafe_ratio: Option<f64>,
+    max_unsafe_ratio_per_crate: Option<f64>,
+    generated_weight: Option<f64>,
+    require_forbid: Option<RequireForbidScope>,
+}
+
+/// Scans the workspace and writes a single archive for CI systems to attach
+/// to a build record, holding the JSON report, its HTML rendering, the
+/// policy options and an in-toto statement of the lockfile as provenance.
+pub fn scan_bundle(
+    workspace: &Workspace,
+    package_set: &PackageSet,
+    root_pack_ids: &[PackageId],
+    graph: &Graph,
+    scan_parameters: &ScanParameters,
+    bundle_path: &Path,
+) -> CliResult {
+    let compression =
+        bundle_compression(bundle_path).map_err(|e| CliError::new(e, 1))?;
+    let scan_details = scan(workspace, package_set, scan_parameters)?;
+    let report = build_report(
+        workspace,
+        package_set,
+        root_pack_ids,
+        graph,
+        scan_parameters,
+        &scan_details,
+        true,
+    )?;
+    let ScanDetails {
+        rs_files_used,
+        geiger_context,
+    } = scan_details;
+    let checks = run_scan_checks(
+        &geiger_context,
+        &rs_files_used,
+        workspace,
+        root_pack_ids,
+        graph,
+        scan_parameters,
+    )?;
+    let geiger_summary = GeigerSummary::new(
+        &geiger_context,
+        graph,
+        &rs_files_used,
+        report.used_but_not_scanned_files.is_empty() && checks.passed(),
+    );
+    let thresholds = UnsafeRatioThresholds::new(scan_parameters.args);
+    let statement = create_statement(workspace, geiger_summary)?;
+    let policy = BundlePolicy {
+        max_unsafe_ratio: thresholds.total,
+        max_unsafe_ratio_per_crate: thresholds.per_crate,
+        generated_weight: thresholds.generated_weight,
+        require_forbid: scan_parameters.args.require_forbid,
+    };
+    let files = vec![
+        (
+            "geiger-report.json",
+            serde_json::to_vec_pretty(&report).unwrap(),
+        ),
+        (
+            "geiger-report.html",
+            render_html_with_baseline(&report, scan_parameters.args)?
+                .into_bytes(),
+        ),
+        ("policy.json", serde_json::to_vec_pretty(&policy).unwrap()),
+        (
+            "provenance.json",
+            serde_json::to_vec_pretty(&statement).unwrap(),
+        ),
+    ];
+    let mtime = scan_parameters
+        .args
+        .scan_time()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    write_bundle_file(bundle_path, compression, mtime, &files).map_err(
+        |e| {
+            CliError::new(
+                anyhow::Error::from(e).context(format!(
+                    "Failed to write the bundle {}",
+                    bundle_path.display()
+                )),
+                1,
+            )
+        },
+    )?;
+    scan_parameters
+        .config
+        .shell()
+        .status("Bundled", bundle_path.display())?;
+    checks.report(scan_parameters.args)
+}
+
+fn bundle_compression(path: &Path) -> anyhow::Result<BundleCompression> {
+    let file_name = path
+        .file_name()
+        .map(|file_name| file_name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if file_name.ends_with(".tar") {
+        Ok(BundleCompression::None)
+    } else if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
+        Ok(BundleCompression::Gzip)
+    } else if file_name.ends_with(".tar.zst") || file_name.ends_with(".tzst") {
+        Ok(BundleCompression::Zstd)
+    } else {
+        Err(anyhow::anyhow!(
+            "Unsupported bundle format of {}, expected a .tar, .tar.gz, \
+             .tgz, .tar.zst or .tzst file",
+            path.display()
+        ))
+    }
+}
+
+fn write_bundle_file(
+    path: &Path,
+    compression: BundleCompression,
+    mtime: u64,
+    files: &[(&str, Vec<u8>)],
+) -> io::Result<()> {
+    let file = File::create(path)?;
+    match compression {
+        BundleCompression::None => write_bundle(file, mtime, files)?.sync_all(),
+        BundleCompression::Gzip => write_bundle(
+            GzEncoder::new(file, Compression::default()),
+            mtime,
+            files,
+        )?
+        .finish()?
+        .sync_all(),
+        BundleCompression::Zstd => write_bundle(
+            zstd::Encoder::new(file, zstd::DEFAULT_COMPRESSION_LEVEL)?,
+            mtime,
+            files,
+        )?
+        .finish()?
+        .sync_all(),
+    }
+}
+
+/// Writes the files into a tar archive at the top level, returning the
+/// writer so that a compressor can be finished.
+fn write_bundle<W: Write>(
+    writer: W,
+    mtime: u64,
+    files: &[(&str, Vec<u8>)],
+) -> io::Result<W> {
+    let mut builder = tar::Builder::new(writer);
+    for (name, content) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(mtime);
+        builder.append_data(&mut header, name, content.as_slice())?;
+    }
+    builder.into_inner()
+}
+
+#[cfg(test)]
+mod bundle_tests {
+    use super::*;
+
+    use rstest::*;
+    use std::io::Read;
+
+    #[rstest(
+        input_path,
+        expected_compression,
+        case("geiger.tar", Some(BundleCompression::None)),
+        case("out/geiger.tar.gz", Some(BundleCompression::Gzip)),
+        case("geiger.TGZ", Some(BundleCompression::Gzip)),
+        case("geiger.tar.zst", Some(BundleCompression::Zstd)),
+        case("geiger.tzst", Some(BundleCompression::Zstd)),
+        case("geiger.zip", None)
+    )]
+    fn bundle_compression_test(
+        input_path: &str,
+        expected_compression: Option<BundleCompression>,
+    ) {
+        assert_eq!(
+            bundle_compression(Path::new(input_path)).ok(),
+            expected_compression
+        );
+    }
+
+    #[rstest]
+    fn write_bundle_test() {
+        let files = vec![
+            ("geiger-report.json", b"{}".to_vec()),
+            ("policy.json", b"{\"require_forbid\":\"all\"}".to_vec()),
+        ];
+
+        let archive = write_bundle(Vec::new(), 0, &files).unwrap();
+
+        let mut entries = Vec::new();
+        for entry in tar::Archive::new(archive.as_slice()).entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let mut content = String::new();
+            entry.read_to_string(&mut content).unwrap();
+            entries
+                .push((entry.path().unwrap().display().to_string(), content));
+        }
+        assert_eq!(
+            entries,
+            vec![
+                (String::from("geiger-report.json"), String::from("{}")),
+                (
+                    String::from("policy.json"),
+                    String::from("{\"require_forbid\":\"all\"}")
+                ),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn write_bundle_file_zstd_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("geiger.tar.zst");
+        let files = vec![("geiger-report.json", b"{}".to_vec())];
+
+        write_bundle_file(&path, BundleCompression::Zstd, 0, &files).unwrap();
+
+        let decoder = zstd::Decoder::new(File::open(&path).unwrap()).unwrap();
+        let mut archive = tar::Archive::new(decoder);
+        let mut entry = archive.entries().unwrap().next().unwrap().unwrap();
+        let mut content = String::new();
+        entry.read_to_string(&mut content).unwrap();
+        assert_eq!(
+            entry.path().unwrap().display().to_string(),
+            "geiger-report.json"
+        );
+        assert_eq!(content, "{}");
+    }
+
+    #[rstest]
+    fn bundle_policy_serialize_test() {
+        let policy = BundlePolicy {
+            max_unsafe_ratio: Some(2.5),
+            max_unsafe_ratio_per_crate: None,
+            generated_weight: None,
+            require_forbid: Some(RequireForbidScope::Direct),
+        };
+
+        let value = serde_json::to_value(&policy).unwrap();
+
+        assert_eq!(value["max_unsafe_ratio"], 2.5);
+        assert_eq!(value["require_forbid"], "direct");
+    }
+}
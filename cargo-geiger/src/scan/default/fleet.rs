@@ -0,0 +1,348 @@
+use crate::args::Args;
+use crate::cli::{
+    get_features, get_registry, get_root_package_ids, get_workspace, resolve,
+};
+use crate::format::emoji_symbols::SymbolOverrides;
+use crate::format::numbers::NumberFormat;
+use crate::format::print_config::{OutputFormat, PrintConfig};
+use crate::format::table::{unsafe_counters_header, unsafe_counters_row};
+use crate::graph::build_graph;
+
+use super::super::attest::GeigerSummary;
+use super::super::{ScanDetails, ScanParameters};
+use super::{build_report, scan};
+
+use cargo::{CliError, CliResult, Config};
+use cargo_geiger_serde::{CounterBlock, SafetyReport};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use walkdir::{DirEntry, WalkDir};
+
+/// The combined report of `cargo geiger fleet`, one entry per workspace.
+#[derive(Debug, Default, Serialize)]
+struct FleetReport {
+    workspaces: Vec<FleetWorkspace>,
+}
+
+#[derive(Debug, Serialize)]
+struct FleetWorkspace {
+    manifest_path: PathBuf,
+    /// Why the workspace was not scanned, the other fields are then empty.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<GeigerSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    report: Option<SafetyReport>,
+}
+
+/// Scans every cargo workspace below `dir`, one after the other, and prints
+/// a summary per workspace, or the combined report with `--json`. The
+/// workspaces share the metrics cache, so packages used by several of them
+/// are only scanned once. A workspace that fails to scan is reported and
+/// skipped.
+pub fn run_fleet(dir: &Path, args: &Args, config: &Config) -> CliResult {
+    if !dir.is_dir() {
+        return Err(CliError::new(
+            anyhow::anyhow!("{} is not a directory", dir.display()),
+            1,
+        ));
+    }
+    scan_fleet(find_workspace_manifests(dir, config), args, config)
+}
+
+/// Scans the workspaces of several `--manifest-path` values into one
+/// combined report, like `run_fleet` does for the workspaces below a
+/// directory.
+pub fn run_manifests(
+    manifest_paths: &[PathBuf],
+    args: &Args,
+    config: &Config,
+) -> CliResult {
+    scan_fleet(manifest_paths.to_vec(), args, config)
+}
+
+fn scan_fleet(
+    manifest_paths: Vec<PathBuf>,
+    args: &Args,
+    config: &Config,
+) -> CliResult {
+    match args.output_format {
+        None | Some(OutputFormat::Json) => {}
+        Some(_) => {
+            return Err(CliError::new(
+                anyhow::anyhow!(
+                    "several workspaces are only printed as a table or, with \
+                     --json, a JSON report"
+                ),
+                1,
+            ))
+        }
+    }
+    let print_config = PrintConfig {
+        numbers: NumberFormat::from_config(config)?,
+        symbols: SymbolOverrides::from_config(config)?,
+        ..PrintConfig::new(args)?
+    };
+    let scan_parameters = ScanParameters {
+        args,
+        config,
+        print_config: &print_config,
+    };
+    let mut fleet_report = FleetReport::default();
+    for manifest_path in manifest_paths {
+        config
+            .shell()
+            .status("Scanning", format!("{}", manifest_path.display()))?;
+        let fleet_workspace =
+            match scan_workspace(&manifest_path, None, &scan_parameters) {
+                Ok((summary, report)) => FleetWorkspace {
+                    manifest_path,
+                    error: None,
+                    summary: Some(summary),
+                    report: if args.output_format.is_some() {
+                        Some(report)
+                    } else {
+                        None
+                    },
+                },
+                Err(e) => {
+                    let error = e
+                        .error
+                        .map(|error| format!("{:#}", error))
+                        .unwrap_or_else(|| String::from("unknown error"));
+                    config.shell().warn(format!(
+                        "skipped {}: {}",
+                        manifest_path.display(),
+                        error
+                    ))?;
+                    FleetWorkspace {
+                        manifest_path,
+                        error: Some(error),
+                        summary: None,
+                        report: None,
+                    }
+                }
+            };
+        fleet_report.workspaces.push(fleet_workspace);
+    }
+    match args.output_format {
+        Some(_) => {
+            println!("{}", serde_json::to_string(&fleet_report).unwrap())
+        }
+        None => {
+            for fleet_line in construct_fleet_lines(&fleet_report) {
+                println!("{}", fleet_line);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The root manifests of the workspaces below `dir`, once per workspace
+/// even when several of its members are found. Build output and hidden
+/// directories, like `target` and `.git`, are not searched.
+fn find_workspace_manifests(dir: &Path, config: &Config) -> Vec<PathBuf> {
+    let mut workspace_manifests = Vec::new();
+    for manifest_path in find_manifests(dir) {
+        let root_manifest = match get_workspace(config, Some(manifest_path)) {
+            Ok(workspace) => workspace.root().join("Cargo.toml"),
+            // Reported when the manifest is scanned.
+            Err(_) => continue,
+        };
+        if !workspace_manifests.contains(&root_manifest) {
+            workspace_manifests.push(root_manifest);
+        }
+    }
+    workspace_manifests
+}
+
+fn find_manifests(dir: &Path) -> Vec<PathBuf> {
+    let mut manifests = WalkDir::new(dir)
+        .sort_by(|a, b| a.file_name().cmp(b.file_name()))
+        .into_iter()
+        .filter_entry(|entry| entry.depth() == 0 || !is_skipped_dir(entry))
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            entry.file_type().is_file() && entry.file_name() == "Cargo.toml"
+        })
+        .map(DirEntry::into_path)
+        .collect::<Vec<PathBuf>>();
+    manifests.sort();
+    manifests
+}
+
+fn is_skipped_dir(entry: &DirEntry) -> bool {
+    entry.file_type().is_dir()
+        && (entry.file_name() == "target"
+            || entry.file_name().to_string_lossy().starts_with('.'))
+}
+
+/// Resolves, builds and scans a workspace like `cargo geiger` run next to its
+/// root manifest, rooted at the package of `package_spec` when given, or at
+/// every member of a virtual workspace.
+pub(super) fn scan_workspace(
+    manifest_path: &Path,
+    package_spec: Option<&str>,
+    scan_parameters: &ScanParameters,
+) -> Result<(GeigerSummary, SafetyReport), CliError> {
+    let args = scan_parameters.args;
+    let config = scan_parameters.config;
+    let workspace = get_workspace(config, Some(manifest_path.to_path_buf()))?;
+    let member_ids = get_root_package_ids(&workspace)?;
+    let mut registry = get_registry(config, &member_ids)?;
+    let (package_set, resolve) = resolve(
+        &member_ids,
+        &mut registry,
+        &workspace,
+        &get_features(args),
+        args.all_features,
+        args.no_default_features,
+    )?;
+    let package_ids = package_set.package_ids().collect::<Vec<_>>();
+    let package_set = registry.get(&package_ids)?;
+    let root_package_ids = match package_spec {
+        Some(package_spec) => vec![resolve.query(package_spec)?],
+        None => member_ids,
+    };
+    let graph = build_graph(
+        &args.graph_options(),
+        config,
+        &resolve,
+        &package_set,
+        &root_package_ids,
+        &workspace,
+    )?;
+    let scan_details = scan(&workspace, &package_set, scan_parameters)?;
+    let report = build_report(
+        &workspace,
+        &package_set,
+        &root_package_ids,
+        &graph,
+        scan_parameters,
+        &scan_details,
+        false,
+    )?;
+    let ScanDetails {
+        rs_files_used,
+        geiger_context,
+    } = scan_details;
+    let summary = GeigerSummary::new(
+        &geiger_context,
+        &graph,
+        &rs_files_used,
+        report.used_but_not_scanned_files.is_empty(),
+    );
+    Ok((summary, report))
+}
+
+fn construct_fleet_lines(fleet_report: &FleetReport) -> Vec<String> {
+    let mut fleet_lines = Vec::<String>::new();
+    let mut total = CounterBlock::default();
+
+    fleet_lines.push(unsafe_counters_header("Workspace"));
+    fleet_lines.push(String::new());
+    for fleet_workspace in &fleet_report.workspaces {
+        let manifest_path = fleet_workspace.manifest_path.display();
+        match &fleet_workspace.summary {
+            Some(summary) => {
+                total += summary.used.clone();
+                fleet_lines.push(format!(
+                    "{}  {}, {} package(s), {} with unsafe usage",
+                    unsafe_counters_row(&summary.used),
+                    manifest_path,
+                    summary.packages,
+                    summary.crates_with_unsafe
+                ));
+            }
+            None => fleet_lines
+                .push(format!("{: <46}  {}, not scanned", "-", manifest_path)),
+        }
+    }
+    fleet_lines.push(String::new());
+    fleet_lines.push(format!(
+        "{}  Total, {} workspace(s)",
+        unsafe_counters_row(&total),
+        fleet_report.workspaces.len()
+    ));
+
+    fleet_lines
+}
+
+#[cfg(test)]
+mod fleet_tests {
+    use super::*;
+
+    use cargo_geiger_serde::Count;
+    use rstest::*;
+    use std::fs;
+
+    #[rstest]
+    fn find_manifests_test() {
+        let dir = tempfile::tempdir().unwrap();
+        for path in &[
+            "a/Cargo.toml",
+            "a/member/Cargo.toml",
+            "a/target/package/copy/Cargo.toml",
+            "b/.git/Cargo.toml",
+            "b/Cargo.toml",
+            "c/README.md",
+        ] {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+
+        assert_eq!(
+            find_manifests(dir.path()),
+            vec![
+                dir.path().join("a/Cargo.toml"),
+                dir.path().join("a/member/Cargo.toml"),
+                dir.path().join("b/Cargo.toml"),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn construct_fleet_lines_test() {
+        let summary = GeigerSummary {
+            packages: 3,
+            crates_with_unsafe: 1,
+            used: CounterBlock {
+                exprs: Count {
+                    safe: 5,
+                    unsafe_: 2,
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let fleet_report = FleetReport {
+            workspaces: vec![
+                FleetWorkspace {
+                    manifest_path: PathBuf::from("a/Cargo.toml"),
+                    error: None,
+                    summary: Some(summary),
+                    report: None,
+                },
+                FleetWorkspace {
+                    manifest_path: PathBuf::from("b/Cargo.toml"),
+                    error: Some(String::from("failed to parse manifest")),
+                    summary: None,
+                    report: None,
+                },
+            ],
+        };
+
+        let fleet_lines = construct_fleet_lines(&fleet_report);
+
+        assert_eq!(fleet_lines.len(), 6);
+        assert_eq!(
+            fleet_lines[2],
+            "0          2            0      0       0        a/Cargo.toml, \
+             3 package(s), 1 with unsafe usage"
+        );
+        assert!(fleet_lines[3].ends_with("  b/Cargo.toml, not scanned"));
+        assert!(fleet_lines[5].ends_with("Total, 2 workspace(s)"));
+    }
+}
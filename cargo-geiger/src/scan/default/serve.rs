@@ -0,0 +1,457 @@
+use crate::format::html::{
+    render_linked_html_report, render_package_html_report,
+};
+use crate::graph::Graph;
+
+use super::super::ScanParameters;
+use super::{build_report, scan};
+
+use cargo::core::{PackageId, PackageSet, Workspace};
+use cargo::{CliError, CliResult};
+use cargo_geiger_serde::SafetyReport;
+use std::collections::HashSet;
+use std::io;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpListener, TcpStream};
+use std::time::Duration;
+
+/// How long a client may take to send a request before the connection is
+/// dropped, so that a stalled client does not block the server.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The longest request line or header line that is read.
+const MAX_LINE_LENGTH: u64 = 8 * 1024;
+
+/// The most header lines of a request that are read.
+const MAX_HEADER_COUNT: usize = 100;
+
+/// The request line, the lowercase names of the headers and the `Host`
+/// header of a request, the body is not read.
+#[derive(Debug, Default, PartialEq)]
+pub(super) struct Request {
+    pub request_line: String,
+    pub header_names: HashSet<String>,
+    pub host: Option<String>,
+}
+
+/// A page of the served report.
+#[derive(Debug, PartialEq)]
+enum Route<'a> {
+    Report,
+    Package { name: &'a str, version: &'a str },
+    NotFound,
+}
+
+/// Serves the HTML report of the scan on a local address until interrupted.
+/// The report page lists the packages, linking to a page with the files of
+/// each package. With `--rescan`, every request of the report page scans the
+/// workspace again, the previous report is kept if that fails.
+pub fn scan_serve(
+    workspace: &Workspace,
+    package_set: &PackageSet,
+    root_pack_ids: &[PackageId],
+    graph: &Graph,
+    scan_parameters: &ScanParameters,
+    address: SocketAddr,
+) -> CliResult {
+    let scan_report = || -> Result<SafetyReport, CliError> {
+        let scan_details = scan(workspace, package_set, scan_parameters)?;
+        build_report(
+            workspace,
+            package_set,
+            root_pack_ids,
+            graph,
+            scan_parameters,
+            &scan_details,
+            true,
+        )
+    };
+    let listener = bind_listener(address, scan_parameters.args.allow_remote)?;
+    let mut report = scan_report()?;
+    scan_parameters.config.shell().status(
+        "Serving",
+        format!(
+            "the report on http://{}, press Ctrl-C to stop",
+            listener.local_addr().unwrap_or(address)
+        ),
+    )?;
+    for stream in listener.incoming() {
+        let mut stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("Failed to accept a connection: {}", e);
+                continue;
+            }
+        };
+        let request = match read_stream_request(&stream) {
+            Ok(request) => request,
+            Err(e) => {
+                eprintln!("Failed to read a request: {}", e);
+                continue;
+            }
+        };
+        let local_address = listener.local_addr().unwrap_or(address);
+        if !is_allowed_host(request.host.as_deref(), local_address) {
+            let response = http_response("403 Forbidden", "Host not allowed");
+            if let Err(e) = stream.write_all(response.as_bytes()) {
+                eprintln!("Failed to send a response: {}", e);
+            }
+            continue;
+        }
+        let route = match parse_request_path(&request.request_line) {
+            Some(path) => route(path),
+            None => Route::NotFound,
+        };
+        if route == Route::Report && scan_parameters.args.rescan {
+            match scan_report() {
+                Ok(rescanned_report) => report = rescanned_report,
+                Err(e) => eprintln!("Failed to scan again: {:?}", e.error),
+            }
+        }
+        if let Err(e) = stream.write_all(respond(&route, &report).as_bytes()) {
+            eprintln!("Failed to send a response: {}", e);
+        }
+    }
+    Ok(())
+}
+
+/// Listens on the address. Addresses other than loopback ones are refused
+/// without `--allow-remote`, since anyone reaching them can read the report
+/// or, with the scan API, have workspaces built.
+pub(super) fn bind_listener(
+    address: SocketAddr,
+    allow_remote: bool,
+) -> Result<TcpListener, CliError> {
+    if !address.ip().is_loopback() && !allow_remote {
+        return Err(CliError::new(
+            anyhow::anyhow!(
+                "refusing to listen on {}, which is not a loopback address, \
+                 without --allow-remote",
+                address
+            ),
+            1,
+        ));
+    }
+    TcpListener::bind(address).map_err(|e| {
+        CliError::new(
+            anyhow::Error::from(e)
+                .context(format!("Failed to listen on {}", address)),
+            1,
+        )
+    })
+}
+
+/// Whether the `Host` header of a request names the listened on address or
+/// localhost. Other names are refused, so that a site whose name is rebound
+/// to a local address cannot read the responses from a browser. Any IP
+/// address is allowed when listening on all of them.
+pub(super) fn is_allowed_host(host: Option<&str>, address: SocketAddr) -> bool {
+    let host = match host {
+        Some(host) => host.trim(),
+        None => return false,
+    };
+    let name = match host.strip_prefix('[') {
+        Some(bracketed) => match bracketed.split_once(']') {
+            Some((name, _)) => name,
+            None => return false,
+        },
+        None => host.split(':').next().unwrap_or_default(),
+    };
+    if name.eq_ignore_ascii_case("localhost") {
+        return true;
+    }
+    match name.parse::<IpAddr>() {
+        Ok(ip) => {
+            ip.is_loopback()
+                || ip == address.ip()
+                || address.ip().is_unspecified()
+        }
+        Err(_) => false,
+    }
+}
+
+/// Reads a request from the stream, giving up after `READ_TIMEOUT`.
+pub(super) fn read_stream_request(stream: &TcpStream) -> io::Result<Request> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    read_request(stream)
+}
+
+/// Reads the request line, the header names and the host. Lines longer than
+/// `MAX_LINE_LENGTH` and more than `MAX_HEADER_COUNT` headers are rejected.
+fn read_request(reader: impl Read) -> io::Result<Request> {
+    let mut reader = BufReader::new(reader);
+    let request_line = read_capped_line(&mut reader)?;
+    let mut header_names = HashSet::new();
+    let mut host = None;
+    for header_count in 0.. {
+        let header = read_capped_line(&mut reader)?;
+        if header.trim().is_empty() {
+            break;
+        }
+        if header_count == MAX_HEADER_COUNT {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "too many headers",
+            ));
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            let name = name.trim().to_lowercase();
+            if name == "host" {
+                host = Some(value.trim().to_string());
+            }
+            header_names.insert(name);
+        }
+    }
+    Ok(Request {
+        request_line,
+        header_names,
+        host,
+    })
+}
+
+fn read_capped_line(reader: &mut impl BufRead) -> io::Result<String> {
+    let mut line = String::new();
+    reader.take(MAX_LINE_LENGTH).read_line(&mut line)?;
+    if line.len() as u64 == MAX_LINE_LENGTH && !line.ends_with('\n') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "line too long",
+        ));
+    }
+    Ok(line)
+}
+
+/// The path of a `GET` request, without the query.
+fn parse_request_path(request_line: &str) -> Option<&str> {
+    let mut parts = request_line.split_whitespace();
+    match (parts.next(), parts.next()) {
+        (Some("GET"), Some(target)) => target.split('?').next(),
+        _ => None,
+    }
+}
+
+fn route(path: &str) -> Route<'_> {
+    if path == "/" {
+        return Route::Report;
+    }
+    let segments = path
+        .trim_start_matches('/')
+        .split('/')
+        .collect::<Vec<&str>>();
+    match segments[..] {
+        ["package", name, version] => Route::Package { name, version },
+        _ => Route::NotFound,
+    }
+}
+
+fn respond(route: &Route, report: &SafetyReport) -> String {
+    match route {
+        Route::Report => {
+            http_response("200 OK", &render_linked_html_report(report))
+        }
+        Route::Package { name, version } => {
+            match report.packages.values().find(|entry| {
+                entry.package.id.name == *name
+                    && entry.package.id.version.to_string() == *version
+            }) {
+                Some(entry) => {
+                    http_response("200 OK", &render_package_html_report(entry))
+                }
+                None => http_response("404 Not Found", "Package not found"),
+            }
+        }
+        Route::NotFound => http_response("404 Not Found", "Not found"),
+    }
+}
+
+fn http_response(status: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/html; charset=utf-8\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+#[cfg(test)]
+mod serve_tests {
+    use super::*;
+
+    use cargo_geiger_serde::{PackageInfo, ReportEntry, Source, UnsafeInfo};
+    use rstest::*;
+    use semver::Version;
+    use url::Url;
+
+    #[rstest]
+    fn read_request_test() {
+        let request = read_request(
+            &b"POST /scans HTTP/1.1\r\nHost: localhost\r\n\
+               X-Cargo-Geiger: 1\r\n\r\nbody"[..],
+        )
+        .unwrap();
+
+        assert_eq!(request.request_line, "POST /scans HTTP/1.1\r\n");
+        assert_eq!(
+            request.header_names,
+            vec![String::from("host"), String::from("x-cargo-geiger")]
+                .into_iter()
+                .collect::<HashSet<String>>()
+        );
+        assert_eq!(request.host, Some(String::from("localhost")));
+    }
+
+    #[rstest(
+        input_host,
+        input_address,
+        expected_allowed,
+        case(Some("localhost:7878"), "127.0.0.1:7878", true),
+        case(Some("LOCALHOST"), "127.0.0.1:7878", true),
+        case(Some("127.0.0.1:7878"), "127.0.0.1:7878", true),
+        case(Some("[::1]:7878"), "[::1]:7878", true),
+        case(Some("192.168.1.2:7878"), "192.168.1.2:7878", true),
+        case(Some("192.168.1.2:7878"), "0.0.0.0:7878", true),
+        case(Some("192.168.1.3:7878"), "192.168.1.2:7878", false),
+        case(Some("rebound.example.com:7878"), "127.0.0.1:7878", false),
+        case(Some("localhost.example.com"), "127.0.0.1:7878", false),
+        case(Some("[::1"), "[::1]:7878", false),
+        case(None, "127.0.0.1:7878", false)
+    )]
+    fn is_allowed_host_test(
+        input_host: Option<&str>,
+        input_address: &str,
+        expected_allowed: bool,
+    ) {
+        assert_eq!(
+            is_allowed_host(input_host, input_address.parse().unwrap()),
+            expected_allowed
+        );
+    }
+
+    #[rstest]
+    fn read_request_rejects_long_lines_test() {
+        let long_request_line =
+            format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(10_000));
+        let many_headers =
+            format!("GET / HTTP/1.1\r\n{}\r\n", "A: b\r\n".repeat(101));
+
+        assert!(read_request(long_request_line.as_bytes()).is_err());
+        assert!(read_request(many_headers.as_bytes()).is_err());
+    }
+
+    #[rstest(
+        input_address,
+        input_allow_remote,
+        expected_refused,
+        case("0.0.0.0:0", false, true),
+        case("127.0.0.1:0", false, false),
+        case("[::1]:0", false, false)
+    )]
+    fn bind_listener_test(
+        input_address: &str,
+        input_allow_remote: bool,
+        expected_refused: bool,
+    ) {
+        let refused = match bind_listener(
+            input_address.parse().unwrap(),
+            input_allow_remote,
+        ) {
+            Ok(_) => false,
+            Err(e) => e.error.is_some_and(|error| {
+                error.to_string().contains("--allow-remote")
+            }),
+        };
+
+        assert_eq!(refused, expected_refused);
+    }
+
+    #[rstest(
+        input_request_line,
+        expected_path,
+        case("GET / HTTP/1.1\r\n", Some("/")),
+        case(
+            "GET /package/a/1.0.0?x=1 HTTP/1.1\r\n",
+            Some("/package/a/1.0.0")
+        ),
+        case("POST / HTTP/1.1\r\n", None),
+        case("", None)
+    )]
+    fn parse_request_path_test(
+        input_request_line: &str,
+        expected_path: Option<&str>,
+    ) {
+        assert_eq!(parse_request_path(input_request_line), expected_path);
+    }
+
+    #[rstest(
+        input_path,
+        expected_route,
+        case("/", Route::Report),
+        case(
+            "/package/serde/1.0.0",
+            Route::Package {
+                name: "serde",
+                version: "1.0.0"
+            }
+        ),
+        case("/package/serde", Route::NotFound),
+        case("/favicon.ico", Route::NotFound)
+    )]
+    fn route_test(input_path: &str, expected_route: Route) {
+        assert_eq!(route(input_path), expected_route);
+    }
+
+    #[rstest]
+    fn respond_test() {
+        let mut report = SafetyReport::default();
+        let package = PackageInfo::new(cargo_geiger_serde::PackageId {
+            name: String::from("serde"),
+            version: Version::new(1, 0, 0),
+            source: Source::Registry {
+                name: String::from("crates.io"),
+                url: Url::parse("https://github.com/rust-lang/crates.io-index")
+                    .unwrap(),
+            },
+        });
+        report.packages.insert(
+            package.id.clone(),
+            ReportEntry {
+                package,
+                unsafety: UnsafeInfo::default(),
+            },
+        );
+
+        let report_response = respond(&Route::Report, &report);
+        assert!(report_response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(report_response.contains("href=\"/package/serde/1.0.0\""));
+
+        let package_response = respond(
+            &Route::Package {
+                name: "serde",
+                version: "1.0.0",
+            },
+            &report,
+        );
+        assert!(package_response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(package_response.contains("serde 1.0.0"));
+
+        let missing_response = respond(
+            &Route::Package {
+                name: "serde",
+                version: "2.0.0",
+            },
+            &report,
+        );
+        assert!(missing_response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+    }
+
+    #[rstest]
+    fn http_response_test() {
+        assert_eq!(
+            http_response("404 Not Found", "Not found"),
+            "HTTP/1.1 404 Not Found\r\nContent-Type: text/html; \
+             charset=utf-8\r\nContent-Length: 9\r\nConnection: close\r\n\r\n\
+             Not found"
+        );
+    }
+}
@@ -1,16 +1,50 @@
+use crate::cli::get_cfgs;
 use crate::format::emoji_symbols::EmojiSymbols;
+use crate::format::i18n::{tr, Message};
+use crate::format::print_config::{MetricsFilter, PrintConfig};
 use crate::format::table::{
     create_table_from_text_tree_lines, TableParameters, UNSAFE_COUNTERS_HEADER,
 };
-use crate::format::SymbolKind;
+use crate::format::{pad_to_width, SymbolKind};
 use crate::graph::Graph;
 use crate::tree::traversal::walk_dependency_tree;
 
+use super::super::attest::{write_attestation, GeigerSummary};
+use super::super::baseline::{construct_baseline_report, save_baseline};
+use super::super::blame::{blame_workspace_unsafe, construct_blame_lines};
+use super::super::checksum::find_checksum_mismatches;
+use super::super::duplicates::{
+    construct_duplicated_file_lines, find_duplicated_files,
+};
+use super::super::github_output::{write_github_outputs, GitHubOutputs};
+use super::super::inactive::{
+    construct_inactive_dependency_lines, find_inactive_dependencies,
+};
+use super::super::latent::{construct_latent_unsafe_lines, find_latent_unsafe};
+use super::super::most_unsafe::{
+    construct_most_unsafe_dependency_lines, find_most_unsafe_dependency,
+};
+use super::super::party::{construct_party_split_lines, find_party_split};
+use super::super::pr_comment::{write_pr_comment, PrComment};
+use super::super::release_age::{
+    collect_release_ages, construct_unmaintained_lines,
+};
+use super::super::rust_version::find_rust_version_notes;
+use super::super::stage::{
+    construct_execution_stage_lines, find_execution_stage_totals,
+};
+use super::super::sysroot::{construct_sysroot_lines, scan_sysroot};
+use super::super::unsafe_docs::{
+    construct_unsafe_docs_lines, find_unsafe_docs,
+};
+use super::super::violation::Check;
 use super::super::{
-    construct_rs_files_used_lines, list_files_used_but_not_scanned,
-    ScanDetails, ScanParameters,
+    construct_asm_locations_lines, construct_rs_files_used_lines,
+    from_cargo_package_id, list_files_used_but_not_scanned,
+    list_yanked_packages_with_unsafe, unsafe_stats, ScanDetails,
+    ScanParameters,
 };
-use super::scan;
+use super::{run_scan_checks, scan};
 
 use cargo::core::shell::Verbosity;
 use cargo::core::{PackageId, PackageSet, Workspace};
@@ -22,7 +56,7 @@ use std::fmt;
 pub fn scan_to_table(
     workspace: &Workspace,
     package_set: &PackageSet,
-    root_pack_id: PackageId,
+    root_pack_ids: &[PackageId],
     graph: &Graph,
     scan_parameters: &ScanParameters,
 ) -> CliResult {
@@ -37,21 +71,41 @@ pub fn scan_to_table(
         let mut rs_files_used_lines =
             construct_rs_files_used_lines(&rs_files_used);
         scan_output_lines.append(&mut rs_files_used_lines);
+        let mut asm_locations_lines =
+            construct_asm_locations_lines(&geiger_context);
+        scan_output_lines.append(&mut asm_locations_lines);
     }
 
-    let emoji_symbols = EmojiSymbols::new(scan_parameters.print_config.charset);
-    let mut output_key_lines = construct_key_lines(&emoji_symbols);
+    let emoji_symbols = EmojiSymbols::new(
+        scan_parameters.print_config.charset,
+        &scan_parameters.print_config.symbols,
+    );
+    let mut output_key_lines =
+        construct_key_lines(&emoji_symbols, scan_parameters.print_config);
     scan_output_lines.append(&mut output_key_lines);
 
-    let text_tree_lines = walk_dependency_tree(
-        root_pack_id,
-        &graph,
-        &scan_parameters.print_config,
-    );
+    // The members of a virtual workspace are printed as a forest, with the
+    // totals of the packages counted once.
+    let text_tree_lines = root_pack_ids
+        .iter()
+        .flat_map(|root_pack_id| {
+            walk_dependency_tree(
+                *root_pack_id,
+                graph,
+                &scan_parameters.print_config.tree_options(),
+            )
+        })
+        .collect();
+    let target_cfgs = get_cfgs(
+        scan_parameters.config,
+        &scan_parameters.args.target,
+        workspace,
+    )?;
     let table_parameters = TableParameters {
         geiger_context: &geiger_context,
-        print_config: &scan_parameters.print_config,
+        print_config: scan_parameters.print_config,
         rs_files_used: &rs_files_used,
+        target_cfgs: target_cfgs.as_deref(),
     };
 
     let (mut table_lines, mut warning_count) =
@@ -60,8 +114,122 @@ pub fn scan_to_table(
             &table_parameters,
             text_tree_lines,
         );
+    let pr_comment_table_lines = if scan_parameters.args.pr_comment.is_some() {
+        table_lines.clone()
+    } else {
+        Vec::new()
+    };
     scan_output_lines.append(&mut table_lines);
 
+    let stage_totals = find_execution_stage_totals(
+        &geiger_context,
+        graph,
+        root_pack_ids,
+        package_set,
+        &rs_files_used,
+    )?;
+    let mut stage_lines = construct_execution_stage_lines(&stage_totals);
+    scan_output_lines.append(&mut stage_lines);
+
+    if scan_parameters.args.first_party_split {
+        let party_split =
+            find_party_split(&geiger_context, graph, workspace, &rs_files_used);
+        let mut party_split_lines = construct_party_split_lines(&party_split);
+        scan_output_lines.append(&mut party_split_lines);
+    }
+
+    // The direct dependencies of a forest are not those of one package.
+    let most_unsafe_dependency = match root_pack_ids {
+        [root_pack_id] => find_most_unsafe_dependency(
+            &geiger_context,
+            graph,
+            *root_pack_id,
+            &rs_files_used,
+        ),
+        _ => None,
+    };
+    if let Some(most_unsafe_dependency) = most_unsafe_dependency {
+        let mut most_unsafe_dependency_lines =
+            construct_most_unsafe_dependency_lines(
+                &most_unsafe_dependency,
+                scan_parameters.args.why_most_unsafe,
+            );
+        scan_output_lines.append(&mut most_unsafe_dependency_lines);
+    }
+
+    let unsafe_docs =
+        find_unsafe_docs(&geiger_context, package_set, &rs_files_used)?;
+    let mut unsafe_docs_lines = construct_unsafe_docs_lines(&unsafe_docs);
+    scan_output_lines.append(&mut unsafe_docs_lines);
+
+    let duplicated_files = find_duplicated_files(&geiger_context, package_set)?;
+    let mut duplicated_file_lines =
+        construct_duplicated_file_lines(&duplicated_files);
+    scan_output_lines.append(&mut duplicated_file_lines);
+
+    if scan_parameters.args.include_release_age {
+        let unsafe_package_ids = graph
+            .nodes
+            .keys()
+            .filter(|package_id| {
+                geiger_context
+                    .package_id_to_metrics
+                    .get(package_id)
+                    .is_some_and(|package_metrics| {
+                        unsafe_stats(package_metrics, &rs_files_used)
+                            .used
+                            .has_unsafe()
+                    })
+            })
+            .map(|package_id| from_cargo_package_id(*package_id))
+            .collect::<Vec<cargo_geiger_serde::PackageId>>();
+        let release_ages = collect_release_ages(
+            unsafe_package_ids.iter(),
+            scan_parameters.args,
+            scan_parameters.config,
+        );
+        let mut unmaintained_lines =
+            construct_unmaintained_lines(&release_ages);
+        scan_output_lines.append(&mut unmaintained_lines);
+    }
+
+    if scan_parameters.args.latent_unsafe {
+        let latent_unsafe =
+            find_latent_unsafe(&geiger_context, graph, target_cfgs.as_deref());
+        let mut latent_unsafe_lines =
+            construct_latent_unsafe_lines(&latent_unsafe);
+        scan_output_lines.append(&mut latent_unsafe_lines);
+    }
+
+    if scan_parameters.args.show_inactive {
+        let inactive_dependencies = find_inactive_dependencies(
+            scan_parameters.config,
+            workspace,
+            package_set,
+            graph,
+            scan_parameters.print_config,
+        )?;
+        let mut inactive_lines =
+            construct_inactive_dependency_lines(&inactive_dependencies);
+        scan_output_lines.append(&mut inactive_lines);
+    }
+
+    if scan_parameters.args.include_sysroot {
+        let sysroot_crates = scan_sysroot(
+            scan_parameters.config,
+            workspace,
+            scan_parameters.print_config,
+        )?;
+        let mut sysroot_lines = construct_sysroot_lines(&sysroot_crates);
+        scan_output_lines.append(&mut sysroot_lines);
+    }
+
+    if scan_parameters.args.blame {
+        let unsafe_blame = blame_workspace_unsafe(&geiger_context, workspace);
+        let mut blame_lines = construct_blame_lines(&unsafe_blame);
+        scan_output_lines.append(&mut blame_lines);
+    }
+
     for scan_output_line in scan_output_lines {
         println!("{}", scan_output_line);
     }
@@ -71,19 +239,123 @@ pub fn scan_to_table(
     warning_count += used_but_not_scanned.len() as u64;
     for path in &used_but_not_scanned {
         eprintln!(
-            "WARNING: Dependency file was never scanned: {}",
+            "{} {}",
+            tr(
+                scan_parameters.print_config.lang,
+                Message::DependencyFileNeverScanned
+            ),
             path.display()
         );
     }
+    for package_id in
+        list_yanked_packages_with_unsafe(&geiger_context, graph, &rs_files_used)
+    {
+        eprintln!(
+            "{} {}",
+            tr(scan_parameters.print_config.lang, Message::YankedWithUnsafe),
+            package_id
+        );
+    }
+    for (package_id, notes) in find_rust_version_notes(
+        scan_parameters.config,
+        workspace,
+        package_set,
+        graph,
+    )? {
+        for note in notes {
+            eprintln!(
+                "{} {}, {}",
+                tr(
+                    scan_parameters.print_config.lang,
+                    Message::RustVersionMismatch
+                ),
+                package_id,
+                note
+            );
+        }
+    }
+    if scan_parameters.args.verify_checksums {
+        for (package_id, mismatches) in
+            find_checksum_mismatches(workspace, package_set, graph)?
+        {
+            for mismatch in mismatches {
+                eprintln!(
+                    "{} {}, {}",
+                    tr(
+                        scan_parameters.print_config.lang,
+                        Message::ChecksumMismatch
+                    ),
+                    package_id,
+                    mismatch
+                );
+            }
+        }
+    }
 
-    if warning_count > 0 {
-        Err(CliError::new(
-            anyhow::Error::new(FoundWarningsError { warning_count }),
-            1,
-        ))
+    let mut checks = run_scan_checks(
+        &geiger_context,
+        &rs_files_used,
+        workspace,
+        root_pack_ids,
+        graph,
+        scan_parameters,
+    )?;
+    if let Some(save_baseline_path) = &scan_parameters.args.save_baseline {
+        save_baseline(
+            save_baseline_path,
+            &construct_baseline_report(
+                &geiger_context,
+                graph,
+                root_pack_ids,
+                &rs_files_used,
+            ),
+        )?;
+    }
+    let warning_violations = if warning_count > 0 {
+        vec![format!("{} warning(s) found by the scan", warning_count)]
     } else {
-        Ok(())
+        Vec::new()
+    };
+    checks.push(Check::new(
+        warning_violations,
+        FoundWarningsError { warning_count },
+    ));
+    let checks_passed = checks.passed();
+
+    if scan_parameters.args.github_output {
+        let github_outputs = GitHubOutputs::new(
+            &geiger_context,
+            graph,
+            &rs_files_used,
+            checks_passed,
+        );
+        write_github_outputs(&github_outputs)
+            .map_err(|e| CliError::new(e.into(), 1))?;
     }
+
+    if let Some(attest_path) = &scan_parameters.args.attest {
+        let geiger_summary = GeigerSummary::new(
+            &geiger_context,
+            graph,
+            &rs_files_used,
+            checks_passed,
+        );
+        write_attestation(attest_path, workspace, geiger_summary)?;
+    }
+
+    if let Some(pr_comment_path) = &scan_parameters.args.pr_comment {
+        let pr_comment = PrComment::new(
+            &geiger_context,
+            graph,
+            &rs_files_used,
+            checks.violations(),
+            &pr_comment_table_lines,
+        );
+        write_pr_comment(pr_comment_path, &pr_comment)
+            .map_err(|e| CliError::new(e.into(), 1))?;
+    }
+
+    checks.report(scan_parameters.args)
 }
 
 #[derive(Debug)]
@@ -93,30 +365,45 @@ struct FoundWarningsError {
 
 impl Error for FoundWarningsError {}
 
-/// Forward Display to Debug.
 impl fmt::Display for FoundWarningsError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::Debug::fmt(self, f)
+        write!(f, "found {} warning(s)", self.warning_count)
     }
 }
 
-fn construct_key_lines(emoji_symbols: &EmojiSymbols) -> Vec<String> {
+fn construct_key_lines(
+    emoji_symbols: &EmojiSymbols,
+    print_config: &PrintConfig,
+) -> Vec<String> {
+    let lang = print_config.lang;
     let mut output_key_lines = Vec::<String>::new();
 
     output_key_lines.push(String::new());
-    output_key_lines.push(String::from("Metric output format: x/y"));
-    output_key_lines
-        .push(String::from("    x = unsafe code used by the build"));
-    output_key_lines
-        .push(String::from("    y = total unsafe code found in the crate"));
+    let metric_messages = match print_config.metrics_filter {
+        MetricsFilter::All => vec![
+            Message::MetricOutputFormat,
+            Message::MetricUsed,
+            Message::MetricTotal,
+        ],
+        MetricsFilter::Used => {
+            vec![Message::MetricOutputFormatSingle, Message::MetricUsed]
+        }
+        MetricsFilter::Unused => {
+            vec![Message::MetricOutputFormatSingle, Message::MetricUnused]
+        }
+    };
+    for message in metric_messages {
+        output_key_lines.push(String::from(tr(lang, message)));
+    }
     output_key_lines.push(String::new());
-    output_key_lines.push(String::from("Symbols: "));
+    output_key_lines.push(String::from(tr(lang, Message::Symbols)));
 
-    let forbids = "No `unsafe` usage found, declares #![forbid(unsafe_code)]";
-    let unknown = "No `unsafe` usage found, missing #![forbid(unsafe_code)]";
-    let guilty = "`unsafe` usage found";
+    let forbids = tr(lang, Message::NoneDetectedForbidsUnsafe);
+    let unknown = tr(lang, Message::NoneDetectedAllowsUnsafe);
+    let guilty = tr(lang, Message::UnsafeDetected);
+    let mismatch = tr(lang, Message::ForbidMismatch);
 
-    let shift_sequence = if emoji_symbols.will_output_emoji() {
+    let shift_sequence = if emoji_symbols.needs_cursor_shift() {
         "\r\x1B[7C" // The radiation icon's Unicode width is 2,
                     // but by most terminals it seems to be rendered at width 1.
     } else {
@@ -127,14 +414,15 @@ fn construct_key_lines(emoji_symbols: &EmojiSymbols) -> Vec<String> {
         (SymbolKind::Lock, "", forbids),
         (SymbolKind::QuestionMark, "", unknown),
         (SymbolKind::Rads, shift_sequence, guilty),
+        (SymbolKind::Warning, shift_sequence, mismatch),
     ];
 
     for (symbol_kind, shift_sequence, string_values) in
         symbol_kinds_to_string_values
     {
         output_key_lines.push(format!(
-            "    {: <2}{} = {}",
-            emoji_symbols.emoji(symbol_kind),
+            "    {}{} = {}",
+            pad_to_width(&emoji_symbols.emoji(symbol_kind).to_string(), 2),
             shift_sequence,
             string_values
         ));
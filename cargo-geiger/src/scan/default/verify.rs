@@ -0,0 +1,476 @@
+use crate::format::treemap::unsafe_count;
+use crate::graph::Graph;
+
+use super::super::render::read_report;
+use super::super::{
+    file_stats, package_metrics, run_checks, unsafe_stats, ScanDetails,
+    ScanParameters,
+};
+use super::{construct_scan_options, scan};
+
+use cargo::core::{PackageId, PackageSet, Workspace};
+use cargo::{CliError, CliResult};
+use cargo_geiger_serde::{
+    CounterBlock, FileUnsafeInfo, ScanOptions, UnsafeInfo,
+};
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Scans the workspace again and compares the unsafe usage of every package
+/// with a previously published JSON report, listing the mismatched entries.
+/// Only the used and unused counters and `forbids_unsafe` are compared, the
+/// optional sections of the report depend on the options of the scan. When
+/// the report lists the files with their digests, the current sources are
+/// also compared with that inventory. A report produced with other scan
+/// options is rejected without comparing anything.
+pub fn scan_verify(
+    workspace: &Workspace,
+    package_set: &PackageSet,
+    root_pack_ids: &[PackageId],
+    graph: &Graph,
+    scan_parameters: &ScanParameters,
+    report_path: &Path,
+) -> CliResult {
+    let published_report = read_report(report_path)?;
+    let scan_options = construct_scan_options(scan_parameters.args);
+    if published_report.scan_options.as_ref() != Some(&scan_options) {
+        return Err(CliError::new(
+            anyhow::Error::new(ScanOptionsMismatchError {
+                published: published_report.scan_options,
+                current: scan_options,
+            }),
+            1,
+        ));
+    }
+    let ScanDetails {
+        rs_files_used,
+        geiger_context,
+    } = scan(workspace, package_set, scan_parameters)?;
+    let current_unsafety =
+        package_metrics(&geiger_context, graph, root_pack_ids)
+            .filter_map(|(package, package_metrics)| {
+                package_metrics.map(|package_metrics| {
+                    let mut unsafe_info =
+                        unsafe_stats(package_metrics, &rs_files_used);
+                    unsafe_info.files =
+                        file_stats(package_metrics, &rs_files_used);
+                    (package_name(&package.id), unsafe_info)
+                })
+            })
+            .collect::<BTreeMap<String, UnsafeInfo>>();
+    let published_unsafety = published_report
+        .packages
+        .values()
+        .map(|entry| (package_name(&entry.package.id), entry.unsafety.clone()))
+        .collect::<BTreeMap<String, UnsafeInfo>>();
+
+    let mut mismatches =
+        find_report_mismatches(&published_unsafety, &current_unsafety);
+    for (package, published) in &published_unsafety {
+        if let Some(current) = current_unsafety.get(package) {
+            mismatches.extend(find_source_mismatches(
+                package,
+                &published.files,
+                &current.files,
+            ));
+        }
+    }
+    for mismatch in &mismatches {
+        println!("MISMATCH: {}", mismatch);
+    }
+    if mismatches.is_empty() {
+        println!(
+            "PASS: the report matches the current lockfile and sources, {} \
+             package(s) verified.",
+            current_unsafety.len()
+        );
+        run_checks(
+            &geiger_context,
+            graph,
+            root_pack_ids,
+            workspace,
+            scan_parameters,
+        )?
+        .report(scan_parameters.args)
+    } else {
+        println!(
+            "FAIL: {} mismatched entries in the report.",
+            mismatches.len()
+        );
+        Err(CliError::new(
+            anyhow::Error::new(ReportMismatchError {
+                mismatch_count: mismatches.len() as u64,
+            }),
+            1,
+        ))
+    }
+}
+
+#[derive(Debug)]
+struct ReportMismatchError {
+    mismatch_count: u64,
+}
+
+impl Error for ReportMismatchError {}
+
+impl fmt::Display for ReportMismatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} mismatched entries in the report",
+            self.mismatch_count
+        )
+    }
+}
+
+#[derive(Debug)]
+struct ScanOptionsMismatchError {
+    published: Option<ScanOptions>,
+    current: ScanOptions,
+}
+
+impl Error for ScanOptionsMismatchError {}
+
+impl fmt::Display for ScanOptionsMismatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.published {
+            Some(published) => write!(
+                f,
+                "the report was produced with other scan options, {} \
+                 instead of {}",
+                describe_scan_options(published),
+                describe_scan_options(&self.current)
+            ),
+            None => write!(
+                f,
+                "the report does not record its scan options, it was \
+                 produced by an earlier version"
+            ),
+        }
+    }
+}
+
+/// The scan options as the arguments they are given with.
+fn describe_scan_options(scan_options: &ScanOptions) -> String {
+    let mut arguments = Vec::new();
+    if scan_options.include_tests {
+        arguments.push(String::from("--include-tests"));
+    }
+    if scan_options.all_targets {
+        arguments.push(String::from("--all-targets"));
+    }
+    for kind in &scan_options.selected_targets {
+        arguments.push(format!("--{}", kind));
+    }
+    if scan_options.all_features {
+        arguments.push(String::from("--all-features"));
+    }
+    if scan_options.no_default_features {
+        arguments.push(String::from("--no-default-features"));
+    }
+    if !scan_options.features.is_empty() {
+        arguments.push(format!(
+            "--features \"{}\"",
+            scan_options.features.join(" ")
+        ));
+    }
+    if let Some(target) = &scan_options.target {
+        arguments.push(format!("--target {}", target));
+    }
+    if arguments.is_empty() {
+        String::from("the default options")
+    } else {
+        format!("`{}`", arguments.join(" "))
+    }
+}
+
+/// Compares the files of a package with the inventory of the report by
+/// their SHA-256 digests, the paths differ between machines. Packages
+/// without an inventory, from reports without `--include-files`, are
+/// skipped.
+fn find_source_mismatches(
+    package: &str,
+    published_files: &BTreeMap<PathBuf, FileUnsafeInfo>,
+    current_files: &BTreeMap<PathBuf, FileUnsafeInfo>,
+) -> Vec<String> {
+    let digests = |files: &BTreeMap<PathBuf, FileUnsafeInfo>| {
+        files
+            .values()
+            .filter_map(|file| file.sha256.clone())
+            .collect::<BTreeSet<String>>()
+    };
+    let published_digests = digests(published_files);
+    if published_digests.is_empty() {
+        return vec![];
+    }
+    let current_digests = digests(current_files);
+
+    let mut mismatches = Vec::new();
+    for (path, file) in published_files {
+        if let Some(sha256) = &file.sha256 {
+            if !current_digests.contains(sha256) {
+                mismatches.push(format!(
+                    "{}: {} in the report differs from the current sources",
+                    package,
+                    path.display()
+                ));
+            }
+        }
+    }
+    for (path, file) in current_files {
+        match &file.sha256 {
+            Some(sha256) if published_digests.contains(sha256) => {}
+            _ => mismatches.push(format!(
+                "{}: {} is not in the inventory of the report",
+                package,
+                path.display()
+            )),
+        }
+    }
+    mismatches
+}
+
+/// Describes every package that is only in one of the reports or whose
+/// unsafe usage differs, sorted by package.
+fn find_report_mismatches(
+    published_unsafety: &BTreeMap<String, UnsafeInfo>,
+    current_unsafety: &BTreeMap<String, UnsafeInfo>,
+) -> Vec<String> {
+    let mut mismatches = Vec::new();
+    for (package, published) in published_unsafety {
+        let current = match current_unsafety.get(package) {
+            Some(current) => current,
+            None => {
+                mismatches.push(format!(
+                    "{}: in the report, but not in the current dependency \
+                     graph",
+                    package
+                ));
+                continue;
+            }
+        };
+        for (name, published_counters, current_counters) in &[
+            ("used", &published.used, &current.used),
+            ("unused", &published.unused, &current.unused),
+        ] {
+            if !counters_match(published_counters, current_counters) {
+                mismatches.push(format!(
+                    "{}: {} unsafe usage is {} in the report, {} now",
+                    package,
+                    name,
+                    unsafe_count(published_counters),
+                    unsafe_count(current_counters)
+                ));
+            }
+        }
+        if published.forbids_unsafe != current.forbids_unsafe {
+            mismatches.push(format!(
+                "{}: forbids unsafe is {} in the report, {} now",
+                package, published.forbids_unsafe, current.forbids_unsafe
+            ));
+        }
+    }
+    for package in current_unsafety.keys() {
+        if !published_unsafety.contains_key(package) {
+            mismatches.push(format!(
+                "{}: in the current dependency graph, but not in the report",
+                package
+            ));
+        }
+    }
+    mismatches.sort();
+    mismatches
+}
+
+/// The counters that are part of every report, the optional categories like
+/// the watched API calls depend on the options of the scan.
+fn counters_match(published: &CounterBlock, current: &CounterBlock) -> bool {
+    published.functions == current.functions
+        && published.exprs == current.exprs
+        && published.item_impls == current.item_impls
+        && published.item_traits == current.item_traits
+        && published.methods == current.methods
+}
+
+/// Packages are matched by name and version, path sources differ between
+/// machines.
+fn package_name(package_id: &cargo_geiger_serde::PackageId) -> String {
+    format!("{} {}", package_id.name, package_id.version)
+}
+
+#[cfg(test)]
+mod verify_tests {
+    use super::*;
+
+    use cargo_geiger_serde::Count;
+    use rstest::*;
+
+    #[rstest]
+    fn find_report_mismatches_test() {
+        let mut published_unsafety = BTreeMap::new();
+        published_unsafety
+            .insert(String::from("a 1.0.0"), create_info(1, true));
+        published_unsafety
+            .insert(String::from("b 1.0.0"), create_info(2, false));
+        published_unsafety
+            .insert(String::from("removed 1.0.0"), create_info(0, false));
+        let mut current_unsafety = BTreeMap::new();
+        current_unsafety.insert(String::from("a 1.0.0"), create_info(1, true));
+        current_unsafety.insert(String::from("b 1.0.0"), create_info(3, true));
+        current_unsafety
+            .insert(String::from("added 1.0.0"), create_info(0, false));
+
+        assert_eq!(
+            find_report_mismatches(&published_unsafety, &current_unsafety),
+            vec![
+                String::from(
+                    "added 1.0.0: in the current dependency graph, but not in \
+                     the report"
+                ),
+                String::from(
+                    "b 1.0.0: forbids unsafe is false in the report, true now"
+                ),
+                String::from(
+                    "b 1.0.0: used unsafe usage is 2 in the report, 3 now"
+                ),
+                String::from(
+                    "removed 1.0.0: in the report, but not in the current \
+                     dependency graph"
+                ),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn find_report_mismatches_ignores_optional_sections_test() {
+        let published = create_info(1, false);
+        let mut current = create_info(1, false);
+        current
+            .used
+            .api_calls
+            .insert(String::from("mem::transmute"), 1);
+        current
+            .platforms
+            .insert(String::from("windows"), current.used.clone());
+
+        let mut published_unsafety = BTreeMap::new();
+        published_unsafety.insert(String::from("a 1.0.0"), published);
+        let mut current_unsafety = BTreeMap::new();
+        current_unsafety.insert(String::from("a 1.0.0"), current);
+
+        assert!(
+            find_report_mismatches(&published_unsafety, &current_unsafety)
+                .is_empty()
+        );
+    }
+
+    #[rstest(
+        input_scan_options,
+        expected_description,
+        case(ScanOptions::default(), "the default options"),
+        case(
+            ScanOptions {
+                include_tests: true,
+                selected_targets: vec![String::from("lib")],
+                features: vec![String::from("a"), String::from("b")],
+                target: Some(String::from("wasm32-unknown-unknown")),
+                ..Default::default()
+            },
+            "`--include-tests --lib --features \"a b\" --target \
+             wasm32-unknown-unknown`"
+        )
+    )]
+    fn describe_scan_options_test(
+        input_scan_options: ScanOptions,
+        expected_description: &str,
+    ) {
+        assert_eq!(
+            describe_scan_options(&input_scan_options),
+            expected_description
+        );
+    }
+
+    #[rstest]
+    fn find_source_mismatches_test() {
+        let published_files = vec![
+            ("/a/src/lib.rs", Some("1")),
+            ("/a/src/changed.rs", Some("2")),
+        ]
+        .into_iter()
+        .map(create_file)
+        .collect();
+        let current_files = vec![
+            ("/b/src/lib.rs", Some("1")),
+            ("/b/src/changed.rs", Some("3")),
+            ("/b/src/unreadable.rs", None),
+        ]
+        .into_iter()
+        .map(create_file)
+        .collect();
+
+        assert_eq!(
+            find_source_mismatches("a 1.0.0", &published_files, &current_files),
+            vec![
+                String::from(
+                    "a 1.0.0: /a/src/changed.rs in the report differs from \
+                     the current sources"
+                ),
+                String::from(
+                    "a 1.0.0: /b/src/changed.rs is not in the inventory of \
+                     the report"
+                ),
+                String::from(
+                    "a 1.0.0: /b/src/unreadable.rs is not in the inventory \
+                     of the report"
+                ),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn find_source_mismatches_skips_reports_without_inventory_test() {
+        let published_files = vec![("/a/src/lib.rs", None)]
+            .into_iter()
+            .map(create_file)
+            .collect();
+        let current_files = vec![("/b/src/lib.rs", Some("1"))]
+            .into_iter()
+            .map(create_file)
+            .collect();
+
+        assert!(find_source_mismatches(
+            "a 1.0.0",
+            &published_files,
+            &current_files
+        )
+        .is_empty());
+    }
+
+    fn create_file(
+        (path, sha256): (&str, Option<&str>),
+    ) -> (PathBuf, FileUnsafeInfo) {
+        (
+            PathBuf::from(path),
+            FileUnsafeInfo {
+                sha256: sha256.map(String::from),
+                ..Default::default()
+            },
+        )
+    }
+
+    fn create_info(used_unsafe_exprs: u64, forbids_unsafe: bool) -> UnsafeInfo {
+        UnsafeInfo {
+            used: CounterBlock {
+                exprs: Count {
+                    safe: 10,
+                    unsafe_: used_unsafe_exprs,
+                },
+                ..Default::default()
+            },
+            forbids_unsafe,
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use super::github_output::unsafe_total;
+use super::{file_digest, from_cargo_package_id, GeigerContext};
+
+use cargo::core::PackageSet;
+use cargo::util::CargoResult;
+use cargo_geiger_serde::{CounterBlock, DuplicatedFile, FileCopy};
+use std::collections::BTreeMap;
+
+/// The files with unsafe code that are byte-identical in more than one
+/// package, e.g. vendored copies and forks, most unsafe first. Every copy of
+/// duplicated unsafe code has to be audited and patched on its own.
+pub fn find_duplicated_files(
+    geiger_context: &GeigerContext,
+    package_set: &PackageSet,
+) -> CargoResult<Vec<DuplicatedFile>> {
+    let mut unsafe_files = Vec::new();
+    for (package_id, package_metrics) in &geiger_context.package_id_to_metrics {
+        let package_root = package_set.get_one(*package_id)?.root();
+        for (path, rs_file_metrics_wrapper) in
+            &package_metrics.rs_path_to_metrics
+        {
+            let counters = &rs_file_metrics_wrapper.metrics.counters;
+            if !counters.has_unsafe() {
+                continue;
+            }
+            let sha256 = match file_digest(path) {
+                Some((_, sha256)) => sha256,
+                None => continue,
+            };
+            let file_copy = FileCopy {
+                package: from_cargo_package_id(*package_id),
+                path: path.strip_prefix(package_root).unwrap_or(path).into(),
+            };
+            unsafe_files.push((sha256, file_copy, counters.clone()));
+        }
+    }
+    Ok(group_duplicated_files(unsafe_files))
+}
+
+/// A line per copy of every duplicated file, below a header, or nothing when
+/// no file is duplicated.
+pub fn construct_duplicated_file_lines(
+    duplicated_files: &[DuplicatedFile],
+) -> Vec<String> {
+    if duplicated_files.is_empty() {
+        return Vec::new();
+    }
+    let mut lines =
+        vec![String::from("Unsafe code duplicated across packages:")];
+    for duplicated_file in duplicated_files {
+        lines.push(format!(
+            "    {} copies of a file with {} unsafe item(s):",
+            duplicated_file.copies.len(),
+            unsafe_total(&duplicated_file.counters)
+        ));
+        for file_copy in &duplicated_file.copies {
+            lines.push(format!(
+                "        {} {}: {}",
+                file_copy.package.name,
+                file_copy.package.version,
+                file_copy.path.display()
+            ));
+        }
+    }
+    lines.push(String::new());
+    lines
+}
+
+/// Groups the files by their digest, keeping the groups with copies in more
+/// than one package. A file included by several targets of one package is
+/// not a duplicate.
+fn group_duplicated_files(
+    unsafe_files: Vec<(String, FileCopy, CounterBlock)>,
+) -> Vec<DuplicatedFile> {
+    let mut files_by_digest = BTreeMap::new();
+    for (sha256, file_copy, counters) in unsafe_files {
+        files_by_digest
+            .entry(sha256.clone())
+            .or_insert_with(|| DuplicatedFile {
+                sha256,
+                counters,
+                copies: Vec::new(),
+            })
+            .copies
+            .push(file_copy);
+    }
+    let mut duplicated_files = files_by_digest
+        .into_values()
+        .map(|mut duplicated_file| {
+            duplicated_file.copies.sort_by(|a, b| {
+                a.package.cmp(&b.package).then(a.path.cmp(&b.path))
+            });
+            duplicated_file
+        })
+        .filter(|duplicated_file| {
+            duplicated_file
+                .copies
+                .windows(2)
+                .any(|pair| pair[0].package != pair[1].package)
+        })
+        .collect::<Vec<DuplicatedFile>>();
+    duplicated_files.sort_by(|a, b| {
+        unsafe_total(&b.counters)
+            .cmp(&unsafe_total(&a.counters))
+            .then(a.sha256.cmp(&b.sha256))
+    });
+    duplicated_files
+}
+
+#[cfg(test)]
+mod duplicates_tests {
+    use super::*;
+
+    use cargo_geiger_serde::{Count, PackageId, Source};
+    use rstest::*;
+    use semver::Version;
+    use std::path::PathBuf;
+    use url::Url;
+
+    #[rstest]
+    fn group_duplicated_files_test() {
+        let unsafe_files = vec![
+            create_unsafe_file("aaa", "vendor", "src/vendored/lib.rs", 2),
+            create_unsafe_file("aaa", "original", "src/lib.rs", 2),
+            create_unsafe_file("bbb", "fork", "src/lib.rs", 5),
+            create_unsafe_file("bbb", "original_fork", "src/lib.rs", 5),
+            create_unsafe_file("ccc", "single", "src/lib.rs", 9),
+            create_unsafe_file("ddd", "twice", "src/a.rs", 9),
+            create_unsafe_file("ddd", "twice", "src/b.rs", 9),
+        ];
+
+        let duplicated_files = group_duplicated_files(unsafe_files);
+
+        assert_eq!(
+            construct_duplicated_file_lines(&duplicated_files),
+            vec![
+                "Unsafe code duplicated across packages:",
+                "    2 copies of a file with 5 unsafe item(s):",
+                "        fork 1.0.0: src/lib.rs",
+                "        original_fork 1.0.0: src/lib.rs",
+                "    2 copies of a file with 2 unsafe item(s):",
+                "        original 1.0.0: src/lib.rs",
+                "        vendor 1.0.0: src/vendored/lib.rs",
+                "",
+            ]
+        );
+        assert!(construct_duplicated_file_lines(&[]).is_empty());
+    }
+
+    fn create_unsafe_file(
+        sha256: &str,
+        name: &str,
+        path: &str,
+        unsafe_exprs: u64,
+    ) -> (String, FileCopy, CounterBlock) {
+        let file_copy = FileCopy {
+            package: PackageId {
+                name: String::from(name),
+                version: Version::new(1, 0, 0),
+                source: Source::Registry {
+                    name: String::from("crates.io"),
+                    url: Url::parse(
+                        "https://github.com/rust-lang/crates.io-index",
+                    )
+                    .unwrap(),
+                },
+            },
+            path: PathBuf::from(path),
+        };
+        let counters = CounterBlock {
+            exprs: Count {
+                safe: 0,
+                unsafe_: unsafe_exprs,
+            },
+            ..Default::default()
+        };
+        (String::from(sha256), file_copy, counters)
+    }
+}
@@ -1,111 +1,679 @@
 use crate::format::print_config::PrintConfig;
 use crate::rs_file::{
-    into_rs_code_file, is_file_with_ext, RsFile, RsFileMetricsWrapper,
+    canonicalize, into_rs_code_file, is_file_with_ext, RsFile,
+    RsFileMetricsWrapper,
 };
 use crate::scan::PackageMetrics;
 
+use super::cache::MetricsCache;
+use super::observer::{ProgressObserver, ScanEvent, ScanObserver};
 use super::{GeigerContext, ScanMode};
 
 use cargo::core::package::PackageSet;
 use cargo::core::{Package, PackageId};
-use cargo::util::CargoResult;
+use cargo::util::{important_paths, CargoResult};
 use cargo::{CliError, Config};
-use geiger::{find_unsafe_in_file, IncludeTests};
-use std::collections::HashMap;
+use cargo_geiger_serde::ScanWarning;
+use geiger::{
+    find_unsafe_in_file, IncludeTests, IncludedFile, RsFileMetrics,
+    ScanFileError,
+};
+use glob::Pattern;
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
 use std::path::Path;
 use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
 use walkdir::WalkDir;
 
+/// Scans the packages, drawing a progress bar. When the files used by the
+/// build are given, the files of each package that no output lists are
+/// folded into its totals as soon as it is scanned, see `fold_files`.
 pub fn find_unsafe(
     mode: ScanMode,
     config: &Config,
     packages: &PackageSet,
     print_config: &PrintConfig,
+    rs_files_used: Option<&HashSet<PathBuf>>,
 ) -> Result<GeigerContext, CliError> {
-    let mut progress = cargo::util::Progress::new("Scanning", config);
-    let geiger_context = find_unsafe_in_packages(
-        packages,
-        print_config.allow_partial_results,
-        print_config.include_tests,
+    let mut progress_observer = ProgressObserver::new(config);
+    let geiger_context = find_unsafe_with_observer(
         mode,
-        |i, count| -> CargoResult<()> { progress.tick(i, count) },
+        config,
+        packages,
+        print_config,
+        rs_files_used,
+        &mut progress_observer,
     );
-    progress.clear();
+    progress_observer.clear();
+    warn_scan_warnings(config, &geiger_context.warnings)?;
     config.shell().status("Scanning", "done")?;
     Ok(geiger_context)
 }
 
-fn find_unsafe_in_packages<F>(
+/// Scans the packages like `find_unsafe`, passing the progress to `observer`
+/// instead of drawing a progress bar and leaving the warnings to the caller.
+pub fn find_unsafe_with_observer(
+    mode: ScanMode,
+    config: &Config,
+    packages: &PackageSet,
+    print_config: &PrintConfig,
+    rs_files_used: Option<&HashSet<PathBuf>>,
+    observer: &mut dyn ScanObserver,
+) -> GeigerContext {
+    // Only full scans are cached, entry point scans are incomplete.
+    let mut metrics_cache = match &mode {
+        ScanMode::Full => Some(MetricsCache::new(config, print_config)),
+        ScanMode::EntryPointsOnly => None,
+    };
+    let geiger_context = find_unsafe_in_packages(
+        packages,
+        print_config,
+        mode,
+        metrics_cache.as_mut(),
+        find_target_dir(config),
+        rs_files_used,
+        observer,
+    );
+    if let Some(metrics_cache) = metrics_cache {
+        metrics_cache.save_run_stats();
+    }
+    geiger_context
+}
+
+/// Prints the files that were left out of the scan, the scan itself goes on
+/// without them.
+pub fn warn_scan_warnings(
+    config: &Config,
+    warnings: &[ScanWarning],
+) -> CargoResult<()> {
+    for warning in warnings {
+        config.shell().warn(format!(
+            "skipped {} of {}: {}",
+            warning.path.display(),
+            warning.package,
+            warning.cause
+        ))?;
+    }
+    Ok(())
+}
+
+fn find_unsafe_in_packages(
     packs: &PackageSet,
-    allow_partial_results: bool,
-    include_tests: IncludeTests,
+    print_config: &PrintConfig,
     mode: ScanMode,
-    mut progress_step: F,
-) -> GeigerContext
-where
-    F: FnMut(usize, usize) -> CargoResult<()>,
-{
+    mut metrics_cache: Option<&mut MetricsCache>,
+    target_dir: Option<PathBuf>,
+    rs_files_used: Option<&HashSet<PathBuf>>,
+    observer: &mut dyn ScanObserver,
+) -> GeigerContext {
     let mut pack_id_to_metrics = HashMap::new();
+    // The sets of the scan below share a single allocation per path.
+    let mut paths = PathInterner::default();
+    let mut scanned_files = HashSet::new();
     let packs = packs.get_many(packs.package_ids()).unwrap();
-    let pack_code_files: Vec<_> = find_rs_files_in_packages(&packs).collect();
-    let pack_code_file_count = pack_code_files.len();
-    for (i, (pack_id, rs_code_file)) in pack_code_files.into_iter().enumerate()
-    {
-        let (is_entry_point, p) = match rs_code_file {
-            RsFile::LibRoot(pb) => (true, pb),
-            RsFile::BinRoot(pb) => (true, pb),
-            RsFile::CustomBuildRoot(pb) => (true, pb),
-            RsFile::Other(pb) => (false, pb),
-        };
+    let packs = match metrics_cache.as_mut() {
+        Some(metrics_cache) => packs
+            .into_iter()
+            .filter(|pack| match metrics_cache.get(pack) {
+                Some(mut package_metrics) => {
+                    scanned_files.extend(
+                        package_metrics
+                            .rs_path_to_metrics
+                            .keys()
+                            .map(|path| paths.intern(path)),
+                    );
+                    shrink_package_metrics(
+                        &mut package_metrics,
+                        print_config,
+                        rs_files_used,
+                    );
+                    pack_id_to_metrics
+                        .insert(pack.package_id(), package_metrics);
+                    false
+                }
+                None => true,
+            })
+            .collect(),
+        None => packs,
+    };
+    let mut warnings = Vec::new();
+    let mut pack_code_files = find_rs_files_in_packages(&packs, &mut warnings)
+        .into_iter()
+        .map(|(pack_id, rs_code_file)| {
+            let is_entry_point = !matches!(rs_code_file, RsFile::Other(_));
+            (pack_id, is_entry_point, paths.intern(rs_code_file.path()))
+        })
+        .collect::<Vec<(PackageId, bool, Rc<Path>)>>();
+    let mut known_files = pack_code_files
+        .iter()
+        .map(|(pack_id, _, path)| (*pack_id, path.clone()))
+        .collect::<HashSet<(PackageId, Rc<Path>)>>();
+    let pack_id_to_pack = packs
+        .iter()
+        .map(|pack| (pack.package_id(), *pack))
+        .collect::<HashMap<PackageId, &Package>>();
+    let mut out_dir_resolver = OutDirResolver::new(target_dir);
+    // Like rustc, files loaded as `#[path]` modules resolve the `#[path]` of
+    // their nested modules like `mod.rs` files, as far as they are known to
+    // be loaded that way when they are scanned.
+    let mut path_module_files = HashSet::new();
+    // Files pulled in with `#[path]` or `include!` from outside the package
+    // directory are inserted while scanning, hence the index based loop. They
+    // are scanned next, keeping the files of a package together.
+    let mut current_package: Option<(PackageId, Instant)> = None;
+    let mut i = 0;
+    while i < pack_code_files.len() {
+        let (pack_id, is_entry_point, p) = pack_code_files[i].clone();
+        i += 1;
         if let (false, ScanMode::EntryPointsOnly) = (is_entry_point, &mode) {
             continue;
         }
-        match find_unsafe_in_file(&p, include_tests) {
-            Err(e) => {
-                if allow_partial_results {
-                    eprintln!(
-                        "Failed to parse file: {}, {:?} ",
-                        &p.display(),
-                        e
-                    );
+        let package_metrics = pack_id_to_metrics.get(&pack_id);
+        if package_metrics.is_some_and(|m| m.not_fully_scanned) {
+            warnings.push(timeout_warning(
+                pack_id,
+                p.to_path_buf(),
+                print_config.timeout_per_crate,
+            ));
+            continue;
+        }
+        if current_package.map(|(package_id, _)| package_id) != Some(pack_id) {
+            if let Some((package_id, started)) = current_package {
+                finish_package(
+                    pack_id_to_pack[&package_id],
+                    pack_id_to_metrics.get_mut(&package_id),
+                    metrics_cache.as_deref_mut(),
+                    print_config,
+                    rs_files_used,
+                );
+                observer.on_event(ScanEvent::PackageFinished {
+                    package_id,
+                    elapsed: started.elapsed(),
+                });
+            }
+            observer.on_event(ScanEvent::PackageStarted {
+                package_id: pack_id,
+            });
+            current_package = Some((pack_id, Instant::now()));
+        }
+        let file_started = Instant::now();
+        let file_metrics = match print_config.timeout_per_crate {
+            Some(timeout) => {
+                let package_started = current_package
+                    .map_or(file_started, |(_, started)| started);
+                let remaining = timeout
+                    .checked_sub(package_started.elapsed())
+                    .unwrap_or_default();
+                find_unsafe_in_file_with_timeout(
+                    &p,
+                    print_config.include_tests,
+                    &print_config.unsafe_apis,
+                    remaining,
+                )
+            }
+            None => Some(find_unsafe_in_file(
+                &p,
+                print_config.include_tests,
+                &print_config.unsafe_apis,
+            )),
+        };
+        observer.on_event(ScanEvent::FileScanned {
+            package_id: pack_id,
+            path: p.to_path_buf(),
+            elapsed: file_started.elapsed(),
+            index: i - 1,
+            file_count: pack_code_files.len(),
+        });
+        match file_metrics {
+            None => {
+                warnings.push(timeout_warning(
+                    pack_id,
+                    p.to_path_buf(),
+                    print_config.timeout_per_crate,
+                ));
+                pack_id_to_metrics
+                    .entry(pack_id)
+                    .or_insert_with(PackageMetrics::default)
+                    .not_fully_scanned = true;
+            }
+            Some(Err(e)) => {
+                if print_config.allow_partial_results {
+                    warnings.push(ScanWarning {
+                        package: format!(
+                            "{} {}",
+                            pack_id.name(),
+                            pack_id.version()
+                        ),
+                        path: p.to_path_buf(),
+                        cause: format!("failed to parse the file, {:?}", e),
+                    });
                 } else {
                     panic!("Failed to parse file: {}, {:?} ", &p.display(), e);
                 }
             }
-            Ok(file_metrics) => {
+            Some(Ok(file_metrics)) => {
+                let pack = pack_id_to_pack[&pack_id];
+                let is_mod_rs =
+                    is_entry_point || path_module_files.contains(&p);
+                for included_file in &file_metrics.included_files {
+                    let included_path = match resolve_included_file(
+                        included_file,
+                        &p,
+                        is_mod_rs,
+                        pack,
+                        &mut out_dir_resolver,
+                    ) {
+                        Some(included_path) => paths.intern(&included_path),
+                        None => continue,
+                    };
+                    if let IncludedFile::ModulePath { .. } = included_file {
+                        path_module_files.insert(included_path.clone());
+                    }
+                    if known_files.insert((pack_id, included_path.clone())) {
+                        pack_code_files
+                            .insert(i, (pack_id, false, included_path));
+                    }
+                }
                 let package_metrics = pack_id_to_metrics
                     .entry(pack_id)
                     .or_insert_with(PackageMetrics::default);
                 let wrapper = package_metrics
                     .rs_path_to_metrics
-                    .entry(p)
+                    .entry(p.to_path_buf())
                     .or_insert_with(RsFileMetricsWrapper::default);
                 wrapper.metrics = file_metrics;
                 wrapper.is_crate_entry_point = is_entry_point;
+                scanned_files.insert(p);
             }
         }
-        let _ = progress_step(i, pack_code_file_count);
     }
-    GeigerContext { package_id_to_metrics: pack_id_to_metrics }
+    if let Some((package_id, started)) = current_package {
+        finish_package(
+            pack_id_to_pack[&package_id],
+            pack_id_to_metrics.get_mut(&package_id),
+            metrics_cache,
+            print_config,
+            rs_files_used,
+        );
+        observer.on_event(ScanEvent::PackageFinished {
+            package_id,
+            elapsed: started.elapsed(),
+        });
+    }
+    // The folded files are only known to be scanned until the scan ends.
+    let used_but_not_scanned = rs_files_used.map(|rs_files_used| {
+        let mut used_but_not_scanned = rs_files_used
+            .iter()
+            .filter(|path| !scanned_files.contains(path.as_path()))
+            .cloned()
+            .collect::<Vec<PathBuf>>();
+        used_but_not_scanned.sort();
+        used_but_not_scanned
+    });
+    // The order of the walk depends on the file system.
+    warnings.sort();
+    GeigerContext {
+        package_id_to_metrics: pack_id_to_metrics,
+        used_but_not_scanned,
+        warnings,
+    }
+}
+
+/// Caches the metrics of a package once all of its files are scanned, then
+/// shrinks them before the rest of the graph is scanned.
+fn finish_package(
+    package: &Package,
+    package_metrics: Option<&mut PackageMetrics>,
+    metrics_cache: Option<&mut MetricsCache>,
+    print_config: &PrintConfig,
+    rs_files_used: Option<&HashSet<PathBuf>>,
+) {
+    let package_metrics = match package_metrics {
+        Some(package_metrics) => package_metrics,
+        None => return,
+    };
+    // A package that was not fully scanned is scanned again on the next run.
+    if let Some(metrics_cache) =
+        metrics_cache.filter(|_| !package_metrics.not_fully_scanned)
+    {
+        metrics_cache.put(package, package_metrics);
+    }
+    shrink_package_metrics(package_metrics, print_config, rs_files_used);
+}
+
+/// Marks the generated files of a scanned package, then drops what no output
+/// reads: the locations of the unsafe usage unless they are listed, and when
+/// the files used by the build are given, the files that are folded into the
+/// totals of the package.
+fn shrink_package_metrics(
+    package_metrics: &mut PackageMetrics,
+    print_config: &PrintConfig,
+    rs_files_used: Option<&HashSet<PathBuf>>,
+) {
+    for (path, wrapper) in package_metrics.rs_path_to_metrics.iter_mut() {
+        wrapper.is_generated = is_generated_file(
+            path,
+            &wrapper.metrics,
+            &print_config.generated_globs,
+        );
+    }
+    if !print_config.retain_locations {
+        drop_locations(package_metrics);
+    }
+    if let Some(rs_files_used) = rs_files_used {
+        fold_files(package_metrics, rs_files_used);
+    }
 }
 
-fn find_rs_files_in_dir(dir: &Path) -> impl Iterator<Item = PathBuf> {
-    let walker = WalkDir::new(dir).into_iter();
-    walker.filter_map(|entry| {
-        let entry = entry.expect("walkdir error."); // TODO: Return result.
+/// Folds the files without unsafe code into the used or unused totals of the
+/// package. The crate entry points, the generated files and the files with
+/// unsafe code or `#[allow(unsafe_code)]` are kept, the detection status,
+/// the policies and the duplicated files look at them one by one.
+fn fold_files(
+    package_metrics: &mut PackageMetrics,
+    rs_files_used: &HashSet<PathBuf>,
+) {
+    let PackageMetrics {
+        rs_path_to_metrics,
+        folded,
+        ..
+    } = package_metrics;
+    rs_path_to_metrics.retain(|path, wrapper| {
+        let metrics = &mut wrapper.metrics;
+        if wrapper.is_crate_entry_point
+            || wrapper.is_generated
+            || metrics.counters.has_unsafe()
+            || !metrics.allow_unsafe_code_locations.is_empty()
+        {
+            return true;
+        }
+        if rs_files_used.contains(path) {
+            folded.used.fold(metrics);
+        } else {
+            folded.unused.fold(metrics);
+        }
+        false
+    });
+    rs_path_to_metrics.shrink_to_fit();
+}
+
+/// Scans the file on a thread of its own, giving up on it after `timeout`.
+/// The parser can not be interrupted, a file that is given up on is left to
+/// be scanned on its thread, which ends with the process.
+fn find_unsafe_in_file_with_timeout(
+    path: &Path,
+    include_tests: IncludeTests,
+    unsafe_apis: &[String],
+    timeout: Duration,
+) -> Option<Result<RsFileMetrics, ScanFileError>> {
+    let (sender, receiver) = mpsc::channel();
+    let path = path.to_path_buf();
+    let unsafe_apis = unsafe_apis.to_vec();
+    thread::spawn(move || {
+        let _ = sender.send(find_unsafe_in_file(
+            &path,
+            include_tests,
+            &unsafe_apis,
+        ));
+    });
+    receiver.recv_timeout(timeout).ok()
+}
+
+/// The warning for a file left out because its package took longer to scan
+/// than `--timeout-per-crate`.
+fn timeout_warning(
+    package_id: PackageId,
+    path: PathBuf,
+    timeout_per_crate: Option<Duration>,
+) -> ScanWarning {
+    ScanWarning {
+        package: format!("{} {}", package_id.name(), package_id.version()),
+        path,
+        cause: format!(
+            "the package exceeded --timeout-per-crate of {} seconds",
+            timeout_per_crate.unwrap_or_default().as_secs()
+        ),
+    }
+}
+
+/// Drops the unsafe locations of each file, only `--blame`, the unsafe
+/// listings, `--include-files`, the HTML output and the verbose output read
+/// them. A single `#[allow(unsafe_code)]` location is kept, the detection
+/// status only checks for one.
+fn drop_locations(package_metrics: &mut PackageMetrics) {
+    for wrapper in package_metrics.rs_path_to_metrics.values_mut() {
+        let metrics = &mut wrapper.metrics;
+        metrics.allow_unsafe_code_locations.truncate(1);
+        metrics.allow_unsafe_code_locations.shrink_to_fit();
+        metrics.asm_locations = Vec::new();
+        metrics.included_files = Vec::new();
+        metrics.undocumented_unsafe_blocks = Vec::new();
+        metrics.unsafe_items = Vec::new();
+        metrics.unsafe_locations = Vec::new();
+    }
+}
+
+/// Finds the canonical paths of the `.rs` files below `dir`, the entries that
+/// can not be read or canonicalized are skipped with a warning.
+pub fn find_rs_files_in_dir(dir: &Path) -> impl Iterator<Item = PathBuf> {
+    walk_rs_files_in_dir(dir, |path, cause| {
+        eprintln!("WARNING: skipped {}: {}", path.display(), cause)
+    })
+    .into_iter()
+}
+
+/// Walks `dir` for `.rs` files, passing the path and cause of every entry
+/// that fails to `on_failure` instead of aborting the walk.
+fn walk_rs_files_in_dir<F>(dir: &Path, mut on_failure: F) -> Vec<PathBuf>
+where
+    F: FnMut(PathBuf, String),
+{
+    let mut rs_files = Vec::new();
+    for entry in WalkDir::new(dir) {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                let path = e.path().unwrap_or(dir).to_path_buf();
+                on_failure(path, format!("failed to read the entry, {}", e));
+                continue;
+            }
+        };
         if !is_file_with_ext(&entry, "rs") {
-            return None;
-        }
-        Some(
-            entry
-                .path()
-                .canonicalize()
-                .expect("Error converting to canonical path"),
-        ) // TODO: Return result.
+            continue;
+        }
+        match canonicalize(entry.path()) {
+            Ok(path) => rs_files.push(path),
+            Err(e) => on_failure(
+                entry.into_path(),
+                format!("failed to canonicalize the path, {}", e),
+            ),
+        }
+    }
+    rs_files
+}
+
+/// Generated files either live in the `OUT_DIR` of a build script, are
+/// marked `@generated` or match one of the globs from `--generated-globs`.
+fn is_generated_file(
+    path: &Path,
+    metrics: &RsFileMetrics,
+    generated_globs: &[Pattern],
+) -> bool {
+    metrics.generated_marker
+        || is_in_out_dir(path)
+        || generated_globs.iter().any(|glob| glob.matches_path(path))
+}
+
+/// Will return true for paths below `<profile>/build/<name>-<hash>/out`.
+fn is_in_out_dir(path: &Path) -> bool {
+    path.ancestors().any(|ancestor| {
+        ancestor.file_name() == Some(OsStr::new("out"))
+            && ancestor
+                .parent()
+                .and_then(Path::parent)
+                .and_then(Path::file_name)
+                == Some(OsStr::new("build"))
     })
 }
 
-fn find_rs_files_in_package(pack: &Package) -> Vec<RsFile> {
+/// The target directory holding the `OUT_DIR`s of previous builds, either
+/// configured or the `target` directory next to the root manifest.
+fn find_target_dir(config: &Config) -> Option<PathBuf> {
+    match config.target_dir() {
+        Ok(Some(target_dir)) => Some(target_dir.into_path_unlocked()),
+        _ => important_paths::find_root_manifest_for_wd(config.cwd())
+            .ok()
+            .and_then(|manifest| {
+                manifest.parent().map(|root| root.join("target"))
+            }),
+    }
+}
+
+/// Hands out a single shared allocation per path, the scan refers to each
+/// file from several of its sets.
+#[derive(Default)]
+struct PathInterner {
+    paths: HashSet<Rc<Path>>,
+}
+
+impl PathInterner {
+    fn intern(&mut self, path: &Path) -> Rc<Path> {
+        if let Some(interned) = self.paths.get(path) {
+            return interned.clone();
+        }
+        let interned = Rc::<Path>::from(path);
+        self.paths.insert(interned.clone());
+        interned
+    }
+}
+
+/// Looks up the `OUT_DIR`s left by previous builds of the build scripts of
+/// packages, `<target>/[<triple>/]<profile>/build/<name>-<hash>/out`.
+struct OutDirResolver {
+    target_dir: Option<PathBuf>,
+    package_name_to_out_dirs: HashMap<String, Vec<PathBuf>>,
+}
+
+impl OutDirResolver {
+    fn new(target_dir: Option<PathBuf>) -> Self {
+        OutDirResolver {
+            target_dir,
+            package_name_to_out_dirs: HashMap::new(),
+        }
+    }
+
+    fn out_dirs(&mut self, package_name: &str) -> &[PathBuf] {
+        let target_dir = &self.target_dir;
+        self.package_name_to_out_dirs
+            .entry(package_name.to_string())
+            .or_insert_with(|| match target_dir {
+                Some(target_dir) => find_out_dirs(target_dir, package_name),
+                None => vec![],
+            })
+    }
+}
+
+fn find_out_dirs(target_dir: &Path, package_name: &str) -> Vec<PathBuf> {
+    let build_dir_prefix = format!("{}-", package_name);
+    WalkDir::new(target_dir)
+        .min_depth(4)
+        .max_depth(5)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            entry.file_type().is_dir() && entry.file_name() == "out"
+        })
+        .map(|entry| entry.into_path())
+        .filter(|out_dir| {
+            let build_script_dir = out_dir.parent();
+            let is_package_build_script_dir = build_script_dir
+                .and_then(Path::file_name)
+                .and_then(|file_name| file_name.to_str())
+                .and_then(|file_name| file_name.strip_prefix(&build_dir_prefix))
+                .is_some_and(|hash| {
+                    !hash.is_empty()
+                        && hash.chars().all(|c| c.is_ascii_hexdigit())
+                });
+            let is_in_build_dir = build_script_dir
+                .and_then(Path::parent)
+                .and_then(Path::file_name)
+                .is_some_and(|file_name| file_name == "build");
+            is_package_build_script_dir && is_in_build_dir
+        })
+        .collect()
+}
+
+/// Resolves a file pulled in with `#[path]` or `include!` to a canonical
+/// path, if it exists. `is_mod_rs` is set for crate roots and other files
+/// resolving the `#[path]` modules like `mod.rs` files, see
+/// `IncludedFile::ModulePath`.
+fn resolve_included_file(
+    included_file: &IncludedFile,
+    including_path: &Path,
+    is_mod_rs: bool,
+    package: &Package,
+    out_dir_resolver: &mut OutDirResolver,
+) -> Option<PathBuf> {
+    let candidates = match included_file {
+        IncludedFile::Relative(path) => {
+            vec![including_path.parent()?.join(path)]
+        }
+        IncludedFile::ModulePath {
+            inline_modules,
+            path,
+        } => {
+            vec![module_dir(including_path, is_mod_rs, inline_modules)?
+                .join(path)]
+        }
+        IncludedFile::EnvRelative { var, suffix } => match var.as_str() {
+            "CARGO_MANIFEST_DIR" => {
+                vec![PathBuf::from(format!(
+                    "{}{}",
+                    package.root().display(),
+                    suffix
+                ))]
+            }
+            "OUT_DIR" => out_dir_resolver
+                .out_dirs(&package.name())
+                .iter()
+                .map(|out_dir| {
+                    PathBuf::from(format!("{}{}", out_dir.display(), suffix))
+                })
+                .collect(),
+            _ => vec![],
+        },
+    };
+    candidates
+        .into_iter()
+        .find_map(|candidate| canonicalize(&candidate).ok())
+        .filter(|path| path.is_file())
+}
+
+/// The directory the `#[path]` modules declared inside `inline_modules` are
+/// relative to, see `IncludedFile::ModulePath`.
+fn module_dir(
+    including_path: &Path,
+    is_mod_rs: bool,
+    inline_modules: &Path,
+) -> Option<PathBuf> {
+    let mut module_dir = including_path.parent()?.to_path_buf();
+    let is_mod_rs =
+        is_mod_rs || including_path.file_name() == Some(OsStr::new("mod.rs"));
+    if !inline_modules.as_os_str().is_empty() && !is_mod_rs {
+        module_dir.push(including_path.file_stem()?);
+    }
+    Some(module_dir.join(inline_modules))
+}
+
+fn find_rs_files_in_package(
+    pack: &Package,
+    warnings: &mut Vec<ScanWarning>,
+) -> Vec<RsFile> {
+    let package = format!("{} {}", pack.name(), pack.version());
     // Find all build target entry point source files.
     let mut canon_targets = HashMap::new();
     for t in pack.targets() {
@@ -119,14 +687,32 @@ fn find_rs_files_in_package(pack: &Package) -> Vec<RsFile> {
             // everything. We have to skip this build target.
             continue;
         }
-        let canon = path
-            .canonicalize() // will Err on non-existing paths.
-            .expect("canonicalize for build target path failed."); // FIXME
+        let canon = match canonicalize(path) {
+            Ok(canon) => canon,
+            Err(e) => {
+                warnings.push(ScanWarning {
+                    package: package.clone(),
+                    path: path.to_path_buf(),
+                    cause: format!(
+                        "failed to canonicalize the build target path, {}",
+                        e
+                    ),
+                });
+                continue;
+            }
+        };
         let targets = canon_targets.entry(canon).or_insert_with(Vec::new);
         targets.push(t);
     }
     let mut out = Vec::new();
-    for p in find_rs_files_in_dir(pack.root()) {
+    let rs_files = walk_rs_files_in_dir(pack.root(), |path, cause| {
+        warnings.push(ScanWarning {
+            package: package.clone(),
+            path,
+            cause,
+        })
+    });
+    for p in rs_files {
         if !canon_targets.contains_key(&p) {
             out.push(RsFile::Other(p));
         }
@@ -139,12 +725,272 @@ fn find_rs_files_in_package(pack: &Package) -> Vec<RsFile> {
     out
 }
 
-fn find_rs_files_in_packages<'a>(
-    packs: &'a [&Package],
-) -> impl Iterator<Item = (PackageId, RsFile)> + 'a {
-    packs.iter().flat_map(|pack| {
-        find_rs_files_in_package(pack)
-            .into_iter()
-            .map(move |path| (pack.package_id(), path))
-    })
+fn find_rs_files_in_packages(
+    packs: &[&Package],
+    warnings: &mut Vec<ScanWarning>,
+) -> Vec<(PackageId, RsFile)> {
+    packs
+        .iter()
+        .flat_map(|pack| {
+            find_rs_files_in_package(pack, warnings)
+                .into_iter()
+                .map(move |path| (pack.package_id(), path))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod find_tests {
+    use super::*;
+
+    use crate::scan::unsafe_stats;
+
+    use geiger::{Location, UnsafeItem, UnsafeKind};
+    use rstest::*;
+    use std::fs;
+
+    #[rstest]
+    fn find_out_dirs_test() {
+        let target_dir = tempfile::tempdir().unwrap();
+        for out_dir in &[
+            "debug/build/foo-0123456789abcdef/out",
+            "x86_64-unknown-linux-gnu/release/build/foo-fedcba9876543210/out",
+            "debug/build/foobar-0123456789abcdef/out",
+            "debug/build/bar-0123456789abcdef/out",
+            "debug/build/foo-bar-0123456789abcdef/out",
+            "debug/deps/foo-0123456789abcdef/out",
+        ] {
+            fs::create_dir_all(target_dir.path().join(out_dir)).unwrap();
+        }
+
+        let mut out_dirs = find_out_dirs(target_dir.path(), "foo");
+        out_dirs.sort();
+
+        assert_eq!(
+            out_dirs,
+            vec![
+                target_dir.path().join("debug/build/foo-0123456789abcdef/out"),
+                target_dir.path().join(
+                    "x86_64-unknown-linux-gnu/release/build/foo-fedcba9876543210/out"
+                ),
+            ]
+        );
+    }
+
+    #[rstest(
+        input_including_path,
+        input_is_mod_rs,
+        input_inline_modules,
+        expected_module_dir,
+        case("/p/src/lib.rs", true, "", "/p/src"),
+        case("/p/src/lib.rs", true, "a/b", "/p/src/a/b"),
+        case("/p/src/foo/mod.rs", false, "a", "/p/src/foo/a"),
+        case("/p/src/foo.rs", false, "", "/p/src"),
+        case("/p/src/foo.rs", false, "a", "/p/src/foo/a")
+    )]
+    fn module_dir_test(
+        input_including_path: &str,
+        input_is_mod_rs: bool,
+        input_inline_modules: &str,
+        expected_module_dir: &str,
+    ) {
+        assert_eq!(
+            module_dir(
+                Path::new(input_including_path),
+                input_is_mod_rs,
+                Path::new(input_inline_modules)
+            ),
+            Some(PathBuf::from(expected_module_dir))
+        );
+    }
+
+    #[rstest]
+    fn walk_rs_files_in_dir_test() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/lib.rs"), "").unwrap();
+        fs::write(dir.path().join("README.md"), "").unwrap();
+
+        let mut failures = Vec::new();
+        let rs_files = walk_rs_files_in_dir(dir.path(), |path, cause| {
+            failures.push((path, cause))
+        });
+
+        assert_eq!(
+            rs_files,
+            vec![canonicalize(&dir.path().join("src/lib.rs")).unwrap()]
+        );
+        assert!(failures.is_empty());
+    }
+
+    #[rstest]
+    fn walk_rs_files_in_dir_continues_on_failure_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing_dir = dir.path().join("missing");
+
+        let mut failures = Vec::new();
+        let rs_files = walk_rs_files_in_dir(&missing_dir, |path, cause| {
+            failures.push((path, cause))
+        });
+
+        assert!(rs_files.is_empty());
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0, missing_dir);
+        assert!(failures[0].1.starts_with("failed to read the entry"));
+    }
+
+    #[rstest(
+        input_path,
+        input_generated_marker,
+        expected_is_generated,
+        case("/target/debug/build/foo-0123/out/bindings.rs", false, true),
+        case("/target/debug/build/foo-0123/out/nested/mod.rs", false, true),
+        case("/foo/src/generated/bindings.rs", false, true),
+        case("/foo/src/lib.rs", true, true),
+        case("/foo/src/lib.rs", false, false),
+        case("/foo/out/lib.rs", false, false)
+    )]
+    fn is_generated_file_test(
+        input_path: &str,
+        input_generated_marker: bool,
+        expected_is_generated: bool,
+    ) {
+        let metrics = RsFileMetrics {
+            generated_marker: input_generated_marker,
+            ..Default::default()
+        };
+        let generated_globs = vec![Pattern::new("**/generated/*.rs").unwrap()];
+
+        assert_eq!(
+            is_generated_file(
+                Path::new(input_path),
+                &metrics,
+                &generated_globs
+            ),
+            expected_is_generated
+        );
+    }
+
+    #[rstest]
+    fn drop_locations_test() {
+        let location = Location { line: 3, column: 4 };
+        let mut package_metrics = PackageMetrics::default();
+        package_metrics.rs_path_to_metrics.insert(
+            PathBuf::from("src/lib.rs"),
+            RsFileMetricsWrapper {
+                metrics: RsFileMetrics {
+                    allow_unsafe_code_locations: vec![location, location],
+                    asm_locations: vec![location],
+                    undocumented_unsafe_blocks: vec![location],
+                    unsafe_items: vec![UnsafeItem {
+                        location,
+                        kind: UnsafeKind::Expression,
+                    }],
+                    unsafe_locations: vec![location, location],
+                    generated_marker: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+
+        drop_locations(&mut package_metrics);
+
+        let metrics = &package_metrics.rs_path_to_metrics
+            [Path::new("src/lib.rs")]
+        .metrics;
+        assert_eq!(metrics.allow_unsafe_code_locations, vec![location]);
+        assert!(metrics.asm_locations.is_empty());
+        assert!(metrics.undocumented_unsafe_blocks.is_empty());
+        assert!(metrics.unsafe_items.is_empty());
+        assert!(metrics.unsafe_locations.is_empty());
+        assert!(metrics.generated_marker);
+    }
+
+    #[rstest]
+    fn fold_files_test() {
+        let mut package_metrics = PackageMetrics::default();
+        for (path, is_crate_entry_point, safe_exprs, unsafe_exprs) in &[
+            ("src/lib.rs", true, 1, 0),
+            ("src/unsafe.rs", false, 2, 3),
+            ("src/used.rs", false, 4, 0),
+            ("src/other_used.rs", false, 5, 0),
+            ("src/unused.rs", false, 6, 0),
+        ] {
+            let mut wrapper = RsFileMetricsWrapper {
+                is_crate_entry_point: *is_crate_entry_point,
+                ..Default::default()
+            };
+            wrapper.metrics.counters.exprs.safe = *safe_exprs;
+            wrapper.metrics.counters.exprs.unsafe_ = *unsafe_exprs;
+            wrapper
+                .metrics
+                .platform_counters
+                .entry(String::from("unix"))
+                .or_default()
+                .exprs
+                .safe = *safe_exprs;
+            package_metrics
+                .rs_path_to_metrics
+                .insert(PathBuf::from(path), wrapper);
+        }
+        let rs_files_used = [
+            "src/lib.rs",
+            "src/unsafe.rs",
+            "src/used.rs",
+            "src/other_used.rs",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect::<HashSet<PathBuf>>();
+        let unsafe_info = unsafe_stats(&package_metrics, &rs_files_used);
+
+        fold_files(&mut package_metrics, &rs_files_used);
+
+        let mut kept_paths = package_metrics
+            .rs_path_to_metrics
+            .keys()
+            .cloned()
+            .collect::<Vec<PathBuf>>();
+        kept_paths.sort();
+        assert_eq!(
+            kept_paths,
+            vec![PathBuf::from("src/lib.rs"), PathBuf::from("src/unsafe.rs")]
+        );
+        assert_eq!(package_metrics.folded.used.counters.exprs.safe, 9);
+        assert_eq!(package_metrics.folded.unused.counters.exprs.safe, 6);
+        assert_eq!(unsafe_stats(&package_metrics, &rs_files_used), unsafe_info);
+    }
+
+    #[rstest]
+    fn path_interner_test() {
+        let mut paths = PathInterner::default();
+
+        let interned = paths.intern(Path::new("src/lib.rs"));
+
+        assert!(Rc::ptr_eq(
+            &interned,
+            &paths.intern(Path::new("src/lib.rs"))
+        ));
+        assert!(!Rc::ptr_eq(&interned, &paths.intern(Path::new("src/a.rs"))));
+    }
+
+    #[rstest]
+    fn find_unsafe_in_file_with_timeout_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lib.rs");
+        fs::write(&path, "fn f() { unsafe {} }").unwrap();
+
+        let file_metrics = find_unsafe_in_file_with_timeout(
+            &path,
+            IncludeTests::No,
+            &[],
+            Duration::from_secs(60),
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(file_metrics.counters.functions.safe, 1);
+        assert_eq!(file_metrics.unsafe_locations.len(), 1);
+    }
 }
@@ -1,36 +1,41 @@
 mod table;
 
-use crate::format::print_config::{OutputFormat, PrintConfig};
+use crate::format::html::render_quick_html_report;
+use crate::format::print_config::OutputFormat;
 use crate::graph::Graph;
 
-use super::find::find_unsafe;
-use super::{package_metrics, ScanMode, ScanParameters};
+use super::{
+    package_forbids_unsafe, package_metrics, GeigerContext, ScanParameters,
+};
 
 use table::scan_forbid_to_table;
 
 use cargo::core::{PackageId, PackageSet};
-use cargo::{CliResult, Config};
+use cargo::{CliError, CliResult};
 use cargo_geiger_serde::{QuickReportEntry, QuickSafetyReport};
+use std::collections::BTreeMap;
 
+/// Only the entry points of the metrics are read, they may come from a scan
+/// of every file for the checks made after this one.
 pub fn scan_forbid_unsafe(
+    geiger_context: &GeigerContext,
     package_set: &PackageSet,
-    root_package_id: PackageId,
+    root_package_ids: &[PackageId],
     graph: &Graph,
     scan_parameters: &ScanParameters,
 ) -> CliResult {
     match scan_parameters.args.output_format {
         Some(output_format) => scan_forbid_to_report(
-            scan_parameters.config,
-            package_set,
-            root_package_id,
+            geiger_context,
+            root_package_ids,
             graph,
-            scan_parameters.print_config,
             output_format,
+            scan_parameters.args.include_files,
         ),
         None => scan_forbid_to_table(
-            scan_parameters.config,
+            geiger_context,
             package_set,
-            root_package_id,
+            root_package_ids,
             graph,
             scan_parameters.print_config,
         ),
@@ -38,18 +43,15 @@ pub fn scan_forbid_unsafe(
 }
 
 fn scan_forbid_to_report(
-    config: &Config,
-    packages: &PackageSet,
-    root_package_id: PackageId,
+    geiger_context: &GeigerContext,
+    root_package_ids: &[PackageId],
     graph: &Graph,
-    print_config: &PrintConfig,
     output_format: OutputFormat,
+    include_files: bool,
 ) -> CliResult {
-    let geiger_context =
-        find_unsafe(ScanMode::EntryPointsOnly, config, packages, print_config)?;
     let mut report = QuickSafetyReport::default();
     for (package, package_metrics) in
-        package_metrics(&geiger_context, graph, root_package_id)
+        package_metrics(geiger_context, graph, root_package_ids)
     {
         let pack_metrics = match package_metrics {
             Some(m) => m,
@@ -58,19 +60,61 @@ fn scan_forbid_to_report(
                 continue;
             }
         };
-        let forbids_unsafe = pack_metrics.rs_path_to_metrics.iter().all(
-            |(_, rs_file_metrics_wrapper)| {
-                rs_file_metrics_wrapper.metrics.forbids_unsafe
-            },
-        );
+        let forbids_unsafe = package_forbids_unsafe(pack_metrics);
+        let entry_points = if include_files {
+            pack_metrics
+                .rs_path_to_metrics
+                .iter()
+                .filter(|(_, rs_file_metrics_wrapper)| {
+                    rs_file_metrics_wrapper.is_crate_entry_point
+                })
+                .map(|(path_buf, rs_file_metrics_wrapper)| {
+                    (
+                        path_buf.clone(),
+                        rs_file_metrics_wrapper.metrics.forbids_unsafe,
+                    )
+                })
+                .collect()
+        } else {
+            BTreeMap::new()
+        };
         let entry = QuickReportEntry {
             package,
             forbids_unsafe,
+            entry_points,
         };
         report.packages.insert(entry.package.id.clone(), entry);
     }
     let s = match output_format {
+        OutputFormat::Html => render_quick_html_report(&report),
         OutputFormat::Json => serde_json::to_string(&report).unwrap(),
+        OutputFormat::Markdown => {
+            return Err(CliError::new(
+                anyhow::anyhow!(
+                    "--markdown needs unsafe counts, which --forbid-only does \
+                     not collect"
+                ),
+                1,
+            ));
+        }
+        OutputFormat::SecurityInsights => {
+            return Err(CliError::new(
+                anyhow::anyhow!(
+                    "--security-insights needs unsafe counts, which \
+                     --forbid-only does not collect"
+                ),
+                1,
+            ));
+        }
+        OutputFormat::Treemap => {
+            return Err(CliError::new(
+                anyhow::anyhow!(
+                    "--treemap needs unsafe counts, which --forbid-only does \
+                     not collect"
+                ),
+                1,
+            ));
+        }
     };
     println!("{}", s);
     Ok(())
@@ -1,33 +1,45 @@
 use crate::format::emoji_symbols::EmojiSymbols;
+use crate::format::i18n::{tr, Lang, Message};
 use crate::format::pattern::Pattern;
 use crate::format::print_config::PrintConfig;
-use crate::format::{get_kind_group_name, SymbolKind};
+use crate::format::{
+    get_kind_group_name, package_name_markers, pad_to_width, SymbolKind,
+};
 use crate::graph::Graph;
+use crate::rs_file::canonicalize;
 use crate::tree::traversal::walk_dependency_tree;
 use crate::tree::TextTreeLine;
 
-use super::super::find::find_unsafe;
-use super::super::ScanMode;
+use super::super::{package_forbids_unsafe, GeigerContext, PackageMetrics};
 
+use cargo::core::shell::Verbosity;
 use cargo::core::{Package, PackageId, PackageSet};
-use cargo::{CliResult, Config};
+use cargo::CliResult;
 use colored::Colorize;
+use std::path::Path;
 
 pub fn scan_forbid_to_table(
-    config: &Config,
+    geiger_context: &GeigerContext,
     package_set: &PackageSet,
-    root_package_id: PackageId,
+    root_package_ids: &[PackageId],
     graph: &Graph,
     print_config: &PrintConfig,
 ) -> CliResult {
     let mut scan_output_lines = Vec::<String>::new();
-    let emoji_symbols = EmojiSymbols::new(print_config.charset);
+    let emoji_symbols =
+        EmojiSymbols::new(print_config.charset, &print_config.symbols);
 
-    let mut output_key_lines = construct_key_lines(&emoji_symbols);
+    let mut output_key_lines =
+        construct_key_lines(&emoji_symbols, print_config.lang);
     scan_output_lines.append(&mut output_key_lines);
 
-    let tree_lines =
-        walk_dependency_tree(root_package_id, &graph, &print_config);
+    let tree_lines = root_package_ids.iter().flat_map(|root_package_id| {
+        walk_dependency_tree(
+            *root_package_id,
+            graph,
+            &print_config.tree_options(),
+        )
+    });
     for tree_line in tree_lines {
         match tree_line {
             TextTreeLine::ExtraDepsGroup { kind, tree_vines } => {
@@ -41,12 +53,22 @@ pub fn scan_forbid_to_table(
             }
             TextTreeLine::Package {
                 id: package_id,
+                patched,
+                local_override,
+                yanked,
+                via_feature,
                 tree_vines,
             } => {
                 handle_package_text_tree_line(
-                    config,
-                    &emoji_symbols,
+                    geiger_context,
                     package_id,
+                    package_name_markers(
+                        print_config.lang,
+                        patched,
+                        local_override,
+                        yanked,
+                        via_feature.as_deref(),
+                    ),
                     package_set,
                     print_config,
                     &mut scan_output_lines,
@@ -63,24 +85,29 @@ pub fn scan_forbid_to_table(
     Ok(())
 }
 
-fn construct_key_lines(emoji_symbols: &EmojiSymbols) -> Vec<String> {
+fn construct_key_lines(
+    emoji_symbols: &EmojiSymbols,
+    lang: Lang,
+) -> Vec<String> {
     let mut output_key_lines = Vec::<String>::new();
 
     output_key_lines.push(String::new());
-    output_key_lines.push(String::from("Symbols: "));
+    output_key_lines.push(String::from(tr(lang, Message::Symbols)));
 
-    let forbids = "All entry point .rs files declare #![forbid(unsafe_code)].";
-    let unknown = "This crate may use unsafe code.";
+    let forbids = tr(lang, Message::EntryPointsForbidUnsafe);
+    let unknown = tr(lang, Message::MayUseUnsafe);
+    let missing = tr(lang, Message::MetricsMissing);
 
     let symbol_kinds_to_string_values = vec![
         (SymbolKind::Lock, forbids),
         (SymbolKind::QuestionMark, unknown),
+        (SymbolKind::Missing, missing),
     ];
 
     for (symbol_kind, string_values) in symbol_kinds_to_string_values {
         output_key_lines.push(format!(
-            "    {: <2} = {}",
-            emoji_symbols.emoji(symbol_kind),
+            "    {} = {}",
+            pad_to_width(&emoji_symbols.emoji(symbol_kind).to_string(), 2),
             string_values
         ));
     }
@@ -97,60 +124,164 @@ fn format_package_name(package: &Package, pattern: &Pattern) -> String {
 }
 
 fn handle_package_text_tree_line(
-    config: &Config,
-    emoji_symbols: &EmojiSymbols,
+    geiger_context: &GeigerContext,
     package_id: PackageId,
+    package_name_markers: String,
     package_set: &PackageSet,
     print_config: &PrintConfig,
     scan_output_lines: &mut Vec<String>,
     tree_vines: String,
 ) -> CliResult {
-    let geiger_ctx = find_unsafe(
-        ScanMode::EntryPointsOnly,
-        config,
-        package_set,
-        print_config,
-    )?;
+    let emoji_symbols =
+        EmojiSymbols::new(print_config.charset, &print_config.symbols);
     let sym_lock = emoji_symbols.emoji(SymbolKind::Lock);
     let sym_qmark = emoji_symbols.emoji(SymbolKind::QuestionMark);
+    let sym_missing = emoji_symbols.emoji(SymbolKind::Missing);
 
     let package = package_set.get_one(package_id).unwrap(); // FIXME
-    let name = format_package_name(package, &print_config.format);
-    let package_metrics = geiger_ctx.package_id_to_metrics.get(&package_id);
-    let package_forbids_unsafe = match package_metrics {
-        None => false, // no metrics available, .rs parsing failed?
-        Some(package_metric) => package_metric.rs_path_to_metrics.iter().all(
-            |(_k, rs_file_metrics_wrapper)| {
-                rs_file_metrics_wrapper.metrics.forbids_unsafe
-            },
-        ),
-    };
-    let (symbol, name) = if package_forbids_unsafe {
-        (&sym_lock, name.green())
-    } else {
-        (&sym_qmark, name.red())
+    let mut name = format_package_name(package, &print_config.format);
+    name.push_str(&package_name_markers);
+    let package_metrics = geiger_context.package_id_to_metrics.get(&package_id);
+    let (symbol, name) = match package_metrics {
+        // No metrics available, .rs parsing failed?
+        None => (&sym_missing, name.yellow()),
+        Some(package_metric) => {
+            if package_forbids_unsafe(package_metric) {
+                (&sym_lock, name.green())
+            } else {
+                (&sym_qmark, name.red())
+            }
+        }
     };
     scan_output_lines.push(format!("{} {}{}", symbol, tree_vines, name));
+    if print_config.verbosity == Verbosity::Verbose {
+        if let Some(package_metrics) = package_metrics {
+            let mut entry_point_lines = construct_entry_point_lines(
+                &emoji_symbols,
+                print_config.lang,
+                package.root(),
+                package_metrics,
+                &tree_vines,
+            );
+            scan_output_lines.append(&mut entry_point_lines);
+        }
+    }
 
     Ok(())
 }
 
+/// The entry points of a package, relative to the package root, each with
+/// the symbol of whether it declares `#![forbid(unsafe_code)]`, indented
+/// below the line of the package.
+fn construct_entry_point_lines(
+    emoji_symbols: &EmojiSymbols,
+    lang: Lang,
+    package_root: &Path,
+    package_metrics: &PackageMetrics,
+    tree_vines: &str,
+) -> Vec<String> {
+    let package_root =
+        canonicalize(package_root).unwrap_or_else(|_| package_root.into());
+    let mut entry_points = package_metrics
+        .rs_path_to_metrics
+        .iter()
+        .filter(|(_, rs_file_metrics_wrapper)| {
+            rs_file_metrics_wrapper.is_crate_entry_point
+        })
+        .map(|(path_buf, rs_file_metrics_wrapper)| {
+            (
+                path_buf.strip_prefix(&package_root).unwrap_or(path_buf),
+                rs_file_metrics_wrapper.metrics.forbids_unsafe,
+            )
+        })
+        .collect::<Vec<(&Path, bool)>>();
+    entry_points.sort();
+
+    let indent = " ".repeat(tree_vines.chars().count());
+    entry_points
+        .into_iter()
+        .map(|(path, forbids_unsafe)| {
+            if forbids_unsafe {
+                format!(
+                    "   {}    {} {}",
+                    indent,
+                    emoji_symbols.emoji(SymbolKind::Lock),
+                    path.display()
+                )
+            } else {
+                format!(
+                    "   {}    {} {} {}",
+                    indent,
+                    emoji_symbols.emoji(SymbolKind::QuestionMark),
+                    path.display(),
+                    tr(lang, Message::MissingForbid)
+                )
+            }
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod forbid_tests {
     use super::*;
 
+    use crate::format::emoji_symbols::SymbolOverrides;
     use crate::format::Charset;
+    use crate::rs_file::RsFileMetricsWrapper;
 
     use cargo::core::Workspace;
     use cargo::util::important_paths;
+    use cargo::Config;
     use rstest::*;
 
     #[rstest]
     fn construct_scan_mode_forbid_only_output_key_lines_test() {
-        let emoji_symbols = EmojiSymbols::new(Charset::Utf8);
-        let output_key_lines = construct_key_lines(&emoji_symbols);
+        let emoji_symbols =
+            EmojiSymbols::new(Charset::Utf8, &SymbolOverrides::default());
+        let output_key_lines = construct_key_lines(&emoji_symbols, Lang::En);
+
+        assert_eq!(output_key_lines.len(), 6);
+    }
+
+    #[rstest]
+    fn construct_entry_point_lines_test() {
+        colored::control::set_override(false);
+        let emoji_symbols =
+            EmojiSymbols::new(Charset::Ascii, &SymbolOverrides::default());
+        let dir = tempfile::tempdir().unwrap();
+        let package_root = canonicalize(dir.path()).unwrap();
+        let mut package_metrics = PackageMetrics::default();
+        for (path, forbids_unsafe, is_crate_entry_point) in &[
+            ("src/main.rs", false, true),
+            ("src/lib.rs", true, true),
+            ("src/module.rs", false, false),
+        ] {
+            let mut rs_file_metrics_wrapper = RsFileMetricsWrapper::default();
+            rs_file_metrics_wrapper.metrics.forbids_unsafe = *forbids_unsafe;
+            rs_file_metrics_wrapper.is_crate_entry_point =
+                *is_crate_entry_point;
+            package_metrics
+                .rs_path_to_metrics
+                .insert(package_root.join(path), rs_file_metrics_wrapper);
+        }
+
+        let entry_point_lines = construct_entry_point_lines(
+            &emoji_symbols,
+            Lang::En,
+            &package_root,
+            &package_metrics,
+            "|-- ",
+        );
 
-        assert_eq!(output_key_lines.len(), 5);
+        assert_eq!(
+            entry_point_lines,
+            vec![
+                String::from("           :) src/lib.rs"),
+                String::from(
+                    "           ? src/main.rs (lacks #![forbid(unsafe_code)])"
+                ),
+            ]
+        );
     }
 
     #[rstest]
@@ -166,7 +297,7 @@ mod forbid_tests {
 
         let package = workspace.current().unwrap();
 
-        let formatted_package_name = format_package_name(&package, &pattern);
+        let formatted_package_name = format_package_name(package, &pattern);
 
         assert_eq!(formatted_package_name, "cargo-geiger 0.10.2");
     }
@@ -0,0 +1,142 @@
+use crate::graph::Graph;
+
+use super::{unsafe_stats, GeigerContext};
+
+use cargo_geiger_serde::CounterBlock;
+use std::collections::HashSet;
+use std::env;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// The environment variable GitHub Actions sets to the file that step outputs
+/// are appended to.
+const GITHUB_OUTPUT: &str = "GITHUB_OUTPUT";
+
+/// The key results of a scan, written as GitHub Actions step outputs.
+#[derive(Debug, PartialEq)]
+pub struct GitHubOutputs {
+    /// The total number of unsafe items used by the build.
+    pub unsafe_total: u64,
+
+    /// False if the scan failed any of the checks it was asked to make.
+    pub policy_ok: bool,
+
+    /// The number of crates using unsafe code in the build.
+    pub crates_with_unsafe: u64,
+}
+
+impl GitHubOutputs {
+    pub fn new(
+        geiger_context: &GeigerContext,
+        graph: &Graph,
+        rs_files_used: &HashSet<PathBuf>,
+        policy_ok: bool,
+    ) -> Self {
+        let used_counter_blocks = graph
+            .nodes
+            .keys()
+            .filter_map(|package_id| {
+                geiger_context.package_id_to_metrics.get(package_id)
+            })
+            .map(|package_metrics| {
+                unsafe_stats(package_metrics, rs_files_used).used
+            })
+            .collect::<Vec<CounterBlock>>();
+
+        GitHubOutputs {
+            unsafe_total: used_counter_blocks.iter().map(unsafe_total).sum(),
+            policy_ok,
+            crates_with_unsafe: used_counter_blocks
+                .iter()
+                .filter(|counter_block| counter_block.has_unsafe())
+                .count() as u64,
+        }
+    }
+}
+
+/// Appends the outputs to the file named by `GITHUB_OUTPUT`, warns and does
+/// nothing when not running under GitHub Actions.
+pub fn write_github_outputs(github_outputs: &GitHubOutputs) -> io::Result<()> {
+    let path = match env::var_os(GITHUB_OUTPUT) {
+        Some(path) => path,
+        None => {
+            eprintln!(
+                "WARNING: --github-output was given but {} is not set",
+                GITHUB_OUTPUT
+            );
+            return Ok(());
+        }
+    };
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    for github_output_line in construct_github_output_lines(github_outputs) {
+        writeln!(file, "{}", github_output_line)?;
+    }
+    Ok(())
+}
+
+fn construct_github_output_lines(
+    github_outputs: &GitHubOutputs,
+) -> Vec<String> {
+    vec![
+        format!("unsafe_total={}", github_outputs.unsafe_total),
+        format!("policy_ok={}", github_outputs.policy_ok),
+        format!("crates_with_unsafe={}", github_outputs.crates_with_unsafe),
+    ]
+}
+
+pub(super) fn unsafe_total(counter_block: &CounterBlock) -> u64 {
+    counter_block.functions.unsafe_
+        + counter_block.exprs.unsafe_
+        + counter_block.item_impls.unsafe_
+        + counter_block.item_traits.unsafe_
+        + counter_block.methods.unsafe_
+}
+
+#[cfg(test)]
+mod github_output_tests {
+    use super::*;
+
+    use cargo_geiger_serde::Count;
+    use rstest::*;
+
+    #[rstest]
+    fn construct_github_output_lines_test() {
+        let github_outputs = GitHubOutputs {
+            unsafe_total: 42,
+            policy_ok: false,
+            crates_with_unsafe: 3,
+        };
+
+        assert_eq!(
+            construct_github_output_lines(&github_outputs),
+            vec![
+                String::from("unsafe_total=42"),
+                String::from("policy_ok=false"),
+                String::from("crates_with_unsafe=3"),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn unsafe_total_test() {
+        let counter_block = CounterBlock {
+            functions: Count {
+                safe: 10,
+                unsafe_: 1,
+            },
+            exprs: Count {
+                safe: 10,
+                unsafe_: 2,
+            },
+            methods: Count {
+                safe: 10,
+                unsafe_: 3,
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(unsafe_total(&counter_block), 6);
+    }
+}
@@ -0,0 +1,224 @@
+use crate::format::print_config::PrintConfig;
+use crate::format::table::{unsafe_counters_header, unsafe_counters_row};
+use crate::graph::Graph;
+
+use super::cache::MetricsCache;
+use super::find::find_rs_files_in_dir;
+use super::PackageMetrics;
+
+use cargo::core::dependency::DepKind;
+use cargo::core::registry::PackageRegistry;
+use cargo::core::{Package, PackageId, PackageSet, Resolve, Workspace};
+use cargo::ops;
+use cargo::util::CargoResult;
+use cargo::Config;
+use cargo_geiger_serde::CounterBlock;
+use colored::Colorize;
+use geiger::find_unsafe_in_file;
+use std::collections::HashSet;
+
+/// An optional dependency of a package in the graph that is not activated by
+/// the current feature selection.
+#[derive(Debug, PartialEq)]
+pub struct InactiveDependency {
+    pub dependent: PackageId,
+    pub package_id: PackageId,
+
+    /// The feature of the dependent that activates the dependency.
+    pub feature: String,
+
+    /// All unsafe usage found in the dependency, none of it is used.
+    pub counter_block: CounterBlock,
+}
+
+/// Finds the optional dependencies left out by the feature selection, in the
+/// lockfile, which is resolved with all features. Their metrics come from the
+/// metrics cache, or a quick scan of all their files that does not follow
+/// `include!` and `#[path]` outside the package.
+pub fn find_inactive_dependencies(
+    config: &Config,
+    workspace: &Workspace,
+    package_set: &PackageSet,
+    graph: &Graph,
+    print_config: &PrintConfig,
+) -> CargoResult<Vec<InactiveDependency>> {
+    let lockfile_resolve = match ops::load_pkg_lockfile(workspace)? {
+        Some(lockfile_resolve) => lockfile_resolve,
+        None => return Ok(vec![]),
+    };
+    let inactive_package_ids =
+        find_inactive_package_ids(&lockfile_resolve, package_set, graph)?;
+    if inactive_package_ids.is_empty() {
+        return Ok(vec![]);
+    }
+    let lockfile_package_set = ops::get_resolved_packages(
+        &lockfile_resolve,
+        PackageRegistry::new(config)?,
+    )?;
+    let packages = lockfile_package_set.get_many(
+        inactive_package_ids
+            .iter()
+            .map(|(_, package_id, _)| *package_id)
+            .collect::<HashSet<PackageId>>(),
+    )?;
+    let mut metrics_cache = MetricsCache::new(config, print_config);
+    let mut inactive_dependencies = Vec::new();
+    for (dependent, package_id, feature) in inactive_package_ids {
+        let package = match packages
+            .iter()
+            .find(|package| package.package_id() == package_id)
+        {
+            Some(package) => package,
+            None => continue,
+        };
+        let counter_block = match metrics_cache.get(package) {
+            Some(package_metrics) => total_counter_block(&package_metrics),
+            None => quick_scan(package, print_config),
+        };
+        inactive_dependencies.push(InactiveDependency {
+            dependent,
+            package_id,
+            feature,
+            counter_block,
+        });
+    }
+    Ok(inactive_dependencies)
+}
+
+/// Lists the inactive optional dependencies greyed out, with the feature
+/// activating them, separated from the table of the crate graph.
+pub fn construct_inactive_dependency_lines(
+    inactive_dependencies: &[InactiveDependency],
+) -> Vec<String> {
+    if inactive_dependencies.is_empty() {
+        return vec![];
+    }
+    let mut inactive_lines = Vec::<String>::new();
+
+    inactive_lines.push(String::from(
+        "Optional dependencies not activated by the selected features:",
+    ));
+    inactive_lines.push(String::new());
+    inactive_lines.push(unsafe_counters_header("Dependency"));
+
+    for inactive_dependency in inactive_dependencies {
+        inactive_lines.push(format!(
+            "{}",
+            format!(
+                "{}  {} (feature `{}` of {})",
+                unsafe_counters_row(&inactive_dependency.counter_block),
+                inactive_dependency.package_id,
+                inactive_dependency.feature,
+                inactive_dependency.dependent.name()
+            )
+            .dimmed()
+        ));
+    }
+
+    inactive_lines.push(String::new());
+    inactive_lines
+}
+
+/// The optional normal dependencies of the packages in the graph without an
+/// edge to a matching package, paired with their package in the lockfile,
+/// sorted by dependent and dependency.
+fn find_inactive_package_ids(
+    lockfile_resolve: &Resolve,
+    package_set: &PackageSet,
+    graph: &Graph,
+) -> CargoResult<Vec<(PackageId, PackageId, String)>> {
+    let mut inactive_package_ids = Vec::new();
+    for (package_id, index) in &graph.nodes {
+        let package = package_set.get_one(*package_id)?;
+        let active_package_ids = graph
+            .graph
+            .neighbors(*index)
+            .map(|dependency_index| graph.graph[dependency_index].id)
+            .collect::<Vec<PackageId>>();
+        for dependency in package.dependencies().iter().filter(|dependency| {
+            dependency.is_optional() && dependency.kind() == DepKind::Normal
+        }) {
+            let is_active = active_package_ids
+                .iter()
+                .any(|id| dependency.matches_ignoring_source(*id));
+            if is_active {
+                continue;
+            }
+            if let Some((dependency_package_id, _)) = lockfile_resolve
+                .deps(*package_id)
+                .find(|(id, _)| dependency.matches_ignoring_source(*id))
+            {
+                inactive_package_ids.push((
+                    *package_id,
+                    dependency_package_id,
+                    dependency.name_in_toml().to_string(),
+                ));
+            }
+        }
+    }
+    inactive_package_ids.sort();
+    inactive_package_ids.dedup();
+    Ok(inactive_package_ids)
+}
+
+fn quick_scan(package: &Package, print_config: &PrintConfig) -> CounterBlock {
+    let mut counter_block = CounterBlock::default();
+    for path in find_rs_files_in_dir(package.root()) {
+        match find_unsafe_in_file(
+            &path,
+            print_config.include_tests,
+            &print_config.unsafe_apis,
+        ) {
+            Ok(file_metrics) => counter_block += file_metrics.counters,
+            Err(e) => {
+                eprintln!("Failed to parse file: {}, {:?} ", path.display(), e)
+            }
+        }
+    }
+    counter_block
+}
+
+fn total_counter_block(package_metrics: &PackageMetrics) -> CounterBlock {
+    package_metrics.rs_path_to_metrics.values().fold(
+        CounterBlock::default(),
+        |total, rs_file_metrics_wrapper| {
+            total + rs_file_metrics_wrapper.metrics.counters.clone()
+        },
+    )
+}
+
+#[cfg(test)]
+mod inactive_tests {
+    use super::*;
+
+    use crate::scan::test_utils::create_package_id;
+
+    use cargo_geiger_serde::Count;
+    use rstest::*;
+
+    #[rstest]
+    fn construct_inactive_dependency_lines_test() {
+        let inactive_dependencies = vec![InactiveDependency {
+            dependent: create_package_id("foo"),
+            package_id: create_package_id("winapi"),
+            feature: String::from("windows"),
+            counter_block: CounterBlock {
+                functions: Count {
+                    safe: 1,
+                    unsafe_: 2,
+                },
+                ..Default::default()
+            },
+        }];
+
+        let inactive_lines =
+            construct_inactive_dependency_lines(&inactive_dependencies);
+
+        assert_eq!(inactive_lines.len(), 5);
+        let inactive_line = console::strip_ansi_codes(&inactive_lines[3]);
+        assert!(inactive_line.starts_with("2 "));
+        assert!(inactive_line.contains("  winapi v1.2.3"));
+        assert!(inactive_line.ends_with("(feature `windows` of foo)"));
+        assert!(construct_inactive_dependency_lines(&[]).is_empty());
+    }
+}
@@ -0,0 +1,194 @@
+use crate::format::table::{unsafe_counters_header, unsafe_counters_row};
+use crate::graph::Graph;
+
+use super::{GeigerContext, PackageMetrics};
+
+use cargo::core::PackageId;
+use cargo_geiger_serde::CounterBlock;
+use cargo_platform::{Cfg, CfgExpr};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// The `cfg` values a package is compiled with, the values of the target
+/// together with the features enabled for the package.
+pub fn package_cfgs(target_cfgs: &[Cfg], features: &[String]) -> Vec<Cfg> {
+    target_cfgs
+        .iter()
+        .cloned()
+        .chain(features.iter().map(|feature| {
+            Cfg::KeyPair(String::from("feature"), feature.clone())
+        }))
+        .collect()
+}
+
+/// Returns the unsafe usage in code that is not compiled with the given `cfg`
+/// values, keyed by the `cfg` predicate guarding it. Predicates that cannot
+/// be parsed are left out, it is unknown whether they are active.
+pub fn latent_unsafe(
+    package_metrics: &PackageMetrics,
+    active_cfgs: &[Cfg],
+) -> BTreeMap<String, CounterBlock> {
+    let mut cfg_counter_blocks = BTreeMap::<String, CounterBlock>::new();
+    let folded_cfg_counters = [
+        &package_metrics.folded.used.cfg_counters,
+        &package_metrics.folded.unused.cfg_counters,
+    ];
+    for cfg_counters in package_metrics
+        .rs_path_to_metrics
+        .values()
+        .map(|rs_file_metrics_wrapper| {
+            &rs_file_metrics_wrapper.metrics.cfg_counters
+        })
+        .chain(folded_cfg_counters)
+    {
+        for (predicate, counter_block) in cfg_counters {
+            *cfg_counter_blocks.entry(predicate.clone()).or_default() +=
+                counter_block.clone();
+        }
+    }
+    cfg_counter_blocks
+        .into_iter()
+        .filter(|(predicate, counter_block)| {
+            let is_inactive = match CfgExpr::from_str(predicate) {
+                Ok(cfg_expr) => !cfg_expr.matches(active_cfgs),
+                Err(_) => false,
+            };
+            is_inactive && counter_block.has_unsafe()
+        })
+        .collect()
+}
+
+/// Finds the latent unsafe usage of all packages in the dependency graph,
+/// sorted by package. Nothing can be told apart without the target `cfg`
+/// values, so nothing is found then.
+pub fn find_latent_unsafe(
+    geiger_context: &GeigerContext,
+    graph: &Graph,
+    target_cfgs: Option<&[Cfg]>,
+) -> BTreeMap<PackageId, BTreeMap<String, CounterBlock>> {
+    let target_cfgs = match target_cfgs {
+        Some(target_cfgs) => target_cfgs,
+        None => return BTreeMap::new(),
+    };
+    graph
+        .graph
+        .node_indices()
+        .filter_map(|index| {
+            let node = &graph.graph[index];
+            let package_metrics =
+                geiger_context.package_id_to_metrics.get(&node.id)?;
+            let latent = latent_unsafe(
+                package_metrics,
+                &package_cfgs(target_cfgs, &node.features),
+            );
+            if latent.is_empty() {
+                None
+            } else {
+                Some((node.id, latent))
+            }
+        })
+        .collect()
+}
+
+/// Lists the latent unsafe usage, one row per package and `cfg` predicate.
+pub fn construct_latent_unsafe_lines(
+    latent_unsafe: &BTreeMap<PackageId, BTreeMap<String, CounterBlock>>,
+) -> Vec<String> {
+    let mut latent_unsafe_lines = Vec::<String>::new();
+
+    latent_unsafe_lines.push(String::from(
+        "Latent unsafe usage, not compiled for the current target and \
+         features:",
+    ));
+    latent_unsafe_lines.push(String::new());
+    latent_unsafe_lines.push(unsafe_counters_header("Crate, cfg"));
+
+    for (package_id, cfg_counter_blocks) in latent_unsafe {
+        for (predicate, counter_block) in cfg_counter_blocks {
+            latent_unsafe_lines.push(format!(
+                "{}  {} {} {}",
+                unsafe_counters_row(counter_block),
+                package_id.name(),
+                package_id.version(),
+                predicate
+            ));
+        }
+    }
+
+    latent_unsafe_lines.push(String::new());
+    latent_unsafe_lines
+}
+
+#[cfg(test)]
+mod latent_tests {
+    use super::*;
+
+    use crate::rs_file::RsFileMetricsWrapper;
+
+    use cargo_geiger_serde::Count;
+    use rstest::*;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    #[rstest]
+    fn package_cfgs_test() {
+        let target_cfgs = vec![Cfg::Name(String::from("unix"))];
+
+        let cfgs = package_cfgs(&target_cfgs, &[String::from("std")]);
+
+        assert_eq!(
+            cfgs,
+            vec![
+                Cfg::Name(String::from("unix")),
+                Cfg::KeyPair(String::from("feature"), String::from("std")),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn latent_unsafe_test() {
+        let mut rs_file_metrics_wrapper = RsFileMetricsWrapper::default();
+        for (predicate, unsafe_) in &[
+            ("feature = \"nightly\"", 2),
+            ("feature = \"std\"", 3),
+            ("windows", 4),
+            ("all(unix, feature = \"simd\")", 5),
+            ("feature = \"safe\"", 0),
+            ("not a predicate", 6),
+        ] {
+            rs_file_metrics_wrapper.metrics.cfg_counters.insert(
+                String::from(*predicate),
+                CounterBlock {
+                    functions: Count {
+                        safe: 1,
+                        unsafe_: *unsafe_,
+                    },
+                    ..Default::default()
+                },
+            );
+        }
+        let mut rs_path_to_metrics = HashMap::new();
+        rs_path_to_metrics
+            .insert(PathBuf::from("src/lib.rs"), rs_file_metrics_wrapper);
+        let package_metrics = PackageMetrics {
+            rs_path_to_metrics,
+            ..Default::default()
+        };
+        let active_cfgs = package_cfgs(
+            &[Cfg::Name(String::from("unix"))],
+            &[String::from("std")],
+        );
+
+        let latent = latent_unsafe(&package_metrics, &active_cfgs);
+
+        assert_eq!(
+            latent.keys().cloned().collect::<Vec<String>>(),
+            vec![
+                String::from("all(unix, feature = \"simd\")"),
+                String::from("feature = \"nightly\""),
+                String::from("windows"),
+            ]
+        );
+        assert_eq!(latent["windows"].functions.unsafe_, 4);
+    }
+}
@@ -0,0 +1,169 @@
+use crate::format::i18n::Lang;
+use crate::format::pattern::Pattern;
+use crate::format::print_config::{MetricsFilter, Prefix, PrintConfig};
+use crate::format::Charset;
+
+use super::find::find_unsafe_with_observer;
+use super::observer::ScanObserver;
+use super::ScanMode;
+
+use cargo::core::shell::Verbosity;
+use cargo::core::{PackageId, PackageSet};
+use cargo::Config;
+use cargo_geiger_serde::ScanWarning;
+use geiger::{IncludeTests, RsFileMetrics, DEFAULT_UNSAFE_APIS};
+use petgraph::EdgeDirection;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// What `scan_packages` scans, every `.rs` file of the packages without the
+/// code only compiled for tests by default.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ScanOptions {
+    /// Whether the code only compiled for tests is counted.
+    pub include_tests: IncludeTests,
+
+    /// Only scan the crate entry points, which is enough to know whether the
+    /// packages forbid unsafe code. These scans are not cached.
+    pub entry_points_only: bool,
+
+    /// Keep the locations of the unsafe usage in the metrics of the files.
+    pub retain_locations: bool,
+
+    /// How long the files of a package may take to scan before the rest of
+    /// them are left out.
+    pub timeout_per_crate: Option<Duration>,
+
+    /// The high-risk APIs to count calls to, `geiger::DEFAULT_UNSAFE_APIS`
+    /// by default.
+    pub unsafe_apis: Vec<String>,
+
+    /// Overrides the metrics cache location.
+    pub cache_dir: Option<PathBuf>,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        ScanOptions {
+            include_tests: IncludeTests::No,
+            entry_points_only: false,
+            retain_locations: false,
+            timeout_per_crate: None,
+            unsafe_apis: DEFAULT_UNSAFE_APIS
+                .iter()
+                .map(|unsafe_api| String::from(*unsafe_api))
+                .collect(),
+            cache_dir: None,
+        }
+    }
+}
+
+/// The metrics of a `.rs` file of a package.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct FileScan {
+    /// The unsafe usage found in the file by the `geiger` crate.
+    pub metrics: RsFileMetrics,
+
+    /// Whether the file is the root of a library, binary or build script.
+    pub is_crate_entry_point: bool,
+
+    /// Whether the file is generated, in the `OUT_DIR` of a build script or
+    /// marked `@generated`.
+    pub is_generated: bool,
+}
+
+/// The metrics of the `.rs` files of a package.
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct PackageScan {
+    /// The key is the canonicalized path to the `.rs` file.
+    pub files: HashMap<PathBuf, FileScan>,
+
+    /// The scan was stopped by `ScanOptions::timeout_per_crate`, the metrics
+    /// only cover the files scanned before.
+    pub not_fully_scanned: bool,
+}
+
+/// The metrics of the scanned packages.
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct PackagesScan {
+    pub packages: HashMap<PackageId, PackageScan>,
+
+    /// The files that were skipped because they failed to be found,
+    /// canonicalized or parsed.
+    pub warnings: Vec<ScanWarning>,
+}
+
+/// Scans the `.rs` files of the packages, passing the progress to
+/// `observer`. Full scans share the metrics cache of the `cargo geiger`
+/// executable.
+pub fn scan_packages(
+    config: &Config,
+    packages: &PackageSet,
+    options: &ScanOptions,
+    observer: &mut dyn ScanObserver,
+) -> PackagesScan {
+    let mode = if options.entry_points_only {
+        ScanMode::EntryPointsOnly
+    } else {
+        ScanMode::Full
+    };
+    let print_config = PrintConfig {
+        all: false,
+        allow_partial_results: true,
+        cache_dir: options.cache_dir.clone(),
+        charset: Charset::Utf8,
+        direction: EdgeDirection::Outgoing,
+        format: Pattern::try_build("{p}").unwrap(),
+        generated_globs: vec![],
+        include_tests: options.include_tests,
+        lang: Lang::En,
+        metrics_filter: MetricsFilter::All,
+        numbers: Default::default(),
+        output_format: None,
+        prefix: Prefix::Indent,
+        retain_locations: options.retain_locations,
+        symbols: Default::default(),
+        timeout_per_crate: options.timeout_per_crate,
+        unsafe_apis: options.unsafe_apis.clone(),
+        verbosity: Verbosity::Normal,
+    };
+    let geiger_context = find_unsafe_with_observer(
+        mode,
+        config,
+        packages,
+        &print_config,
+        None,
+        observer,
+    );
+    PackagesScan {
+        packages: geiger_context
+            .package_id_to_metrics
+            .into_iter()
+            .map(|(package_id, package_metrics)| {
+                let files = package_metrics
+                    .rs_path_to_metrics
+                    .into_iter()
+                    .map(|(path, wrapper)| {
+                        let file_scan = FileScan {
+                            metrics: wrapper.metrics,
+                            is_crate_entry_point: wrapper.is_crate_entry_point,
+                            is_generated: wrapper.is_generated,
+                        };
+                        (path, file_scan)
+                    })
+                    .collect();
+                let package_scan = PackageScan {
+                    files,
+                    not_fully_scanned: package_metrics.not_fully_scanned,
+                };
+                (package_id, package_scan)
+            })
+            .collect(),
+        warnings: geiger_context.warnings,
+    }
+}
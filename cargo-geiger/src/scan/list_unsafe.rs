@@ -0,0 +1,136 @@
+use crate::graph::Graph;
+
+use super::{GeigerContext, PackageMetrics};
+
+use cargo::core::PackageIdSpec;
+use cargo::{CliError, CliResult};
+
+/// Prints every unsafe function, expression, impl, trait and method of the
+/// package matching the spec, for `--list-unsafe`. These are the items the
+/// unsafe counters of the package are made of.
+pub fn scan_list_unsafe(
+    geiger_context: &GeigerContext,
+    graph: &Graph,
+    spec: &str,
+) -> CliResult {
+    let package_id = PackageIdSpec::parse(spec)
+        .and_then(|package_id_spec| {
+            package_id_spec.query(graph.nodes.keys().cloned())
+        })
+        .map_err(|e| {
+            CliError::new(
+                e.context(format!(
+                    "could not list the unsafe code of `{}`",
+                    spec
+                )),
+                1,
+            )
+        })?;
+
+    let unsafe_item_lines = geiger_context
+        .package_id_to_metrics
+        .get(&package_id)
+        .map(construct_unsafe_item_lines)
+        .unwrap_or_default();
+    let unsafe_item_count = unsafe_item_lines.len();
+    for unsafe_item_line in unsafe_item_lines {
+        println!("{}", unsafe_item_line);
+    }
+    println!();
+    println!(
+        "Found {} unsafe item(s) in {} {}.",
+        unsafe_item_count,
+        package_id.name(),
+        package_id.version()
+    );
+    Ok(())
+}
+
+/// A line per unsafe item of the package, ordered by file and position.
+fn construct_unsafe_item_lines(
+    package_metrics: &PackageMetrics,
+) -> Vec<String> {
+    let mut unsafe_items = package_metrics
+        .rs_path_to_metrics
+        .iter()
+        .flat_map(|(path_buf, rs_file_metrics_wrapper)| {
+            rs_file_metrics_wrapper
+                .metrics
+                .unsafe_items
+                .iter()
+                .map(move |unsafe_item| (path_buf, unsafe_item))
+        })
+        .collect::<Vec<_>>();
+
+    unsafe_items.sort();
+
+    unsafe_items
+        .iter()
+        .map(|(path_buf, unsafe_item)| {
+            format!(
+                "{}:{}: unsafe {}",
+                path_buf.display(),
+                unsafe_item.location,
+                unsafe_item.kind
+            )
+        })
+        .collect::<Vec<String>>()
+}
+
+#[cfg(test)]
+mod list_unsafe_tests {
+    use super::*;
+
+    use crate::rs_file::RsFileMetricsWrapper;
+
+    use geiger::{Location, UnsafeItem, UnsafeKind};
+    use rstest::*;
+    use std::path::PathBuf;
+
+    #[rstest]
+    fn construct_unsafe_item_lines_test() {
+        let mut package_metrics = PackageMetrics::default();
+        package_metrics.rs_path_to_metrics.insert(
+            PathBuf::from("b/path.rs"),
+            create_rs_file_metrics_wrapper(vec![
+                (12, 8, UnsafeKind::Expression),
+                (12, 4, UnsafeKind::Expression),
+                (3, 0, UnsafeKind::Impl),
+            ]),
+        );
+        package_metrics.rs_path_to_metrics.insert(
+            PathBuf::from("a/path.rs"),
+            create_rs_file_metrics_wrapper(vec![
+                (5, 4, UnsafeKind::Method),
+                (1, 0, UnsafeKind::Function),
+                (9, 0, UnsafeKind::Trait),
+            ]),
+        );
+
+        assert_eq!(
+            construct_unsafe_item_lines(&package_metrics),
+            vec![
+                "a/path.rs:1:0: unsafe fn",
+                "a/path.rs:5:4: unsafe method",
+                "a/path.rs:9:0: unsafe trait",
+                "b/path.rs:3:0: unsafe impl",
+                "b/path.rs:12:4: unsafe expr",
+                "b/path.rs:12:8: unsafe expr",
+            ]
+        );
+    }
+
+    fn create_rs_file_metrics_wrapper(
+        unsafe_items: Vec<(usize, usize, UnsafeKind)>,
+    ) -> RsFileMetricsWrapper {
+        let mut rs_file_metrics_wrapper = RsFileMetricsWrapper::default();
+        rs_file_metrics_wrapper.metrics.unsafe_items = unsafe_items
+            .into_iter()
+            .map(|(line, column, kind)| UnsafeItem {
+                location: Location { line, column },
+                kind,
+            })
+            .collect();
+        rs_file_metrics_wrapper
+    }
+}
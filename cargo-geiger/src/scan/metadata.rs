@@ -0,0 +1,163 @@
+use crate::graph::Graph;
+
+use super::from_cargo_package_id;
+
+use cargo::core::{Package, PackageSet};
+use cargo::util::CargoResult;
+use cargo_geiger_serde::{PackageId, PackageMetadata, SelfReportedClaims};
+use std::collections::HashMap;
+use std::fs;
+
+/// The manifest metadata of the packages in the graph, for
+/// `--include-metadata`.
+pub fn collect_package_metadata(
+    package_set: &PackageSet,
+    graph: &Graph,
+) -> CargoResult<HashMap<PackageId, PackageMetadata>> {
+    graph
+        .graph
+        .raw_nodes()
+        .iter()
+        .map(|node| &node.weight)
+        .map(|node| {
+            let package = package_set.get_one(node.id)?;
+            Ok((from_cargo_package_id(node.id), package_metadata(package)))
+        })
+        .collect()
+}
+
+/// The claims the packages in the graph declare about themselves in
+/// `[package.metadata.geiger]`, for the packages declaring any.
+pub fn collect_self_reported_claims(
+    package_set: &PackageSet,
+    graph: &Graph,
+) -> CargoResult<HashMap<PackageId, SelfReportedClaims>> {
+    let mut claims = HashMap::new();
+    for node in graph.graph.raw_nodes().iter().map(|node| &node.weight) {
+        let package = package_set.get_one(node.id)?;
+        if let Some(package_claims) = package
+            .manifest()
+            .custom_metadata()
+            .and_then(self_reported_claims)
+        {
+            claims.insert(from_cargo_package_id(node.id), package_claims);
+        }
+    }
+    Ok(claims)
+}
+
+fn package_metadata(package: &Package) -> PackageMetadata {
+    let manifest_metadata = package.manifest().metadata();
+    PackageMetadata {
+        authors: manifest_metadata.authors.clone(),
+        description: manifest_metadata.description.clone(),
+        repository: manifest_metadata.repository.clone(),
+        rust_version: fs::read_to_string(package.manifest_path())
+            .ok()
+            .and_then(|manifest| rust_version_from_manifest(&manifest)),
+    }
+}
+
+/// The `rust-version` of the `[package]` section of a manifest. The field is
+/// not known to the version of cargo this is built with, so it is read from
+/// the manifest itself.
+pub(super) fn rust_version_from_manifest(manifest: &str) -> Option<String> {
+    let manifest = manifest.parse::<toml::Value>().ok()?;
+    manifest
+        .get("package")?
+        .get("rust-version")?
+        .as_str()
+        .map(String::from)
+}
+
+/// The claims of the `geiger` table of the `[package.metadata]` of a
+/// manifest. Fields of the wrong type are left out, like unknown fields.
+fn self_reported_claims(
+    custom_metadata: &toml::Value,
+) -> Option<SelfReportedClaims> {
+    let geiger = custom_metadata.get("geiger")?;
+    let claims = SelfReportedClaims {
+        audited: geiger.get("audited").and_then(toml::Value::as_bool),
+        safety_docs: geiger
+            .get("safety-docs")
+            .and_then(toml::Value::as_str)
+            .map(String::from),
+        categories: geiger
+            .get("categories")
+            .and_then(toml::Value::as_array)
+            .map(|categories| {
+                categories
+                    .iter()
+                    .filter_map(toml::Value::as_str)
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default(),
+    };
+    if claims == SelfReportedClaims::default() {
+        None
+    } else {
+        Some(claims)
+    }
+}
+
+#[cfg(test)]
+mod metadata_tests {
+    use super::*;
+
+    use rstest::*;
+
+    #[rstest(
+        input_manifest,
+        expected_rust_version,
+        case(
+            "[package]\nname = \"foo\"\nrust-version = \"1.40\"\n",
+            Some("1.40")
+        ),
+        case("[package]\nname = \"foo\"\n", None),
+        case("[package]\nname = \"foo\"\nrust-version = 1.40\n", None),
+        case("[workspace]\nmembers = [\"foo\"]\n", None),
+        case("not toml", None)
+    )]
+    fn rust_version_from_manifest_test(
+        input_manifest: &str,
+        expected_rust_version: Option<&str>,
+    ) {
+        assert_eq!(
+            rust_version_from_manifest(input_manifest).as_deref(),
+            expected_rust_version
+        );
+    }
+
+    #[rstest(
+        input_custom_metadata,
+        expected_claims,
+        case(
+            "[geiger]\n\
+             audited = true\n\
+             safety-docs = \"docs/unsafe.md\"\n\
+             categories = [\"ffi\", \"simd\"]\n",
+            Some(SelfReportedClaims {
+                audited: Some(true),
+                safety_docs: Some(String::from("docs/unsafe.md")),
+                categories: vec![String::from("ffi"), String::from("simd")],
+            })
+        ),
+        case(
+            "[geiger]\naudited = \"yes\"\ncategories = [\"ffi\", 1]\n",
+            Some(SelfReportedClaims {
+                categories: vec![String::from("ffi")],
+                ..Default::default()
+            })
+        ),
+        case("[geiger]\nunknown = true\n", None),
+        case("[docs.rs]\nall-features = true\n", None)
+    )]
+    fn self_reported_claims_test(
+        input_custom_metadata: &str,
+        expected_claims: Option<SelfReportedClaims>,
+    ) {
+        let custom_metadata = input_custom_metadata.parse().unwrap();
+        assert_eq!(self_reported_claims(&custom_metadata), expected_claims);
+    }
+}
@@ -0,0 +1,104 @@
+use crate::graph::Graph;
+
+use super::violation::Check;
+use super::GeigerContext;
+
+use cargo::core::PackageId;
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fmt;
+
+/// Fails if any package in the graph has no metrics, e.g. because its
+/// source files could not be parsed, listing the packages. Nothing is known
+/// about the unsafe usage of such a package.
+pub fn check_missing_metrics(
+    geiger_context: &GeigerContext,
+    graph: &Graph,
+) -> Check {
+    let packages_without_metrics =
+        find_packages_without_metrics(geiger_context, graph);
+    let violations = packages_without_metrics
+        .iter()
+        .map(|package_id| format!("No metrics for package: {}", package_id))
+        .collect::<Vec<String>>();
+
+    let package_count = violations.len() as u64;
+    Check::new(violations, MissingMetricsError { package_count })
+}
+
+#[derive(Debug)]
+struct MissingMetricsError {
+    package_count: u64,
+}
+
+impl Error for MissingMetricsError {}
+
+impl fmt::Display for MissingMetricsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} package(s) without metrics", self.package_count)
+    }
+}
+
+fn find_packages_without_metrics(
+    geiger_context: &GeigerContext,
+    graph: &Graph,
+) -> BTreeSet<PackageId> {
+    graph
+        .nodes
+        .keys()
+        .filter(|package_id| {
+            !geiger_context
+                .package_id_to_metrics
+                .contains_key(package_id)
+        })
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod missing_metrics_tests {
+    use super::*;
+
+    use crate::graph::Node;
+    use crate::scan::test_utils::create_package_id;
+    use crate::scan::PackageMetrics;
+
+    use rstest::*;
+    use std::collections::HashMap;
+
+    #[rstest]
+    fn find_packages_without_metrics_test() {
+        let scanned = create_package_id("scanned");
+        let unparsable = create_package_id("unparsable");
+
+        let mut graph = Graph {
+            graph: petgraph::Graph::new(),
+            nodes: HashMap::new(),
+            edge_features: HashMap::new(),
+        };
+        for package_id in &[scanned, unparsable] {
+            let index = graph.graph.add_node(Node {
+                id: *package_id,
+                features: vec![],
+                patched: false,
+                local_override: false,
+                yanked: false,
+            });
+            graph.nodes.insert(*package_id, index);
+        }
+        let mut package_id_to_metrics = HashMap::new();
+        package_id_to_metrics.insert(scanned, PackageMetrics::default());
+        let geiger_context = GeigerContext {
+            package_id_to_metrics,
+            used_but_not_scanned: None,
+            warnings: vec![],
+        };
+
+        assert_eq!(
+            find_packages_without_metrics(&geiger_context, &graph)
+                .into_iter()
+                .collect::<Vec<PackageId>>(),
+            vec![unparsable]
+        );
+    }
+}
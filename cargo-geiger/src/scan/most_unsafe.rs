@@ -0,0 +1,231 @@
+use crate::graph::{reachable_package_ids, Graph};
+
+use super::{unsafe_stats, GeigerContext};
+
+use cargo::core::PackageId;
+use petgraph::EdgeDirection;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+/// The unsafe expressions used by the build in the packages that are only
+/// reachable through one direct dependency of the root package.
+#[derive(Debug, PartialEq)]
+pub struct DependencyContribution {
+    pub dependency: PackageId,
+    pub unsafe_exprs: u64,
+    /// The packages only reachable through the dependency, the dependency
+    /// included, with their unsafe expressions, most unsafe first.
+    pub packages: Vec<(PackageId, u64)>,
+}
+
+/// The direct dependency whose removal would remove the most unsafe
+/// expressions from the build, if any contributes unsafe expressions.
+pub fn find_most_unsafe_dependency(
+    geiger_context: &GeigerContext,
+    graph: &Graph,
+    root_package_id: PackageId,
+    rs_files_used: &HashSet<PathBuf>,
+) -> Option<DependencyContribution> {
+    let package_unsafe_exprs = geiger_context
+        .package_id_to_metrics
+        .iter()
+        .map(|(package_id, package_metrics)| {
+            (
+                *package_id,
+                unsafe_stats(package_metrics, rs_files_used)
+                    .used
+                    .exprs
+                    .unsafe_,
+            )
+        })
+        .collect::<HashMap<PackageId, u64>>();
+    most_unsafe_dependency(graph, root_package_id, &package_unsafe_exprs)
+}
+
+fn most_unsafe_dependency(
+    graph: &Graph,
+    root_package_id: PackageId,
+    package_unsafe_exprs: &HashMap<PackageId, u64>,
+) -> Option<DependencyContribution> {
+    let reachable = reachable_package_ids(graph, root_package_id, |_| false);
+    let direct_dependencies = graph
+        .graph
+        .neighbors_directed(
+            graph.nodes[&root_package_id],
+            EdgeDirection::Outgoing,
+        )
+        .map(|index| graph.graph[index].id)
+        .filter(|package_id| *package_id != root_package_id)
+        .collect::<HashSet<PackageId>>();
+    direct_dependencies
+        .into_iter()
+        .map(|dependency| {
+            let reachable_without =
+                reachable_package_ids(graph, root_package_id, |package_id| {
+                    package_id == dependency
+                });
+            let mut packages = reachable
+                .difference(&reachable_without)
+                .map(|package_id| {
+                    (
+                        *package_id,
+                        package_unsafe_exprs
+                            .get(package_id)
+                            .copied()
+                            .unwrap_or(0),
+                    )
+                })
+                .collect::<Vec<(PackageId, u64)>>();
+            packages.sort_by(|(a_id, a_unsafe), (b_id, b_unsafe)| {
+                b_unsafe.cmp(a_unsafe).then(a_id.cmp(b_id))
+            });
+            DependencyContribution {
+                dependency,
+                unsafe_exprs: packages.iter().map(|(_, unsafe_)| unsafe_).sum(),
+                packages,
+            }
+        })
+        .filter(|contribution| contribution.unsafe_exprs > 0)
+        .max_by(|a, b| {
+            a.unsafe_exprs
+                .cmp(&b.unsafe_exprs)
+                .then(b.dependency.cmp(&a.dependency))
+        })
+}
+
+/// A summary line naming the dependency, followed by its packages with
+/// `--why-most-unsafe`.
+pub fn construct_most_unsafe_dependency_lines(
+    contribution: &DependencyContribution,
+    why: bool,
+) -> Vec<String> {
+    let mut lines = vec![format!(
+        "Most unsafe direct dependency: {} {}, {} unsafe expressions used by \
+         the build are only reachable through it.",
+        contribution.dependency.name(),
+        contribution.dependency.version(),
+        contribution.unsafe_exprs
+    )];
+    if why {
+        for (package_id, unsafe_exprs) in &contribution.packages {
+            lines.push(format!(
+                "    {:>6}  {} {}",
+                unsafe_exprs,
+                package_id.name(),
+                package_id.version()
+            ));
+        }
+    }
+    lines.push(String::new());
+    lines
+}
+
+#[cfg(test)]
+mod most_unsafe_tests {
+    use super::*;
+
+    use crate::graph::Node;
+    use crate::scan::test_utils::create_package_id;
+
+    use cargo::core::dependency::DepKind;
+    use rstest::*;
+
+    #[rstest]
+    fn most_unsafe_dependency_test() {
+        let root = create_package_id("root");
+        let a = create_package_id("a");
+        let b = create_package_id("b");
+        let a_only = create_package_id("a_only");
+        let shared = create_package_id("shared");
+
+        let mut graph = Graph {
+            graph: petgraph::Graph::new(),
+            nodes: HashMap::new(),
+            edge_features: HashMap::new(),
+        };
+        for package_id in &[root, a, b, a_only, shared] {
+            let index = graph.graph.add_node(Node {
+                id: *package_id,
+                features: vec![],
+                patched: false,
+                local_override: false,
+                yanked: false,
+            });
+            graph.nodes.insert(*package_id, index);
+        }
+        for (from, to) in
+            &[(root, a), (root, b), (a, a_only), (a, shared), (b, shared)]
+        {
+            graph.graph.add_edge(
+                graph.nodes[from],
+                graph.nodes[to],
+                DepKind::Normal,
+            );
+        }
+        let mut package_unsafe_exprs = HashMap::new();
+        package_unsafe_exprs.insert(root, 50);
+        package_unsafe_exprs.insert(a, 2);
+        package_unsafe_exprs.insert(a_only, 3);
+        package_unsafe_exprs.insert(b, 4);
+        package_unsafe_exprs.insert(shared, 100);
+
+        let contribution =
+            most_unsafe_dependency(&graph, root, &package_unsafe_exprs)
+                .unwrap();
+
+        assert_eq!(
+            contribution,
+            DependencyContribution {
+                dependency: a,
+                unsafe_exprs: 5,
+                packages: vec![(a_only, 3), (a, 2)],
+            }
+        );
+        assert_eq!(
+            construct_most_unsafe_dependency_lines(&contribution, true),
+            vec![
+                String::from(
+                    "Most unsafe direct dependency: a 1.2.3, 5 unsafe \
+                     expressions used by the build are only reachable \
+                     through it."
+                ),
+                String::from("         3  a_only 1.2.3"),
+                String::from("         2  a 1.2.3"),
+                String::new(),
+            ]
+        );
+        assert_eq!(
+            construct_most_unsafe_dependency_lines(&contribution, false).len(),
+            2
+        );
+    }
+
+    #[rstest]
+    fn most_unsafe_dependency_without_unsafe_test() {
+        let root = create_package_id("root");
+        let a = create_package_id("a");
+
+        let mut graph = Graph {
+            graph: petgraph::Graph::new(),
+            nodes: HashMap::new(),
+            edge_features: HashMap::new(),
+        };
+        for package_id in &[root, a] {
+            let index = graph.graph.add_node(Node {
+                id: *package_id,
+                features: vec![],
+                patched: false,
+                local_override: false,
+                yanked: false,
+            });
+            graph.nodes.insert(*package_id, index);
+        }
+        graph.graph.add_edge(
+            graph.nodes[&root],
+            graph.nodes[&a],
+            DepKind::Normal,
+        );
+
+        assert_eq!(most_unsafe_dependency(&graph, root, &HashMap::new()), None);
+    }
+}
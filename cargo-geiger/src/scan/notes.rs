@@ -0,0 +1,86 @@
+use cargo::util::CargoResult;
+use cargo::Config;
+use cargo_geiger_serde::PackageId;
+use std::collections::BTreeMap;
+
+/// The `cargo` configuration key of the crate notes.
+const NOTES_CONFIG_KEY: &str = "geiger.notes";
+
+/// Free-text notes on crates, configured in the `[geiger.notes]` table of
+/// the `cargo` configuration, e.g.
+///
+/// ```toml
+/// [geiger.notes]
+/// libc = "Audited 2024-03 by team X, see JIRA-123"
+/// "smallvec@1.4.2" = "Patched for RUSTSEC-2021-0003"
+/// ```
+///
+/// A note under the crate name is on all its versions, a note under
+/// `name@version` only on that version. The notes are carried into the
+/// reports next to the crate.
+#[derive(Debug, Default, PartialEq)]
+pub struct CrateNotes {
+    notes: BTreeMap<String, String>,
+}
+
+impl CrateNotes {
+    pub fn from_config(config: &Config) -> CargoResult<CrateNotes> {
+        Ok(CrateNotes {
+            notes: config
+                .get::<Option<BTreeMap<String, String>>>(NOTES_CONFIG_KEY)?
+                .unwrap_or_default(),
+        })
+    }
+
+    /// The notes on the package, the one on all versions first.
+    pub fn notes_for(&self, package_id: &PackageId) -> Vec<String> {
+        [
+            package_id.name.clone(),
+            format!("{}@{}", package_id.name, package_id.version),
+        ]
+        .iter()
+        .filter_map(|key| self.notes.get(key))
+        .cloned()
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod notes_tests {
+    use super::*;
+
+    use cargo_geiger_serde::Source;
+    use rstest::*;
+    use semver::Version;
+    use url::Url;
+
+    #[rstest(
+        input_version,
+        expected_notes,
+        case("0.2.80", vec!["Audited", "Patched"]),
+        case("0.2.81", vec!["Audited"])
+    )]
+    fn notes_for_test(input_version: &str, expected_notes: Vec<&str>) {
+        let crate_notes = CrateNotes {
+            notes: vec![
+                ("libc", "Audited"),
+                ("libc@0.2.80", "Patched"),
+                ("smallvec", "Not libc"),
+            ]
+            .into_iter()
+            .map(|(key, note)| (String::from(key), String::from(note)))
+            .collect(),
+        };
+        let package_id = PackageId {
+            name: String::from("libc"),
+            version: Version::parse(input_version).unwrap(),
+            source: Source::Registry {
+                name: String::from("crates.io"),
+                url: Url::parse("https://github.com/rust-lang/crates.io-index")
+                    .unwrap(),
+            },
+        };
+
+        assert_eq!(crate_notes.notes_for(&package_id), expected_notes);
+    }
+}
@@ -0,0 +1,117 @@
+use cargo::core::PackageId;
+use cargo::util::Progress;
+use cargo::Config;
+use std::path::PathBuf;
+use std::sync::mpsc::Sender;
+use std::time::Duration;
+
+/// Progress of a scan, the files of a package are scanned between its
+/// `PackageStarted` and `PackageFinished` events. Packages taken from the
+/// metrics cache are not scanned and have no events.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScanEvent {
+    PackageStarted {
+        package_id: PackageId,
+    },
+    FileScanned {
+        package_id: PackageId,
+        path: PathBuf,
+        /// The time spent parsing the file.
+        elapsed: Duration,
+        /// The zero based index of the file among the files to scan.
+        index: usize,
+        /// The number of files to scan, growing when files pulled in with
+        /// `#[path]` or `include!` are found.
+        file_count: usize,
+    },
+    PackageFinished {
+        package_id: PackageId,
+        /// The time spent scanning the files of the package.
+        elapsed: Duration,
+    },
+}
+
+/// Receives the events of a scan, for embedders to drive their own progress
+/// reporting or to collect timing data.
+pub trait ScanObserver {
+    fn on_event(&mut self, event: ScanEvent);
+}
+
+/// Forwards the events to a channel, a dropped receiver is ignored.
+impl ScanObserver for Sender<ScanEvent> {
+    fn on_event(&mut self, event: ScanEvent) {
+        let _ = self.send(event);
+    }
+}
+
+/// The progress bar of the command line.
+pub struct ProgressObserver<'cfg> {
+    progress: Progress<'cfg>,
+}
+
+impl<'cfg> ProgressObserver<'cfg> {
+    pub fn new(config: &'cfg Config) -> Self {
+        ProgressObserver {
+            progress: Progress::new("Scanning", config),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.progress.clear();
+    }
+}
+
+impl ScanObserver for ProgressObserver<'_> {
+    fn on_event(&mut self, event: ScanEvent) {
+        if let ScanEvent::FileScanned {
+            index, file_count, ..
+        } = event
+        {
+            let _ = self.progress.tick(index, file_count);
+        }
+    }
+}
+
+#[cfg(test)]
+mod observer_tests {
+    use super::*;
+
+    use crate::scan::test_utils::create_package_id;
+
+    use rstest::*;
+    use std::sync::mpsc::channel;
+
+    #[rstest]
+    fn sender_forwards_events_test() {
+        let package_id = create_package_id("package");
+        let (mut sender, receiver) = channel();
+
+        sender.on_event(ScanEvent::PackageStarted { package_id });
+        sender.on_event(ScanEvent::PackageFinished {
+            package_id,
+            elapsed: Duration::from_millis(5),
+        });
+        drop(sender);
+
+        assert_eq!(
+            receiver.iter().collect::<Vec<ScanEvent>>(),
+            vec![
+                ScanEvent::PackageStarted { package_id },
+                ScanEvent::PackageFinished {
+                    package_id,
+                    elapsed: Duration::from_millis(5),
+                },
+            ]
+        );
+    }
+
+    #[rstest]
+    fn sender_ignores_dropped_receiver_test() {
+        let (mut sender, receiver) = channel();
+        drop(receiver);
+
+        sender.on_event(ScanEvent::PackageStarted {
+            package_id: create_package_id("package"),
+        });
+    }
+}
@@ -0,0 +1,207 @@
+use crate::args::Args;
+
+use super::cache::cache_dir;
+
+use cargo::ops::http_handle;
+use cargo::util::CargoResult;
+use cargo::Config;
+use cargo_geiger_serde::{PackageId, ReportEntry, Source};
+use crates_io::Registry;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+const CRATES_IO_HOST: &str = "https://crates.io";
+const OWNERS_ENTRY_PREFIX: &str = "owners-";
+
+/// Owners change rarely, a cached list is used for a week before it is
+/// fetched again.
+const OWNERS_MAX_AGE: Duration = Duration::from_secs(7 * 86_400);
+
+/// The number of distinct crates.io owners of every crates.io package in the
+/// report with unsafe code, for `--include-owners`. The owners are cached in
+/// the metrics cache directory. Offline, or when crates.io cannot be
+/// reached, the cached owners are used regardless of their age and packages
+/// without cached owners are left out with a warning.
+pub fn collect_owner_counts<'a>(
+    report_entries: impl Iterator<Item = &'a ReportEntry>,
+    args: &Args,
+    config: &Config,
+) -> HashMap<PackageId, u64> {
+    let dir = cache_dir(args.cache_dir.as_deref(), config);
+    let mut registry = None;
+    let mut owner_counts = HashMap::new();
+    for report_entry in report_entries {
+        let package_id = &report_entry.package.id;
+        if !report_entry.unsafety.used.has_unsafe() || !is_crates_io(package_id)
+        {
+            continue;
+        }
+        let entry_path = owners_entry_path(&dir, &package_id.name);
+        let cached_owners = read_owners_entry(&entry_path);
+        let owners = match cached_owners {
+            Some((owners, modified))
+                if is_fresh(modified) || config.offline() =>
+            {
+                Some(owners)
+            }
+            cached_owners => {
+                match fetch_owners(&mut registry, &package_id.name, config) {
+                    Ok(owners) => {
+                        write_owners_entry(&entry_path, &owners);
+                        Some(owners)
+                    }
+                    Err(e) => {
+                        eprintln!(
+                            "WARNING: Failed to fetch the owners of {}: {}",
+                            package_id.name, e
+                        );
+                        cached_owners.map(|(owners, _)| owners)
+                    }
+                }
+            }
+        };
+        if let Some(owners) = owners {
+            owner_counts.insert(package_id.clone(), owners.len() as u64);
+        }
+    }
+    owner_counts
+}
+
+#[derive(Deserialize, Serialize)]
+struct OwnersEntry {
+    /// The logins of the users and teams owning the crate.
+    owners: BTreeSet<String>,
+}
+
+/// Creates the crates.io client on the first fetch, so that a scan without
+/// any unsafe crates.io package never touches the network.
+fn fetch_owners(
+    registry: &mut Option<Registry>,
+    crate_name: &str,
+    config: &Config,
+) -> CargoResult<BTreeSet<String>> {
+    if config.offline() {
+        anyhow::bail!("not cached, and the network is not used offline");
+    }
+    if registry.is_none() {
+        *registry = Some(Registry::new_handle(
+            String::from(CRATES_IO_HOST),
+            None,
+            http_handle(config)?,
+        ));
+    }
+    Ok(registry
+        .as_mut()
+        .expect("The crates.io client was created above")
+        .list_owners(crate_name)?
+        .into_iter()
+        .map(|user| user.login)
+        .collect())
+}
+
+pub(super) fn is_crates_io(package_id: &PackageId) -> bool {
+    match &package_id.source {
+        Source::Registry { name, .. } => name == "crates.io",
+        _ => false,
+    }
+}
+
+fn is_fresh(modified: SystemTime) -> bool {
+    SystemTime::now()
+        .duration_since(modified)
+        .map_or(true, |age| age <= OWNERS_MAX_AGE)
+}
+
+fn owners_entry_path(dir: &Path, crate_name: &str) -> PathBuf {
+    dir.join(format!("{}{}.json", OWNERS_ENTRY_PREFIX, crate_name))
+}
+
+fn read_owners_entry(
+    entry_path: &Path,
+) -> Option<(BTreeSet<String>, SystemTime)> {
+    let modified = fs::metadata(entry_path).ok()?.modified().ok()?;
+    let file = File::open(entry_path).ok()?;
+    let owners_entry = serde_json::from_reader::<_, OwnersEntry>(file).ok()?;
+    Some((owners_entry.owners, modified))
+}
+
+/// Failures are ignored, the owners are fetched again on the next run.
+fn write_owners_entry(entry_path: &Path, owners: &BTreeSet<String>) {
+    if let Some(dir) = entry_path.parent() {
+        if fs::create_dir_all(dir).is_err() {
+            return;
+        }
+    }
+    if let Ok(file) = File::create(entry_path) {
+        let owners_entry = OwnersEntry {
+            owners: owners.clone(),
+        };
+        let _ = serde_json::to_writer(file, &owners_entry);
+    }
+}
+
+#[cfg(test)]
+mod owners_tests {
+    use super::*;
+
+    use rstest::*;
+    use semver::Version;
+    use url::Url;
+
+    #[rstest(
+        input_source,
+        expected_is_crates_io,
+        case(
+            Source::Registry {
+                name: String::from("crates.io"),
+                url: Url::parse("https://github.com/rust-lang/crates.io-index")
+                    .unwrap(),
+            },
+            true
+        ),
+        case(
+            Source::Registry {
+                name: String::from("internal"),
+                url: Url::parse("https://example.com/index").unwrap(),
+            },
+            false
+        ),
+        case(
+            Source::Git {
+                url: Url::parse("https://example.com/repo").unwrap(),
+                rev: String::from("1234"),
+            },
+            false
+        )
+    )]
+    fn is_crates_io_test(input_source: Source, expected_is_crates_io: bool) {
+        let package_id = PackageId {
+            name: String::from("libc"),
+            version: Version::new(0, 2, 0),
+            source: input_source,
+        };
+        assert_eq!(is_crates_io(&package_id), expected_is_crates_io);
+    }
+
+    #[rstest]
+    fn owners_entry_round_trip_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let entry_path = owners_entry_path(dir.path(), "libc");
+        let owners = vec!["alice", "github:rust-lang:libs"]
+            .into_iter()
+            .map(String::from)
+            .collect::<BTreeSet<String>>();
+
+        assert!(read_owners_entry(&entry_path).is_none());
+        write_owners_entry(&entry_path, &owners);
+
+        let (read_owners, modified) = read_owners_entry(&entry_path).unwrap();
+        assert_eq!(read_owners, owners);
+        assert!(is_fresh(modified));
+        assert!(!is_fresh(SystemTime::now() - 2 * OWNERS_MAX_AGE));
+    }
+}
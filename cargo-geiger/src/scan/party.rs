@@ -0,0 +1,182 @@
+use crate::format::table::{unsafe_counters_header, unsafe_counters_row};
+use crate::graph::Graph;
+
+use super::{unsafe_stats, GeigerContext};
+
+use cargo::core::{Package, PackageId, Workspace};
+use cargo_geiger_serde::PartySplit;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+/// Sums the unsafe usage of the packages in the graph, split between the
+/// members of the workspace and the external dependencies. Unsafe code of
+/// the workspace is reviewed and fixed, in a dependency it is audited,
+/// replaced or reported upstream.
+pub fn find_party_split(
+    geiger_context: &GeigerContext,
+    graph: &Graph,
+    workspace: &Workspace,
+    rs_files_used: &HashSet<PathBuf>,
+) -> PartySplit {
+    let member_ids = workspace
+        .members()
+        .map(Package::package_id)
+        .collect::<HashSet<PackageId>>();
+    split_by_party(geiger_context, graph, &member_ids, rs_files_used)
+}
+
+fn split_by_party(
+    geiger_context: &GeigerContext,
+    graph: &Graph,
+    member_ids: &HashSet<PackageId>,
+    rs_files_used: &HashSet<PathBuf>,
+) -> PartySplit {
+    let mut party_split = PartySplit::default();
+    for node in graph.graph.raw_nodes().iter().map(|node| &node.weight) {
+        let party_total = if member_ids.contains(&node.id) {
+            &mut party_split.first_party
+        } else {
+            &mut party_split.third_party
+        };
+        party_total.packages += 1;
+        if let Some(package_metrics) =
+            geiger_context.package_id_to_metrics.get(&node.id)
+        {
+            let unsafety = unsafe_stats(package_metrics, rs_files_used);
+            party_total.used += unsafety.used;
+            party_total.unused += unsafety.unused;
+        }
+    }
+    party_split
+}
+
+/// The unsafe usage used by the build of the workspace members and of the
+/// external dependencies, below the totals of the table.
+pub fn construct_party_split_lines(party_split: &PartySplit) -> Vec<String> {
+    let mut party_split_lines = vec![
+        String::from("Unsafe usage used by the build per party:"),
+        String::new(),
+        unsafe_counters_header("Party"),
+    ];
+    for (party_total, party_name) in &[
+        (&party_split.first_party, "First-party, workspace members"),
+        (&party_split.third_party, "Third-party, dependencies"),
+    ] {
+        party_split_lines.push(format!(
+            "{}  {}, {} package(s)",
+            unsafe_counters_row(&party_total.used),
+            party_name,
+            party_total.packages
+        ));
+    }
+    party_split_lines.push(String::new());
+    party_split_lines
+}
+
+#[cfg(test)]
+mod party_tests {
+    use super::*;
+
+    use crate::graph::Node;
+    use crate::rs_file::RsFileMetricsWrapper;
+    use crate::scan::test_utils::create_package_id;
+
+    use cargo_geiger_serde::{Count, CounterBlock, PartyTotal};
+    use rstest::*;
+    use std::collections::HashMap;
+
+    #[rstest]
+    fn split_by_party_test() {
+        let member = create_package_id("member");
+        let other_member = create_package_id("other_member");
+        let dependency = create_package_id("dependency");
+
+        let mut graph = Graph {
+            graph: petgraph::Graph::new(),
+            nodes: HashMap::new(),
+            edge_features: HashMap::new(),
+        };
+        for package_id in &[member, other_member, dependency] {
+            let index = graph.graph.add_node(Node {
+                id: *package_id,
+                features: vec![],
+                patched: false,
+                local_override: false,
+                yanked: false,
+            });
+            graph.nodes.insert(*package_id, index);
+        }
+
+        let mut geiger_context = GeigerContext {
+            package_id_to_metrics: HashMap::new(),
+            used_but_not_scanned: None,
+            warnings: vec![],
+        };
+        for (package_id, path, unsafe_exprs) in &[
+            (member, "member/src/lib.rs", 2),
+            (dependency, "dependency/src/lib.rs", 5),
+            (dependency, "dependency/src/unused.rs", 3),
+        ] {
+            let mut wrapper = RsFileMetricsWrapper::default();
+            wrapper.metrics.counters.exprs.unsafe_ = *unsafe_exprs;
+            geiger_context
+                .package_id_to_metrics
+                .entry(*package_id)
+                .or_default()
+                .rs_path_to_metrics
+                .insert(PathBuf::from(path), wrapper);
+        }
+        let member_ids = vec![member, other_member].into_iter().collect();
+        let rs_files_used = vec![
+            PathBuf::from("member/src/lib.rs"),
+            PathBuf::from("dependency/src/lib.rs"),
+        ]
+        .into_iter()
+        .collect();
+
+        let party_split = split_by_party(
+            &geiger_context,
+            &graph,
+            &member_ids,
+            &rs_files_used,
+        );
+
+        assert_eq!(party_split.first_party.packages, 2);
+        assert_eq!(party_split.first_party.used.exprs.unsafe_, 2);
+        assert_eq!(party_split.third_party.packages, 1);
+        assert_eq!(party_split.third_party.used.exprs.unsafe_, 5);
+        assert_eq!(party_split.third_party.unused.exprs.unsafe_, 3);
+    }
+
+    #[rstest]
+    fn construct_party_split_lines_test() {
+        let party_split = PartySplit {
+            first_party: PartyTotal {
+                packages: 2,
+                used: CounterBlock {
+                    exprs: Count {
+                        safe: 10,
+                        unsafe_: 4,
+                    },
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            third_party: PartyTotal {
+                packages: 7,
+                ..Default::default()
+            },
+        };
+
+        let party_split_lines = construct_party_split_lines(&party_split);
+
+        assert_eq!(party_split_lines.len(), 6);
+        assert_eq!(
+            party_split_lines[3],
+            "0          4            0      0       0        First-party, \
+             workspace members, 2 package(s)"
+        );
+        assert!(party_split_lines[4]
+            .ends_with("Third-party, dependencies, 7 package(s)"));
+    }
+}
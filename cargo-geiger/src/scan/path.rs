@@ -0,0 +1,190 @@
+use crate::args::Args;
+use crate::format::html::render_path_html_report;
+use crate::format::print_config::{OutputFormat, PrintConfig};
+use crate::format::table::{unsafe_counters_header, unsafe_counters_row};
+use crate::format::treemap::treemap_from_path_report;
+use crate::rs_file::canonicalize;
+
+use super::find::find_rs_files_in_dir;
+
+use cargo::util::CargoResult;
+use cargo::{CliError, CliResult};
+use cargo_geiger_serde::PathSafetyReport;
+use geiger::{find_unsafe_in_file, IncludeTests};
+use std::path::Path;
+
+/// Scans all `.rs` files in a directory that is not a cargo package, like
+/// vendored snippets or generated code, without building anything. Every file
+/// found is counted, there is no build to tell used from unused code.
+pub fn run_scan_path(dir: &Path, args: &Args) -> CliResult {
+    let print_config = PrintConfig::new(args)?;
+    let report =
+        scan_path(dir, print_config.include_tests, &print_config.unsafe_apis)?;
+    for path in &report.files_without_metrics {
+        eprintln!("WARNING: Failed to parse file: {}", path.display());
+    }
+    match print_config.output_format {
+        Some(OutputFormat::Html) => {
+            println!("{}", render_path_html_report(&report))
+        }
+        Some(OutputFormat::Json) => {
+            println!("{}", serde_json::to_string(&report).unwrap())
+        }
+        Some(OutputFormat::Markdown) => {
+            return Err(CliError::new(
+                anyhow::anyhow!(
+                    "--markdown describes packages, scan-path does not scan \
+                     any"
+                ),
+                1,
+            ));
+        }
+        Some(OutputFormat::SecurityInsights) => {
+            return Err(CliError::new(
+                anyhow::anyhow!(
+                    "--security-insights describes packages, scan-path does \
+                     not scan any"
+                ),
+                1,
+            ));
+        }
+        Some(OutputFormat::Treemap) => println!(
+            "{}",
+            serde_json::to_string(&treemap_from_path_report(&report)).unwrap()
+        ),
+        None => {
+            for scan_path_line in construct_scan_path_lines(&report) {
+                println!("{}", scan_path_line);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn scan_path(
+    dir: &Path,
+    include_tests: IncludeTests,
+    unsafe_apis: &[String],
+) -> CargoResult<PathSafetyReport> {
+    if !dir.is_dir() {
+        anyhow::bail!("{} is not a directory", dir.display());
+    }
+    let dir = canonicalize(dir)?;
+    let mut report = PathSafetyReport::default();
+    for path in find_rs_files_in_dir(&dir) {
+        let relative_path = path
+            .strip_prefix(&dir)
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|_| path.clone());
+        match find_unsafe_in_file(&path, include_tests, unsafe_apis) {
+            Ok(file_metrics) => {
+                report.total += file_metrics.counters.clone();
+                report.files.insert(relative_path, file_metrics.counters);
+            }
+            Err(_) => {
+                report.files_without_metrics.insert(relative_path);
+            }
+        }
+    }
+    Ok(report)
+}
+
+fn construct_scan_path_lines(report: &PathSafetyReport) -> Vec<String> {
+    let mut scan_path_lines = Vec::<String>::new();
+
+    scan_path_lines.push(unsafe_counters_header("File"));
+    scan_path_lines.push(String::new());
+    for (path, counter_block) in &report.files {
+        scan_path_lines.push(format!(
+            "{}  {}",
+            unsafe_counters_row(counter_block),
+            path.display()
+        ));
+    }
+    scan_path_lines.push(String::new());
+    scan_path_lines.push(format!(
+        "{}  Total, {} file(s)",
+        unsafe_counters_row(&report.total),
+        report.files.len()
+    ));
+
+    scan_path_lines
+}
+
+#[cfg(test)]
+mod path_tests {
+    use super::*;
+
+    use cargo_geiger_serde::{Count, CounterBlock};
+    use rstest::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    #[rstest]
+    fn scan_path_test() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("nested")).unwrap();
+        fs::write(dir.path().join("safe.rs"), "pub fn safe() -> u32 { 1 }")
+            .unwrap();
+        fs::write(
+            dir.path().join("nested").join("unsafe.rs"),
+            "pub unsafe fn unchecked() {}",
+        )
+        .unwrap();
+        fs::write(dir.path().join("broken.rs"), "pub fn {").unwrap();
+        fs::write(dir.path().join("notes.txt"), "unsafe").unwrap();
+
+        let report = scan_path(dir.path(), IncludeTests::No, &[]).unwrap();
+
+        assert_eq!(
+            report.files.keys().cloned().collect::<Vec<PathBuf>>(),
+            vec![PathBuf::from("nested/unsafe.rs"), PathBuf::from("safe.rs")]
+        );
+        assert_eq!(
+            report.total.functions,
+            Count {
+                safe: 1,
+                unsafe_: 1
+            }
+        );
+        assert_eq!(
+            report.files_without_metrics.into_iter().collect::<Vec<_>>(),
+            vec![PathBuf::from("broken.rs")]
+        );
+    }
+
+    #[rstest]
+    fn scan_path_not_a_directory_test() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert!(
+            scan_path(&dir.path().join("missing"), IncludeTests::No, &[])
+                .is_err()
+        );
+    }
+
+    #[rstest]
+    fn construct_scan_path_lines_test() {
+        let mut report = PathSafetyReport::default();
+        report.files.insert(
+            PathBuf::from("a.rs"),
+            CounterBlock {
+                exprs: Count {
+                    safe: 3,
+                    unsafe_: 2,
+                },
+                ..Default::default()
+            },
+        );
+        report.total = report.files[&PathBuf::from("a.rs")].clone();
+
+        let scan_path_lines = construct_scan_path_lines(&report);
+
+        assert_eq!(scan_path_lines.len(), 5);
+        assert_eq!(
+            scan_path_lines[2],
+            "0          2            0      0       0        a.rs"
+        );
+        assert!(scan_path_lines[4].ends_with("Total, 1 file(s)"));
+    }
+}
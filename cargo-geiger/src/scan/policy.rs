@@ -0,0 +1,1343 @@
+use crate::format::numbers::NumberFormat;
+use crate::graph::Graph;
+
+use super::stage::reachable_through;
+use super::violation::Check;
+use super::{package_forbids_unsafe, GeigerContext};
+
+use cargo::core::dependency::DepKind;
+use cargo::core::{PackageId, SourceId};
+use cargo::CliError;
+use cargo_geiger_serde::CounterBlock;
+use petgraph::EdgeDirection;
+use serde::Deserialize;
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// The rules of a policy file given with `--policy`, e.g.
+///
+/// ```toml
+/// # The weighted unsafe usage of all dependencies must stay below this.
+/// max-score = 500
+///
+/// # New packages allowed past `--quarantine`.
+/// acknowledge = [
+///     { name = "zeroize", reviewed-by = "Jane Doe", review-date = "2020-10-12" },
+/// ]
+///
+/// # Direct dependencies must forbid unsafe code.
+/// [[rule]]
+/// direct = true
+/// require = "forbid-unsafe"
+///
+/// # Transitive dependencies may only contain unsafe code if allowlisted.
+/// [[rule]]
+/// min-depth = 2
+/// require = "no-unsafe"
+/// allow = [
+///     "memchr",
+///     { name = "libc", reviewed-by = "Jane Doe", review-date = "2020-10-01" },
+/// ]
+///
+/// # Unsafe code is only acceptable from crates.io packages.
+/// [[rule]]
+/// sources = ["git", "path"]
+/// require = "no-unsafe"
+///
+/// # Reports must not reflect local forks of registry packages.
+/// [[rule]]
+/// min-depth = 2
+/// require = "registry-source"
+///
+/// # Transmutes weigh five times as much as other unsafe expressions, the
+/// # packages only used by tests a tenth of the rest.
+/// [weights]
+/// api-calls = { "mem::transmute" = 5.0 }
+/// test-only = 0.1
+///
+/// # The changes since the `--baseline` report that are not reported.
+/// [diff]
+/// ignore-test-only = true
+/// ignore-crates = ["windows*", "winapi"]
+/// ignore-decreases = true
+/// ```
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Policy {
+    /// Every exception must name its reviewer and the date of the review.
+    #[serde(default)]
+    pub require_review: bool,
+    /// The packages that are not in the report given to `--quarantine`, but
+    /// may be used anyway.
+    #[serde(default)]
+    pub acknowledge: Vec<Exception>,
+    /// The highest weighted score of the dependencies that passes.
+    pub max_score: Option<f64>,
+    #[serde(default, rename = "rule")]
+    pub rules: Vec<PolicyRule>,
+    /// The weights of the score, the default weights if the table is
+    /// missing.
+    pub weights: Option<Weights>,
+    #[serde(default)]
+    pub diff: DiffRules,
+}
+
+/// The changes since the `--baseline` report that are left out of the
+/// comparison, both of the HTML output and of the regression check, so that
+/// only the changes that matter to the team are surfaced.
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct DiffRules {
+    /// Ignore the packages only used by tests, that are not reached from the
+    /// root packages through normal and build dependencies.
+    pub ignore_test_only: bool,
+    /// Ignore the crates with a name matching any of these globs, e.g.
+    /// `windows*`.
+    pub ignore_crates: Vec<String>,
+    /// Ignore less unsafe code used and removed packages, only more unsafe
+    /// code and new packages are reported.
+    pub ignore_decreases: bool,
+}
+
+/// The weight of every unsafe usage counted towards the score of a policy
+/// check. The unsafe functions, expressions, impls, traits and methods weigh
+/// 1 and the other metrics 0 by default, the calls to watched APIs add their
+/// weight on top of the unsafe expressions they are in. The score of the
+/// packages only used by tests and of the generated files is multiplied by
+/// their factor, 1 by default.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Weights {
+    pub functions: f64,
+    pub exprs: f64,
+    pub impls: f64,
+    pub traits: f64,
+    pub methods: f64,
+    pub asm: f64,
+    pub drop_exprs: f64,
+    pub unsafe_fn_body_exprs: f64,
+    pub const_exprs: f64,
+    pub packed_structs: f64,
+    pub static_muts: f64,
+    pub static_mut_refs: f64,
+    pub unions: f64,
+    pub union_field_accesses: f64,
+    pub extern_statics: f64,
+    pub ffi_calls: f64,
+    pub js_ffi_calls: f64,
+    pub exported_globals: f64,
+    pub public_unsafe_fns: f64,
+    pub public_unsafe_traits: f64,
+    /// The factor of the score of the packages only used by tests, those not
+    /// reached from the root packages through normal and build dependencies.
+    pub test_only: f64,
+    /// The factor of the score of the generated files.
+    pub generated: f64,
+    /// The weights of the calls to the watched APIs, by name, e.g.
+    /// `mem::transmute`.
+    pub api_calls: BTreeMap<String, f64>,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights {
+            functions: 1.0,
+            exprs: 1.0,
+            impls: 1.0,
+            traits: 1.0,
+            methods: 1.0,
+            asm: 0.0,
+            drop_exprs: 0.0,
+            unsafe_fn_body_exprs: 0.0,
+            const_exprs: 0.0,
+            packed_structs: 0.0,
+            static_muts: 0.0,
+            static_mut_refs: 0.0,
+            unions: 0.0,
+            union_field_accesses: 0.0,
+            extern_statics: 0.0,
+            ffi_calls: 0.0,
+            js_ffi_calls: 0.0,
+            exported_globals: 0.0,
+            public_unsafe_fns: 0.0,
+            public_unsafe_traits: 0.0,
+            test_only: 1.0,
+            generated: 1.0,
+            api_calls: BTreeMap::new(),
+        }
+    }
+}
+
+impl Weights {
+    /// The weighted sum of the unsafe usage in the counters.
+    pub fn score(&self, counters: &CounterBlock) -> f64 {
+        let api_calls_score = counters
+            .api_calls
+            .iter()
+            .map(|(api, count)| {
+                self.api_calls.get(api).copied().unwrap_or(0.0) * *count as f64
+            })
+            .sum::<f64>();
+        self.functions * counters.functions.unsafe_ as f64
+            + self.exprs * counters.exprs.unsafe_ as f64
+            + self.impls * counters.item_impls.unsafe_ as f64
+            + self.traits * counters.item_traits.unsafe_ as f64
+            + self.methods * counters.methods.unsafe_ as f64
+            + self.asm * counters.asm as f64
+            + self.drop_exprs * counters.drop_exprs.unsafe_ as f64
+            + self.unsafe_fn_body_exprs * counters.unsafe_fn_body_exprs as f64
+            + self.const_exprs * counters.const_exprs.unsafe_ as f64
+            + self.packed_structs * counters.packed_structs as f64
+            + self.static_muts * counters.static_muts as f64
+            + self.static_mut_refs * counters.static_mut_refs as f64
+            + self.unions * counters.unions as f64
+            + self.union_field_accesses * counters.union_field_accesses as f64
+            + self.extern_statics * counters.extern_statics as f64
+            + self.ffi_calls * counters.ffi_calls as f64
+            + self.js_ffi_calls * counters.js_ffi_calls as f64
+            + self.exported_globals * counters.exported_globals as f64
+            + self.public_unsafe_fns * counters.public_unsafe_fns as f64
+            + self.public_unsafe_traits * counters.public_unsafe_traits as f64
+            + api_calls_score
+    }
+}
+
+/// A requirement for the dependencies in a range of depths and from the
+/// given sources, the direct dependencies of the root package are at
+/// depth 1. Every dependency is checked at its shortest distance from the
+/// root package.
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct PolicyRule {
+    /// Only the direct dependencies, the same as `max-depth = 1`.
+    #[serde(default)]
+    pub direct: bool,
+    pub min_depth: Option<usize>,
+    pub max_depth: Option<usize>,
+    /// The sources of the packages the rule applies to, all if empty.
+    #[serde(default)]
+    pub sources: Vec<SourceKind>,
+    pub require: Requirement,
+    /// The packages exempt from the rule.
+    #[serde(default)]
+    pub allow: Vec<Exception>,
+}
+
+impl PolicyRule {
+    fn applies_to(&self, package_id: PackageId, depth: usize) -> bool {
+        self.applies_to_depth(depth)
+            && self.applies_to_source(package_id.source_id())
+    }
+
+    fn exception(&self, package_id: PackageId) -> Option<&Exception> {
+        self.allow
+            .iter()
+            .find(|exception| exception.name == package_id.name().as_str())
+    }
+
+    fn applies_to_depth(&self, depth: usize) -> bool {
+        let max_depth = if self.direct { Some(1) } else { self.max_depth };
+        depth >= self.min_depth.unwrap_or(1)
+            && max_depth.is_none_or(|max_depth| depth <= max_depth)
+    }
+
+    fn applies_to_source(&self, source_id: SourceId) -> bool {
+        self.sources.is_empty()
+            || source_kind(source_id)
+                .is_some_and(|kind| self.sources.contains(&kind))
+    }
+}
+
+/// A package exempt from a rule, given by its name or by a table with the
+/// review of the exception.
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(from = "ExceptionEntry")]
+pub struct Exception {
+    pub name: String,
+    pub reviewed_by: Option<String>,
+    /// The date of the review, as YYYY-MM-DD.
+    pub review_date: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ExceptionEntry {
+    Name(String),
+    Reviewed {
+        name: String,
+        #[serde(rename = "reviewed-by")]
+        reviewed_by: Option<String>,
+        #[serde(rename = "review-date")]
+        review_date: Option<String>,
+    },
+}
+
+impl From<ExceptionEntry> for Exception {
+    fn from(exception_entry: ExceptionEntry) -> Self {
+        match exception_entry {
+            ExceptionEntry::Name(name) => Exception {
+                name,
+                reviewed_by: None,
+                review_date: None,
+            },
+            ExceptionEntry::Reviewed {
+                name,
+                reviewed_by,
+                review_date,
+            } => Exception {
+                name,
+                reviewed_by,
+                review_date,
+            },
+        }
+    }
+}
+
+/// The trust tier of a package source.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SourceKind {
+    CratesIo,
+
+    /// Any registry other than crates.io.
+    Registry,
+
+    Git,
+    Path,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Requirement {
+    /// All entry points declare `#![forbid(unsafe_code)]`.
+    ForbidUnsafe,
+
+    /// No unsafe usage is found in any file of the package, used by the
+    /// build or not.
+    NoUnsafe,
+
+    /// The package is taken from crates.io or another registry, and not
+    /// overridden with a local path or a git repository.
+    RegistrySource,
+}
+
+/// Fails if any dependency of the root packages breaks a rule of the policy
+/// file, listing the violations. A policy with invalid exceptions fails
+/// right away, whatever the violations.
+pub fn check_policy(
+    geiger_context: &GeigerContext,
+    root_package_ids: &[PackageId],
+    graph: &Graph,
+    numbers: NumberFormat,
+    policy_path: &Path,
+) -> Result<Check, CliError> {
+    let policy = load_policy(policy_path)?;
+    let invalid_exceptions = find_invalid_exceptions(&policy);
+    for invalid_exception in &invalid_exceptions {
+        eprintln!("ERROR: Invalid policy exception: {}", invalid_exception);
+    }
+    if !invalid_exceptions.is_empty() {
+        return Err(CliError::new(
+            anyhow::Error::new(InvalidExceptionError {
+                invalid_exception_count: invalid_exceptions.len() as u64,
+            }),
+            1,
+        ));
+    }
+    let package_depths = package_depths(graph, root_package_ids);
+    let test_only_ids = test_only_package_ids(graph, root_package_ids);
+    for applied_exception in find_applied_exceptions(&policy, &package_depths) {
+        eprintln!("Policy exception: {}", applied_exception);
+    }
+    if policy.max_score.is_some() || policy.weights.is_some() {
+        let score = policy_score(
+            &policy,
+            geiger_context,
+            &package_depths,
+            &test_only_ids,
+        );
+        eprintln!("Policy score: {}", numbers.ratio(score));
+    }
+    let violations = find_all_policy_violations(
+        &policy,
+        geiger_context,
+        &package_depths,
+        &test_only_ids,
+        numbers,
+    );
+    let violations = violations
+        .iter()
+        .map(|violation| format!("Policy violation: {}", violation))
+        .collect::<Vec<String>>();
+
+    let violation_count = violations.len() as u64;
+    Ok(Check::new(
+        violations,
+        PolicyViolationError { violation_count },
+    ))
+}
+
+#[derive(Debug)]
+struct PolicyViolationError {
+    violation_count: u64,
+}
+
+impl Error for PolicyViolationError {}
+
+impl fmt::Display for PolicyViolationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} policy violation(s)", self.violation_count)
+    }
+}
+
+#[derive(Debug)]
+struct InvalidExceptionError {
+    invalid_exception_count: u64,
+}
+
+impl Error for InvalidExceptionError {}
+
+impl fmt::Display for InvalidExceptionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} invalid policy exception(s)",
+            self.invalid_exception_count
+        )
+    }
+}
+
+pub(super) fn load_policy(policy_path: &Path) -> Result<Policy, CliError> {
+    read_policy(policy_path).map_err(|e| {
+        CliError::new(
+            e.context(format!(
+                "Failed to read the policy {}",
+                policy_path.display()
+            )),
+            1,
+        )
+    })
+}
+
+fn read_policy(path: &Path) -> anyhow::Result<Policy> {
+    let policy_str = fs::read_to_string(path)?;
+    Ok(toml::from_str(&policy_str)?)
+}
+
+/// The shortest distance of every package in the graph from the nearest
+/// root package, the roots are at depth 0.
+fn package_depths(
+    graph: &Graph,
+    root_package_ids: &[PackageId],
+) -> BTreeMap<PackageId, usize> {
+    let mut depths = BTreeMap::new();
+    let mut queue = VecDeque::new();
+    for root_package_id in root_package_ids {
+        depths.insert(*root_package_id, 0);
+        queue.push_back(graph.nodes[root_package_id]);
+    }
+    while let Some(index) = queue.pop_front() {
+        let depth = depths[&graph.graph[index].id];
+        for dependency_index in graph
+            .graph
+            .neighbors_directed(index, EdgeDirection::Outgoing)
+        {
+            let dependency_id = graph.graph[dependency_index].id;
+            if let Entry::Vacant(entry) = depths.entry(dependency_id) {
+                entry.insert(depth + 1);
+                queue.push_back(dependency_index);
+            }
+        }
+    }
+    depths
+}
+
+/// The packages only used by tests, those not reached from the root packages
+/// through normal and build dependencies.
+fn test_only_package_ids(
+    graph: &Graph,
+    root_package_ids: &[PackageId],
+) -> HashSet<PackageId> {
+    let built = reachable_through(graph, root_package_ids, |kind, _| {
+        kind != DepKind::Development
+    });
+    reachable_through(graph, root_package_ids, |_, _| true)
+        .into_iter()
+        .filter(|package_id| !built.contains(package_id))
+        .collect()
+}
+
+/// Describes every exception or acknowledgement that is missing review
+/// metadata required by the policy, or has a malformed review date.
+fn find_invalid_exceptions(policy: &Policy) -> Vec<String> {
+    let exception_lists = policy
+        .rules
+        .iter()
+        .enumerate()
+        .map(|(rule_index, rule)| {
+            (format!("rule {}", rule_index + 1), &rule.allow)
+        })
+        .chain(std::iter::once((
+            String::from("acknowledge"),
+            &policy.acknowledge,
+        )));
+    let mut invalid_exceptions = Vec::new();
+    for (location, exceptions) in exception_lists {
+        for exception in exceptions {
+            let mut problems = Vec::new();
+            if policy.require_review && exception.reviewed_by.is_none() {
+                problems.push("reviewed-by is missing");
+            }
+            match &exception.review_date {
+                Some(review_date) if !is_valid_date(review_date) => {
+                    problems.push("review-date is not a YYYY-MM-DD date")
+                }
+                None if policy.require_review => {
+                    problems.push("review-date is missing")
+                }
+                _ => {}
+            }
+            if !problems.is_empty() {
+                invalid_exceptions.push(format!(
+                    "{} in {}, {}",
+                    exception.name,
+                    location,
+                    problems.join(", ")
+                ));
+            }
+        }
+    }
+    invalid_exceptions
+}
+
+fn is_valid_date(date: &str) -> bool {
+    let parts = date.split('-').collect::<Vec<&str>>();
+    match parts.as_slice() {
+        [year, month, day]
+            if year.len() == 4 && month.len() == 2 && day.len() == 2 =>
+        {
+            match (
+                year.parse::<u32>(),
+                month.parse::<u32>(),
+                day.parse::<u32>(),
+            ) {
+                (Ok(year), Ok(month), Ok(day)) => {
+                    (1..=12).contains(&month)
+                        && (1..=days_in_month(year, month)).contains(&day)
+                }
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Describes every exception exempting a package from a rule that applies
+/// to it, with its review, for the compliance record of the scan.
+fn find_applied_exceptions(
+    policy: &Policy,
+    package_depths: &BTreeMap<PackageId, usize>,
+) -> Vec<String> {
+    let mut applied_exceptions = Vec::new();
+    for (package_id, depth) in package_depths {
+        for (rule_index, rule) in policy.rules.iter().enumerate() {
+            if !rule.applies_to(*package_id, *depth) {
+                continue;
+            }
+            if let Some(exception) = rule.exception(*package_id) {
+                applied_exceptions.push(format!(
+                    "{} is exempt from rule {}, {}",
+                    package_id,
+                    rule_index + 1,
+                    describe_review(exception)
+                ));
+            }
+        }
+    }
+    applied_exceptions
+}
+
+/// Who reviewed the exception and when, as far as the policy tells.
+pub(super) fn describe_review(exception: &Exception) -> String {
+    match (&exception.reviewed_by, &exception.review_date) {
+        (Some(reviewed_by), Some(review_date)) => {
+            format!("reviewed by {} on {}", reviewed_by, review_date)
+        }
+        (Some(reviewed_by), None) => format!("reviewed by {}", reviewed_by),
+        (None, Some(review_date)) => format!("reviewed on {}", review_date),
+        (None, None) => String::from("not reviewed"),
+    }
+}
+
+/// Directory sources, like vendored packages, have no kind.
+fn source_kind(source_id: SourceId) -> Option<SourceKind> {
+    if source_id.is_git() {
+        Some(SourceKind::Git)
+    } else if source_id.is_path() {
+        Some(SourceKind::Path)
+    } else if source_id.is_registry() {
+        if source_id.display_registry_name() == "crates.io" {
+            Some(SourceKind::CratesIo)
+        } else {
+            Some(SourceKind::Registry)
+        }
+    } else {
+        None
+    }
+}
+
+/// The score of the unsafe usage in every file of the packages, weighted by
+/// the weights of the policy. Packages without metrics add nothing.
+fn policy_score(
+    policy: &Policy,
+    geiger_context: &GeigerContext,
+    package_depths: &BTreeMap<PackageId, usize>,
+    test_only_ids: &HashSet<PackageId>,
+) -> f64 {
+    let default_weights = Weights::default();
+    let weights = policy.weights.as_ref().unwrap_or(&default_weights);
+    let mut score = 0.0;
+    for package_id in package_depths.keys() {
+        let package_metrics =
+            match geiger_context.package_id_to_metrics.get(package_id) {
+                Some(package_metrics) => package_metrics,
+                None => continue,
+            };
+        let package_score = package_metrics
+            .rs_path_to_metrics
+            .values()
+            .map(|rs_file_metrics_wrapper| {
+                let file_score =
+                    weights.score(&rs_file_metrics_wrapper.metrics.counters);
+                if rs_file_metrics_wrapper.is_generated {
+                    file_score * weights.generated
+                } else {
+                    file_score
+                }
+            })
+            .sum::<f64>()
+            + weights.score(&package_metrics.folded.used.counters)
+            + weights.score(&package_metrics.folded.unused.counters);
+        score += if test_only_ids.contains(package_id) {
+            package_score * weights.test_only
+        } else {
+            package_score
+        };
+    }
+    score
+}
+
+/// The violations of the rules of the policy, followed by the max-score of
+/// the policy if the score of the packages is above it.
+fn find_all_policy_violations(
+    policy: &Policy,
+    geiger_context: &GeigerContext,
+    package_depths: &BTreeMap<PackageId, usize>,
+    test_only_ids: &HashSet<PackageId>,
+    numbers: NumberFormat,
+) -> Vec<String> {
+    let mut violations =
+        find_policy_violations(policy, geiger_context, package_depths);
+    if let Some(max_score) = policy.max_score {
+        let score =
+            policy_score(policy, geiger_context, package_depths, test_only_ids);
+        if score > max_score {
+            violations.push(format!(
+                "the score {} is above max-score {}",
+                numbers.ratio(score),
+                numbers.ratio(max_score)
+            ));
+        }
+    }
+    violations
+}
+
+/// Describes every rule broken by a package, sorted by package. Packages
+/// without metrics break every rule that applies to them, since it cannot be
+/// verified that they follow it.
+fn find_policy_violations(
+    policy: &Policy,
+    geiger_context: &GeigerContext,
+    package_depths: &BTreeMap<PackageId, usize>,
+) -> Vec<String> {
+    let mut violations = Vec::new();
+    for (package_id, depth) in package_depths {
+        for (rule_index, rule) in policy.rules.iter().enumerate() {
+            if !rule.applies_to(*package_id, *depth)
+                || rule.exception(*package_id).is_some()
+            {
+                continue;
+            }
+            let package_metrics =
+                geiger_context.package_id_to_metrics.get(package_id);
+            let follows_rule = match (rule.require, package_metrics) {
+                (Requirement::RegistrySource, _) => {
+                    package_id.source_id().is_registry()
+                }
+                (_, None) => false,
+                (Requirement::ForbidUnsafe, Some(package_metrics)) => {
+                    package_forbids_unsafe(package_metrics)
+                }
+                (Requirement::NoUnsafe, Some(package_metrics)) => {
+                    !package_metrics.rs_path_to_metrics.values().any(
+                        |rs_file_metrics_wrapper| {
+                            rs_file_metrics_wrapper
+                                .metrics
+                                .counters
+                                .has_unsafe()
+                        },
+                    )
+                }
+            };
+            if !follows_rule {
+                violations.push(format!(
+                    "{} at depth {} breaks rule {}, {}",
+                    package_id,
+                    depth,
+                    rule_index + 1,
+                    match rule.require {
+                        Requirement::ForbidUnsafe => {
+                            "it must declare #![forbid(unsafe_code)]"
+                        }
+                        Requirement::NoUnsafe => "it must not use unsafe code",
+                        Requirement::RegistrySource => {
+                            "it must be taken from a registry"
+                        }
+                    }
+                ));
+            }
+        }
+    }
+    violations
+}
+
+#[cfg(test)]
+mod policy_tests {
+    use super::*;
+
+    use crate::graph::Node;
+    use crate::rs_file::RsFileMetricsWrapper;
+    use crate::scan::test_utils::create_package_id;
+    use crate::scan::PackageMetrics;
+
+    use cargo::core::dependency::DepKind;
+    use cargo::core::SourceId;
+    use cargo::util::ToSemver;
+    use cargo_geiger_serde::Count;
+    use rstest::*;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use url::Url;
+
+    #[rstest]
+    fn policy_from_toml_test() {
+        let policy = toml::from_str::<Policy>(
+            r#"
+            acknowledge = ["zeroize"]
+
+            [[rule]]
+            direct = true
+            require = "forbid-unsafe"
+
+            [[rule]]
+            min-depth = 2
+            require = "no-unsafe"
+            allow = [
+                "libc",
+                { name = "memchr", reviewed-by = "Jane Doe", review-date = "2020-10-01" },
+            ]
+
+            [[rule]]
+            sources = ["crates-io", "registry", "git", "path"]
+            require = "no-unsafe"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            policy.acknowledge,
+            vec![create_exception("zeroize", None, None)]
+        );
+        assert_eq!(
+            policy.rules,
+            vec![
+                PolicyRule {
+                    direct: true,
+                    min_depth: None,
+                    max_depth: None,
+                    sources: vec![],
+                    require: Requirement::ForbidUnsafe,
+                    allow: vec![],
+                },
+                PolicyRule {
+                    direct: false,
+                    min_depth: Some(2),
+                    max_depth: None,
+                    sources: vec![],
+                    require: Requirement::NoUnsafe,
+                    allow: vec![
+                        create_exception("libc", None, None),
+                        create_exception(
+                            "memchr",
+                            Some("Jane Doe"),
+                            Some("2020-10-01")
+                        ),
+                    ],
+                },
+                PolicyRule {
+                    direct: false,
+                    min_depth: None,
+                    max_depth: None,
+                    sources: vec![
+                        SourceKind::CratesIo,
+                        SourceKind::Registry,
+                        SourceKind::Git,
+                        SourceKind::Path,
+                    ],
+                    require: Requirement::NoUnsafe,
+                    allow: vec![],
+                },
+            ]
+        );
+        assert!(toml::from_str::<Policy>("[[rule]]\nrequire = \"x\"").is_err());
+    }
+
+    #[rstest]
+    fn diff_rules_from_toml_test() {
+        let policy = toml::from_str::<Policy>(
+            r#"
+            [diff]
+            ignore-test-only = true
+            ignore-crates = ["windows*"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            policy.diff,
+            DiffRules {
+                ignore_test_only: true,
+                ignore_crates: vec![String::from("windows*")],
+                ignore_decreases: false,
+            }
+        );
+        assert_eq!(
+            toml::from_str::<Policy>("").unwrap().diff,
+            DiffRules::default()
+        );
+        assert!(toml::from_str::<Policy>("[diff]\nignore = true").is_err());
+    }
+
+    #[rstest]
+    fn weights_from_toml_test() {
+        let policy = toml::from_str::<Policy>(
+            r#"
+            max-score = 500
+
+            [weights]
+            exprs = 0.5
+            unions = 2
+            api-calls = { "mem::transmute" = 5.0 }
+            test-only = 0.1
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(policy.max_score, Some(500.0));
+        let weights = policy.weights.unwrap();
+        assert_eq!(weights.exprs, 0.5);
+        assert_eq!(weights.functions, 1.0);
+        assert_eq!(weights.unions, 2.0);
+        assert_eq!(weights.asm, 0.0);
+        assert_eq!(weights.api_calls["mem::transmute"], 5.0);
+        assert_eq!(weights.test_only, 0.1);
+        assert_eq!(weights.generated, 1.0);
+        assert!(toml::from_str::<Policy>("[weights]\nx = 1.0").is_err());
+    }
+
+    #[rstest]
+    fn policy_score_test() {
+        let root = create_package_id("root");
+        let generated = create_package_id("generated");
+        let test_helper = create_package_id("test_helper");
+
+        let mut graph = Graph {
+            graph: petgraph::Graph::new(),
+            nodes: HashMap::new(),
+            edge_features: HashMap::new(),
+        };
+        for package_id in &[root, generated, test_helper] {
+            let index = graph.graph.add_node(Node {
+                id: *package_id,
+                features: vec![],
+                patched: false,
+                local_override: false,
+                yanked: false,
+            });
+            graph.nodes.insert(*package_id, index);
+        }
+        graph.graph.add_edge(
+            graph.nodes[&root],
+            graph.nodes[&generated],
+            DepKind::Normal,
+        );
+        graph.graph.add_edge(
+            graph.nodes[&root],
+            graph.nodes[&test_helper],
+            DepKind::Development,
+        );
+
+        let mut generated_metrics = create_package_metrics(false, true);
+        for rs_file_metrics_wrapper in
+            generated_metrics.rs_path_to_metrics.values_mut()
+        {
+            rs_file_metrics_wrapper.is_generated = true;
+        }
+        let mut package_id_to_metrics = HashMap::new();
+        package_id_to_metrics.insert(root, create_package_metrics(false, true));
+        package_id_to_metrics.insert(generated, generated_metrics);
+        package_id_to_metrics
+            .insert(test_helper, create_package_metrics(false, true));
+        let geiger_context = GeigerContext {
+            package_id_to_metrics,
+            used_but_not_scanned: None,
+            warnings: vec![],
+        };
+        let mut policy = Policy::default();
+
+        let package_depths = package_depths(&graph, &[root]);
+        let test_only_ids = test_only_package_ids(&graph, &[root]);
+        assert_eq!(
+            test_only_ids,
+            vec![test_helper]
+                .into_iter()
+                .collect::<HashSet<PackageId>>()
+        );
+        assert_eq!(
+            policy_score(
+                &policy,
+                &geiger_context,
+                &package_depths,
+                &test_only_ids
+            ),
+            3.0
+        );
+
+        policy.weights = Some(Weights {
+            test_only: 0.5,
+            generated: 0.25,
+            ..Weights::default()
+        });
+        assert_eq!(
+            policy_score(
+                &policy,
+                &geiger_context,
+                &package_depths,
+                &test_only_ids
+            ),
+            1.75
+        );
+    }
+
+    #[rstest]
+    fn weights_score_test() {
+        let mut counters = CounterBlock::default();
+        counters.functions.unsafe_ = 1;
+        counters.exprs = Count {
+            safe: 10,
+            unsafe_: 4,
+        };
+        counters.static_muts = 2;
+        counters.api_calls.insert(String::from("mem::transmute"), 1);
+        counters.api_calls.insert(String::from("ptr::read"), 3);
+        let mut weights = Weights::default();
+
+        assert_eq!(weights.score(&counters), 5.0);
+
+        weights.static_muts = 1.5;
+        weights
+            .api_calls
+            .insert(String::from("mem::transmute"), 5.0);
+        assert_eq!(weights.score(&counters), 13.0);
+    }
+
+    #[rstest(
+        input_direct,
+        input_min_depth,
+        input_max_depth,
+        expected_depths,
+        case(false, None, None, vec![1, 2, 3]),
+        case(true, None, None, vec![1]),
+        case(false, Some(2), None, vec![2, 3]),
+        case(false, None, Some(2), vec![1, 2]),
+        case(true, Some(2), None, vec![])
+    )]
+    fn policy_rule_applies_to_depth_test(
+        input_direct: bool,
+        input_min_depth: Option<usize>,
+        input_max_depth: Option<usize>,
+        expected_depths: Vec<usize>,
+    ) {
+        let rule = PolicyRule {
+            direct: input_direct,
+            min_depth: input_min_depth,
+            max_depth: input_max_depth,
+            sources: vec![],
+            require: Requirement::NoUnsafe,
+            allow: vec![],
+        };
+
+        assert_eq!(
+            (0..=3)
+                .filter(|depth| rule.applies_to_depth(*depth))
+                .collect::<Vec<usize>>(),
+            expected_depths
+        );
+    }
+
+    #[rstest(
+        input_source_id,
+        expected_applies,
+        case(
+            SourceId::for_registry(
+                &Url::parse("https://github.com/rust-lang/crates.io-index")
+                    .unwrap()
+            )
+            .unwrap(),
+            false
+        ),
+        case(
+            SourceId::for_registry(
+                &Url::parse("https://example.com/index").unwrap()
+            )
+            .unwrap(),
+            false
+        ),
+        case(
+            SourceId::from_url(
+                "git+https://github.com/rust-secure-code/cargo-geiger"
+            )
+            .unwrap(),
+            true
+        ),
+        case(SourceId::for_path(&std::env::temp_dir()).unwrap(), true)
+    )]
+    fn policy_rule_applies_to_source_test(
+        input_source_id: SourceId,
+        expected_applies: bool,
+    ) {
+        let rule = PolicyRule {
+            direct: false,
+            min_depth: None,
+            max_depth: None,
+            sources: vec![SourceKind::Git, SourceKind::Path],
+            require: Requirement::NoUnsafe,
+            allow: vec![],
+        };
+
+        assert_eq!(rule.applies_to_source(input_source_id), expected_applies);
+        assert!(PolicyRule {
+            sources: vec![],
+            ..rule
+        }
+        .applies_to_source(input_source_id));
+    }
+
+    #[rstest]
+    fn find_policy_violations_test() {
+        let root = create_package_id("root");
+        let direct_forbids = create_package_id("direct_forbids");
+        let direct_unsafe = create_package_id("direct_unsafe");
+        let transitive_unsafe = create_package_id("transitive_unsafe");
+        let transitive_allowed = create_package_id("transitive_allowed");
+
+        let mut graph = Graph {
+            graph: petgraph::Graph::new(),
+            nodes: HashMap::new(),
+            edge_features: HashMap::new(),
+        };
+        for package_id in &[
+            root,
+            direct_forbids,
+            direct_unsafe,
+            transitive_unsafe,
+            transitive_allowed,
+        ] {
+            let index = graph.graph.add_node(Node {
+                id: *package_id,
+                features: vec![],
+                patched: false,
+                local_override: false,
+                yanked: false,
+            });
+            graph.nodes.insert(*package_id, index);
+        }
+        for (from, to) in &[
+            (root, direct_forbids),
+            (root, direct_unsafe),
+            (direct_forbids, transitive_unsafe),
+            (direct_unsafe, transitive_allowed),
+            (transitive_unsafe, direct_unsafe),
+        ] {
+            graph.graph.add_edge(
+                graph.nodes[from],
+                graph.nodes[to],
+                DepKind::Normal,
+            );
+        }
+
+        let mut package_id_to_metrics = HashMap::new();
+        package_id_to_metrics.insert(root, create_package_metrics(false, true));
+        package_id_to_metrics
+            .insert(direct_forbids, create_package_metrics(true, false));
+        package_id_to_metrics
+            .insert(direct_unsafe, create_package_metrics(false, true));
+        package_id_to_metrics
+            .insert(transitive_unsafe, create_package_metrics(false, true));
+        package_id_to_metrics
+            .insert(transitive_allowed, create_package_metrics(false, true));
+        let geiger_context = GeigerContext {
+            package_id_to_metrics,
+            used_but_not_scanned: None,
+            warnings: vec![],
+        };
+        let policy = Policy {
+            require_review: false,
+            acknowledge: vec![],
+            max_score: None,
+            rules: vec![
+                PolicyRule {
+                    direct: true,
+                    min_depth: None,
+                    max_depth: None,
+                    sources: vec![],
+                    require: Requirement::ForbidUnsafe,
+                    allow: vec![],
+                },
+                PolicyRule {
+                    direct: false,
+                    min_depth: Some(2),
+                    max_depth: None,
+                    sources: vec![],
+                    require: Requirement::NoUnsafe,
+                    allow: vec![create_exception(
+                        "transitive_allowed",
+                        Some("Jane Doe"),
+                        Some("2020-10-01"),
+                    )],
+                },
+            ],
+            weights: None,
+            diff: DiffRules::default(),
+        };
+
+        let package_depths = package_depths(&graph, &[root]);
+        assert_eq!(package_depths[&root], 0);
+        assert_eq!(package_depths[&direct_unsafe], 1);
+        assert_eq!(package_depths[&transitive_allowed], 2);
+
+        assert_eq!(
+            find_policy_violations(&policy, &geiger_context, &package_depths),
+            vec![
+                format!(
+                    "{} at depth 1 breaks rule 1, it must declare \
+                     #![forbid(unsafe_code)]",
+                    direct_unsafe
+                ),
+                format!(
+                    "{} at depth 2 breaks rule 2, it must not use unsafe code",
+                    transitive_unsafe
+                ),
+            ]
+        );
+        assert_eq!(
+            find_applied_exceptions(&policy, &package_depths),
+            vec![format!(
+                "{} is exempt from rule 2, reviewed by Jane Doe on 2020-10-01",
+                transitive_allowed
+            )]
+        );
+        assert_eq!(
+            policy_score(
+                &policy,
+                &geiger_context,
+                &package_depths,
+                &HashSet::new()
+            ),
+            4.0
+        );
+    }
+
+    #[rstest]
+    fn find_policy_violations_registry_source_test() {
+        let root = create_package_id("root");
+        let local_fork = PackageId::new(
+            "local_fork",
+            "1.2.3".to_semver().unwrap(),
+            SourceId::for_path(&std::env::temp_dir()).unwrap(),
+        )
+        .unwrap();
+        let registry_package = PackageId::new(
+            "registry_package",
+            "1.2.3".to_semver().unwrap(),
+            SourceId::for_registry(
+                &Url::parse("https://github.com/rust-lang/crates.io-index")
+                    .unwrap(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let mut package_depths = BTreeMap::new();
+        package_depths.insert(root, 0);
+        package_depths.insert(local_fork, 1);
+        package_depths.insert(registry_package, 1);
+        let geiger_context = GeigerContext {
+            package_id_to_metrics: HashMap::new(),
+            used_but_not_scanned: None,
+            warnings: vec![],
+        };
+        let policy = Policy {
+            require_review: false,
+            acknowledge: vec![],
+            max_score: None,
+            rules: vec![PolicyRule {
+                direct: false,
+                min_depth: None,
+                max_depth: None,
+                sources: vec![],
+                require: Requirement::RegistrySource,
+                allow: vec![],
+            }],
+            weights: None,
+            diff: DiffRules::default(),
+        };
+
+        assert_eq!(
+            find_policy_violations(&policy, &geiger_context, &package_depths),
+            vec![format!(
+                "{} at depth 1 breaks rule 1, it must be taken from a registry",
+                local_fork
+            )]
+        );
+    }
+
+    #[rstest(
+        input_require_review,
+        expected_invalid_exceptions,
+        case(
+            false,
+            vec![
+                "b in rule 1, review-date is not a YYYY-MM-DD date",
+                "d in acknowledge, review-date is not a YYYY-MM-DD date",
+            ]
+        ),
+        case(
+            true,
+            vec![
+                "a in rule 1, reviewed-by is missing, review-date is missing",
+                "b in rule 1, review-date is not a YYYY-MM-DD date",
+                "d in acknowledge, review-date is not a YYYY-MM-DD date",
+            ]
+        )
+    )]
+    fn find_invalid_exceptions_test(
+        input_require_review: bool,
+        expected_invalid_exceptions: Vec<&str>,
+    ) {
+        let policy = Policy {
+            require_review: input_require_review,
+            acknowledge: vec![create_exception(
+                "d",
+                Some("Jane Doe"),
+                Some("12/10/2020"),
+            )],
+            max_score: None,
+            rules: vec![PolicyRule {
+                direct: false,
+                min_depth: None,
+                max_depth: None,
+                sources: vec![],
+                require: Requirement::NoUnsafe,
+                allow: vec![
+                    create_exception("a", None, None),
+                    create_exception("b", Some("Jane Doe"), Some("01/10/2020")),
+                    create_exception("c", Some("Jane Doe"), Some("2020-10-01")),
+                ],
+            }],
+            weights: None,
+            diff: DiffRules::default(),
+        };
+
+        assert_eq!(
+            find_invalid_exceptions(&policy),
+            expected_invalid_exceptions
+        );
+    }
+
+    #[rstest(
+        input_date,
+        expected_valid,
+        case("2020-10-01", true),
+        case("2020-13-01", false),
+        case("2020-04-31", false),
+        case("2020-02-29", true),
+        case("2021-02-29", false),
+        case("2000-02-29", true),
+        case("1900-02-29", false),
+        case("2020-12-31", true),
+        case("2020-10-00", false),
+        case("2020-10-1", false),
+        case("20-10-01", false),
+        case("yesterday", false)
+    )]
+    fn is_valid_date_test(input_date: &str, expected_valid: bool) {
+        assert_eq!(is_valid_date(input_date), expected_valid);
+    }
+
+    fn create_exception(
+        name: &str,
+        reviewed_by: Option<&str>,
+        review_date: Option<&str>,
+    ) -> Exception {
+        Exception {
+            name: String::from(name),
+            reviewed_by: reviewed_by.map(String::from),
+            review_date: review_date.map(String::from),
+        }
+    }
+
+    fn create_package_metrics(
+        forbids_unsafe: bool,
+        has_unsafe: bool,
+    ) -> PackageMetrics {
+        let mut rs_file_metrics_wrapper = RsFileMetricsWrapper::default();
+        rs_file_metrics_wrapper.metrics.forbids_unsafe = forbids_unsafe;
+        if has_unsafe {
+            rs_file_metrics_wrapper.metrics.counters.exprs = Count {
+                safe: 0,
+                unsafe_: 1,
+            };
+        }
+        rs_file_metrics_wrapper.is_crate_entry_point = true;
+        let mut rs_path_to_metrics = HashMap::new();
+        rs_path_to_metrics
+            .insert(PathBuf::from("src/lib.rs"), rs_file_metrics_wrapper);
+        PackageMetrics {
+            rs_path_to_metrics,
+            ..Default::default()
+        }
+    }
+}
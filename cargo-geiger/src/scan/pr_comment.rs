@@ -0,0 +1,316 @@
+use crate::format::markdown::markdown_row;
+use crate::format::table::UNSAFE_COUNTERS_HEADER;
+use crate::graph::Graph;
+
+use super::github_output::unsafe_total;
+use super::{unsafe_stats, GeigerContext};
+
+use cargo::core::PackageId;
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// GitHub rejects comments with a body of more characters than this.
+const COMMENT_MAX_CHARS: usize = 65_536;
+
+/// The number of packages with the most unsafe usage listed in the comment.
+const TOP_OFFENDER_COUNT: usize = 10;
+
+/// The characters kept free for the note on left out lines.
+const TRUNCATION_NOTE_CHARS: usize = 64;
+
+/// The content of the pull request comment written with `--pr-comment`.
+#[derive(Debug, PartialEq)]
+pub struct PrComment {
+    /// The total number of unsafe items used by the build.
+    pub unsafe_total: u64,
+
+    /// The number of crates using unsafe code in the build.
+    pub crates_with_unsafe: u64,
+
+    /// The number of crates in the dependency graph.
+    pub crate_count: u64,
+
+    /// The packages using the most unsafe items, most unsafe first.
+    pub top_offenders: Vec<(PackageId, u64)>,
+
+    /// Why the scan failed its checks, it passed if there are none.
+    pub violations: Vec<String>,
+
+    /// The dependency tree with the unsafe counters, without colors.
+    pub tree_lines: Vec<String>,
+}
+
+impl PrComment {
+    pub fn new(
+        geiger_context: &GeigerContext,
+        graph: &Graph,
+        rs_files_used: &HashSet<PathBuf>,
+        violations: Vec<String>,
+        table_lines: &[String],
+    ) -> Self {
+        let mut package_unsafe_totals = graph
+            .nodes
+            .keys()
+            .filter_map(|package_id| {
+                geiger_context.package_id_to_metrics.get(package_id).map(
+                    |package_metrics| {
+                        (
+                            *package_id,
+                            unsafe_total(
+                                &unsafe_stats(package_metrics, rs_files_used)
+                                    .used,
+                            ),
+                        )
+                    },
+                )
+            })
+            .filter(|(_, package_unsafe_total)| *package_unsafe_total > 0)
+            .collect::<Vec<(PackageId, u64)>>();
+        package_unsafe_totals.sort_by(|(a_id, a_total), (b_id, b_total)| {
+            b_total.cmp(a_total).then(a_id.cmp(b_id))
+        });
+
+        PrComment {
+            unsafe_total: package_unsafe_totals
+                .iter()
+                .map(|(_, package_unsafe_total)| package_unsafe_total)
+                .sum(),
+            crates_with_unsafe: package_unsafe_totals.len() as u64,
+            crate_count: graph.nodes.len() as u64,
+            top_offenders: package_unsafe_totals
+                .into_iter()
+                .take(TOP_OFFENDER_COUNT)
+                .collect(),
+            violations,
+            tree_lines: table_lines
+                .iter()
+                .map(|line| console::strip_ansi_codes(line).into_owned())
+                .collect(),
+        }
+    }
+}
+
+/// Writes the Markdown body of the comment, ready to be posted, e.g. with
+/// `gh pr comment --body-file`.
+pub fn write_pr_comment(path: &Path, pr_comment: &PrComment) -> io::Result<()> {
+    fs::write(path, render_pr_comment(pr_comment, COMMENT_MAX_CHARS))
+}
+
+/// The summary, the top offenders and the verdict are always included. The
+/// violations may take up to half of the characters left, and the tree the
+/// rest, the lines that do not fit are left out with a note.
+fn render_pr_comment(pr_comment: &PrComment, max_chars: usize) -> String {
+    let mut head_lines = vec![
+        String::from("## cargo-geiger"),
+        String::new(),
+        if pr_comment.violations.is_empty() {
+            String::from("**Verdict: passed**")
+        } else {
+            format!(
+                "**Verdict: failed**, with {} violation(s)",
+                pr_comment.violations.len()
+            )
+        },
+        String::new(),
+        format!(
+            "{} unsafe item(s) used by the build, in {} of {} crate(s).",
+            pr_comment.unsafe_total,
+            pr_comment.crates_with_unsafe,
+            pr_comment.crate_count
+        ),
+        String::new(),
+    ];
+    if !pr_comment.top_offenders.is_empty() {
+        head_lines.push(String::from("### Top offenders"));
+        head_lines.push(String::new());
+        head_lines.push(markdown_row(&["Package", "Unsafe used"]));
+        head_lines.push(markdown_row(&["---", "---"]));
+        for (package_id, package_unsafe_total) in &pr_comment.top_offenders {
+            head_lines.push(markdown_row(&[
+                format!("{} {}", package_id.name(), package_id.version()),
+                package_unsafe_total.to_string(),
+            ]));
+        }
+        head_lines.push(String::new());
+    }
+    let violations_header_lines = if pr_comment.violations.is_empty() {
+        vec![]
+    } else {
+        vec![String::from("### Violations"), String::new()]
+    };
+    let tree_header_lines = vec![
+        String::from("<details>"),
+        String::from("<summary>Full dependency tree</summary>"),
+        String::new(),
+        String::from("```text"),
+        UNSAFE_COUNTERS_HEADER.join(" "),
+    ];
+    let tree_footer_lines = vec![
+        String::from("```"),
+        String::new(),
+        String::from("</details>"),
+    ];
+    let fixed_chars = line_chars(&head_lines)
+        + line_chars(&violations_header_lines)
+        + line_chars(&tree_header_lines)
+        + line_chars(&tree_footer_lines)
+        // The empty line after the violations.
+        + 1;
+    let budget = max_chars.saturating_sub(fixed_chars);
+
+    let violation_lines = pr_comment
+        .violations
+        .iter()
+        .map(|violation| format!("- {}", violation))
+        .collect::<Vec<String>>();
+    let violation_lines = fit_lines(&violation_lines, budget / 2);
+    let tree_lines = fit_lines(
+        &pr_comment.tree_lines,
+        budget.saturating_sub(line_chars(&violation_lines)),
+    );
+
+    let mut comment_lines = head_lines;
+    if !violation_lines.is_empty() {
+        comment_lines.extend(violations_header_lines);
+        comment_lines.extend(violation_lines);
+        comment_lines.push(String::new());
+    }
+    comment_lines.extend(tree_header_lines);
+    comment_lines.extend(tree_lines);
+    comment_lines.extend(tree_footer_lines);
+    let mut comment = comment_lines.join("\n");
+    comment.push('\n');
+    comment
+}
+
+/// The lines that fit in `budget` characters, counting a newline after each.
+/// When not all of them fit, a note on how many were left out is added.
+fn fit_lines(lines: &[String], budget: usize) -> Vec<String> {
+    if line_chars(lines) <= budget {
+        return lines.to_vec();
+    }
+    let mut fitted_lines = Vec::new();
+    let mut used_chars = TRUNCATION_NOTE_CHARS;
+    for line in lines {
+        used_chars += line.chars().count() + 1;
+        if used_chars > budget {
+            break;
+        }
+        fitted_lines.push(line.clone());
+    }
+    fitted_lines.push(format!(
+        "... {} more line(s), see the job log",
+        lines.len() - fitted_lines.len()
+    ));
+    fitted_lines
+}
+
+fn line_chars(lines: &[String]) -> usize {
+    lines.iter().map(|line| line.chars().count() + 1).sum()
+}
+
+#[cfg(test)]
+mod pr_comment_tests {
+    use super::*;
+
+    use crate::scan::test_utils::create_package_id;
+
+    use rstest::*;
+
+    #[rstest]
+    fn render_pr_comment_test() {
+        let pr_comment = PrComment {
+            unsafe_total: 12,
+            crates_with_unsafe: 1,
+            crate_count: 3,
+            top_offenders: vec![(create_package_id("libc"), 12)],
+            violations: vec![String::from("Policy violation: libc")],
+            tree_lines: vec![String::from("0/0 0/12 0/0 0/0 0/0 !  root")],
+        };
+
+        assert_eq!(
+            render_pr_comment(&pr_comment, COMMENT_MAX_CHARS),
+            [
+                "## cargo-geiger",
+                "",
+                "**Verdict: failed**, with 1 violation(s)",
+                "",
+                "12 unsafe item(s) used by the build, in 1 of 3 crate(s).",
+                "",
+                "### Top offenders",
+                "",
+                "| Package | Unsafe used |",
+                "| --- | --- |",
+                "| libc 1.2.3 | 12 |",
+                "",
+                "### Violations",
+                "",
+                "- Policy violation: libc",
+                "",
+                "<details>",
+                "<summary>Full dependency tree</summary>",
+                "",
+                "```text",
+                "Functions  Expressions  Impls  Traits  Methods  Dependency",
+                "0/0 0/12 0/0 0/0 0/0 !  root",
+                "```",
+                "",
+                "</details>",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[rstest]
+    fn render_pr_comment_truncated_test() {
+        let pr_comment = PrComment {
+            unsafe_total: 0,
+            crates_with_unsafe: 0,
+            crate_count: 1000,
+            top_offenders: vec![],
+            violations: vec![],
+            tree_lines: (0..1000)
+                .map(|index| format!("0/0 0/0 0/0 0/0 0/0 ?  crate_{}", index))
+                .collect(),
+        };
+
+        let comment = render_pr_comment(&pr_comment, 2000);
+
+        assert!(comment.chars().count() <= 2000);
+        assert!(comment.starts_with("## cargo-geiger\n\n**Verdict: passed**"));
+        assert!(comment
+            .ends_with("more line(s), see the job log\n```\n\n</details>\n"));
+    }
+
+    #[rstest(
+        input_budget,
+        expected_kept_line_count,
+        expected_note,
+        case(200, 3, None),
+        case(
+            TRUNCATION_NOTE_CHARS + 5,
+            2,
+            Some("... 1 more line(s), see the job log")
+        ),
+        case(0, 0, Some("... 3 more line(s), see the job log"))
+    )]
+    fn fit_lines_test(
+        input_budget: usize,
+        expected_kept_line_count: usize,
+        expected_note: Option<&str>,
+    ) {
+        // The last line does not fit next to the truncation note.
+        let lines = vec![
+            String::from("a"),
+            String::from("bb"),
+            "c".repeat(TRUNCATION_NOTE_CHARS),
+        ];
+        let mut expected_lines = lines[..expected_kept_line_count].to_vec();
+        expected_lines.extend(expected_note.map(String::from));
+
+        assert_eq!(fit_lines(&lines, input_budget), expected_lines);
+    }
+}
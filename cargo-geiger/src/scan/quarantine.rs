@@ -0,0 +1,140 @@
+use crate::args::Args;
+use crate::graph::Graph;
+
+use super::policy::{describe_review, load_policy, Exception};
+use super::render::read_report;
+use super::violation::Check;
+
+use cargo::core::PackageId;
+use cargo::CliError;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+
+/// Fails if the dependency graph has packages that are not in the report of
+/// an earlier scan, e.g. the last one recorded for the main branch, unless
+/// they are listed in `acknowledge` of the policy file. Packages are matched
+/// by name, a new version of a recorded package is not quarantined.
+pub fn check_quarantine(
+    graph: &Graph,
+    args: &Args,
+    report_path: &Path,
+) -> Result<Check, CliError> {
+    let report = read_report(report_path)?;
+    let recorded_names = report
+        .packages
+        .keys()
+        .chain(report.packages_without_metrics.iter())
+        .map(|package_id| package_id.name.as_str())
+        .collect::<HashSet<&str>>();
+    let acknowledge = match &args.policy {
+        Some(policy_path) => load_policy(policy_path)?.acknowledge,
+        None => Vec::new(),
+    };
+    let mut package_ids = graph.nodes.keys().copied().collect::<Vec<_>>();
+    package_ids.sort();
+
+    let (quarantined_packages, acknowledged_packages) =
+        find_new_packages(&package_ids, &recorded_names, &acknowledge);
+    for acknowledged_package in &acknowledged_packages {
+        eprintln!("Quarantine acknowledged: {}", acknowledged_package);
+    }
+    let violations = quarantined_packages
+        .iter()
+        .map(|quarantined_package| {
+            format!("Quarantined package: {}", quarantined_package)
+        })
+        .collect::<Vec<String>>();
+
+    let package_count = violations.len() as u64;
+    Ok(Check::new(violations, QuarantineError { package_count }))
+}
+
+#[derive(Debug)]
+struct QuarantineError {
+    package_count: u64,
+}
+
+impl Error for QuarantineError {}
+
+impl fmt::Display for QuarantineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} quarantined package(s)", self.package_count)
+    }
+}
+
+/// Describes the packages whose names are not recorded, the quarantined ones
+/// and the acknowledged ones with their review.
+fn find_new_packages(
+    package_ids: &[PackageId],
+    recorded_names: &HashSet<&str>,
+    acknowledge: &[Exception],
+) -> (Vec<String>, Vec<String>) {
+    let mut quarantined_packages = Vec::new();
+    let mut acknowledged_packages = Vec::new();
+    for package_id in package_ids {
+        let name = package_id.name();
+        if recorded_names.contains(name.as_str()) {
+            continue;
+        }
+        match acknowledge
+            .iter()
+            .find(|exception| exception.name == name.as_str())
+        {
+            Some(exception) => acknowledged_packages.push(format!(
+                "{} is new, {}",
+                package_id,
+                describe_review(exception)
+            )),
+            None => quarantined_packages.push(format!(
+                "{} is new, acknowledge it in the policy file to use it",
+                package_id
+            )),
+        }
+    }
+    (quarantined_packages, acknowledged_packages)
+}
+
+#[cfg(test)]
+mod quarantine_tests {
+    use super::*;
+
+    use crate::scan::test_utils::create_versioned_package_id;
+
+    use rstest::*;
+
+    #[rstest]
+    fn find_new_packages_test() {
+        let recorded = create_versioned_package_id("recorded", "1.1.0");
+        let acknowledged = create_versioned_package_id("acknowledged", "0.1.0");
+        let new = create_versioned_package_id("new", "2.0.0");
+        let recorded_names = vec!["recorded"].into_iter().collect();
+        let acknowledge = vec![Exception {
+            name: String::from("acknowledged"),
+            reviewed_by: Some(String::from("Jane Doe")),
+            review_date: Some(String::from("2020-10-12")),
+        }];
+
+        let (quarantined_packages, acknowledged_packages) = find_new_packages(
+            &[acknowledged, new, recorded],
+            &recorded_names,
+            &acknowledge,
+        );
+
+        assert_eq!(
+            quarantined_packages,
+            vec![format!(
+                "{} is new, acknowledge it in the policy file to use it",
+                new
+            )]
+        );
+        assert_eq!(
+            acknowledged_packages,
+            vec![format!(
+                "{} is new, reviewed by Jane Doe on 2020-10-12",
+                acknowledged
+            )]
+        );
+    }
+}
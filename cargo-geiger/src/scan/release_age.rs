@@ -0,0 +1,381 @@
+use crate::args::Args;
+
+use super::cache::cache_dir;
+use super::owners::is_crates_io;
+
+use cargo::ops::http_handle;
+use cargo::util::CargoResult;
+use cargo::Config;
+use cargo_geiger_serde::PackageId;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const CRATES_IO_API: &str = "https://crates.io/api/v1/crates";
+const RELEASES_ENTRY_PREFIX: &str = "releases-";
+
+/// The releases of a crate are cached for a week, like its owners, unless
+/// the resolved version is newer than the cached releases.
+const RELEASES_MAX_AGE: Duration = Duration::from_secs(7 * 86_400);
+
+/// Packages with unsafe code and no release for longer than this are listed
+/// below the tree as possibly unmaintained.
+const UNMAINTAINED_DAYS: u64 = 365;
+
+/// When the resolved version of a crates.io package was released, and how
+/// long ago the latest release of the crate was.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReleaseAge {
+    /// The release date of the resolved version, as `YYYY-MM-DD`.
+    pub release_date: Option<String>,
+
+    /// The number of days since any version of the crate was released.
+    pub days_since_last_release: u64,
+}
+
+/// The release age of every crates.io package among `package_ids`, for
+/// `--include-release-age`. The release dates are fetched from the crates.io
+/// API and cached in the metrics cache directory. Offline, or when crates.io
+/// cannot be reached, the cached release dates are used regardless of their
+/// age and packages without cached release dates are left out with a
+/// warning.
+pub fn collect_release_ages<'a>(
+    package_ids: impl Iterator<Item = &'a PackageId>,
+    args: &Args,
+    config: &Config,
+) -> HashMap<PackageId, ReleaseAge> {
+    let dir = cache_dir(args.cache_dir.as_deref(), config);
+    let today = days_since_epoch(args.scan_time());
+    let mut release_ages = HashMap::new();
+    for package_id in package_ids {
+        if !is_crates_io(package_id) {
+            continue;
+        }
+        let entry_path = releases_entry_path(&dir, &package_id.name);
+        let version = package_id.version.to_string();
+        let cached_releases = read_releases_entry(&entry_path);
+        let releases = match cached_releases {
+            Some((releases, modified))
+                if (is_fresh(modified) && releases.contains_key(&version))
+                    || config.offline() =>
+            {
+                Some(releases)
+            }
+            cached_releases => match fetch_releases(&package_id.name, config) {
+                Ok(releases) => {
+                    write_releases_entry(&entry_path, &releases);
+                    Some(releases)
+                }
+                Err(e) => {
+                    eprintln!(
+                        "WARNING: Failed to fetch the releases of {}: {}",
+                        package_id.name, e
+                    );
+                    cached_releases.map(|(releases, _)| releases)
+                }
+            },
+        };
+        if let Some(release_age) = releases
+            .and_then(|releases| find_release_age(&releases, &version, today))
+        {
+            release_ages.insert(package_id.clone(), release_age);
+        }
+    }
+    release_ages
+}
+
+/// A line per package with unsafe code whose crate had no release for over
+/// a year, below a header, or nothing when there are none.
+pub fn construct_unmaintained_lines(
+    release_ages: &HashMap<PackageId, ReleaseAge>,
+) -> Vec<String> {
+    let mut unmaintained = release_ages
+        .iter()
+        .filter(|(_, release_age)| {
+            release_age.days_since_last_release > UNMAINTAINED_DAYS
+        })
+        .collect::<Vec<(&PackageId, &ReleaseAge)>>();
+    if unmaintained.is_empty() {
+        return Vec::new();
+    }
+    unmaintained.sort_by(|(a_id, a_age), (b_id, b_age)| {
+        b_age
+            .days_since_last_release
+            .cmp(&a_age.days_since_last_release)
+            .then(a_id.cmp(b_id))
+    });
+    let mut lines = vec![String::from(
+        "Packages with unsafe code and no release for over a year:",
+    )];
+    for (package_id, release_age) in unmaintained {
+        lines.push(format!(
+            "    {} {}: released {}, last release {} days ago",
+            package_id.name,
+            package_id.version,
+            release_age
+                .release_date
+                .as_deref()
+                .unwrap_or("on an unknown date"),
+            release_age.days_since_last_release
+        ));
+    }
+    lines.push(String::new());
+    lines
+}
+
+#[derive(Deserialize, Serialize)]
+struct ReleasesEntry {
+    /// The release date of every version of the crate, as `YYYY-MM-DD`.
+    releases: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct CrateResponse {
+    versions: Vec<VersionResponse>,
+}
+
+#[derive(Deserialize)]
+struct VersionResponse {
+    num: String,
+    created_at: String,
+}
+
+fn fetch_releases(
+    crate_name: &str,
+    config: &Config,
+) -> CargoResult<BTreeMap<String, String>> {
+    if config.offline() {
+        anyhow::bail!("not cached, and the network is not used offline");
+    }
+    let mut handle = http_handle(config)?;
+    handle.get(true)?;
+    handle.url(&format!("{}/{}", CRATES_IO_API, crate_name))?;
+    let mut body = Vec::new();
+    {
+        let mut transfer = handle.transfer();
+        transfer.write_function(|data| {
+            body.extend_from_slice(data);
+            Ok(data.len())
+        })?;
+        transfer.perform()?;
+    }
+    let response_code = handle.response_code()?;
+    if response_code != 200 {
+        anyhow::bail!("crates.io responded with status {}", response_code);
+    }
+    let crate_response = serde_json::from_slice::<CrateResponse>(&body)?;
+    Ok(crate_response
+        .versions
+        .into_iter()
+        .map(|version| {
+            // The dates are RFC 3339 timestamps, only the day is kept.
+            let date = version.created_at.chars().take(10).collect();
+            (version.num, date)
+        })
+        .collect())
+}
+
+fn find_release_age(
+    releases: &BTreeMap<String, String>,
+    version: &str,
+    today: u64,
+) -> Option<ReleaseAge> {
+    let last_release = releases
+        .values()
+        .filter_map(|date| parse_date(date))
+        .max()?;
+    Some(ReleaseAge {
+        release_date: releases.get(version).cloned(),
+        days_since_last_release: today.saturating_sub(last_release),
+    })
+}
+
+/// Parses a `YYYY-MM-DD` date into the number of days since 1970-01-01.
+fn parse_date(date: &str) -> Option<u64> {
+    let parts = date
+        .split('-')
+        .map(|part| part.parse::<u64>().ok())
+        .collect::<Option<Vec<u64>>>()?;
+    let (year, month, day) = match parts.as_slice() {
+        [year, month, day]
+            if *year >= 1970
+                && (1..=12).contains(month)
+                && (1..=31).contains(day) =>
+        {
+            (*year, *month, *day)
+        }
+        _ => return None,
+    };
+    // The days from civil algorithm, with years starting in March so that
+    // the leap day is the last day of the year.
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let month_from_march = (month + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day - 1;
+    let day_of_era =
+        year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    Some(era * 146_097 + day_of_era - 719_468)
+}
+
+fn days_since_epoch(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs() / 86_400)
+}
+
+fn is_fresh(modified: SystemTime) -> bool {
+    SystemTime::now()
+        .duration_since(modified)
+        .map_or(true, |age| age <= RELEASES_MAX_AGE)
+}
+
+fn releases_entry_path(dir: &Path, crate_name: &str) -> PathBuf {
+    dir.join(format!("{}{}.json", RELEASES_ENTRY_PREFIX, crate_name))
+}
+
+fn read_releases_entry(
+    entry_path: &Path,
+) -> Option<(BTreeMap<String, String>, SystemTime)> {
+    let modified = fs::metadata(entry_path).ok()?.modified().ok()?;
+    let file = File::open(entry_path).ok()?;
+    let releases_entry =
+        serde_json::from_reader::<_, ReleasesEntry>(file).ok()?;
+    Some((releases_entry.releases, modified))
+}
+
+/// Failures are ignored, the releases are fetched again on the next run.
+fn write_releases_entry(
+    entry_path: &Path,
+    releases: &BTreeMap<String, String>,
+) {
+    if let Some(dir) = entry_path.parent() {
+        if fs::create_dir_all(dir).is_err() {
+            return;
+        }
+    }
+    if let Ok(file) = File::create(entry_path) {
+        let releases_entry = ReleasesEntry {
+            releases: releases.clone(),
+        };
+        let _ = serde_json::to_writer(file, &releases_entry);
+    }
+}
+
+#[cfg(test)]
+mod release_age_tests {
+    use super::*;
+
+    use cargo_geiger_serde::Source;
+    use rstest::*;
+    use semver::Version;
+    use url::Url;
+
+    #[rstest(
+        input_date,
+        expected_days,
+        case("1970-01-01", Some(0)),
+        case("2000-03-01", Some(11_017)),
+        case("2020-02-29", Some(18_321)),
+        case("2020-10-05", Some(18_540)),
+        case("2020-13-01", None),
+        case("2020-10", None),
+        case("not a date", None)
+    )]
+    fn parse_date_test(input_date: &str, expected_days: Option<u64>) {
+        assert_eq!(parse_date(input_date), expected_days);
+    }
+
+    #[rstest]
+    fn find_release_age_test() {
+        let releases = vec![("0.1.0", "2020-01-01"), ("0.2.0", "2020-10-05")]
+            .into_iter()
+            .map(|(version, date)| (String::from(version), String::from(date)))
+            .collect::<BTreeMap<String, String>>();
+
+        assert_eq!(
+            find_release_age(&releases, "0.1.0", 18_550),
+            Some(ReleaseAge {
+                release_date: Some(String::from("2020-01-01")),
+                days_since_last_release: 10,
+            })
+        );
+        assert_eq!(
+            find_release_age(&releases, "0.3.0", 18_550),
+            Some(ReleaseAge {
+                release_date: None,
+                days_since_last_release: 10,
+            })
+        );
+        assert_eq!(find_release_age(&BTreeMap::new(), "0.1.0", 18_550), None);
+    }
+
+    #[rstest]
+    fn construct_unmaintained_lines_test() {
+        let mut release_ages = HashMap::new();
+        release_ages.insert(
+            create_package_id("old"),
+            ReleaseAge {
+                release_date: Some(String::from("2018-01-01")),
+                days_since_last_release: 1000,
+            },
+        );
+        release_ages.insert(
+            create_package_id("fresh"),
+            ReleaseAge {
+                release_date: Some(String::from("2020-01-01")),
+                days_since_last_release: 10,
+            },
+        );
+
+        assert_eq!(
+            construct_unmaintained_lines(&release_ages),
+            vec![
+                String::from(
+                    "Packages with unsafe code and no release for over a \
+                     year:"
+                ),
+                String::from(
+                    "    old 1.2.3: released 2018-01-01, last release 1000 \
+                     days ago"
+                ),
+                String::new(),
+            ]
+        );
+        release_ages.remove(&create_package_id("old"));
+        assert!(construct_unmaintained_lines(&release_ages).is_empty());
+    }
+
+    #[rstest]
+    fn releases_entry_round_trip_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let entry_path = releases_entry_path(dir.path(), "libc");
+        let releases =
+            vec![(String::from("0.2.80"), String::from("2020-10-05"))]
+                .into_iter()
+                .collect::<BTreeMap<String, String>>();
+
+        assert!(read_releases_entry(&entry_path).is_none());
+        write_releases_entry(&entry_path, &releases);
+
+        let (read_releases, modified) =
+            read_releases_entry(&entry_path).unwrap();
+        assert_eq!(read_releases, releases);
+        assert!(is_fresh(modified));
+        assert!(!is_fresh(SystemTime::now() - 2 * RELEASES_MAX_AGE));
+    }
+
+    fn create_package_id(name: &str) -> PackageId {
+        PackageId {
+            name: String::from(name),
+            version: Version::new(1, 2, 3),
+            source: Source::Registry {
+                name: String::from("crates.io"),
+                url: Url::parse("https://github.com/rust-lang/crates.io-index")
+                    .unwrap(),
+            },
+        }
+    }
+}
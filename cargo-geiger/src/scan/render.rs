@@ -0,0 +1,368 @@
+use crate::args::Args;
+use crate::format::emoji_symbols::{EmojiSymbols, SymbolOverrides};
+use crate::format::html::{
+    render_diff_html_report, render_html_report, report_dependency_ids,
+    report_root_ids,
+};
+use crate::format::i18n::{tr, Message};
+use crate::format::markdown::render_markdown_report;
+use crate::format::numbers::NumberFormat;
+use crate::format::print_config::{colorize, OutputFormat, PrintConfig};
+use crate::format::security_insights::security_insights_from_report;
+use crate::format::table::{
+    table_footer, table_row, table_row_empty, UNSAFE_COUNTERS_HEADER,
+};
+use crate::format::treemap::treemap_from_report;
+use crate::format::{
+    package_name_markers, pad_to_width, CrateDetectionStatus, SymbolKind,
+};
+use crate::tree::construct_tree_vines_string;
+
+use super::baseline::read_baseline;
+
+use cargo::{CliError, CliResult, Config};
+use cargo_geiger_serde::{CounterBlock, PackageId, SafetyReport, UnsafeInfo};
+use colored::Colorize;
+use std::collections::HashSet;
+use std::fs::File;
+use std::path::Path;
+
+/// Renders the JSON report of a previous scan in another output format,
+/// without the sources or a build. The table is rebuilt from the
+/// dependencies recorded in the report.
+pub fn run_render(
+    report_path: &Path,
+    args: &Args,
+    config: &Config,
+) -> CliResult {
+    let report = read_report(report_path)?;
+    let print_config = PrintConfig {
+        numbers: NumberFormat::from_config(config)?,
+        symbols: SymbolOverrides::from_config(config)?,
+        ..PrintConfig::new(args)?
+    };
+    match print_config.output_format {
+        Some(OutputFormat::Html) => {
+            println!("{}", render_html_with_baseline(&report, args)?)
+        }
+        Some(OutputFormat::Json) => {
+            println!("{}", serde_json::to_string(&report).unwrap())
+        }
+        Some(OutputFormat::Markdown) => {
+            println!("{}", render_markdown_report(&report))
+        }
+        Some(OutputFormat::SecurityInsights) => println!(
+            "{}",
+            serde_json::to_string(&security_insights_from_report(&report))
+                .unwrap()
+        ),
+        Some(OutputFormat::Treemap) => println!(
+            "{}",
+            serde_json::to_string(&treemap_from_report(&report)).unwrap()
+        ),
+        None => {
+            for report_tree_line in
+                construct_report_tree_lines(&report, &print_config)
+            {
+                println!("{}", report_tree_line);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Renders the HTML report, marking the changes since the `--baseline`
+/// report if one is given, except those ignored by the `[diff]` rules of the
+/// `--policy` file.
+pub fn render_html_with_baseline(
+    report: &SafetyReport,
+    args: &Args,
+) -> Result<String, CliError> {
+    match &args.baseline {
+        Some(baseline_path) => Ok(render_diff_html_report(
+            report,
+            &read_baseline(baseline_path, args.policy.as_deref(), report)?,
+        )),
+        None => Ok(render_html_report(report)),
+    }
+}
+
+/// Reads a JSON report written by `cargo geiger --json`.
+pub fn read_report(report_path: &Path) -> Result<SafetyReport, CliError> {
+    File::open(report_path)
+        .map_err(anyhow::Error::from)
+        .and_then(|file| {
+            serde_json::from_reader::<_, SafetyReport>(file)
+                .map_err(anyhow::Error::from)
+        })
+        .map_err(|e| {
+            CliError::new(
+                e.context(format!(
+                    "Failed to read the report {}",
+                    report_path.display()
+                )),
+                1,
+            )
+        })
+}
+
+/// The table of the report, with a tree for every package no other package
+/// in the report depends on, and the total unsafe usage of the packages.
+fn construct_report_tree_lines(
+    report: &SafetyReport,
+    print_config: &PrintConfig,
+) -> Vec<String> {
+    let emoji_symbols =
+        EmojiSymbols::new(print_config.charset, &print_config.symbols);
+    let mut report_tree_lines = vec![
+        format!("{}", UNSAFE_COUNTERS_HEADER.join(" ").bold()),
+        String::new(),
+    ];
+    let mut visited_ids = HashSet::new();
+    for root_id in report_root_ids(report) {
+        walk_report_tree(
+            report,
+            root_id,
+            &mut Vec::new(),
+            &mut visited_ids,
+            &emoji_symbols,
+            print_config,
+            &mut report_tree_lines,
+        );
+    }
+
+    let mut total_used = CounterBlock::default();
+    let mut total_unused = CounterBlock::default();
+    for entry in report.packages.values() {
+        total_used += entry.unsafety.used.clone();
+        total_unused += entry.unsafety.unused.clone();
+    }
+    let statuses = report
+        .packages
+        .values()
+        .map(|entry| detection_status(&entry.unsafety))
+        .collect::<Vec<CrateDetectionStatus>>();
+    let total_detection_status = if statuses
+        .contains(&CrateDetectionStatus::ForbidMismatch)
+    {
+        CrateDetectionStatus::ForbidMismatch
+    } else if statuses.contains(&CrateDetectionStatus::UnsafeDetected) {
+        CrateDetectionStatus::UnsafeDetected
+    } else if !statuses.is_empty()
+        && !statuses.contains(&CrateDetectionStatus::NoneDetectedAllowsUnsafe)
+    {
+        CrateDetectionStatus::NoneDetectedForbidsUnsafe
+    } else {
+        CrateDetectionStatus::NoneDetectedAllowsUnsafe
+    };
+    report_tree_lines.push(String::new());
+    report_tree_lines.push(format!(
+        "{}",
+        table_footer(
+            total_used,
+            total_unused,
+            total_detection_status,
+            print_config.metrics_filter,
+            print_config.numbers
+        )
+    ));
+    report_tree_lines
+}
+
+fn walk_report_tree<'a>(
+    report: &'a SafetyReport,
+    package_id: &'a PackageId,
+    levels_continue: &mut Vec<bool>,
+    visited_ids: &mut HashSet<&'a PackageId>,
+    emoji_symbols: &EmojiSymbols,
+    print_config: &PrintConfig,
+    report_tree_lines: &mut Vec<String>,
+) {
+    let tree_vines = construct_tree_vines_string(
+        levels_continue,
+        &print_config.tree_options(),
+    );
+    let mut package_name =
+        format!("{} {}", package_id.name, package_id.version);
+    let entry = match report.packages.get(package_id) {
+        Some(entry) => entry,
+        None => {
+            report_tree_lines.push(format!(
+                "{}{}{}",
+                table_row_empty(),
+                tree_vines,
+                package_name
+            ));
+            return;
+        }
+    };
+    package_name.push_str(&package_name_markers(
+        print_config.lang,
+        entry.package.patched,
+        entry.package.local_override,
+        entry.package.yanked,
+        None,
+    ));
+    if entry.unsafety.not_fully_scanned {
+        package_name.push(' ');
+        package_name.push_str(tr(print_config.lang, Message::NotFullyScanned));
+    }
+    let status = detection_status(&entry.unsafety);
+    let icon = match status {
+        CrateDetectionStatus::NoneDetectedForbidsUnsafe => {
+            emoji_symbols.emoji(SymbolKind::Lock)
+        }
+        CrateDetectionStatus::NoneDetectedAllowsUnsafe => {
+            emoji_symbols.emoji(SymbolKind::QuestionMark)
+        }
+        CrateDetectionStatus::UnsafeDetected => {
+            emoji_symbols.emoji(SymbolKind::Rads)
+        }
+        CrateDetectionStatus::ForbidMismatch => {
+            emoji_symbols.emoji(SymbolKind::Warning)
+        }
+    };
+    report_tree_lines.push(format!(
+        "{}  {} {}{}",
+        colorize(
+            table_row(
+                &entry.unsafety.used,
+                &entry.unsafety.unused,
+                print_config.metrics_filter,
+                print_config.numbers
+            ),
+            &status
+        ),
+        pad_to_width(&icon.to_string(), 2),
+        tree_vines,
+        colorize(package_name, &status)
+    ));
+    if !visited_ids.insert(package_id) && !print_config.all {
+        return;
+    }
+    let dependency_ids = report_dependency_ids(report, package_id);
+    for (index, dependency_id) in dependency_ids.iter().enumerate() {
+        levels_continue.push(index + 1 < dependency_ids.len());
+        walk_report_tree(
+            report,
+            dependency_id,
+            levels_continue,
+            visited_ids,
+            emoji_symbols,
+            print_config,
+            report_tree_lines,
+        );
+        levels_continue.pop();
+    }
+}
+
+/// The normal, build and dev dependencies of a package, in that order.
+fn detection_status(unsafety: &UnsafeInfo) -> CrateDetectionStatus {
+    if unsafety.forbid_mismatch {
+        CrateDetectionStatus::ForbidMismatch
+    } else if unsafety.used.has_unsafe() {
+        CrateDetectionStatus::UnsafeDetected
+    } else if unsafety.forbids_unsafe {
+        CrateDetectionStatus::NoneDetectedForbidsUnsafe
+    } else {
+        CrateDetectionStatus::NoneDetectedAllowsUnsafe
+    }
+}
+
+#[cfg(test)]
+mod render_tests {
+    use super::*;
+
+    use crate::format::Charset;
+
+    use cargo_geiger_serde::{
+        DependencyKind, PackageInfo, ReportEntry, Source,
+    };
+    use rstest::*;
+    use semver::Version;
+    use std::fs;
+    use url::Url;
+
+    #[rstest]
+    fn construct_report_tree_lines_test() {
+        let root = package_id("root");
+        let lib = package_id("lib");
+        let shared = package_id("shared");
+        let missing = package_id("missing");
+        let mut report = SafetyReport::default();
+        for (package_id, dependencies, unsafe_exprs) in &[
+            (&root, vec![&lib, &shared, &missing], 0),
+            (&lib, vec![&shared], 2),
+            (&shared, vec![], 0),
+        ] {
+            let mut package = PackageInfo::new((*package_id).clone());
+            for dependency in dependencies {
+                package.add_dependency(
+                    (*dependency).clone(),
+                    DependencyKind::Normal,
+                );
+            }
+            let mut unsafety = UnsafeInfo::default();
+            unsafety.used.exprs.unsafe_ = *unsafe_exprs;
+            unsafety.forbids_unsafe = *package_id == &shared;
+            report
+                .packages
+                .insert(package.id.clone(), ReportEntry { package, unsafety });
+        }
+        report.packages_without_metrics.insert(missing);
+        let print_config = PrintConfig {
+            charset: Charset::Ascii,
+            ..PrintConfig::new(
+                &Args::parse_args(pico_args::Arguments::from_vec(vec![]))
+                    .unwrap(),
+            )
+            .unwrap()
+        };
+        colored::control::set_override(false);
+
+        let report_tree_lines =
+            construct_report_tree_lines(&report, &print_config);
+
+        assert_eq!(
+            report_tree_lines[2..],
+            [
+                "0/0        0/0          0/0    0/0     0/0      ?  root 1.0.0",
+                "0/0        2/2          0/0    0/0     0/0      !  |-- lib \
+                 1.0.0",
+                "0/0        0/0          0/0    0/0     0/0      :) |   `-- \
+                 shared 1.0.0",
+                "                                                   |-- \
+                 missing 1.0.0",
+                "0/0        0/0          0/0    0/0     0/0      :) `-- \
+                 shared 1.0.0",
+                "",
+                "0/0        2/2          0/0    0/0     0/0    ",
+            ]
+        );
+    }
+
+    #[rstest]
+    fn read_report_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let report_path = dir.path().join("geiger-report.json");
+        let mut report = SafetyReport::default();
+        report.packages_without_metrics.insert(package_id("root"));
+        fs::write(&report_path, serde_json::to_string(&report).unwrap())
+            .unwrap();
+
+        assert_eq!(read_report(&report_path).unwrap(), report);
+        assert!(read_report(&dir.path().join("missing.json")).is_err());
+    }
+
+    fn package_id(name: &str) -> PackageId {
+        PackageId {
+            name: String::from(name),
+            version: Version::new(1, 0, 0),
+            source: Source::Registry {
+                name: String::from("crates.io"),
+                url: Url::parse("https://github.com/rust-lang/crates.io-index")
+                    .unwrap(),
+            },
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use crate::graph::Graph;
+
+use super::violation::Check;
+use super::{package_forbids_unsafe, GeigerContext, RequireForbidScope};
+
+use cargo::core::PackageId;
+use petgraph::EdgeDirection;
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fmt;
+
+/// Fails if any dependency in the scope does not declare
+/// `#![forbid(unsafe_code)]` in all of its entry points, listing the
+/// offenders.
+pub fn check_require_forbid(
+    geiger_context: &GeigerContext,
+    root_package_ids: &[PackageId],
+    graph: &Graph,
+    scope: RequireForbidScope,
+) -> Check {
+    let offenders = find_dependencies_missing_forbid(
+        geiger_context,
+        graph,
+        root_package_ids,
+        scope,
+    );
+    let violations = offenders
+        .iter()
+        .map(|offender| {
+            format!(
+                "Dependency does not declare #![forbid(unsafe_code)]: {}",
+                offender
+            )
+        })
+        .collect::<Vec<String>>();
+
+    let offender_count = violations.len() as u64;
+    Check::new(violations, MissingForbidError { offender_count })
+}
+
+#[derive(Debug)]
+struct MissingForbidError {
+    offender_count: u64,
+}
+
+impl Error for MissingForbidError {}
+
+impl fmt::Display for MissingForbidError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} package(s) do not forbid unsafe code",
+            self.offender_count
+        )
+    }
+}
+
+/// Packages without metrics are counted as offenders, since it cannot be
+/// verified that they forbid unsafe code. The roots themselves are never
+/// offenders, also when one workspace member depends on another.
+fn find_dependencies_missing_forbid(
+    geiger_context: &GeigerContext,
+    graph: &Graph,
+    root_package_ids: &[PackageId],
+    scope: RequireForbidScope,
+) -> BTreeSet<PackageId> {
+    let dependency_ids = match scope {
+        RequireForbidScope::Direct => root_package_ids
+            .iter()
+            .flat_map(|root_package_id| {
+                graph.graph.neighbors_directed(
+                    graph.nodes[root_package_id],
+                    EdgeDirection::Outgoing,
+                )
+            })
+            .map(|index| graph.graph[index].id)
+            .collect::<BTreeSet<PackageId>>(),
+        RequireForbidScope::All => graph.nodes.keys().cloned().collect(),
+    };
+
+    dependency_ids
+        .into_iter()
+        .filter(|package_id| !root_package_ids.contains(package_id))
+        .filter(|package_id| {
+            match geiger_context.package_id_to_metrics.get(package_id) {
+                Some(package_metrics) => {
+                    !package_forbids_unsafe(package_metrics)
+                }
+                None => true,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod require_forbid_tests {
+    use super::*;
+
+    use crate::graph::Node;
+    use crate::rs_file::RsFileMetricsWrapper;
+    use crate::scan::test_utils::create_package_id;
+    use crate::scan::PackageMetrics;
+
+    use cargo::core::dependency::DepKind;
+    use rstest::*;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    #[rstest(
+        input_scope,
+        expected_offender_names,
+        case(RequireForbidScope::Direct, vec!["direct_unsafe"]),
+        case(
+            RequireForbidScope::All,
+            vec!["direct_unsafe", "transitive_unknown", "transitive_unsafe"]
+        )
+    )]
+    fn find_dependencies_missing_forbid_test(
+        input_scope: RequireForbidScope,
+        expected_offender_names: Vec<&str>,
+    ) {
+        let root = create_package_id("root");
+        let direct_forbids = create_package_id("direct_forbids");
+        let direct_unsafe = create_package_id("direct_unsafe");
+        let transitive_unsafe = create_package_id("transitive_unsafe");
+        let transitive_unknown = create_package_id("transitive_unknown");
+
+        let mut graph = Graph {
+            graph: petgraph::Graph::new(),
+            nodes: HashMap::new(),
+            edge_features: HashMap::new(),
+        };
+        for package_id in &[
+            root,
+            direct_forbids,
+            direct_unsafe,
+            transitive_unsafe,
+            transitive_unknown,
+        ] {
+            let index = graph.graph.add_node(Node {
+                id: *package_id,
+                features: vec![],
+                patched: false,
+                local_override: false,
+                yanked: false,
+            });
+            graph.nodes.insert(*package_id, index);
+        }
+        for (from, to) in &[
+            (root, direct_forbids),
+            (root, direct_unsafe),
+            (direct_forbids, transitive_unsafe),
+            (direct_unsafe, transitive_unknown),
+        ] {
+            graph.graph.add_edge(
+                graph.nodes[from],
+                graph.nodes[to],
+                DepKind::Normal,
+            );
+        }
+
+        let mut package_id_to_metrics = HashMap::new();
+        package_id_to_metrics.insert(root, create_package_metrics(false));
+        package_id_to_metrics
+            .insert(direct_forbids, create_package_metrics(true));
+        package_id_to_metrics
+            .insert(direct_unsafe, create_package_metrics(false));
+        package_id_to_metrics
+            .insert(transitive_unsafe, create_package_metrics(false));
+        let geiger_context = GeigerContext {
+            package_id_to_metrics,
+            used_but_not_scanned: None,
+            warnings: vec![],
+        };
+
+        let offender_names = find_dependencies_missing_forbid(
+            &geiger_context,
+            &graph,
+            &[root],
+            input_scope,
+        )
+        .into_iter()
+        .map(|package_id| package_id.name().to_string())
+        .collect::<Vec<String>>();
+
+        assert_eq!(offender_names, expected_offender_names);
+    }
+
+    fn create_package_metrics(forbids_unsafe: bool) -> PackageMetrics {
+        let mut rs_file_metrics_wrapper = RsFileMetricsWrapper::default();
+        rs_file_metrics_wrapper.metrics.forbids_unsafe = forbids_unsafe;
+        rs_file_metrics_wrapper.is_crate_entry_point = true;
+        let mut rs_path_to_metrics = HashMap::new();
+        rs_path_to_metrics
+            .insert(PathBuf::from("src/lib.rs"), rs_file_metrics_wrapper);
+        PackageMetrics {
+            rs_path_to_metrics,
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use crate::graph::Graph;
+
+use super::metadata::rust_version_from_manifest;
+
+use cargo::core::{PackageId, PackageSet, Workspace};
+use cargo::util::CargoResult;
+use cargo::Config;
+use std::collections::BTreeMap;
+use std::fs;
+
+/// A Rust version as `(major, minor, patch)`.
+type RustVersion = (u64, u64, u64);
+
+/// The language changes that affect what is unsafe and how it is written, by
+/// the Rust version that introduced them.
+const UNSAFE_SEMANTICS_CHANGES: [(RustVersion, &str); 3] = [
+    (
+        (1, 52, 0),
+        "the unsafe_op_in_unsafe_fn lint, unsafe blocks in unsafe fn bodies",
+    ),
+    ((1, 82, 0), "unsafe attributes and unsafe extern blocks"),
+    (
+        (1, 85, 0),
+        "edition 2024, where unsafe operations in unsafe fn bodies are linted \
+         and #[no_mangle] is written #[unsafe(no_mangle)]",
+    ),
+];
+
+/// Notes on the packages in the graph whose `rust-version` is newer than the
+/// toolchain of the scan. The build of such a package may fail or leave out
+/// files, and the unsafe-relevant language changes the toolchain predates
+/// are listed, since their code may not be counted as intended.
+pub fn find_rust_version_notes(
+    config: &Config,
+    workspace: &Workspace,
+    package_set: &PackageSet,
+    graph: &Graph,
+) -> CargoResult<BTreeMap<PackageId, Vec<String>>> {
+    let rustc_version = &config.load_global_rustc(Some(workspace))?.version;
+    let toolchain_version = (
+        rustc_version.major,
+        rustc_version.minor,
+        rustc_version.patch,
+    );
+    let mut rust_version_notes = BTreeMap::new();
+    for node in graph.graph.raw_nodes().iter().map(|node| &node.weight) {
+        let package = package_set.get_one(node.id)?;
+        let rust_version = match fs::read_to_string(package.manifest_path())
+            .ok()
+            .and_then(|manifest| rust_version_from_manifest(&manifest))
+        {
+            Some(rust_version) => rust_version,
+            None => continue,
+        };
+        let notes =
+            construct_rust_version_notes(&rust_version, toolchain_version);
+        if !notes.is_empty() {
+            rust_version_notes.insert(node.id, notes);
+        }
+    }
+    Ok(rust_version_notes)
+}
+
+fn construct_rust_version_notes(
+    rust_version: &str,
+    toolchain_version: RustVersion,
+) -> Vec<String> {
+    let package_version = match parse_rust_version(rust_version) {
+        Some(package_version) => package_version,
+        None => return Vec::new(),
+    };
+    if package_version <= toolchain_version {
+        return Vec::new();
+    }
+    let mut notes = vec![format!(
+        "requires Rust {}, newer than the scanning toolchain {}, the used \
+         counts may be incomplete",
+        rust_version,
+        format_rust_version(toolchain_version)
+    )];
+    for (change_version, change) in &UNSAFE_SEMANTICS_CHANGES {
+        if toolchain_version < *change_version
+            && *change_version <= package_version
+        {
+            notes.push(format!(
+                "may use {} from Rust {}, which the scanning toolchain \
+                 predates",
+                change,
+                format_rust_version(*change_version)
+            ));
+        }
+    }
+    notes
+}
+
+/// Parses a `rust-version` like `1.56` or `1.56.1`, a missing patch version
+/// is 0.
+fn parse_rust_version(rust_version: &str) -> Option<RustVersion> {
+    let parts = rust_version
+        .split('.')
+        .map(|part| part.parse::<u64>().ok())
+        .collect::<Option<Vec<u64>>>()?;
+    match parts.as_slice() {
+        [major, minor] => Some((*major, *minor, 0)),
+        [major, minor, patch] => Some((*major, *minor, *patch)),
+        _ => None,
+    }
+}
+
+fn format_rust_version((major, minor, patch): RustVersion) -> String {
+    format!("{}.{}.{}", major, minor, patch)
+}
+
+#[cfg(test)]
+mod rust_version_tests {
+    use super::*;
+
+    use rstest::*;
+
+    #[rstest(
+        input_rust_version,
+        input_toolchain_version,
+        expected_notes,
+        case("1.40", (1, 47, 0), vec![]),
+        case("1.47.0", (1, 47, 0), vec![]),
+        case("not a version", (1, 47, 0), vec![]),
+        case(
+            "1.50",
+            (1, 47, 0),
+            vec![
+                "requires Rust 1.50, newer than the scanning toolchain \
+                 1.47.0, the used counts may be incomplete"
+            ]
+        ),
+        case(
+            "1.82",
+            (1, 60, 0),
+            vec![
+                "requires Rust 1.82, newer than the scanning toolchain \
+                 1.60.0, the used counts may be incomplete",
+                "may use unsafe attributes and unsafe extern blocks from \
+                 Rust 1.82.0, which the scanning toolchain predates"
+            ]
+        )
+    )]
+    fn construct_rust_version_notes_test(
+        input_rust_version: &str,
+        input_toolchain_version: RustVersion,
+        expected_notes: Vec<&str>,
+    ) {
+        assert_eq!(
+            construct_rust_version_notes(
+                input_rust_version,
+                input_toolchain_version
+            ),
+            expected_notes
+        );
+    }
+
+    #[rstest(
+        input_rust_version,
+        expected_version,
+        case("1.56", Some((1, 56, 0))),
+        case("1.56.1", Some((1, 56, 1))),
+        case("1", None),
+        case("1.56.1.0", None),
+        case("1.x", None)
+    )]
+    fn parse_rust_version_test(
+        input_rust_version: &str,
+        expected_version: Option<RustVersion>,
+    ) {
+        assert_eq!(parse_rust_version(input_rust_version), expected_version);
+    }
+}
@@ -0,0 +1,266 @@
+use crate::format::table::{unsafe_counters_header, unsafe_counters_row};
+use crate::graph::Graph;
+
+use super::{unsafe_stats, GeigerContext};
+
+use cargo::core::dependency::DepKind;
+use cargo::core::{PackageId, PackageSet};
+use cargo::util::CargoResult;
+use cargo_geiger_serde::CounterBlock;
+use petgraph::visit::EdgeRef;
+use petgraph::EdgeDirection;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+
+/// When the code of a package is run, the unsafe code of each stage feeds a
+/// different threat model.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum ExecutionStage {
+    /// Ships in the product, reachable through normal dependencies.
+    Runtime,
+    /// Only run while building, in build dependencies and procedural macros
+    /// and their dependencies.
+    BuildTime,
+    /// Only reachable through dev-dependencies, for tests and examples.
+    Development,
+}
+
+/// The packages of an execution stage and the unsafe usage in their code
+/// used by the build.
+#[derive(Debug, Default, PartialEq)]
+pub struct StageTotal {
+    pub packages: usize,
+    pub used: CounterBlock,
+}
+
+/// Sums the unsafe usage used by the build per execution stage of the
+/// packages in the graph.
+pub fn find_execution_stage_totals(
+    geiger_context: &GeigerContext,
+    graph: &Graph,
+    root_package_ids: &[PackageId],
+    package_set: &PackageSet,
+    rs_files_used: &HashSet<PathBuf>,
+) -> CargoResult<BTreeMap<ExecutionStage, StageTotal>> {
+    let mut proc_macros = HashSet::new();
+    for node in graph.graph.raw_nodes().iter().map(|node| &node.weight) {
+        if package_set.get_one(node.id)?.proc_macro() {
+            proc_macros.insert(node.id);
+        }
+    }
+    let stages = classify_execution_stages(graph, root_package_ids, |id| {
+        proc_macros.contains(&id)
+    });
+    let mut stage_totals = BTreeMap::<ExecutionStage, StageTotal>::new();
+    for (package_id, stage) in stages {
+        let stage_total = stage_totals.entry(stage).or_default();
+        stage_total.packages += 1;
+        if let Some(package_metrics) =
+            geiger_context.package_id_to_metrics.get(&package_id)
+        {
+            stage_total.used +=
+                unsafe_stats(package_metrics, rs_files_used).used;
+        }
+    }
+    Ok(stage_totals)
+}
+
+/// The execution stage of each package reachable from the roots. A package
+/// used both at runtime and while building ships in the product and counts
+/// as runtime.
+fn classify_execution_stages<F>(
+    graph: &Graph,
+    root_package_ids: &[PackageId],
+    is_proc_macro: F,
+) -> HashMap<PackageId, ExecutionStage>
+where
+    F: Fn(PackageId) -> bool,
+{
+    let runtime = reachable_through(graph, root_package_ids, |kind, id| {
+        kind == DepKind::Normal && !is_proc_macro(id)
+    });
+    let built = reachable_through(graph, root_package_ids, |kind, _| {
+        kind != DepKind::Development
+    });
+    let all = reachable_through(graph, root_package_ids, |_, _| true);
+    all.into_iter()
+        .map(|package_id| {
+            let stage = if runtime.contains(&package_id) {
+                ExecutionStage::Runtime
+            } else if built.contains(&package_id) {
+                ExecutionStage::BuildTime
+            } else {
+                ExecutionStage::Development
+            };
+            (package_id, stage)
+        })
+        .collect()
+}
+
+/// The packages reached from the roots through the dependencies for which
+/// `follow` is true.
+pub(super) fn reachable_through<F>(
+    graph: &Graph,
+    root_package_ids: &[PackageId],
+    follow: F,
+) -> HashSet<PackageId>
+where
+    F: Fn(DepKind, PackageId) -> bool,
+{
+    let mut reachable =
+        root_package_ids.iter().copied().collect::<HashSet<_>>();
+    let mut pending = root_package_ids
+        .iter()
+        .map(|root_package_id| graph.nodes[root_package_id])
+        .collect::<Vec<_>>();
+    while let Some(index) = pending.pop() {
+        for edge in graph.graph.edges_directed(index, EdgeDirection::Outgoing) {
+            let dependency_id = graph.graph[edge.target()].id;
+            if follow(*edge.weight(), dependency_id)
+                && reachable.insert(dependency_id)
+            {
+                pending.push(edge.target());
+            }
+        }
+    }
+    reachable
+}
+
+/// The unsafe usage per execution stage, below the totals of the table. Left
+/// out when nothing in the graph is only run while building, the totals are
+/// then all runtime.
+pub fn construct_execution_stage_lines(
+    stage_totals: &BTreeMap<ExecutionStage, StageTotal>,
+) -> Vec<String> {
+    if !stage_totals.contains_key(&ExecutionStage::BuildTime) {
+        return Vec::new();
+    }
+    let mut stage_lines = vec![
+        String::from("Unsafe usage used by the build per execution stage:"),
+        String::new(),
+        unsafe_counters_header("Stage"),
+    ];
+    for (stage, stage_total) in stage_totals {
+        let stage_name = match stage {
+            ExecutionStage::Runtime => "Runtime, shipped in the product",
+            ExecutionStage::BuildTime => {
+                "Build time, build dependencies and procedural macros"
+            }
+            ExecutionStage::Development => "Development, dev-dependencies",
+        };
+        stage_lines.push(format!(
+            "{}  {}, {} package(s)",
+            unsafe_counters_row(&stage_total.used),
+            stage_name,
+            stage_total.packages
+        ));
+    }
+    stage_lines.push(String::new());
+    stage_lines
+}
+
+#[cfg(test)]
+mod stage_tests {
+    use super::*;
+
+    use crate::graph::Node;
+    use crate::scan::test_utils::create_package_id;
+
+    use cargo_geiger_serde::Count;
+    use rstest::*;
+
+    #[rstest]
+    fn classify_execution_stages_test() {
+        let root = create_package_id("root");
+        let lib = create_package_id("lib");
+        let shared = create_package_id("shared");
+        let build = create_package_id("build");
+        let derive = create_package_id("derive");
+        let derive_dep = create_package_id("derive_dep");
+        let test_util = create_package_id("test_util");
+
+        let mut graph = Graph {
+            graph: petgraph::Graph::new(),
+            nodes: HashMap::new(),
+            edge_features: HashMap::new(),
+        };
+        for package_id in
+            &[root, lib, shared, build, derive, derive_dep, test_util]
+        {
+            let index = graph.graph.add_node(Node {
+                id: *package_id,
+                features: vec![],
+                patched: false,
+                local_override: false,
+                yanked: false,
+            });
+            graph.nodes.insert(*package_id, index);
+        }
+        for (from, to, kind) in &[
+            (root, lib, DepKind::Normal),
+            (root, build, DepKind::Build),
+            (root, derive, DepKind::Normal),
+            (root, test_util, DepKind::Development),
+            (lib, shared, DepKind::Normal),
+            (build, shared, DepKind::Normal),
+            (derive, derive_dep, DepKind::Normal),
+        ] {
+            graph
+                .graph
+                .add_edge(graph.nodes[from], graph.nodes[to], *kind);
+        }
+
+        let stages =
+            classify_execution_stages(&graph, &[root], |id| id == derive);
+
+        let mut expected_stages = HashMap::new();
+        expected_stages.insert(root, ExecutionStage::Runtime);
+        expected_stages.insert(lib, ExecutionStage::Runtime);
+        expected_stages.insert(shared, ExecutionStage::Runtime);
+        expected_stages.insert(build, ExecutionStage::BuildTime);
+        expected_stages.insert(derive, ExecutionStage::BuildTime);
+        expected_stages.insert(derive_dep, ExecutionStage::BuildTime);
+        expected_stages.insert(test_util, ExecutionStage::Development);
+        assert_eq!(stages, expected_stages);
+    }
+
+    #[rstest]
+    fn construct_execution_stage_lines_test() {
+        let mut stage_totals = BTreeMap::new();
+        stage_totals.insert(
+            ExecutionStage::Runtime,
+            StageTotal {
+                packages: 3,
+                used: CounterBlock {
+                    exprs: Count {
+                        safe: 10,
+                        unsafe_: 4,
+                    },
+                    ..Default::default()
+                },
+            },
+        );
+
+        assert!(construct_execution_stage_lines(&stage_totals).is_empty());
+
+        stage_totals.insert(
+            ExecutionStage::BuildTime,
+            StageTotal {
+                packages: 2,
+                ..Default::default()
+            },
+        );
+        let stage_lines = construct_execution_stage_lines(&stage_totals);
+
+        assert_eq!(stage_lines.len(), 6);
+        assert_eq!(
+            stage_lines[3],
+            "0          4            0      0       0        Runtime, shipped \
+             in the product, 3 package(s)"
+        );
+        assert!(stage_lines[4].ends_with(
+            "Build time, build dependencies and procedural macros, 2 \
+             package(s)"
+        ));
+    }
+}
@@ -0,0 +1,466 @@
+use crate::format::treemap::unsafe_count;
+use crate::graph::{reachable_package_ids, Graph};
+
+use super::GeigerContext;
+
+use cargo::core::{FeatureValue, Package, PackageId, PackageSet};
+use cargo::{CliError, CliResult};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// What enabling a feature of the root package turns on.
+#[derive(Debug, Default, PartialEq)]
+struct FeatureEffects {
+    /// The other features of the root package it enables.
+    features: Vec<String>,
+    /// The package names of the optional dependencies it enables.
+    dependencies: Vec<String>,
+}
+
+/// A feature to disable, with everything that goes away with it.
+#[derive(Debug, PartialEq)]
+struct FeatureSuggestion {
+    feature: String,
+    /// The enabled features that require the feature, and have to be
+    /// disabled with it.
+    also_disabled: Vec<String>,
+    dropped_dependencies: Vec<String>,
+    dropped_packages: Vec<PackageId>,
+    unsafe_count: u64,
+}
+
+/// Suggests the features of the root package to disable, one after the
+/// other, to remove the most unsafe usage from the dependency graph while
+/// disabling as few features as possible. The search is greedy, it picks the
+/// feature with the most unsafe usage removed per disabled feature until no
+/// feature removes any. Only the optional dependencies of the root package
+/// are followed, not the features it enables in its dependencies.
+pub fn scan_suggest_features(
+    geiger_context: &GeigerContext,
+    package_set: &PackageSet,
+    root_package_id: PackageId,
+    graph: &Graph,
+) -> CliResult {
+    let root_package = package_set
+        .get_one(root_package_id)
+        .map_err(|e| CliError::new(e, 1))?;
+    let feature_effects = root_feature_effects(root_package);
+    let enabled_features = graph.graph[graph.nodes[&root_package_id]]
+        .features
+        .iter()
+        .cloned()
+        .collect::<BTreeSet<String>>();
+    let suggestions = suggest_features(
+        root_package_id,
+        graph,
+        &feature_effects,
+        &enabled_features,
+        &package_unsafe_counts(geiger_context),
+    );
+
+    for suggestion in &suggestions {
+        println!("{}", format_suggestion(suggestion));
+    }
+    if suggestions.is_empty() {
+        println!(
+            "No enabled feature of {} can be disabled to remove unsafe usage.",
+            root_package_id
+        );
+    }
+    Ok(())
+}
+
+/// The effects of the features declared in the manifest of the root package,
+/// and of the implicit features of its optional dependencies.
+fn root_feature_effects(package: &Package) -> BTreeMap<String, FeatureEffects> {
+    let optional_dependencies = package
+        .dependencies()
+        .iter()
+        .filter(|dependency| dependency.is_optional())
+        .map(|dependency| {
+            (
+                dependency.name_in_toml().to_string(),
+                dependency.package_name().to_string(),
+            )
+        })
+        .collect::<HashMap<String, String>>();
+    let mut feature_effects = optional_dependencies
+        .iter()
+        .map(|(name_in_toml, package_name)| {
+            (
+                name_in_toml.clone(),
+                FeatureEffects {
+                    features: vec![],
+                    dependencies: vec![package_name.clone()],
+                },
+            )
+        })
+        .collect::<BTreeMap<String, FeatureEffects>>();
+    for (feature, feature_values) in package.summary().features() {
+        let mut effects = FeatureEffects::default();
+        for feature_value in feature_values {
+            let dependency_name = match feature_value {
+                FeatureValue::Feature(name) => {
+                    effects.features.push(name.to_string());
+                    continue;
+                }
+                FeatureValue::Crate(name)
+                | FeatureValue::CrateFeature(name, _) => name,
+            };
+            if let Some(package_name) =
+                optional_dependencies.get(dependency_name.as_str())
+            {
+                effects.dependencies.push(package_name.clone());
+            }
+        }
+        feature_effects.insert(feature.to_string(), effects);
+    }
+    feature_effects
+}
+
+/// The unsafe usage in all files of every scanned package, used by the build
+/// or not.
+fn package_unsafe_counts(
+    geiger_context: &GeigerContext,
+) -> HashMap<PackageId, u64> {
+    geiger_context
+        .package_id_to_metrics
+        .iter()
+        .map(|(package_id, package_metrics)| {
+            (
+                *package_id,
+                package_metrics
+                    .rs_path_to_metrics
+                    .values()
+                    .map(|wrapper| unsafe_count(&wrapper.metrics.counters))
+                    .sum(),
+            )
+        })
+        .collect()
+}
+
+fn suggest_features(
+    root_package_id: PackageId,
+    graph: &Graph,
+    feature_effects: &BTreeMap<String, FeatureEffects>,
+    enabled_features: &BTreeSet<String>,
+    package_unsafe_counts: &HashMap<PackageId, u64>,
+) -> Vec<FeatureSuggestion> {
+    let mut enabled_features = enabled_features.clone();
+    let mut suggestions = Vec::new();
+    loop {
+        let enabled_dependencies =
+            dependencies_enabled_by(feature_effects, &enabled_features);
+        let disabled_dependencies = optional_dependencies_except(
+            feature_effects,
+            &enabled_dependencies,
+        );
+        let reachable =
+            reachable_package_ids(graph, root_package_id, |package_id| {
+                disabled_dependencies.contains(package_id.name().as_str())
+            });
+        let best_suggestion = enabled_features
+            .iter()
+            .map(|feature| {
+                let disabled = requiring_features(
+                    feature_effects,
+                    &enabled_features,
+                    feature,
+                );
+                let remaining_features = enabled_features
+                    .difference(&disabled)
+                    .cloned()
+                    .collect::<BTreeSet<String>>();
+                let remaining_dependencies = dependencies_enabled_by(
+                    feature_effects,
+                    &remaining_features,
+                );
+                let excluded_dependencies = optional_dependencies_except(
+                    feature_effects,
+                    &remaining_dependencies,
+                );
+                let remaining_packages = reachable_package_ids(
+                    graph,
+                    root_package_id,
+                    |package_id| {
+                        excluded_dependencies
+                            .contains(package_id.name().as_str())
+                    },
+                );
+                let mut dropped_packages = reachable
+                    .difference(&remaining_packages)
+                    .copied()
+                    .collect::<Vec<PackageId>>();
+                dropped_packages.sort();
+                FeatureSuggestion {
+                    feature: feature.clone(),
+                    also_disabled: disabled
+                        .iter()
+                        .filter(|disabled_feature| *disabled_feature != feature)
+                        .cloned()
+                        .collect(),
+                    dropped_dependencies: enabled_dependencies
+                        .difference(&remaining_dependencies)
+                        .cloned()
+                        .collect(),
+                    unsafe_count: dropped_packages
+                        .iter()
+                        .filter_map(|package_id| {
+                            package_unsafe_counts.get(package_id)
+                        })
+                        .sum(),
+                    dropped_packages,
+                }
+            })
+            .filter(|suggestion| suggestion.unsafe_count > 0)
+            .max_by(|a, b| {
+                // Most unsafe usage removed per disabled feature, ties go to
+                // the fewest disabled features, then to the first name.
+                let a_ratio =
+                    a.unsafe_count * (b.also_disabled.len() as u64 + 1);
+                let b_ratio =
+                    b.unsafe_count * (a.also_disabled.len() as u64 + 1);
+                a_ratio
+                    .cmp(&b_ratio)
+                    .then(b.also_disabled.len().cmp(&a.also_disabled.len()))
+                    .then(b.feature.cmp(&a.feature))
+            });
+        let suggestion = match best_suggestion {
+            Some(suggestion) => suggestion,
+            None => return suggestions,
+        };
+        enabled_features.remove(&suggestion.feature);
+        for feature in &suggestion.also_disabled {
+            enabled_features.remove(feature);
+        }
+        suggestions.push(suggestion);
+    }
+}
+
+/// The package names of the optional dependencies enabled by the features.
+fn dependencies_enabled_by(
+    feature_effects: &BTreeMap<String, FeatureEffects>,
+    enabled_features: &BTreeSet<String>,
+) -> BTreeSet<String> {
+    enabled_features
+        .iter()
+        .filter_map(|feature| feature_effects.get(feature))
+        .flat_map(|effects| effects.dependencies.iter().cloned())
+        .collect()
+}
+
+/// The optional dependencies that are not among `enabled_dependencies`.
+fn optional_dependencies_except(
+    feature_effects: &BTreeMap<String, FeatureEffects>,
+    enabled_dependencies: &BTreeSet<String>,
+) -> BTreeSet<String> {
+    feature_effects
+        .values()
+        .flat_map(|effects| effects.dependencies.iter())
+        .filter(|dependency| !enabled_dependencies.contains(*dependency))
+        .cloned()
+        .collect()
+}
+
+/// The feature and every enabled feature that enables it, directly or
+/// through other features, since those would turn it back on.
+fn requiring_features(
+    feature_effects: &BTreeMap<String, FeatureEffects>,
+    enabled_features: &BTreeSet<String>,
+    feature: &str,
+) -> BTreeSet<String> {
+    let mut disabled = BTreeSet::new();
+    disabled.insert(feature.to_string());
+    loop {
+        let newly_disabled = enabled_features
+            .iter()
+            .filter(|enabled_feature| !disabled.contains(*enabled_feature))
+            .filter(|enabled_feature| {
+                feature_effects
+                    .get(*enabled_feature)
+                    .is_some_and(|effects| {
+                        effects
+                            .features
+                            .iter()
+                            .any(|implied| disabled.contains(implied))
+                    })
+            })
+            .cloned()
+            .collect::<Vec<String>>();
+        if newly_disabled.is_empty() {
+            return disabled;
+        }
+        disabled.extend(newly_disabled);
+    }
+}
+
+fn format_suggestion(suggestion: &FeatureSuggestion) -> String {
+    let mut lines = vec![format!(
+        "Disable feature `{}`{}: removes {} unsafe usages",
+        suggestion.feature,
+        if suggestion.also_disabled.is_empty() {
+            String::new()
+        } else {
+            format!(
+                " (also disables {})",
+                suggestion
+                    .also_disabled
+                    .iter()
+                    .map(|feature| format!("`{}`", feature))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )
+        },
+        suggestion.unsafe_count
+    )];
+    if !suggestion.dropped_dependencies.is_empty() {
+        lines.push(format!(
+            "    drops optional dependencies: {}",
+            suggestion.dropped_dependencies.join(", ")
+        ));
+    }
+    lines.push(format!(
+        "    drops packages: {}",
+        suggestion
+            .dropped_packages
+            .iter()
+            .map(|package_id| {
+                format!("{} {}", package_id.name(), package_id.version())
+            })
+            .collect::<Vec<String>>()
+            .join(", ")
+    ));
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod suggest_features_tests {
+    use super::*;
+
+    use crate::graph::Node;
+    use crate::scan::test_utils::create_package_id;
+
+    use cargo::core::dependency::DepKind;
+    use rstest::*;
+
+    #[rstest]
+    fn requiring_features_test() {
+        let feature_effects = create_feature_effects();
+        let enabled_features = create_set(&["default", "std", "simd"]);
+
+        assert_eq!(
+            requiring_features(&feature_effects, &enabled_features, "std"),
+            create_set(&["default", "std"])
+        );
+        assert_eq!(
+            requiring_features(&feature_effects, &enabled_features, "simd"),
+            create_set(&["simd"])
+        );
+    }
+
+    #[rstest]
+    fn suggest_features_test() {
+        let root = create_package_id("root");
+        let libc = create_package_id("libc");
+        let memchr = create_package_id("memchr");
+        let shared = create_package_id("shared");
+        let required = create_package_id("required");
+
+        let mut graph = Graph {
+            graph: petgraph::Graph::new(),
+            nodes: HashMap::new(),
+            edge_features: HashMap::new(),
+        };
+        for package_id in &[root, libc, memchr, shared, required] {
+            let index = graph.graph.add_node(Node {
+                id: *package_id,
+                features: vec![],
+                patched: false,
+                local_override: false,
+                yanked: false,
+            });
+            graph.nodes.insert(*package_id, index);
+        }
+        for (from, to) in &[
+            (root, libc),
+            (root, memchr),
+            (root, required),
+            (libc, shared),
+            (required, shared),
+        ] {
+            graph.graph.add_edge(
+                graph.nodes[from],
+                graph.nodes[to],
+                DepKind::Normal,
+            );
+        }
+        let mut package_unsafe_counts = HashMap::new();
+        package_unsafe_counts.insert(libc, 10);
+        package_unsafe_counts.insert(memchr, 4);
+        package_unsafe_counts.insert(shared, 100);
+        package_unsafe_counts.insert(required, 1);
+
+        let suggestions = suggest_features(
+            root,
+            &graph,
+            &create_feature_effects(),
+            &create_set(&["default", "std", "simd"]),
+            &package_unsafe_counts,
+        );
+
+        assert_eq!(
+            suggestions,
+            vec![
+                FeatureSuggestion {
+                    feature: String::from("std"),
+                    also_disabled: vec![String::from("default")],
+                    dropped_dependencies: vec![String::from("libc")],
+                    dropped_packages: vec![libc],
+                    unsafe_count: 10,
+                },
+                FeatureSuggestion {
+                    feature: String::from("simd"),
+                    also_disabled: vec![],
+                    dropped_dependencies: vec![String::from("memchr")],
+                    dropped_packages: vec![memchr],
+                    unsafe_count: 4,
+                },
+            ]
+        );
+        assert_eq!(
+            format_suggestion(&suggestions[0]),
+            "Disable feature `std` (also disables `default`): removes 10 \
+             unsafe usages\n    drops optional dependencies: libc\n    \
+             drops packages: libc 1.2.3"
+        );
+    }
+
+    fn create_feature_effects() -> BTreeMap<String, FeatureEffects> {
+        let mut feature_effects = BTreeMap::new();
+        feature_effects.insert(
+            String::from("default"),
+            FeatureEffects {
+                features: vec![String::from("std")],
+                dependencies: vec![],
+            },
+        );
+        feature_effects.insert(
+            String::from("std"),
+            FeatureEffects {
+                features: vec![],
+                dependencies: vec![String::from("libc")],
+            },
+        );
+        feature_effects.insert(
+            String::from("simd"),
+            FeatureEffects {
+                features: vec![],
+                dependencies: vec![String::from("memchr")],
+            },
+        );
+        feature_effects
+    }
+
+    fn create_set(values: &[&str]) -> BTreeSet<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+}
@@ -0,0 +1,284 @@
+use super::{package_forbids_unsafe, GeigerContext, ScanParameters};
+
+use cargo::core::compiler::CompileMode;
+use cargo::core::{PackageId, Workspace};
+use cargo::ops;
+use cargo::ops::CompileOptions;
+use cargo::{CliError, CliResult};
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+
+const FORBID_UNSAFE_CODE: &str = "#![forbid(unsafe_code)]";
+
+/// Lists the entry points of the workspace members that are free of unsafe
+/// usage but do not declare `#![forbid(unsafe_code)]`, and inserts the
+/// attribute into them with `--fix`.
+pub fn scan_suggest_forbid(
+    geiger_context: &GeigerContext,
+    scan_parameters: &ScanParameters,
+    workspace: &Workspace,
+) -> CliResult {
+    let member_ids = workspace
+        .members()
+        .map(|package| package.package_id())
+        .collect::<HashSet<PackageId>>();
+
+    let entry_points = find_entry_points_to_forbid(geiger_context, &member_ids);
+    if scan_parameters.args.fix {
+        fix_entry_points(&entry_points, scan_parameters, workspace)?;
+    } else {
+        for entry_point in &entry_points {
+            println!(
+                "{}: no unsafe usage, consider adding {}",
+                entry_point.display(),
+                FORBID_UNSAFE_CODE
+            );
+        }
+    }
+    if entry_points.is_empty() {
+        println!(
+            "All workspace members without unsafe usage forbid unsafe code."
+        );
+    }
+    Ok(())
+}
+
+/// Inserts the attribute into the entry points and checks the workspace. If
+/// it no longer builds, e.g. because a macro expands to unsafe code, the
+/// entry points are restored.
+fn fix_entry_points(
+    entry_points: &[PathBuf],
+    scan_parameters: &ScanParameters,
+    workspace: &Workspace,
+) -> CliResult {
+    let mut sources = Vec::new();
+    for entry_point in entry_points {
+        let source = fs::read_to_string(entry_point)
+            .map_err(|e| CliError::new(e.into(), 1))?;
+        fs::write(entry_point, insert_forbid_unsafe_code(&source))
+            .map_err(|e| CliError::new(e.into(), 1))?;
+        sources.push((entry_point, source));
+    }
+    if sources.is_empty() {
+        return Ok(());
+    }
+    let compile_options = CompileOptions::new(
+        scan_parameters.config,
+        CompileMode::Check { test: false },
+    )?;
+    if let Err(e) = ops::compile(workspace, &compile_options) {
+        for (entry_point, source) in &sources {
+            fs::write(entry_point, source)
+                .map_err(|e| CliError::new(e.into(), 1))?;
+        }
+        return Err(CliError::new(
+            e.context(format!(
+                "the workspace does not build with {}, the entry points \
+                 were restored",
+                FORBID_UNSAFE_CODE
+            )),
+            1,
+        ));
+    }
+    for entry_point in entry_points {
+        scan_parameters.config.shell().status(
+            "Fixed",
+            format!("{}: added {}", entry_point.display(), FORBID_UNSAFE_CODE),
+        )?;
+    }
+    Ok(())
+}
+
+/// The entry points missing the attribute, of the packages among `package_ids`
+/// without any unsafe usage, used or unused, and without any
+/// `#[allow(unsafe_code)]`, which conflicts with the attribute. Sorted by
+/// path.
+fn find_entry_points_to_forbid(
+    geiger_context: &GeigerContext,
+    package_ids: &HashSet<PackageId>,
+) -> Vec<PathBuf> {
+    let mut entry_points = geiger_context
+        .package_id_to_metrics
+        .iter()
+        .filter(|(package_id, _)| package_ids.contains(package_id))
+        .map(|(_, package_metrics)| package_metrics)
+        .filter(|package_metrics| {
+            !package_forbids_unsafe(package_metrics)
+                && package_metrics.rs_path_to_metrics.values().all(
+                    |rs_file_metrics_wrapper| {
+                        let metrics = &rs_file_metrics_wrapper.metrics;
+                        !metrics.counters.has_unsafe()
+                            && metrics.allow_unsafe_code_locations.is_empty()
+                    },
+                )
+        })
+        .flat_map(|package_metrics| package_metrics.rs_path_to_metrics.iter())
+        .filter(|(_, rs_file_metrics_wrapper)| {
+            rs_file_metrics_wrapper.is_crate_entry_point
+                && !rs_file_metrics_wrapper.metrics.forbids_unsafe
+        })
+        .map(|(path_buf, _)| path_buf.clone())
+        .collect::<Vec<PathBuf>>();
+    entry_points.sort();
+    entry_points
+}
+
+/// Inserts the attribute below the leading shebang, comments and inner doc
+/// comments of the file, where inner attributes usually go.
+fn insert_forbid_unsafe_code(source: &str) -> String {
+    let mut offset = 0;
+    while offset < source.len() {
+        let line_end = source[offset..]
+            .find('\n')
+            .map_or(source.len(), |index| offset + index + 1);
+        let trimmed = source[offset..line_end].trim();
+        let is_header = trimmed.is_empty()
+            || trimmed.starts_with("//")
+            || (offset == 0
+                && trimmed.starts_with("#!")
+                && !trimmed.starts_with("#!["));
+        if !is_header {
+            break;
+        }
+        offset = line_end;
+    }
+    let (header, body) = source.split_at(offset);
+    let mut header = header.trim_end().to_string();
+    if !header.is_empty() {
+        header.push_str("\n\n");
+    }
+    let separator = if body.trim_start().starts_with("#![") {
+        "\n"
+    } else {
+        "\n\n"
+    };
+    format!("{}{}{}{}", header, FORBID_UNSAFE_CODE, separator, body)
+}
+
+#[cfg(test)]
+mod suggest_forbid_tests {
+    use super::*;
+
+    use crate::rs_file::RsFileMetricsWrapper;
+    use crate::scan::test_utils::create_package_id;
+    use crate::scan::PackageMetrics;
+
+    use cargo_geiger_serde::Count;
+    use geiger::Location;
+    use rstest::*;
+    use std::collections::HashMap;
+
+    #[rstest(
+        input_source,
+        expected_source,
+        case("fn main() {}\n", "#![forbid(unsafe_code)]\n\nfn main() {}\n"),
+        case(
+            "//! Crate docs.\n\nmod a;\n",
+            "//! Crate docs.\n\n#![forbid(unsafe_code)]\n\nmod a;\n"
+        ),
+        case(
+            "#!/usr/bin/env run-cargo-script\n#![deny(warnings)]\n",
+            "#!/usr/bin/env run-cargo-script\n\n#![forbid(unsafe_code)]\n\
+             #![deny(warnings)]\n"
+        )
+    )]
+    fn insert_forbid_unsafe_code_test(
+        input_source: &str,
+        expected_source: &str,
+    ) {
+        assert_eq!(insert_forbid_unsafe_code(input_source), expected_source);
+    }
+
+    #[rstest]
+    fn find_entry_points_to_forbid_test() {
+        let safe_member = create_package_id("safe_member");
+        let unsafe_member = create_package_id("unsafe_member");
+        let forbidding_member = create_package_id("forbidding_member");
+        let allowing_member = create_package_id("allowing_member");
+        let safe_dependency = create_package_id("safe_dependency");
+
+        let mut package_id_to_metrics = HashMap::new();
+        package_id_to_metrics.insert(
+            safe_member,
+            create_package_metrics(&[
+                ("safe/src/lib.rs", true, false, 0),
+                ("safe/src/main.rs", true, true, 0),
+                ("safe/src/module.rs", false, false, 0),
+            ]),
+        );
+        package_id_to_metrics.insert(
+            unsafe_member,
+            create_package_metrics(&[
+                ("unsafe/src/lib.rs", true, false, 0),
+                ("unsafe/src/unused.rs", false, false, 1),
+            ]),
+        );
+        let mut allowing_metrics =
+            create_package_metrics(&[("allowing/src/lib.rs", true, false, 0)]);
+        for rs_file_metrics_wrapper in
+            allowing_metrics.rs_path_to_metrics.values_mut()
+        {
+            rs_file_metrics_wrapper
+                .metrics
+                .allow_unsafe_code_locations
+                .push(Location { line: 1, column: 0 });
+        }
+        package_id_to_metrics.insert(allowing_member, allowing_metrics);
+        package_id_to_metrics.insert(
+            forbidding_member,
+            create_package_metrics(&[("forbidding/src/lib.rs", true, true, 0)]),
+        );
+        package_id_to_metrics.insert(
+            safe_dependency,
+            create_package_metrics(&[(
+                "dependency/src/lib.rs",
+                true,
+                false,
+                0,
+            )]),
+        );
+        let geiger_context = GeigerContext {
+            package_id_to_metrics,
+            used_but_not_scanned: None,
+            warnings: vec![],
+        };
+        let member_ids = vec![
+            safe_member,
+            unsafe_member,
+            forbidding_member,
+            allowing_member,
+        ]
+        .into_iter()
+        .collect::<HashSet<PackageId>>();
+
+        assert_eq!(
+            find_entry_points_to_forbid(&geiger_context, &member_ids),
+            vec![PathBuf::from("safe/src/lib.rs")]
+        );
+    }
+
+    fn create_package_metrics(
+        files: &[(&str, bool, bool, u64)],
+    ) -> PackageMetrics {
+        let mut rs_path_to_metrics = HashMap::new();
+        for (path, is_crate_entry_point, forbids_unsafe, unsafe_exprs) in files
+        {
+            let mut rs_file_metrics_wrapper = RsFileMetricsWrapper {
+                is_crate_entry_point: *is_crate_entry_point,
+                ..RsFileMetricsWrapper::default()
+            };
+            rs_file_metrics_wrapper.metrics.forbids_unsafe = *forbids_unsafe;
+            rs_file_metrics_wrapper.metrics.counters.exprs = Count {
+                safe: 0,
+                unsafe_: *unsafe_exprs,
+            };
+            rs_path_to_metrics
+                .insert(PathBuf::from(path), rs_file_metrics_wrapper);
+        }
+        PackageMetrics {
+            rs_path_to_metrics,
+            ..Default::default()
+        }
+    }
+}
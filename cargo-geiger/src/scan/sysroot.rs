@@ -0,0 +1,132 @@
+use crate::cli::get_sysroot;
+use crate::format::print_config::PrintConfig;
+use crate::format::table::{unsafe_counters_header, unsafe_counters_row};
+
+use super::find::find_rs_files_in_dir;
+
+use cargo::core::Workspace;
+use cargo::util::CargoResult;
+use cargo::Config;
+use cargo_geiger_serde::CounterBlock;
+use geiger::find_unsafe_in_file;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// The standard library crates scanned with `--include-sysroot`.
+const SYSROOT_CRATES: [&str; 3] = ["alloc", "core", "std"];
+
+/// Scans the standard library crates in the rust-src component of the
+/// toolchain. The sysroot crates are not part of the crate graph and there is
+/// no build to tell used from unused code, so all code found is counted.
+pub fn scan_sysroot(
+    config: &Config,
+    workspace: &Workspace,
+    print_config: &PrintConfig,
+) -> CargoResult<BTreeMap<String, CounterBlock>> {
+    let sysroot = get_sysroot(config, workspace)?;
+    let mut sysroot_crates = BTreeMap::new();
+    for crate_name in &SYSROOT_CRATES {
+        let crate_dir = find_sysroot_crate_dir(&sysroot, crate_name)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "The source of `{}` was not found in the sysroot {}, \
+                     install it with `rustup component add rust-src`",
+                    crate_name,
+                    sysroot.display()
+                )
+            })?;
+        let mut counter_block = CounterBlock::default();
+        for path in find_rs_files_in_dir(&crate_dir) {
+            match find_unsafe_in_file(
+                &path,
+                print_config.include_tests,
+                &print_config.unsafe_apis,
+            ) {
+                Ok(file_metrics) => counter_block += file_metrics.counters,
+                Err(e) => eprintln!(
+                    "Failed to parse file: {}, {:?} ",
+                    path.display(),
+                    e
+                ),
+            }
+        }
+        sysroot_crates.insert(crate_name.to_string(), counter_block);
+    }
+    Ok(sysroot_crates)
+}
+
+/// Lists the unsafe usage in the sysroot crates, separated from the table of
+/// the crate graph.
+pub fn construct_sysroot_lines(
+    sysroot_crates: &BTreeMap<String, CounterBlock>,
+) -> Vec<String> {
+    let mut sysroot_lines = Vec::<String>::new();
+
+    sysroot_lines.push(String::from(
+        "Unsafe usage in the sysroot crates, not part of the crate graph:",
+    ));
+    sysroot_lines.push(String::new());
+    sysroot_lines.push(unsafe_counters_header("Crate"));
+
+    for (crate_name, counter_block) in sysroot_crates {
+        sysroot_lines.push(format!(
+            "{}  {}",
+            unsafe_counters_row(counter_block),
+            crate_name
+        ));
+    }
+
+    sysroot_lines.push(String::new());
+    sysroot_lines
+}
+
+/// The layout of rust-src changed from `src/lib<name>` to `library/<name>`,
+/// both are supported.
+fn find_sysroot_crate_dir(sysroot: &Path, crate_name: &str) -> Option<PathBuf> {
+    let rust_src = sysroot.join("lib").join("rustlib").join("src").join("rust");
+    vec![
+        rust_src.join("library").join(crate_name),
+        rust_src.join("src").join(format!("lib{}", crate_name)),
+    ]
+    .into_iter()
+    .find(|crate_dir| crate_dir.is_dir())
+}
+
+#[cfg(test)]
+mod sysroot_tests {
+    use super::*;
+
+    use rstest::*;
+    use std::fs;
+
+    #[rstest(
+        input_crate_dir,
+        case("lib/rustlib/src/rust/library/core"),
+        case("lib/rustlib/src/rust/src/libcore")
+    )]
+    fn find_sysroot_crate_dir_test(input_crate_dir: &str) {
+        let sysroot = tempfile::tempdir().unwrap();
+        assert_eq!(find_sysroot_crate_dir(sysroot.path(), "core"), None);
+
+        let crate_dir = sysroot.path().join(input_crate_dir);
+        fs::create_dir_all(&crate_dir).unwrap();
+
+        assert_eq!(
+            find_sysroot_crate_dir(sysroot.path(), "core"),
+            Some(crate_dir)
+        );
+    }
+
+    #[rstest]
+    fn construct_sysroot_lines_test() {
+        let mut sysroot_crates = BTreeMap::new();
+        sysroot_crates.insert(String::from("alloc"), CounterBlock::default());
+        sysroot_crates.insert(String::from("core"), CounterBlock::default());
+
+        let sysroot_lines = construct_sysroot_lines(&sysroot_crates);
+
+        assert_eq!(sysroot_lines.len(), 6);
+        assert!(sysroot_lines[3].ends_with("  alloc"));
+        assert!(sysroot_lines[4].ends_with("  core"));
+    }
+}
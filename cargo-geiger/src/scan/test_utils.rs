@@ -0,0 +1,25 @@
+//! Factories shared by the tests of the scan modules.
+
+use cargo::core::{PackageId, SourceId};
+use cargo::util::ToSemver;
+
+/// A package id of version 1.2.3 from the git source of this repository.
+pub(super) fn create_package_id(name: &str) -> PackageId {
+    create_versioned_package_id(name, "1.2.3")
+}
+
+/// A package id from the git source of this repository.
+pub(super) fn create_versioned_package_id(
+    name: &str,
+    version: &str,
+) -> PackageId {
+    PackageId::new(
+        name,
+        version.to_semver().unwrap(),
+        SourceId::from_url(
+            "git+https://github.com/rust-secure-code/cargo-geiger",
+        )
+        .unwrap(),
+    )
+    .unwrap()
+}
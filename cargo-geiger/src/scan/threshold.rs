@@ -0,0 +1,546 @@
+use crate::args::Args;
+use crate::format::numbers::NumberFormat;
+use crate::graph::Graph;
+
+use super::violation::Check;
+use super::{unsafe_stats, GeigerContext};
+
+use cargo::core::PackageId;
+use cargo_geiger_serde::{Count, CounterBlock, UnsafeInfo};
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Percentage thresholds on the share of expressions used by the build that
+/// are unsafe, these scale across projects of different sizes better than
+/// absolute counts.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct UnsafeRatioThresholds {
+    /// The maximum percentage across all packages in the dependency graph.
+    pub total: Option<f64>,
+
+    /// The maximum percentage for any single package.
+    pub per_crate: Option<f64>,
+
+    /// The weight of the expressions in generated files, between 0 and 1,
+    /// they are counted fully by default.
+    pub generated_weight: Option<f64>,
+}
+
+impl UnsafeRatioThresholds {
+    pub fn new(args: &Args) -> Self {
+        UnsafeRatioThresholds {
+            total: args.max_unsafe_ratio,
+            per_crate: args.max_unsafe_ratio_per_crate,
+            generated_weight: args.generated_weight,
+        }
+    }
+}
+
+/// A maximum given to `--max-unsafe-functions` and the other unsafe count
+/// options, as `N` for the total across all packages or as `NAME=N` for
+/// every package of the crate NAME.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnsafeCountLimit {
+    pub package: Option<String>,
+    pub max: u64,
+}
+
+impl FromStr for UnsafeCountLimit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<UnsafeCountLimit, String> {
+        let (package, max) = match s.find('=') {
+            Some(index) => (Some(&s[..index]), &s[index + 1..]),
+            None => (None, s),
+        };
+        if package.is_some_and(str::is_empty) {
+            return Err(format!("{} has no package name before the =", s));
+        }
+        let max = max
+            .parse::<u64>()
+            .map_err(|e| format!("invalid maximum {}, {}", max, e))?;
+        Ok(UnsafeCountLimit {
+            package: package.map(String::from),
+            max,
+        })
+    }
+}
+
+/// Absolute thresholds on the unsafe usage of the build, a machine
+/// enforceable gate where the ratio is not wanted, e.g. no unsafe functions
+/// at all.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UnsafeCountThresholds {
+    pub functions: Vec<UnsafeCountLimit>,
+    pub exprs: Vec<UnsafeCountLimit>,
+    pub impls: Vec<UnsafeCountLimit>,
+    pub traits: Vec<UnsafeCountLimit>,
+    pub methods: Vec<UnsafeCountLimit>,
+}
+
+impl UnsafeCountThresholds {
+    pub fn new(args: &Args) -> Self {
+        UnsafeCountThresholds {
+            functions: args.max_unsafe_functions.clone(),
+            exprs: args.max_unsafe_expressions.clone(),
+            impls: args.max_unsafe_impls.clone(),
+            traits: args.max_unsafe_traits.clone(),
+            methods: args.max_unsafe_methods.clone(),
+        }
+    }
+}
+
+/// The name of an unsafe counter, its limits and how to read it from a
+/// counter block.
+type UnsafeCounter<'a> =
+    (&'a str, &'a [UnsafeCountLimit], fn(&CounterBlock) -> u64);
+
+/// Returns a description of every exceeded unsafe count threshold, the
+/// totals before the packages, sorted by package.
+fn find_unsafe_count_violations(
+    geiger_context: &GeigerContext,
+    graph: &Graph,
+    rs_files_used: &HashSet<PathBuf>,
+    thresholds: &UnsafeCountThresholds,
+) -> Vec<String> {
+    let mut package_used = graph
+        .nodes
+        .keys()
+        .filter_map(|package_id| {
+            geiger_context.package_id_to_metrics.get(package_id).map(
+                |package_metrics| {
+                    let used =
+                        unsafe_stats(package_metrics, rs_files_used).used;
+                    (*package_id, used)
+                },
+            )
+        })
+        .collect::<Vec<(PackageId, CounterBlock)>>();
+    package_used.sort_by_key(|(a, _)| *a);
+    let total_used = package_used
+        .iter()
+        .fold(CounterBlock::default(), |total, (_, used)| {
+            total + used.clone()
+        });
+
+    let counters: [UnsafeCounter; 5] = [
+        ("functions", &thresholds.functions, |c| c.functions.unsafe_),
+        ("expressions", &thresholds.exprs, |c| c.exprs.unsafe_),
+        ("impls", &thresholds.impls, |c| c.item_impls.unsafe_),
+        ("traits", &thresholds.traits, |c| c.item_traits.unsafe_),
+        ("methods", &thresholds.methods, |c| c.methods.unsafe_),
+    ];
+    let mut violations = Vec::new();
+    for (name, limits, unsafe_count) in counters.iter() {
+        for limit in limits.iter().filter(|limit| limit.package.is_none()) {
+            let count = unsafe_count(&total_used);
+            if count > limit.max {
+                violations.push(format!(
+                    "{} unsafe {} are used by the build, exceeding the \
+                     maximum of {}",
+                    count, name, limit.max
+                ));
+            }
+        }
+    }
+    for (package_id, used) in &package_used {
+        for (name, limits, unsafe_count) in counters.iter() {
+            let package_limits = limits.iter().filter(|limit| {
+                limit.package.as_deref() == Some(package_id.name().as_str())
+            });
+            for limit in package_limits {
+                let count = unsafe_count(used);
+                if count > limit.max {
+                    violations.push(format!(
+                        "{} unsafe {} are used by the build in {}, \
+                         exceeding the maximum of {}",
+                        count, name, package_id, limit.max
+                    ));
+                }
+            }
+        }
+    }
+    violations
+}
+
+/// Returns a description of every exceeded threshold, sorted by package.
+fn find_unsafe_ratio_violations(
+    geiger_context: &GeigerContext,
+    graph: &Graph,
+    rs_files_used: &HashSet<PathBuf>,
+    thresholds: &UnsafeRatioThresholds,
+    numbers: NumberFormat,
+) -> Vec<String> {
+    let mut package_exprs = graph
+        .nodes
+        .keys()
+        .filter_map(|package_id| {
+            geiger_context.package_id_to_metrics.get(package_id).map(
+                |package_metrics| {
+                    let exprs = weighted_exprs(
+                        &unsafe_stats(package_metrics, rs_files_used),
+                        thresholds.generated_weight,
+                    );
+                    (*package_id, exprs)
+                },
+            )
+        })
+        .collect::<Vec<(PackageId, Count)>>();
+    package_exprs.sort_by_key(|(a, _)| *a);
+
+    let mut violations = Vec::new();
+    if let Some(max_percentage) = thresholds.per_crate {
+        for (package_id, exprs) in &package_exprs {
+            let percentage = unsafe_percentage(exprs);
+            if percentage > max_percentage {
+                violations.push(format!(
+                    "Unsafe expressions are {}% of the expressions used in \
+                     {}, exceeding the {}% per crate threshold",
+                    numbers.ratio(percentage),
+                    package_id,
+                    numbers.ratio(max_percentage)
+                ));
+            }
+        }
+    }
+    if let Some(max_percentage) = thresholds.total {
+        let total = package_exprs
+            .into_iter()
+            .fold(Count::default(), |total, (_, exprs)| total + exprs);
+        let percentage = unsafe_percentage(&total);
+        if percentage > max_percentage {
+            violations.push(format!(
+                "Unsafe expressions are {}% of the expressions used in the \
+                 dependency graph, exceeding the {}% threshold",
+                numbers.ratio(percentage),
+                numbers.ratio(max_percentage)
+            ));
+        }
+    }
+    violations
+}
+
+/// Fails if the unsafe usage of the build exceeds any of the unsafe ratio or
+/// unsafe count thresholds, listing them.
+pub fn check_thresholds(
+    geiger_context: &GeigerContext,
+    graph: &Graph,
+    rs_files_used: &HashSet<PathBuf>,
+    args: &Args,
+    numbers: NumberFormat,
+) -> Check {
+    let mut violations = find_unsafe_ratio_violations(
+        geiger_context,
+        graph,
+        rs_files_used,
+        &UnsafeRatioThresholds::new(args),
+        numbers,
+    );
+    violations.extend(find_unsafe_count_violations(
+        geiger_context,
+        graph,
+        rs_files_used,
+        &UnsafeCountThresholds::new(args),
+    ));
+    let violation_count = violations.len() as u64;
+    Check::new(violations, ThresholdExceededError { violation_count })
+}
+
+#[derive(Debug)]
+struct ThresholdExceededError {
+    violation_count: u64,
+}
+
+impl Error for ThresholdExceededError {}
+
+impl fmt::Display for ThresholdExceededError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} threshold(s) exceeded", self.violation_count)
+    }
+}
+
+/// The expressions used by the build, with those in generated files scaled by
+/// the weight.
+fn weighted_exprs(
+    unsafe_info: &UnsafeInfo,
+    generated_weight: Option<f64>,
+) -> Count {
+    let used = &unsafe_info.used.exprs;
+    let generated = &unsafe_info.generated.exprs;
+    match generated_weight {
+        Some(weight) => {
+            let scale = |used: u64, generated: u64| {
+                used.saturating_sub(generated)
+                    + (generated as f64 * weight).round() as u64
+            };
+            Count {
+                safe: scale(used.safe, generated.safe),
+                unsafe_: scale(used.unsafe_, generated.unsafe_),
+            }
+        }
+        None => used.clone(),
+    }
+}
+
+fn unsafe_percentage(count: &Count) -> f64 {
+    let total = count.safe + count.unsafe_;
+    if total == 0 {
+        0.0
+    } else {
+        count.unsafe_ as f64 * 100.0 / total as f64
+    }
+}
+
+#[cfg(test)]
+mod threshold_tests {
+    use super::*;
+
+    use crate::graph::Node;
+    use crate::rs_file::RsFileMetricsWrapper;
+    use crate::scan::test_utils::create_package_id;
+    use crate::scan::PackageMetrics;
+
+    use cargo_geiger_serde::CounterBlock;
+    use rstest::*;
+    use std::collections::HashMap;
+
+    #[rstest(
+        input_count,
+        expected_percentage,
+        case(Count { safe: 0, unsafe_: 0 }, 0.0),
+        case(Count { safe: 99, unsafe_: 1 }, 1.0),
+        case(Count { safe: 0, unsafe_: 4 }, 100.0)
+    )]
+    fn unsafe_percentage_test(input_count: Count, expected_percentage: f64) {
+        assert!(
+            (unsafe_percentage(&input_count) - expected_percentage).abs()
+                < 1e-9
+        );
+    }
+
+    #[rstest(
+        input_generated_weight,
+        expected_exprs,
+        case(None, Count { safe: 10, unsafe_: 6 }),
+        case(Some(1.0), Count { safe: 10, unsafe_: 6 }),
+        case(Some(0.5), Count { safe: 8, unsafe_: 4 }),
+        case(Some(0.0), Count { safe: 6, unsafe_: 2 })
+    )]
+    fn weighted_exprs_test(
+        input_generated_weight: Option<f64>,
+        expected_exprs: Count,
+    ) {
+        let unsafe_info = UnsafeInfo {
+            used: CounterBlock {
+                exprs: Count {
+                    safe: 10,
+                    unsafe_: 6,
+                },
+                ..Default::default()
+            },
+            generated: CounterBlock {
+                exprs: Count {
+                    safe: 4,
+                    unsafe_: 4,
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(
+            weighted_exprs(&unsafe_info, input_generated_weight),
+            expected_exprs
+        );
+    }
+
+    #[rstest(
+        input_thresholds,
+        expected_violation_count,
+        case(UnsafeRatioThresholds::default(), 0),
+        case(
+            UnsafeRatioThresholds {
+                total: Some(2.0),
+                per_crate: None,
+                generated_weight: None
+            },
+            1
+        ),
+        case(
+            UnsafeRatioThresholds {
+                total: Some(5.0),
+                per_crate: None,
+                generated_weight: None
+            },
+            0
+        ),
+        case(
+            UnsafeRatioThresholds {
+                total: None,
+                per_crate: Some(3.0),
+                generated_weight: None
+            },
+            1
+        ),
+        case(
+            UnsafeRatioThresholds {
+                total: Some(2.0),
+                per_crate: Some(3.0),
+                generated_weight: None
+            },
+            2
+        )
+    )]
+    fn find_unsafe_ratio_violations_test(
+        input_thresholds: UnsafeRatioThresholds,
+        expected_violation_count: usize,
+    ) {
+        let mut graph = Graph {
+            graph: petgraph::Graph::new(),
+            nodes: HashMap::new(),
+            edge_features: HashMap::new(),
+        };
+        let mut package_id_to_metrics = HashMap::new();
+        // 2% and 4% unsafe expressions, 3.33% in total.
+        for (name, safe, unsafe_) in &[("crate_a", 98, 2), ("crate_b", 192, 8)]
+        {
+            let package_id = create_package_id(name);
+            let index = graph.graph.add_node(Node {
+                id: package_id,
+                features: vec![],
+                patched: false,
+                local_override: false,
+                yanked: false,
+            });
+            graph.nodes.insert(package_id, index);
+            package_id_to_metrics
+                .insert(package_id, create_package_metrics(*safe, *unsafe_));
+        }
+        let geiger_context = GeigerContext {
+            package_id_to_metrics,
+            used_but_not_scanned: None,
+            warnings: vec![],
+        };
+        let rs_files_used =
+            vec![PathBuf::from("src/lib.rs")].into_iter().collect();
+
+        let violations = find_unsafe_ratio_violations(
+            &geiger_context,
+            &graph,
+            &rs_files_used,
+            &input_thresholds,
+            NumberFormat::default(),
+        );
+
+        assert_eq!(violations.len(), expected_violation_count);
+    }
+
+    #[rstest(
+        input_str,
+        expected_limit,
+        case(
+            "0",
+            Ok(UnsafeCountLimit {
+                package: None,
+                max: 0
+            })
+        ),
+        case(
+            "libc=100",
+            Ok(UnsafeCountLimit {
+                package: Some(String::from("libc")),
+                max: 100
+            })
+        ),
+        case("=100", Err(())),
+        case("libc=", Err(())),
+        case("-1", Err(()))
+    )]
+    fn unsafe_count_limit_from_str_test(
+        input_str: &str,
+        expected_limit: Result<UnsafeCountLimit, ()>,
+    ) {
+        assert_eq!(
+            UnsafeCountLimit::from_str(input_str).map_err(|_| ()),
+            expected_limit
+        );
+    }
+
+    #[rstest]
+    fn find_unsafe_count_violations_test() {
+        let mut graph = Graph {
+            graph: petgraph::Graph::new(),
+            nodes: HashMap::new(),
+            edge_features: HashMap::new(),
+        };
+        let mut package_id_to_metrics = HashMap::new();
+        for (name, safe, unsafe_) in &[("crate_a", 98, 2), ("crate_b", 192, 8)]
+        {
+            let package_id = create_package_id(name);
+            let index = graph.graph.add_node(Node {
+                id: package_id,
+                features: vec![],
+                patched: false,
+                local_override: false,
+                yanked: false,
+            });
+            graph.nodes.insert(package_id, index);
+            package_id_to_metrics
+                .insert(package_id, create_package_metrics(*safe, *unsafe_));
+        }
+        let geiger_context = GeigerContext {
+            package_id_to_metrics,
+            used_but_not_scanned: None,
+            warnings: vec![],
+        };
+        let rs_files_used =
+            vec![PathBuf::from("src/lib.rs")].into_iter().collect();
+        let thresholds = UnsafeCountThresholds {
+            functions: vec![UnsafeCountLimit::from_str("0").unwrap()],
+            exprs: vec![
+                UnsafeCountLimit::from_str("9").unwrap(),
+                UnsafeCountLimit::from_str("crate_a=2").unwrap(),
+                UnsafeCountLimit::from_str("crate_b=5").unwrap(),
+            ],
+            ..Default::default()
+        };
+
+        let violations = find_unsafe_count_violations(
+            &geiger_context,
+            &graph,
+            &rs_files_used,
+            &thresholds,
+        );
+
+        assert_eq!(
+            violations,
+            vec![
+                String::from(
+                    "10 unsafe expressions are used by the build, exceeding \
+                     the maximum of 9"
+                ),
+                format!(
+                    "8 unsafe expressions are used by the build in {}, \
+                     exceeding the maximum of 5",
+                    create_package_id("crate_b")
+                ),
+            ]
+        );
+    }
+
+    fn create_package_metrics(safe: u64, unsafe_: u64) -> PackageMetrics {
+        let mut rs_file_metrics_wrapper = RsFileMetricsWrapper::default();
+        rs_file_metrics_wrapper.metrics.counters.exprs =
+            Count { safe, unsafe_ };
+        let mut rs_path_to_metrics = HashMap::new();
+        rs_path_to_metrics
+            .insert(PathBuf::from("src/lib.rs"), rs_file_metrics_wrapper);
+        PackageMetrics {
+            rs_path_to_metrics,
+            ..Default::default()
+        }
+    }
+}
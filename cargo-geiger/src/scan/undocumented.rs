@@ -0,0 +1,190 @@
+use crate::graph::Graph;
+
+use super::violation::Check;
+use super::{GeigerContext, UndocumentedUnsafeScope};
+
+use cargo::core::{PackageId, Workspace};
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+
+pub fn scan_undocumented_unsafe(
+    geiger_context: &GeigerContext,
+    graph: &Graph,
+    scope: UndocumentedUnsafeScope,
+    workspace: &Workspace,
+) {
+    let package_ids = undocumented_unsafe_package_ids(graph, scope, workspace);
+
+    let undocumented_unsafe_lines =
+        construct_undocumented_unsafe_lines(geiger_context, &package_ids);
+    let undocumented_count = undocumented_unsafe_lines.len() as u64;
+    for undocumented_unsafe_line in undocumented_unsafe_lines {
+        println!("{}", undocumented_unsafe_line);
+    }
+    println!();
+    println!(
+        "Found {} unsafe block(s) without a `// SAFETY:` comment.",
+        undocumented_count
+    );
+}
+
+/// Fails if any package in the scope has unsafe blocks without a
+/// `// SAFETY:` comment, for `--deny-undocumented-unsafe`.
+pub fn check_undocumented_unsafe(
+    geiger_context: &GeigerContext,
+    graph: &Graph,
+    scope: UndocumentedUnsafeScope,
+    workspace: &Workspace,
+) -> Check {
+    let package_ids = undocumented_unsafe_package_ids(graph, scope, workspace);
+    let undocumented_count =
+        construct_undocumented_unsafe_lines(geiger_context, &package_ids).len()
+            as u64;
+    let violations = if undocumented_count > 0 {
+        vec![format!(
+            "{} unsafe block(s) lack a `// SAFETY:` comment",
+            undocumented_count
+        )]
+    } else {
+        Vec::new()
+    };
+    Check::new(
+        violations,
+        FoundUndocumentedUnsafeError { undocumented_count },
+    )
+}
+
+fn undocumented_unsafe_package_ids(
+    graph: &Graph,
+    scope: UndocumentedUnsafeScope,
+    workspace: &Workspace,
+) -> HashSet<PackageId> {
+    match scope {
+        UndocumentedUnsafeScope::Workspace => workspace
+            .members()
+            .map(|package| package.package_id())
+            .collect(),
+        UndocumentedUnsafeScope::All => graph.nodes.keys().cloned().collect(),
+    }
+}
+
+#[derive(Debug)]
+struct FoundUndocumentedUnsafeError {
+    undocumented_count: u64,
+}
+
+impl Error for FoundUndocumentedUnsafeError {}
+
+impl fmt::Display for FoundUndocumentedUnsafeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} unsafe block(s) lack a `// SAFETY:` comment",
+            self.undocumented_count
+        )
+    }
+}
+
+fn construct_undocumented_unsafe_lines(
+    geiger_context: &GeigerContext,
+    package_ids: &HashSet<PackageId>,
+) -> Vec<String> {
+    let mut locations = geiger_context
+        .package_id_to_metrics
+        .iter()
+        .filter(|(package_id, _)| package_ids.contains(package_id))
+        .flat_map(|(_, package_metrics)| {
+            package_metrics.rs_path_to_metrics.iter()
+        })
+        .flat_map(|(path_buf, rs_file_metrics_wrapper)| {
+            rs_file_metrics_wrapper
+                .metrics
+                .undocumented_unsafe_blocks
+                .iter()
+                .map(move |location| (path_buf, location))
+        })
+        .collect::<Vec<_>>();
+
+    locations.sort();
+
+    locations
+        .iter()
+        .map(|(path_buf, location)| {
+            format!(
+                "{}:{}: unsafe block without a `// SAFETY:` comment",
+                path_buf.display(),
+                location
+            )
+        })
+        .collect::<Vec<String>>()
+}
+
+#[cfg(test)]
+mod undocumented_tests {
+    use super::*;
+
+    use crate::rs_file::RsFileMetricsWrapper;
+    use crate::scan::test_utils::create_package_id;
+    use crate::scan::PackageMetrics;
+
+    use geiger::Location;
+    use rstest::*;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    #[rstest]
+    fn construct_undocumented_unsafe_lines_test() {
+        let workspace_package_id = create_package_id("workspace_package");
+        let dependency_package_id = create_package_id("dependency_package");
+
+        let mut package_id_to_metrics = HashMap::new();
+        package_id_to_metrics.insert(
+            workspace_package_id,
+            create_package_metrics("b/path.rs", vec![(7, 8), (3, 4)]),
+        );
+        package_id_to_metrics.insert(
+            dependency_package_id,
+            create_package_metrics("a/path.rs", vec![(1, 0)]),
+        );
+        let geiger_context = GeigerContext {
+            package_id_to_metrics,
+            used_but_not_scanned: None,
+            warnings: vec![],
+        };
+
+        let package_ids: HashSet<PackageId> =
+            vec![workspace_package_id].into_iter().collect();
+        let undocumented_unsafe_lines =
+            construct_undocumented_unsafe_lines(&geiger_context, &package_ids);
+
+        assert_eq!(
+            undocumented_unsafe_lines,
+            vec![
+                String::from(
+                    "b/path.rs:3:4: unsafe block without a `// SAFETY:` comment"
+                ),
+                String::from(
+                    "b/path.rs:7:8: unsafe block without a `// SAFETY:` comment"
+                ),
+            ]
+        );
+    }
+
+    fn create_package_metrics(
+        path: &str,
+        locations: Vec<(usize, usize)>,
+    ) -> PackageMetrics {
+        let mut rs_file_metrics_wrapper = RsFileMetricsWrapper::default();
+        rs_file_metrics_wrapper.metrics.undocumented_unsafe_blocks = locations
+            .into_iter()
+            .map(|(line, column)| Location { line, column })
+            .collect();
+        let mut rs_path_to_metrics = HashMap::new();
+        rs_path_to_metrics.insert(PathBuf::from(path), rs_file_metrics_wrapper);
+        PackageMetrics {
+            rs_path_to_metrics,
+            ..Default::default()
+        }
+    }
+}
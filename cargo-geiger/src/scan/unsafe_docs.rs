@@ -0,0 +1,168 @@
+use super::{unsafe_stats, GeigerContext};
+
+use cargo::core::{PackageId, PackageSet};
+use cargo::util::CargoResult;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// The files, relative to the package root, in which crates commonly
+/// document the invariants of their unsafe code.
+const UNSAFE_DOC_PATHS: [&str; 5] = [
+    "SAFETY.md",
+    "UNSAFE.md",
+    "docs/safety.md",
+    "docs/unsafe.md",
+    "doc/unsafe.md",
+];
+
+/// The number of packages with the most unsafe usage whose documentation is
+/// looked up.
+const TOP_UNSAFE_PACKAGE_COUNT: usize = 10;
+
+/// The unsafe documentation found in the sources of the packages with the
+/// most unsafe expressions used by the build, most unsafe first. Packages
+/// without any of the documentation files are left out.
+pub fn find_unsafe_docs(
+    geiger_context: &GeigerContext,
+    package_set: &PackageSet,
+    rs_files_used: &HashSet<PathBuf>,
+) -> CargoResult<Vec<(PackageId, Vec<PathBuf>)>> {
+    let package_unsafe_exprs = geiger_context
+        .package_id_to_metrics
+        .iter()
+        .map(|(package_id, package_metrics)| {
+            (
+                *package_id,
+                unsafe_stats(package_metrics, rs_files_used)
+                    .used
+                    .exprs
+                    .unsafe_,
+            )
+        })
+        .collect::<Vec<(PackageId, u64)>>();
+    let mut unsafe_docs = Vec::new();
+    for package_id in
+        top_unsafe_packages(package_unsafe_exprs, TOP_UNSAFE_PACKAGE_COUNT)
+    {
+        let package = package_set.get_one(package_id)?;
+        let doc_paths = find_unsafe_doc_paths(package.root());
+        if !doc_paths.is_empty() {
+            unsafe_docs.push((package_id, doc_paths));
+        }
+    }
+    Ok(unsafe_docs)
+}
+
+/// A line per package naming its unsafe documentation, below a header, or
+/// nothing when no documentation was found.
+pub fn construct_unsafe_docs_lines(
+    unsafe_docs: &[(PackageId, Vec<PathBuf>)],
+) -> Vec<String> {
+    if unsafe_docs.is_empty() {
+        return Vec::new();
+    }
+    let mut lines = vec![String::from(
+        "Unsafe documentation of the most unsafe packages:",
+    )];
+    for (package_id, doc_paths) in unsafe_docs {
+        for doc_path in doc_paths {
+            lines.push(format!(
+                "    {} {}: {}",
+                package_id.name(),
+                package_id.version(),
+                doc_path.display()
+            ));
+        }
+    }
+    lines.push(String::new());
+    lines
+}
+
+/// The packages with any unsafe expressions, most unsafe first, at most
+/// `count`.
+fn top_unsafe_packages(
+    mut package_unsafe_exprs: Vec<(PackageId, u64)>,
+    count: usize,
+) -> Vec<PackageId> {
+    package_unsafe_exprs.retain(|(_, unsafe_exprs)| *unsafe_exprs > 0);
+    package_unsafe_exprs.sort_by(|(a_id, a_unsafe), (b_id, b_unsafe)| {
+        b_unsafe.cmp(a_unsafe).then(a_id.cmp(b_id))
+    });
+    package_unsafe_exprs
+        .into_iter()
+        .take(count)
+        .map(|(package_id, _)| package_id)
+        .collect()
+}
+
+fn find_unsafe_doc_paths(package_root: &Path) -> Vec<PathBuf> {
+    UNSAFE_DOC_PATHS
+        .iter()
+        .map(|doc_path| package_root.join(doc_path))
+        .filter(|doc_path| doc_path.is_file())
+        .collect()
+}
+
+#[cfg(test)]
+mod unsafe_docs_tests {
+    use super::*;
+
+    use crate::scan::test_utils::create_package_id;
+
+    use rstest::*;
+    use std::fs;
+
+    #[rstest]
+    fn top_unsafe_packages_test() {
+        let a = create_package_id("a");
+        let b = create_package_id("b");
+        let c = create_package_id("c");
+        let safe = create_package_id("safe");
+
+        assert_eq!(
+            top_unsafe_packages(vec![(a, 5), (b, 20), (c, 5), (safe, 0)], 2),
+            vec![b, a]
+        );
+        assert_eq!(
+            top_unsafe_packages(vec![(safe, 0)], 10),
+            Vec::<PackageId>::new()
+        );
+    }
+
+    #[rstest]
+    fn find_unsafe_doc_paths_test() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("docs")).unwrap();
+        fs::write(dir.path().join("SAFETY.md"), "").unwrap();
+        fs::write(dir.path().join("docs").join("unsafe.md"), "").unwrap();
+        fs::write(dir.path().join("README.md"), "").unwrap();
+
+        assert_eq!(
+            find_unsafe_doc_paths(dir.path()),
+            vec![
+                dir.path().join("SAFETY.md"),
+                dir.path().join("docs/unsafe.md")
+            ]
+        );
+    }
+
+    #[rstest]
+    fn construct_unsafe_docs_lines_test() {
+        let a = create_package_id("a");
+
+        assert!(construct_unsafe_docs_lines(&[]).is_empty());
+        assert_eq!(
+            construct_unsafe_docs_lines(&[(
+                a,
+                vec![PathBuf::from("/src/a/SAFETY.md")]
+            )]),
+            vec![
+                String::from(
+                    "Unsafe documentation of the most unsafe packages:"
+                ),
+                String::from("    a 1.2.3: /src/a/SAFETY.md"),
+                String::new(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,393 @@
+use crate::args::Args;
+use crate::cli::{
+    get_features, get_registry, get_root_package_ids, get_workspace, resolve,
+    resolve_updated,
+};
+use crate::format::print_config::{OutputFormat, PrintConfig};
+use crate::format::table::unsafe_counters_header;
+use crate::format::treemap::unsafe_count;
+
+use super::find::find_unsafe;
+use super::violation::report_violations;
+use super::{unsafe_stats, GeigerContext, ScanMode};
+
+use cargo::core::registry::PackageRegistry;
+use cargo::core::PackageId;
+use cargo::{CliError, CliResult, Config};
+use cargo_geiger_serde::{Count, CounterBlock};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+
+/// The JSON output of `cargo geiger check-update`.
+#[derive(Debug, Default, Serialize)]
+struct UpdateReport {
+    updates: Vec<UpdateDelta>,
+}
+
+/// The unsafe code found in the versions of a package before and after the
+/// update. Every `.rs` file of the package is counted, the would-be versions
+/// are not built.
+#[derive(Debug, Default, PartialEq, Serialize)]
+struct UpdateDelta {
+    name: String,
+    /// Empty for a package the update adds.
+    locked_versions: Vec<String>,
+    /// Empty for a package the update removes.
+    updated_versions: Vec<String>,
+    locked: CounterBlock,
+    updated: CounterBlock,
+}
+
+/// Resolves the dependencies like `cargo update --dry-run`, scans the
+/// packages whose versions would change and prints the change of their
+/// unsafe usage, without touching `Cargo.lock`. Unchanged packages are not
+/// scanned, the updated ones come from the metrics cache when possible.
+/// Fails when the update adds unsafe code to any package, so a CI job can
+/// veto it, unless `--report-only` is given.
+pub fn run_check_update(args: &Args, config: &Config) -> CliResult {
+    match args.output_format {
+        None | Some(OutputFormat::Json) => {}
+        Some(_) => {
+            return Err(CliError::new(
+                anyhow::anyhow!(
+                    "check-update is only printed as a table or, with \
+                     --json, as JSON"
+                ),
+                1,
+            ))
+        }
+    }
+    let print_config = PrintConfig::new(args)?;
+    let workspace =
+        get_workspace(config, args.manifest_paths.first().cloned())?;
+    // The members of a virtual workspace are resolved together, like by
+    // `cargo update`.
+    let member_ids = get_root_package_ids(&workspace)?;
+    let features = get_features(args);
+
+    let mut locked_registry = get_registry(config, &member_ids)?;
+    let (_, locked_resolve) = resolve(
+        &member_ids,
+        &mut locked_registry,
+        &workspace,
+        &features,
+        args.all_features,
+        args.no_default_features,
+    )?;
+    // A registry only takes the patches of one resolve.
+    let mut updated_registry = get_registry(config, &member_ids)?;
+    let updated_resolve = resolve_updated(
+        &member_ids,
+        &mut updated_registry,
+        &workspace,
+        &features,
+        args.all_features,
+        args.no_default_features,
+    )?;
+
+    let (removed_ids, added_ids) = changed_package_ids(
+        locked_resolve.iter().collect(),
+        updated_resolve.iter().collect(),
+    );
+    let locked_context =
+        scan_packages(&removed_ids, locked_registry, config, &print_config)?;
+    let updated_context =
+        scan_packages(&added_ids, updated_registry, config, &print_config)?;
+    let update_report = UpdateReport {
+        updates: find_update_deltas(
+            &removed_ids,
+            &added_ids,
+            &locked_context,
+            &updated_context,
+        ),
+    };
+
+    match args.output_format {
+        Some(_) => {
+            println!("{}", serde_json::to_string(&update_report).unwrap())
+        }
+        None => {
+            for update_line in construct_update_lines(&update_report.updates) {
+                println!("{}", update_line);
+            }
+        }
+    }
+    let violations = update_report
+        .updates
+        .iter()
+        .filter(|update_delta| {
+            unsafe_count(&update_delta.updated)
+                > unsafe_count(&update_delta.locked)
+        })
+        .map(|update_delta| {
+            format!("The update adds unsafe code to {}", update_delta.name)
+        })
+        .collect::<Vec<String>>();
+
+    report_violations(
+        args,
+        &violations,
+        UnsafeIncreaseError {
+            package_count: violations.len() as u64,
+        },
+    )
+}
+
+#[derive(Debug)]
+struct UnsafeIncreaseError {
+    package_count: u64,
+}
+
+impl Error for UnsafeIncreaseError {}
+
+impl fmt::Display for UnsafeIncreaseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} package(s) use more unsafe code after the update",
+            self.package_count
+        )
+    }
+}
+
+/// The packages only in the locked resolve and those only in the updated
+/// one, each sorted. A package that changes versions is in both.
+fn changed_package_ids(
+    locked_ids: HashSet<PackageId>,
+    updated_ids: HashSet<PackageId>,
+) -> (Vec<PackageId>, Vec<PackageId>) {
+    let mut removed_ids = locked_ids
+        .difference(&updated_ids)
+        .copied()
+        .collect::<Vec<PackageId>>();
+    removed_ids.sort();
+    let mut added_ids = updated_ids
+        .difference(&locked_ids)
+        .copied()
+        .collect::<Vec<PackageId>>();
+    added_ids.sort();
+    (removed_ids, added_ids)
+}
+
+fn scan_packages(
+    package_ids: &[PackageId],
+    registry: PackageRegistry,
+    config: &Config,
+    print_config: &PrintConfig,
+) -> Result<GeigerContext, CliError> {
+    if package_ids.is_empty() {
+        return Ok(GeigerContext {
+            package_id_to_metrics: HashMap::new(),
+            used_but_not_scanned: None,
+            warnings: vec![],
+        });
+    }
+    let package_set = registry.get(package_ids)?;
+    find_unsafe(ScanMode::Full, config, &package_set, print_config, None)
+}
+
+/// Pairs the removed and added versions by package name, sorted by name.
+fn find_update_deltas(
+    removed_ids: &[PackageId],
+    added_ids: &[PackageId],
+    locked_context: &GeigerContext,
+    updated_context: &GeigerContext,
+) -> Vec<UpdateDelta> {
+    let mut update_deltas = BTreeMap::<String, UpdateDelta>::new();
+    for package_id in removed_ids {
+        let update_delta = update_deltas
+            .entry(package_id.name().to_string())
+            .or_insert_with(|| UpdateDelta {
+                name: package_id.name().to_string(),
+                ..Default::default()
+            });
+        update_delta
+            .locked_versions
+            .push(package_id.version().to_string());
+        update_delta.locked += package_unsafety(locked_context, *package_id);
+    }
+    for package_id in added_ids {
+        let update_delta = update_deltas
+            .entry(package_id.name().to_string())
+            .or_insert_with(|| UpdateDelta {
+                name: package_id.name().to_string(),
+                ..Default::default()
+            });
+        update_delta
+            .updated_versions
+            .push(package_id.version().to_string());
+        update_delta.updated += package_unsafety(updated_context, *package_id);
+    }
+    update_deltas.into_values().collect()
+}
+
+/// All unsafe code found in the package, a package that failed to scan
+/// counts as none.
+fn package_unsafety(
+    geiger_context: &GeigerContext,
+    package_id: PackageId,
+) -> CounterBlock {
+    geiger_context
+        .package_id_to_metrics
+        .get(&package_id)
+        .map(|package_metrics| {
+            unsafe_stats(package_metrics, &HashSet::new()).unused
+        })
+        .unwrap_or_default()
+}
+
+fn construct_update_lines(update_deltas: &[UpdateDelta]) -> Vec<String> {
+    if update_deltas.is_empty() {
+        return vec![String::from("No dependency updates are available.")];
+    }
+    let mut update_lines =
+        vec![unsafe_counters_header("Update"), String::new()];
+    let mut total_locked = CounterBlock::default();
+    let mut total_updated = CounterBlock::default();
+    for update_delta in update_deltas {
+        total_locked += update_delta.locked.clone();
+        total_updated += update_delta.updated.clone();
+        update_lines.push(format!(
+            "{}  {} {} -> {}",
+            unsafe_delta_row(&update_delta.locked, &update_delta.updated),
+            update_delta.name,
+            versions(&update_delta.locked_versions),
+            versions(&update_delta.updated_versions)
+        ));
+    }
+    update_lines.push(String::new());
+    update_lines.push(format!(
+        "{}  Total, {} package(s) changed",
+        unsafe_delta_row(&total_locked, &total_updated),
+        update_deltas.len()
+    ));
+    update_lines
+}
+
+/// A table row with the change of the unsafe counts, e.g. `+3` or `-1`.
+fn unsafe_delta_row(locked: &CounterBlock, updated: &CounterBlock) -> String {
+    let delta = |locked: &Count, updated: &Count| {
+        format!("{:+}", updated.unsafe_ as i64 - locked.unsafe_ as i64)
+    };
+    format!(
+        "{: <10} {: <12} {: <6} {: <7} {: <7}",
+        delta(&locked.functions, &updated.functions),
+        delta(&locked.exprs, &updated.exprs),
+        delta(&locked.item_impls, &updated.item_impls),
+        delta(&locked.item_traits, &updated.item_traits),
+        delta(&locked.methods, &updated.methods),
+    )
+}
+
+fn versions(versions: &[String]) -> String {
+    if versions.is_empty() {
+        String::from("none")
+    } else {
+        versions.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod update_tests {
+    use super::*;
+
+    use crate::rs_file::RsFileMetricsWrapper;
+    use crate::scan::test_utils::create_versioned_package_id;
+
+    use rstest::*;
+    use std::path::PathBuf;
+
+    #[rstest]
+    fn changed_package_ids_test() {
+        let kept = create_versioned_package_id("kept", "1.0.0");
+        let old = create_versioned_package_id("updated", "1.0.0");
+        let new = create_versioned_package_id("updated", "1.1.0");
+        let added = create_versioned_package_id("added", "0.1.0");
+
+        let (removed_ids, added_ids) = changed_package_ids(
+            vec![kept, old].into_iter().collect(),
+            vec![kept, new, added].into_iter().collect(),
+        );
+
+        assert_eq!(removed_ids, vec![old]);
+        assert_eq!(added_ids, vec![added, new]);
+    }
+
+    #[rstest]
+    fn find_update_deltas_test() {
+        let old = create_versioned_package_id("updated", "1.0.0");
+        let new = create_versioned_package_id("updated", "1.1.0");
+        let added = create_versioned_package_id("added", "0.1.0");
+        let locked_context = create_geiger_context(&[(old, 2)]);
+        let updated_context = create_geiger_context(&[(new, 5), (added, 1)]);
+
+        let update_deltas = find_update_deltas(
+            &[old],
+            &[added, new],
+            &locked_context,
+            &updated_context,
+        );
+
+        assert_eq!(update_deltas.len(), 2);
+        assert_eq!(update_deltas[0].name, "added");
+        assert!(update_deltas[0].locked_versions.is_empty());
+        assert_eq!(update_deltas[0].updated.exprs.unsafe_, 1);
+        assert_eq!(update_deltas[1].locked_versions, vec!["1.0.0"]);
+        assert_eq!(update_deltas[1].updated_versions, vec!["1.1.0"]);
+        assert_eq!(update_deltas[1].locked.exprs.unsafe_, 2);
+        assert_eq!(update_deltas[1].updated.exprs.unsafe_, 5);
+    }
+
+    #[rstest]
+    fn construct_update_lines_test() {
+        let mut update_delta = UpdateDelta {
+            name: String::from("updated"),
+            locked_versions: vec![String::from("1.0.0")],
+            updated_versions: vec![String::from("1.1.0")],
+            ..Default::default()
+        };
+        update_delta.locked.exprs.unsafe_ = 5;
+        update_delta.updated.exprs.unsafe_ = 2;
+        update_delta.updated.functions.unsafe_ = 1;
+
+        let update_lines = construct_update_lines(&[update_delta]);
+
+        assert_eq!(update_lines.len(), 5);
+        assert_eq!(
+            update_lines[2],
+            "+1         -3           +0     +0      +0       updated 1.0.0 \
+             -> 1.1.0"
+        );
+        assert!(update_lines[4].ends_with("Total, 1 package(s) changed"));
+        assert_eq!(
+            construct_update_lines(&[]),
+            vec!["No dependency updates are available."]
+        );
+    }
+
+    fn create_geiger_context(
+        package_unsafe_exprs: &[(PackageId, u64)],
+    ) -> GeigerContext {
+        let mut geiger_context = GeigerContext {
+            package_id_to_metrics: HashMap::new(),
+            used_but_not_scanned: None,
+            warnings: vec![],
+        };
+        for (package_id, unsafe_exprs) in package_unsafe_exprs {
+            let mut wrapper = RsFileMetricsWrapper::default();
+            wrapper.metrics.counters.exprs.unsafe_ = *unsafe_exprs;
+            geiger_context
+                .package_id_to_metrics
+                .entry(*package_id)
+                .or_default()
+                .rs_path_to_metrics
+                .insert(
+                    PathBuf::from(format!("{}/src/lib.rs", package_id.name())),
+                    wrapper,
+                );
+        }
+        geiger_context
+    }
+}
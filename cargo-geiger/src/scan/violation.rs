@@ -0,0 +1,199 @@
+use crate::args::Args;
+
+use cargo::{CliError, CliResult};
+use std::error::Error;
+
+/// Prints the violations of a check and fails with the error if there are
+/// any. With `--report-only` the violations are printed as warnings, or as
+/// GitHub Actions warning annotations with `--github-output`, and the check
+/// passes.
+pub fn report_violations<E>(
+    args: &Args,
+    violations: &[String],
+    error: E,
+) -> CliResult
+where
+    E: Error + Send + Sync + 'static,
+{
+    print_violations(args, args.report_only, violations);
+    if violations.is_empty() || args.report_only {
+        Ok(())
+    } else {
+        Err(CliError::new(anyhow::Error::new(error), 1))
+    }
+}
+
+/// The violations found by one check, with the error the check fails with,
+/// or without one for violations that are only ever warned about.
+pub struct Check {
+    violations: Vec<String>,
+    error: Option<anyhow::Error>,
+}
+
+impl Check {
+    pub fn new<E>(violations: Vec<String>, error: E) -> Check
+    where
+        E: Error + Send + Sync + 'static,
+    {
+        Check {
+            violations,
+            error: Some(anyhow::Error::new(error)),
+        }
+    }
+
+    /// Violations that are printed as warnings and never fail the scan.
+    pub fn warnings(violations: Vec<String>) -> Check {
+        Check {
+            violations,
+            error: None,
+        }
+    }
+}
+
+/// The checks of a scan, collected so that their combined outcome is known
+/// before anything recording it is written.
+#[derive(Default)]
+pub struct Checks {
+    checks: Vec<Check>,
+}
+
+impl Checks {
+    pub fn push(&mut self, check: Check) {
+        self.checks.push(check);
+    }
+
+    pub fn extend(&mut self, checks: Checks) {
+        self.checks.extend(checks.checks);
+    }
+
+    /// No check found a violation, also when `--report-only` lets the scan
+    /// pass anyway.
+    pub fn passed(&self) -> bool {
+        self.checks
+            .iter()
+            .filter(|check| check.error.is_some())
+            .all(|check| check.violations.is_empty())
+    }
+
+    /// The violations of every check, in the order the checks were made.
+    pub fn violations(&self) -> Vec<String> {
+        self.checks
+            .iter()
+            .filter(|check| check.error.is_some())
+            .flat_map(|check| check.violations.iter().cloned())
+            .collect()
+    }
+
+    /// Prints the violations of every check like `report_violations`, and
+    /// fails with the error of the first check that found any.
+    pub fn report(self, args: &Args) -> CliResult {
+        for check in &self.checks {
+            print_violations(
+                args,
+                args.report_only || check.error.is_none(),
+                &check.violations,
+            );
+        }
+        let error = self
+            .checks
+            .into_iter()
+            .filter(|check| !check.violations.is_empty())
+            .find_map(|check| check.error);
+        match error {
+            Some(error) if !args.report_only => Err(CliError::new(error, 1)),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn print_violations(args: &Args, report_only: bool, violations: &[String]) {
+    for violation in violations {
+        eprintln!(
+            "{}",
+            construct_violation_line(
+                report_only,
+                args.github_output,
+                violation
+            )
+        );
+    }
+}
+
+fn construct_violation_line(
+    report_only: bool,
+    github_output: bool,
+    violation: &str,
+) -> String {
+    if !report_only {
+        format!("ERROR: {}", violation)
+    } else if github_output {
+        format!(
+            "::warning title=cargo-geiger::{}",
+            escape_annotation_message(violation)
+        )
+    } else {
+        format!("WARNING: {}", violation)
+    }
+}
+
+/// Escapes the characters GitHub Actions gives a meaning to in the message of
+/// a workflow command.
+fn escape_annotation_message(message: &str) -> String {
+    message
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+#[cfg(test)]
+mod violation_tests {
+    use super::*;
+
+    use rstest::*;
+    use std::fmt;
+
+    #[rstest(
+        input_report_only,
+        input_github_output,
+        expected_line,
+        case(false, false, "ERROR: 2.50% of foo"),
+        case(false, true, "ERROR: 2.50% of foo"),
+        case(true, false, "WARNING: 2.50% of foo"),
+        case(true, true, "::warning title=cargo-geiger::2.50%25 of foo")
+    )]
+    fn construct_violation_line_test(
+        input_report_only: bool,
+        input_github_output: bool,
+        expected_line: &str,
+    ) {
+        assert_eq!(
+            construct_violation_line(
+                input_report_only,
+                input_github_output,
+                "2.50% of foo"
+            ),
+            expected_line
+        );
+    }
+
+    #[rstest]
+    fn checks_test() {
+        let mut checks = Checks::default();
+        checks.push(Check::new(vec![], fmt::Error));
+        checks.push(Check::warnings(vec![String::from("foo")]));
+        assert!(checks.passed());
+
+        checks.push(Check::new(vec![String::from("bar")], fmt::Error));
+        checks.push(Check::warnings(vec![String::from("baz")]));
+        assert!(!checks.passed());
+        assert_eq!(checks.violations(), vec![String::from("bar")]);
+    }
+
+    #[rstest]
+    fn escape_annotation_message_test() {
+        assert_eq!(
+            escape_annotation_message("50% of\r\nfoo"),
+            "50%25 of%0D%0Afoo"
+        );
+    }
+}
@@ -0,0 +1,437 @@
+use crate::graph::Graph;
+
+use cargo::core::dependency::DepKind;
+use cargo::core::{PackageId, PackageIdSpec, PackageSet};
+use cargo::util::CargoResult;
+use cargo::{CliError, CliResult};
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use petgraph::visit::EdgeRef;
+use petgraph::EdgeDirection;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// The number of paths listed before the rest are left out, the number of
+/// paths can grow exponentially with the depth of the graph.
+const MAX_DEPENDENCY_PATHS: usize = 100;
+
+/// Prints every dependency path from the root packages to the package
+/// matching the spec, for `--why`. Each dependency is annotated with the
+/// kind of the dependency, what the manifest of the dependent requests, and
+/// the features the resolve enables for the package.
+pub fn scan_why(
+    package_set: &PackageSet,
+    root_package_ids: &[PackageId],
+    graph: &Graph,
+    spec: &str,
+) -> CliResult {
+    let package_id = PackageIdSpec::parse(spec)
+        .and_then(|package_id_spec| {
+            package_id_spec.query(graph.nodes.keys().cloned())
+        })
+        .map_err(|e| {
+            CliError::new(e.context(format!("could not explain `{}`", spec)), 1)
+        })?;
+    let paths = find_dependency_paths(
+        graph,
+        root_package_ids,
+        package_id,
+        MAX_DEPENDENCY_PATHS + 1,
+    );
+
+    let mut edge_descriptions = HashMap::new();
+    for edge_index in paths.iter().flat_map(|path| &path.edges) {
+        if edge_descriptions.contains_key(edge_index) {
+            continue;
+        }
+        let (from_index, to_index) = graph
+            .graph
+            .edge_endpoints(*edge_index)
+            .expect("A path only contains edges of the graph");
+        let kind = graph.graph[*edge_index];
+        let request = dependency_request(
+            package_set,
+            graph.graph[from_index].id,
+            graph.graph[to_index].id,
+            kind,
+        )?;
+        edge_descriptions.insert(*edge_index, describe_edge(kind, &request));
+    }
+
+    println!(
+        "{} {} is reached through {}{} dependency path(s):",
+        package_id.name(),
+        package_id.version(),
+        if paths.len() > MAX_DEPENDENCY_PATHS {
+            "more than "
+        } else {
+            ""
+        },
+        paths.len().min(MAX_DEPENDENCY_PATHS)
+    );
+    for path in paths.iter().take(MAX_DEPENDENCY_PATHS) {
+        println!();
+        for line in construct_path_lines(graph, path, &edge_descriptions) {
+            println!("{}", line);
+        }
+    }
+    if paths.len() > MAX_DEPENDENCY_PATHS {
+        println!();
+        println!("Only the first {} paths are listed.", MAX_DEPENDENCY_PATHS);
+    }
+    Ok(())
+}
+
+/// A path from a root package along the dependency edges.
+#[derive(Debug, PartialEq)]
+struct DependencyPath {
+    root: NodeIndex,
+    edges: Vec<EdgeIndex>,
+}
+
+/// What the manifest of a package requests of one of its dependencies.
+#[derive(Debug, PartialEq)]
+struct DependencyRequest {
+    optional: bool,
+    default_features: bool,
+    features: BTreeSet<String>,
+}
+
+/// The paths from the root packages to the target, at most `max_paths`.
+/// A path never visits a package twice, so that cycles through dev
+/// dependencies end. The dependencies are followed in the order of their
+/// package ids, to list the paths in a stable order.
+fn find_dependency_paths(
+    graph: &Graph,
+    root_package_ids: &[PackageId],
+    target: PackageId,
+    max_paths: usize,
+) -> Vec<DependencyPath> {
+    let target_index = graph.nodes[&target];
+    let mut reaching_target = HashSet::new();
+    reaching_target.insert(target_index);
+    let mut pending = vec![target_index];
+    while let Some(index) = pending.pop() {
+        for dependent_index in graph
+            .graph
+            .neighbors_directed(index, EdgeDirection::Incoming)
+        {
+            if reaching_target.insert(dependent_index) {
+                pending.push(dependent_index);
+            }
+        }
+    }
+
+    let mut paths = Vec::new();
+    for root_package_id in root_package_ids {
+        let root = graph.nodes[root_package_id];
+        if !reaching_target.contains(&root) {
+            continue;
+        }
+        let mut path_walk = PathWalk {
+            graph,
+            target_index,
+            reaching_target: &reaching_target,
+            on_path: HashSet::new(),
+            edges: Vec::new(),
+            max_paths,
+        };
+        path_walk.walk(root, root, &mut paths);
+    }
+    paths
+}
+
+/// The state of the depth first walk of `find_dependency_paths`.
+struct PathWalk<'a> {
+    graph: &'a Graph,
+    target_index: NodeIndex,
+    reaching_target: &'a HashSet<NodeIndex>,
+    on_path: HashSet<NodeIndex>,
+    edges: Vec<EdgeIndex>,
+    max_paths: usize,
+}
+
+impl PathWalk<'_> {
+    fn walk(
+        &mut self,
+        root: NodeIndex,
+        index: NodeIndex,
+        paths: &mut Vec<DependencyPath>,
+    ) {
+        if paths.len() >= self.max_paths {
+            return;
+        }
+        if index == self.target_index {
+            paths.push(DependencyPath {
+                root,
+                edges: self.edges.clone(),
+            });
+            return;
+        }
+        self.on_path.insert(index);
+        let mut dependency_edges = self
+            .graph
+            .graph
+            .edges_directed(index, EdgeDirection::Outgoing)
+            .filter(|edge| {
+                self.reaching_target.contains(&edge.target())
+                    && !self.on_path.contains(&edge.target())
+            })
+            .map(|edge| (edge.id(), edge.target()))
+            .collect::<Vec<(EdgeIndex, NodeIndex)>>();
+        dependency_edges.sort_by_key(|(edge_index, dependency_index)| {
+            (
+                self.graph.graph[*dependency_index].id,
+                dep_kind_name(self.graph.graph[*edge_index]),
+            )
+        });
+        for (edge_index, dependency_index) in dependency_edges {
+            self.edges.push(edge_index);
+            self.walk(root, dependency_index, paths);
+            self.edges.pop();
+        }
+        self.on_path.remove(&index);
+    }
+}
+
+/// Merges the dependency declarations of the package matching the
+/// dependency, a package can declare it more than once for different
+/// targets.
+fn dependency_request(
+    package_set: &PackageSet,
+    package_id: PackageId,
+    dependency_id: PackageId,
+    kind: DepKind,
+) -> CargoResult<DependencyRequest> {
+    let package = package_set.get_one(package_id)?;
+    let mut request = DependencyRequest {
+        optional: false,
+        default_features: false,
+        features: BTreeSet::new(),
+    };
+    for dependency in package.dependencies().iter().filter(|dependency| {
+        dependency.kind() == kind && dependency.matches_id(dependency_id)
+    }) {
+        request.optional |= dependency.is_optional();
+        request.default_features |= dependency.uses_default_features();
+        request.features.extend(
+            dependency
+                .features()
+                .iter()
+                .map(|feature| feature.to_string()),
+        );
+    }
+    Ok(request)
+}
+
+fn describe_edge(kind: DepKind, request: &DependencyRequest) -> String {
+    let mut parts = vec![String::from(dep_kind_name(kind))];
+    if request.optional {
+        parts.push(String::from("optional"));
+    }
+    if !request.default_features {
+        parts.push(String::from("no default features"));
+    }
+    if !request.features.is_empty() {
+        parts.push(format!(
+            "requests {}",
+            request
+                .features
+                .iter()
+                .cloned()
+                .collect::<Vec<String>>()
+                .join(", ")
+        ));
+    }
+    parts.join(", ")
+}
+
+fn dep_kind_name(kind: DepKind) -> &'static str {
+    match kind {
+        DepKind::Normal => "normal",
+        DepKind::Development => "dev",
+        DepKind::Build => "build",
+    }
+}
+
+/// The root package, followed by a line for each dependency of the path
+/// with the description of its edge and its enabled features.
+fn construct_path_lines(
+    graph: &Graph,
+    path: &DependencyPath,
+    edge_descriptions: &HashMap<EdgeIndex, String>,
+) -> Vec<String> {
+    let mut lines = vec![format!("    {}", describe_package(graph, path.root))];
+    for edge_index in &path.edges {
+        let (_, dependency_index) = graph
+            .graph
+            .edge_endpoints(*edge_index)
+            .expect("A path only contains edges of the graph");
+        lines.push(format!(
+            "    -> {} [{}]",
+            describe_package(graph, dependency_index),
+            edge_descriptions[edge_index]
+        ));
+    }
+    lines
+}
+
+fn describe_package(graph: &Graph, index: NodeIndex) -> String {
+    let node = &graph.graph[index];
+    if node.features.is_empty() {
+        format!("{} {}", node.id.name(), node.id.version())
+    } else {
+        format!(
+            "{} {} (features: {})",
+            node.id.name(),
+            node.id.version(),
+            node.features.join(", ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod why_tests {
+    use super::*;
+
+    use crate::graph::Node;
+    use crate::scan::test_utils::create_package_id;
+
+    use rstest::*;
+
+    #[rstest]
+    fn find_dependency_paths_test() {
+        let root = create_package_id("root");
+        let a = create_package_id("a");
+        let b = create_package_id("b");
+        let target = create_package_id("target");
+        let unrelated = create_package_id("unrelated");
+        let graph = create_graph(
+            &[root, a, b, target, unrelated],
+            &[
+                (root, a, DepKind::Normal),
+                (root, b, DepKind::Build),
+                (root, unrelated, DepKind::Normal),
+                (a, target, DepKind::Normal),
+                (b, target, DepKind::Normal),
+                (target, a, DepKind::Development),
+            ],
+        );
+
+        let paths = find_dependency_paths(&graph, &[root], target, 10);
+        let path_names = paths
+            .iter()
+            .map(|path| {
+                path.edges
+                    .iter()
+                    .map(|edge_index| {
+                        let (_, index) =
+                            graph.graph.edge_endpoints(*edge_index).unwrap();
+                        graph.graph[index].id.name().to_string()
+                    })
+                    .collect::<Vec<String>>()
+            })
+            .collect::<Vec<Vec<String>>>();
+
+        assert_eq!(path_names, vec![vec!["a", "target"], vec!["b", "target"]]);
+        assert_eq!(find_dependency_paths(&graph, &[root], target, 1).len(), 1);
+        assert!(
+            find_dependency_paths(&graph, &[unrelated], target, 10).is_empty()
+        );
+    }
+
+    #[rstest]
+    fn construct_path_lines_test() {
+        let root = create_package_id("root");
+        let target = create_package_id("target");
+        let mut graph =
+            create_graph(&[root, target], &[(root, target, DepKind::Normal)]);
+        graph.graph[graph.nodes[&target]].features =
+            vec![String::from("default"), String::from("std")];
+        let paths = find_dependency_paths(&graph, &[root], target, 10);
+        let mut edge_descriptions = HashMap::new();
+        edge_descriptions.insert(
+            paths[0].edges[0],
+            describe_edge(
+                DepKind::Normal,
+                &DependencyRequest {
+                    optional: true,
+                    default_features: false,
+                    features: vec![String::from("std")].into_iter().collect(),
+                },
+            ),
+        );
+
+        assert_eq!(
+            construct_path_lines(&graph, &paths[0], &edge_descriptions),
+            vec![
+                String::from("    root 1.2.3"),
+                String::from(
+                    "    -> target 1.2.3 (features: default, std) [normal, \
+                     optional, no default features, requests std]"
+                ),
+            ]
+        );
+    }
+
+    #[rstest(
+        input_kind,
+        input_request,
+        expected_description,
+        case(
+            DepKind::Normal,
+            DependencyRequest {
+                optional: false,
+                default_features: true,
+                features: BTreeSet::new(),
+            },
+            "normal"
+        ),
+        case(
+            DepKind::Build,
+            DependencyRequest {
+                optional: true,
+                default_features: true,
+                features: vec![String::from("alloc"), String::from("std")]
+                    .into_iter()
+                    .collect(),
+            },
+            "build, optional, requests alloc, std"
+        )
+    )]
+    fn describe_edge_test(
+        input_kind: DepKind,
+        input_request: DependencyRequest,
+        expected_description: &str,
+    ) {
+        assert_eq!(
+            describe_edge(input_kind, &input_request),
+            expected_description
+        );
+    }
+
+    fn create_graph(
+        package_ids: &[PackageId],
+        edges: &[(PackageId, PackageId, DepKind)],
+    ) -> Graph {
+        let mut graph = Graph {
+            graph: petgraph::Graph::new(),
+            nodes: HashMap::new(),
+            edge_features: HashMap::new(),
+        };
+        for package_id in package_ids {
+            let index = graph.graph.add_node(Node {
+                id: *package_id,
+                features: vec![],
+                patched: false,
+                local_override: false,
+                yanked: false,
+            });
+            graph.nodes.insert(*package_id, index);
+        }
+        for (from, to, kind) in edges {
+            graph
+                .graph
+                .add_edge(graph.nodes[from], graph.nodes[to], *kind);
+        }
+        graph
+    }
+}
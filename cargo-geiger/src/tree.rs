@@ -1,16 +1,56 @@
 pub mod traversal;
 
-use crate::format::print_config::{Prefix, PrintConfig};
-use crate::format::Charset;
+pub use crate::format::print_config::Prefix;
+pub use crate::format::Charset;
 
 use cargo::core::dependency::DepKind;
 use cargo::core::PackageId;
+use petgraph::EdgeDirection;
+
+/// How `traversal::walk_dependency_tree` walks and draws the tree.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TreeOptions {
+    /// Don't truncate dependencies that have already been displayed.
+    pub all: bool,
+
+    /// The characters the tree vines are drawn with.
+    pub charset: Charset,
+
+    /// `Outgoing` walks the dependencies of the root package, `Incoming` its
+    /// dependents.
+    pub direction: EdgeDirection,
+
+    /// What the lines of the tree start with.
+    pub prefix: Prefix,
+}
+
+impl Default for TreeOptions {
+    fn default() -> Self {
+        TreeOptions {
+            all: false,
+            charset: Charset::Utf8,
+            direction: EdgeDirection::Outgoing,
+            prefix: Prefix::Indent,
+        }
+    }
+}
 
 /// A step towards decoupling some parts of the table-tree printing from the
 /// dependency graph traversal.
+#[non_exhaustive]
 pub enum TextTreeLine {
-    /// A text line for a package
-    Package { id: PackageId, tree_vines: String },
+    /// A text line for a package, marked when it is taken from a `[patch]` or
+    /// `[replace]`, when that is a local path, and when its version is yanked.
+    /// `via_feature` is the non-default feature of the dependent that the
+    /// edge to the package only exists through.
+    Package {
+        id: PackageId,
+        patched: bool,
+        local_override: bool,
+        yanked: bool,
+        via_feature: Option<String>,
+        tree_vines: String,
+    },
     /// There are extra dependencies coming and we should print a group header,
     /// eg. "[build-dependencies]".
     ExtraDepsGroup { kind: DepKind, tree_vines: String },
@@ -24,13 +64,13 @@ pub struct TreeSymbols {
     pub right: &'static str,
 }
 
-fn construct_tree_vines_string(
-    levels_continue: &mut Vec<bool>,
-    print_config: &PrintConfig,
+pub fn construct_tree_vines_string(
+    levels_continue: &[bool],
+    tree_options: &TreeOptions,
 ) -> String {
-    let tree_symbols = get_tree_symbols(print_config.charset);
+    let tree_symbols = get_tree_symbols(tree_options.charset);
 
-    match print_config.prefix {
+    match tree_options.prefix {
         Prefix::Depth => format!("{} ", levels_continue.len()),
         Prefix::Indent => {
             let mut buffer = String::new();
@@ -78,12 +118,6 @@ const UTF8_TREE_SYMBOLS: TreeSymbols = TreeSymbols {
 mod tree_tests {
     use super::*;
 
-    use crate::format::pattern::Pattern;
-    use crate::format::Charset;
-
-    use cargo::core::shell::Verbosity;
-    use geiger::IncludeTests;
-    use petgraph::EdgeDirection;
     use rstest::*;
 
     #[rstest(
@@ -106,11 +140,15 @@ mod tree_tests {
         input_prefix: Prefix,
         expected_tree_vines_string: &str
     ) {
-        let mut levels_continue = vec![true, false, true];
+        let levels_continue = vec![true, false, true];
 
-        let print_config = construct_print_config(input_prefix);
+        let tree_options = TreeOptions {
+            charset: Charset::Ascii,
+            prefix: input_prefix,
+            ..TreeOptions::default()
+        };
         let tree_vines_string =
-            construct_tree_vines_string(&mut levels_continue, &print_config);
+            construct_tree_vines_string(&levels_continue, &tree_options);
 
         assert_eq!(tree_vines_string, expected_tree_vines_string);
     }
@@ -133,19 +171,4 @@ mod tree_tests {
     ) {
         assert_eq!(get_tree_symbols(input_charset), expected_tree_symbols);
     }
-
-    fn construct_print_config(prefix: Prefix) -> PrintConfig {
-        let pattern = Pattern::try_build("{p}").unwrap();
-        PrintConfig {
-            all: false,
-            verbosity: Verbosity::Verbose,
-            direction: EdgeDirection::Outgoing,
-            prefix,
-            format: pattern,
-            charset: Charset::Ascii,
-            allow_partial_results: false,
-            include_tests: IncludeTests::Yes,
-            output_format: None,
-        }
-    }
 }
@@ -1,6 +1,5 @@
-use crate::format::print_config::{Prefix, PrintConfig};
 use crate::graph::{Graph, Node};
-use crate::tree::{get_tree_symbols, TextTreeLine};
+use crate::tree::{get_tree_symbols, Prefix, TextTreeLine, TreeOptions};
 
 use super::construct_tree_vines_string;
 
@@ -19,26 +18,32 @@ use std::collections::{HashMap, HashSet};
 pub fn walk_dependency_tree(
     root_pack_id: PackageId,
     graph: &Graph,
-    print_config: &PrintConfig,
+    tree_options: &TreeOptions,
 ) -> Vec<TextTreeLine> {
     let mut visited_deps = HashSet::new();
     let mut levels_continue = vec![];
     let node = &graph.graph[graph.nodes[&root_pack_id]];
     walk_dependency_node(
         node,
+        None,
         graph,
         &mut visited_deps,
         &mut levels_continue,
-        print_config,
+        tree_options,
     )
 }
 
+/// The dependencies of the package by kind, each with the feature its edge
+/// only exists through, if any.
 fn construct_dependency_type_nodes_hashmap<'a>(
     graph: &'a Graph,
     package: &Node,
-    print_config: &PrintConfig,
-) -> HashMap<DepKind, Vec<&'a Node>> {
-    let mut dependency_type_nodes: HashMap<DepKind, Vec<&Node>> = [
+    tree_options: &TreeOptions,
+) -> HashMap<DepKind, Vec<(&'a Node, Option<&'a str>)>> {
+    let mut dependency_type_nodes: HashMap<
+        DepKind,
+        Vec<(&Node, Option<&str>)>,
+    > = [
         (DepKind::Build, vec![]),
         (DepKind::Development, vec![]),
         (DepKind::Normal, vec![]),
@@ -49,17 +54,17 @@ fn construct_dependency_type_nodes_hashmap<'a>(
 
     for edge in graph
         .graph
-        .edges_directed(graph.nodes[&package.id], print_config.direction)
+        .edges_directed(graph.nodes[&package.id], tree_options.direction)
     {
-        let dependency = match print_config.direction {
+        let dependency = match tree_options.direction {
             EdgeDirection::Incoming => &graph.graph[edge.source()],
             EdgeDirection::Outgoing => &graph.graph[edge.target()],
         };
 
-        dependency_type_nodes
-            .get_mut(edge.weight())
-            .unwrap()
-            .push(dependency);
+        dependency_type_nodes.get_mut(edge.weight()).unwrap().push((
+            dependency,
+            graph.edge_features.get(&edge.id()).map(String::as_str),
+        ));
     }
 
     dependency_type_nodes
@@ -67,22 +72,22 @@ fn construct_dependency_type_nodes_hashmap<'a>(
 
 fn walk_dependency_kind(
     kind: DepKind,
-    deps: &mut Vec<&Node>,
+    deps: &mut Vec<(&Node, Option<&str>)>,
     graph: &Graph,
     visited_deps: &mut HashSet<PackageId>,
     levels_continue: &mut Vec<bool>,
-    print_config: &PrintConfig,
+    tree_options: &TreeOptions,
 ) -> Vec<TextTreeLine> {
     if deps.is_empty() {
         return Vec::new();
     }
 
     // Resolve uses Hash data types internally but we want consistent output ordering
-    deps.sort_by_key(|n| n.id);
+    deps.sort_by_key(|(n, _)| n.id);
 
-    let tree_symbols = get_tree_symbols(print_config.charset);
+    let tree_symbols = get_tree_symbols(tree_options.charset);
     let mut output = Vec::new();
-    if let Prefix::Indent = print_config.prefix {
+    if let Prefix::Indent = tree_options.prefix {
         match kind {
             DepKind::Normal => (),
             _ => {
@@ -97,14 +102,15 @@ fn walk_dependency_kind(
     }
 
     let mut node_iterator = deps.iter().peekable();
-    while let Some(dependency) = node_iterator.next() {
+    while let Some((dependency, via_feature)) = node_iterator.next() {
         levels_continue.push(node_iterator.peek().is_some());
         output.append(&mut walk_dependency_node(
             dependency,
+            *via_feature,
             graph,
             visited_deps,
             levels_continue,
-            print_config,
+            tree_options,
         ));
         levels_continue.pop();
     }
@@ -113,16 +119,21 @@ fn walk_dependency_kind(
 
 fn walk_dependency_node(
     package: &Node,
+    via_feature: Option<&str>,
     graph: &Graph,
     visited_deps: &mut HashSet<PackageId>,
     levels_continue: &mut Vec<bool>,
-    print_config: &PrintConfig,
+    tree_options: &TreeOptions,
 ) -> Vec<TextTreeLine> {
-    let new = print_config.all || visited_deps.insert(package.id);
-    let tree_vines = construct_tree_vines_string(levels_continue, print_config);
+    let new = tree_options.all || visited_deps.insert(package.id);
+    let tree_vines = construct_tree_vines_string(levels_continue, tree_options);
 
     let mut all_out_text_tree_lines = vec![TextTreeLine::Package {
         id: package.id,
+        patched: package.patched,
+        local_override: package.local_override,
+        yanked: package.yanked,
+        via_feature: via_feature.map(String::from),
         tree_vines,
     }];
 
@@ -131,16 +142,17 @@ fn walk_dependency_node(
     }
 
     let mut dependency_type_nodes =
-        construct_dependency_type_nodes_hashmap(graph, package, print_config);
+        construct_dependency_type_nodes_hashmap(graph, package, tree_options);
 
-    for (dep_kind, nodes) in dependency_type_nodes.iter_mut() {
+    // Iterating the hashmap would order the kinds differently between runs.
+    for dep_kind in &[DepKind::Normal, DepKind::Build, DepKind::Development] {
         let mut dep_kind_out = walk_dependency_kind(
             *dep_kind,
-            nodes,
+            dependency_type_nodes.get_mut(dep_kind).unwrap(),
             graph,
             visited_deps,
             levels_continue,
-            print_config,
+            tree_options,
         );
 
         all_out_text_tree_lines.append(&mut dep_kind_out);
@@ -4,7 +4,7 @@
 use assert_cmd::prelude::*;
 use cargo_geiger_serde::{
     Count, CounterBlock, PackageId, PackageInfo, QuickReportEntry, QuickSafetyReport, ReportEntry,
-    SafetyReport, Source, UnsafeInfo,
+    SafetyReport, ScanOptions, Source, UnsafeInfo,
 };
 use insta::assert_snapshot;
 use rstest::rstest;
@@ -154,6 +154,9 @@ impl Test for Test1 {
                 used: CounterBlock {
                     functions: Count { safe: 1, unsafe_: 1 },
                     exprs: Count { safe: 4, unsafe_: 2 },
+                    api_calls: vec![("str::from_utf8_unchecked".into(), 1)].into_iter().collect(),
+                    unsafe_fn_body_exprs: 1,
+                    public_unsafe_fns: 1,
                     ..Default::default()
                 },
                 ..Default::default()
@@ -356,6 +359,8 @@ where
         .arg("--all-features")
         .args(extra_args)
         .current_dir(cx.crate_dir(test_name))
+        // The toolchain cargo runs the tests with is not part of the reports.
+        .env_remove("RUSTUP_TOOLCHAIN")
         .output()
         .expect("failed to run `cargo-geiger`");
     (output, cx)
@@ -450,6 +455,7 @@ fn to_quick_report(report: SafetyReport) -> QuickSafetyReport {
             let quick_entry = QuickReportEntry {
                 package: entry.package,
                 forbids_unsafe: entry.unsafety.forbids_unsafe,
+                entry_points: Default::default(),
             };
             (id, quick_entry)
         })
@@ -463,6 +469,12 @@ fn to_quick_report(report: SafetyReport) -> QuickSafetyReport {
 fn single_entry_safety_report(entry: ReportEntry) -> SafetyReport {
     SafetyReport {
         packages: report_entry_list_to_map(vec![entry]),
+        // The options every test is run with, see `run_geiger_with`.
+        scan_options: Some(ScanOptions {
+            all_targets: true,
+            all_features: true,
+            ..Default::default()
+        }),
         ..Default::default()
     }
 }
@@ -497,6 +509,12 @@ mod external {
                 used: CounterBlock {
                     functions: Count { safe: 4, unsafe_: 0 },
                     exprs: Count { safe: 10, unsafe_: 2 },
+                    api_calls: vec![
+                        ("slice::from_raw_parts".into(), 1),
+                        ("slice::from_raw_parts_mut".into(), 1),
+                    ]
+                    .into_iter()
+                    .collect(),
                     ..Default::default()
                 },
                 ..Default::default()
@@ -577,6 +595,7 @@ mod external {
                     item_impls: Count { safe: 129, unsafe_: 0 },
                     item_traits: Count { safe: 7, unsafe_: 0 },
                     methods: Count { safe: 180, unsafe_: 0 },
+                    ..Default::default()
                 },
                 unused: CounterBlock {
                     functions: Count { safe: 67, unsafe_: 0 },
@@ -584,6 +603,7 @@ mod external {
                     item_impls: Count { safe: 24, unsafe_: 3 },
                     item_traits: Count { safe: 2, unsafe_: 1 },
                     methods: Count { safe: 29, unsafe_: 3 },
+                    ..Default::default()
                 },
                 ..Default::default()
             }
@@ -638,6 +658,7 @@ mod external {
                     ..Default::default()
                 },
                 forbids_unsafe: true,
+                ..Default::default()
             },
         };
         let mut report = single_entry_safety_report(entry);
@@ -718,6 +739,7 @@ mod external {
                     item_impls: Count { safe: 48, unsafe_: 4 },
                     item_traits: Count { safe: 3, unsafe_: 1 },
                     methods: Count { safe: 92, unsafe_: 13 },
+                    ..Default::default()
                 },
                 unused: CounterBlock {
                     functions: Count { safe: 18, unsafe_: 0 },
@@ -725,6 +747,7 @@ mod external {
                     item_impls: Count { safe: 2, unsafe_: 0 },
                     item_traits: Count { safe: 1, unsafe_: 0 },
                     methods: Count { safe: 14, unsafe_: 0 },
+                    ..Default::default()
                 },
                 ..Default::default()
             }
@@ -784,6 +807,7 @@ mod external {
                     item_impls: Count { safe: 9, unsafe_: 0 },
                     item_traits: Count { safe: 1, unsafe_: 0 },
                     methods: Count { safe: 21, unsafe_: 0 },
+                    ..Default::default()
                 },
                 unused: CounterBlock {
                     functions: Count { safe: 22, unsafe_: 0 },
@@ -810,6 +834,8 @@ mod external {
         let entry = ReportEntry {
             package: PackageInfo {
                 dependencies: to_set(vec![super::make_package_id(cx, super::Test1::NAME)]),
+                patched: true,
+                local_override: true,
                 ..PackageInfo::new(num_cpus_package_id(cx))
             },
             unsafety: UnsafeInfo {
@@ -7,16 +7,25 @@
 #![forbid(unsafe_code)]
 #![forbid(warnings)]
 
-use cargo_geiger_serde::CounterBlock;
+use cargo_geiger_serde::{Count, CounterBlock};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::error::Error;
 use std::fmt;
 use std::fs::File;
 use std::io;
 use std::io::Read;
+use std::ops::Range;
 use std::path::Path;
 use std::path::PathBuf;
 use std::string::FromUtf8Error;
-use syn::{visit, Expr, ImplItemMethod, ItemFn, ItemImpl, ItemMod, ItemTrait};
+use syn::spanned::Spanned;
+use syn::{
+    visit, Attribute, Expr, ForeignItem, ForeignItemStatic, ImplItemConst,
+    ImplItemMethod, ItemConst, ItemFn, ItemForeignMod, ItemImpl, ItemMod,
+    ItemStatic, ItemStruct, ItemTrait, ItemUnion, ItemUse, Macro, Member,
+    TraitItemConst, UseTree, Visibility,
+};
 
 #[derive(Debug)]
 pub enum ScanFileError {
@@ -35,26 +44,220 @@ impl fmt::Display for ScanFileError {
     }
 }
 
+/// A line and column position within a source file.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Deserialize,
+    Eq,
+    Hash,
+    Ord,
+    PartialEq,
+    PartialOrd,
+    Serialize,
+)]
+pub struct Location {
+    /// 1-indexed line number.
+    pub line: usize,
+
+    /// 0-indexed column number, in UTF-8 characters.
+    pub column: usize,
+}
+
+impl From<proc_macro2::Span> for Location {
+    fn from(span: proc_macro2::Span) -> Self {
+        let start = span.start();
+        Location {
+            line: start.line,
+            column: start.column,
+        }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// The kind of an unsafe item, one per unsafe counter of `CounterBlock`.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Eq,
+    Hash,
+    Ord,
+    PartialEq,
+    PartialOrd,
+    Serialize,
+)]
+pub enum UnsafeKind {
+    Function,
+    Expression,
+    Impl,
+    Trait,
+    Method,
+}
+
+impl fmt::Display for UnsafeKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            UnsafeKind::Function => "fn",
+            UnsafeKind::Expression => "expr",
+            UnsafeKind::Impl => "impl",
+            UnsafeKind::Trait => "trait",
+            UnsafeKind::Method => "method",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A single unsafe function, expression, impl, trait or method, counted as
+/// unsafe in `RsFileMetrics::counters`.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Eq,
+    Hash,
+    Ord,
+    PartialEq,
+    PartialOrd,
+    Serialize,
+)]
+pub struct UnsafeItem {
+    pub location: Location,
+    pub kind: UnsafeKind,
+}
+
 /// Scan result for a single `.rs` file.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct RsFileMetrics {
     /// Metrics storage.
     pub counters: CounterBlock,
 
     /// This file is decorated with `#![forbid(unsafe_code)]`
     pub forbids_unsafe: bool,
+
+    /// Locations of all inline assembly macro invocations, `asm!`,
+    /// `global_asm!` etc.
+    #[serde(default)]
+    pub asm_locations: Vec<Location>,
+
+    /// Metrics for code guarded by platform specific `cfg` attributes, keyed
+    /// by the `cfg` predicate, e.g. `target_arch = "x86_64"`. The same code
+    /// is also included in `counters`.
+    #[serde(default)]
+    pub platform_counters: BTreeMap<String, CounterBlock>,
+
+    /// Metrics for code guarded by any `cfg` attribute, including feature
+    /// gates, keyed by the `cfg` predicate, e.g. `feature = "nightly"`. The
+    /// same code is also included in `counters`.
+    #[serde(default)]
+    pub cfg_counters: BTreeMap<String, CounterBlock>,
+
+    /// Locations of all `#[allow(unsafe_code)]` and
+    /// `#![allow(unsafe_code)]` attributes.
+    #[serde(default)]
+    pub allow_unsafe_code_locations: Vec<Location>,
+
+    /// Locations of all `unsafe` blocks that are not preceded by a
+    /// `// SAFETY:` comment.
+    #[serde(default)]
+    pub undocumented_unsafe_blocks: Vec<Location>,
+
+    /// Locations of the `unsafe` keyword of all unsafe blocks, functions,
+    /// methods, impls and traits.
+    #[serde(default)]
+    pub unsafe_locations: Vec<Location>,
+
+    /// Every unsafe item counted in `counters`, in the order it was visited.
+    #[serde(default)]
+    pub unsafe_items: Vec<UnsafeItem>,
+
+    /// Source files pulled into this file by `#[path = "..."]` module
+    /// declarations and `include!` invocations, these can live outside the
+    /// directory tree of the package.
+    #[serde(default)]
+    pub included_files: Vec<IncludedFile>,
+
+    /// This file is marked as generated with an `@generated` comment in its
+    /// leading comment lines, like the output of bindgen or prost.
+    #[serde(default)]
+    pub generated_marker: bool,
 }
 
+/// A source file pulled into the including file by a module declaration with
+/// a `#[path = "..."]` attribute, or by `include!`.
+#[derive(
+    Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize,
+)]
+pub enum IncludedFile {
+    /// A path relative to the directory of the including file, from
+    /// `include!`.
+    Relative(PathBuf),
+
+    /// The path of a `#[path]` module declared inside the inline modules
+    /// `inline_modules`, e.g. `a/b` for `mod a { mod b { ... } }`. It is
+    /// relative to the directory of the including file when not inside an
+    /// inline module. Otherwise it is relative to the directory of the
+    /// inline modules, which is below the directory of the including file
+    /// for `mod.rs` files and crate roots, and below a directory named after
+    /// the including file for other files.
+    ModulePath {
+        inline_modules: PathBuf,
+        path: PathBuf,
+    },
+
+    /// A path appended to the value of an environment variable set by cargo,
+    /// e.g. `include!(concat!(env!("OUT_DIR"), "/generated.rs"))`.
+    EnvRelative { var: String, suffix: String },
+}
+
+/// The high-risk APIs that are watched by default, calls to these are counted
+/// per API. Calls are matched against the trailing path segments, or only the
+/// function name for method calls and imported functions.
+pub const DEFAULT_UNSAFE_APIS: [&str; 16] = [
+    "Box::from_raw",
+    "CStr::from_ptr",
+    "MaybeUninit::assume_init",
+    "String::from_raw_parts",
+    "String::from_utf8_unchecked",
+    "Vec::from_raw_parts",
+    "Vec::set_len",
+    "mem::transmute",
+    "mem::transmute_copy",
+    "mem::uninitialized",
+    "mem::zeroed",
+    "ptr::copy",
+    "ptr::copy_nonoverlapping",
+    "slice::from_raw_parts",
+    "slice::from_raw_parts_mut",
+    "str::from_utf8_unchecked",
+];
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum IncludeTests {
     Yes,
     No,
 }
 
-struct GeigerSynVisitor {
+struct GeigerSynVisitor<'a> {
     /// Count unsafe usage inside tests
     include_tests: IncludeTests,
 
+    /// The high-risk APIs to count calls to, e.g. `mem::transmute`.
+    unsafe_apis: &'a [String],
+
+    /// The paths of the names imported with `use` declarations in the file,
+    /// keyed by the name they are imported as.
+    imports: HashMap<String, Vec<String>>,
+
     /// The resulting data from a single file scan.
     metrics: RsFileMetrics,
 
@@ -66,14 +269,189 @@ struct GeigerSynVisitor {
     /// This is needed since unsafe scopes can be nested and we need to know
     /// when we leave the outmost unsafe scope and get back into a safe scope.
     unsafe_scopes: u32,
+
+    /// The number of nested `unsafe` blocks that the GeigerSynVisitor is
+    /// currently in, the explicit part of `unsafe_scopes`. Expressions in an
+    /// unsafe scope outside of any `unsafe` block are in the body of an
+    /// `unsafe fn`, where unsafe operations do not need a block.
+    unsafe_block_scopes: u32,
+
+    /// The number of nested const evaluation contexts that the
+    /// GeigerSynVisitor is currently in, `const fn` bodies and the
+    /// initializers of `const` and `static` items.
+    const_scopes: u32,
+
+    /// The `cfg` predicates of the scopes that the GeigerSynVisitor is
+    /// currently in, outermost first.
+    cfgs: Vec<String>,
+
+    /// The platform specific `cfg` predicates of the scopes that the
+    /// GeigerSynVisitor is currently in, outermost first.
+    platform_cfgs: Vec<String>,
+
+    /// Locations of all visited `unsafe` blocks. Comments are not part of the
+    /// syntax tree so these are checked against the source text afterwards.
+    unsafe_blocks: Vec<Location>,
+
+    /// True while the visitor is inside an `impl Drop for ...` block.
+    in_drop_impl: bool,
+
+    /// The directories of the inline modules that the GeigerSynVisitor is
+    /// currently in, outermost first, the `#[path]` or the module name.
+    inline_modules: PathBuf,
+
+    /// The names of the `static mut` items declared in the file.
+    static_mut_names: HashSet<String>,
+
+    /// The names of the fields of the unions declared in the file.
+    union_field_names: HashSet<String>,
+
+    /// True while the visitor is at the left-hand side of a plain assignment
+    /// to a union field, writing a union field is safe.
+    union_field_write: bool,
+
+    /// The names of the functions declared in the `extern` blocks of the
+    /// file.
+    ffi_fn_names: FfiFnNames,
 }
 
-impl GeigerSynVisitor {
-    fn new(include_tests: IncludeTests) -> Self {
+impl<'a> GeigerSynVisitor<'a> {
+    fn new(
+        include_tests: IncludeTests,
+        unsafe_apis: &'a [String],
+        imports: HashMap<String, Vec<String>>,
+        static_mut_names: HashSet<String>,
+        union_field_names: HashSet<String>,
+        ffi_fn_names: FfiFnNames,
+    ) -> Self {
         GeigerSynVisitor {
             include_tests,
+            unsafe_apis,
+            imports,
             metrics: Default::default(),
             unsafe_scopes: 0,
+            unsafe_block_scopes: 0,
+            const_scopes: 0,
+            cfgs: vec![],
+            platform_cfgs: vec![],
+            unsafe_blocks: vec![],
+            in_drop_impl: false,
+            inline_modules: PathBuf::new(),
+            static_mut_names,
+            union_field_names,
+            union_field_write: false,
+            ffi_fn_names,
+        }
+    }
+
+    /// Count an item, both in the file totals and for the `cfg` scopes that
+    /// the visitor is currently in, if any.
+    fn count(
+        &mut self,
+        counter: fn(&mut CounterBlock) -> &mut Count,
+        is_unsafe: bool,
+    ) {
+        counter(&mut self.metrics.counters).count(is_unsafe);
+        if let Some(predicate) = combine_cfg_predicates(&self.cfgs) {
+            let cfg_counters =
+                self.metrics.cfg_counters.entry(predicate).or_default();
+            counter(cfg_counters).count(is_unsafe);
+        }
+        if let Some(platform) = combine_cfg_predicates(&self.platform_cfgs) {
+            let platform_counters =
+                self.metrics.platform_counters.entry(platform).or_default();
+            counter(platform_counters).count(is_unsafe);
+        }
+    }
+
+    /// Records an unsafe item at the start of the span, for listing every
+    /// unsafe item one by one.
+    fn push_unsafe_item(&mut self, kind: UnsafeKind, span: proc_macro2::Span) {
+        self.metrics.unsafe_items.push(UnsafeItem {
+            location: span.into(),
+            kind,
+        });
+    }
+
+    /// Enters the `cfg` scopes of the attributes, if any, the returned scope
+    /// should be passed to `exit_cfg`.
+    fn enter_cfg(&mut self, attrs: &[Attribute]) -> CfgScope {
+        let mut cfg_scope = CfgScope::default();
+        if let Some(predicate) = cfg_predicate(attrs) {
+            self.cfgs.push(predicate);
+            cfg_scope.entered_cfg = true;
+        }
+        if let Some(predicate) = platform_cfg(attrs) {
+            self.platform_cfgs.push(predicate);
+            cfg_scope.entered_platform_cfg = true;
+        }
+        cfg_scope
+    }
+
+    fn exit_cfg(&mut self, cfg_scope: CfgScope) {
+        if cfg_scope.entered_cfg {
+            self.cfgs.pop();
+        }
+        if cfg_scope.entered_platform_cfg {
+            self.platform_cfgs.pop();
+        }
+    }
+
+    /// Count the call if it is a call to one of the watched high-risk APIs.
+    /// Only calls in unsafe scopes are counted, since the APIs are unsafe to
+    /// call and same named safe functions would otherwise match. The type of
+    /// the receiver of a method call is not known, so method calls are
+    /// matched by the method name only, this count is a heuristic.
+    fn count_api_call(&mut self, i: &Expr) {
+        if self.unsafe_scopes == 0 {
+            return;
+        }
+        let api = match i {
+            Expr::Call(call) => match &*call.func {
+                Expr::Path(path) => {
+                    let called =
+                        resolve_imported_path(&path.path, &self.imports);
+                    self.unsafe_apis
+                        .iter()
+                        .find(|api| is_api_call(&called, api))
+                }
+                _ => return,
+            },
+            Expr::MethodCall(method_call) => {
+                let method = method_call.method.to_string();
+                self.unsafe_apis.iter().find(|api| {
+                    api.rsplit("::").next() == Some(method.as_str())
+                })
+            }
+            _ => return,
+        };
+        if let Some(api) = api {
+            *self
+                .metrics
+                .counters
+                .api_calls
+                .entry(api.clone())
+                .or_insert(0) += 1;
+        }
+    }
+
+    /// Count the call if it is a call to a function declared in one of the
+    /// `extern` blocks of the file, as a call to JavaScript or to native code.
+    fn count_ffi_call(&mut self, i: &Expr) {
+        let called = match i {
+            Expr::Call(call) => match &*call.func {
+                Expr::Path(path) => match path.path.segments.last() {
+                    Some(segment) => segment.ident.to_string(),
+                    None => return,
+                },
+                _ => return,
+            },
+            _ => return,
+        };
+        if self.ffi_fn_names.js.contains(&called) {
+            self.metrics.counters.js_ffi_calls += 1;
+        } else if self.ffi_fn_names.native.contains(&called) {
+            self.metrics.counters.ffi_calls += 1;
         }
     }
 
@@ -147,6 +525,700 @@ fn is_test_fn(i: &ItemFn) -> bool {
         .any(|m| meta_is_word_test(&m))
 }
 
+/// Will return true if any of the leading comment lines, before the first
+/// line of code, contains an `@generated` marker.
+fn has_generated_marker(src_lines: &[&str]) -> bool {
+    src_lines
+        .iter()
+        .map(|line| line.trim())
+        .take_while(|line| {
+            line.is_empty()
+                || line.starts_with("//")
+                || line.starts_with("/*")
+                || line.starts_with('*')
+                || line.starts_with("#!")
+        })
+        .any(|line| line.contains("@generated"))
+}
+
+/// Will return true if the `unsafe` keyword at `location` is documented by a
+/// `SAFETY:` comment, either earlier on the same line or in the comment lines
+/// directly above it. Attributes between the comment and the block are
+/// allowed, blank lines are not. `comment_lines` are the lines of the source
+/// with everything but the comments blanked out, see `blank_non_comments`.
+fn has_safety_comment(
+    src_lines: &[&str],
+    comment_lines: &[&str],
+    location: &Location,
+) -> bool {
+    if location.line == 0
+        || location.line > src_lines.len()
+        || src_lines.len() != comment_lines.len()
+    {
+        return false;
+    }
+    let before_unsafe = comment_lines[location.line - 1]
+        .chars()
+        .take(location.column)
+        .collect::<String>();
+    if before_unsafe.contains("SAFETY:") {
+        return true;
+    }
+    for (line, comment_line) in src_lines[..location.line - 1]
+        .iter()
+        .zip(&comment_lines[..location.line - 1])
+        .rev()
+    {
+        let indent = line.chars().take_while(|c| c.is_whitespace()).count();
+        let is_comment = matches!(
+            comment_line.chars().nth(indent),
+            Some(c) if !c.is_whitespace()
+        );
+        if is_comment {
+            if comment_line.contains("SAFETY:") {
+                return true;
+            }
+        } else if !line.trim_start().starts_with("#[") {
+            return false;
+        }
+    }
+    false
+}
+
+/// A copy of `src` with everything but the comments replaced by spaces,
+/// keeping the lines and columns. The comments are found in the text between
+/// the tokens of the source, so `//` in string literals and URLs in them
+/// does not start one. Doc comments are tokens and are blanked out too.
+fn blank_non_comments(src: &str) -> String {
+    // The lexer does not skip a shebang line like `syn::parse_file` does, it
+    // is blanked out keeping the columns.
+    let lexed_src = if src.starts_with("#!") && !src.starts_with("#![") {
+        let shebang_length = src.find('\n').unwrap_or(src.len());
+        " ".repeat(src[..shebang_length].chars().count())
+            + &src[shebang_length..]
+    } else {
+        src.replacen('\u{feff}', " ", 1)
+    };
+    let line_starts = std::iter::once(0)
+        .chain(lexed_src.match_indices('\n').map(|(index, _)| index + 1))
+        .collect::<Vec<usize>>();
+    let mut token_ranges = Vec::new();
+    if let Ok(token_stream) = lexed_src.parse::<proc_macro2::TokenStream>() {
+        collect_token_ranges(
+            token_stream,
+            &lexed_src,
+            &line_starts,
+            &mut token_ranges,
+        );
+    }
+
+    let mut comments = Vec::new();
+    let mut gap_start = 0;
+    for token_range in token_ranges {
+        if token_range.start > gap_start {
+            find_comments(
+                &lexed_src,
+                gap_start..token_range.start,
+                &mut comments,
+            );
+        }
+        gap_start = gap_start.max(token_range.end);
+    }
+    find_comments(&lexed_src, gap_start..lexed_src.len(), &mut comments);
+
+    // The comments are in the order of the source.
+    let mut comments = comments.into_iter().peekable();
+    lexed_src
+        .char_indices()
+        .map(|(index, c)| {
+            while matches!(comments.peek(), Some(comment) if comment.end <= index)
+            {
+                comments.next();
+            }
+            let is_comment = matches!(
+                comments.peek(),
+                Some(comment) if comment.contains(&index)
+            );
+            if c == '\n' || is_comment {
+                c
+            } else {
+                ' '
+            }
+        })
+        .collect()
+}
+
+/// Pushes the byte ranges of the tokens in `token_stream`, in the order of
+/// the source.
+fn collect_token_ranges(
+    token_stream: proc_macro2::TokenStream,
+    src: &str,
+    line_starts: &[usize],
+    token_ranges: &mut Vec<Range<usize>>,
+) {
+    let byte_offset = |line_column: proc_macro2::LineColumn| {
+        let line_start = line_starts
+            .get(line_column.line.saturating_sub(1))
+            .copied()
+            .unwrap_or(src.len());
+        src[line_start..]
+            .char_indices()
+            .nth(line_column.column)
+            .map_or(src.len(), |(index, _)| line_start + index)
+    };
+    let span_range = |span: proc_macro2::Span| {
+        byte_offset(span.start())..byte_offset(span.end())
+    };
+    for token_tree in token_stream {
+        match token_tree {
+            proc_macro2::TokenTree::Group(group) => {
+                token_ranges.push(span_range(group.span_open()));
+                collect_token_ranges(
+                    group.stream(),
+                    src,
+                    line_starts,
+                    token_ranges,
+                );
+                token_ranges.push(span_range(group.span_close()));
+            }
+            token_tree => token_ranges.push(span_range(token_tree.span())),
+        }
+    }
+}
+
+/// Pushes the byte ranges of the line and block comments in `gap`, text of
+/// `src` between two tokens.
+fn find_comments(
+    src: &str,
+    gap: Range<usize>,
+    comments: &mut Vec<Range<usize>>,
+) {
+    let mut offset = gap.start;
+    while offset < gap.end {
+        let rest = &src[offset..gap.end];
+        let comment_length = if rest.starts_with("//") {
+            rest.find('\n').unwrap_or(rest.len())
+        } else if rest.starts_with("/*") {
+            block_comment_length(rest)
+        } else {
+            offset += rest.chars().next().map_or(1, char::len_utf8);
+            continue;
+        };
+        comments.push(offset..offset + comment_length);
+        offset += comment_length;
+    }
+}
+
+/// The length of the block comment `text` starts with, block comments nest.
+fn block_comment_length(text: &str) -> usize {
+    let mut depth = 0;
+    let mut offset = 0;
+    while offset < text.len() {
+        let rest = &text[offset..];
+        if rest.starts_with("/*") {
+            depth += 1;
+            offset += 2;
+        } else if rest.starts_with("*/") {
+            depth -= 1;
+            offset += 2;
+            if depth == 0 {
+                return offset;
+            }
+        } else {
+            offset += rest.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    text.len()
+}
+
+/// The `cfg` scopes entered by `enter_cfg`.
+#[derive(Default)]
+struct CfgScope {
+    entered_cfg: bool,
+    entered_platform_cfg: bool,
+}
+
+/// Combines the predicates of nested `cfg` scopes with `all(...)`.
+fn combine_cfg_predicates(predicates: &[String]) -> Option<String> {
+    match predicates.len() {
+        0 => None,
+        1 => Some(predicates[0].clone()),
+        _ => Some(format!("all({})", predicates.join(", "))),
+    }
+}
+
+/// Returns the `cfg` predicate from the attributes, if any. Multiple `cfg`
+/// attributes are combined with `all(...)`.
+fn cfg_predicate(attrs: &[Attribute]) -> Option<String> {
+    use syn::Meta;
+    let predicates = attrs
+        .iter()
+        .flat_map(Attribute::parse_meta)
+        .filter_map(|m| match m {
+            Meta::List(ml) if ml.path.is_ident("cfg") => Some(ml),
+            _ => None,
+        })
+        .flat_map(|ml| ml.nested.into_iter())
+        .map(|n| nested_meta_to_string(&n))
+        .collect::<Vec<String>>();
+    combine_cfg_predicates(&predicates)
+}
+
+/// The `cfg` keys that make code platform specific.
+const PLATFORM_CFG_KEYS: [&str; 3] =
+    ["target_arch", "target_family", "target_os"];
+
+/// Returns the platform specific `cfg` predicate, e.g.
+/// `target_arch = "x86_64"` or `not(windows)`, from the attributes, if any.
+/// Multiple platform specific `cfg` attributes are combined with `all(...)`.
+fn platform_cfg(attrs: &[Attribute]) -> Option<String> {
+    use syn::Meta;
+    let mut predicates = attrs
+        .iter()
+        .flat_map(Attribute::parse_meta)
+        .filter_map(|m| match m {
+            Meta::List(ml) if ml.path.is_ident("cfg") => Some(ml),
+            _ => None,
+        })
+        .flat_map(|ml| ml.nested.into_iter())
+        .filter(nested_meta_is_platform_specific)
+        .map(|n| nested_meta_to_string(&n))
+        .collect::<Vec<String>>();
+    match predicates.len() {
+        0 => None,
+        1 => predicates.pop(),
+        _ => Some(format!("all({})", predicates.join(", "))),
+    }
+}
+
+fn nested_meta_is_platform_specific(n: &syn::NestedMeta) -> bool {
+    use syn::Meta;
+    use syn::NestedMeta;
+    match n {
+        NestedMeta::Meta(Meta::Path(p)) => {
+            p.is_ident("unix") || p.is_ident("windows")
+        }
+        NestedMeta::Meta(Meta::NameValue(nv)) => {
+            PLATFORM_CFG_KEYS.iter().any(|key| nv.path.is_ident(key))
+        }
+        NestedMeta::Meta(Meta::List(ml)) => {
+            ml.nested.iter().any(nested_meta_is_platform_specific)
+        }
+        NestedMeta::Lit(_) => false,
+    }
+}
+
+/// Formats a `cfg` predicate the way it is written in the source code, so
+/// that it can be parsed back by `cargo_platform::CfgExpr`.
+fn nested_meta_to_string(n: &syn::NestedMeta) -> String {
+    use syn::Lit;
+    use syn::Meta;
+    use syn::NestedMeta;
+    let path_to_string = |p: &syn::Path| {
+        p.segments
+            .iter()
+            .map(|s| s.ident.to_string())
+            .collect::<Vec<_>>()
+            .join("::")
+    };
+    match n {
+        NestedMeta::Meta(Meta::Path(p)) => path_to_string(p),
+        NestedMeta::Meta(Meta::NameValue(nv)) => match &nv.lit {
+            Lit::Str(s) => {
+                format!("{} = {:?}", path_to_string(&nv.path), s.value())
+            }
+            _ => path_to_string(&nv.path),
+        },
+        NestedMeta::Meta(Meta::List(ml)) => format!(
+            "{}({})",
+            path_to_string(&ml.path),
+            ml.nested
+                .iter()
+                .map(nested_meta_to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        NestedMeta::Lit(_) => String::new(),
+    }
+}
+
+/// Will return true if the called path ends with the path of the API, e.g.
+/// `std::mem::transmute` matches `mem::transmute`. A function called by its
+/// name alone only matches when imported from the API path, see
+/// `resolve_imported_path`.
+fn is_api_call(called: &[String], api: &str) -> bool {
+    let api_segments = api.split("::").collect::<Vec<_>>();
+    called.len() >= api_segments.len()
+        && called[called.len() - api_segments.len()..]
+            .iter()
+            .zip(api_segments.iter())
+            .all(|(called_segment, api_segment)| called_segment == api_segment)
+}
+
+/// The segments of the path, with a leading name imported with `use` replaced
+/// by the path it was imported from, e.g. `transmute` is
+/// `std::mem::transmute` after `use std::mem::transmute`.
+fn resolve_imported_path(
+    path: &syn::Path,
+    imports: &HashMap<String, Vec<String>>,
+) -> Vec<String> {
+    let mut segments = path
+        .segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect::<Vec<_>>();
+    if path.leading_colon.is_none() {
+        if let Some(imported) =
+            segments.first().and_then(|first| imports.get(first))
+        {
+            segments.splice(0..1, imported.iter().cloned());
+        }
+    }
+    segments
+}
+
+/// Will return true for structs with a `#[repr(packed)]` or
+/// `#[repr(packed(N))]` attribute.
+fn is_packed_struct(i: &ItemStruct) -> bool {
+    use syn::Meta;
+    use syn::NestedMeta;
+    i.attrs
+        .iter()
+        .filter(|a| a.path.is_ident("repr"))
+        .filter_map(|a| a.parse_meta().ok())
+        .any(|meta| match meta {
+            Meta::List(ml) => ml.nested.iter().any(|n| match n {
+                NestedMeta::Meta(m) => m.path().is_ident("packed"),
+                _ => false,
+            }),
+            _ => false,
+        })
+}
+
+/// Will return true for `pub` items, restricted visibilities like
+/// `pub(crate)` do not export the item to other crates.
+fn is_public(vis: &Visibility) -> bool {
+    matches!(vis, Visibility::Public(_))
+}
+
+/// Will return true for statics exported to foreign code with `#[no_mangle]`
+/// or `#[export_name = "..."]`.
+fn is_exported_global(i: &ItemStatic) -> bool {
+    i.attrs
+        .iter()
+        .any(|a| a.path.is_ident("no_mangle") || a.path.is_ident("export_name"))
+}
+
+/// Will return true for `#[allow(unsafe_code)]`, also when listed together
+/// with other lints.
+fn is_allow_unsafe_code_attr(a: &Attribute) -> bool {
+    use syn::Meta;
+    use syn::NestedMeta;
+    if !a.path.is_ident("allow") {
+        return false;
+    }
+    match a.parse_meta() {
+        Ok(Meta::List(list)) => list.nested.iter().any(|n| match n {
+            NestedMeta::Meta(Meta::Path(p)) => p.is_ident("unsafe_code"),
+            _ => false,
+        }),
+        _ => false,
+    }
+}
+
+/// Returns the value of a `#[path = "..."]` attribute, if any.
+fn path_attr(attrs: &[Attribute]) -> Option<String> {
+    use syn::Lit;
+    use syn::Meta;
+    attrs
+        .iter()
+        .filter(|a| a.path.is_ident("path"))
+        .filter_map(|a| a.parse_meta().ok())
+        .find_map(|meta| match meta {
+            Meta::NameValue(nv) => match nv.lit {
+                Lit::Str(s) => Some(s.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+}
+
+/// Resolves the file of an `include!` invocation, as far as it can be done
+/// without a build. Supported are string literals and
+/// `concat!(env!("VAR"), "...")`, other forms are ignored.
+fn included_file(i: &Macro) -> Option<IncludedFile> {
+    use syn::punctuated::Punctuated;
+    use syn::{ExprLit, Lit, LitStr, Token};
+    let is_include = i
+        .path
+        .segments
+        .last()
+        .is_some_and(|segment| segment.ident == "include");
+    if !is_include {
+        return None;
+    }
+    if let Ok(path) = i.parse_body::<LitStr>() {
+        return Some(IncludedFile::Relative(PathBuf::from(path.value())));
+    }
+    let concat = match i.parse_body::<Expr>().ok()? {
+        Expr::Macro(concat) if concat.mac.path.is_ident("concat") => concat,
+        _ => return None,
+    };
+    let mut parts = concat
+        .mac
+        .parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated)
+        .ok()?
+        .into_iter();
+    let var = match parts.next()? {
+        Expr::Macro(env) if env.mac.path.is_ident("env") => {
+            env.mac.parse_body::<LitStr>().ok()?.value()
+        }
+        _ => return None,
+    };
+    let suffix = parts
+        .map(|part| match part {
+            Expr::Lit(ExprLit {
+                lit: Lit::Str(s), ..
+            }) => Some(s.value()),
+            _ => None,
+        })
+        .collect::<Option<String>>()?;
+    Some(IncludedFile::EnvRelative { var, suffix })
+}
+
+/// Collects the names of all `static mut` items in a file, these are needed
+/// up front to count the references to them. References are matched by the
+/// last segment of a path only, the paths are not resolved, so this count is
+/// a heuristic.
+struct StaticMutNameVisitor {
+    static_mut_names: HashSet<String>,
+}
+
+impl<'ast> visit::Visit<'ast> for StaticMutNameVisitor {
+    fn visit_item_static(&mut self, i: &ItemStatic) {
+        if i.mutability.is_some() {
+            self.static_mut_names.insert(i.ident.to_string());
+        }
+        visit::visit_item_static(self, i);
+    }
+}
+
+/// Collects the paths of the names imported with `use` declarations in a file,
+/// keyed by the name they are imported as, these are needed up front to
+/// match the calls of imported functions against the high-risk APIs. Glob
+/// imports are left out.
+#[derive(Default)]
+struct ImportVisitor {
+    imports: HashMap<String, Vec<String>>,
+}
+
+impl ImportVisitor {
+    fn add_use_tree(&mut self, prefix: &mut Vec<String>, tree: &UseTree) {
+        match tree {
+            UseTree::Path(use_path) => {
+                prefix.push(use_path.ident.to_string());
+                self.add_use_tree(prefix, &use_path.tree);
+                prefix.pop();
+            }
+            UseTree::Name(use_name) if use_name.ident == "self" => {
+                if let Some(name) = prefix.last() {
+                    self.imports.insert(name.clone(), prefix.clone());
+                }
+            }
+            UseTree::Name(use_name) => {
+                let name = use_name.ident.to_string();
+                let mut path = prefix.clone();
+                path.push(name.clone());
+                self.imports.insert(name, path);
+            }
+            UseTree::Rename(use_rename) => {
+                let mut path = prefix.clone();
+                path.push(use_rename.ident.to_string());
+                self.imports.insert(use_rename.rename.to_string(), path);
+            }
+            UseTree::Group(use_group) => {
+                for tree in &use_group.items {
+                    self.add_use_tree(prefix, tree);
+                }
+            }
+            UseTree::Glob(_) => {}
+        }
+    }
+}
+
+impl<'ast> visit::Visit<'ast> for ImportVisitor {
+    fn visit_item_use(&mut self, i: &ItemUse) {
+        self.add_use_tree(&mut Vec::new(), &i.tree);
+        visit::visit_item_use(self, i);
+    }
+}
+
+/// Collects the field names of all unions in a file, these are needed up
+/// front to count the accesses to them. Field accesses are matched by name
+/// only, the type of the accessed value is not known.
+struct UnionFieldNameVisitor {
+    union_field_names: HashSet<String>,
+}
+
+impl<'ast> visit::Visit<'ast> for UnionFieldNameVisitor {
+    fn visit_item_union(&mut self, i: &ItemUnion) {
+        for field in &i.fields.named {
+            if let Some(ident) = &field.ident {
+                self.union_field_names.insert(ident.to_string());
+            }
+        }
+        visit::visit_item_union(self, i);
+    }
+}
+
+/// The names of the functions declared in `extern` blocks, split into the
+/// functions imported from JavaScript on `wasm32` targets and native
+/// functions.
+#[derive(Default)]
+struct FfiFnNames {
+    js: HashSet<String>,
+    native: HashSet<String>,
+}
+
+/// Collects the names of all functions declared in the `extern` blocks of a
+/// file, these are needed up front to count the calls to them. Calls are
+/// matched by name only, like references to `static mut` items.
+#[derive(Default)]
+struct FfiFnNameVisitor {
+    ffi_fn_names: FfiFnNames,
+
+    /// The number of nested modules only built for `target_arch = "wasm32"`
+    /// that the visitor is currently in.
+    wasm_scopes: u32,
+}
+
+impl<'ast> visit::Visit<'ast> for FfiFnNameVisitor {
+    fn visit_item_mod(&mut self, i: &ItemMod) {
+        let is_wasm_mod = is_wasm_cfg(&i.attrs);
+        if is_wasm_mod {
+            self.wasm_scopes += 1;
+        }
+        visit::visit_item_mod(self, i);
+        if is_wasm_mod {
+            self.wasm_scopes -= 1;
+        }
+    }
+
+    fn visit_item_foreign_mod(&mut self, i: &ItemForeignMod) {
+        let names = if self.wasm_scopes > 0 || is_js_ffi_block(i) {
+            &mut self.ffi_fn_names.js
+        } else {
+            &mut self.ffi_fn_names.native
+        };
+        for item in &i.items {
+            if let ForeignItem::Fn(f) = item {
+                names.insert(f.sig.ident.to_string());
+            }
+        }
+        visit::visit_item_foreign_mod(self, i);
+    }
+}
+
+/// Will return true for `extern` blocks importing functions from JavaScript,
+/// blocks with a `#[wasm_bindgen]` or `#[link(wasm_import_module = "...")]`
+/// attribute and blocks only built for `target_arch = "wasm32"`, where there
+/// is no native code to link to on `wasm32-unknown-unknown`.
+fn is_js_ffi_block(i: &ItemForeignMod) -> bool {
+    use syn::Meta;
+    use syn::NestedMeta;
+    let is_wasm_bindgen = i.attrs.iter().any(|a| {
+        a.path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "wasm_bindgen")
+    });
+    let is_wasm_import = i
+        .attrs
+        .iter()
+        .filter(|a| a.path.is_ident("link"))
+        .filter_map(|a| a.parse_meta().ok())
+        .any(|meta| match meta {
+            Meta::List(ml) => ml.nested.iter().any(|n| match n {
+                NestedMeta::Meta(m) => m.path().is_ident("wasm_import_module"),
+                _ => false,
+            }),
+            _ => false,
+        });
+    is_wasm_bindgen || is_wasm_import || is_wasm_cfg(&i.attrs)
+}
+
+/// Will return true if the attributes include a `cfg` that only holds for
+/// `target_arch = "wasm32"`, alone or in an `all(...)` predicate.
+fn is_wasm_cfg(attrs: &[Attribute]) -> bool {
+    use syn::Meta;
+    attrs
+        .iter()
+        .filter(|a| a.path.is_ident("cfg"))
+        .filter_map(|a| a.parse_meta().ok())
+        .any(|meta| match meta {
+            Meta::List(ml) => ml.nested.iter().any(nested_meta_requires_wasm),
+            _ => false,
+        })
+}
+
+fn nested_meta_requires_wasm(n: &syn::NestedMeta) -> bool {
+    use syn::Lit;
+    use syn::Meta;
+    use syn::NestedMeta;
+    match n {
+        NestedMeta::Meta(Meta::NameValue(nv)) => {
+            nv.path.is_ident("target_arch")
+                && matches!(&nv.lit, Lit::Str(s) if s.value() == "wasm32")
+        }
+        NestedMeta::Meta(Meta::List(ml)) if ml.path.is_ident("all") => {
+            ml.nested.iter().any(nested_meta_requires_wasm)
+        }
+        _ => false,
+    }
+}
+
+/// Will return true for field accesses, like `u.f`, by the name of a field
+/// of one of the unions in `union_field_names`. This is a heuristic, the
+/// type of `u` is not resolved, so fields of other types sharing a name with
+/// a union field are matched as well.
+fn is_union_field_access(
+    i: &Expr,
+    union_field_names: &HashSet<String>,
+) -> bool {
+    match i {
+        Expr::Field(field) => match &field.member {
+            Member::Named(ident) => {
+                union_field_names.contains(&ident.to_string())
+            }
+            Member::Unnamed(_) => false,
+        },
+        _ => false,
+    }
+}
+
+/// Will return true for `impl Drop for ...` blocks.
+fn is_drop_impl(i: &ItemImpl) -> bool {
+    match &i.trait_ {
+        Some((None, path, _)) => path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Drop"),
+        _ => false,
+    }
+}
+
+/// Will return true for the inline assembly macros, `asm!`, `global_asm!`,
+/// `naked_asm!` and the deprecated `llvm_asm!`.
+fn is_asm_macro(m: &Macro) -> bool {
+    match m.path.segments.last() {
+        Some(segment) => matches!(
+            segment.ident.to_string().as_str(),
+            "asm" | "global_asm" | "naked_asm" | "llvm_asm"
+        ),
+        None => false,
+    }
+}
+
 fn file_forbids_unsafe(f: &syn::File) -> bool {
     use syn::AttrStyle;
     use syn::Meta;
@@ -178,41 +1250,82 @@ fn file_forbids_unsafe(f: &syn::File) -> bool {
         > 0
 }
 
-impl<'ast> visit::Visit<'ast> for GeigerSynVisitor {
+impl<'a, 'ast> visit::Visit<'ast> for GeigerSynVisitor<'a> {
     fn visit_file(&mut self, i: &'ast syn::File) {
         self.metrics.forbids_unsafe = file_forbids_unsafe(i);
         syn::visit::visit_file(self, i);
     }
 
+    fn visit_attribute(&mut self, i: &Attribute) {
+        if is_allow_unsafe_code_attr(i) {
+            self.metrics
+                .allow_unsafe_code_locations
+                .push(i.pound_token.spans[0].into());
+        }
+        visit::visit_attribute(self, i);
+    }
+
     /// Free-standing functions
     fn visit_item_fn(&mut self, i: &ItemFn) {
         if IncludeTests::No == self.include_tests && is_test_fn(i) {
             return;
         }
-        if i.sig.unsafety.is_some() {
+        let cfg_scope = self.enter_cfg(&i.attrs);
+        if let Some(unsafety) = i.sig.unsafety {
+            self.metrics.unsafe_locations.push(unsafety.span.into());
+            self.push_unsafe_item(UnsafeKind::Function, unsafety.span);
             self.enter_unsafe_scope()
         }
-        self.metrics
-            .counters
-            .functions
-            .count(i.sig.unsafety.is_some());
+        self.count(|c| &mut c.functions, i.sig.unsafety.is_some());
+        if i.sig.unsafety.is_some() && is_public(&i.vis) {
+            self.metrics.counters.public_unsafe_fns += 1;
+        }
+        let is_const = i.sig.constness.is_some();
+        if is_const {
+            self.const_scopes += 1;
+        }
         visit::visit_item_fn(self, i);
+        if is_const {
+            self.const_scopes -= 1;
+        }
         if i.sig.unsafety.is_some() {
             self.exit_unsafe_scope()
         }
+        self.exit_cfg(cfg_scope);
     }
 
     fn visit_expr(&mut self, i: &Expr) {
         // Total number of expressions of any type
         match i {
             Expr::Unsafe(i) => {
+                self.unsafe_blocks.push(i.unsafe_token.span.into());
+                self.metrics
+                    .unsafe_locations
+                    .push(i.unsafe_token.span.into());
+                let cfg_scope = self.enter_cfg(&i.attrs);
                 self.enter_unsafe_scope();
+                self.unsafe_block_scopes += 1;
                 visit::visit_expr_unsafe(self, i);
+                self.unsafe_block_scopes -= 1;
                 self.exit_unsafe_scope();
+                self.exit_cfg(cfg_scope);
+            }
+            Expr::Path(path) => {
+                // Do not count as an expression. The expression `f(x)` should
+                // count as one expression, not three. A path naming a
+                // `static mut` of the file is counted as a reference to it,
+                // without resolving the path.
+                let refers_to_static_mut =
+                    path.path.segments.last().is_some_and(|segment| {
+                        self.static_mut_names
+                            .contains(&segment.ident.to_string())
+                    });
+                if refers_to_static_mut {
+                    self.metrics.counters.static_mut_refs += 1;
+                }
             }
-            Expr::Path(_) | Expr::Lit(_) => {
-                // Do not count. The expression `f(x)` should count as one
-                // expression, not three.
+            Expr::Lit(_) => {
+                // Do not count.
             }
             other => {
                 // TODO: Print something pretty here or gather the data for later
@@ -220,7 +1333,37 @@ impl<'ast> visit::Visit<'ast> for GeigerSynVisitor {
                 // if self.verbosity == Verbosity::Verbose && self.unsafe_scopes > 0 {
                 //     println!("{:#?}", other);
                 // }
-                self.metrics.counters.exprs.count(self.unsafe_scopes > 0);
+                self.count(|c| &mut c.exprs, self.unsafe_scopes > 0);
+                if self.unsafe_scopes > 0 {
+                    self.push_unsafe_item(UnsafeKind::Expression, other.span());
+                }
+                if self.unsafe_scopes > 0 && self.unsafe_block_scopes == 0 {
+                    self.metrics.counters.unsafe_fn_body_exprs += 1;
+                }
+                self.count_api_call(other);
+                self.count_ffi_call(other);
+                if self.in_drop_impl {
+                    self.count(|c| &mut c.drop_exprs, self.unsafe_scopes > 0);
+                }
+                if self.const_scopes > 0 {
+                    self.count(|c| &mut c.const_exprs, self.unsafe_scopes > 0);
+                }
+                // Reading a union field is unsafe, writing one with a plain
+                // assignment is not.
+                let union_field_write =
+                    std::mem::take(&mut self.union_field_write);
+                if self.unsafe_scopes > 0
+                    && !union_field_write
+                    && is_union_field_access(other, &self.union_field_names)
+                {
+                    self.metrics.counters.union_field_accesses += 1;
+                }
+                if let Expr::Assign(assign) = other {
+                    self.union_field_write = is_union_field_access(
+                        &assign.left,
+                        &self.union_field_names,
+                    );
+                }
                 visit::visit_expr(self, other);
             }
         }
@@ -230,39 +1373,141 @@ impl<'ast> visit::Visit<'ast> for GeigerSynVisitor {
         if IncludeTests::No == self.include_tests && is_test_mod(i) {
             return;
         }
-        visit::visit_item_mod(self, i);
+        let cfg_scope = self.enter_cfg(&i.attrs);
+        if i.content.is_none() {
+            if let Some(path) = path_attr(&i.attrs) {
+                self.metrics.included_files.push(IncludedFile::ModulePath {
+                    inline_modules: self.inline_modules.clone(),
+                    path: PathBuf::from(path),
+                });
+            }
+            visit::visit_item_mod(self, i);
+        } else {
+            self.inline_modules.push(
+                path_attr(&i.attrs).unwrap_or_else(|| i.ident.to_string()),
+            );
+            visit::visit_item_mod(self, i);
+            self.inline_modules.pop();
+        }
+        self.exit_cfg(cfg_scope);
     }
 
     fn visit_item_impl(&mut self, i: &ItemImpl) {
+        let cfg_scope = self.enter_cfg(&i.attrs);
         // unsafe trait impl's
-        self.metrics.counters.item_impls.count(i.unsafety.is_some());
+        if let Some(unsafety) = i.unsafety {
+            self.metrics.unsafe_locations.push(unsafety.span.into());
+            self.push_unsafe_item(UnsafeKind::Impl, unsafety.span);
+        }
+        self.count(|c| &mut c.item_impls, i.unsafety.is_some());
+        let was_in_drop_impl = self.in_drop_impl;
+        self.in_drop_impl = is_drop_impl(i);
         visit::visit_item_impl(self, i);
+        self.in_drop_impl = was_in_drop_impl;
+        self.exit_cfg(cfg_scope);
+    }
+
+    fn visit_item_static(&mut self, i: &ItemStatic) {
+        if i.mutability.is_some() {
+            self.metrics.counters.static_muts += 1;
+        }
+        if is_exported_global(i) {
+            self.metrics.counters.exported_globals += 1;
+        }
+        self.const_scopes += 1;
+        visit::visit_item_static(self, i);
+        self.const_scopes -= 1;
+    }
+
+    fn visit_item_const(&mut self, i: &ItemConst) {
+        self.const_scopes += 1;
+        visit::visit_item_const(self, i);
+        self.const_scopes -= 1;
+    }
+
+    fn visit_impl_item_const(&mut self, i: &ImplItemConst) {
+        self.const_scopes += 1;
+        visit::visit_impl_item_const(self, i);
+        self.const_scopes -= 1;
+    }
+
+    fn visit_trait_item_const(&mut self, i: &TraitItemConst) {
+        self.const_scopes += 1;
+        visit::visit_trait_item_const(self, i);
+        self.const_scopes -= 1;
+    }
+
+    fn visit_foreign_item_static(&mut self, i: &ForeignItemStatic) {
+        self.metrics.counters.extern_statics += 1;
+        visit::visit_foreign_item_static(self, i);
+    }
+
+    fn visit_item_struct(&mut self, i: &ItemStruct) {
+        if is_packed_struct(i) {
+            self.metrics.counters.packed_structs += 1;
+        }
+        visit::visit_item_struct(self, i);
+    }
+
+    fn visit_item_union(&mut self, i: &ItemUnion) {
+        self.metrics.counters.unions += 1;
+        visit::visit_item_union(self, i);
     }
 
     fn visit_item_trait(&mut self, i: &ItemTrait) {
+        let cfg_scope = self.enter_cfg(&i.attrs);
         // Unsafe traits
-        self.metrics
-            .counters
-            .item_traits
-            .count(i.unsafety.is_some());
+        if let Some(unsafety) = i.unsafety {
+            self.metrics.unsafe_locations.push(unsafety.span.into());
+            self.push_unsafe_item(UnsafeKind::Trait, unsafety.span);
+        }
+        self.count(|c| &mut c.item_traits, i.unsafety.is_some());
+        if i.unsafety.is_some() && is_public(&i.vis) {
+            self.metrics.counters.public_unsafe_traits += 1;
+        }
         visit::visit_item_trait(self, i);
+        self.exit_cfg(cfg_scope);
     }
 
     fn visit_impl_item_method(&mut self, i: &ImplItemMethod) {
-        if i.sig.unsafety.is_some() {
+        let cfg_scope = self.enter_cfg(&i.attrs);
+        if let Some(unsafety) = i.sig.unsafety {
+            self.metrics.unsafe_locations.push(unsafety.span.into());
+            self.push_unsafe_item(UnsafeKind::Method, unsafety.span);
             self.enter_unsafe_scope()
         }
-        self.metrics
-            .counters
-            .methods
-            .count(i.sig.unsafety.is_some());
+        self.count(|c| &mut c.methods, i.sig.unsafety.is_some());
+        if i.sig.unsafety.is_some() && is_public(&i.vis) {
+            self.metrics.counters.public_unsafe_fns += 1;
+        }
+        let is_const = i.sig.constness.is_some();
+        if is_const {
+            self.const_scopes += 1;
+        }
         visit::visit_impl_item_method(self, i);
+        if is_const {
+            self.const_scopes -= 1;
+        }
         if i.sig.unsafety.is_some() {
             self.exit_unsafe_scope()
         }
+        self.exit_cfg(cfg_scope);
     }
 
-    // TODO: Visit macros.
+    fn visit_macro(&mut self, i: &Macro) {
+        if let Some(included_file) = included_file(i) {
+            self.metrics.included_files.push(included_file);
+        }
+        if is_asm_macro(i) {
+            self.metrics.counters.asm += 1;
+            if let Some(segment) = i.path.segments.last() {
+                self.metrics.asm_locations.push(segment.ident.span().into());
+            }
+        }
+        visit::visit_macro(self, i);
+    }
+
+    // TODO: Visit macro bodies, the tokens are not parsed.
     //
     // TODO: Figure out if there are other visit methods that should be
     // implemented here.
@@ -271,18 +1516,54 @@ impl<'ast> visit::Visit<'ast> for GeigerSynVisitor {
 pub fn find_unsafe_in_string(
     src: &str,
     include_tests: IncludeTests,
+    unsafe_apis: &[String],
 ) -> Result<RsFileMetrics, syn::Error> {
     use syn::visit::Visit;
-    let syntax = syn::parse_file(&src)?;
-    let mut vis = GeigerSynVisitor::new(include_tests);
+    let syntax = syn::parse_file(src)?;
+    let mut static_mut_name_visitor = StaticMutNameVisitor {
+        static_mut_names: HashSet::new(),
+    };
+    static_mut_name_visitor.visit_file(&syntax);
+    let mut union_field_name_visitor = UnionFieldNameVisitor {
+        union_field_names: HashSet::new(),
+    };
+    union_field_name_visitor.visit_file(&syntax);
+    let mut ffi_fn_name_visitor = FfiFnNameVisitor::default();
+    ffi_fn_name_visitor.visit_file(&syntax);
+    let mut import_visitor = ImportVisitor::default();
+    import_visitor.visit_file(&syntax);
+    let mut vis = GeigerSynVisitor::new(
+        include_tests,
+        unsafe_apis,
+        import_visitor.imports,
+        static_mut_name_visitor.static_mut_names,
+        union_field_name_visitor.union_field_names,
+        ffi_fn_name_visitor.ffi_fn_names,
+    );
     vis.visit_file(&syntax);
-    Ok(vis.metrics)
+    let src_lines = src.lines().collect::<Vec<_>>();
+    let mut metrics = vis.metrics;
+    metrics.generated_marker = has_generated_marker(&src_lines);
+    if !vis.unsafe_blocks.is_empty() {
+        let comments = blank_non_comments(src);
+        let comment_lines = comments.lines().collect::<Vec<_>>();
+        metrics.undocumented_unsafe_blocks = vis
+            .unsafe_blocks
+            .into_iter()
+            .filter(|location| {
+                !has_safety_comment(&src_lines, &comment_lines, location)
+            })
+            .collect();
+    }
+    Ok(metrics)
 }
 
-/// Scan a single file for `unsafe` usage.
+/// Scan a single file for `unsafe` usage. Calls to the `unsafe_apis` are
+/// counted per API, see `DEFAULT_UNSAFE_APIS`.
 pub fn find_unsafe_in_file(
     p: &Path,
     include_tests: IncludeTests,
+    unsafe_apis: &[String],
 ) -> Result<RsFileMetrics, ScanFileError> {
     let mut file =
         File::open(p).map_err(|e| ScanFileError::Io(e, p.to_path_buf()))?;
@@ -291,6 +1572,153 @@ pub fn find_unsafe_in_file(
         .map_err(|e| ScanFileError::Io(e, p.to_path_buf()))?;
     let src = String::from_utf8(src)
         .map_err(|e| ScanFileError::Utf8(e, p.to_path_buf()))?;
-    find_unsafe_in_string(&src, include_tests)
+    find_unsafe_in_string(&src, include_tests, unsafe_apis)
         .map_err(|e| ScanFileError::Syn(e, p.to_path_buf()))
 }
+
+#[cfg(test)]
+mod lib_tests {
+    use super::*;
+
+    use rstest::*;
+
+    fn find_unsafe(src: &str) -> RsFileMetrics {
+        let unsafe_apis = DEFAULT_UNSAFE_APIS
+            .iter()
+            .map(|api| api.to_string())
+            .collect::<Vec<String>>();
+        find_unsafe_in_string(src, IncludeTests::No, &unsafe_apis).unwrap()
+    }
+
+    #[rstest]
+    fn find_unsafe_asm_test() {
+        let metrics = find_unsafe(
+            "fn f() {\n    unsafe { core::arch::asm!(\"nop\") };\n}\n\
+             global_asm!(\"\");\n",
+        );
+
+        assert_eq!(metrics.counters.asm, 2);
+        assert_eq!(
+            metrics.asm_locations,
+            vec![
+                Location {
+                    line: 2,
+                    column: 25
+                },
+                Location { line: 4, column: 0 }
+            ]
+        );
+    }
+
+    #[rstest]
+    fn find_unsafe_platform_test() {
+        let metrics = find_unsafe(
+            "#[cfg(target_os = \"linux\")]\nunsafe fn f() {}\n\
+             #[cfg(feature = \"nightly\")]\nunsafe fn g() {}\n",
+        );
+
+        assert_eq!(metrics.counters.functions.unsafe_, 2);
+        assert_eq!(
+            metrics.platform_counters.keys().collect::<Vec<_>>(),
+            vec!["target_os = \"linux\""]
+        );
+        assert_eq!(
+            metrics.platform_counters["target_os = \"linux\""]
+                .functions
+                .unsafe_,
+            1
+        );
+    }
+
+    #[rstest]
+    fn find_unsafe_safety_comment_test() {
+        let metrics = find_unsafe(
+            "fn f() {\n    // SAFETY: nothing is read.\n    unsafe {}\n\
+             \x20   let _ = \"// SAFETY: in a string\"; unsafe {}\n\
+             \x20   unsafe {}\n}\n",
+        );
+
+        assert_eq!(
+            metrics
+                .undocumented_unsafe_blocks
+                .iter()
+                .map(|location| location.line)
+                .collect::<Vec<usize>>(),
+            vec![4, 5]
+        );
+    }
+
+    #[rstest]
+    fn find_unsafe_union_test() {
+        let metrics = find_unsafe(
+            "union U { a: u32, b: f32 }\n\
+             fn f(mut u: U) -> u32 { u.b = 1.0; unsafe { u.a } }\n",
+        );
+
+        assert_eq!(metrics.counters.unions, 1);
+        assert_eq!(metrics.counters.union_field_accesses, 1);
+    }
+
+    #[rstest]
+    fn find_unsafe_static_mut_test() {
+        let metrics = find_unsafe(
+            "static mut COUNTER: u32 = 0;\nstatic LIMIT: u32 = 1;\n\
+             fn f() -> u32 { unsafe { COUNTER += LIMIT; self::COUNTER } }\n",
+        );
+
+        assert_eq!(metrics.counters.static_muts, 1);
+        assert_eq!(metrics.counters.static_mut_refs, 2);
+    }
+
+    #[rstest(
+        input_src,
+        expected_transmute_calls,
+        case("fn f() { unsafe { std::mem::transmute::<u8, i8>(1) }; }", 1),
+        case("fn f() { unsafe { mem::transmute::<u8, i8>(1) }; }", 1),
+        case(
+            "use std::mem; fn f() { unsafe { mem::transmute::<u8, i8>(1) }; }",
+            1
+        ),
+        case(
+            "use core::mem::transmute; \
+             fn f() { unsafe { transmute::<u8, i8>(1) }; }",
+            1
+        ),
+        case(
+            "use std::mem::{self, transmute as cast}; \
+             fn f() { unsafe { cast::<u8, i8>(1) }; }",
+            1
+        ),
+        case("fn f() { unsafe { transmute(1) }; }", 0),
+        case("fn f() { unsafe { bytes::transmute(1) }; }", 0),
+        case("fn f() { std::mem::transmute::<u8, i8>(1); }", 0)
+    )]
+    fn count_api_call_path_test(
+        input_src: &str,
+        expected_transmute_calls: u64,
+    ) {
+        let metrics = find_unsafe(input_src);
+
+        assert_eq!(
+            metrics
+                .counters
+                .api_calls
+                .get("mem::transmute")
+                .copied()
+                .unwrap_or(0),
+            expected_transmute_calls
+        );
+    }
+
+    #[rstest]
+    fn count_api_call_method_test() {
+        let metrics = find_unsafe(
+            "fn f(x: MaybeUninit<u8>) { unsafe { x.assume_init() }; }",
+        );
+
+        assert_eq!(
+            metrics.counters.api_calls.get("MaybeUninit::assume_init"),
+            Some(&1)
+        );
+    }
+}